@@ -1,5 +1,8 @@
 fn main() {
-    if std::env::var("CARGO_FEATURE_LOG").ok().is_some() {
+    // the shims forward the messages of libwayland to the log handler of the system backends
+    let features =
+        ["CARGO_FEATURE_LOG", "CARGO_FEATURE_CLIENT_SYSTEM", "CARGO_FEATURE_SERVER_SYSTEM"];
+    if features.iter().any(|feature| std::env::var_os(feature).is_some()) {
         // build the client shim
         cc::Build::new().file("src/sys/client_impl/log_shim.c").compile("log_shim_client");
         println!("cargo:rerun-if-changed=src/sys/client_impl/log_shim.c");
@@ -0,0 +1,33 @@
+//! Generation helpers shared by the `__arbitrary` modules of `wayland-client` and `wayland-server`,
+//! for the code generated by `wayland-scanner` with its `arbitrary` option
+
+use arbitrary::{Error, Result, Unstructured};
+
+pub use arbitrary;
+
+pub fn optional<T>(
+    u: &mut Unstructured<'_>,
+    value: impl FnOnce(&mut Unstructured<'_>) -> Result<T>,
+) -> Result<Option<T>> {
+    if u.arbitrary()? {
+        value(u).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+// the fixed point numbers have 8 fractional bits
+pub fn fixed(u: &mut Unstructured<'_>) -> Result<f64> {
+    Ok(u.arbitrary::<i32>()? as f64 / 256.)
+}
+
+// the strings are sent nul-terminated
+pub fn string(u: &mut Unstructured<'_>) -> Result<String> {
+    Ok(u.arbitrary::<String>()?.replace('\0', ""))
+}
+
+// pick one of the values of an enum, or try an arbitrary value when they are not known
+pub fn enum_value<E: TryFrom<u32>>(u: &mut Unstructured<'_>, values: &[u32]) -> Result<E> {
+    let value = if values.is_empty() { u.arbitrary()? } else { *u.choose(values)? };
+    E::try_from(value).map_err(|_| Error::IncorrectFormat)
+}
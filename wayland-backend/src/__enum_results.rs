@@ -0,0 +1,17 @@
+//! Conversion of the enum arguments, for the code generated by `wayland-scanner` with its
+//! `enum_results` option
+
+use crate::protocol::UnknownVariant;
+
+// parse an enum from the integer format used by the wayland protocol
+pub fn parse<T: TryFrom<u32>>(value: u32) -> Result<T, UnknownVariant> {
+    T::try_from(value).map_err(|_| UnknownVariant(value))
+}
+
+// convert a parsed enum back into the integer format used by the wayland protocol
+pub fn to_raw<T: Into<u32>>(value: Result<T, UnknownVariant>) -> u32 {
+    match value {
+        Ok(t) => t.into(),
+        Err(UnknownVariant(u)) => u,
+    }
+}
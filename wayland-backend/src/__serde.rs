@@ -0,0 +1,75 @@
+//! Serialization helpers shared by the `__serde` modules of `wayland-client` and `wayland-server`, for
+//! the code generated by `wayland-scanner` with its `serde` option
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{
+    __enum_results,
+    protocol::{Interface, UnknownVariant},
+};
+
+pub use serde;
+
+/// The object ids of both sides, serialized as their protocol id
+pub trait ProtocolId {
+    /// The protocol id of the object
+    fn protocol_id(&self) -> u32;
+}
+
+impl ProtocolId for crate::client::ObjectId {
+    fn protocol_id(&self) -> u32 {
+        self.protocol_id()
+    }
+}
+
+impl ProtocolId for crate::server::ObjectId {
+    fn protocol_id(&self) -> u32 {
+        self.protocol_id()
+    }
+}
+
+pub fn enum_value<T: Copy + Into<u32>, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32((*value).into())
+}
+
+pub fn enum_result<T: Copy + Into<u32>, S: Serializer>(
+    value: &Result<T, UnknownVariant>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(__enum_results::to_raw(*value))
+}
+
+// a placeholder for the file descriptor, with its index among the ones of the message
+pub fn fd<const INDEX: u32, F, S: Serializer>(_: &F, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut placeholder = serializer.serialize_struct("Fd", 1)?;
+    placeholder.serialize_field("fd", &INDEX)?;
+    placeholder.end()
+}
+
+pub fn object_id<Id: ProtocolId, S: Serializer>(id: &Id, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(id.protocol_id())
+}
+
+pub fn optional_object_id<Id: ProtocolId, S: Serializer>(
+    id: &Option<Id>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    id.as_ref().map(ProtocolId::protocol_id).serialize(serializer)
+}
+
+pub fn interface_version<S: Serializer>(
+    (interface, version): &(&'static Interface, u32),
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    (interface.name, version).serialize(serializer)
+}
+
+pub fn bind_args<Id: ProtocolId, S: Serializer>(
+    (interface, version, id): &(String, u32, Id),
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    (interface, version, id.protocol_id()).serialize(serializer)
+}
@@ -1,16 +1,26 @@
 use std::{
     any::Any,
+    collections::HashMap,
     fmt,
+    marker::PhantomData,
+    ops::Deref,
     os::unix::io::{BorrowedFd, OwnedFd},
-    os::unix::{io::RawFd, net::UnixStream},
-    sync::Arc,
+    os::unix::net::UnixStream,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use crate::protocol::{Interface, Message, ObjectInfo};
+use crate::core_interfaces::WL_CALLBACK_INTERFACE;
+use crate::protocol::{Argument, BorrowedMessage, Interface, Message, ObjectInfo, ProtocolError};
 
 use super::client_impl;
 
-pub use crate::types::client::{InvalidId, NoWaylandLib, WaylandError};
+pub use crate::debug::DebugSink;
+#[cfg(feature = "metrics")]
+pub use crate::metrics::Metrics;
+pub use crate::types::client::{BufferSizes, InvalidId, MemoryReport, NoWaylandLib, WaylandError};
 
 /// A trait representing your data associated to an object
 ///
@@ -24,12 +34,29 @@ pub trait ObjectData: downcast_rs::DowncastSync {
     ///
     /// If the event has a `NewId` argument, the callback must return the object data
     /// for the newly created object
+    ///
+    /// **Note:** A panic in this method cannot unwind through libwayland, with the system backend it is
+    /// turned into a fatal error of the backend, returned by the dispatching.
     fn event(
         self: Arc<Self>,
         backend: &Backend,
         msg: Message<ObjectId, OwnedFd>,
     ) -> Option<Arc<dyn ObjectData>>;
 
+    /// Dispatch an event for the associated object, without copying its arguments
+    ///
+    /// This is the method the backend actually invokes. The string and array arguments of the message
+    /// borrow the buffers of the backend and are only valid during this call. The default implementation
+    /// converts the message into an owned one and forwards it to [`event()`](ObjectData::event),
+    /// implement it to avoid these allocations in hot paths.
+    fn event_borrowed(
+        self: Arc<Self>,
+        backend: &Backend,
+        msg: BorrowedMessage<'_, ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData>> {
+        self.event(backend, msg.into_owned())
+    }
+
     /// Notification that the object has been destroyed and is no longer active
     fn destroyed(&self, object_id: ObjectId);
 
@@ -58,8 +85,102 @@ impl std::fmt::Debug for dyn ObjectData {
     }
 }
 
+/// The user data of an object, downcasted to a concrete type
+///
+/// It is obtained with [`Backend::get_data_as()`] and dereferences to the value returned by
+/// [`ObjectData::data_as_any()`]. It keeps the object data alive.
+pub struct DataRef<T> {
+    data: Arc<dyn ObjectData>,
+    _type: PhantomData<T>,
+}
+
+impl<T: Any> DataRef<T> {
+    fn new(data: Arc<dyn ObjectData>) -> Option<Self> {
+        data.data_as_any().is::<T>().then_some(Self { data, _type: PhantomData })
+    }
+
+    /// Access the object data the user data was taken from
+    pub fn object_data(&self) -> &Arc<dyn ObjectData> {
+        &self.data
+    }
+}
+
+impl<T: Any> Deref for DataRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // the type was checked on creation
+        self.data.data_as_any().downcast_ref().unwrap()
+    }
+}
+
+impl<T> Clone for DataRef<T> {
+    fn clone(&self) -> Self {
+        Self { data: self.data.clone(), _type: PhantomData }
+    }
+}
+
+impl<T: Any + fmt::Debug> fmt::Debug for DataRef<T> {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
 downcast_rs::impl_downcast!(sync ObjectData);
 
+/// A hook seeing all the messages exchanged with the server
+///
+/// Each method returns whether the message should go through, messages vetoed by returning `false` are
+/// silently dropped. Messages creating or destroying objects cannot be vetoed, as this would
+/// desynchronize the client and the server: the return value is ignored for them.
+///
+/// Events to `wl_display` and the requests sent by the backend itself are not intercepted.
+///
+/// The interceptor may be invoked while the backend is internally locked, and must thus not call back
+/// into it, except to query objects with [`Backend::info()`] and [`Backend::get_data()`].
+///
+/// See [`Backend::set_interceptor()`].
+pub trait Interceptor: Send + Sync {
+    /// Inspect a request before it is sent to the server
+    fn request(&self, _msg: &Message<ObjectId, BorrowedFd<'_>>) -> bool {
+        true
+    }
+
+    /// Inspect an event from the server before it is dispatched to its [`ObjectData`]
+    fn event(&self, _msg: &BorrowedMessage<'_, ObjectId, OwnedFd>) -> bool {
+        true
+    }
+
+    /// Inspect a request along with its sequence number on the connection
+    ///
+    /// The messages are numbered from 1 in each direction, in the order they go through the socket: the
+    /// `n`-th request sent by the client is the `n`-th request received by the server, which allows to
+    /// correlate the traces of both sides. A vetoed request does not consume its number. The system
+    /// backend only numbers the messages going through this crate, in the order it handles them, not the
+    /// ones libwayland handles itself.
+    ///
+    /// The default implementation forwards to [`request()`](Interceptor::request).
+    fn request_sequenced(&self, _seq: u64, msg: &Message<ObjectId, BorrowedFd<'_>>) -> bool {
+        self.request(msg)
+    }
+
+    /// Inspect an event along with its sequence number on the connection
+    ///
+    /// See [`request_sequenced()`](Interceptor::request_sequenced) for the numbering. The default
+    /// implementation forwards to [`event()`](Interceptor::event).
+    fn event_sequenced(&self, _seq: u64, msg: &BorrowedMessage<'_, ObjectId, OwnedFd>) -> bool {
+        self.event(msg)
+    }
+}
+
+impl std::fmt::Debug for dyn Interceptor {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interceptor").finish_non_exhaustive()
+    }
+}
+
 /// An ID representing a Wayland object
 ///
 /// The backend internally tracks which IDs are still valid, invalidates them when the protocol object they
@@ -71,6 +192,12 @@ pub struct ObjectId {
     pub(crate) id: client_impl::InnerObjectId,
 }
 
+impl crate::debug::DebugId for ObjectId {
+    fn debug_id(&self) -> Option<(&str, u32)> {
+        (!self.is_null()).then(|| (self.interface().name, self.protocol_id()))
+    }
+}
+
 impl fmt::Display for ObjectId {
     #[cfg_attr(coverage, coverage(off))]
     #[inline]
@@ -125,6 +252,20 @@ impl ObjectId {
     }
 }
 
+/// An object known by the backend, as listed by [`Backend::all_objects()`]
+#[derive(Clone, Debug)]
+pub struct ObjectEntry {
+    /// The ID of the object
+    pub id: ObjectId,
+    /// The protocol information of the object
+    pub info: ObjectInfo,
+    /// Whether the object is alive
+    ///
+    /// An object destroyed by the client is still known by the rust backend until the server acknowledges
+    /// its destruction, it is then listed as not alive.
+    pub alive: bool,
+}
+
 /// A Wayland client backend
 ///
 /// This type hosts all the interface for interacting with the wayland protocol. It can be
@@ -150,6 +291,22 @@ impl WeakBackend {
     pub fn upgrade(&self) -> Option<Backend> {
         self.inner.upgrade().map(|backend| Backend { backend })
     }
+
+    /// Checks whether the associated backend is still alive
+    ///
+    /// Once this returns `false`, [`upgrade()`](WeakBackend::upgrade) will always fail.
+    pub fn is_alive(&self) -> bool {
+        self.inner.is_alive()
+    }
+
+    /// Register a callback to be invoked when the associated backend is dropped
+    ///
+    /// The callback is invoked from the thread dropping the last [`Backend`], after the connection has been
+    /// closed. If the backend was already dropped, it is invoked immediately. Any number of callbacks can
+    /// be registered.
+    pub fn on_drop(&self, callback: Box<dyn FnOnce() + Send>) {
+        self.inner.on_drop(DropCallback(callback))
+    }
 }
 
 impl Backend {
@@ -164,6 +321,48 @@ impl Backend {
         client_impl::InnerBackend::connect(stream).map(|backend| Self { backend })
     }
 
+    /// Try to initialize a Wayland backend on the provided socket file descriptor
+    ///
+    /// This is the same as [`Backend::connect()`], for an already connected stream socket that is not
+    /// wrapped in a [`UnixStream`], for example one end of a `socketpair()` received from a parent
+    /// process.
+    pub fn connect_fd(fd: OwnedFd) -> Result<Self, NoWaylandLib> {
+        Self::connect(UnixStream::from(fd))
+    }
+
+    /// Try to initialize a Wayland backend on the provided unix stream, with given buffer sizes
+    ///
+    /// This is the same as [`Backend::connect()`], but allows large messages to be exchanged by
+    /// configuring how much the buffers of the connection can grow, see [`BufferSizes`]. The `sys`
+    /// backend ignores these sizes.
+    pub fn connect_with_buffer_sizes(
+        stream: UnixStream,
+        sizes: BufferSizes,
+    ) -> Result<Self, NoWaylandLib> {
+        client_impl::InnerBackend::connect_with_buffer_sizes(stream, sizes)
+            .map(|backend| Self { backend })
+    }
+
+    /// Create a guest backend on the connection of this one
+    ///
+    /// The guest shares the connection, but dispatches its own objects: those created by the
+    /// requests sent through it, and the objects created by their events. Whichever thread reads
+    /// the connection, the events of these objects are queued for the guest, and only dispatched by
+    /// its [`ReadEventsGuard::read()`] or [`dispatch_inner_queue()`](Backend::dispatch_inner_queue).
+    /// This is the same as a private event queue of libwayland, and lets a library use a connection
+    /// whose main dispatching is driven elsewhere.
+    ///
+    /// To wait for its events, the guest follows the reading contract of the connection: it prepares
+    /// a read with [`prepare_read()`](Backend::prepare_read), which returns `None` while events are
+    /// queued for it, polls the connection fd and reads. The other readers of the connection wait for
+    /// the guest to read or drop its guard, so that its events are never read behind its back.
+    ///
+    /// The guest keeps the connection alive. Dropping it abandons its remaining objects, their events
+    /// are discarded.
+    pub fn create_guest(&self) -> Self {
+        Self { backend: self.backend.create_guest() }
+    }
+
     /// Get a [`WeakBackend`] from this backend
     pub fn downgrade(&self) -> WeakBackend {
         WeakBackend { inner: self.backend.downgrade() }
@@ -176,17 +375,90 @@ impl Backend {
     /// later.
     ///
     /// You can however expect this method returning `WouldBlock` to be very rare: it can only occur if
-    /// either your client sent a lot of big messages at once, or the server is very laggy.
+    /// either your client sent a lot of big messages at once, or the server is very laggy. The requests
+    /// that could not be written, including the end of a partially written one, stay buffered and are
+    /// sent first by the next flush.
     pub fn flush(&self) -> Result<(), WaylandError> {
         self.backend.flush()
     }
 
+    /// Number of bytes of requests buffered and not yet written to the socket
+    ///
+    /// This is non-zero after [`Backend::flush()`] returned a `WouldBlock` error.
+    ///
+    /// **Note:** This is always 0 with the system backend, libwayland does not expose its buffers.
+    #[inline]
+    pub fn pending_output_bytes(&self) -> usize {
+        self.backend.pending_output_bytes()
+    }
+
     /// Access the Wayland socket FD for polling
     #[inline]
     pub fn poll_fd(&self) -> BorrowedFd {
         self.backend.poll_fd()
     }
 
+    /// Wake up the threads waiting for events on this connection
+    ///
+    /// This makes the file descriptor of [`Backend::wakeup_fd()`] readable until the wakeup is consumed
+    /// by [`Backend::take_wakeup()`]. The blocking methods of `wayland-client` monitor it along with the
+    /// connection and return early once it is readable, so that another thread can promptly get a thread
+    /// blocked waiting for the server to flush its requests or to stop. A wakeup happening while no thread
+    /// is waiting is kept until the next wait.
+    #[inline]
+    pub fn wakeup(&self) -> std::io::Result<()> {
+        self.backend.wakeup()
+    }
+
+    /// Access the file descriptor to poll along with the connection to be interrupted by [`Backend::wakeup()`]
+    ///
+    /// It is readable while a wakeup is pending. It is created on first use of the wakeup, which is why
+    /// this can fail.
+    #[inline]
+    pub fn wakeup_fd(&self) -> std::io::Result<BorrowedFd> {
+        self.backend.wakeup_fd()
+    }
+
+    /// Consume the pending wakeups, returning whether there were any
+    #[inline]
+    pub fn take_wakeup(&self) -> bool {
+        self.backend.take_wakeup()
+    }
+
+    /// Retrieve the performance counters of this connection
+    ///
+    /// **Note:** The system backend only counts the messages, file descriptors and dispatch time, see
+    /// [`Metrics`] for details.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    pub fn metrics(&self) -> Metrics {
+        self.backend.metrics()
+    }
+
+    /// Number of events discarded because the object they were sent to was already destroyed, per
+    /// interface name
+    ///
+    /// These are the events the server sent before it processed the destruction of the object, some
+    /// are expected, but a steady count can reveal objects destroyed too early.
+    ///
+    /// **Note:** always empty on the system backend, libwayland discards these events by itself.
+    #[inline]
+    pub fn dead_object_events(&self) -> HashMap<&'static str, u64> {
+        self.backend.dead_object_events()
+    }
+
+    /// Report the objects, messages and buffers held by the backend for this connection
+    ///
+    /// The sizes are approximate, they do not include the object data of the objects. A steady growth
+    /// of these numbers in a long-running client reveals protocol state that is never released.
+    ///
+    /// **Note:** The system backend only accounts for the objects it manages, the messages and buffers
+    /// of libwayland are not exposed.
+    #[inline]
+    pub fn memory_report(&self) -> MemoryReport {
+        self.backend.memory_report()
+    }
+
     /// Get the object ID for the `wl_display`
     #[inline]
     pub fn display_id(&self) -> ObjectId {
@@ -204,15 +476,35 @@ impl Backend {
     /// Get the detailed protocol information about a wayland object
     ///
     /// Returns an error if the provided object ID is no longer valid.
+    ///
+    /// This does not wait for other threads sending requests or dispatching events, so that it can be
+    /// invoked often, for example to check the version of an object when rendering.
     #[inline]
     pub fn info(&self, id: ObjectId) -> Result<ObjectInfo, InvalidId> {
         self.backend.info(id)
     }
 
+    /// List all the objects known by this backend
+    ///
+    /// This is meant for debugging, like detecting leaked objects. Objects not managed by this backend
+    /// (when multiple libraries share the same Wayland socket via `libwayland` if using the system backend)
+    /// are not listed.
+    #[inline]
+    pub fn all_objects(&self) -> Vec<ObjectEntry> {
+        self.backend.all_objects()
+    }
+
     /// Sends a request to the server
     ///
     /// Returns an error if the sender ID of the provided message is no longer valid.
     ///
+    /// An error is also returned if the request creates an object while all the IDs of the client
+    /// namespace are used, the request is then not sent. The rust backend also fails the connection
+    /// with an `ENOSPC` IO error, the error libwayland uses when it cannot allocate an ID.
+    ///
+    /// The file descriptors of the message are duplicated by the backend, the caller keeps ownership
+    /// of the borrowed ones.
+    ///
     /// **Panic:**
     ///
     /// Several checks against the protocol specification are done, and this method will panic if they do
@@ -225,33 +517,128 @@ impl Backend {
     ///   can be provided. If one is provided, it'll be checked against the protocol spec. If the
     ///   protocol specification does not define the interface of the created object (notable example
     ///   is `wl_registry.bind`), the `child_spec` must be provided.
+    ///
+    /// With the `debug-checks` cargo feature, this method also panics instead of returning an error
+    /// if the sender or an object argument is no longer alive while the connection is.
     pub fn send_request(
         &self,
-        msg: Message<ObjectId, RawFd>,
+        msg: Message<ObjectId, BorrowedFd<'_>>,
         data: Option<Arc<dyn ObjectData>>,
         child_spec: Option<(&'static Interface, u32)>,
     ) -> Result<ObjectId, InvalidId> {
-        self.backend.send_request(msg, data, child_spec)
+        #[cfg(feature = "debug-checks")]
+        let (opcode, objects) = (
+            msg.opcode,
+            std::iter::once(msg.sender_id.clone())
+                .chain(msg.args.iter().filter_map(|arg| match arg {
+                    Argument::Object(id) if !id.is_null() => Some(id.clone()),
+                    _ => None,
+                }))
+                .collect::<Vec<_>>(),
+        );
+        let ret = self.backend.send_request(msg, data, child_spec);
+        #[cfg(feature = "debug-checks")]
+        if ret.is_err() {
+            self.check_dead_objects(opcode, &objects);
+        }
+        ret
+    }
+
+    // Panic if a request was not sent because it uses a dead object. The other reasons of failure
+    // are the connection being dead, and the IDs being exhausted on the system backend.
+    #[cfg(feature = "debug-checks")]
+    fn check_dead_objects(&self, opcode: u16, objects: &[ObjectId]) {
+        if self.last_error().is_some() {
+            return;
+        }
+        if let Some(dead) = objects.iter().find(|id| self.info((*id).clone()).is_err()) {
+            let request =
+                objects[0].interface().requests.get(opcode as usize).map_or("?", |r| r.name);
+            panic!(
+                "[wayland-backend] Sending request {}.{} on a dead object: {} is no longer alive.",
+                objects[0], request, dead
+            );
+        }
     }
 
     /// Access the object data associated with a given object ID
     ///
-    /// Returns an error if the object ID is not longer valid or if it corresponds to a Wayland
-    /// object that is not managed by this backend (when multiple libraries share the same Wayland
-    /// socket via `libwayland` if using the system backend).
+    /// Returns an error if the object ID is not longer valid. For a Wayland object that is not managed
+    /// by this backend (when multiple libraries share the same Wayland socket via `libwayland` if using
+    /// the system backend), this is the data set with [`Backend::set_data()`], if any.
     pub fn get_data(&self, id: ObjectId) -> Result<Arc<dyn ObjectData>, InvalidId> {
         self.backend.get_data(id)
     }
 
+    /// Access the user data of an object as a concrete type
+    ///
+    /// This downcasts the value returned by [`ObjectData::data_as_any()`] for the object data of this
+    /// object. Returns `None` if the object ID is no longer valid or if its user data is not a `T`.
+    pub fn get_data_as<T: Any>(&self, id: ObjectId) -> Option<DataRef<T>> {
+        self.get_data(id).ok().and_then(DataRef::new)
+    }
+
     /// Set the object data associated with a given object ID
     ///
-    /// Returns an error if the object ID is not longer valid or if it corresponds to a Wayland
-    /// object that is not managed by this backend (when multiple libraries share the same Wayland
-    /// socket via `libwayland` if using the system backend).
+    /// Returns an error if the object ID is not longer valid.
+    ///
+    /// A Wayland object that is not managed by this backend (when multiple libraries share the same
+    /// Wayland socket via `libwayland` if using the system backend) can also be given an object data,
+    /// to be retrieved with [`Backend::get_data()`] when it is received as an event argument. Its
+    /// [`ObjectData::event()`] and [`ObjectData::destroyed()`] methods are never invoked, as this
+    /// backend does not handle the events of such an object, and it must be removed with
+    /// `Backend::remove_data()` of the system backend before the object is destroyed.
     pub fn set_data(&self, id: ObjectId, data: Arc<dyn ObjectData>) -> Result<(), InvalidId> {
         self.backend.set_data(id, data)
     }
 
+    /// Replace the object data associated with a given object ID, returning the previous one
+    ///
+    /// Unlike a [`Backend::get_data()`] followed by a [`Backend::set_data()`], no other thread can
+    /// change the object data in between. This allows to wrap the object data of an object, and to
+    /// unwrap it later.
+    ///
+    /// Returns an error if the object ID is not longer valid. For a Wayland object that is not
+    /// managed by this backend, an error is also returned if it was not given an object data with
+    /// [`Backend::set_data()`] beforehand.
+    pub fn replace_data(
+        &self,
+        id: ObjectId,
+        data: Arc<dyn ObjectData>,
+    ) -> Result<Arc<dyn ObjectData>, InvalidId> {
+        self.backend.replace_data(id, data)
+    }
+
+    /// Set the interceptor seeing all requests and events before they are sent or dispatched, or
+    /// remove it with `None`
+    ///
+    /// See [`Interceptor`] for the messages it can observe and veto.
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.backend.set_interceptor(interceptor)
+    }
+
+    /// Set a sink receiving the debug output of this connection, or remove it with `None`
+    ///
+    /// Each message sent or received is given to the sink as a line in the same format as the
+    /// `WAYLAND_DEBUG` output of libwayland, without its trailing newline. While a sink is set the output
+    /// is no longer printed to stderr, and it is produced even if `WAYLAND_DEBUG` is not set.
+    ///
+    /// **Note:** This is ignored by the system backend, libwayland prints its own debug output.
+    pub fn set_debug_sink(&self, sink: Option<DebugSink>) {
+        self.backend.set_debug_sink(sink)
+    }
+
+    /// Set the maximum size the buffers of the connection can grow to, in bytes
+    ///
+    /// This allows to exchange larger messages, or bursts of messages, than the defaults allow. See
+    /// [`BufferSizes`] to configure the buffers when connecting.
+    ///
+    /// **Note:** The system backend only supports it if the `libwayland_1_23` cargo feature is enabled,
+    /// which requires libwayland 1.23. A size of `0` then means unlimited.
+    pub fn set_max_buffer_size(&self, size: usize) {
+        self.backend.set_max_buffer_size(size)
+    }
+
     /// Create a new reading guard
     ///
     /// This is the first step for actually reading events from the Wayland socket. See
@@ -263,8 +650,20 @@ impl Backend {
     #[inline]
     #[must_use]
     pub fn prepare_read(&self) -> Option<ReadEventsGuard> {
-        client_impl::InnerReadEventsGuard::try_new(self.backend.clone())
-            .map(|guard| ReadEventsGuard { guard })
+        #[cfg(feature = "debug-checks")]
+        let mark = crate::debug_checks::enter(
+            crate::debug_checks::Activity::Reading,
+            self.backend.connection_key(),
+            "prepare_read() was called while this thread already holds a ReadEventsGuard of the \
+             same connection, reading would wait for the first guard forever.",
+        );
+        client_impl::InnerReadEventsGuard::try_new(self.backend.clone()).map(|guard| {
+            ReadEventsGuard {
+                guard,
+                #[cfg(feature = "debug-checks")]
+                _mark: mark,
+            }
+        })
     }
 
     /// Dispatches the inner queue of this backend if necessary
@@ -281,6 +680,63 @@ impl Backend {
     pub fn dispatch_inner_queue(&self) -> Result<usize, WaylandError> {
         self.backend.dispatch_inner_queue()
     }
+
+    /// Send a `wl_display.sync` request, invoking `callback` once the server answered it
+    ///
+    /// As the server processes the requests in order, the callback is invoked once all the requests
+    /// sent before this one have been processed. It is invoked from the dispatching of the events, like
+    /// the [`ObjectData`] callbacks.
+    ///
+    /// Returns the ID of the `wl_callback` object created by the request.
+    pub fn sync(
+        &self,
+        callback: impl FnOnce(&Backend) + Send + 'static,
+    ) -> Result<ObjectId, InvalidId> {
+        self.send_request(
+            Message {
+                sender_id: self.display_id(),
+                opcode: 0,
+                args: smallvec::smallvec![Argument::NewId(ObjectId::null())],
+            },
+            Some(Arc::new(SyncData(Mutex::new(Some(Box::new(callback)))))),
+            Some((&WL_CALLBACK_INTERFACE, 1)),
+        )
+    }
+
+    /// Do a roundtrip to the server
+    ///
+    /// This method flushes the pending requests and blocks until the server has processed and answered
+    /// all of them, dispatching the received events to their [`ObjectData`] in the meantime.
+    ///
+    /// Returns the number of messages that were dispatched.
+    pub fn roundtrip(&self) -> Result<usize, WaylandError> {
+        let done = Arc::new(AtomicBool::new(false));
+        let done2 = done.clone();
+        self.sync(move |_| done2.store(true, Ordering::Relaxed))
+            .map_err(|_| WaylandError::Disconnected(self.protocol_error()))?;
+
+        let mut dispatched = 0;
+
+        while !done.load(Ordering::Relaxed) {
+            self.flush()?;
+
+            if let Some(guard) = self.prepare_read() {
+                dispatched += guard.blocking_read(None)?;
+            } else {
+                dispatched += self.dispatch_inner_queue()?;
+            }
+        }
+
+        Ok(dispatched)
+    }
+
+    fn protocol_error(&self) -> Option<ProtocolError> {
+        match self.last_error()? {
+            WaylandError::Protocol(err) => Some(err),
+            WaylandError::Disconnected(err) => err,
+            WaylandError::Io(_) | WaylandError::Wire(_) => None,
+        }
+    }
 }
 
 /// Guard for synchronizing event reading across multiple threads
@@ -302,6 +758,8 @@ impl Backend {
 #[derive(Debug)]
 pub struct ReadEventsGuard {
     pub(crate) guard: client_impl::InnerReadEventsGuard,
+    #[cfg(feature = "debug-checks")]
+    _mark: crate::debug_checks::ActivityMark,
 }
 
 impl ReadEventsGuard {
@@ -322,8 +780,55 @@ impl ReadEventsGuard {
     /// If no events are available to read from the socket, this returns a `WouldBlock` IO error.
     #[inline]
     pub fn read(self) -> Result<usize, WaylandError> {
+        // the event callbacks may prepare new reads
+        #[cfg(feature = "debug-checks")]
+        drop(self._mark);
         self.guard.read()
     }
+
+    /// Wait for the Wayland socket to be readable and read the events
+    ///
+    /// Unlike [`read()`](ReadEventsGuard::read), this returns `0` instead of a `WouldBlock` IO error if
+    /// another thread read the events.
+    ///
+    /// If a backend is given, this also returns `0` early without reading, cancelling the read, once it
+    /// is woken up by [`Backend::wakeup()`]. The wakeup is then consumed, unless events can be read as
+    /// well: they are read first and the wakeup is kept for the next wait.
+    pub fn blocking_read(self, wakeup: Option<&Backend>) -> Result<usize, WaylandError> {
+        let fd = self.connection_fd();
+        let wakeup_fd = wakeup.map(Backend::wakeup_fd).transpose().map_err(WaylandError::Io)?;
+        let mut fds = vec![rustix::event::PollFd::new(
+            &fd,
+            rustix::event::PollFlags::IN | rustix::event::PollFlags::ERR,
+        )];
+        if let Some(ref wakeup_fd) = wakeup_fd {
+            fds.push(rustix::event::PollFd::new(wakeup_fd, rustix::event::PollFlags::IN));
+        }
+
+        loop {
+            match rustix::event::poll(&mut fds, -1) {
+                Ok(_) => break,
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(e) => return Err(WaylandError::Io(e.into())),
+            }
+        }
+
+        let woken_up = fds.get(1).map_or(false, |wakeup_fd| !wakeup_fd.revents().is_empty());
+        if woken_up && fds[0].revents().is_empty() {
+            // woken up before any event arrived, dropping the guard cancels the read
+            if let Some(backend) = wakeup {
+                backend.take_wakeup();
+            }
+            return Ok(0);
+        }
+
+        match self.read() {
+            Ok(n) => Ok(n),
+            // the events were read by an other thread, the caller will retry
+            Err(WaylandError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
 }
 pub(crate) struct DumbObjectData;
 
@@ -363,3 +868,54 @@ impl ObjectData for UninitObjectData {
         f.debug_struct("UninitObjectData").finish()
     }
 }
+
+type SyncCallback = Box<dyn FnOnce(&Backend) + Send>;
+
+// The object data of the wl_callback created by `Backend::sync()`
+struct SyncData(Mutex<Option<SyncCallback>>);
+
+impl ObjectData for SyncData {
+    fn event(
+        self: Arc<Self>,
+        backend: &Backend,
+        _msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData>> {
+        if let Some(callback) = self.0.lock().unwrap().take() {
+            callback(backend);
+        }
+        None
+    }
+
+    fn destroyed(&self, _object_id: ObjectId) {}
+
+    #[cfg_attr(coverage, coverage(off))]
+    fn debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncData").finish_non_exhaustive()
+    }
+}
+
+pub(crate) struct DropCallback(pub(crate) Box<dyn FnOnce() + Send>);
+
+// The callbacks registered with `WeakBackend::on_drop()`, invoked when the backend state is dropped
+#[derive(Debug, Default)]
+pub(crate) struct DropCallbacks(Mutex<Vec<DropCallback>>);
+
+impl DropCallbacks {
+    pub(crate) fn push(&self, callback: DropCallback) {
+        self.0.lock().unwrap().push(callback);
+    }
+}
+
+impl Drop for DropCallbacks {
+    fn drop(&mut self) {
+        for DropCallback(cb) in std::mem::take(self.0.get_mut().unwrap()) {
+            cb();
+        }
+    }
+}
+
+impl fmt::Debug for DropCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DropCallback").finish_non_exhaustive()
+    }
+}
@@ -1,64 +1,292 @@
 //! Debugging helpers to handle `WAYLAND_DEBUG` env variable.
+//!
+//! The output follows the format of libwayland, so that logs of both backends look the same.
 
 use std::{
-    fmt::Display,
-    os::unix::prelude::AsRawFd,
+    fmt::{self, Write},
+    os::unix::io::{AsFd, AsRawFd, BorrowedFd},
+    sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::protocol::Argument;
+use smallvec::SmallVec;
+
+use crate::protocol::{
+    Argument, BorrowedArgument, Direction, EnumDesc, Interface, MessageDesc, INLINE_ARGS,
+};
 
 /// The `WAYLAND_DEBUG` env variable is set to debug client.
 pub fn has_debug_client_env() -> bool {
-    matches!(std::env::var_os("WAYLAND_DEBUG"), Some(str) if str == "1" || str == "client")
+    debug_enabled(std::env::var_os("WAYLAND_DEBUG").as_deref(), "client")
+}
+
+/// The `WAYLAND_DEBUG` env variable is set to debug server.
+pub fn has_debug_server_env() -> bool {
+    debug_enabled(std::env::var_os("WAYLAND_DEBUG").as_deref(), "server")
+}
+
+// Like libwayland, `WAYLAND_DEBUG` enables the output if it contains "1" or the side name, so that
+// for example `client,server` enables both
+fn debug_enabled(value: Option<&std::ffi::OsStr>, side: &str) -> bool {
+    match value {
+        Some(value) => {
+            let value = value.to_string_lossy();
+            value.contains('1') || value.contains(side)
+        }
+        None => false,
+    }
+}
+
+/// A sink receiving the debug output of the rust backend, one line per message
+pub type DebugSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Where the debug output is written
+#[derive(Clone)]
+pub(crate) enum DebugOutput {
+    Stderr,
+    Sink(DebugSink),
+}
+
+impl DebugOutput {
+    /// The output to use, if any: the sink if one is set, otherwise stderr if enabled by `WAYLAND_DEBUG`
+    pub(crate) fn new(sink: Option<DebugSink>, env: bool) -> Option<Self> {
+        match sink {
+            Some(sink) => Some(Self::Sink(sink)),
+            None => env.then_some(Self::Stderr),
+        }
+    }
+
+    fn write(&self, line: &str) {
+        match self {
+            Self::Stderr => eprintln!("{}", line),
+            Self::Sink(sink) => sink(line),
+        }
+    }
+}
+
+impl fmt::Debug for DebugOutput {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stderr => f.write_str("Stderr"),
+            Self::Sink(_) => f.write_str("Sink(..)"),
+        }
+    }
+}
+
+/// An object ID as shown in the debug output
+pub(crate) trait DebugId {
+    /// The interface name and protocol ID of the object, or `None` for a null object
+    fn debug_id(&self) -> Option<(&str, u32)>;
+}
+
+/// An object ID from the wire, with its interface if known
+pub(crate) struct RawId {
+    pub(crate) interface: Option<&'static str>,
+    pub(crate) id: u32,
+}
+
+impl DebugId for RawId {
+    fn debug_id(&self) -> Option<(&str, u32)> {
+        (self.id != 0).then(|| (self.interface.unwrap_or("[unknown]"), self.id))
+    }
+}
+
+/// Resolve the object IDs of arguments from the wire, using the interfaces of the objects they refer to
+pub(crate) fn resolve_ids<'a, Fd: AsFd>(
+    args: &'a [BorrowedArgument<'a, u32, Fd>],
+    desc: &MessageDesc,
+    interface_of: impl Fn(u32) -> Option<&'static Interface>,
+) -> SmallVec<[BorrowedArgument<'a, RawId, BorrowedFd<'a>>; INLINE_ARGS]> {
+    args.iter()
+        .map(|arg| match *arg {
+            BorrowedArgument::Int(value) => BorrowedArgument::Int(value),
+            BorrowedArgument::Uint(value) => BorrowedArgument::Uint(value),
+            BorrowedArgument::Fixed(value) => BorrowedArgument::Fixed(value),
+            BorrowedArgument::Str(value) => BorrowedArgument::Str(value),
+            BorrowedArgument::Object(id) => BorrowedArgument::Object(RawId {
+                interface: interface_of(id).map(|iface| iface.name),
+                id,
+            }),
+            BorrowedArgument::NewId(id) => BorrowedArgument::NewId(RawId {
+                interface: desc.child_interface.map(|iface| iface.name),
+                id,
+            }),
+            BorrowedArgument::Array(value) => BorrowedArgument::Array(value),
+            BorrowedArgument::Fd(ref fd) => BorrowedArgument::Fd(fd.as_fd()),
+        })
+        .collect()
+}
+
+/// An argument as shown in the debug output
+pub(crate) trait DebugArgument {
+    fn fmt_debug(&self, f: &mut String) -> fmt::Result;
+
+    /// The value of an integer argument, which may refer to an enum
+    fn enum_value(&self) -> Option<u32>;
+}
+
+impl<Id: DebugId, Fd: AsRawFd> DebugArgument for Argument<Id, Fd> {
+    fn fmt_debug(&self, f: &mut String) -> fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Uint(value) => write!(f, "{}", value),
+            Self::Fixed(value) => write_fixed(f, *value),
+            Self::Str(Some(value)) => write!(f, "\"{}\"", value.to_string_lossy()),
+            Self::Str(None) => f.write_str("nil"),
+            Self::Object(id) => write_object(f, id),
+            Self::NewId(id) => write_new_id(f, id),
+            Self::Array(value) => write!(f, "array[{}]", value.len()),
+            Self::Fd(fd) => write!(f, "fd {}", fd.as_raw_fd()),
+        }
+    }
+
+    fn enum_value(&self) -> Option<u32> {
+        match *self {
+            Self::Int(value) => Some(value as u32),
+            Self::Uint(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, Id: DebugId, Fd: AsRawFd> DebugArgument for BorrowedArgument<'a, Id, Fd> {
+    fn fmt_debug(&self, f: &mut String) -> fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Uint(value) => write!(f, "{}", value),
+            Self::Fixed(value) => write_fixed(f, *value),
+            Self::Str(Some(value)) => write!(f, "\"{}\"", value.to_string_lossy()),
+            Self::Str(None) => f.write_str("nil"),
+            Self::Object(id) => write_object(f, id),
+            Self::NewId(id) => write_new_id(f, id),
+            Self::Array(value) => write!(f, "array[{}]", value.len()),
+            Self::Fd(fd) => write!(f, "fd {}", fd.as_raw_fd()),
+        }
+    }
+
+    fn enum_value(&self) -> Option<u32> {
+        match *self {
+            Self::Int(value) => Some(value as u32),
+            Self::Uint(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// libwayland prints fixed point numbers with 8 decimals, 390625 is 1e8 / 256
+fn write_fixed(f: &mut String, value: i32) -> fmt::Result {
+    if value >= 0 {
+        write!(f, "{}.{:08}", value / 256, 390625 * (value % 256))
+    } else {
+        write!(f, "-{}.{:08}", value / -256, -390625 * (value % 256))
+    }
+}
+
+fn write_object(f: &mut String, id: &impl DebugId) -> fmt::Result {
+    match id.debug_id() {
+        Some((interface, id)) => write!(f, "{}@{}", interface, id),
+        None => f.write_str("nil"),
+    }
 }
 
-/// Print the dispatched message to stderr in a following format:
+fn write_new_id(f: &mut String, id: &impl DebugId) -> fmt::Result {
+    match id.debug_id() {
+        Some((interface, id)) => write!(f, "new id {}@{}", interface, id),
+        None => f.write_str("new id nil"),
+    }
+}
+
+/// Print the dispatched message in a following format:
 ///
-/// [timestamp] <- interface@id.msg_name(args)
+/// [timestamp] interface@id.msg_name(args)
+///
+/// If the message was received from a client with a label, it is printed after the timestamp.
 #[cfg_attr(coverage, coverage(off))]
-pub fn print_dispatched_message<Id: Display, Fd: AsRawFd>(
+pub(crate) fn print_dispatched_message<A: DebugArgument>(
+    output: &DebugOutput,
+    direction: Direction,
     interface: &str,
     id: u32,
     msg_name: &str,
-    args: &[Argument<Id, Fd>],
+    args: &[A],
+    label: Option<&str>,
 ) {
-    // Add timestamp to output.
-    print_timestamp();
-
-    eprint!(" <- {}@{}.{}, ({})", interface, id, msg_name, DisplaySlice(args));
+    let message = format!("{}@{}.{}", interface, id, msg_name);
+    let enums = |arg| EnumDesc::find(interface, direction, msg_name, arg);
+    print_message(output, &message, args, enums, label)
+}
 
-    // Add a new line.
-    eprintln!();
+/// Print the send message in a following format:
+///
+/// [timestamp]  -> interface@id.msg_name(args)
+///
+/// If the message is sent to a client with a label, it is printed after the timestamp.
+#[cfg_attr(coverage, coverage(off))]
+pub(crate) fn print_send_message<A: DebugArgument>(
+    output: &DebugOutput,
+    direction: Direction,
+    interface: &str,
+    id: u32,
+    msg_name: &str,
+    args: &[A],
+    label: Option<&str>,
+) {
+    let message = format!(" -> {}@{}.{}", interface, id, msg_name);
+    let enums = |arg| EnumDesc::find(interface, direction, msg_name, arg);
+    print_message(output, &message, args, enums, label)
 }
 
-/// Print the send message to stderr in a following format:
+/// Print a request discarded because its object is already destroyed, in a following format:
 ///
-/// [timestamp] -> interface@id.msg_name(args)
+/// [timestamp]discarded  -> interface@id.msg_name(args)
 #[cfg_attr(coverage, coverage(off))]
-pub fn print_send_message<Id: Display, Fd: AsRawFd>(
+pub(crate) fn print_discarded_message<A: DebugArgument>(
+    output: &DebugOutput,
     interface: &str,
     id: u32,
     msg_name: &str,
-    args: &[Argument<Id, Fd>],
-    discarded: bool,
+    args: &[A],
 ) {
-    // Add timestamp to output.
-    print_timestamp();
+    let message = format!("discarded  -> {}@{}.{}", interface, id, msg_name);
+    let enums = |arg| EnumDesc::find(interface, Direction::Request, msg_name, arg);
+    print_message(output, &message, args, enums, None)
+}
 
-    if discarded {
-        eprint!("[discarded]");
+#[cfg_attr(coverage, coverage(off))]
+fn print_message<A: DebugArgument>(
+    output: &DebugOutput,
+    message: &str,
+    args: &[A],
+    enums: impl Fn(usize) -> Option<&'static EnumDesc>,
+    label: Option<&str>,
+) {
+    let mut line = timestamp();
+    if let Some(label) = label {
+        let _ = write!(line, "[{}] ", label);
     }
-
-    eprint!(" -> {}@{}.{}({})", interface, id, msg_name, DisplaySlice(args));
-
-    // Add a new line.
-    eprintln!();
+    let _ = write!(line, "{}(", message);
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            line.push_str(", ");
+        }
+        // the arguments referring to a registered enum are shown by name
+        match arg.enum_value().and_then(|value| Some((value, enums(i)?))) {
+            Some((value, desc)) => line.push_str(&desc.format(value)),
+            None => {
+                let _ = arg.fmt_debug(&mut line);
+            }
+        }
+    }
+    line.push(')');
+    output.write(&line);
 }
 
+#[cfg(feature = "log")]
 pub(crate) struct DisplaySlice<'a, D>(pub &'a [D]);
 
-impl<'a, D: Display> Display for DisplaySlice<'a, D> {
+#[cfg(feature = "log")]
+impl<'a, D: std::fmt::Display> std::fmt::Display for DisplaySlice<'a, D> {
     #[cfg_attr(coverage, coverage(off))]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut it = self.0.iter();
@@ -72,14 +300,125 @@ impl<'a, D: Display> Display for DisplaySlice<'a, D> {
     }
 }
 
-/// Print timestamp in seconds.microseconds format.
+/// Timestamp in the milliseconds.microseconds format of libwayland
 #[cfg_attr(coverage, coverage(off))]
-fn print_timestamp() {
-    if let Ok(timestamp) = SystemTime::now().duration_since(UNIX_EPOCH) {
-        // NOTE this is all to make timestamps the same with libwayland, so the log doesn't look
-        // out of place when sys tries to log on their own.
-        let time = (timestamp.as_secs() * 1000000 + timestamp.subsec_nanos() as u64 / 1000) as u32;
-        // NOTE annotate timestamp so we know which library emmited the log entry.
-        eprint!("[{:7}.{:03}][rs]", time / 1000, time % 1000);
+fn timestamp() -> String {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(timestamp) => {
+            // libwayland truncates the time in microseconds to 32 bits
+            let time =
+                (timestamp.as_secs() * 1000000 + timestamp.subsec_nanos() as u64 / 1000) as u32;
+            format!("[{:7}.{:03}] ", time / 1000, time % 1000)
+        }
+        Err(_) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smallvec::smallvec;
+    use std::{ffi::CString, os::unix::io::RawFd, sync::Mutex};
+
+    #[test]
+    fn debug_env() {
+        let enabled = |value: &str, side| debug_enabled(Some(value.as_ref()), side);
+        assert!(enabled("1", "server") && enabled("1", "client"));
+        assert!(enabled("server", "server") && !enabled("server", "client"));
+        assert!(enabled("client,server", "server") && enabled("client,server", "client"));
+        assert!(!enabled("0", "server"));
+        assert!(!debug_enabled(None, "client"));
+    }
+
+    #[test]
+    fn libwayland_format() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let lines2 = lines.clone();
+        let output = DebugOutput::Sink(Arc::new(move |line: &str| {
+            lines2.lock().unwrap().push(line.to_owned())
+        }));
+        let raw_id = |interface, id| RawId { interface, id };
+        let args: smallvec::SmallVec<[Argument<RawId, RawFd>; 4]> = smallvec![
+            Argument::Int(-3),
+            Argument::Uint(7),
+            Argument::Fixed(-384),
+            Argument::Fixed(640),
+            Argument::Str(Some(Box::new(CString::new("hello").unwrap()))),
+            Argument::Str(None),
+            Argument::Object(raw_id(Some("wl_surface"), 5)),
+            Argument::Object(raw_id(None, 0)),
+            Argument::NewId(raw_id(None, 8)),
+            Argument::Array(Box::new(vec![0; 12])),
+            Argument::Fd(4),
+        ];
+        print_send_message(&output, Direction::Request, "test", 3, "send", &args, None);
+        print_discarded_message(&output, "test", 3, "send", &args[..1]);
+        print_dispatched_message(
+            &output,
+            Direction::Event,
+            "test",
+            3,
+            "recv",
+            &args[..0],
+            Some("label"),
+        );
+
+        let lines = lines.lock().unwrap();
+        // strip the timestamps
+        let lines = lines.iter().map(|line| line.split_once("] ").unwrap().1).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                " -> test@3.send(-3, 7, -1.50000000, 2.50000000, \"hello\", nil, wl_surface@5, nil, new id [unknown]@8, array[12], fd 4)",
+                "discarded  -> test@3.send(-3)",
+                "[label] test@3.recv()",
+            ]
+        );
+    }
+
+    #[test]
+    fn enum_arguments() {
+        static CAPABILITY: EnumDesc = EnumDesc {
+            name: "test_seat.capability",
+            bitfield: true,
+            entries: &[("pointer", 1), ("keyboard", 2)],
+        };
+        EnumDesc::register(&[crate::protocol::ArgEnum {
+            interface: "test_seat",
+            direction: Direction::Event,
+            message: "capabilities",
+            arg: 1,
+            desc: &CAPABILITY,
+        }]);
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let lines2 = lines.clone();
+        let output = DebugOutput::Sink(Arc::new(move |line: &str| {
+            lines2.lock().unwrap().push(line.to_owned())
+        }));
+        let args: [Argument<RawId, RawFd>; 2] = [Argument::Uint(3), Argument::Uint(3)];
+        print_dispatched_message(
+            &output,
+            Direction::Event,
+            "test_seat",
+            4,
+            "capabilities",
+            &args,
+            None,
+        );
+        // a request of the same name does not refer to the enum of the event
+        print_send_message(
+            &output,
+            Direction::Request,
+            "test_seat",
+            4,
+            "capabilities",
+            &args,
+            None,
+        );
+
+        let lines = lines.lock().unwrap();
+        assert!(lines[0].ends_with("test_seat@4.capabilities(3, pointer|keyboard)"));
+        assert!(lines[1].ends_with(" -> test_seat@4.capabilities(3, 3)"));
     }
 }
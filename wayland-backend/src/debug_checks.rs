@@ -0,0 +1,56 @@
+//! Assertions on misuses of the backends, enabled by the `debug-checks` cargo feature
+//!
+//! Some misuses of the API are not unsound, but deadlock or silently lose messages. These checks
+//! turn them into panics explaining what went wrong, at the cost of some bookkeeping.
+
+use std::cell::RefCell;
+
+/// Something the current thread is doing on a connection, that it cannot start again on the same
+/// connection before it is done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Activity {
+    /// The thread holds a `ReadEventsGuard`, reading from another one would wait for it forever
+    Reading,
+    /// The thread is dispatching the inner queue of the system backend, which is not re-entrant
+    #[cfg(any(test, feature = "client_system"))]
+    Dispatching,
+}
+
+thread_local! {
+    // the activities of the current thread, with the address of the state of their connection
+    static ACTIVITIES: RefCell<Vec<(Activity, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks an activity of the current thread on a connection until it is dropped
+#[derive(Debug)]
+pub(crate) struct ActivityMark {
+    activity: Activity,
+    connection: usize,
+}
+
+impl Drop for ActivityMark {
+    fn drop(&mut self) {
+        ACTIVITIES.with(|activities| {
+            let mut activities = activities.borrow_mut();
+            if let Some(idx) =
+                activities.iter().rposition(|&entry| entry == (self.activity, self.connection))
+            {
+                activities.remove(idx);
+            }
+        });
+    }
+}
+
+/// Start an activity on a connection, panicking with `misuse` if the current thread is already doing it
+pub(crate) fn enter(activity: Activity, connection: usize, misuse: &str) -> ActivityMark {
+    ACTIVITIES.with(|activities| {
+        let mut activities = activities.borrow_mut();
+        if activities.contains(&(activity, connection)) {
+            // release the borrow, the panic may unwind through other marks
+            drop(activities);
+            panic!("[wayland-backend] {}", misuse);
+        }
+        activities.push((activity, connection));
+    });
+    ActivityMark { activity, connection }
+}
@@ -29,7 +29,8 @@
 //!
 //! This crate can generate some runtime error message (notably when a protocol error occurs). By default
 //! those messages are printed to stderr. If you activate the `log` cargo feature, they will instead be
-//! piped through the `log` crate.
+//! piped through the `log` crate. An application can also capture them with [`set_log_handler()`],
+//! along with the messages libwayland reports when using the system backends.
 //!
 //! ## raw-window-handle integration
 //!
@@ -37,6 +38,37 @@
 //! module [`Backend`](client::Backend) type if you activate the `raw-window-handle` feature.
 //!
 //! Note that the `client_system` feature must also be enabled for the implementation to be activated.
+//!
+//! ## io_uring
+//!
+//! On Linux, the `io_uring` cargo feature makes the server side of the rust backend use io_uring to
+//! batch the socket I/O of its clients: the outgoing messages of all clients are written by a single
+//! submission when flushing, and the incoming messages of all clients ready to be read are read by
+//! a single submission when dispatching. If io_uring is not available at runtime, the backend
+//! silently falls back to regular syscalls.
+//!
+//! ## Metrics
+//!
+//! The `metrics` cargo feature makes both backends count the messages, bytes and file descriptors they
+//! exchange, the time spent dispatching messages and the allocations of the socket buffers. These
+//! counters are retrieved with the `Backend::metrics()` method of the client and server, to benchmark
+//! changes of the backends or monitor applications in production. Without the feature, nothing is
+//! counted.
+//!
+//! ## Debug checks
+//!
+//! The `debug-checks` cargo feature makes the client panic on some misuses that would otherwise deadlock
+//! or silently do nothing, to help finding them during development:
+//!
+//! - preparing a read while the same thread already holds a `ReadEventsGuard` of the connection,
+//! - dispatching the events of a connection from one of its event callbacks, with the system backend,
+//! - sending a request on an object that is no longer alive while the connection still is.
+//!
+//! ## Runtime protocols
+//!
+//! The `protocol_xml` cargo feature provides the [`protocol_xml`] module, loading the interfaces of a
+//! protocol from its XML description at runtime, for the tools speaking protocols not known at compile
+//! time.
 
 #![forbid(improper_ctypes)]
 #![deny(unsafe_op_in_unsafe_fn)]
@@ -62,15 +94,22 @@ macro_rules! message {
     }
 }
 
-// internal imports for dispatching logging depending on the `log` feature
+// internal logging macros, going through the log handler
+macro_rules! log_error {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::LogLevel::Error, format_args!($($arg)*)) };
+}
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::LogLevel::Warn, format_args!($($arg)*)) };
+}
+// the debug messages trace the protocol, they are only emitted with the `log` feature
 #[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { $crate::logging::emit($crate::LogLevel::Debug, format_args!($($arg)*)) };
+}
+#[cfg(feature = "log")]
+pub(crate) use log_debug;
 #[allow(unused_imports)]
-use log::{debug as log_debug, error as log_error, info as log_info, warn as log_warn};
-#[cfg(not(feature = "log"))]
-#[allow(unused_imports)]
-use std::{
-    eprintln as log_error, eprintln as log_warn, eprintln as log_info, eprintln as log_debug,
-};
+pub(crate) use {log_error, log_warn};
 
 #[cfg(any(test, feature = "client_system", feature = "server_system"))]
 pub mod sys;
@@ -90,10 +129,31 @@ pub use sys::server;
 #[cfg(test)]
 mod test;
 
+#[doc(hidden)]
+pub mod __enum_results;
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod __serde;
+
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+pub mod __arbitrary;
+
 mod core_interfaces;
 mod debug;
+#[cfg(feature = "debug-checks")]
+mod debug_checks;
+mod logging;
+mod metrics;
 pub mod protocol;
+#[cfg(feature = "protocol_xml")]
+pub mod protocol_xml;
 mod types;
+mod wakeup;
+pub mod wire;
+
+pub use logging::{set_log_handler, LogLevel};
 
 /*
  * These trampoline functions need to always be here because the build script cannot
@@ -101,18 +161,17 @@ mod types;
  * They'll be optimized out when unused.
  */
 
-#[cfg(feature = "log")]
 #[no_mangle]
 extern "C" fn wl_log_rust_logger_client(msg: *const std::os::raw::c_char) {
     let cstr = unsafe { std::ffi::CStr::from_ptr(msg) };
     let text = cstr.to_string_lossy();
-    log::error!("{}", text);
+    // libwayland terminates its messages with a newline
+    log_error!("{}", text.trim_end());
 }
 
-#[cfg(feature = "log")]
 #[no_mangle]
 extern "C" fn wl_log_rust_logger_server(msg: *const std::os::raw::c_char) {
     let cstr = unsafe { std::ffi::CStr::from_ptr(msg) };
     let text = cstr.to_string_lossy();
-    log::error!("{}", text);
+    log_error!("{}", text.trim_end());
 }
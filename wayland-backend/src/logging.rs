@@ -0,0 +1,62 @@
+//! Reporting of the diagnostics of the backends
+//!
+//! The messages go to the handler set with [`set_log_handler()`] if any, and otherwise to the `log`
+//! crate if the `log` cargo feature is enabled, or to stderr.
+
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+/// The severity of a message of the backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum LogLevel {
+    /// Something went wrong, like a protocol error
+    Error,
+    /// Something unexpected happened, like a client being killed
+    Warn,
+    /// Tracing of the messages, only emitted with the `log` cargo feature
+    Debug,
+}
+
+type LogHandler = Arc<dyn Fn(LogLevel, &str) + Send + Sync>;
+
+static HANDLER: RwLock<Option<LogHandler>> = RwLock::new(None);
+
+/// Set the handler receiving the messages of the backends
+///
+/// The handler receives the messages of both the client and the server backends, including the ones
+/// libwayland reports with `wl_log()` when using the system backends. It replaces the default output,
+/// to the `log` crate or stderr, which is restored by setting `None`.
+///
+/// The handler is global to the process, and may be invoked from any thread.
+pub fn set_log_handler(handler: Option<LogHandler>) {
+    *HANDLER.write().unwrap() = handler;
+}
+
+pub(crate) fn emit(level: LogLevel, args: fmt::Arguments<'_>) {
+    // cloned so that the handler can replace itself
+    let handler = HANDLER.read().unwrap().clone();
+    match handler {
+        Some(handler) => match args.as_str() {
+            Some(text) => handler(level, text),
+            None => handler(level, &args.to_string()),
+        },
+        None => default_log(level, args),
+    }
+}
+
+#[cfg(feature = "log")]
+fn default_log(level: LogLevel, args: fmt::Arguments<'_>) {
+    match level {
+        LogLevel::Error => log::error!("{}", args),
+        LogLevel::Warn => log::warn!("{}", args),
+        LogLevel::Debug => log::debug!("{}", args),
+    }
+}
+
+#[cfg(not(feature = "log"))]
+fn default_log(_: LogLevel, args: fmt::Arguments<'_>) {
+    eprintln!("{}", args);
+}
@@ -0,0 +1,191 @@
+//! Performance counters of the backends, enabled by the `metrics` cargo feature
+//!
+//! When the feature is disabled, the counters are zero-sized and recording into them is a no-op, so
+//! that the backends can record unconditionally.
+
+#[cfg(feature = "metrics")]
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Snapshot of the performance counters of a backend
+///
+/// The counters are cumulative since the creation of the backend. On the server, they cover all the
+/// clients, including the disconnected ones.
+///
+/// On the system backend, only the messages going through the rust side are counted: those handled
+/// internally by libwayland, such as the requests to the `wl_display` and their answers on the server,
+/// are missing.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Number of messages received from the peer(s)
+    pub messages_in: u64,
+    /// Number of messages sent to the peer(s)
+    pub messages_out: u64,
+    /// Number of bytes read from the socket(s)
+    ///
+    /// **Note:** always 0 on the system backend, libwayland does not expose it.
+    pub bytes_in: u64,
+    /// Number of bytes written to the socket(s)
+    ///
+    /// **Note:** always 0 on the system backend, libwayland does not expose it.
+    pub bytes_out: u64,
+    /// Number of file descriptors received from the peer(s)
+    pub fds_in: u64,
+    /// Number of file descriptors sent to the peer(s)
+    pub fds_out: u64,
+    /// Number of file descriptors currently held by the backend, received but not yet dispatched or
+    /// queued but not yet sent
+    ///
+    /// Unlike the other counters, this is not cumulative.
+    ///
+    /// **Note:** always 0 on the system backend, libwayland does not expose it.
+    pub fds_in_flight: u64,
+    /// Total time spent in the callbacks of the object data dispatching the received messages
+    pub dispatch_time: Duration,
+    /// Number of allocations and reallocations of the socket buffers
+    ///
+    /// **Note:** always 0 on the system backend, libwayland does not expose it.
+    pub buffer_allocations: u64,
+    /// Number of buffers for decoding the received messages taken from a pool rather than allocated
+    ///
+    /// **Note:** always 0 on the system backend, libwayland does not expose it.
+    pub pool_reuses: u64,
+    /// Number of buffers for decoding the received messages allocated because the pool was empty
+    ///
+    /// **Note:** always 0 on the system backend, libwayland does not expose it.
+    pub pool_allocations: u64,
+}
+
+/// The counters shared by a backend and its sockets
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    #[cfg(feature = "metrics")]
+    messages_in: AtomicU64,
+    #[cfg(feature = "metrics")]
+    messages_out: AtomicU64,
+    #[cfg(feature = "metrics")]
+    bytes_in: AtomicU64,
+    #[cfg(feature = "metrics")]
+    bytes_out: AtomicU64,
+    #[cfg(feature = "metrics")]
+    fds_in: AtomicU64,
+    #[cfg(feature = "metrics")]
+    fds_out: AtomicU64,
+    #[cfg(feature = "metrics")]
+    fds_in_flight: AtomicU64,
+    // in nanoseconds
+    #[cfg(feature = "metrics")]
+    dispatch_time: AtomicU64,
+    #[cfg(feature = "metrics")]
+    buffer_allocations: AtomicU64,
+    #[cfg(feature = "metrics")]
+    pool_reuses: AtomicU64,
+    #[cfg(feature = "metrics")]
+    pool_allocations: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+fn add(counter: &AtomicU64, value: usize) {
+    counter.fetch_add(value as u64, Ordering::Relaxed);
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+impl MetricsCounters {
+    #[inline]
+    pub(crate) fn message_in(&self, fds: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            add(&self.messages_in, 1);
+            add(&self.fds_in, fds);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn message_out(&self, fds: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            add(&self.messages_out, 1);
+            add(&self.fds_out, fds);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn bytes_in(&self, bytes: usize) {
+        #[cfg(feature = "metrics")]
+        add(&self.bytes_in, bytes);
+    }
+
+    #[inline]
+    pub(crate) fn bytes_out(&self, bytes: usize) {
+        #[cfg(feature = "metrics")]
+        add(&self.bytes_out, bytes);
+    }
+
+    /// File descriptors entering the buffers of a socket
+    #[inline]
+    pub(crate) fn fds_queued(&self, fds: usize) {
+        #[cfg(feature = "metrics")]
+        add(&self.fds_in_flight, fds);
+    }
+
+    /// File descriptors leaving the buffers of a socket, sent, dispatched or closed
+    #[inline]
+    pub(crate) fn fds_released(&self, fds: usize) {
+        #[cfg(feature = "metrics")]
+        self.fds_in_flight.fetch_sub(fds as u64, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn buffer_allocation(&self) {
+        #[cfg(feature = "metrics")]
+        add(&self.buffer_allocations, 1);
+    }
+
+    #[inline]
+    pub(crate) fn pool_reuse(&self) {
+        #[cfg(feature = "metrics")]
+        add(&self.pool_reuses, 1);
+    }
+
+    #[inline]
+    pub(crate) fn pool_allocation(&self) {
+        #[cfg(feature = "metrics")]
+        add(&self.pool_allocations, 1);
+    }
+
+    /// Run the dispatching of a message, measuring its duration
+    #[inline]
+    pub(crate) fn time_dispatch<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "metrics")]
+        {
+            let start = std::time::Instant::now();
+            let ret = f();
+            let elapsed = start.elapsed().as_nanos().min(u64::MAX as u128) as u64;
+            self.dispatch_time.fetch_add(elapsed, Ordering::Relaxed);
+            ret
+        }
+        #[cfg(not(feature = "metrics"))]
+        f()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn snapshot(&self) -> Metrics {
+        let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        Metrics {
+            messages_in: get(&self.messages_in),
+            messages_out: get(&self.messages_out),
+            bytes_in: get(&self.bytes_in),
+            bytes_out: get(&self.bytes_out),
+            fds_in: get(&self.fds_in),
+            fds_out: get(&self.fds_out),
+            fds_in_flight: get(&self.fds_in_flight),
+            dispatch_time: Duration::from_nanos(get(&self.dispatch_time)),
+            buffer_allocations: get(&self.buffer_allocations),
+            pool_reuses: get(&self.pool_reuses),
+            pool_allocations: get(&self.pool_allocations),
+        }
+    }
+}
@@ -1,6 +1,12 @@
 //! Types and utilities for manipulating the Wayland protocol
 
-use std::{ffi::CString, os::unix::prelude::AsRawFd};
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    os::unix::io::BorrowedFd,
+    os::unix::prelude::AsRawFd,
+    sync::RwLock,
+};
 
 pub use wayland_sys::common::{wl_argument, wl_interface, wl_message};
 
@@ -67,9 +73,13 @@ pub enum Argument<Id, Fd> {
     /// The value is boxed to reduce the stack size of Argument. The performance
     /// impact is negligible as `array` arguments are pretty rare in the protocol.
     Array(Box<Vec<u8>>),
-    /// A file descriptor argument. Represented by a [`RawFd`].
+    /// A file descriptor argument
     ///
-    /// [`RawFd`]: std::os::fd::RawFd
+    /// The messages received from the peer own their file descriptors as [`OwnedFd`], which are
+    /// closed when dropped. The messages sent to the peer borrow them as [`BorrowedFd`], and the
+    /// backend duplicates them, so they can be closed as soon as the message is sent.
+    ///
+    /// [`OwnedFd`]: std::os::fd::OwnedFd
     Fd(Fd),
 }
 
@@ -88,6 +98,17 @@ impl<Id, Fd> Argument<Id, Fd> {
         }
     }
 
+    /// Take the file descriptor of this argument, if it is one
+    ///
+    /// The file descriptors of the received messages are closed when the message is dropped, this
+    /// keeps one open past it.
+    pub fn take_fd(self) -> Option<Fd> {
+        match self {
+            Self::Fd(val) => Some(val),
+            _ => None,
+        }
+    }
+
     fn map_fd<T>(self, f: &mut impl FnMut(Fd) -> T) -> Argument<Id, T> {
         match self {
             Self::Int(val) => Argument::Int(val),
@@ -136,6 +157,127 @@ impl<Id: std::fmt::Display, Fd: AsRawFd> std::fmt::Display for Argument<Id, Fd>
     }
 }
 
+/// Borrowed version of [`Argument`]
+///
+/// String and array arguments borrow the buffers they were received in, which avoids allocating them
+/// for every message. Use [`BorrowedArgument::into_owned()`] to keep them around.
+#[derive(Debug, Clone)]
+pub enum BorrowedArgument<'a, Id, Fd> {
+    /// An integer argument. Represented by a [`i32`].
+    Int(i32),
+    /// An unsigned integer argument. Represented by a [`u32`].
+    Uint(u32),
+    /// A signed fixed point number with 1/256 precision
+    Fixed(i32),
+    /// CStr
+    Str(Option<&'a CStr>),
+    /// Id of a wayland object
+    Object(Id),
+    /// Id of a newly created wayland object
+    NewId(Id),
+    /// `[u8]`
+    Array(&'a [u8]),
+    /// A file descriptor argument, see [`Argument::Fd`]
+    Fd(Fd),
+}
+
+impl<'a, Id, Fd> BorrowedArgument<'a, Id, Fd> {
+    /// Retrieve the type of a given argument instance
+    pub fn get_type(&self) -> ArgumentType {
+        match *self {
+            Self::Int(_) => ArgumentType::Int,
+            Self::Uint(_) => ArgumentType::Uint,
+            Self::Fixed(_) => ArgumentType::Fixed,
+            Self::Str(_) => ArgumentType::Str(AllowNull::Yes),
+            Self::Object(_) => ArgumentType::Object(AllowNull::Yes),
+            Self::NewId(_) => ArgumentType::NewId,
+            Self::Array(_) => ArgumentType::Array,
+            Self::Fd(_) => ArgumentType::Fd,
+        }
+    }
+
+    /// Convert this argument into an owned [`Argument`], copying its contents if needed
+    pub fn into_owned(self) -> Argument<Id, Fd> {
+        match self {
+            Self::Int(val) => Argument::Int(val),
+            Self::Uint(val) => Argument::Uint(val),
+            Self::Fixed(val) => Argument::Fixed(val),
+            Self::Str(val) => Argument::Str(val.map(|s| Box::new(s.into()))),
+            Self::Object(val) => Argument::Object(val),
+            Self::NewId(val) => Argument::NewId(val),
+            Self::Array(val) => Argument::Array(Box::new(val.into())),
+            Self::Fd(val) => Argument::Fd(val),
+        }
+    }
+
+    /// Map some closure on the object ids of this argument
+    pub fn map_id<T>(self, f: impl FnOnce(Id) -> T) -> BorrowedArgument<'a, T, Fd> {
+        match self {
+            Self::Int(val) => BorrowedArgument::Int(val),
+            Self::Uint(val) => BorrowedArgument::Uint(val),
+            Self::Fixed(val) => BorrowedArgument::Fixed(val),
+            Self::Str(val) => BorrowedArgument::Str(val),
+            Self::Object(val) => BorrowedArgument::Object(f(val)),
+            Self::NewId(val) => BorrowedArgument::NewId(f(val)),
+            Self::Array(val) => BorrowedArgument::Array(val),
+            Self::Fd(val) => BorrowedArgument::Fd(val),
+        }
+    }
+}
+
+impl<'a, Id: Clone, Fd: AsRawFd> BorrowedArgument<'a, Id, Fd> {
+    /// Borrow an owned [`Argument`]
+    pub fn from_owned(arg: &'a Argument<Id, Fd>) -> BorrowedArgument<'a, Id, BorrowedFd<'a>> {
+        match *arg {
+            Argument::Int(val) => BorrowedArgument::Int(val),
+            Argument::Uint(val) => BorrowedArgument::Uint(val),
+            Argument::Fixed(val) => BorrowedArgument::Fixed(val),
+            Argument::Str(ref val) => BorrowedArgument::Str(val.as_deref().map(CString::as_c_str)),
+            Argument::Object(ref val) => BorrowedArgument::Object(val.clone()),
+            Argument::NewId(ref val) => BorrowedArgument::NewId(val.clone()),
+            Argument::Array(ref val) => BorrowedArgument::Array(val),
+            // Safety: the fd is owned by `arg`, which outlives the returned argument
+            Argument::Fd(ref val) => {
+                BorrowedArgument::Fd(unsafe { BorrowedFd::borrow_raw(val.as_raw_fd()) })
+            }
+        }
+    }
+}
+
+impl<'a, Id: PartialEq, Fd: AsRawFd> PartialEq for BorrowedArgument<'a, Id, Fd> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Uint(a), Self::Uint(b)) => a == b,
+            (Self::Fixed(a), Self::Fixed(b)) => a == b,
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::Object(a), Self::Object(b)) => a == b,
+            (Self::NewId(a), Self::NewId(b)) => a == b,
+            (Self::Array(a), Self::Array(b)) => a == b,
+            (Self::Fd(a), Self::Fd(b)) => a.as_raw_fd() == b.as_raw_fd(),
+            _ => false,
+        }
+    }
+}
+
+impl<'a, Id: Eq, Fd: AsRawFd> Eq for BorrowedArgument<'a, Id, Fd> {}
+
+impl<'a, Id: std::fmt::Display, Fd: AsRawFd> std::fmt::Display for BorrowedArgument<'a, Id, Fd> {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Uint(value) => write!(f, "{}", value),
+            Self::Fixed(value) => write!(f, "{}", value),
+            Self::Str(value) => write!(f, "{:?}", value),
+            Self::Object(value) => write!(f, "{}", value),
+            Self::NewId(value) => write!(f, "{}", value),
+            Self::Array(value) => write!(f, "{:?}", value),
+            Self::Fd(value) => write!(f, "{}", value.as_raw_fd()),
+        }
+    }
+}
+
 /// Description of wayland interface.
 ///
 /// An interface describes the possible requests and events that a wayland client and compositor use to
@@ -151,6 +293,8 @@ pub struct Interface {
     /// A list that describes every event this interface supports.
     pub events: &'static [MessageDesc],
     /// A C representation of this interface that may be used to interoperate with libwayland.
+    ///
+    /// If it is `None`, the system backend generates one from this description when needed.
     pub c_ptr: Option<&'static wayland_sys::common::wl_interface>,
 }
 
@@ -161,6 +305,155 @@ impl std::fmt::Display for Interface {
     }
 }
 
+// The interfaces registered to be found by name
+static REGISTRY: RwLock<Option<HashMap<&'static str, &'static Interface>>> = RwLock::new(None);
+
+impl Interface {
+    /// Register interfaces so that they can be found by name with [`Interface::find()`]
+    ///
+    /// The code generated by `wayland-scanner` lists the interfaces of each protocol in an
+    /// `INTERFACES` static, to be given here. When several interfaces have the same name, the one with
+    /// the highest version is kept.
+    pub fn register(interfaces: &[&'static Interface]) {
+        let mut registry = REGISTRY.write().unwrap();
+        let registry = registry.get_or_insert_with(HashMap::new);
+        for &interface in interfaces {
+            let known = registry.entry(interface.name).or_insert(interface);
+            if known.version < interface.version {
+                *known = interface;
+            }
+        }
+    }
+
+    /// Find a registered interface by name
+    ///
+    /// The `wl_display`, `wl_registry` and `wl_callback` interfaces are always found, the others must
+    /// have been given to [`Interface::register()`].
+    pub fn find(name: &str) -> Option<&'static Interface> {
+        let registry = REGISTRY.read().unwrap();
+        if let Some(&interface) = registry.as_ref().and_then(|registry| registry.get(name)) {
+            return Some(interface);
+        }
+        use crate::core_interfaces::*;
+        [&WL_DISPLAY_INTERFACE, &WL_REGISTRY_INTERFACE, &WL_CALLBACK_INTERFACE]
+            .into_iter()
+            .find(|interface| interface.name == name)
+    }
+
+    /// The description of the request with this opcode, if any
+    ///
+    /// This gives access to the name and signature of a request known only by its opcode, for example
+    /// to print it as `wl_surface.attach` in logs.
+    pub fn request(&self, opcode: u16) -> Option<&'static MessageDesc> {
+        self.requests.get(opcode as usize)
+    }
+
+    /// The description of the event with this opcode, if any
+    ///
+    /// See [`Interface::request()`].
+    pub fn event(&self, opcode: u16) -> Option<&'static MessageDesc> {
+        self.events.get(opcode as usize)
+    }
+}
+
+/// Description of an enum of a protocol
+///
+/// The backends use it to show the arguments referring to an enum by the names of its entries in
+/// their debug output, see [`EnumDesc::register()`].
+#[derive(Copy, Clone, Debug)]
+pub struct EnumDesc {
+    /// Name of the enum, prefixed by the name of its interface as `wl_seat.capability`
+    pub name: &'static str,
+    /// Whether the values of this enum are a combination of its entries
+    pub bitfield: bool,
+    /// The names and values of the entries of this enum
+    pub entries: &'static [(&'static str, u32)],
+}
+
+/// Whether a message is a request or an event
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// A request, sent by the client
+    Request,
+    /// An event, sent by the server
+    Event,
+}
+
+/// An argument of a message referring to an enum, see [`EnumDesc::register()`]
+#[derive(Copy, Clone, Debug)]
+pub struct ArgEnum {
+    /// Name of the interface of the message
+    pub interface: &'static str,
+    /// Whether the message is a request or an event
+    pub direction: Direction,
+    /// Name of the message
+    pub message: &'static str,
+    /// Index of the argument in the signature of the message
+    pub arg: usize,
+    /// The enum of the argument
+    pub desc: &'static EnumDesc,
+}
+
+// The enums of the arguments registered to be shown by name
+#[allow(clippy::type_complexity)]
+static ARG_ENUMS: RwLock<
+    Option<HashMap<(&'static str, Direction, &'static str, usize), &'static EnumDesc>>,
+> = RwLock::new(None);
+
+impl EnumDesc {
+    /// Register the enums of message arguments, to show them by name in the debug output
+    ///
+    /// The code generated by `wayland-scanner` lists them in an `ARG_ENUMS` static next to the
+    /// `INTERFACES` one, to be given here. The arguments are identified by the names of their interface
+    /// and message, and by the direction of the message.
+    pub fn register(args: &[ArgEnum]) {
+        let mut registry = ARG_ENUMS.write().unwrap();
+        let registry = registry.get_or_insert_with(HashMap::new);
+        for arg in args {
+            registry.insert((arg.interface, arg.direction, arg.message, arg.arg), arg.desc);
+        }
+    }
+
+    /// Find the registered enum of an argument
+    pub fn find(
+        interface: &str,
+        direction: Direction,
+        message: &str,
+        arg: usize,
+    ) -> Option<&'static EnumDesc> {
+        ARG_ENUMS.read().unwrap().as_ref()?.get(&(interface, direction, message, arg)).copied()
+    }
+
+    /// Format a value as the names of the entries of this enum
+    ///
+    /// The entries of a bitfield are joined as `keyboard|pointer`, with the unknown bits in
+    /// hexadecimal. A value matching no entry of a regular enum is written as a number.
+    pub fn format(&self, value: u32) -> String {
+        if !self.bitfield {
+            return match self.entries.iter().find(|&&(_, entry)| entry == value) {
+                Some(&(name, _)) => name.to_owned(),
+                None => value.to_string(),
+            };
+        }
+        let mut names = Vec::new();
+        let mut rest = value;
+        for &(name, entry) in self.entries {
+            if entry != 0 && value & entry == entry {
+                names.push(name.to_owned());
+                rest &= !entry;
+            }
+        }
+        if rest != 0 || names.is_empty() {
+            // a zero value is shown by the name of its entry if any
+            match self.entries.iter().find(|&&(_, entry)| entry == 0) {
+                Some(&(name, _)) if value == 0 => names.push(name.to_owned()),
+                _ => names.push(format!("{:#x}", rest)),
+            }
+        }
+        names.join("|")
+    }
+}
+
 /// Wire metadata of a given message
 #[derive(Copy, Clone, Debug)]
 pub struct MessageDesc {
@@ -180,6 +473,37 @@ pub struct MessageDesc {
     pub arg_interfaces: &'static [&'static Interface],
 }
 
+/// Reflection on a message of a protocol
+///
+/// It is implemented by the `Request` and `Event` enums generated by `wayland-scanner`, so that
+/// loggers and interceptors can print a message as `wl_surface.attach` without relying on their
+/// `Debug` output.
+pub trait MessageInfo {
+    /// The interface of the objects sending or receiving this message
+    fn interface(&self) -> &'static Interface;
+
+    /// The description of this message in its interface
+    fn desc(&self) -> &'static MessageDesc;
+
+    /// The opcode of this message
+    fn opcode(&self) -> u16;
+
+    /// The name of this message, as in the protocol description
+    fn name(&self) -> &'static str {
+        self.desc().name
+    }
+
+    /// The minimal version of the objects supporting this message
+    fn since(&self) -> u32 {
+        self.desc().since
+    }
+
+    /// The types of the arguments of this message on the wire
+    fn signature(&self) -> &'static [ArgumentType] {
+        self.desc().signature
+    }
+}
+
 /// Special interface representing an anonymous object
 pub static ANONYMOUS_INTERFACE: Interface =
     Interface { name: "<anonymous>", version: 0, requests: &[], events: &[], c_ptr: None };
@@ -209,8 +533,13 @@ pub struct ProtocolError {
     /// The id of the object that caused the error
     pub object_id: u32,
     /// The interface of the object that caused the error
+    ///
+    /// It is empty if the object is unknown to the client.
     pub object_interface: String,
     /// The message sent by the server describing the error
+    ///
+    /// libwayland does not expose it, so it is empty with the system client backend, which logs it
+    /// instead.
     pub message: String,
 }
 
@@ -222,6 +551,9 @@ pub struct ProtocolError {
 pub const INLINE_ARGS: usize = 4;
 
 /// Represents a message that has been sent from some object.
+///
+/// The received messages own their file descriptors, which are closed when the message is dropped
+/// unless they are taken out of it, for example with [`Argument::take_fd()`].
 #[derive(Clone, Debug)]
 pub struct Message<Id, Fd> {
     /// The id of the object that sent the message.
@@ -243,6 +575,51 @@ impl<Id, Fd> Message<Id, Fd> {
     }
 }
 
+/// Borrowed version of [`Message`]
+///
+/// Its string and array arguments borrow the buffers the message was received in, and are only valid
+/// for the duration of the callback it is given to. Use [`BorrowedMessage::into_owned()`] to keep it
+/// around.
+#[derive(Clone, Debug)]
+pub struct BorrowedMessage<'a, Id, Fd> {
+    /// The id of the object that sent the message.
+    pub sender_id: Id,
+    /// The opcode of the message.
+    pub opcode: u16,
+    /// The arguments of the message.
+    pub args: smallvec::SmallVec<[BorrowedArgument<'a, Id, Fd>; INLINE_ARGS]>,
+}
+
+impl<'a, Id, Fd> BorrowedMessage<'a, Id, Fd> {
+    /// Convert this message into an owned [`Message`], copying its string and array arguments
+    pub fn into_owned(self) -> Message<Id, Fd> {
+        Message {
+            sender_id: self.sender_id,
+            opcode: self.opcode,
+            args: self.args.into_iter().map(BorrowedArgument::into_owned).collect(),
+        }
+    }
+}
+
+impl<'a, Id: Clone, Fd: AsRawFd> BorrowedMessage<'a, Id, Fd> {
+    /// Borrow an owned [`Message`]
+    pub fn from_owned(msg: &'a Message<Id, Fd>) -> BorrowedMessage<'a, Id, BorrowedFd<'a>> {
+        BorrowedMessage {
+            sender_id: msg.sender_id.clone(),
+            opcode: msg.opcode,
+            args: msg.args.iter().map(BorrowedArgument::from_owned).collect(),
+        }
+    }
+}
+
+impl<'a, Id: PartialEq, Fd: AsRawFd> PartialEq for BorrowedMessage<'a, Id, Fd> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sender_id == other.sender_id && self.opcode == other.opcode && self.args == other.args
+    }
+}
+
+impl<'a, Id: Eq, Fd: AsRawFd> Eq for BorrowedMessage<'a, Id, Fd> {}
+
 impl<Id: PartialEq, Fd: AsRawFd> PartialEq for Message<Id, Fd> {
     fn eq(&self, other: &Self) -> bool {
         self.sender_id == other.sender_id && self.opcode == other.opcode && self.args == other.args
@@ -264,6 +641,177 @@ impl std::fmt::Display for ProtocolError {
     }
 }
 
+/// An error generated when a message does not fit in the buffers of a connection
+///
+/// The rust backend grows its buffers as needed, up to a maximum size. A message larger than this
+/// maximum cannot be sent or received at all, in which case this error is returned, wrapped in an
+/// IO error of kind [`InvalidData`](std::io::ErrorKind::InvalidData).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageTooLarge {
+    /// The size of the message, in bytes
+    pub size: usize,
+    /// The maximum size of the buffers, in bytes
+    pub max: usize,
+}
+
+impl std::error::Error for MessageTooLarge {}
+
+impl std::fmt::Display for MessageTooLarge {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(
+            f,
+            "Message of {} bytes does not fit in the maximum buffer size of {} bytes",
+            self.size, self.max
+        )
+    }
+}
+
+impl From<MessageTooLarge> for std::io::Error {
+    #[cfg_attr(coverage, coverage(off))]
+    fn from(err: MessageTooLarge) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// An error generated when too many file descriptors are queued on a connection
+///
+/// The rust backend limits the number of file descriptors waiting to be sent or dispatched on each
+/// connection. Exceeding it results in this error, wrapped in an IO error of kind
+/// [`Other`](std::io::ErrorKind::Other).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TooManyFds {
+    /// The number of file descriptors that would be queued
+    pub count: usize,
+    /// The maximum number of queued file descriptors
+    pub max: usize,
+}
+
+impl std::error::Error for TooManyFds {}
+
+impl std::fmt::Display for TooManyFds {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(
+            f,
+            "{} file descriptors would be queued on the connection, but at most {} are allowed",
+            self.count, self.max
+        )
+    }
+}
+
+impl From<TooManyFds> for std::io::Error {
+    #[cfg_attr(coverage, coverage(off))]
+    fn from(err: TooManyFds) -> Self {
+        std::io::Error::new(std::io::ErrorKind::Other, err)
+    }
+}
+
+/// An error generated when the data received from the peer cannot be decoded into a message
+///
+/// Only the rust backend produces this error, libwayland does its own decoding. It is reported as
+/// [`WaylandError::Wire`](crate::client::WaylandError::Wire) on the client, and as
+/// [`DisconnectReason::InvalidMessage`](crate::server::DisconnectReason::InvalidMessage) on the
+/// server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WireError {
+    /// The message was sent by an unknown object, or with an opcode its interface does not define
+    UnknownOpcode {
+        /// The id of the sender of the message
+        object_id: u32,
+        /// The opcode of the message
+        opcode: u16,
+    },
+    /// The size of the message in its header is too small for the header or for its arguments
+    SizeMismatch {
+        /// The id of the sender of the message
+        object_id: u32,
+        /// The opcode of the message
+        opcode: u16,
+        /// The size of the message in its header, in bytes
+        size: usize,
+    },
+    /// A string argument is not nul-terminated or contains a nul byte
+    InvalidString {
+        /// The id of the sender of the message
+        object_id: u32,
+        /// The opcode of the message
+        opcode: u16,
+        /// The index of the argument in the message
+        arg: usize,
+    },
+    /// The message was received without all the file descriptors its signature expects
+    FdCountMismatch {
+        /// The id of the sender of the message
+        object_id: u32,
+        /// The opcode of the message
+        opcode: u16,
+        /// The number of file descriptors expected by the signature
+        expected: usize,
+        /// The number of file descriptors that were received
+        received: usize,
+    },
+}
+
+impl WireError {
+    /// The id of the object that sent the offending message
+    pub fn object_id(&self) -> u32 {
+        match *self {
+            Self::UnknownOpcode { object_id, .. }
+            | Self::SizeMismatch { object_id, .. }
+            | Self::InvalidString { object_id, .. }
+            | Self::FdCountMismatch { object_id, .. } => object_id,
+        }
+    }
+
+    /// The opcode of the offending message
+    pub fn opcode(&self) -> u16 {
+        match *self {
+            Self::UnknownOpcode { opcode, .. }
+            | Self::SizeMismatch { opcode, .. }
+            | Self::InvalidString { opcode, .. }
+            | Self::FdCountMismatch { opcode, .. } => opcode,
+        }
+    }
+
+    // what is wrong with the message
+    pub(crate) fn reason(&self) -> String {
+        match self {
+            Self::UnknownOpcode { .. } => "unknown object or opcode".into(),
+            Self::SizeMismatch { size, .. } => {
+                format!("its size of {} bytes does not match its contents", size)
+            }
+            Self::InvalidString { arg, .. } => format!("argument {} is not a valid string", arg),
+            Self::FdCountMismatch { expected, received, .. } => {
+                format!("expected {} file descriptors but received {}", expected, received)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl std::fmt::Display for WireError {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+        write!(
+            f,
+            "Invalid message from object {} with opcode {}: {}",
+            self.object_id(),
+            self.opcode(),
+            self.reason()
+        )
+    }
+}
+
+impl From<WireError> for std::io::Error {
+    #[cfg_attr(coverage, coverage(off))]
+    fn from(err: WireError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
 /// Returns true if the two interfaces are the same.
 #[inline]
 pub fn same_interface(a: &'static Interface, b: &'static Interface) -> bool {
@@ -285,6 +833,12 @@ pub(crate) fn check_for_signature<Id, Fd>(
     true
 }
 
+// Whether an interceptor may veto this message, messages creating or destroying objects must always go
+// through to keep both peers in sync
+pub(crate) fn can_veto(desc: &MessageDesc) -> bool {
+    !desc.is_destructor && !desc.signature.iter().any(|arg| matches!(arg, ArgumentType::NewId))
+}
+
 #[inline]
 #[allow(dead_code)]
 pub(crate) fn same_interface_or_anonymous(a: &'static Interface, b: &'static Interface) -> bool {
@@ -356,3 +910,98 @@ impl<T: Into<u32>> From<WEnum<T>> for u32 {
         }
     }
 }
+
+/// A numeric value not matching any variant of an enum of the protocol file
+///
+/// This is the error of the `Result<T, UnknownVariant>` representing the enum arguments in the code
+/// generated with the `enum_results` option of `wayland-scanner`, as an alternative to [`WEnum`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownVariant(pub u32);
+
+impl std::error::Error for UnknownVariant {}
+
+impl std::fmt::Display for UnknownVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown numeric value {} for enum", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::io::{AsFd, OwnedFd};
+
+    use super::*;
+
+    #[test]
+    fn take_fd() {
+        let stdin = std::io::stdin().as_fd().try_clone_to_owned().unwrap();
+        let msg: Message<u32, OwnedFd> = Message {
+            sender_id: 1,
+            opcode: 0,
+            args: smallvec::smallvec![Argument::Uint(42), Argument::Fd(stdin)],
+        };
+
+        let mut fds = msg.args.into_iter().map(Argument::take_fd);
+        assert!(fds.next().unwrap().is_none());
+        let fd = fds.next().unwrap().unwrap();
+        let stat1 = rustix::fs::fstat(&fd).unwrap();
+        let stat2 = rustix::fs::fstat(std::io::stdin()).unwrap();
+        assert_eq!((stat1.st_dev, stat1.st_ino), (stat2.st_dev, stat2.st_ino));
+    }
+
+    #[test]
+    fn find_interface() {
+        static OLD: Interface = Interface {
+            name: "test_registry",
+            version: 1,
+            requests: &[],
+            events: &[],
+            c_ptr: None,
+        };
+        static NEW: Interface = Interface { version: 3, ..OLD };
+
+        assert!(Interface::find("test_registry").is_none());
+        Interface::register(&[&NEW, &OLD]);
+        let found = Interface::find("test_registry").unwrap();
+        assert!(std::ptr::eq(found, &NEW));
+        assert!(same_interface(found, &OLD));
+
+        // the core interfaces are always known
+        let display = Interface::find("wl_display").unwrap();
+        assert!(std::ptr::eq(display, &crate::core_interfaces::WL_DISPLAY_INTERFACE));
+        assert!(!same_interface(display, found));
+    }
+
+    #[test]
+    fn enum_desc() {
+        static CAPABILITY: EnumDesc = EnumDesc {
+            name: "test_seat.capability",
+            bitfield: true,
+            entries: &[("pointer", 1), ("keyboard", 2), ("touch", 4)],
+        };
+        static TRANSFORM: EnumDesc = EnumDesc {
+            name: "test_output.transform",
+            bitfield: false,
+            entries: &[("normal", 0), ("90", 1)],
+        };
+
+        assert_eq!(CAPABILITY.format(3), "pointer|keyboard");
+        assert_eq!(CAPABILITY.format(9), "pointer|0x8");
+        assert_eq!(CAPABILITY.format(0), "0x0");
+        assert_eq!(TRANSFORM.format(1), "90");
+        assert_eq!(TRANSFORM.format(5), "5");
+
+        assert!(EnumDesc::find("test_input", Direction::Event, "capabilities", 0).is_none());
+        EnumDesc::register(&[ArgEnum {
+            interface: "test_input",
+            direction: Direction::Event,
+            message: "capabilities",
+            arg: 0,
+            desc: &CAPABILITY,
+        }]);
+        let found = EnumDesc::find("test_input", Direction::Event, "capabilities", 0).unwrap();
+        assert!(std::ptr::eq(found, &CAPABILITY));
+        assert!(EnumDesc::find("test_input", Direction::Event, "capabilities", 1).is_none());
+        assert!(EnumDesc::find("test_input", Direction::Request, "capabilities", 0).is_none());
+    }
+}
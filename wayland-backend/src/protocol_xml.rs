@@ -0,0 +1,895 @@
+//! Loading of protocol descriptions at runtime
+//!
+//! This module parses the XML description of a protocol into [`Interface`](protocol::Interface)s, which can then be used
+//! like the ones generated by `wayland-scanner`, with the generic APIs of the backends. It is meant for
+//! the tools speaking protocols that are not known at compile time, such as protocol browsers or
+//! generic test clients.
+//!
+//! ```
+//! use wayland_backend::protocol::Interface;
+//!
+//! let xml = r#"<protocol name="example">
+//!   <interface name="example_global" version="2">
+//!     <request name="create_child">
+//!       <arg name="id" type="new_id" interface="example_child"/>
+//!     </request>
+//!   </interface>
+//!   <interface name="example_child" version="2">
+//!     <request name="destroy" type="destructor"/>
+//!     <event name="parent">
+//!       <arg name="parent" type="object" interface="example_global" allow-null="true"/>
+//!     </event>
+//!   </interface>
+//! </protocol>"#;
+//!
+//! let interfaces = wayland_backend::protocol_xml::load(xml.as_bytes()).unwrap();
+//! assert_eq!(interfaces[0].name, "example_global");
+//! let child = interfaces[0].requests[0].child_interface.unwrap();
+//! assert!(std::ptr::eq(child, interfaces[1]));
+//! assert!(child.requests[0].is_destructor);
+//!
+//! // they can also be looked up by name
+//! Interface::register(&interfaces);
+//! assert!(Interface::find("example_child").is_some());
+//! ```
+//!
+//! The whole description of the protocol, with its enums and documentation, is given by [`parse()`].
+//! It is the parser used by `wayland-scanner` for the code it generates.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    mem::MaybeUninit,
+    ptr::addr_of_mut,
+    str::FromStr,
+};
+
+use quick_xml::{
+    events::{
+        attributes::{AttrError, Attribute, Attributes},
+        BytesStart, Event,
+    },
+    Reader,
+};
+
+use crate::protocol::{self, AllowNull, ArgumentType, MessageDesc, ANONYMOUS_INTERFACE};
+
+/// An error preventing a protocol description from being loaded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadError {
+    /// The file is not a valid protocol description
+    Parse(ParseError),
+    /// An argument refers to an interface that is neither described by the file nor registered with
+    /// [`Interface::register()`](protocol::Interface::register)
+    UnknownInterface(String),
+}
+
+impl std::error::Error for LoadError {}
+
+impl std::fmt::Display for LoadError {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "Invalid protocol description: {}", err),
+            Self::UnknownInterface(name) => write!(f, "Unknown interface {}", name),
+        }
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// Load the interfaces of a protocol from its XML description
+///
+/// The interfaces are returned in the order of the file. The interfaces of other protocols the
+/// arguments refer to, like `wl_surface`, must have been given to
+/// [`Interface::register()`](protocol::Interface::register) before,
+/// for example with the `INTERFACES` list of `wayland-client` or `wayland-server`.
+///
+/// The interfaces and their messages are leaked to be `'static`, a protocol should thus be loaded only
+/// once. They have no `c_ptr`, the system backend generates their description for libwayland when
+/// needed.
+pub fn load<R: Read>(xml: R) -> Result<Vec<&'static protocol::Interface>, LoadError> {
+    let protocol = parse(xml)?;
+
+    // The interfaces of the file may refer to each other, their addresses are thus needed before
+    // they are initialized. No reference to them or to their messages is created before all of
+    // them are: the messages and the interfaces are written through raw pointers, the references
+    // they hold being written as the pointers they have the layout of.
+    let indices = protocol
+        .interfaces
+        .iter()
+        .enumerate()
+        .map(|(i, interface)| (interface.name.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let slots = protocol
+        .interfaces
+        .iter()
+        .map(|_| Box::leak(Box::new(MaybeUninit::<protocol::Interface>::uninit())).as_mut_ptr())
+        .collect::<Vec<_>>();
+    let resolve = |name: &str| -> Result<*const protocol::Interface, LoadError> {
+        match indices.get(name) {
+            Some(&i) => Ok(slots[i]),
+            None => protocol::Interface::find(name)
+                .map(|interface| interface as *const _)
+                .ok_or_else(|| LoadError::UnknownInterface(name.into())),
+        }
+    };
+
+    // the interfaces are only initialized once all their messages were successfully built, the slots
+    // of a failed load stay leaked but are never read
+    let mut messages = Vec::with_capacity(protocol.interfaces.len());
+    for interface in &protocol.interfaces {
+        messages.push((
+            build_messages(&interface.requests, &resolve)?,
+            build_messages(&interface.events, &resolve)?,
+        ));
+    }
+    for ((&slot, interface), (requests, events)) in
+        slots.iter().zip(&protocol.interfaces).zip(messages)
+    {
+        // Safety: the slot is leaked and valid for writes, and no reference to it exists yet. The
+        // message slices were fully initialized by `build_messages()`.
+        unsafe {
+            addr_of_mut!((*slot).name).write(leak_str(&interface.name));
+            addr_of_mut!((*slot).version).write(interface.version);
+            addr_of_mut!((*slot).requests).cast::<*const [MessageDesc]>().write(requests);
+            addr_of_mut!((*slot).events).cast::<*const [MessageDesc]>().write(events);
+            addr_of_mut!((*slot).c_ptr).write(None);
+        }
+    }
+    // Safety: all the interfaces and the messages referring to them are now initialized, and are
+    // never written again
+    Ok(slots.into_iter().map(|slot| unsafe { &*slot }).collect())
+}
+
+// build the descriptions of the messages of an interface, the interfaces their arguments refer to
+// may not be initialized yet
+fn build_messages(
+    messages: &[Message],
+    resolve: &impl Fn(&str) -> Result<*const protocol::Interface, LoadError>,
+) -> Result<*const [MessageDesc], LoadError> {
+    let descs = Box::leak(
+        messages.iter().map(|_| MaybeUninit::<MessageDesc>::uninit()).collect::<Box<[_]>>(),
+    );
+    for (desc, message) in descs.iter_mut().zip(messages) {
+        let mut signature = Vec::with_capacity(message.args.len());
+        let mut child_interface = std::ptr::null();
+        let mut arg_interfaces = Vec::new();
+        for arg in &message.args {
+            let allow_null = if arg.allow_null { AllowNull::Yes } else { AllowNull::No };
+            match arg.typ {
+                ArgType::Int => signature.push(ArgumentType::Int),
+                ArgType::Uint => signature.push(ArgumentType::Uint),
+                ArgType::Fixed => signature.push(ArgumentType::Fixed),
+                ArgType::String => signature.push(ArgumentType::Str(allow_null)),
+                ArgType::Array => signature.push(ArgumentType::Array),
+                ArgType::Fd => signature.push(ArgumentType::Fd),
+                ArgType::Object => {
+                    signature.push(ArgumentType::Object(allow_null));
+                    arg_interfaces.push(match arg.interface {
+                        Some(ref name) => resolve(name)?,
+                        None => &ANONYMOUS_INTERFACE as *const _,
+                    });
+                }
+                ArgType::NewId => match arg.interface {
+                    Some(ref name) => {
+                        signature.push(ArgumentType::NewId);
+                        child_interface = resolve(name)?;
+                    }
+                    // a generic constructor, the interface and version are given before the id
+                    None => signature.extend_from_slice(&[
+                        ArgumentType::Str(AllowNull::No),
+                        ArgumentType::Uint,
+                        ArgumentType::NewId,
+                    ]),
+                },
+            }
+        }
+        let arg_interfaces = Box::leak(arg_interfaces.into_boxed_slice()) as *const [_];
+        let desc = desc.as_mut_ptr();
+        // Safety: the message is leaked and valid for writes. A reference has the layout of a
+        // non-null pointer, an `Option` of a reference the one of a nullable pointer, so the pointers
+        // to the interfaces are written as the references the fields hold.
+        unsafe {
+            addr_of_mut!((*desc).name).write(leak_str(&message.name));
+            addr_of_mut!((*desc).signature).write(Box::leak(signature.into_boxed_slice()));
+            addr_of_mut!((*desc).since).write(message.since);
+            addr_of_mut!((*desc).is_destructor).write(message.is_destructor);
+            addr_of_mut!((*desc).child_interface)
+                .cast::<*const protocol::Interface>()
+                .write(child_interface);
+            addr_of_mut!((*desc).arg_interfaces)
+                .cast::<*const [*const protocol::Interface]>()
+                .write(arg_interfaces);
+        }
+    }
+    Ok(descs as *const [MaybeUninit<MessageDesc>] as *const [MessageDesc])
+}
+
+fn leak_str(s: &str) -> &'static str {
+    Box::leak(s.to_owned().into_boxed_str())
+}
+
+/*
+ * Description of a protocol
+ */
+
+/// The description of a protocol, as given by its XML file
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Protocol {
+    /// The name of the protocol
+    pub name: String,
+    /// The copyright notice of the protocol
+    pub copyright: Option<String>,
+    /// The summary and the text of the description of the protocol
+    pub description: Option<(String, String)>,
+    /// The interfaces of the protocol, in the order of the file
+    pub interfaces: Vec<Interface>,
+}
+
+/// The description of an interface
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Interface {
+    /// The name of the interface
+    pub name: String,
+    /// The version of the interface
+    pub version: u32,
+    /// The summary and the text of the description of the interface
+    pub description: Option<(String, String)>,
+    /// The requests of the interface, by opcode
+    pub requests: Vec<Message>,
+    /// The events of the interface, by opcode
+    pub events: Vec<Message>,
+    /// The enums of the interface
+    pub enums: Vec<Enum>,
+}
+
+/// The description of a request or an event
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Message {
+    /// The name of the message
+    pub name: String,
+    /// Whether the message destroys its object
+    pub is_destructor: bool,
+    /// The version of the interface the message was introduced in
+    pub since: u32,
+    /// The version of the interface the message was deprecated in
+    pub deprecated_since: Option<u32>,
+    /// The summary and the text of the description of the message
+    pub description: Option<(String, String)>,
+    /// The arguments of the message
+    pub args: Vec<Arg>,
+}
+
+/// The description of an argument of a message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Arg {
+    /// The name of the argument
+    pub name: String,
+    /// The type of the argument
+    pub typ: ArgType,
+    /// The interface of an object or a new object
+    pub interface: Option<String>,
+    /// The summary of the argument
+    pub summary: Option<String>,
+    /// The summary and the text of the description of the argument
+    pub description: Option<(String, String)>,
+    /// Whether an object or a string can be null
+    pub allow_null: bool,
+    /// The enum the value of the argument is an entry of, as `enum` or `interface.enum`
+    pub enum_: Option<String>,
+}
+
+/// The type of an argument
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArgType {
+    /// A signed integer
+    Int,
+    /// An unsigned integer
+    Uint,
+    /// A fixed-point number
+    Fixed,
+    /// A string
+    String,
+    /// An existing object
+    Object,
+    /// A new object
+    NewId,
+    /// An array of bytes
+    Array,
+    /// A file descriptor
+    Fd,
+}
+
+/// The description of an enum
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Enum {
+    /// The name of the enum
+    pub name: String,
+    /// The version of the interface the enum was introduced in
+    pub since: u16,
+    /// Whether the entries of the enum are flags which can be combined
+    pub bitfield: bool,
+    /// The summary and the text of the description of the enum
+    pub description: Option<(String, String)>,
+    /// The entries of the enum
+    pub entries: Vec<Entry>,
+}
+
+/// The description of an entry of an enum
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Entry {
+    /// The name of the entry
+    pub name: String,
+    /// The value of the entry
+    pub value: u32,
+    /// The version of the interface the entry was introduced in
+    pub since: u16,
+    /// The version of the interface the entry was deprecated in
+    pub deprecated_since: Option<u16>,
+    /// The summary of the entry
+    pub summary: Option<String>,
+    /// The summary and the text of the description of the entry
+    pub description: Option<(String, String)>,
+}
+
+/*
+ * Parsing of the XML file
+ */
+
+/// An error in a protocol file
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseError {
+    /// The position of the error in the file, in bytes
+    pub position: usize,
+    /// The elements containing the error, from the outermost one
+    pub path: Vec<String>,
+    /// The description of the error
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(position: usize, message: impl Into<String>) -> ParseError {
+        ParseError { position, path: Vec::new(), message: message.into() }
+    }
+
+    // add an element containing the error to its path
+    fn within(mut self, element: &str, name: &str) -> ParseError {
+        self.path.insert(0, format!("{} `{}`", element, name));
+        self
+    }
+
+    /// Describe the error with its line and column in the given file
+    pub fn describe(&self, file: &str, contents: &[u8]) -> String {
+        // the position of an element is the one of the end of the previous element, the whitespace
+        // between them is skipped
+        let position = self.position.min(contents.len());
+        let skipped = contents[position..].iter().take_while(|c| c.is_ascii_whitespace()).count();
+        let before = &contents[..position + skipped];
+        let line = before.iter().filter(|&&c| c == b'\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != b'\n').count() + 1;
+        format!("{}:{}:{}: {}", file, line, column, self)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::fmt::Display for ParseError {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)?;
+        if !self.path.is_empty() {
+            write!(f, ", in {}", self.path.join(" > "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the XML description of a protocol
+pub fn parse<R: Read>(xml: R) -> Result<Protocol, ParseError> {
+    let mut reader = Reader::from_reader(BufReader::new(xml));
+    reader.trim_text(true).expand_empty_elements(true);
+    parse_protocol(&mut reader)
+}
+
+fn decode_utf8(position: usize, txt: Vec<u8>) -> Result<String, ParseError> {
+    String::from_utf8(txt).map_err(|e| {
+        ParseError::new(
+            position,
+            format!("Invalid UTF8: '{}'", String::from_utf8_lossy(&e.into_bytes())),
+        )
+    })
+}
+
+fn parse_value<T: FromStr>(position: usize, txt: &[u8]) -> Result<T, ParseError> {
+    std::str::from_utf8(txt).ok().and_then(|val| val.parse().ok()).ok_or_else(|| {
+        ParseError::new(
+            position,
+            format!(
+                "Invalid value '{}' for parsing type '{}'",
+                String::from_utf8_lossy(txt),
+                std::any::type_name::<T>()
+            ),
+        )
+    })
+}
+
+fn attribute<'a>(
+    position: usize,
+    attr: Result<Attribute<'a>, AttrError>,
+) -> Result<Attribute<'a>, ParseError> {
+    attr.map_err(|e| ParseError::new(position, format!("Invalid attribute: {}", e)))
+}
+
+fn unexpected_element(position: usize, name: &[u8]) -> ParseError {
+    ParseError::new(position, format!("Unexpected element `{}`", String::from_utf8_lossy(name)))
+}
+
+fn unexpected_type(position: usize, txt: &[u8]) -> ParseError {
+    ParseError::new(position, format!("Unexpected type: {}", String::from_utf8_lossy(txt)))
+}
+
+// the names of the items of a protocol must be unique among their siblings
+fn check_unique<'a>(
+    position: usize,
+    kind: &str,
+    name: &str,
+    mut siblings: impl Iterator<Item = &'a str>,
+) -> Result<(), ParseError> {
+    match siblings.any(|sibling| sibling == name) {
+        true => Err(ParseError::new(position, format!("Duplicate {} `{}`", kind, name))),
+        false => Ok(()),
+    }
+}
+
+// read the next event, the errors of the XML parser are located where it stopped
+fn read_event<R: BufRead>(reader: &mut Reader<R>) -> Result<Event<'static>, ParseError> {
+    reader.read_event_into(&mut Vec::new()).map(Event::into_owned).map_err(|e| {
+        ParseError::new(reader.buffer_position(), format!("Ill-formed protocol file: {}", e))
+    })
+}
+
+fn expect_end<R: BufRead>(reader: &mut Reader<R>, tag: &[u8]) -> Result<(), ParseError> {
+    let position = reader.buffer_position();
+    match read_event(reader)? {
+        Event::End(bytes) if bytes.name().into_inner() == tag => Ok(()),
+        _ => Err(ParseError::new(
+            position,
+            format!(
+                "Ill-formed protocol file: expected the end of `{}`",
+                String::from_utf8_lossy(tag)
+            ),
+        )),
+    }
+}
+
+// parse the child elements of an element until its end, given their position, the text and the
+// comments are ignored
+fn parse_children<R: BufRead>(
+    reader: &mut Reader<R>,
+    tag: &[u8],
+    mut parse_child: impl FnMut(&mut Reader<R>, usize, BytesStart<'static>) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    loop {
+        let position = reader.buffer_position();
+        match read_event(reader)? {
+            Event::Start(bytes) => parse_child(reader, position, bytes)?,
+            Event::End(bytes) if bytes.name().into_inner() == tag => return Ok(()),
+            Event::End(bytes) => {
+                return Err(ParseError::new(
+                    position,
+                    format!(
+                        "Unexpected closing token `{}`",
+                        String::from_utf8_lossy(bytes.name().into_inner())
+                    ),
+                ))
+            }
+            Event::Eof => {
+                return Err(ParseError::new(
+                    position,
+                    format!(
+                        "Ill-formed protocol file: unexpected end of file in `{}`",
+                        String::from_utf8_lossy(tag)
+                    ),
+                ))
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_protocol<R: BufRead>(reader: &mut Reader<R>) -> Result<Protocol, ParseError> {
+    // skip the <?xml ... ?> declaration
+    let (position, bytes) = loop {
+        let position = reader.buffer_position();
+        match read_event(reader)? {
+            Event::Start(bytes) if bytes.name().into_inner() == b"protocol" => {
+                break (position, bytes)
+            }
+            Event::Decl(_) | Event::Comment(_) => {}
+            _ => return Err(ParseError::new(position, "Missing protocol toplevel tag")),
+        }
+    };
+
+    let mut protocol = Protocol {
+        name: String::new(),
+        copyright: None,
+        description: None,
+        interfaces: Vec::new(),
+    };
+    for attr in bytes.attributes() {
+        let attr = attribute(position, attr)?;
+        if attr.key.into_inner() == b"name" {
+            protocol.name = decode_utf8(position, attr.value.into_owned())?;
+        }
+    }
+    if protocol.name.is_empty() {
+        return Err(ParseError::new(position, "Protocol must have a name"));
+    }
+
+    parse_children(reader, b"protocol", |reader, position, bytes| {
+        match bytes.name().into_inner() {
+            b"copyright" => {
+                // parse the copyright
+                let copyright = match read_event(reader)? {
+                    Event::Text(copyright) => copyright.unescape().ok().map(|x| x.to_string()),
+                    Event::CData(copyright) => {
+                        String::from_utf8(copyright.into_inner().into()).ok()
+                    }
+                    _ => {
+                        return Err(ParseError::new(
+                            position,
+                            "Ill-formed protocol file: expected the text of the copyright",
+                        ))
+                    }
+                };
+
+                expect_end(reader, b"copyright")?;
+                protocol.copyright = copyright
+            }
+            b"interface" => {
+                let interface = parse_interface(reader, position, bytes.attributes())?;
+                let siblings = protocol.interfaces.iter().map(|other| &other.name[..]);
+                check_unique(position, "interface", &interface.name, siblings)?;
+                protocol.interfaces.push(interface);
+            }
+            b"description" => {
+                protocol.description =
+                    Some(parse_description(reader, position, bytes.attributes())?);
+            }
+            name => return Err(unexpected_element(position, name)),
+        }
+        Ok(())
+    })
+    .map_err(|e| e.within("protocol", &protocol.name))?;
+
+    Ok(protocol)
+}
+
+fn parse_interface<R: BufRead>(
+    reader: &mut Reader<R>,
+    position: usize,
+    attrs: Attributes,
+) -> Result<Interface, ParseError> {
+    let mut interface = Interface {
+        name: String::new(),
+        version: 1,
+        description: None,
+        requests: Vec::new(),
+        events: Vec::new(),
+        enums: Vec::new(),
+    };
+    for attr in attrs {
+        let attr = attribute(position, attr)?;
+        match attr.key.into_inner() {
+            b"name" => interface.name = decode_utf8(position, attr.value.into_owned())?,
+            b"version" => interface.version = parse_value(position, &attr.value)?,
+            _ => {}
+        }
+    }
+
+    parse_children(reader, b"interface", |reader, position, bytes| {
+        match bytes.name().into_inner() {
+            b"description" => {
+                interface.description =
+                    Some(parse_description(reader, position, bytes.attributes())?)
+            }
+            b"request" => {
+                let request = parse_message(reader, position, bytes.attributes(), b"request")?;
+                let siblings = interface.requests.iter().map(|other| &other.name[..]);
+                check_unique(position, "request", &request.name, siblings)?;
+                interface.requests.push(request)
+            }
+            b"event" => {
+                let event = parse_message(reader, position, bytes.attributes(), b"event")?;
+                let siblings = interface.events.iter().map(|other| &other.name[..]);
+                check_unique(position, "event", &event.name, siblings)?;
+                interface.events.push(event)
+            }
+            b"enum" => {
+                let enu = parse_enum(reader, position, bytes.attributes())?;
+                let siblings = interface.enums.iter().map(|other| &other.name[..]);
+                check_unique(position, "enum", &enu.name, siblings)?;
+                interface.enums.push(enu)
+            }
+            name => return Err(unexpected_element(position, name)),
+        }
+        Ok(())
+    })
+    .map_err(|e| e.within("interface", &interface.name))?;
+
+    Ok(interface)
+}
+
+fn parse_description<R: BufRead>(
+    reader: &mut Reader<R>,
+    position: usize,
+    attrs: Attributes,
+) -> Result<(String, String), ParseError> {
+    let mut summary = String::new();
+    for attr in attrs {
+        let attr = attribute(position, attr)?;
+        if attr.key.into_inner() == b"summary" {
+            summary = String::from_utf8_lossy(&attr.value)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+    }
+
+    let mut description = String::new();
+    // Some protocols have comments inside their descriptions, so we need to parse them in a loop and
+    // concatenate the parts into a single block of text
+    loop {
+        let position = reader.buffer_position();
+        match read_event(reader)? {
+            Event::Text(bytes) => {
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&bytes.unescape().unwrap_or_default())
+            }
+            Event::End(bytes) if bytes.name().into_inner() == b"description" => break,
+            Event::Comment(_) => {}
+            _ => {
+                return Err(ParseError::new(
+                    position,
+                    "Ill-formed protocol file: expected the text of the description",
+                ))
+            }
+        }
+    }
+
+    Ok((summary, description))
+}
+
+// parse a request or an event, given its tag
+fn parse_message<R: BufRead>(
+    reader: &mut Reader<R>,
+    position: usize,
+    attrs: Attributes,
+    tag: &[u8],
+) -> Result<Message, ParseError> {
+    let mut message = Message {
+        name: String::new(),
+        is_destructor: false,
+        since: 1,
+        deprecated_since: None,
+        description: None,
+        args: Vec::new(),
+    };
+    for attr in attrs {
+        let attr = attribute(position, attr)?;
+        match attr.key.into_inner() {
+            b"name" => message.name = decode_utf8(position, attr.value.into_owned())?,
+            b"type" => match &*attr.value {
+                b"destructor" => message.is_destructor = true,
+                typ => return Err(unexpected_type(position, typ)),
+            },
+            b"since" => message.since = parse_value(position, &attr.value)?,
+            b"deprecated-since" => {
+                message.deprecated_since = Some(parse_value(position, &attr.value)?)
+            }
+            _ => {}
+        }
+    }
+
+    parse_children(reader, tag, |reader, position, bytes| {
+        match bytes.name().into_inner() {
+            b"description" => {
+                message.description = Some(parse_description(reader, position, bytes.attributes())?)
+            }
+            b"arg" => {
+                let arg = parse_arg(reader, position, bytes.attributes())?;
+                let siblings = message.args.iter().map(|other| &other.name[..]);
+                check_unique(position, "argument", &arg.name, siblings)?;
+                message.args.push(arg)
+            }
+            name => return Err(unexpected_element(position, name)),
+        }
+        Ok(())
+    })
+    .map_err(|e| e.within(&String::from_utf8_lossy(tag), &message.name))?;
+
+    Ok(message)
+}
+
+fn parse_enum<R: BufRead>(
+    reader: &mut Reader<R>,
+    position: usize,
+    attrs: Attributes,
+) -> Result<Enum, ParseError> {
+    let mut enu = Enum {
+        name: String::new(),
+        since: 1,
+        bitfield: false,
+        description: None,
+        entries: Vec::new(),
+    };
+    for attr in attrs {
+        let attr = attribute(position, attr)?;
+        match attr.key.into_inner() {
+            b"name" => enu.name = decode_utf8(position, attr.value.into_owned())?,
+            b"since" => enu.since = parse_value(position, &attr.value)?,
+            b"bitfield" if &attr.value[..] == b"true" => enu.bitfield = true,
+            _ => {}
+        }
+    }
+
+    parse_children(reader, b"enum", |reader, position, bytes| {
+        match bytes.name().into_inner() {
+            b"description" => {
+                enu.description = Some(parse_description(reader, position, bytes.attributes())?)
+            }
+            b"entry" => {
+                let entry = parse_entry(reader, position, bytes.attributes())?;
+                let siblings = enu.entries.iter().map(|other| &other.name[..]);
+                check_unique(position, "entry", &entry.name, siblings)?;
+                enu.entries.push(entry)
+            }
+            name => return Err(unexpected_element(position, name)),
+        }
+        Ok(())
+    })
+    .map_err(|e| e.within("enum", &enu.name))?;
+
+    Ok(enu)
+}
+
+fn parse_arg<R: BufRead>(
+    reader: &mut Reader<R>,
+    position: usize,
+    attrs: Attributes,
+) -> Result<Arg, ParseError> {
+    let mut arg = Arg {
+        name: String::new(),
+        typ: ArgType::Object,
+        interface: None,
+        summary: None,
+        description: None,
+        allow_null: false,
+        enum_: None,
+    };
+    for attr in attrs {
+        let attr = attribute(position, attr)?;
+        match attr.key.into_inner() {
+            b"name" => arg.name = decode_utf8(position, attr.value.into_owned())?,
+            b"type" => arg.typ = parse_type(position, &attr.value)?,
+            b"summary" => {
+                arg.summary = Some(
+                    String::from_utf8_lossy(&attr.value)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
+            }
+            b"interface" => arg.interface = Some(decode_utf8(position, attr.value.into_owned())?),
+            b"allow-null" if &*attr.value == b"true" => arg.allow_null = true,
+            b"enum" => arg.enum_ = Some(decode_utf8(position, attr.value.into_owned())?),
+            _ => {}
+        }
+    }
+
+    parse_children(reader, b"arg", |reader, position, bytes| {
+        match bytes.name().into_inner() {
+            b"description" => {
+                arg.description = Some(parse_description(reader, position, bytes.attributes())?)
+            }
+            name => return Err(unexpected_element(position, name)),
+        }
+        Ok(())
+    })
+    .map_err(|e| e.within("arg", &arg.name))?;
+
+    Ok(arg)
+}
+
+fn parse_type(position: usize, txt: &[u8]) -> Result<ArgType, ParseError> {
+    Ok(match txt {
+        b"int" => ArgType::Int,
+        b"uint" => ArgType::Uint,
+        b"fixed" => ArgType::Fixed,
+        b"string" => ArgType::String,
+        b"object" => ArgType::Object,
+        b"new_id" => ArgType::NewId,
+        b"array" => ArgType::Array,
+        b"fd" => ArgType::Fd,
+        e => return Err(unexpected_type(position, e)),
+    })
+}
+
+fn parse_entry<R: BufRead>(
+    reader: &mut Reader<R>,
+    position: usize,
+    attrs: Attributes,
+) -> Result<Entry, ParseError> {
+    let mut entry = Entry {
+        name: String::new(),
+        value: 0,
+        since: 1,
+        deprecated_since: None,
+        summary: None,
+        description: None,
+    };
+    for attr in attrs {
+        let attr = attribute(position, attr)?;
+        match attr.key.into_inner() {
+            b"name" => entry.name = decode_utf8(position, attr.value.into_owned())?,
+            b"value" => {
+                entry.value = if attr.value.starts_with(b"0x") {
+                    if let Some(val) = std::str::from_utf8(&attr.value[2..])
+                        .ok()
+                        .and_then(|s| u32::from_str_radix(s, 16).ok())
+                    {
+                        val
+                    } else {
+                        return Err(ParseError::new(
+                            position,
+                            format!("Invalid number: {}", String::from_utf8_lossy(&attr.value)),
+                        ));
+                    }
+                } else {
+                    parse_value(position, &attr.value)?
+                };
+            }
+            b"since" => entry.since = parse_value(position, &attr.value)?,
+            b"deprecated-since" => {
+                entry.deprecated_since = Some(parse_value(position, &attr.value)?)
+            }
+            b"summary" => {
+                entry.summary = Some(
+                    String::from_utf8_lossy(&attr.value)
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
+            }
+            _ => {}
+        }
+    }
+
+    parse_children(reader, b"entry", |reader, position, bytes| {
+        match bytes.name().into_inner() {
+            b"description" => {
+                entry.description = Some(parse_description(reader, position, bytes.attributes())?)
+            }
+            name => return Err(unexpected_element(position, name)),
+        }
+        Ok(())
+    })
+    .map_err(|e| e.within("entry", &entry.name))?;
+
+    Ok(entry)
+}
@@ -1,23 +1,24 @@
 //! Client-side rust implementation of a Wayland protocol backend
 
 use std::{
+    collections::{HashMap, VecDeque},
     fmt,
     os::unix::io::{BorrowedFd, OwnedFd},
-    os::unix::{
-        io::{AsRawFd, RawFd},
-        net::UnixStream,
-    },
-    sync::{Arc, Condvar, Mutex, MutexGuard, Weak},
+    os::unix::{io::AsRawFd, net::UnixStream},
+    sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak},
+    thread::ThreadId,
 };
 
 use crate::{
     core_interfaces::WL_DISPLAY_INTERFACE,
-    debug,
+    debug::{self, DebugOutput},
+    metrics::MetricsCounters,
     protocol::{
-        check_for_signature, same_interface, same_interface_or_anonymous, AllowNull, Argument,
-        ArgumentType, Interface, Message, ObjectInfo, ProtocolError, ANONYMOUS_INTERFACE,
-        INLINE_ARGS,
+        can_veto, check_for_signature, same_interface, same_interface_or_anonymous, AllowNull,
+        Argument, ArgumentType, BorrowedArgument, BorrowedMessage, Direction, Interface, Message,
+        MessageDesc, ObjectInfo, ProtocolError, ANONYMOUS_INTERFACE, INLINE_ARGS,
     },
+    wakeup::Wakeup,
 };
 use smallvec::SmallVec;
 
@@ -34,6 +35,22 @@ struct Data {
     server_destroyed: bool,
     user_data: Arc<dyn ObjectData>,
     serial: u32,
+    // the queue of the guest dispatching this object, if it belongs to one
+    queue: Option<Weak<GuestQueue>>,
+}
+
+/// The events received for the objects of a guest backend, until the guest dispatches them
+#[derive(Debug, Default)]
+struct GuestQueue {
+    events: Mutex<VecDeque<QueuedEvent>>,
+}
+
+#[derive(Debug)]
+struct QueuedEvent {
+    message: Message<ObjectId, OwnedFd>,
+    desc: &'static MessageDesc,
+    // the object created by the event, if any
+    created_id: Option<InnerObjectId>,
 }
 
 /// An ID representing a Wayland object
@@ -92,10 +109,14 @@ impl InnerObjectId {
 #[derive(Debug)]
 struct ProtocolState {
     socket: BufferedSocket,
-    map: ObjectMap<Data>,
+    // only modified with the protocol state locked, shared with the connection state
+    map: Arc<RwLock<ObjectMap<Data>>>,
     last_error: Option<WaylandError>,
     last_serial: u32,
-    debug: bool,
+    debug: Option<DebugOutput>,
+    interceptor: Option<Arc<dyn Interceptor>>,
+    // number of events discarded because their object was destroyed, per interface name
+    dead_object_events: HashMap<&'static str, u64>,
 }
 
 #[derive(Debug)]
@@ -103,12 +124,21 @@ struct ReadingState {
     prepared_reads: usize,
     read_condvar: Arc<Condvar>,
     read_serial: usize,
+    // the thread currently reading and dispatching events, if any
+    reader: Option<ThreadId>,
 }
 
 #[derive(Debug)]
 pub struct ConnectionState {
     protocol: Mutex<ProtocolState>,
+    // the object map of the protocol state, read without locking it so that the queries about objects
+    // do not wait for the dispatching or the sending of messages
+    objects: Arc<RwLock<ObjectMap<Data>>>,
     read: Mutex<ReadingState>,
+    wakeup: Wakeup,
+    metrics: Arc<MetricsCounters>,
+    // last, so that the callbacks run once the socket is closed
+    drop_callbacks: DropCallbacks,
 }
 
 impl ConnectionState {
@@ -119,27 +149,62 @@ impl ConnectionState {
     fn lock_read(&self) -> MutexGuard<ReadingState> {
         self.read.lock().unwrap()
     }
+
+    fn read_objects(&self) -> RwLockReadGuard<ObjectMap<Data>> {
+        self.objects.read().unwrap()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct InnerBackend {
     state: Arc<ConnectionState>,
+    // set on a guest, which dispatches its objects itself
+    queue: Option<Arc<GuestQueue>>,
 }
 
 #[derive(Clone, Debug)]
 pub struct WeakInnerBackend {
     state: Weak<ConnectionState>,
+    queue: Option<Weak<GuestQueue>>,
 }
 
 impl WeakInnerBackend {
     pub fn upgrade(&self) -> Option<InnerBackend> {
-        Weak::upgrade(&self.state).map(|state| InnerBackend { state })
+        let state = Weak::upgrade(&self.state)?;
+        let queue = match self.queue {
+            Some(ref queue) => Some(Weak::upgrade(queue)?),
+            None => None,
+        };
+        Some(InnerBackend { state, queue })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.state.strong_count() > 0
+    }
+
+    pub fn on_drop(&self, callback: DropCallback) {
+        match Weak::upgrade(&self.state) {
+            Some(state) => state.drop_callbacks.push(callback),
+            None => (callback.0)(),
+        }
+    }
+}
+
+impl InnerBackend {
+    #[cfg(feature = "debug-checks")]
+    pub(crate) fn connection_key(&self) -> usize {
+        Arc::as_ptr(&self.state) as *const () as usize
     }
 }
 
 impl PartialEq for InnerBackend {
     fn eq(&self, rhs: &Self) -> bool {
-        Arc::ptr_eq(&self.state, &rhs.state)
+        let same_queue = match (&self.queue, &rhs.queue) {
+            (Some(queue), Some(other)) => Arc::ptr_eq(queue, other),
+            (None, None) => true,
+            _ => false,
+        };
+        Arc::ptr_eq(&self.state, &rhs.state) && same_queue
     }
 }
 
@@ -147,13 +212,28 @@ impl Eq for InnerBackend {}
 
 impl InnerBackend {
     pub fn downgrade(&self) -> WeakInnerBackend {
-        WeakInnerBackend { state: Arc::downgrade(&self.state) }
+        WeakInnerBackend {
+            state: Arc::downgrade(&self.state),
+            queue: self.queue.as_ref().map(Arc::downgrade),
+        }
+    }
+
+    pub fn create_guest(&self) -> Self {
+        Self { state: self.state.clone(), queue: Some(Arc::new(GuestQueue::default())) }
     }
 
     pub fn connect(stream: UnixStream) -> Result<Self, NoWaylandLib> {
-        let socket = BufferedSocket::new(Socket::from(stream));
+        Self::connect_with_buffer_sizes(stream, BufferSizes::default())
+    }
+
+    pub fn connect_with_buffer_sizes(
+        stream: UnixStream,
+        sizes: BufferSizes,
+    ) -> Result<Self, NoWaylandLib> {
+        let metrics = Arc::new(MetricsCounters::default());
+        let socket = BufferedSocket::with_metrics(Socket::from(stream), sizes, metrics.clone());
         let mut map = ObjectMap::new();
-        map.insert_at(
+        map.client_insert_at(
             1,
             Object {
                 interface: &WL_DISPLAY_INTERFACE,
@@ -163,28 +243,38 @@ impl InnerBackend {
                     server_destroyed: false,
                     user_data: Arc::new(DumbObjectData),
                     serial: 0,
+                    queue: None,
                 },
             },
         )
         .unwrap();
 
-        let debug = debug::has_debug_client_env();
+        let map = Arc::new(RwLock::new(map));
+        let debug = DebugOutput::new(None, debug::has_debug_client_env());
 
         Ok(Self {
             state: Arc::new(ConnectionState {
+                objects: map.clone(),
                 protocol: Mutex::new(ProtocolState {
                     socket,
                     map,
                     last_error: None,
                     last_serial: 0,
                     debug,
+                    interceptor: None,
+                    dead_object_events: HashMap::new(),
                 }),
                 read: Mutex::new(ReadingState {
                     prepared_reads: 0,
                     read_condvar: Arc::new(Condvar::new()),
                     read_serial: 0,
+                    reader: None,
                 }),
+                wakeup: Wakeup::default(),
+                metrics,
+                drop_callbacks: DropCallbacks::default(),
             }),
+            queue: None,
         })
     }
 
@@ -198,6 +288,52 @@ impl InnerBackend {
         Ok(())
     }
 
+    pub fn pending_output_bytes(&self) -> usize {
+        self.state.lock_protocol().socket.pending_output_bytes()
+    }
+
+    pub fn wakeup(&self) -> std::io::Result<()> {
+        self.state.wakeup.wake()
+    }
+
+    pub fn wakeup_fd(&self) -> std::io::Result<BorrowedFd> {
+        self.state.wakeup.fd()
+    }
+
+    pub fn take_wakeup(&self) -> bool {
+        self.state.wakeup.take()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.state.metrics.snapshot()
+    }
+
+    pub fn dead_object_events(&self) -> HashMap<&'static str, u64> {
+        self.state.lock_protocol().dead_object_events.clone()
+    }
+
+    pub fn memory_report(&self) -> MemoryReport {
+        let guard = self.state.lock_protocol();
+        let (buffered_bytes, buffered_fds) = guard.socket.buffered();
+        let objects = self.state.read_objects();
+        let objects_memory = objects.memory_usage();
+        // the events of a guest wait in its queue, the ones of the host are dispatched as they are read
+        let queued_messages =
+            self.queue.as_ref().map_or(0, |queue| queue.events.lock().unwrap().len());
+        MemoryReport {
+            objects: objects.all_objects().count(),
+            objects_memory,
+            queued_messages,
+            buffered_bytes,
+            buffered_fds,
+            memory: std::mem::size_of::<ConnectionState>()
+                + objects_memory
+                + guard.socket.memory_usage()
+                + queued_messages * std::mem::size_of::<QueuedEvent>(),
+        }
+    }
+
     pub fn poll_fd(&self) -> BorrowedFd {
         let raw_fd = self.state.lock_protocol().socket.as_raw_fd();
         // This allows the lifetime of the BorrowedFd to be tied to &self rather than the lock guard,
@@ -209,6 +345,7 @@ impl InnerBackend {
 #[derive(Debug)]
 pub struct InnerReadEventsGuard {
     state: Arc<ConnectionState>,
+    queue: Option<Arc<GuestQueue>>,
     done: bool,
 }
 
@@ -218,8 +355,14 @@ impl InnerReadEventsGuard {
     /// This call will not block, but event callbacks may be invoked in the process
     /// of preparing the guard.
     pub fn try_new(backend: InnerBackend) -> Option<Self> {
+        if let Some(ref queue) = backend.queue {
+            // the events already read for the guest must be dispatched first
+            if !queue.events.lock().unwrap().is_empty() {
+                return None;
+            }
+        }
         backend.state.lock_read().prepared_reads += 1;
-        Some(Self { state: backend.state, done: false })
+        Some(Self { state: backend.state, queue: backend.queue, done: false })
     }
 
     /// Access the Wayland socket FD for polling
@@ -240,19 +383,44 @@ impl InnerReadEventsGuard {
     /// This returns the number of dispatched events, or `0` if an other thread handled the dispatching.
     /// If no events are available to read from the socket, this returns a `WouldBlock` IO error.
     pub fn read(mut self) -> Result<usize, WaylandError> {
+        let ret = self.read_connection()?;
+        // a guest then dispatches the events read for its objects, by any thread
+        match self.queue.take() {
+            Some(queue) => {
+                let backend = InnerBackend { state: self.state.clone(), queue: Some(queue) };
+                Ok(ret + backend.dispatch_guest_queue())
+            }
+            None => Ok(ret),
+        }
+    }
+
+    fn read_connection(&mut self) -> Result<usize, WaylandError> {
         let mut guard = self.state.lock_read();
         guard.prepared_reads -= 1;
         self.done = true;
-        if guard.prepared_reads == 0 {
-            // We should be the one reading
+        let current = std::thread::current().id();
+        if guard.reader == Some(current) {
+            // We are invoked from a callback of the events being dispatched by this thread, they
+            // will be dispatched once it returns
+            std::mem::drop(guard);
+            self.state.lock_protocol().no_last_error()?;
+            Ok(0)
+        } else if guard.prepared_reads == 0 && guard.reader.is_none() {
+            // We should be the one reading, the lock is released so that the callbacks can
+            // prepare new reads
+            guard.reader = Some(current);
+            std::mem::drop(guard);
             let ret = dispatch_events(self.state.clone());
             // wake up other threads
+            let mut guard = self.state.lock_read();
+            guard.reader = None;
             guard.read_serial = guard.read_serial.wrapping_add(1);
             guard.read_condvar.notify_all();
             // forward the return value
             ret
         } else {
-            // We should wait for an other thread to read (or cancel)
+            // We should wait for an other thread to read (or cancel), or for the read in progress
+            // to complete
             let serial = guard.read_serial;
             let condvar = guard.read_condvar.clone();
             let _guard =
@@ -268,7 +436,7 @@ impl Drop for InnerReadEventsGuard {
         if !self.done {
             let mut guard = self.state.lock_read();
             guard.prepared_reads -= 1;
-            if guard.prepared_reads == 0 {
+            if guard.prepared_reads == 0 && guard.reader.is_none() {
                 // Cancel the read
                 guard.read_serial = guard.read_serial.wrapping_add(1);
                 guard.read_condvar.notify_all();
@@ -287,7 +455,7 @@ impl InnerBackend {
     }
 
     pub fn info(&self, id: ObjectId) -> Result<ObjectInfo, InvalidId> {
-        let object = self.state.lock_protocol().get_object(id.id.clone())?;
+        let object = get_object(&self.state.read_objects(), id.id.clone())?;
         if object.data.client_destroyed {
             Err(InvalidId)
         } else {
@@ -295,13 +463,31 @@ impl InnerBackend {
         }
     }
 
+    pub fn all_objects(&self) -> Vec<ObjectEntry> {
+        self.state
+            .read_objects()
+            .all_objects()
+            .map(|(id, object)| ObjectEntry {
+                id: ObjectId {
+                    id: InnerObjectId {
+                        serial: object.data.serial,
+                        id,
+                        interface: object.interface,
+                    },
+                },
+                info: ObjectInfo { id, interface: object.interface, version: object.version },
+                alive: !object.data.client_destroyed,
+            })
+            .collect()
+    }
+
     pub fn null_id() -> ObjectId {
         ObjectId { id: InnerObjectId { serial: 0, id: 0, interface: &ANONYMOUS_INTERFACE } }
     }
 
     pub fn send_request(
         &self,
-        Message { sender_id: ObjectId { id }, opcode, args }: Message<ObjectId, RawFd>,
+        Message { sender_id: ObjectId { id }, opcode, args }: Message<ObjectId, BorrowedFd<'_>>,
         data: Option<Arc<dyn ObjectData>>,
         child_spec: Option<(&'static Interface, u32)>,
     ) -> Result<ObjectId, InvalidId> {
@@ -316,8 +502,14 @@ impl InnerBackend {
         };
 
         if object.data.client_destroyed {
-            if guard.debug {
-                debug::print_send_message(id.interface.name, id.id, message_desc.name, &args, true);
+            if let Some(ref output) = guard.debug {
+                debug::print_discarded_message(
+                    output,
+                    id.interface.name,
+                    id.id,
+                    message_desc.name,
+                    &args,
+                );
             }
             return Err(InvalidId);
         }
@@ -384,13 +576,22 @@ impl InnerBackend {
                     server_destroyed: false,
                     user_data: Arc::new(DumbObjectData),
                     serial: child_serial,
+                    queue: self.queue.as_ref().map(Arc::downgrade),
                 },
             };
 
-            let child_id = guard.map.client_insert_new(child);
+            let child_id = guard.objects_mut().client_insert_new(child);
+            let child_id = match child_id {
+                Some(id) => id,
+                None => {
+                    // all the ids of the client namespace are used, the connection cannot go on
+                    guard.store_and_return_error(std::io::Error::from(rustix::io::Errno::NOSPC));
+                    return Err(InvalidId);
+                }
+            };
 
             guard
-                .map
+                .objects_mut()
                 .with(child_id, |obj| {
                     obj.data.user_data = data.expect(
                         "Sending a request creating an object without providing an object data.",
@@ -418,17 +619,42 @@ impl InnerBackend {
             }
         }).collect::<SmallVec<[_; INLINE_ARGS]>>();
 
-        if guard.debug {
-            debug::print_send_message(
-                object.interface.name,
-                id.id,
-                message_desc.name,
-                &args,
-                false,
-            );
+        let seq = guard.socket.next_sequence_out();
+        let msg = Message { sender_id: ObjectId { id: id.clone() }, opcode, args };
+        // requests creating an object are intercepted once they are sent, like with the system backend
+        let (vetoed, sent_msg) = match guard.interceptor {
+            Some(_) if child.is_some() => (false, Some(msg.clone())),
+            Some(ref interceptor) => {
+                (!interceptor.request_sequenced(seq, &msg) && can_veto(message_desc), None)
+            }
+            None => (false, None),
+        };
+        let args = msg.args;
+
+        if let Some(ref output) = guard.debug {
+            let (interface, name) = (object.interface.name, message_desc.name);
+            if vetoed {
+                debug::print_discarded_message(output, interface, id.id, name, &args);
+            } else {
+                debug::print_send_message(
+                    output,
+                    Direction::Request,
+                    interface,
+                    id.id,
+                    name,
+                    &args,
+                    None,
+                );
+            }
         }
         #[cfg(feature = "log")]
-        crate::log_debug!("Sending {}.{} ({})", id, message_desc.name, debug::DisplaySlice(&args));
+        crate::log_debug!(
+            "Sending #{} {}.{} ({})",
+            seq,
+            id,
+            message_desc.name,
+            debug::DisplaySlice(&args)
+        );
 
         // Send the message
 
@@ -460,14 +686,19 @@ impl InnerBackend {
 
         let msg = Message { sender_id: id.id, opcode, args: msg_args };
 
-        if let Err(err) = guard.socket.write_message(&msg) {
-            guard.last_error = Some(WaylandError::Io(err));
+        if !vetoed {
+            if let Err(err) = guard.socket.write_message(&msg) {
+                guard.store_and_return_error(err);
+            }
+        }
+        if let (Some(interceptor), Some(sent_msg)) = (guard.interceptor.as_ref(), sent_msg) {
+            interceptor.request_sequenced(seq, &sent_msg);
         }
 
         // Handle destruction if relevant
         if message_desc.is_destructor {
             guard
-                .map
+                .objects_mut()
                 .with(id.id, |obj| {
                     obj.data.client_destroyed = true;
                 })
@@ -488,32 +719,138 @@ impl InnerBackend {
     }
 
     pub fn get_data(&self, id: ObjectId) -> Result<Arc<dyn ObjectData>, InvalidId> {
-        let object = self.state.lock_protocol().get_object(id.id)?;
+        let object = get_object(&self.state.read_objects(), id.id)?;
         Ok(object.data.user_data)
     }
 
     pub fn set_data(&self, id: ObjectId, data: Arc<dyn ObjectData>) -> Result<(), InvalidId> {
-        self.state
-            .lock_protocol()
-            .map
+        self.replace_data(id, data).map(drop)
+    }
+
+    pub fn replace_data(
+        &self,
+        id: ObjectId,
+        data: Arc<dyn ObjectData>,
+    ) -> Result<Arc<dyn ObjectData>, InvalidId> {
+        // the lock of the protocol state keeps the map from being modified concurrently
+        let guard = self.state.lock_protocol();
+        let old_data = guard
+            .objects_mut()
             .with(id.id.id, move |objdata| {
                 if objdata.data.serial != id.id.serial {
                     Err(InvalidId)
                 } else {
-                    objdata.data.user_data = data;
-                    Ok(())
+                    Ok(std::mem::replace(&mut objdata.data.user_data, data))
                 }
             })
-            .unwrap_or(Err(InvalidId))
+            .unwrap_or(Err(InvalidId));
+        old_data
+    }
+
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.state.lock_protocol().interceptor = interceptor;
     }
 
-    // Nothing to do here, we don't have an inner queue
+    pub fn set_debug_sink(&self, sink: Option<DebugSink>) {
+        self.state.lock_protocol().debug = DebugOutput::new(sink, debug::has_debug_client_env());
+    }
+
+    pub fn set_max_buffer_size(&self, size: usize) {
+        self.state.lock_protocol().socket.set_max_size(size);
+    }
+
+    // Only a guest has an inner queue
     pub fn dispatch_inner_queue(&self) -> Result<usize, WaylandError> {
-        Ok(0)
+        self.state.lock_protocol().no_last_error()?;
+        Ok(self.dispatch_guest_queue())
+    }
+
+    /// Dispatch the events queued for the objects of this guest
+    fn dispatch_guest_queue(&self) -> usize {
+        let Some(ref queue) = self.queue else {
+            return 0;
+        };
+        let backend = Backend { backend: self.clone() };
+        let mut dispatched = 0;
+        loop {
+            // popped one at a time, the callbacks may dispatch the queue as well
+            let Some(event) = queue.events.lock().unwrap().pop_front() else {
+                return dispatched;
+            };
+            let QueuedEvent { message, desc, created_id } = event;
+            let mut guard = self.state.lock_protocol();
+            // the guest may have destroyed the object since the event was read
+            let receiver = match guard.get_object(message.sender_id.id.clone()) {
+                Ok(receiver) if !receiver.data.client_destroyed => receiver,
+                _ => {
+                    *guard
+                        .dead_object_events
+                        .entry(message.sender_id.id.interface.name)
+                        .or_default() += 1;
+                    if let Some(child_id) = created_id {
+                        guard
+                            .objects_mut()
+                            .with(child_id.id, |obj| obj.data.client_destroyed = true)
+                            .unwrap();
+                    }
+                    continue;
+                }
+            };
+            std::mem::drop(guard);
+            let sender_id = message.sender_id.id.id;
+            let metrics = &self.state.metrics;
+            let ret =
+                metrics.time_dispatch(|| receiver.data.user_data.clone().event(&backend, message));
+            let mut guard = self.state.lock_protocol();
+            guard.finish_dispatch(sender_id, &receiver, desc, created_id, ret);
+            dispatched += 1;
+        }
     }
 }
 
 impl ProtocolState {
+    /// Destroy the object receiving a destructor event, and attach its data to the object created by
+    /// an event, once it has been dispatched
+    fn finish_dispatch(
+        &mut self,
+        sender_id: u32,
+        receiver: &Object<Data>,
+        message_desc: &MessageDesc,
+        created_id: Option<InnerObjectId>,
+        ret: Option<Arc<dyn ObjectData>>,
+    ) {
+        if message_desc.is_destructor {
+            self.objects_mut()
+                .with(sender_id, |obj| {
+                    obj.data.server_destroyed = true;
+                    obj.data.client_destroyed = true;
+                })
+                .unwrap();
+            receiver.data.user_data.destroyed(ObjectId {
+                id: InnerObjectId {
+                    id: sender_id,
+                    serial: receiver.data.serial,
+                    interface: receiver.interface,
+                },
+            });
+        }
+
+        match (created_id, ret) {
+            (Some(child_id), Some(child_data)) => {
+                self.objects_mut()
+                    .with(child_id.id, |obj| obj.data.user_data = child_data)
+                    .unwrap();
+            }
+            (None, None) => {}
+            (Some(child_id), None) => {
+                panic!("Callback creating object {} did not provide any object data.", child_id);
+            }
+            (None, Some(_)) => {
+                panic!("An object data was returned from a callback not creating any object");
+            }
+        }
+    }
+
     fn next_serial(&mut self) -> u32 {
         self.last_serial = self.last_serial.wrapping_add(1);
         self.last_serial
@@ -530,7 +867,10 @@ impl ProtocolState {
 
     #[inline]
     fn store_and_return_error(&mut self, err: impl Into<WaylandError>) -> WaylandError {
-        let err = err.into();
+        let mut err = err.into();
+        if let WaylandError::Disconnected(None) = err {
+            err = WaylandError::Disconnected(self.pending_protocol_error());
+        }
         crate::log_error!("{}", err);
         self.last_error = Some(err.clone());
         err
@@ -545,36 +885,67 @@ impl ProtocolState {
         }
     }
 
-    fn get_object(&self, id: InnerObjectId) -> Result<Object<Data>, InvalidId> {
-        let object = self.map.find(id.id).ok_or(InvalidId)?;
-        if object.data.serial != id.serial {
-            return Err(InvalidId);
+    /// Look for a protocol error sent by the server before closing the connection, but not
+    /// dispatched yet
+    fn pending_protocol_error(&mut self) -> Option<ProtocolError> {
+        // receive whatever the server sent before closing the connection
+        while self.socket.fill_incoming_buffers().is_ok() {}
+        let mut scratch = Vec::new();
+        let message = self.socket.find_message(
+            1,
+            0,
+            WL_DISPLAY_INTERFACE.events[0].signature,
+            &mut scratch,
+        )?;
+        match self.handle_display_event(message) {
+            Err(WaylandError::Protocol(err)) => Some(err),
+            _ => None,
         }
-        Ok(object)
     }
 
-    fn handle_display_event(&mut self, message: Message<u32, OwnedFd>) -> Result<(), WaylandError> {
-        if self.debug {
+    fn get_object(&self, id: InnerObjectId) -> Result<Object<Data>, InvalidId> {
+        get_object(&self.objects(), id)
+    }
+
+    fn objects(&self) -> RwLockReadGuard<ObjectMap<Data>> {
+        self.map.read().unwrap()
+    }
+
+    fn objects_mut(&self) -> RwLockWriteGuard<ObjectMap<Data>> {
+        self.map.write().unwrap()
+    }
+
+    fn handle_display_event(
+        &mut self,
+        message: BorrowedMessage<'_, u32, OwnedFd>,
+    ) -> Result<(), WaylandError> {
+        if let Some(ref output) = self.debug {
+            let desc = &WL_DISPLAY_INTERFACE.events[message.opcode as usize];
+            let map = self.objects();
             debug::print_dispatched_message(
+                output,
+                Direction::Event,
                 "wl_display",
                 message.sender_id,
-                if message.opcode == 0 { "error" } else { "delete_id" },
-                &message.args,
+                desc.name,
+                &debug::resolve_ids(&message.args, desc, |id| map.find(id).map(|o| o.interface)),
+                None,
             );
         }
         match message.opcode {
             0 => {
                 // wl_display.error
-                if let [Argument::Object(obj), Argument::Uint(code), Argument::Str(Some(ref message))] =
+                if let [BorrowedArgument::Object(obj), BorrowedArgument::Uint(code), BorrowedArgument::Str(Some(message))] =
                     message.args[..]
                 {
-                    let object = self.map.find(obj);
+                    let object = self.objects().find(obj);
                     let err = WaylandError::Protocol(ProtocolError {
                         code,
                         object_id: obj,
+                        // left empty for an unknown object, like libwayland does
                         object_interface: object
                             .map(|obj| obj.interface.name)
-                            .unwrap_or("<unknown>")
+                            .unwrap_or_default()
                             .into(),
                         message: message.to_string_lossy().into(),
                     });
@@ -585,16 +956,16 @@ impl ProtocolState {
             }
             1 => {
                 // wl_display.delete_id
-                if let [Argument::Uint(id)] = message.args[..] {
-                    let client_destroyed = self
-                        .map
+                if let [BorrowedArgument::Uint(id)] = message.args[..] {
+                    let mut map = self.objects_mut();
+                    let client_destroyed = map
                         .with(id, |obj| {
                             obj.data.server_destroyed = true;
                             obj.data.client_destroyed
                         })
                         .unwrap_or(false);
                     if client_destroyed {
-                        self.map.remove(id);
+                        map.remove(id);
                     }
                 } else {
                     unreachable!()
@@ -606,48 +977,62 @@ impl ProtocolState {
     }
 }
 
+fn get_object(map: &ObjectMap<Data>, id: InnerObjectId) -> Result<Object<Data>, InvalidId> {
+    let object = map.find(id.id).ok_or(InvalidId)?;
+    if object.data.serial != id.serial {
+        return Err(InvalidId);
+    }
+    Ok(object)
+}
+
 fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
-    let backend = Backend { backend: InnerBackend { state } };
+    let backend = Backend { backend: InnerBackend { state, queue: None } };
     let mut guard = backend.backend.state.lock_protocol();
     guard.no_last_error()?;
     let mut dispatched = 0;
-    loop {
+    // the events read for the objects of guests, which dispatch them
+    let mut queued = 0;
+    // the raw events are copied here, and their arguments borrowed from it
+    let mut scratch = guard.socket.pool.take_scratch();
+    let ret = loop {
         // Attempt to read a message
-        let ProtocolState { ref mut socket, ref map, .. } = *guard;
-        let message = match socket.read_one_message(|id, opcode| {
-            map.find(id)
-                .and_then(|o| o.interface.events.get(opcode as usize))
-                .map(|desc| desc.signature)
-        }) {
+        let message = {
+            let ProtocolState { ref mut socket, ref map, .. } = *guard;
+            let map = map.read().unwrap();
+            let signature = |id, opcode| {
+                map.find(id)
+                    .and_then(|o: Object<Data>| o.interface.events.get(opcode as usize))
+                    .map(|desc| desc.signature)
+            };
+            match socket.peek_message(signature) {
+                Ok(_) => socket.read_one_message(signature, &mut scratch),
+                Err(e) => Err(e),
+            }
+        };
+        let mut message = match message {
             Ok(msg) => msg,
             Err(MessageParseError::MissingData) | Err(MessageParseError::MissingFD) => {
                 // need to read more data
                 if let Err(e) = guard.socket.fill_incoming_buffers() {
                     if e.kind() != std::io::ErrorKind::WouldBlock {
                         return Err(guard.store_and_return_error(e));
-                    } else if dispatched == 0 {
-                        return Err(e.into());
-                    } else {
-                        break;
                     }
+                    break if dispatched == 0 && queued == 0 {
+                        Err(e.into())
+                    } else {
+                        Ok(dispatched)
+                    };
                 }
                 continue;
             }
-            Err(MessageParseError::Malformed) => {
-                // malformed error, protocol error
-                let err = WaylandError::Protocol(ProtocolError {
-                    code: 0,
-                    object_id: 0,
-                    object_interface: "".into(),
-                    message: "Malformed Wayland message.".into(),
-                });
-                return Err(guard.store_and_return_error(err));
+            Err(MessageParseError::Malformed(e)) => {
+                return Err(guard.store_and_return_error(WaylandError::Wire(e)));
             }
         };
 
         // We got a message, retrieve its associated object & details
         // These lookups must succeed otherwise we would not have been able to parse this message
-        let receiver = guard.map.find(message.sender_id).unwrap();
+        let receiver = guard.objects().find(message.sender_id).unwrap();
         let message_desc = receiver.interface.events.get(message.opcode as usize).unwrap();
 
         // Short-circuit display-associated events
@@ -661,18 +1046,19 @@ fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
         // Convert the arguments and create the new object if applicable
         let mut args = SmallVec::with_capacity(message.args.len());
         let mut arg_interfaces = message_desc.arg_interfaces.iter();
-        for arg in message.args.into_iter() {
+        for arg in message.args.drain(..) {
             args.push(match arg {
-                Argument::Array(a) => Argument::Array(a),
-                Argument::Int(i) => Argument::Int(i),
-                Argument::Uint(u) => Argument::Uint(u),
-                Argument::Str(s) => Argument::Str(s),
-                Argument::Fixed(f) => Argument::Fixed(f),
-                Argument::Fd(f) => Argument::Fd(f),
-                Argument::Object(o) => {
+                BorrowedArgument::Array(a) => BorrowedArgument::Array(a),
+                BorrowedArgument::Int(i) => BorrowedArgument::Int(i),
+                BorrowedArgument::Uint(u) => BorrowedArgument::Uint(u),
+                BorrowedArgument::Str(s) => BorrowedArgument::Str(s),
+                BorrowedArgument::Fixed(f) => BorrowedArgument::Fixed(f),
+                BorrowedArgument::Fd(f) => BorrowedArgument::Fd(f),
+                BorrowedArgument::Object(o) => {
                     if o != 0 {
                         // Lookup the object to make the appropriate Id
-                        let obj = match guard.map.find(o) {
+                        let obj = guard.objects().find(o);
+                        let obj = match obj {
                             Some(o) => o,
                             None => {
                                 let err = WaylandError::Protocol(ProtocolError {
@@ -698,12 +1084,12 @@ fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
                                 return Err(guard.store_and_return_error(err));
                             }
                         }
-                        Argument::Object(ObjectId { id: InnerObjectId { id: o, serial: obj.data.serial, interface: obj.interface }})
+                        BorrowedArgument::Object(ObjectId { id: InnerObjectId { id: o, serial: obj.data.serial, interface: obj.interface }})
                     } else {
-                        Argument::Object(ObjectId { id: InnerObjectId { id: 0, serial: 0, interface: &ANONYMOUS_INTERFACE }})
+                        BorrowedArgument::Object(ObjectId { id: InnerObjectId { id: 0, serial: 0, interface: &ANONYMOUS_INTERFACE }})
                     }
                 }
-                Argument::NewId(new_id) => {
+                BorrowedArgument::NewId(new_id) => {
                     // An object should be created
                     let child_interface = match message_desc.child_interface {
                         Some(iface) => iface,
@@ -714,9 +1100,9 @@ fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
 
                     // if this ID belonged to a now destroyed server object, we can replace it
                     if new_id >= SERVER_ID_LIMIT
-                        && guard.map.with(new_id, |obj| obj.data.client_destroyed).unwrap_or(false)
+                        && guard.objects().find(new_id).map_or(false, |obj| obj.data.client_destroyed)
                     {
-                        guard.map.remove(new_id);
+                        guard.objects_mut().remove(new_id);
                     }
 
                     let child_obj = Object {
@@ -727,13 +1113,15 @@ fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
                             server_destroyed: false,
                             user_data: child_udata,
                             serial: guard.next_serial(),
+                            queue: receiver.data.queue.clone(),
                         }
                     };
 
                     let child_id = InnerObjectId { id: new_id, serial: child_obj.data.serial, interface: child_obj.interface };
                     created_id = Some(child_id.clone());
 
-                    if let Err(()) = guard.map.insert_at(new_id, child_obj) {
+                    let inserted = guard.objects_mut().server_insert_at(new_id, child_obj);
+                    if let Err(()) = inserted {
                         // abort parsing, this is an unrecoverable error
                         let err = WaylandError::Protocol(ProtocolError {
                             code: 0,
@@ -748,22 +1136,27 @@ fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
                         return Err(guard.store_and_return_error(err));
                     }
 
-                    Argument::NewId(ObjectId { id: child_id })
+                    BorrowedArgument::NewId(ObjectId { id: child_id })
                 }
             });
         }
+        guard.socket.pool.recycle_args(std::mem::take(&mut message.args));
 
-        if guard.debug {
+        if let Some(ref output) = guard.debug {
             debug::print_dispatched_message(
+                output,
+                Direction::Event,
                 receiver.interface.name,
                 message.sender_id,
                 message_desc.name,
                 &args,
+                None,
             );
         }
 
         // If this event is send to an already destroyed object (by the client), swallow it
         if receiver.data.client_destroyed {
+            *guard.dead_object_events.entry(receiver.interface.name).or_default() += 1;
             continue;
         }
 
@@ -774,55 +1167,55 @@ fn dispatch_events(state: Arc<ConnectionState>) -> Result<usize, WaylandError> {
             interface: receiver.interface,
         };
 
+        let seq = guard.socket.last_sequence_in();
+        let interceptor = guard.interceptor.clone();
         // unlock the mutex while we invoke the user callback
         std::mem::drop(guard);
+        let msg = BorrowedMessage { sender_id: ObjectId { id }, opcode: message.opcode, args };
+        let vetoed = match interceptor {
+            Some(interceptor) => !interceptor.event_sequenced(seq, &msg) && can_veto(message_desc),
+            None => false,
+        };
+
+        // The objects of a guest are dispatched by the guest, their events are queued for it
+        if let Some(ref queue) = receiver.data.queue {
+            guard = backend.backend.state.lock_protocol();
+            let Some(queue) = Weak::upgrade(queue) else {
+                // the guest was dropped, abandoning its objects
+                *guard.dead_object_events.entry(receiver.interface.name).or_default() += 1;
+                continue;
+            };
+            if !vetoed {
+                queue.events.lock().unwrap().push_back(QueuedEvent {
+                    message: msg.into_owned(),
+                    desc: message_desc,
+                    created_id,
+                });
+            }
+            queued += 1;
+            continue;
+        }
+
         #[cfg(feature = "log")]
         crate::log_debug!(
-            "Dispatching {}.{} ({})",
-            id,
+            "Dispatching #{} {}.{} ({})",
+            seq,
+            msg.sender_id.id,
             receiver.version,
-            debug::DisplaySlice(&args)
+            debug::DisplaySlice(&msg.args)
         );
-        let ret = receiver
-            .data
-            .user_data
-            .clone()
-            .event(&backend, Message { sender_id: ObjectId { id }, opcode: message.opcode, args });
+        let ret = if vetoed {
+            None
+        } else {
+            let metrics = &backend.backend.state.metrics;
+            metrics.time_dispatch(|| receiver.data.user_data.clone().event_borrowed(&backend, msg))
+        };
         // lock it again to resume dispatching
         guard = backend.backend.state.lock_protocol();
-
-        // If this event is a destructor, destroy the object
-        if message_desc.is_destructor {
-            guard
-                .map
-                .with(message.sender_id, |obj| {
-                    obj.data.server_destroyed = true;
-                    obj.data.client_destroyed = true;
-                })
-                .unwrap();
-            receiver.data.user_data.destroyed(ObjectId {
-                id: InnerObjectId {
-                    id: message.sender_id,
-                    serial: receiver.data.serial,
-                    interface: receiver.interface,
-                },
-            });
-        }
-
-        match (created_id, ret) {
-            (Some(child_id), Some(child_data)) => {
-                guard.map.with(child_id.id, |obj| obj.data.user_data = child_data).unwrap();
-            }
-            (None, None) => {}
-            (Some(child_id), None) => {
-                panic!("Callback creating object {} did not provide any object data.", child_id);
-            }
-            (None, Some(_)) => {
-                panic!("An object data was returned from a callback not creating any object");
-            }
-        }
+        guard.finish_dispatch(message.sender_id, &receiver, message_desc, created_id, ret);
 
         dispatched += 1;
-    }
-    Ok(dispatched)
+    };
+    guard.socket.pool.recycle_scratch(scratch);
+    ret
 }
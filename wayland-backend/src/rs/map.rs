@@ -2,11 +2,16 @@
 
 use crate::protocol::Interface;
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::BTreeSet};
 
 /// Limit separating server-created from client-created objects IDs in the namespace
 pub const SERVER_ID_LIMIT: u32 = 0xFF00_0000;
 
+// Number of IDs in each side of the namespace, the client IDs start at 1 and the server IDs end at
+// u32::MAX
+const CLIENT_IDS: usize = (SERVER_ID_LIMIT - 1) as usize;
+const SERVER_IDS: usize = (u32::MAX - SERVER_ID_LIMIT) as usize + 1;
+
 /// The representation of a protocol object
 #[derive(Debug, Clone)]
 pub struct Object<Data> {
@@ -22,16 +27,26 @@ pub struct Object<Data> {
 ///
 /// Keeps track of which object id is associated to which
 /// interface object, and which is currently unused.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ObjectMap<Data> {
-    client_objects: Vec<Option<Object<Data>>>,
-    server_objects: Vec<Option<Object<Data>>>,
+    client_objects: Store<Data>,
+    server_objects: Store<Data>,
+    // number of live objects in the client namespace
+    client_count: usize,
 }
 
 impl<Data: Clone> ObjectMap<Data> {
     /// Create a new empty object map
     pub fn new() -> Self {
-        Self { client_objects: Vec::new(), server_objects: Vec::new() }
+        Self::with_limits(CLIENT_IDS, SERVER_IDS)
+    }
+
+    fn with_limits(client_ids: usize, server_ids: usize) -> Self {
+        Self {
+            client_objects: Store::new(client_ids),
+            server_objects: Store::new(server_ids),
+            client_count: 0,
+        }
     }
 
     /// Find an object in the store
@@ -39,9 +54,9 @@ impl<Data: Clone> ObjectMap<Data> {
         if id == 0 {
             None
         } else if id >= SERVER_ID_LIMIT {
-            self.server_objects.get((id - SERVER_ID_LIMIT) as usize).and_then(Clone::clone)
+            self.server_objects.get((id - SERVER_ID_LIMIT) as usize).cloned()
         } else {
-            self.client_objects.get((id - 1) as usize).and_then(Clone::clone)
+            self.client_objects.get((id - 1) as usize).cloned()
         }
     }
 
@@ -52,108 +67,247 @@ impl<Data: Clone> ObjectMap<Data> {
         if id == 0 {
             // nothing
         } else if id >= SERVER_ID_LIMIT {
-            if let Some(place) = self.server_objects.get_mut((id - SERVER_ID_LIMIT) as usize) {
-                *place = None;
-            }
-        } else if let Some(place) = self.client_objects.get_mut((id - 1) as usize) {
-            *place = None;
+            self.server_objects.remove((id - SERVER_ID_LIMIT) as usize);
+        } else if self.client_objects.remove((id - 1) as usize) {
+            self.client_count -= 1;
         }
     }
 
-    /// Insert given object for given id
+    /// Insert given object for given id, which must be in the client namespace
     ///
-    /// Can fail if the requested id is not the next free id of this store.
+    /// Can fail if the requested id is not a free id or the next id of the client namespace.
     /// (In which case this is a protocol error)
-    pub fn insert_at(&mut self, id: u32, object: Object<Data>) -> Result<(), ()> {
+    pub fn client_insert_at(&mut self, id: u32, object: Object<Data>) -> Result<(), ()> {
+        if id >= SERVER_ID_LIMIT {
+            return Err(());
+        }
+        self.insert_at(id, object)
+    }
+
+    /// Insert given object for given id, which must be in the server namespace
+    ///
+    /// Can fail if the requested id is not a free id or the next id of the server namespace.
+    /// (In which case this is a protocol error)
+    pub fn server_insert_at(&mut self, id: u32, object: Object<Data>) -> Result<(), ()> {
+        if id < SERVER_ID_LIMIT {
+            return Err(());
+        }
+        self.insert_at(id, object)
+    }
+
+    fn insert_at(&mut self, id: u32, object: Object<Data>) -> Result<(), ()> {
         if id == 0 {
             Err(())
         } else if id >= SERVER_ID_LIMIT {
-            insert_in_at(&mut self.server_objects, (id - SERVER_ID_LIMIT) as usize, object)
+            self.server_objects.insert_at((id - SERVER_ID_LIMIT) as usize, object)
         } else {
-            insert_in_at(&mut self.client_objects, (id - 1) as usize, object)
+            self.client_objects.insert_at((id - 1) as usize, object)?;
+            self.client_count += 1;
+            Ok(())
         }
     }
 
     /// Allocate a new id for an object in the client namespace
-    pub fn client_insert_new(&mut self, object: Object<Data>) -> u32 {
-        insert_in(&mut self.client_objects, object) + 1
+    ///
+    /// Returns `None` if all the ids of the client namespace are used.
+    pub fn client_insert_new(&mut self, object: Object<Data>) -> Option<u32> {
+        let idx = self.client_objects.insert_new(object)?;
+        self.client_count += 1;
+        Some(idx as u32 + 1)
     }
 
     /// Allocate a new id for an object in the server namespace
-    pub fn server_insert_new(&mut self, object: Object<Data>) -> u32 {
-        insert_in(&mut self.server_objects, object) + SERVER_ID_LIMIT
+    ///
+    /// Returns `None` if all the ids of the server namespace are used.
+    pub fn server_insert_new(&mut self, object: Object<Data>) -> Option<u32> {
+        self.server_objects.insert_new(object).map(|idx| idx as u32 + SERVER_ID_LIMIT)
     }
 
     /// Mutably access an object of the map
     pub fn with<T, F: FnOnce(&mut Object<Data>) -> T>(&mut self, id: u32, f: F) -> Result<T, ()> {
-        if id == 0 {
-            Err(())
+        let object = if id == 0 {
+            None
         } else if id >= SERVER_ID_LIMIT {
-            if let Some(&mut Some(ref mut obj)) =
-                self.server_objects.get_mut((id - SERVER_ID_LIMIT) as usize)
-            {
-                Ok(f(obj))
-            } else {
-                Err(())
-            }
-        } else if let Some(&mut Some(ref mut obj)) = self.client_objects.get_mut((id - 1) as usize)
-        {
-            Ok(f(obj))
+            self.server_objects.get_mut((id - SERVER_ID_LIMIT) as usize)
         } else {
-            Err(())
-        }
+            self.client_objects.get_mut((id - 1) as usize)
+        };
+        object.map(f).ok_or(())
     }
 
     pub fn all_objects(&self) -> impl Iterator<Item = (u32, &Object<Data>)> {
-        let client_side_iter = self
-            .client_objects
-            .iter()
-            .enumerate()
-            .flat_map(|(idx, obj)| obj.as_ref().map(|obj| (idx as u32 + 1, obj)));
-
-        let server_side_iter = self
-            .server_objects
-            .iter()
-            .enumerate()
-            .flat_map(|(idx, obj)| obj.as_ref().map(|obj| (idx as u32 + SERVER_ID_LIMIT, obj)));
+        let client_side_iter = self.client_objects.iter().map(|(idx, obj)| (idx as u32 + 1, obj));
+
+        let server_side_iter =
+            self.server_objects.iter().map(|(idx, obj)| (idx as u32 + SERVER_ID_LIMIT, obj));
 
         client_side_iter.chain(server_side_iter)
     }
+
+    /// Number of objects in the client namespace
+    pub fn client_objects_count(&self) -> usize {
+        self.client_count
+    }
+
+    /// Approximate memory used by the object tables, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.client_objects.memory_usage() + self.server_objects.memory_usage()
+    }
+}
+
+/// The objects of one side of the namespace
+///
+/// The free places are tracked so that allocating an id reuses the lowest one without scanning the
+/// whole store, which matters for connections with many objects.
+#[derive(Debug)]
+struct Store<Data> {
+    objects: Vec<Option<Object<Data>>>,
+    // indices of the empty places of `objects`
+    free: BTreeSet<usize>,
+    // number of ids of this side of the namespace
+    limit: usize,
 }
 
-// insert a new object in a store at the first free place
-fn insert_in<Data>(store: &mut Vec<Option<Object<Data>>>, object: Object<Data>) -> u32 {
-    match store.iter().position(Option::is_none) {
-        Some(id) => {
-            store[id] = Some(object);
-            id as u32
+impl<Data> Store<Data> {
+    fn new(limit: usize) -> Self {
+        Self { objects: Vec::new(), free: BTreeSet::new(), limit }
+    }
+
+    fn get(&self, idx: usize) -> Option<&Object<Data>> {
+        self.objects.get(idx).and_then(Option::as_ref)
+    }
+
+    fn get_mut(&mut self, idx: usize) -> Option<&mut Object<Data>> {
+        self.objects.get_mut(idx).and_then(Option::as_mut)
+    }
+
+    // returns whether there was an object at this place
+    fn remove(&mut self, idx: usize) -> bool {
+        match self.objects.get_mut(idx) {
+            Some(place) if place.is_some() => {
+                *place = None;
+                self.free.insert(idx);
+                true
+            }
+            _ => false,
         }
-        None => {
-            store.push(Some(object));
-            (store.len() - 1) as u32
+    }
+
+    // insert a new object at the first free place, if there is one left
+    fn insert_new(&mut self, object: Object<Data>) -> Option<usize> {
+        match self.free.iter().next().copied() {
+            Some(idx) => {
+                self.free.remove(&idx);
+                self.objects[idx] = Some(object);
+                Some(idx)
+            }
+            None if self.objects.len() < self.limit => {
+                self.objects.push(Some(object));
+                Some(self.objects.len() - 1)
+            }
+            None => None,
+        }
+    }
+
+    // insert an object at a given place
+    fn insert_at(&mut self, idx: usize, object: Object<Data>) -> Result<(), ()> {
+        match idx.cmp(&self.objects.len()) {
+            Ordering::Greater => Err(()),
+            Ordering::Equal if idx >= self.limit => Err(()),
+            Ordering::Equal => {
+                self.objects.push(Some(object));
+                Ok(())
+            }
+            Ordering::Less => {
+                if !self.free.remove(&idx) {
+                    return Err(());
+                }
+                self.objects[idx] = Some(object);
+                Ok(())
+            }
         }
     }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, &Object<Data>)> {
+        self.objects.iter().enumerate().flat_map(|(idx, obj)| obj.as_ref().map(|obj| (idx, obj)))
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.objects.capacity() * std::mem::size_of::<Option<Object<Data>>>()
+            + self.free.len() * std::mem::size_of::<usize>()
+    }
 }
 
-// insert an object at a given place in a store
-fn insert_in_at<Data>(
-    store: &mut Vec<Option<Object<Data>>>,
-    id: usize,
-    object: Object<Data>,
-) -> Result<(), ()> {
-    match id.cmp(&store.len()) {
-        Ordering::Greater => Err(()),
-        Ordering::Equal => {
-            store.push(Some(object));
-            Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object() -> Object<()> {
+        Object { interface: &crate::core_interfaces::WL_DISPLAY_INTERFACE, version: 1, data: () }
+    }
+
+    #[test]
+    fn reuse_lowest_free_id() {
+        let mut map = ObjectMap::new();
+        let ids = (0..5).map(|_| map.server_insert_new(object()).unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, (SERVER_ID_LIMIT..SERVER_ID_LIMIT + 5).collect::<Vec<_>>());
+        map.remove(ids[3]);
+        map.remove(ids[1]);
+        assert_eq!(map.server_insert_new(object()), Some(ids[1]));
+        assert_eq!(map.server_insert_new(object()), Some(ids[3]));
+        assert_eq!(map.server_insert_new(object()), Some(SERVER_ID_LIMIT + 5));
+
+        // the peer may only reuse a free id, or allocate the next one
+        assert!(map.insert_at(1, object()).is_ok());
+        assert!(map.insert_at(1, object()).is_err());
+        assert!(map.insert_at(3, object()).is_err());
+        assert!(map.insert_at(2, object()).is_ok());
+        map.remove(1);
+        assert!(map.insert_at(1, object()).is_ok());
+        assert_eq!(map.client_insert_new(object()), Some(3));
+        assert_eq!(map.client_objects_count(), 3);
+    }
+
+    #[test]
+    fn ids_stay_in_their_namespace() {
+        let mut map = ObjectMap::new();
+        assert!(map.client_insert_at(SERVER_ID_LIMIT, object()).is_err());
+        assert!(map.server_insert_at(1, object()).is_err());
+        assert!(map.client_insert_at(1, object()).is_ok());
+        assert!(map.server_insert_at(SERVER_ID_LIMIT, object()).is_ok());
+    }
+
+    #[test]
+    fn ids_exhaustion() {
+        let mut map = ObjectMap::with_limits(3, 2);
+        for id in 1..=3 {
+            assert_eq!(map.client_insert_new(object()), Some(id));
         }
-        Ordering::Less => {
-            let previous = &mut store[id];
-            if !previous.is_none() {
-                return Err(());
-            }
-            *previous = Some(object);
-            Ok(())
+        assert_eq!(map.client_insert_new(object()), None);
+        assert_eq!(map.client_objects_count(), 3);
+        assert_eq!(map.server_insert_new(object()), Some(SERVER_ID_LIMIT));
+        assert!(map.server_insert_at(SERVER_ID_LIMIT + 1, object()).is_ok());
+        assert_eq!(map.server_insert_new(object()), None);
+        assert!(map.server_insert_at(SERVER_ID_LIMIT + 2, object()).is_err());
+
+        // a freed id can be allocated again
+        map.remove(2);
+        assert!(map.client_insert_at(4, object()).is_err());
+        assert_eq!(map.client_insert_new(object()), Some(2));
+    }
+
+    #[test]
+    fn short_lived_objects_reuse_ids() {
+        let mut map = ObjectMap::with_limits(4, 4);
+        let long_lived = map.client_insert_new(object()).unwrap();
+        for _ in 0..3_000_000 {
+            let id = map.client_insert_new(object()).unwrap();
+            assert_eq!(id, long_lived + 1);
+            map.remove(id);
+            let id = map.server_insert_new(object()).unwrap();
+            assert_eq!(id, SERVER_ID_LIMIT);
+            map.remove(id);
         }
+        assert_eq!(map.client_objects_count(), 1);
     }
 }
@@ -4,8 +4,11 @@ mod client_impl;
 mod server_impl;
 
 mod map;
+mod pool;
 pub(crate) mod socket;
-mod wire;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+mod uring;
+pub(crate) mod wire;
 
 /// Client-side rust implementation of a Wayland protocol backend
 ///
@@ -0,0 +1,141 @@
+//! Recycling of the buffers used to decode the received messages
+
+use std::sync::Arc;
+
+use smallvec::SmallVec;
+
+use crate::{metrics::MetricsCounters, protocol::INLINE_ARGS};
+
+use super::wire::WireArgs;
+
+/// Maximum number of spare buffers of each kind kept by a pool
+const MAX_SPARE: usize = 4;
+
+/// A pool of the buffers allocated while decoding messages
+///
+/// It keeps the scratch buffers the raw messages are copied in, and the argument lists that do not
+/// fit inline, so that dispatching messages does not allocate once the pool is warm.
+#[derive(Debug)]
+pub(crate) struct MessagePool {
+    scratch: Vec<Vec<u8>>,
+    // the arguments of these empty lists never outlive them, see `relabel()`
+    args: Vec<WireArgs<'static>>,
+    metrics: Arc<MetricsCounters>,
+}
+
+impl MessagePool {
+    pub(crate) fn new(metrics: Arc<MetricsCounters>) -> Self {
+        Self { scratch: Vec::new(), args: Vec::new(), metrics }
+    }
+
+    /// Take a scratch buffer to copy the raw messages in
+    pub(crate) fn take_scratch(&mut self) -> Vec<u8> {
+        match self.scratch.pop() {
+            Some(scratch) => {
+                self.metrics.pool_reuse();
+                scratch
+            }
+            None => {
+                self.metrics.pool_allocation();
+                Vec::new()
+            }
+        }
+    }
+
+    /// Give back a scratch buffer once the messages borrowing it are dropped
+    pub(crate) fn recycle_scratch(&mut self, mut scratch: Vec<u8>) {
+        if self.scratch.len() < MAX_SPARE {
+            scratch.clear();
+            self.scratch.push(scratch);
+        }
+    }
+
+    /// Take an argument list able to hold `len` arguments
+    pub(crate) fn take_args<'a>(&mut self, len: usize) -> WireArgs<'a> {
+        if len <= INLINE_ARGS {
+            // no allocation needed
+            return SmallVec::new();
+        }
+        match self.args.pop() {
+            Some(args) => {
+                self.metrics.pool_reuse();
+                let mut args = relabel(args);
+                args.reserve(len);
+                args
+            }
+            None => {
+                self.metrics.pool_allocation();
+                SmallVec::with_capacity(len)
+            }
+        }
+    }
+
+    /// Give back an argument list, dropping its remaining arguments
+    pub(crate) fn recycle_args(&mut self, mut args: WireArgs<'_>) {
+        args.clear();
+        if args.spilled() && self.args.len() < MAX_SPARE {
+            self.args.push(relabel(args));
+        }
+    }
+}
+
+/// Change the lifetime of an empty argument list, keeping its allocation
+fn relabel<'a, 'b>(mut args: WireArgs<'a>) -> WireArgs<'b> {
+    debug_assert!(args.is_empty());
+    if !args.spilled() {
+        return SmallVec::new();
+    }
+    let capacity = args.capacity();
+    let ptr = args.as_mut_ptr();
+    std::mem::forget(args);
+    // SAFETY: the allocation is handed over from a spilled SmallVec of the same capacity, and the
+    // lifetime does not change the layout of its items. The list is empty so that none of them can
+    // outlive what it borrows.
+    unsafe { SmallVec::from_raw_parts(ptr.cast(), 0, capacity) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::BorrowedArgument;
+
+    #[test]
+    fn recycle_args() {
+        let mut pool = MessagePool::new(Arc::default());
+
+        // short lists are inline and not pooled
+        let args = pool.take_args(INLINE_ARGS);
+        assert!(!args.spilled());
+        pool.recycle_args(args);
+        assert!(pool.args.is_empty());
+
+        let bytes = b"hello\0".to_vec();
+        let mut args = pool.take_args(INLINE_ARGS + 2);
+        let ptr = args.as_ptr().cast::<u8>();
+        for _ in 0..INLINE_ARGS + 2 {
+            args.push(BorrowedArgument::Str(Some(
+                std::ffi::CStr::from_bytes_with_nul(&bytes).unwrap(),
+            )));
+        }
+        pool.recycle_args(args);
+        std::mem::drop(bytes);
+
+        // the same allocation is handed out again
+        let args = pool.take_args(INLINE_ARGS + 1);
+        assert!(args.is_empty());
+        assert_eq!(args.as_ptr().cast::<u8>(), ptr);
+    }
+
+    #[test]
+    fn recycle_scratch() {
+        let mut pool = MessagePool::new(Arc::default());
+        let mut scratch = pool.take_scratch();
+        scratch.extend_from_slice(&[1, 2, 3]);
+        let ptr = scratch.as_ptr();
+        pool.recycle_scratch(scratch);
+
+        let scratch = pool.take_scratch();
+        assert!(scratch.is_empty());
+        assert_eq!(scratch.as_ptr(), ptr);
+    }
+}
@@ -1,24 +1,29 @@
 use std::{
     ffi::CString,
     os::unix::io::{AsFd, BorrowedFd, OwnedFd},
-    os::unix::{io::RawFd, net::UnixStream},
-    sync::Arc,
+    os::unix::net::UnixStream,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Instant,
 };
 
 use crate::{
     core_interfaces::{WL_CALLBACK_INTERFACE, WL_DISPLAY_INTERFACE, WL_REGISTRY_INTERFACE},
-    debug,
+    debug::{self, DebugOutput},
+    metrics::MetricsCounters,
     protocol::{
-        check_for_signature, same_interface, same_interface_or_anonymous, AllowNull, Argument,
-        ArgumentType, Interface, Message, ObjectInfo, ProtocolError, ANONYMOUS_INTERFACE,
-        INLINE_ARGS,
+        can_veto, check_for_signature, same_interface, same_interface_or_anonymous, AllowNull,
+        Argument, ArgumentType, BorrowedArgument, BorrowedMessage, Direction, Interface, Message,
+        MessageDesc, ObjectInfo, ProtocolError, WireError, ANONYMOUS_INTERFACE, INLINE_ARGS,
     },
     rs::map::SERVER_ID_LIMIT,
-    types::server::{DisconnectReason, InvalidId},
+    types::client::BufferSizes,
+    types::server::{DisconnectReason, InvalidId, ResourceUsage},
 };
 
 use smallvec::SmallVec;
 
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use crate::rs::uring::{Batch, Ring};
 use crate::rs::{
     map::{Object, ObjectMap},
     socket::{BufferedSocket, Socket},
@@ -27,11 +32,11 @@ use crate::rs::{
 
 use super::{
     handle::PendingDestructor, registry::Registry, ClientData, ClientId, Credentials, Data,
-    DumbObjectData, GlobalHandler, InnerClientId, InnerGlobalId, InnerObjectId, ObjectData,
-    ObjectId, UninitObjectData,
+    DisconnectCallback, DumbObjectData, GlobalHandler, InnerClientId, InnerGlobalId, InnerObjectId,
+    Interceptor, ObjectData, ObjectId, ObjectLimitCallback, UninitObjectData,
 };
 
-type ArgSmallVec<Fd> = SmallVec<[Argument<ObjectId, Fd>; INLINE_ARGS]>;
+type ArgSmallVec<'a, Fd> = SmallVec<[BorrowedArgument<'a, ObjectId, Fd>; INLINE_ARGS]>;
 
 #[repr(u32)]
 #[allow(dead_code)]
@@ -42,15 +47,39 @@ pub(crate) enum DisplayError {
     Implementation = 3,
 }
 
+// The limit on the number of objects a client may create
+#[derive(Clone, Default)]
+pub(crate) struct ObjectLimit {
+    pub(crate) max_objects: Option<usize>,
+    pub(crate) callback: Option<ObjectLimitCallback>,
+}
+
+impl std::fmt::Debug for ObjectLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ObjectLimit")
+            .field("max_objects", &self.max_objects)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Client<D: 'static> {
     socket: BufferedSocket,
-    pub(crate) map: ObjectMap<Data<D>>,
-    debug: bool,
+    // shared with the handle, which reads it without locking the state of the backend
+    pub(crate) map: Arc<RwLock<ObjectMap<Data<D>>>>,
+    pub(crate) debug: Option<DebugOutput>,
+    pub(crate) object_limit: ObjectLimit,
     last_serial: u32,
     pub(crate) id: InnerClientId,
     pub(crate) killed: bool,
     pub(crate) data: Arc<dyn ClientData>,
+    // retrieved on connection, as the client may have exited when they are requested, None if the
+    // platform does not support it
+    credentials: Option<Credentials>,
+    // time of connection, reset to None once the client has sent its first request
+    pub(crate) awaiting_handshake: Option<Instant>,
+    pub(crate) label: Option<String>,
+    pub(crate) disconnect_callbacks: Vec<DisconnectCallback>,
 }
 
 impl<D> Client<D> {
@@ -64,12 +93,15 @@ impl<D> Client<D> {
     pub(crate) fn new(
         stream: UnixStream,
         id: InnerClientId,
-        debug: bool,
+        debug: Option<DebugOutput>,
         data: Arc<dyn ClientData>,
+        credentials: Option<Credentials>,
+        metrics: Arc<MetricsCounters>,
     ) -> Self {
-        let socket = BufferedSocket::new(Socket::from(stream));
+        let socket =
+            BufferedSocket::with_metrics(Socket::from(stream), BufferSizes::default(), metrics);
         let mut map = ObjectMap::new();
-        map.insert_at(
+        map.client_insert_at(
             1,
             Object {
                 interface: &WL_DISPLAY_INTERFACE,
@@ -81,7 +113,20 @@ impl<D> Client<D> {
 
         data.initialized(ClientId { id: id.clone() });
 
-        Self { socket, map, debug, id, killed: false, last_serial: 0, data }
+        Self {
+            socket,
+            map: Arc::new(RwLock::new(map)),
+            debug,
+            object_limit: ObjectLimit::default(),
+            id,
+            killed: false,
+            last_serial: 0,
+            data,
+            credentials,
+            awaiting_handshake: Some(Instant::now()),
+            label: None,
+            disconnect_callbacks: Vec::new(),
+        }
     }
 
     pub(crate) fn create_object(
@@ -89,14 +134,24 @@ impl<D> Client<D> {
         interface: &'static Interface,
         version: u32,
         user_data: Arc<dyn ObjectData<D>>,
-    ) -> InnerObjectId {
+    ) -> Result<InnerObjectId, InvalidId> {
         let serial = self.next_serial();
-        let id = self.map.server_insert_new(Object {
+        let id = self.objects_mut().server_insert_new(Object {
             interface,
             version,
             data: Data { serial, user_data },
         });
-        InnerObjectId { id, serial, client_id: self.id.clone(), interface }
+        match id {
+            Some(id) => Ok(InnerObjectId { id, serial, client_id: self.id.clone(), interface }),
+            None => {
+                // the server namespace of this client is full, kill it as on an allocation failure
+                self.post_display_error(
+                    DisplayError::NoMemory,
+                    CString::new("No more object ids available for the server.").unwrap(),
+                );
+                Err(InvalidId)
+            }
+        }
     }
 
     pub(crate) fn object_info(&self, id: InnerObjectId) -> Result<ObjectInfo, InvalidId> {
@@ -104,9 +159,25 @@ impl<D> Client<D> {
         Ok(ObjectInfo { id: id.id, interface: object.interface, version: object.version })
     }
 
+    // Returns whether the event should be sent, according to the interceptor
+    pub(crate) fn intercept_event(
+        &self,
+        interceptor: &dyn Interceptor,
+        msg: &Message<ObjectId, BorrowedFd<'_>>,
+    ) -> Result<bool, InvalidId> {
+        if self.killed {
+            return Ok(true);
+        }
+        let object = self.get_object(msg.sender_id.id.clone())?;
+        let seq = self.socket.next_sequence_out();
+        let accepted = interceptor.event_sequenced(ClientId { id: self.id.clone() }, seq, msg);
+        // unknown opcodes are left for `send_event()` to report
+        Ok(accepted || !object.interface.events.get(msg.opcode as usize).map_or(false, can_veto))
+    }
+
     pub(crate) fn send_event(
         &mut self,
-        Message { sender_id: object_id, opcode, args }: Message<ObjectId, RawFd>,
+        Message { sender_id: object_id, opcode, args }: Message<ObjectId, BorrowedFd<'_>>,
         pending_destructors: Option<&mut Vec<super::handle::PendingDestructor<D>>>,
     ) -> Result<(), InvalidId> {
         if self.killed {
@@ -135,13 +206,15 @@ impl<D> Client<D> {
             );
         }
 
-        if self.debug {
+        if let Some(ref output) = self.debug {
             debug::print_send_message(
+                output,
+                Direction::Event,
                 object.interface.name,
                 object_id.id.id,
                 message_desc.name,
                 &args,
-                false,
+                Some(&self.debug_label()),
             );
         }
 
@@ -193,13 +266,13 @@ impl<D> Client<D> {
 
         let msg = Message { sender_id: object_id.id.id, opcode, args: msg_args };
 
-        if self.socket.write_message(&msg).is_err() {
-            self.kill(DisconnectReason::ConnectionClosed);
+        if let Err(e) = self.socket.write_message(&msg) {
+            self.kill(DisconnectReason::SendFailed(e));
         }
 
         // Handle destruction if relevant
         if message_desc.is_destructor {
-            self.map.remove(object_id.id.id);
+            self.objects_mut().remove(object_id.id.id);
             if let Some(vec) = pending_destructors {
                 vec.push((object.data.user_data.clone(), self.id.clone(), object_id.id.clone()));
             }
@@ -213,49 +286,53 @@ impl<D> Client<D> {
         // We should only send delete_id for objects in the client ID space
         if object_id.id < SERVER_ID_LIMIT {
             let msg = message!(1, 1, [Argument::Uint(object_id.id)]);
-            if self.socket.write_message(&msg).is_err() {
-                self.kill(DisconnectReason::ConnectionClosed);
+            if let Err(e) = self.socket.write_message(&msg) {
+                self.kill(DisconnectReason::SendFailed(e));
             }
         }
-        self.map.remove(object_id.id);
+        self.objects_mut().remove(object_id.id);
     }
 
-    pub(crate) fn get_object_data(
-        &self,
-        id: InnerObjectId,
-    ) -> Result<Arc<dyn ObjectData<D>>, InvalidId> {
-        let object = self.get_object(id)?;
-        Ok(object.data.user_data)
-    }
-
-    pub(crate) fn set_object_data(
+    pub(crate) fn replace_object_data(
         &mut self,
         id: InnerObjectId,
         data: Arc<dyn ObjectData<D>>,
-    ) -> Result<(), InvalidId> {
-        self.map
+    ) -> Result<Arc<dyn ObjectData<D>>, InvalidId> {
+        self.objects_mut()
             .with(id.id, |objdata| {
                 if objdata.data.serial != id.serial {
                     Err(InvalidId)
                 } else {
-                    objdata.data.user_data = data;
-                    Ok(())
+                    Ok(std::mem::replace(&mut objdata.data.user_data, data))
                 }
             })
             .unwrap_or(Err(InvalidId))
     }
 
+    // Check whether the client may create a new object, killing it with a no_memory error if not
+    fn check_object_limit(&mut self) -> bool {
+        let Some(max_objects) = self.object_limit.max_objects else {
+            return true;
+        };
+        let count = self.objects().client_objects_count();
+        if count < max_objects {
+            return true;
+        }
+        if let Some(ref callback) = self.object_limit.callback {
+            if callback(ClientId { id: self.id.clone() }, count) {
+                return true;
+            }
+        }
+        self.post_display_error(
+            DisplayError::NoMemory,
+            CString::new(format!("Too many objects, the limit is {}.", max_objects)).unwrap(),
+        );
+        false
+    }
+
     pub(crate) fn post_display_error(&mut self, code: DisplayError, message: CString) {
-        self.post_error(
-            InnerObjectId {
-                id: 1,
-                interface: &WL_DISPLAY_INTERFACE,
-                client_id: self.id.clone(),
-                serial: 0,
-            },
-            code as u32,
-            message,
-        )
+        crate::log_warn!("[wayland-backend] Killing client: {}", message.to_string_lossy());
+        self.post_error(self.display_id(), code as u32, message)
     }
 
     pub(crate) fn post_error(
@@ -265,20 +342,91 @@ impl<D> Client<D> {
         message: CString,
     ) {
         let converted_message = message.to_string_lossy().into();
+        self.send_error(object_id.clone(), error_code, message);
+        self.kill(DisconnectReason::ProtocolError(ProtocolError {
+            code: error_code,
+            object_id: object_id.id,
+            object_interface: object_id.interface.name.into(),
+            message: converted_message,
+        }));
+    }
+
+    // Kill the client for sending a malformed message, telling it what is wrong with it
+    fn reject_message(&mut self, err: WireError) {
+        let (code, message) = self.describe_wire_error(&err);
+        crate::log_warn!("[wayland-backend] Killing client: {}", message);
+        self.send_error(self.display_id(), code as u32, CString::new(message).unwrap());
+        self.kill(DisconnectReason::InvalidMessage(err));
+    }
+
+    fn describe_wire_error(&self, err: &WireError) -> (DisplayError, String) {
+        let object_id = err.object_id();
+        let object = self.objects().find(object_id);
+        let Some(object) = object else {
+            return (DisplayError::InvalidObject, format!("Unknown object {}.", object_id));
+        };
+        let Some(desc) = object.interface.requests.get(err.opcode() as usize) else {
+            return (
+                DisplayError::InvalidMethod,
+                format!(
+                    "Unknown opcode {} for {}@{}.",
+                    err.opcode(),
+                    object.interface.name,
+                    object_id
+                ),
+            );
+        };
+        (
+            DisplayError::InvalidMethod,
+            format!(
+                "Invalid arguments for {}({}): {}.",
+                request_name(object.interface, object_id, desc),
+                describe_signature(desc.signature),
+                err.reason()
+            ),
+        )
+    }
+
+    fn display_id(&self) -> InnerObjectId {
+        InnerObjectId {
+            id: 1,
+            interface: &WL_DISPLAY_INTERFACE,
+            client_id: self.id.clone(),
+            serial: 0,
+        }
+    }
+
+    fn post_invalid_new_id(
+        &mut self,
+        interface: &'static Interface,
+        object_id: u32,
+        opcode: u16,
+        arg: usize,
+        new_id: u32,
+    ) {
+        let desc = &interface.requests[opcode as usize];
+        self.post_display_error(
+            DisplayError::InvalidObject,
+            CString::new(format!(
+                "Invalid arguments for {}: argument {} creates a {} with the invalid id {}.",
+                request_name(interface, object_id, desc),
+                arg,
+                desc.child_interface.unwrap_or(&ANONYMOUS_INTERFACE).name,
+                new_id
+            ))
+            .unwrap(),
+        );
+    }
+
+    // Send a wl_display.error event, before the client is killed
+    fn send_error(&mut self, object_id: InnerObjectId, error_code: u32, message: CString) {
         // errors are ignored, as the client will be killed anyway
         let _ = self.send_event(
             message!(
-                ObjectId {
-                    id: InnerObjectId {
-                        id: 1,
-                        interface: &WL_DISPLAY_INTERFACE,
-                        client_id: self.id.clone(),
-                        serial: 0
-                    }
-                },
+                ObjectId { id: self.display_id() },
                 0, // wl_display.error
                 [
-                    Argument::Object(ObjectId { id: object_id.clone() }),
+                    Argument::Object(ObjectId { id: object_id }),
                     Argument::Uint(error_code),
                     Argument::Str(Some(Box::new(message))),
                 ],
@@ -287,64 +435,92 @@ impl<D> Client<D> {
             None,
         );
         let _ = self.flush();
-        self.kill(DisconnectReason::ProtocolError(ProtocolError {
-            code: error_code,
-            object_id: object_id.id,
-            object_interface: object_id.interface.name.into(),
-            message: converted_message,
-        }));
     }
 
-    #[cfg(any(target_os = "linux", target_os = "android"))]
-    pub(crate) fn get_credentials(&self) -> Credentials {
-        let creds =
-            rustix::net::sockopt::get_socket_peercred(&self.socket).expect("getsockopt failed!?");
-        let pid = rustix::process::Pid::as_raw(Some(creds.pid));
-        Credentials { pid, uid: creds.uid.as_raw(), gid: creds.gid.as_raw() }
+    pub(crate) fn get_credentials(&self) -> Option<Credentials> {
+        self.credentials
     }
 
-    #[cfg(not(any(target_os = "linux", target_os = "android")))]
-    // for now this only works on linux
-    pub(crate) fn get_credentials(&self) -> Credentials {
-        Credentials { pid: 0, uid: 0, gid: 0 }
+    pub(crate) fn resource_usage(&self) -> ResourceUsage {
+        let (buffered_bytes, buffered_fds) = self.socket.buffered();
+        let objects = self.objects();
+        ResourceUsage {
+            objects: objects.all_objects().count(),
+            buffered_bytes,
+            pending_output_bytes: self.socket.pending_output_bytes(),
+            buffered_fds,
+            memory: std::mem::size_of::<Self>()
+                + objects.memory_usage()
+                + self.socket.memory_usage(),
+        }
     }
 
     pub(crate) fn kill(&mut self, reason: DisconnectReason) {
         self.killed = true;
         self.data.disconnected(ClientId { id: self.id.clone() }, reason);
+        for DisconnectCallback(cb) in std::mem::take(&mut self.disconnect_callbacks) {
+            cb(ClientId { id: self.id.clone() });
+        }
     }
 
     pub(crate) fn flush(&mut self) -> std::io::Result<()> {
         self.socket.flush()
     }
 
-    pub(crate) fn all_objects(&self) -> impl Iterator<Item = ObjectId> + '_ {
-        let client_id = self.id.clone();
-        self.map.all_objects().map(move |(id, obj)| ObjectId {
-            id: InnerObjectId {
-                id,
-                client_id: client_id.clone(),
-                interface: obj.interface,
-                serial: obj.data.serial,
-            },
-        })
+    /// Take a scratch buffer from the pool of the socket to read the requests in
+    pub(crate) fn take_scratch(&mut self) -> Vec<u8> {
+        self.socket.pool.take_scratch()
+    }
+
+    pub(crate) fn recycle_scratch(&mut self, scratch: Vec<u8>) {
+        self.socket.pool.recycle_scratch(scratch);
     }
 
+    pub(crate) fn has_pending_output(&self) -> bool {
+        self.socket.has_pending_output()
+    }
+
+    pub(crate) fn all_objects(&self) -> Vec<ObjectId> {
+        self.objects()
+            .all_objects()
+            .map(|(id, obj)| ObjectId {
+                id: InnerObjectId {
+                    id,
+                    client_id: self.id.clone(),
+                    interface: obj.interface,
+                    serial: obj.data.serial,
+                },
+            })
+            .collect()
+    }
+
+    /// The sequence number of the last request read from the client
+    pub(crate) fn last_request_sequence(&self) -> u64 {
+        self.socket.last_sequence_in()
+    }
+
+    /// Read the next request of this client
+    ///
+    /// The string and array arguments of the returned message borrow `scratch`.
     #[allow(clippy::type_complexity)]
-    pub(crate) fn next_request(
+    pub(crate) fn next_request<'s>(
         &mut self,
-    ) -> std::io::Result<(Message<u32, OwnedFd>, Object<Data<D>>)> {
+        scratch: &'s mut Vec<u8>,
+    ) -> std::io::Result<(BorrowedMessage<'s, u32, OwnedFd>, Object<Data<D>>)> {
         if self.killed {
             return Err(rustix::io::Errno::PIPE.into());
         }
+        let map = &self.map;
+        let signature = |id, opcode| {
+            map.read()
+                .unwrap()
+                .find(id)
+                .and_then(|o: Object<Data<D>>| o.interface.requests.get(opcode as usize))
+                .map(|desc| desc.signature)
+        };
         loop {
-            let map = &self.map;
-            let msg = match self.socket.read_one_message(|id, opcode| {
-                map.find(id)
-                    .and_then(|o| o.interface.requests.get(opcode as usize))
-                    .map(|desc| desc.signature)
-            }) {
-                Ok(msg) => msg,
+            match self.socket.peek_message(signature) {
+                Ok(_) => break,
                 Err(MessageParseError::MissingData) | Err(MessageParseError::MissingFD) => {
                     // need to read more data
                     if let Err(e) = self.socket.fill_incoming_buffers() {
@@ -353,39 +529,68 @@ impl<D> Client<D> {
                         }
                         return Err(e);
                     }
-                    continue;
                 }
-                Err(MessageParseError::Malformed) => {
-                    self.kill(DisconnectReason::ConnectionClosed);
+                Err(MessageParseError::Malformed(e)) => {
+                    self.reject_message(e);
                     return Err(rustix::io::Errno::PROTO.into());
                 }
-            };
+            }
+        }
+        let msg = match self.socket.read_one_message(signature, scratch) {
+            Ok(msg) => msg,
+            Err(e) => {
+                match e {
+                    MessageParseError::Malformed(e) => self.reject_message(e),
+                    _ => self.kill(DisconnectReason::ConnectionClosed),
+                }
+                return Err(rustix::io::Errno::PROTO.into());
+            }
+        };
 
-            let obj = self.map.find(msg.sender_id).unwrap();
+        let obj = self.objects().find(msg.sender_id).unwrap();
+        self.awaiting_handshake = None;
 
-            if self.debug {
-                debug::print_dispatched_message(
-                    obj.interface.name,
-                    msg.sender_id,
-                    obj.interface.requests.get(msg.opcode as usize).unwrap().name,
-                    &msg.args,
-                );
-            }
+        if let Some(ref output) = self.debug {
+            let desc = obj.interface.requests.get(msg.opcode as usize).unwrap();
+            debug::print_dispatched_message(
+                output,
+                Direction::Request,
+                obj.interface.name,
+                msg.sender_id,
+                desc.name,
+                &debug::resolve_ids(&msg.args, desc, |id| {
+                    map.read().unwrap().find(id).map(|o| o.interface)
+                }),
+                Some(&self.debug_label()),
+            );
+        }
+
+        Ok((msg, obj))
+    }
 
-            return Ok((msg, obj));
+    // identifies the client in the debug output, by its label or its id
+    fn debug_label(&self) -> std::borrow::Cow<'_, str> {
+        match self.label {
+            Some(ref label) => label.into(),
+            None => format!("client {}", self.id.id).into(),
         }
     }
 
     fn get_object(&self, id: InnerObjectId) -> Result<Object<Data<D>>, InvalidId> {
-        let object = self.map.find(id.id).ok_or(InvalidId)?;
-        if object.data.serial != id.serial {
-            return Err(InvalidId);
-        }
-        Ok(object)
+        find_object(&self.objects(), &id)
+    }
+
+    pub(crate) fn objects(&self) -> RwLockReadGuard<'_, ObjectMap<Data<D>>> {
+        self.map.read().unwrap()
+    }
+
+    // the guard must not be held while invoking the callbacks or the interceptor, which may read the map
+    pub(crate) fn objects_mut(&self) -> RwLockWriteGuard<'_, ObjectMap<Data<D>>> {
+        self.map.write().unwrap()
     }
 
     pub(crate) fn object_for_protocol_id(&self, pid: u32) -> Result<InnerObjectId, InvalidId> {
-        let object = self.map.find(pid).ok_or(InvalidId)?;
+        let object = self.objects().find(pid).ok_or(InvalidId)?;
         Ok(InnerObjectId {
             id: pid,
             client_id: self.id.clone(),
@@ -395,7 +600,7 @@ impl<D> Client<D> {
     }
 
     fn queue_all_destructors(&mut self, pending_destructors: &mut Vec<PendingDestructor<D>>) {
-        pending_destructors.extend(self.map.all_objects().map(|(id, obj)| {
+        pending_destructors.extend(self.objects().all_objects().map(|(id, obj)| {
             (
                 obj.data.user_data.clone(),
                 self.id.clone(),
@@ -411,23 +616,27 @@ impl<D> Client<D> {
 
     pub(crate) fn handle_display_request(
         &mut self,
-        message: Message<u32, OwnedFd>,
+        message: BorrowedMessage<'_, u32, OwnedFd>,
         registry: &mut Registry<D>,
     ) {
         match message.opcode {
             // wl_display.sync(new id wl_callback)
             0 => {
-                if let [Argument::NewId(new_id)] = message.args[..] {
+                if let [BorrowedArgument::NewId(new_id)] = message.args[..] {
                     let serial = self.next_serial();
                     let callback_obj = Object {
                         interface: &WL_CALLBACK_INTERFACE,
                         version: 1,
                         data: Data { user_data: Arc::new(DumbObjectData), serial },
                     };
-                    if let Err(()) = self.map.insert_at(new_id, callback_obj) {
-                        self.post_display_error(
-                            DisplayError::InvalidObject,
-                            CString::new(format!("Invalid new_id: {}.", new_id)).unwrap(),
+                    let inserted = self.objects_mut().client_insert_at(new_id, callback_obj);
+                    if let Err(()) = inserted {
+                        self.post_invalid_new_id(
+                            &WL_DISPLAY_INTERFACE,
+                            message.sender_id,
+                            message.opcode,
+                            0,
+                            new_id,
                         );
                         return;
                     }
@@ -447,7 +656,7 @@ impl<D> Client<D> {
             }
             // wl_display.get_registry(new id wl_registry)
             1 => {
-                if let [Argument::NewId(new_id)] = message.args[..] {
+                if let [BorrowedArgument::NewId(new_id)] = message.args[..] {
                     let serial = self.next_serial();
                     let registry_obj = Object {
                         interface: &WL_REGISTRY_INTERFACE,
@@ -460,10 +669,17 @@ impl<D> Client<D> {
                         client_id: self.id.clone(),
                         interface: &WL_REGISTRY_INTERFACE,
                     };
-                    if let Err(()) = self.map.insert_at(new_id, registry_obj) {
-                        self.post_display_error(
-                            DisplayError::InvalidObject,
-                            CString::new(format!("Invalid new_id: {}.", new_id)).unwrap(),
+                    if !self.check_object_limit() {
+                        return;
+                    }
+                    let inserted = self.objects_mut().client_insert_at(new_id, registry_obj);
+                    if let Err(()) = inserted {
+                        self.post_invalid_new_id(
+                            &WL_DISPLAY_INTERFACE,
+                            message.sender_id,
+                            message.opcode,
+                            0,
+                            new_id,
                         );
                         return;
                     }
@@ -489,13 +705,13 @@ impl<D> Client<D> {
     #[allow(clippy::type_complexity)]
     pub(crate) fn handle_registry_request(
         &mut self,
-        message: Message<u32, OwnedFd>,
+        message: BorrowedMessage<'_, u32, OwnedFd>,
         registry: &mut Registry<D>,
     ) -> Option<(InnerClientId, InnerGlobalId, InnerObjectId, Arc<dyn GlobalHandler<D>>)> {
         match message.opcode {
             // wl_registry.bind(uint name, str interface, uint version, new id)
             0 => {
-                if let [Argument::Uint(name), Argument::Str(Some(ref interface_name)), Argument::Uint(version), Argument::NewId(new_id)] =
+                if let [BorrowedArgument::Uint(name), BorrowedArgument::Str(Some(interface_name)), BorrowedArgument::Uint(version), BorrowedArgument::NewId(new_id)] =
                     message.args[..]
                 {
                     if let Some((interface, global_id, handler)) =
@@ -507,10 +723,17 @@ impl<D> Client<D> {
                             version,
                             data: Data { serial, user_data: Arc::new(UninitObjectData) },
                         };
-                        if let Err(()) = self.map.insert_at(new_id, object) {
-                            self.post_display_error(
-                                DisplayError::InvalidObject,
-                                CString::new(format!("Invalid new_id: {}.", new_id)).unwrap(),
+                        if !self.check_object_limit() {
+                            return None;
+                        }
+                        let inserted = self.objects_mut().client_insert_at(new_id, object);
+                        if let Err(()) = inserted {
+                            self.post_invalid_new_id(
+                                &WL_REGISTRY_INTERFACE,
+                                message.sender_id,
+                                message.opcode,
+                                3,
+                                new_id,
                             );
                             return None;
                         }
@@ -557,34 +780,39 @@ impl<D> Client<D> {
         }
     }
 
-    pub(crate) fn process_request(
+    pub(crate) fn process_request<'s>(
         &mut self,
         object: &Object<Data<D>>,
-        message: Message<u32, OwnedFd>,
-    ) -> Option<(ArgSmallVec<OwnedFd>, bool, Option<InnerObjectId>)> {
+        mut message: BorrowedMessage<'s, u32, OwnedFd>,
+    ) -> Option<(ArgSmallVec<'s, OwnedFd>, bool, Option<InnerObjectId>)> {
         let message_desc = object.interface.requests.get(message.opcode as usize).unwrap();
+        let request = request_name(object.interface, message.sender_id, message_desc);
         // Convert the arguments and create the new object if applicable
         let mut new_args = SmallVec::with_capacity(message.args.len());
         let mut arg_interfaces = message_desc.arg_interfaces.iter();
         let mut created_id = None;
-        for (i, arg) in message.args.into_iter().enumerate() {
+        for (i, arg) in message.args.drain(..).enumerate() {
             new_args.push(match arg {
-                Argument::Array(a) => Argument::Array(a),
-                Argument::Int(i) => Argument::Int(i),
-                Argument::Uint(u) => Argument::Uint(u),
-                Argument::Str(s) => Argument::Str(s),
-                Argument::Fixed(f) => Argument::Fixed(f),
-                Argument::Fd(f) => Argument::Fd(f),
-                Argument::Object(o) => {
+                BorrowedArgument::Array(a) => BorrowedArgument::Array(a),
+                BorrowedArgument::Int(i) => BorrowedArgument::Int(i),
+                BorrowedArgument::Uint(u) => BorrowedArgument::Uint(u),
+                BorrowedArgument::Str(s) => BorrowedArgument::Str(s),
+                BorrowedArgument::Fixed(f) => BorrowedArgument::Fixed(f),
+                BorrowedArgument::Fd(f) => BorrowedArgument::Fd(f),
+                BorrowedArgument::Object(o) => {
                     let next_interface = arg_interfaces.next();
                     if o != 0 {
                         // Lookup the object to make the appropriate Id
-                        let obj = match self.map.find(o) {
+                        let obj = self.objects().find(o);
+                        let obj = match obj {
                             Some(o) => o,
                             None => {
                                 self.post_display_error(
                                     DisplayError::InvalidObject,
-                                    CString::new(format!("Unknown id: {}.", o)).unwrap()
+                                    CString::new(format!(
+                                        "Invalid arguments for {}: argument {} is the unknown object {}.",
+                                        request, i, o
+                                    )).unwrap()
                                 );
                                 return None;
                             }
@@ -594,34 +822,38 @@ impl<D> Client<D> {
                                 self.post_display_error(
                                     DisplayError::InvalidObject,
                                     CString::new(format!(
-                                        "Invalid object {} in request {}.{}: expected {} but got {}.",
-                                        o,
-                                        object.interface.name,
-                                        message_desc.name,
+                                        "Invalid arguments for {}: argument {} must be a {}, but {} is a {}.",
+                                        request,
+                                        i,
                                         next_interface.name,
+                                        o,
                                         obj.interface.name,
                                     )).unwrap()
                                 );
                                 return None;
                             }
                         }
-                        Argument::Object(ObjectId { id: InnerObjectId { id: o, client_id: self.id.clone(), serial: obj.data.serial, interface: obj.interface }})
+                        BorrowedArgument::Object(ObjectId { id: InnerObjectId { id: o, client_id: self.id.clone(), serial: obj.data.serial, interface: obj.interface }})
                     } else if matches!(message_desc.signature[i], ArgumentType::Object(AllowNull::Yes)) {
-                        Argument::Object(ObjectId { id: InnerObjectId { id: 0, client_id: self.id.clone(), serial: 0, interface: &ANONYMOUS_INTERFACE }})
+                        BorrowedArgument::Object(ObjectId { id: InnerObjectId { id: 0, client_id: self.id.clone(), serial: 0, interface: &ANONYMOUS_INTERFACE }})
                     } else {
                         self.post_display_error(
                             DisplayError::InvalidObject,
                             CString::new(format!(
-                                "Invalid null object in request {}.{}.",
-                                object.interface.name,
-                                message_desc.name,
+                                "Invalid arguments for {}: argument {} must be a {}, but is null.",
+                                request,
+                                i,
+                                next_interface.map_or("object", |iface| iface.name),
                             )).unwrap()
                         );
                         return None;
                     }
                 }
-                Argument::NewId(new_id) => {
+                BorrowedArgument::NewId(new_id) => {
                     // An object should be created
+                    if !self.check_object_limit() {
+                        return None;
+                    }
                     let child_interface = match message_desc.child_interface {
                         Some(iface) => iface,
                         None => panic!("Received request {}@{}.{} which creates an object without specifying its interface, this is unsupported.", object.interface.name, message.sender_id, message_desc.name),
@@ -641,19 +873,18 @@ impl<D> Client<D> {
                     let child_id = InnerObjectId { id: new_id, client_id: self.id.clone(), serial: child_obj.data.serial, interface: child_obj.interface };
                     created_id = Some(child_id.clone());
 
-                    if let Err(()) = self.map.insert_at(new_id, child_obj) {
+                    let inserted = self.objects_mut().client_insert_at(new_id, child_obj);
+                    if let Err(()) = inserted {
                         // abort parsing, this is an unrecoverable error
-                        self.post_display_error(
-                            DisplayError::InvalidObject,
-                            CString::new(format!("Invalid new_id: {}.", new_id)).unwrap()
-                        );
+                        self.post_invalid_new_id(object.interface, message.sender_id, message.opcode, i, new_id);
                         return None;
                     }
 
-                    Argument::NewId(ObjectId { id: child_id })
+                    BorrowedArgument::NewId(ObjectId { id: child_id })
                 }
             });
         }
+        self.socket.pool.recycle_args(message.args);
         Some((new_args, message_desc.is_destructor, created_id))
     }
 }
@@ -664,22 +895,143 @@ impl<D> AsFd for Client<D> {
     }
 }
 
+// Find an object in a map, checking that the id does not refer to a previous object with the same number
+pub(crate) fn find_object<D>(
+    map: &ObjectMap<Data<D>>,
+    id: &InnerObjectId,
+) -> Result<Object<Data<D>>, InvalidId> {
+    let object = map.find(id.id).ok_or(InvalidId)?;
+    if object.data.serial != id.serial {
+        return Err(InvalidId);
+    }
+    Ok(object)
+}
+
+// The object map of a client, without its type parameter
+pub(crate) trait ErasedObjectMap: downcast_rs::DowncastSync {
+    fn object_info(&self, id: &InnerObjectId) -> Result<ObjectInfo, InvalidId>;
+    fn object_data_any(
+        &self,
+        id: &InnerObjectId,
+    ) -> Result<Arc<dyn std::any::Any + Send + Sync>, InvalidId>;
+}
+
+downcast_rs::impl_downcast!(sync ErasedObjectMap);
+
+impl<D: 'static> ErasedObjectMap for RwLock<ObjectMap<Data<D>>> {
+    fn object_info(&self, id: &InnerObjectId) -> Result<ObjectInfo, InvalidId> {
+        let object = find_object(&self.read().unwrap(), id)?;
+        Ok(ObjectInfo { id: id.id, interface: object.interface, version: object.version })
+    }
+
+    fn object_data_any(
+        &self,
+        id: &InnerObjectId,
+    ) -> Result<Arc<dyn std::any::Any + Send + Sync>, InvalidId> {
+        Ok(find_object(&self.read().unwrap(), id)?.data.user_data.into_any_arc())
+    }
+}
+
+type ClientMap = (InnerClientId, Arc<dyn ErasedObjectMap>);
+
+/// The object maps of all the clients of a store
+///
+/// The handle queries the objects through them without locking the state of the backend, so that
+/// these queries do not wait for the dispatching of the requests.
+#[derive(Default)]
+pub(crate) struct ClientMaps(RwLock<Vec<Option<ClientMap>>>);
+
+impl ClientMaps {
+    pub(crate) fn get(&self, id: &InnerClientId) -> Result<Arc<dyn ErasedObjectMap>, InvalidId> {
+        match self.0.read().unwrap().get(id.id as usize) {
+            Some(Some((client_id, map))) if client_id == id => Ok(map.clone()),
+            _ => Err(InvalidId),
+        }
+    }
+
+    fn set(&self, id: &InnerClientId, map: Option<Arc<dyn ErasedObjectMap>>) {
+        let mut maps = self.0.write().unwrap();
+        let index = id.id as usize;
+        if maps.len() <= index {
+            maps.resize_with(index + 1, || None);
+        }
+        maps[index] = map.map(|map| (id.clone(), map));
+    }
+}
+
+impl std::fmt::Debug for ClientMaps {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ClientMaps").finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct ClientStore<D: 'static> {
     clients: Vec<Option<Client<D>>>,
+    pub(crate) maps: Arc<ClientMaps>,
     last_serial: u32,
-    debug: bool,
+    debug: Option<DebugOutput>,
+    max_fds: usize,
+    max_buffer_size: usize,
+    object_limit: ObjectLimit,
+    // shared by the sockets of all clients
+    pub(crate) metrics: Arc<MetricsCounters>,
 }
 
 impl<D> ClientStore<D> {
-    pub(crate) fn new(debug: bool) -> Self {
-        Self { clients: Vec::new(), last_serial: 0, debug }
+    pub(crate) fn new(debug: Option<DebugOutput>) -> Self {
+        Self {
+            clients: Vec::new(),
+            maps: Arc::default(),
+            last_serial: 0,
+            debug,
+            max_fds: BufferSizes::default().max_fds,
+            max_buffer_size: BufferSizes::default().max,
+            object_limit: ObjectLimit::default(),
+            metrics: Arc::default(),
+        }
+    }
+
+    pub(crate) fn set_debug(&mut self, debug: Option<DebugOutput>) {
+        for client in self.clients.iter_mut().flatten() {
+            client.debug = debug.clone();
+        }
+        self.debug = debug;
+    }
+
+    pub(crate) fn set_max_fds(&mut self, max_fds: usize) {
+        self.max_fds = max_fds;
+        for client in self.clients.iter_mut().flatten() {
+            client.socket.set_max_fds(max_fds);
+        }
+    }
+
+    pub(crate) fn set_max_buffer_size(&mut self, size: usize) {
+        self.max_buffer_size = size;
+        for client in self.clients.iter_mut().flatten() {
+            client.socket.set_max_size(size);
+        }
+    }
+
+    pub(crate) fn set_max_objects(&mut self, max_objects: Option<usize>) {
+        self.object_limit.max_objects = max_objects;
+        for client in self.clients.iter_mut().flatten() {
+            client.object_limit.max_objects = max_objects;
+        }
+    }
+
+    pub(crate) fn set_object_limit_callback(&mut self, callback: Option<ObjectLimitCallback>) {
+        for client in self.clients.iter_mut().flatten() {
+            client.object_limit.callback = callback.clone();
+        }
+        self.object_limit.callback = callback;
     }
 
     pub(crate) fn create_client(
         &mut self,
         stream: UnixStream,
         data: Arc<dyn ClientData>,
+        credentials: Option<Credentials>,
     ) -> InnerClientId {
         let serial = self.next_serial();
         // Find the next free place
@@ -693,7 +1045,19 @@ impl<D> ClientStore<D> {
 
         let id = InnerClientId { id: id as u32, serial };
 
-        *place = Some(Client::new(stream, id.clone(), self.debug, data));
+        let mut client = Client::new(
+            stream,
+            id.clone(),
+            self.debug.clone(),
+            data,
+            credentials,
+            self.metrics.clone(),
+        );
+        client.socket.set_max_fds(self.max_fds);
+        client.socket.set_max_size(self.max_buffer_size);
+        client.object_limit = self.object_limit.clone();
+        self.maps.set(&id, Some(client.map.clone()));
+        *place = Some(client);
 
         id
     }
@@ -724,6 +1088,7 @@ impl<D> ClientStore<D> {
             if place.as_ref().map(|client| client.killed).unwrap_or(false) {
                 // Remove the client from the store and flush it one last time before dropping it
                 let mut client = place.take().unwrap();
+                self.maps.set(&client.id, None);
                 client.queue_all_destructors(pending_destructors);
                 let _ = client.flush();
                 cleaned.push(ClientId { id: client.id });
@@ -741,9 +1106,67 @@ impl<D> ClientStore<D> {
         self.clients.iter_mut().flat_map(|o| o.as_mut()).filter(|c| !c.killed)
     }
 
+    /// Flush all clients, submitting all the writes at once
+    ///
+    /// Returns the result of the flush of each client. All clients are flushed even if the ring fails.
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    pub(crate) fn flush_all_batched(
+        &mut self,
+        ring: &mut Ring,
+    ) -> Batch<(InnerClientId, std::io::Result<()>)> {
+        let (ids, mut sockets): (Vec<_>, Vec<_>) =
+            self.clients_mut().map(|client| (client.id.clone(), &mut client.socket)).unzip();
+        let batch = BufferedSocket::flush_batch(ring, &mut sockets);
+        Batch { results: ids.into_iter().zip(batch.results).collect(), error: batch.error }
+    }
+
+    /// Fill the incoming buffers of the given clients, submitting all the reads at once
+    ///
+    /// Returns the result of the read of each client that had room for more data. All of them are read
+    /// even if the ring fails.
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    pub(crate) fn fill_incoming_buffers_batched(
+        &mut self,
+        ring: &mut Ring,
+        ids: &[InnerClientId],
+    ) -> Batch<(InnerClientId, std::io::Result<()>)> {
+        let (ids, mut sockets): (Vec<_>, Vec<_>) = self
+            .clients_mut()
+            .filter(|client| ids.contains(&client.id) && client.socket.can_receive())
+            .map(|client| (client.id.clone(), &mut client.socket))
+            .unzip();
+        if sockets.is_empty() {
+            return Batch { results: Vec::new(), error: None };
+        }
+        let batch = BufferedSocket::fill_incoming_buffers_batch(ring, &mut sockets);
+        Batch { results: ids.into_iter().zip(batch.results).collect(), error: batch.error }
+    }
+
     pub(crate) fn all_clients_id(&self) -> impl Iterator<Item = ClientId> + '_ {
         self.clients.iter().flat_map(|opt| {
             opt.as_ref().filter(|c| !c.killed).map(|client| ClientId { id: client.id.clone() })
         })
     }
 }
+
+// The name of a request in the error messages, as `interface@id.request`
+fn request_name(interface: &Interface, object_id: u32, desc: &MessageDesc) -> String {
+    format!("{}@{}.{}", interface.name, object_id, desc.name)
+}
+
+// The types of the arguments of a request, in the error messages
+fn describe_signature(signature: &[ArgumentType]) -> String {
+    let types = signature.iter().map(|arg| match *arg {
+        ArgumentType::Int => "int",
+        ArgumentType::Uint => "uint",
+        ArgumentType::Fixed => "fixed",
+        ArgumentType::Str(AllowNull::Yes) => "?string",
+        ArgumentType::Str(AllowNull::No) => "string",
+        ArgumentType::Object(AllowNull::Yes) => "?object",
+        ArgumentType::Object(AllowNull::No) => "object",
+        ArgumentType::NewId => "new_id",
+        ArgumentType::Array => "array",
+        ArgumentType::Fd => "fd",
+    });
+    types.collect::<Vec<_>>().join(", ")
+}
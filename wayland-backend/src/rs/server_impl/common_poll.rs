@@ -1,67 +1,66 @@
 use std::{
-    os::unix::io::AsRawFd,
+    os::unix::io::{AsFd, AsRawFd},
     os::unix::io::{BorrowedFd, OwnedFd},
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use super::{
-    handle::State, ClientId, Data, GlobalHandler, GlobalId, Handle, InnerClientId, InnerGlobalId,
-    InnerHandle, InnerObjectId, ObjectId,
+    client::ClientMaps, handle::State, poll::Poller, ClientId, Data, GlobalHandler, GlobalId,
+    Handle, InertObjectData, InnerClientId, InnerGlobalId, InnerHandle, InnerObjectId, ObjectData,
+    ObjectId, ObjectLimitCallback,
 };
 use crate::{
     core_interfaces::{WL_DISPLAY_INTERFACE, WL_REGISTRY_INTERFACE},
-    protocol::{same_interface, Argument, Message},
+    debug::{has_debug_server_env, DebugOutput, DebugSink},
+    protocol::{same_interface, BorrowedArgument, BorrowedMessage},
     rs::map::Object,
     types::server::InitError,
 };
 
-#[cfg(any(target_os = "linux", target_os = "android"))]
-use rustix::event::epoll;
-
-#[cfg(any(
-    target_os = "dragonfly",
-    target_os = "freebsd",
-    target_os = "netbsd",
-    target_os = "openbsd",
-    target_os = "macos"
-))]
-use rustix::event::kqueue::*;
 use smallvec::SmallVec;
 
+// Number of requests of a client dispatched before moving on to the next client, so that a client
+// sending many requests does not delay the others
+const CLIENT_DISPATCH_BUDGET: usize = 32;
+
 #[derive(Debug)]
 pub struct InnerBackend<D: 'static> {
     state: Arc<Mutex<State<D>>>,
+    objects: Arc<ClientMaps>,
 }
 
 impl<D> InnerBackend<D> {
     pub fn new() -> Result<Self, InitError> {
-        #[cfg(any(target_os = "linux", target_os = "android"))]
-        let poll_fd = epoll::create(epoll::CreateFlags::CLOEXEC)
-            .map_err(Into::into)
-            .map_err(InitError::Io)?;
-
-        #[cfg(any(
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd",
-            target_os = "macos"
-        ))]
-        let poll_fd = kqueue().map_err(Into::into).map_err(InitError::Io)?;
-
-        Ok(Self { state: Arc::new(Mutex::new(State::new(poll_fd))) })
+        let poller = Poller::new().map_err(InitError::Io)?;
+        let state = State::new(poller);
+        let objects = state.clients.maps.clone();
+        Ok(Self { state: Arc::new(Mutex::new(state)), objects })
     }
 
     pub fn flush(&self, client: Option<ClientId>) -> std::io::Result<()> {
-        self.state.lock().unwrap().flush(client)
+        let idle_callback = {
+            let mut state = self.state.lock().unwrap();
+            state.flush(client.clone())?;
+            client.map_or_else(|| state.take_idle_callback(), |_| None)
+        };
+        if let Some(cb) = idle_callback {
+            cb();
+        }
+        Ok(())
     }
 
     pub fn handle(&self) -> Handle {
-        Handle { handle: InnerHandle { state: self.state.clone() as Arc<_> } }
+        Handle {
+            handle: InnerHandle {
+                state: self.state.clone() as Arc<_>,
+                objects: self.objects.clone(),
+            },
+        }
     }
 
     pub fn poll_fd(&self) -> BorrowedFd {
-        let raw_fd = self.state.lock().unwrap().poll_fd.as_raw_fd();
+        let raw_fd = self.state.lock().unwrap().poller.as_fd().as_raw_fd();
         // This allows the lifetime of the BorrowedFd to be tied to &self rather than the lock guard,
         // which is the real safety concern
         unsafe { BorrowedFd::borrow_raw(raw_fd) }
@@ -72,93 +71,163 @@ impl<D> InnerBackend<D> {
         data: &mut D,
         client_id: InnerClientId,
     ) -> std::io::Result<usize> {
-        let ret = self.dispatch_events_for(data, client_id);
+        let ret = self.dispatch_events_for(data, client_id, usize::MAX, None);
+        let cleanup = self.state.lock().unwrap().cleanup();
+        cleanup(&self.handle(), data);
+        ret.map(|(dispatched, _)| dispatched)
+    }
+
+    pub fn dispatch_client_timeout(
+        &self,
+        data: &mut D,
+        client_id: InnerClientId,
+        timeout: Duration,
+    ) -> std::io::Result<(usize, bool)> {
+        let deadline = Instant::now() + timeout;
+        let ret = self.dispatch_events_for(data, client_id, usize::MAX, Some(deadline));
         let cleanup = self.state.lock().unwrap().cleanup();
         cleanup(&self.handle(), data);
         ret
     }
 
-    #[cfg(any(target_os = "linux", target_os = "android"))]
     pub fn dispatch_all_clients(&self, data: &mut D) -> std::io::Result<usize> {
-        use std::os::unix::io::AsFd;
+        self.dispatch_all_clients_limited(data, usize::MAX).map(|(dispatched, _)| dispatched)
+    }
+
+    pub fn dispatch_all_clients_limited(
+        &self,
+        data: &mut D,
+        max_requests: usize,
+    ) -> std::io::Result<(usize, bool)> {
+        self.kill_stalled_clients(data);
 
-        let poll_fd = self.poll_fd();
         let mut dispatched = 0;
         loop {
-            let mut events = epoll::EventVec::with_capacity(32);
-            epoll::wait(poll_fd.as_fd(), &mut events, 0)?;
+            // the clients interrupted by the limit of a previous call come first
+            let mut ids = std::mem::take(&mut self.state.lock().unwrap().pending_dispatch);
+            for id in self.ready_clients()? {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
 
-            if events.is_empty() {
-                break;
+            if ids.is_empty() {
+                return Ok((dispatched, false));
             }
 
-            for event in events.iter() {
-                let id = InnerClientId::from_u64(event.data.u64());
-                // remove the cb while we call it, to gracefully handle reentrancy
-                if let Ok(count) = self.dispatch_events_for(data, id) {
+            for (i, id) in ids.iter().enumerate() {
+                if dispatched == max_requests {
+                    // the limit is reached, the remaining clients are kept for the next call, before
+                    // the ones interrupted by their budget in this round
+                    let pending = &mut self.state.lock().unwrap().pending_dispatch;
+                    pending.splice(0..0, ids[i..].iter().cloned());
+                    break;
+                }
+                // a client interrupted by its budget is dispatched again in the next round
+                let budget = CLIENT_DISPATCH_BUDGET.min(max_requests - dispatched);
+                if let Ok((count, _)) = self.dispatch_events_for(data, id.clone(), budget, None) {
                     dispatched += count;
                 }
             }
             let cleanup = self.state.lock().unwrap().cleanup();
             cleanup(&self.handle(), data);
+
+            if dispatched == max_requests {
+                return Ok((dispatched, true));
+            }
         }
+    }
 
-        Ok(dispatched)
+    // The clients with data ready to be read
+    fn ready_clients(&self) -> std::io::Result<Vec<InnerClientId>> {
+        let ids = self.state.lock().unwrap().poller.ready()?;
+        let ids = ids.into_iter().map(InnerClientId::from_u64).collect::<Vec<_>>();
+
+        #[cfg(all(feature = "io_uring", target_os = "linux"))]
+        self.state.lock().unwrap().fill_incoming_buffers(&ids);
+
+        Ok(ids)
     }
 
-    #[cfg(any(
-        target_os = "dragonfly",
-        target_os = "freebsd",
-        target_os = "netbsd",
-        target_os = "openbsd",
-        target_os = "macos"
-    ))]
-    pub fn dispatch_all_clients(&self, data: &mut D) -> std::io::Result<usize> {
-        use std::time::Duration;
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.state.lock().unwrap().handshake_timeout = timeout;
+    }
 
-        let poll_fd = self.poll_fd();
-        let mut dispatched = 0;
-        loop {
-            let mut events = Vec::with_capacity(32);
-            let nevents = unsafe { kevent(&poll_fd, &[], &mut events, Some(Duration::ZERO))? };
+    pub fn set_max_client_fds(&mut self, max_fds: usize) {
+        self.state.lock().unwrap().clients.set_max_fds(max_fds);
+    }
 
-            if nevents == 0 {
-                break;
-            }
+    pub fn set_max_client_buffer_size(&mut self, size: usize) {
+        self.state.lock().unwrap().clients.set_max_buffer_size(size);
+    }
 
-            for event in events.iter().take(nevents) {
-                let id = InnerClientId::from_u64(event.udata() as u64);
-                // remove the cb while we call it, to gracefully handle reentrancy
-                if let Ok(count) = self.dispatch_events_for(data, id) {
-                    dispatched += count;
-                }
-            }
-            let cleanup = self.state.lock().unwrap().cleanup();
-            cleanup(&self.handle(), data);
-        }
+    pub fn set_max_client_objects(&mut self, max_objects: Option<usize>) {
+        self.state.lock().unwrap().clients.set_max_objects(max_objects);
+    }
+
+    pub fn set_object_limit_callback(&mut self, callback: Option<ObjectLimitCallback>) {
+        self.state.lock().unwrap().clients.set_object_limit_callback(callback);
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.state.lock().unwrap().clients.metrics.snapshot()
+    }
+
+    pub fn set_debug_sink(&mut self, sink: Option<DebugSink>) {
+        let output = DebugOutput::new(sink, has_debug_server_env());
+        self.state.lock().unwrap().clients.set_debug(output);
+    }
 
-        Ok(dispatched)
+    fn kill_stalled_clients(&self, data: &mut D) {
+        let cleanup = {
+            let mut state = self.state.lock().unwrap();
+            if !state.kill_stalled_clients() {
+                return;
+            }
+            state.cleanup()
+        };
+        cleanup(&self.handle(), data);
     }
 
     pub(crate) fn dispatch_events_for(
         &self,
         data: &mut D,
         client_id: InnerClientId,
-    ) -> std::io::Result<usize> {
+        max_requests: usize,
+        deadline: Option<Instant>,
+    ) -> std::io::Result<(usize, bool)> {
         let mut dispatched = 0;
+        let mut interrupted = false;
         let handle = self.handle();
         let mut state = self.state.lock().unwrap();
-        loop {
+        // the raw requests are copied here, and their arguments borrowed from it
+        let mut scratch = match state.clients.get_client_mut(client_id.clone()) {
+            Ok(client) => client.take_scratch(),
+            Err(_) => Vec::new(),
+        };
+        let ret = loop {
+            // at least one request is dispatched whatever the deadline, so that the client progresses
+            if dispatched == max_requests
+                || (dispatched > 0 && deadline.map_or(false, |deadline| Instant::now() >= deadline))
+            {
+                // some requests may remain buffered
+                if !state.pending_dispatch.contains(&client_id) {
+                    state.pending_dispatch.push(client_id.clone());
+                }
+                interrupted = true;
+                break Ok(());
+            }
             let action = {
                 let state = &mut *state;
                 if let Ok(client) = state.clients.get_client_mut(client_id.clone()) {
-                    let (message, object) = match client.next_request() {
+                    let (message, object) = match client.next_request(&mut scratch) {
                         Ok(v) => v,
                         Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                             if dispatched > 0 {
-                                break;
+                                break Ok(());
                             } else {
-                                return Err(e);
+                                break Err(e);
                             }
                         }
                         Err(e) => return Err(e),
@@ -183,6 +252,7 @@ impl<D> InnerBackend<D> {
                             client_id: client.id.clone(),
                         };
                         let opcode = message.opcode;
+                        let seq = client.last_request_sequence();
                         let (arguments, is_destructor, created_id) =
                             match client.process_request(&object, message) {
                                 Some(args) => args,
@@ -193,6 +263,7 @@ impl<D> InnerBackend<D> {
                             object,
                             object_id,
                             opcode,
+                            seq,
                             arguments,
                             is_destructor,
                             created_id,
@@ -210,22 +281,46 @@ impl<D> InnerBackend<D> {
                     object,
                     object_id,
                     opcode,
+                    seq,
                     arguments,
                     is_destructor,
                     created_id,
                 } => {
+                    let interceptor = state.interceptor.clone();
+                    let metrics = state.clients.metrics.clone();
                     // temporarily unlock the state Mutex while this request is dispatched
                     std::mem::drop(state);
-                    let ret = object.data.user_data.clone().request(
-                        &handle.clone(),
-                        data,
-                        ClientId { id: client_id.clone() },
-                        Message {
-                            sender_id: ObjectId { id: object_id.clone() },
-                            opcode,
-                            args: arguments,
+                    let message = BorrowedMessage {
+                        sender_id: ObjectId { id: object_id.clone() },
+                        opcode,
+                        args: arguments,
+                    };
+                    let ret = metrics.time_dispatch(|| match interceptor {
+                        // the interceptor works on owned messages
+                        Some(interceptor) => match interceptor.intercept_sequenced(
+                            &handle,
+                            ClientId { id: client_id.clone() },
+                            seq,
+                            message.into_owned(),
+                        ) {
+                            Some(message) => object.data.user_data.clone().request(
+                                &handle.clone(),
+                                data,
+                                ClientId { id: client_id.clone() },
+                                message,
+                            ),
+                            // the request was consumed, a created object is left inert
+                            None => created_id
+                                .as_ref()
+                                .map(|_| Arc::new(InertObjectData) as Arc<dyn ObjectData<D>>),
                         },
-                    );
+                        None => object.data.user_data.clone().request_borrowed(
+                            &handle.clone(),
+                            data,
+                            ClientId { id: client_id.clone() },
+                            message,
+                        ),
+                    });
                     if is_destructor {
                         object.data.user_data.clone().destroyed(
                             &handle.clone(),
@@ -244,10 +339,11 @@ impl<D> InnerBackend<D> {
                     match (created_id, ret) {
                         (Some(child_id), Some(child_data)) => {
                             if let Ok(client) = state.clients.get_client_mut(client_id.clone()) {
-                                client
-                                    .map
-                                    .with(child_id.id, |obj| obj.data.user_data = child_data)
-                                    .unwrap();
+                                // the object may already have been destroyed by the callback, for
+                                // example by firing a `wl_callback` right away
+                                let _ = client
+                                    .objects_mut()
+                                    .with(child_id.id, |obj| obj.data.user_data = child_data);
                             }
                         }
                         (None, None) => {}
@@ -281,21 +377,30 @@ impl<D> InnerBackend<D> {
                     // acquire the lock again and continue
                     state = self.state.lock().unwrap();
                     if let Ok(client) = state.clients.get_client_mut(client.clone()) {
-                        client.map.with(object.id, |obj| obj.data.user_data = child_data).unwrap();
+                        client
+                            .objects_mut()
+                            .with(object.id, |obj| obj.data.user_data = child_data)
+                            .unwrap();
                     }
                 }
             }
+        };
+        if let Ok(client) = state.clients.get_client_mut(client_id) {
+            client.recycle_scratch(scratch);
         }
-        Ok(dispatched)
+        ret?;
+        state.busy = true;
+        Ok((dispatched, interrupted))
     }
 }
 
-enum DispatchAction<D: 'static> {
+enum DispatchAction<'s, D: 'static> {
     Request {
         object: Object<Data<D>>,
         object_id: InnerObjectId,
         opcode: u16,
-        arguments: SmallVec<[Argument<ObjectId, OwnedFd>; 4]>,
+        seq: u64,
+        arguments: SmallVec<[BorrowedArgument<'s, ObjectId, OwnedFd>; 4]>,
         is_destructor: bool,
         created_id: Option<InnerObjectId>,
     },
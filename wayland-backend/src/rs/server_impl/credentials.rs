@@ -0,0 +1,106 @@
+//! Retrieval of the credentials of the peer of a unix socket
+
+use std::{io, os::unix::io::BorrowedFd};
+
+use super::Credentials;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn peer_credentials(fd: BorrowedFd<'_>) -> io::Result<Credentials> {
+    let creds = rustix::net::sockopt::get_socket_peercred(fd)?;
+    let pid = rustix::process::Pid::as_raw(Some(creds.pid));
+    Ok(Credentials { pid, uid: creds.uid.as_raw(), gid: creds.gid.as_raw() })
+}
+
+#[cfg(target_os = "openbsd")]
+pub(crate) fn peer_credentials(fd: BorrowedFd<'_>) -> io::Result<Credentials> {
+    let creds: libc::sockpeercred = getsockopt(fd, libc::SOL_SOCKET, libc::SO_PEERCRED)?;
+    Ok(Credentials { pid: creds.pid, uid: creds.uid, gid: creds.gid })
+}
+
+#[cfg(target_os = "netbsd")]
+pub(crate) fn peer_credentials(fd: BorrowedFd<'_>) -> io::Result<Credentials> {
+    // LOCAL_PEEREID is an option of the local socket level, which is 0
+    let creds: libc::unpcbid = getsockopt(fd, 0, libc::LOCAL_PEEREID)?;
+    Ok(Credentials { pid: creds.unp_pid, uid: creds.unp_euid, gid: creds.unp_egid })
+}
+
+#[cfg(target_os = "freebsd")]
+pub(crate) fn peer_credentials(fd: BorrowedFd<'_>) -> io::Result<Credentials> {
+    // struct xucred of <sys/ucred.h>, the definition of libc does not expose the pid consistently
+    #[repr(C)]
+    struct XuCred {
+        cr_version: libc::c_uint,
+        cr_uid: libc::uid_t,
+        cr_ngroups: libc::c_short,
+        cr_groups: [libc::gid_t; 16],
+        cr_pid: XuCredPid,
+    }
+
+    #[repr(C)]
+    union XuCredPid {
+        _cr_unused1: *mut libc::c_void,
+        // set since FreeBSD 13, 0 before
+        cr_pid: libc::pid_t,
+    }
+
+    let creds: XuCred = getsockopt(fd, libc::SOL_LOCAL, libc::LOCAL_PEERCRED)?;
+    if creds.cr_version != libc::XUCRED_VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported xucred version"));
+    }
+    let pid = unsafe { creds.cr_pid.cr_pid };
+    Ok(Credentials { pid, uid: creds.cr_uid, gid: creds.cr_groups[0] })
+}
+
+// getpeereid() does not provide the pid, it is only available on macOS
+#[cfg(any(target_os = "dragonfly", target_os = "macos"))]
+pub(crate) fn peer_credentials(fd: BorrowedFd<'_>) -> io::Result<Credentials> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut uid = 0;
+    let mut gid = 0;
+    if unsafe { libc::getpeereid(fd.as_raw_fd(), &mut uid, &mut gid) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    #[cfg(target_os = "macos")]
+    let pid = getsockopt(fd, libc::SOL_LOCAL, libc::LOCAL_PEERPID)?;
+    #[cfg(not(target_os = "macos"))]
+    let pid = 0;
+    Ok(Credentials { pid, uid, gid })
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "macos"
+)))]
+pub(crate) fn peer_credentials(_fd: BorrowedFd<'_>) -> io::Result<Credentials> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(any(
+    target_os = "freebsd",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+fn getsockopt<T>(fd: BorrowedFd<'_>, level: libc::c_int, name: libc::c_int) -> io::Result<T> {
+    use std::{mem::MaybeUninit, os::unix::io::AsRawFd};
+
+    let mut value = MaybeUninit::<T>::zeroed();
+    let mut len = std::mem::size_of::<T>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(fd.as_raw_fd(), level, name, value.as_mut_ptr().cast(), &mut len)
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if len as usize != std::mem::size_of::<T>() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected socket option size"));
+    }
+    // SAFETY: the option was fully written by the kernel
+    Ok(unsafe { value.assume_init() })
+}
@@ -1,18 +1,30 @@
 use std::{
     ffi::CString,
-    os::unix::io::OwnedFd,
-    os::unix::{io::RawFd, net::UnixStream},
-    sync::{Arc, Mutex, Weak},
+    os::unix::io::{AsFd, BorrowedFd, OwnedFd},
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex, RwLock, Weak},
+    time::Duration,
 };
 
 use crate::{
+    debug::{has_debug_server_env, DebugOutput},
     protocol::{same_interface, Interface, Message, ObjectInfo, ANONYMOUS_INTERFACE},
-    types::server::{DisconnectReason, GlobalInfo, InvalidId},
+    rs::map::ObjectMap,
+    types::server::{DisconnectReason, GlobalInfo, InvalidId, ResourceUsage},
 };
 
+use rustix::event::{PollFd, PollFlags};
+
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use crate::rs::uring::Ring;
+
 use super::{
-    client::ClientStore, registry::Registry, ClientData, ClientId, Credentials, GlobalHandler,
-    InnerClientId, InnerGlobalId, InnerObjectId, ObjectData, ObjectId,
+    client::{find_object, ClientMaps, ClientStore},
+    credentials::peer_credentials,
+    poll::Poller,
+    registry::Registry,
+    ClientData, ClientId, Credentials, Data, DisconnectCallback, GlobalHandler, GlobalRestriction,
+    IdleCallback, InnerClientId, InnerGlobalId, InnerObjectId, Interceptor, ObjectData, ObjectId,
 };
 
 pub(crate) type PendingDestructor<D> = (Arc<dyn ObjectData<D>>, InnerClientId, InnerObjectId);
@@ -22,21 +34,77 @@ pub struct State<D: 'static> {
     pub(crate) clients: ClientStore<D>,
     pub(crate) registry: Registry<D>,
     pub(crate) pending_destructors: Vec<PendingDestructor<D>>,
-    pub(crate) poll_fd: OwnedFd,
+    pub(crate) poller: Poller,
+    pub(crate) handshake_timeout: Option<Duration>,
+    pub(crate) interceptor: Option<Arc<dyn Interceptor>>,
+    queued_events: Vec<Message<ObjectId, OwnedFd>>,
+    // clients whose dispatching was interrupted by the limit of `dispatch_all_clients_limited()`, their
+    // remaining requests may be buffered and not signaled by the poll fd
+    pub(crate) pending_dispatch: Vec<InnerClientId>,
+    idle_callback: Option<IdleCallback>,
+    // whether some activity happened since the idle callback was last invoked
+    pub(crate) busy: bool,
+    // used to batch the socket I/O of all clients, if io_uring is available
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    ring: Option<Ring>,
 }
 
 impl<D> State<D> {
-    pub(crate) fn new(poll_fd: OwnedFd) -> Self {
-        let debug =
-            matches!(std::env::var_os("WAYLAND_DEBUG"), Some(str) if str == "1" || str == "server");
+    pub(crate) fn new(poller: Poller) -> Self {
         Self {
-            clients: ClientStore::new(debug),
+            clients: ClientStore::new(DebugOutput::new(None, has_debug_server_env())),
             registry: Registry::new(),
             pending_destructors: Vec::new(),
-            poll_fd,
+            poller,
+            handshake_timeout: None,
+            interceptor: None,
+            queued_events: Vec::new(),
+            pending_dispatch: Vec::new(),
+            idle_callback: None,
+            busy: false,
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            ring: Ring::new().ok(),
+        }
+    }
+
+    /// Read the pending data of several clients in a single batch
+    ///
+    /// This only prepares the incoming buffers of the clients, and is a no-op if io_uring is not
+    /// available. Like when their requests are read, the clients whose socket failed are killed.
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    pub(crate) fn fill_incoming_buffers(&mut self, ids: &[InnerClientId]) {
+        let Some(ring) = self.ring.as_mut() else { return };
+        let batch = self.clients.fill_incoming_buffers_batched(ring, ids);
+        for (id, result) in batch.results {
+            if let Err(e) = result {
+                if e.kind() != std::io::ErrorKind::WouldBlock {
+                    if let Ok(client) = self.clients.get_client_mut(id) {
+                        client.kill(DisconnectReason::ConnectionClosed);
+                    }
+                }
+            }
+        }
+        if let Some(e) = batch.error {
+            crate::log_warn!("[wayland-backend] Disabling io_uring after error: {e}");
+            self.ring = None;
         }
     }
 
+    /// Kill the clients that did not send any request within the handshake timeout
+    ///
+    /// Returns whether any client was killed.
+    pub(crate) fn kill_stalled_clients(&mut self) -> bool {
+        let Some(timeout) = self.handshake_timeout else { return false };
+        let mut killed = false;
+        for client in self.clients.clients_mut() {
+            if client.awaiting_handshake.map(|t| t.elapsed() >= timeout).unwrap_or(false) {
+                client.kill(DisconnectReason::Killed);
+                killed = true;
+            }
+        }
+        killed
+    }
+
     pub(crate) fn cleanup<'a>(&mut self) -> impl FnOnce(&super::Handle, &mut D) + 'a {
         let dead_clients = self.clients.cleanup(&mut self.pending_destructors);
         self.registry.cleanup(&dead_clients);
@@ -54,13 +122,56 @@ impl<D> State<D> {
         }
     }
 
+    fn send_queued_events(&mut self) {
+        for msg in std::mem::take(&mut self.queued_events) {
+            // the fds are kept open until the message is serialized
+            let mut fds = Vec::new();
+            let msg = msg.map_fd(|fd| fds.push(fd));
+            let mut fds = fds.iter();
+            let _ = self.send_event(msg.map_fd(|()| fds.next().unwrap().as_fd()));
+        }
+    }
+
+    // Returns the idle callback if the server just became idle: all events have been written to the
+    // sockets and no request is waiting to be read
+    pub(crate) fn take_idle_callback(&mut self) -> Option<Arc<dyn Fn() + Send + Sync>> {
+        if !self.busy
+            || self.idle_callback.is_none()
+            || !self.queued_events.is_empty()
+            || !self.pending_dispatch.is_empty()
+        {
+            return None;
+        }
+        if self.clients.clients_mut().any(|client| client.has_pending_output()) {
+            return None;
+        }
+        let mut fds = [PollFd::new(&self.poller, PollFlags::IN)];
+        if !matches!(rustix::event::poll(&mut fds, 0), Ok(0)) {
+            return None;
+        }
+        self.busy = false;
+        self.idle_callback.as_ref().map(|cb| cb.0.clone())
+    }
+
     pub(crate) fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
+        self.send_queued_events();
         if let Some(ClientId { id: client }) = client {
             match self.clients.get_client_mut(client) {
                 Ok(client) => client.flush(),
                 Err(InvalidId) => Ok(()),
             }
         } else {
+            #[cfg(all(feature = "io_uring", target_os = "linux"))]
+            if let Some(ring) = self.ring.as_mut() {
+                // the errors of individual clients are ignored like below, and the clients the ring
+                // did not reach were already flushed without it
+                let batch = self.clients.flush_all_batched(ring);
+                if let Some(e) = batch.error {
+                    crate::log_warn!("[wayland-backend] Disabling io_uring after error: {e}");
+                    self.ring = None;
+                }
+                return Ok(());
+            }
             for client in self.clients.clients_mut() {
                 let _ = client.flush();
             }
@@ -72,6 +183,8 @@ impl<D> State<D> {
 #[derive(Clone)]
 pub struct InnerHandle {
     pub(crate) state: Arc<Mutex<dyn ErasedState + Send>>,
+    // the object maps of the clients, read without locking the state
+    pub(crate) objects: Arc<ClientMaps>,
 }
 
 impl std::fmt::Debug for InnerHandle {
@@ -83,6 +196,7 @@ impl std::fmt::Debug for InnerHandle {
 #[derive(Clone)]
 pub struct WeakInnerHandle {
     pub(crate) state: Weak<Mutex<dyn ErasedState + Send>>,
+    pub(crate) objects: Weak<ClientMaps>,
 }
 
 impl std::fmt::Debug for WeakInnerHandle {
@@ -93,17 +207,22 @@ impl std::fmt::Debug for WeakInnerHandle {
 
 impl WeakInnerHandle {
     pub fn upgrade(&self) -> Option<InnerHandle> {
-        self.state.upgrade().map(|state| InnerHandle { state })
+        let state = self.state.upgrade()?;
+        let objects = self.objects.upgrade()?;
+        Some(InnerHandle { state, objects })
     }
 }
 
 impl InnerHandle {
     pub fn downgrade(&self) -> WeakInnerHandle {
-        WeakInnerHandle { state: Arc::downgrade(&self.state) }
+        WeakInnerHandle {
+            state: Arc::downgrade(&self.state),
+            objects: Arc::downgrade(&self.objects),
+        }
     }
 
     pub fn object_info(&self, id: InnerObjectId) -> Result<ObjectInfo, InvalidId> {
-        self.state.lock().unwrap().object_info(id)
+        self.objects.get(&id.client_id)?.object_info(&id)
     }
 
     pub fn insert_client(
@@ -126,6 +245,34 @@ impl InnerHandle {
         self.state.lock().unwrap().get_client_credentials(id)
     }
 
+    pub fn get_client_resource_usage(&self, id: InnerClientId) -> Result<ResourceUsage, InvalidId> {
+        self.state.lock().unwrap().get_client_resource_usage(id)
+    }
+
+    pub fn set_client_label(&self, id: InnerClientId, label: String) -> Result<(), InvalidId> {
+        self.state.lock().unwrap().set_client_label(id, label)
+    }
+
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.state.lock().unwrap().set_interceptor(interceptor)
+    }
+
+    pub fn get_client_label(&self, id: InnerClientId) -> Result<Option<String>, InvalidId> {
+        self.state.lock().unwrap().get_client_label(id)
+    }
+
+    pub fn is_client_alive(&self, id: InnerClientId) -> bool {
+        self.state.lock().unwrap().is_client_alive(id)
+    }
+
+    pub fn add_client_disconnect_callback(
+        &self,
+        id: InnerClientId,
+        callback: DisconnectCallback,
+    ) -> Result<(), InvalidId> {
+        self.state.lock().unwrap().add_client_disconnect_callback(id, callback)
+    }
+
     pub fn with_all_clients(&self, mut f: impl FnMut(ClientId)) {
         self.state.lock().unwrap().with_all_clients(&mut f)
     }
@@ -159,7 +306,7 @@ impl InnerHandle {
             .downcast_mut::<State<D>>()
             .expect("Wrong type parameter passed to Handle::create_object().");
         let client = state.clients.get_client_mut(client_id)?;
-        Ok(ObjectId { id: client.create_object(interface, version, data) })
+        client.create_object(interface, version, data).map(|id| ObjectId { id })
     }
 
     pub fn null_id() -> ObjectId {
@@ -173,26 +320,31 @@ impl InnerHandle {
         }
     }
 
-    pub fn send_event(&self, msg: Message<ObjectId, RawFd>) -> Result<(), InvalidId> {
+    pub fn send_event(&self, msg: Message<ObjectId, BorrowedFd<'_>>) -> Result<(), InvalidId> {
         self.state.lock().unwrap().send_event(msg)
     }
 
+    pub fn queue_event(&self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId> {
+        self.state.lock().unwrap().queue_event(msg)
+    }
+
     pub fn get_object_data<D: 'static>(
         &self,
         id: InnerObjectId,
     ) -> Result<Arc<dyn ObjectData<D>>, InvalidId> {
-        let mut state = self.state.lock().unwrap();
-        let state = (&mut *state as &mut dyn ErasedState)
-            .downcast_mut::<State<D>>()
+        let map = self.objects.get(&id.client_id)?;
+        let map = map
+            .downcast_ref::<RwLock<ObjectMap<Data<D>>>>()
             .expect("Wrong type parameter passed to Handle::get_object_data().");
-        state.clients.get_client(id.client_id.clone())?.get_object_data(id)
+        let object = find_object(&map.read().unwrap(), &id)?;
+        Ok(object.data.user_data)
     }
 
     pub fn get_object_data_any(
         &self,
         id: InnerObjectId,
     ) -> Result<Arc<dyn std::any::Any + Send + Sync>, InvalidId> {
-        self.state.lock().unwrap().get_object_data_any(id)
+        self.objects.get(&id.client_id)?.object_data_any(&id)
     }
 
     pub fn set_object_data<D: 'static>(
@@ -200,11 +352,19 @@ impl InnerHandle {
         id: InnerObjectId,
         data: Arc<dyn ObjectData<D>>,
     ) -> Result<(), InvalidId> {
+        self.replace_object_data(id, data).map(drop)
+    }
+
+    pub fn replace_object_data<D: 'static>(
+        &self,
+        id: InnerObjectId,
+        data: Arc<dyn ObjectData<D>>,
+    ) -> Result<Arc<dyn ObjectData<D>>, InvalidId> {
         let mut state = self.state.lock().unwrap();
         let state = (&mut *state as &mut dyn ErasedState)
             .downcast_mut::<State<D>>()
-            .expect("Wrong type parameter passed to Handle::set_object_data().");
-        state.clients.get_client_mut(id.client_id.clone())?.set_object_data(id, data)
+            .expect("Wrong type parameter passed to Handle::replace_object_data().");
+        state.clients.get_client_mut(id.client_id.clone())?.replace_object_data(id, data)
     }
 
     pub fn post_error(&self, object_id: InnerObjectId, error_code: u32, message: CString) {
@@ -246,6 +406,19 @@ impl InnerHandle {
         state.registry.remove_global(id, &mut state.clients)
     }
 
+    pub fn restrict_global<D: 'static>(
+        &self,
+        id: InnerGlobalId,
+        restriction: Option<GlobalRestriction>,
+    ) -> Result<(), InvalidId> {
+        let mut state = self.state.lock().unwrap();
+        let state = (&mut *state as &mut dyn ErasedState)
+            .downcast_mut::<State<D>>()
+            .expect("Wrong type parameter passed to Handle::restrict_global().");
+
+        state.registry.restrict_global(id, restriction)
+    }
+
     pub fn global_info(&self, id: InnerGlobalId) -> Result<GlobalInfo, InvalidId> {
         self.state.lock().unwrap().global_info(id)
     }
@@ -261,8 +434,20 @@ impl InnerHandle {
         state.registry.get_handler(id)
     }
 
+    pub fn set_idle_callback(&self, cb: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.state.lock().unwrap().set_idle_callback(cb)
+    }
+
     pub fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
-        self.state.lock().unwrap().flush(client)
+        let idle_callback = {
+            let mut state = self.state.lock().unwrap();
+            state.flush(client.clone())?;
+            client.map_or_else(|| state.take_idle_callback(), |_| None)
+        };
+        if let Some(cb) = idle_callback {
+            cb();
+        }
+        Ok(())
     }
 }
 
@@ -276,6 +461,16 @@ pub(crate) trait ErasedState: downcast_rs::Downcast {
     fn get_client(&self, id: InnerObjectId) -> Result<ClientId, InvalidId>;
     fn get_client_data(&self, id: InnerClientId) -> Result<Arc<dyn ClientData>, InvalidId>;
     fn get_client_credentials(&self, id: InnerClientId) -> Result<Credentials, InvalidId>;
+    fn get_client_resource_usage(&self, id: InnerClientId) -> Result<ResourceUsage, InvalidId>;
+    fn set_client_label(&mut self, id: InnerClientId, label: String) -> Result<(), InvalidId>;
+    fn set_interceptor(&mut self, interceptor: Option<Arc<dyn Interceptor>>);
+    fn get_client_label(&self, id: InnerClientId) -> Result<Option<String>, InvalidId>;
+    fn is_client_alive(&self, id: InnerClientId) -> bool;
+    fn add_client_disconnect_callback(
+        &mut self,
+        id: InnerClientId,
+        callback: DisconnectCallback,
+    ) -> Result<(), InvalidId>;
     fn with_all_clients(&self, f: &mut dyn FnMut(ClientId));
     fn with_all_objects_for(
         &self,
@@ -288,15 +483,14 @@ pub(crate) trait ErasedState: downcast_rs::Downcast {
         interface: &'static Interface,
         protocol_id: u32,
     ) -> Result<ObjectId, InvalidId>;
-    fn get_object_data_any(
-        &self,
-        id: InnerObjectId,
-    ) -> Result<Arc<dyn std::any::Any + Send + Sync>, InvalidId>;
-    fn send_event(&mut self, msg: Message<ObjectId, RawFd>) -> Result<(), InvalidId>;
+    fn send_event(&mut self, msg: Message<ObjectId, BorrowedFd<'_>>) -> Result<(), InvalidId>;
+    fn queue_event(&mut self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId>;
     fn post_error(&mut self, object_id: InnerObjectId, error_code: u32, message: CString);
     fn kill_client(&mut self, client_id: InnerClientId, reason: DisconnectReason);
     fn global_info(&self, id: InnerGlobalId) -> Result<GlobalInfo, InvalidId>;
     fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()>;
+    fn take_idle_callback(&mut self) -> Option<Arc<dyn Fn() + Send + Sync>>;
+    fn set_idle_callback(&mut self, cb: Option<Arc<dyn Fn() + Send + Sync>>);
 }
 
 downcast_rs::impl_downcast!(ErasedState);
@@ -311,47 +505,21 @@ impl<D> ErasedState for State<D> {
         stream: UnixStream,
         data: Arc<dyn ClientData>,
     ) -> std::io::Result<InnerClientId> {
-        let id = self.clients.create_client(stream, data);
-        let client = self.clients.get_client(id.clone()).unwrap();
-
-        // register the client to the internal epoll
-        #[cfg(any(target_os = "linux", target_os = "android"))]
-        let ret = {
-            use rustix::event::epoll;
-            epoll::add(
-                &self.poll_fd,
-                client,
-                epoll::EventData::new_u64(id.as_u64()),
-                epoll::EventFlags::IN,
-            )
-        };
-
-        #[cfg(any(
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "netbsd",
-            target_os = "openbsd",
-            target_os = "macos"
-        ))]
-        let ret = {
-            use rustix::event::kqueue::*;
-            use std::os::unix::io::{AsFd, AsRawFd};
-
-            let evt = Event::new(
-                EventFilter::Read(client.as_fd().as_raw_fd()),
-                EventFlags::ADD | EventFlags::RECEIPT,
-                id.as_u64() as isize,
-            );
-
-            let mut events = Vec::new();
-            unsafe { kevent(&self.poll_fd, &[evt], &mut events, None).map(|_| ()) }
+        // global restrictions deny access to the clients whose credentials are unavailable
+        let credentials = match peer_credentials(stream.as_fd()) {
+            Ok(credentials) => Some(credentials),
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => None,
+            Err(e) => return Err(e),
         };
+        let id = self.clients.create_client(stream, data, credentials);
+        let client = self.clients.get_client(id.clone()).unwrap();
 
-        match ret {
+        // register the client to the internal poller
+        match self.poller.add(client.as_fd(), id.as_u64()) {
             Ok(()) => Ok(id),
             Err(e) => {
-                self.kill_client(id, DisconnectReason::ConnectionClosed);
-                Err(e.into())
+                self.kill_client(id, DisconnectReason::Killed);
+                Err(e)
             }
         }
     }
@@ -371,7 +539,42 @@ impl<D> ErasedState for State<D> {
 
     fn get_client_credentials(&self, id: InnerClientId) -> Result<Credentials, InvalidId> {
         let client = self.clients.get_client(id)?;
-        Ok(client.get_credentials())
+        Ok(client.get_credentials().unwrap_or(Credentials { pid: 0, uid: 0, gid: 0 }))
+    }
+
+    fn get_client_resource_usage(&self, id: InnerClientId) -> Result<ResourceUsage, InvalidId> {
+        let client = self.clients.get_client(id)?;
+        Ok(client.resource_usage())
+    }
+
+    fn set_client_label(&mut self, id: InnerClientId, label: String) -> Result<(), InvalidId> {
+        self.clients.get_client_mut(id)?.label = Some(label);
+        Ok(())
+    }
+
+    fn get_client_label(&self, id: InnerClientId) -> Result<Option<String>, InvalidId> {
+        Ok(self.clients.get_client(id)?.label.clone())
+    }
+
+    fn is_client_alive(&self, id: InnerClientId) -> bool {
+        self.clients.get_client(id).map(|client| !client.killed).unwrap_or(false)
+    }
+
+    fn add_client_disconnect_callback(
+        &mut self,
+        id: InnerClientId,
+        callback: DisconnectCallback,
+    ) -> Result<(), InvalidId> {
+        let client = self.clients.get_client_mut(id)?;
+        if client.killed {
+            return Err(InvalidId);
+        }
+        client.disconnect_callbacks.push(callback);
+        Ok(())
+    }
+
+    fn set_interceptor(&mut self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.interceptor = interceptor;
     }
 
     fn with_all_clients(&self, f: &mut dyn FnMut(ClientId)) {
@@ -407,20 +610,21 @@ impl<D> ErasedState for State<D> {
         }
     }
 
-    fn get_object_data_any(
-        &self,
-        id: InnerObjectId,
-    ) -> Result<Arc<dyn std::any::Any + Send + Sync>, InvalidId> {
-        self.clients
-            .get_client(id.client_id.clone())?
-            .get_object_data(id)
-            .map(|arc| arc.into_any_arc())
+    fn send_event(&mut self, msg: Message<ObjectId, BorrowedFd<'_>>) -> Result<(), InvalidId> {
+        self.busy = true;
+        let client = self.clients.get_client_mut(msg.sender_id.id.client_id.clone())?;
+        if let Some(interceptor) = self.interceptor.as_deref() {
+            if !client.intercept_event(interceptor, &msg)? {
+                return Ok(());
+            }
+        }
+        client.send_event(msg, Some(&mut self.pending_destructors))
     }
 
-    fn send_event(&mut self, msg: Message<ObjectId, RawFd>) -> Result<(), InvalidId> {
-        self.clients
-            .get_client_mut(msg.sender_id.id.client_id.clone())?
-            .send_event(msg, Some(&mut self.pending_destructors))
+    fn queue_event(&mut self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId> {
+        self.object_info(msg.sender_id.id.clone())?;
+        self.queued_events.push(msg);
+        Ok(())
     }
 
     fn post_error(&mut self, object_id: InnerObjectId, error_code: u32, message: CString) {
@@ -441,4 +645,12 @@ impl<D> ErasedState for State<D> {
     fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
         self.flush(client)
     }
+
+    fn take_idle_callback(&mut self) -> Option<Arc<dyn Fn() + Send + Sync>> {
+        self.take_idle_callback()
+    }
+
+    fn set_idle_callback(&mut self, cb: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.idle_callback = cb.map(IdleCallback);
+    }
 }
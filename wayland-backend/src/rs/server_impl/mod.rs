@@ -7,7 +7,9 @@ use crate::protocol::{same_interface, Interface, Message};
 
 mod client;
 mod common_poll;
+mod credentials;
 mod handle;
+mod poll;
 mod registry;
 
 pub use crate::types::server::Credentials;
@@ -16,6 +18,8 @@ pub use handle::{InnerHandle, WeakInnerHandle};
 
 use super::server::*;
 
+pub(crate) const BACKEND_KIND: BackendKind = BackendKind::Rust;
+
 #[derive(Clone)]
 pub struct InnerObjectId {
     id: u32,
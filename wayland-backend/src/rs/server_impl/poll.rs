@@ -0,0 +1,108 @@
+//! Polling of the sockets of the clients
+//!
+//! The sockets are registered in a single poller, epoll on Linux and Android and kqueue on the BSDs and
+//! macOS, whose file descriptor is the `poll_fd()` of the backend: it is readable whenever a client sent
+//! data or disconnected. The poller is level-triggered, a client stays ready until its socket is drained.
+
+use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use rustix::event::epoll;
+
+#[cfg(any(
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "macos"
+))]
+use rustix::event::kqueue;
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "macos"
+)))]
+compile_error!(
+    "The rust server backend requires epoll or kqueue, which this platform does not provide."
+);
+
+// Number of ready sources retrieved at once, the others are reported by the next call
+const MAX_EVENTS: usize = 32;
+
+/// The poller of the sockets, each registered with the `u64` identifying its client
+#[derive(Debug)]
+pub(crate) struct Poller {
+    fd: OwnedFd,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Poller {
+    pub(crate) fn new() -> std::io::Result<Self> {
+        Ok(Self { fd: epoll::create(epoll::CreateFlags::CLOEXEC)? })
+    }
+
+    /// Start polling a socket for reading
+    ///
+    /// It is removed automatically when it is closed.
+    pub(crate) fn add(&self, source: BorrowedFd, id: u64) -> std::io::Result<()> {
+        epoll::add(&self.fd, source, epoll::EventData::new_u64(id), epoll::EventFlags::IN)?;
+        Ok(())
+    }
+
+    /// The ids of the sources ready to be read, without blocking
+    pub(crate) fn ready(&self) -> std::io::Result<Vec<u64>> {
+        let mut events = epoll::EventVec::with_capacity(MAX_EVENTS);
+        epoll::wait(&self.fd, &mut events, 0)?;
+        Ok(events.iter().map(|event| event.data.u64()).collect())
+    }
+}
+
+#[cfg(any(
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "macos"
+))]
+impl Poller {
+    pub(crate) fn new() -> std::io::Result<Self> {
+        Ok(Self { fd: kqueue::kqueue()? })
+    }
+
+    /// Start polling a socket for reading
+    ///
+    /// It is removed automatically when it is closed.
+    pub(crate) fn add(&self, source: BorrowedFd, id: u64) -> std::io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let event = kqueue::Event::new(
+            kqueue::EventFilter::Read(source.as_raw_fd()),
+            kqueue::EventFlags::ADD | kqueue::EventFlags::RECEIPT,
+            id as isize,
+        );
+        // with no room for the receipt, a failed registration is reported as an error of the call
+        let mut receipts = Vec::new();
+        unsafe { kqueue::kevent(&self.fd, &[event], &mut receipts, None)? };
+        Ok(())
+    }
+
+    /// The ids of the sources ready to be read, without blocking
+    pub(crate) fn ready(&self) -> std::io::Result<Vec<u64>> {
+        let mut events = Vec::with_capacity(MAX_EVENTS);
+        unsafe {
+            kqueue::kevent(&self.fd, &[], &mut events, Some(std::time::Duration::ZERO))?;
+        }
+        Ok(events.iter().map(|event| event.udata() as u64).collect())
+    }
+}
+
+impl AsFd for Poller {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
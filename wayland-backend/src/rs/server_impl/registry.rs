@@ -8,7 +8,7 @@ use crate::types::server::{GlobalInfo, InvalidId};
 
 use super::{
     client::{Client, ClientStore},
-    ClientId, GlobalHandler, GlobalId, InnerGlobalId, InnerObjectId, ObjectId,
+    ClientId, GlobalHandler, GlobalId, GlobalRestriction, InnerGlobalId, InnerObjectId, ObjectId,
 };
 
 /*
@@ -23,6 +23,22 @@ struct Global<D: 'static> {
     version: u32,
     handler: Arc<dyn GlobalHandler<D>>,
     disabled: bool,
+    restriction: Option<GlobalRestriction>,
+}
+
+impl<D> Global<D> {
+    fn visible_to(&self, client: &Client<D>) -> bool {
+        if let Some(ref restriction) = self.restriction {
+            if !restriction.allows(client.get_credentials().as_ref()) {
+                return false;
+            }
+        }
+        self.handler.can_view(
+            ClientId { id: client.id.clone() },
+            &client.data,
+            GlobalId { id: self.id.clone() },
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -67,7 +83,14 @@ impl<D> Registry<D> {
 
         let id = InnerGlobalId { id: id as u32 + 1, serial };
 
-        *place = Some(Global { id: id.clone(), interface, version, handler, disabled: false });
+        *place = Some(Global {
+            id: id.clone(),
+            interface,
+            version,
+            handler,
+            disabled: false,
+            restriction: None,
+        });
 
         self.send_global_to_all(id.clone(), clients).unwrap();
 
@@ -91,6 +114,21 @@ impl<D> Registry<D> {
         })
     }
 
+    pub(crate) fn restrict_global(
+        &mut self,
+        id: InnerGlobalId,
+        restriction: Option<GlobalRestriction>,
+    ) -> Result<(), InvalidId> {
+        let global = self
+            .globals
+            .get_mut(id.id as usize - 1)
+            .and_then(|o| o.as_mut())
+            .filter(|o| o.id == id)
+            .ok_or(InvalidId)?;
+        global.restriction = restriction;
+        Ok(())
+    }
+
     pub(crate) fn get_handler(
         &self,
         id: InnerGlobalId,
@@ -116,11 +154,7 @@ impl<D> Registry<D> {
         if target_global.version < version {
             return None;
         }
-        if !target_global.handler.can_view(
-            ClientId { id: client.id.clone() },
-            &client.data,
-            GlobalId { id: target_global.id.clone() },
-        ) {
+        if !target_global.visible_to(client) {
             return None;
         }
 
@@ -178,13 +212,7 @@ impl<D> Registry<D> {
         client: &mut Client<D>,
     ) -> Result<(), InvalidId> {
         for global in self.globals.iter().flat_map(|opt| opt.as_ref()) {
-            if !global.disabled
-                && global.handler.can_view(
-                    ClientId { id: client.id.clone() },
-                    &client.data,
-                    GlobalId { id: global.id.clone() },
-                )
-            {
+            if !global.disabled && global.visible_to(client) {
                 // fail the whole send on error, there is no point in trying further on a failing client
                 send_global_to(client, global, ObjectId { id: registry.clone() })?;
             }
@@ -203,13 +231,7 @@ impl<D> Registry<D> {
         }
         for registry in self.known_registries.iter().cloned() {
             if let Ok(client) = clients.get_client_mut(registry.client_id.clone()) {
-                if !global.disabled
-                    && global.handler.can_view(
-                        ClientId { id: client.id.clone() },
-                        &client.data,
-                        GlobalId { id: global.id.clone() },
-                    )
-                {
+                if !global.disabled && global.visible_to(client) {
                     // don't fail the whole send for a single erroring client
                     let _ = send_global_to(client, global, ObjectId { id: registry.clone() });
                 }
@@ -5,6 +5,7 @@ use std::io::{ErrorKind, IoSlice, IoSliceMut, Result as IoResult};
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
 use std::os::unix::net::UnixStream;
 use std::slice;
+use std::sync::Arc;
 
 use rustix::io::retry_on_intr;
 use rustix::net::{
@@ -12,14 +13,28 @@ use rustix::net::{
     SendAncillaryBuffer, SendAncillaryMessage, SendFlags,
 };
 
-use crate::protocol::{ArgumentType, Message};
+use crate::metrics::MetricsCounters;
+use crate::protocol::{
+    Argument, ArgumentType, BorrowedMessage, Message, MessageTooLarge, TooManyFds, WireError,
+};
+use crate::types::client::BufferSizes;
+
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use rustix::io_uring::msghdr;
 
-use super::wire::{parse_message, write_to_buffers, MessageParseError, MessageWriteError};
+use super::pool::MessagePool;
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+use super::uring::{Batch, Op, Ring};
+use super::wire::{
+    message_size, parse_message, write_to_buffers, MessageParseError, MessageWriteError,
+};
 
 /// Maximum number of FD that can be sent in a single socket message
 pub const MAX_FDS_OUT: usize = 28;
 /// Maximum number of bytes that can be sent in a single socket message
 pub const MAX_BYTES_OUT: usize = 4096;
+/// Maximum size of a single wayland message, its length is encoded on 16 bits
+pub const MAX_MESSAGE_SIZE: usize = u16::MAX as usize;
 
 /*
  * Socket
@@ -132,33 +147,147 @@ pub struct BufferedSocket {
     in_fds: VecDeque<OwnedFd>,
     out_data: Buffer<u8>,
     out_fds: Vec<OwnedFd>,
+    max_size: usize,
+    max_fds: usize,
+    pub(crate) pool: MessagePool,
+    metrics: Arc<MetricsCounters>,
+    // the number of messages written and read so far
+    messages_out: u64,
+    messages_in: u64,
 }
 
 impl BufferedSocket {
     /// Wrap a Socket into a Buffered Socket
+    #[cfg(test)]
     pub fn new(socket: Socket) -> Self {
+        Self::with_buffer_sizes(socket, BufferSizes::default())
+    }
+
+    /// Wrap a Socket into a Buffered Socket, with given buffer sizes
+    #[cfg(test)]
+    pub fn with_buffer_sizes(socket: Socket, sizes: BufferSizes) -> Self {
+        Self::with_metrics(socket, sizes, Arc::default())
+    }
+
+    /// Wrap a Socket into a Buffered Socket, recording its activity in `metrics`
+    pub(crate) fn with_metrics(
+        socket: Socket,
+        sizes: BufferSizes,
+        metrics: Arc<MetricsCounters>,
+    ) -> Self {
+        // the incoming and outgoing buffers
+        metrics.buffer_allocation();
+        metrics.buffer_allocation();
         Self {
             socket,
-            in_data: Buffer::new(2 * MAX_BYTES_OUT), // Incoming buffers are twice as big in order to be
-            in_fds: VecDeque::new(),                 // able to store leftover data if needed
-            out_data: Buffer::new(MAX_BYTES_OUT),
+            in_data: Buffer::new(sizes.incoming),
+            in_fds: VecDeque::new(),
+            out_data: Buffer::new(sizes.outgoing),
             out_fds: Vec::new(),
+            max_size: sizes.max.max(sizes.incoming).max(sizes.outgoing),
+            max_fds: sizes.max_fds,
+            pool: MessagePool::new(metrics.clone()),
+            metrics,
+            messages_out: 0,
+            messages_in: 0,
         }
     }
 
+    /// Set the maximum number of file descriptors queued in each direction
+    pub fn set_max_fds(&mut self, max_fds: usize) {
+        self.max_fds = max_fds;
+    }
+
+    /// Set the maximum size the buffers can grow to
+    ///
+    /// The buffers never shrink, so the maximum is at least their current size.
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size.max(self.in_data.storage.len()).max(self.out_data.storage.len());
+    }
+
     /// Flush the contents of the outgoing buffer into the socket
+    ///
+    /// This only returns `Ok(())` once the whole buffer has been written. If the socket does not accept
+    /// all of it, a `WouldBlock` error is returned and the rest, which may end in the middle of a
+    /// message, stays buffered to be sent first by the next flush.
     pub fn flush(&mut self) -> IoResult<()> {
-        let written = {
-            let bytes = self.out_data.get_contents();
-            if bytes.is_empty() {
-                return Ok(());
-            }
-            self.socket.send_msg(bytes, &self.out_fds)?
-        };
+        while !self.out_data.get_contents().is_empty() {
+            let (bytes, fds) = self.next_chunk();
+            let written = self.socket.send_msg(bytes, fds)?;
+            let fds = fds.len();
+            self.consume_chunk(written, fds);
+        }
+        Ok(())
+    }
+
+    // internal method
+    //
+    // the next chunk of the outgoing buffer to send in a single socket message
+    //
+    // the receiving end accepts at most `MAX_FDS_OUT` fds per socket message, if more are pending
+    // they are sent along a single byte each, so that enough bytes remain for the others
+    fn next_chunk(&self) -> (&[u8], &[OwnedFd]) {
+        let bytes = self.out_data.get_contents();
+        if self.out_fds.len() > MAX_FDS_OUT {
+            (&bytes[..1], &self.out_fds[..MAX_FDS_OUT])
+        } else {
+            (bytes, &self.out_fds)
+        }
+    }
+
+    // internal method
+    //
+    // send the pending fds that do not fit in a single socket message
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    fn flush_extra_fds(&mut self) -> IoResult<()> {
+        while self.out_fds.len() > MAX_FDS_OUT {
+            let (bytes, fds) = self.next_chunk();
+            let written = self.socket.send_msg(bytes, fds)?;
+            self.consume_chunk(written, MAX_FDS_OUT);
+        }
+        Ok(())
+    }
+
+    // internal method
+    //
+    // remove a chunk that was sent from the outgoing buffer
+    fn consume_chunk(&mut self, written: usize, fds: usize) {
+        self.metrics.bytes_out(written);
         self.out_data.offset(written);
         self.out_data.move_to_front();
-        self.out_fds.clear();
-        Ok(())
+        self.out_fds.drain(..fds);
+        self.metrics.fds_released(fds);
+    }
+
+    /// Check whether the outgoing buffer contains data not yet written to the socket
+    pub fn has_pending_output(&self) -> bool {
+        !self.out_data.get_contents().is_empty()
+    }
+
+    /// Number of bytes in the outgoing buffer not yet written to the socket
+    pub fn pending_output_bytes(&self) -> usize {
+        self.out_data.get_contents().len()
+    }
+
+    /// Check whether the incoming buffer has room for more data
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    pub(crate) fn can_receive(&self) -> bool {
+        self.in_data.offset > 0 || self.in_data.occupied < self.in_data.storage.len()
+    }
+
+    /// Number of bytes and fds currently buffered in both directions
+    pub fn buffered(&self) -> (usize, usize) {
+        (
+            self.in_data.get_contents().len() + self.out_data.get_contents().len(),
+            self.in_fds.len() + self.out_fds.len(),
+        )
+    }
+
+    /// Approximate memory used by the buffers, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.in_data.storage.capacity()
+            + self.out_data.storage.capacity()
+            + (self.in_fds.capacity() + self.out_fds.capacity()) * std::mem::size_of::<OwnedFd>()
     }
 
     // internal method
@@ -168,10 +297,13 @@ impl BufferedSocket {
     //
     // if false is returned, it means there is not enough space
     // in the buffer
-    fn attempt_write_message(&mut self, msg: &Message<u32, RawFd>) -> IoResult<bool> {
+    fn attempt_write_message(&mut self, msg: &Message<u32, BorrowedFd<'_>>) -> IoResult<bool> {
         match write_to_buffers(msg, self.out_data.get_writable_storage(), &mut self.out_fds) {
             Ok(bytes_out) => {
                 self.out_data.advance(bytes_out);
+                let fds = msg.args.iter().filter(|arg| matches!(arg, Argument::Fd(_))).count();
+                self.metrics.message_out(fds);
+                self.metrics.fds_queued(fds);
                 Ok(true)
             }
             Err(MessageWriteError::BufferTooSmall) => Ok(false),
@@ -181,11 +313,31 @@ impl BufferedSocket {
 
     /// Write a message to the outgoing buffer
     ///
-    /// This method may flush the internal buffer if necessary (if it is full).
+    /// This method may flush the internal buffer if necessary (if it is full), and grows it if
+    /// the message still does not fit.
     ///
-    /// If the message is too big to fit in the buffer, the error `Error::Sys(E2BIG)`
-    /// will be returned.
-    pub fn write_message(&mut self, msg: &Message<u32, RawFd>) -> IoResult<()> {
+    /// If the message is too big to fit in the buffer even at its maximum size, a
+    /// [`MessageTooLarge`] error will be returned. If the message would exceed the maximum number of
+    /// queued file descriptors, a [`TooManyFds`] error will be returned.
+    pub fn write_message(&mut self, msg: &Message<u32, BorrowedFd<'_>>) -> IoResult<()> {
+        let size = message_size(msg);
+        let max = self.max_size.min(MAX_MESSAGE_SIZE);
+        if size > max {
+            return Err(MessageTooLarge { size, max }.into());
+        }
+        let fds = msg.args.iter().filter(|arg| matches!(arg, Argument::Fd(_))).count();
+        if self.out_fds.len() + fds > self.max_fds {
+            // try to make room by sending the pending fds
+            if let Err(e) = self.flush() {
+                if e.kind() != ErrorKind::WouldBlock {
+                    return Err(e);
+                }
+            }
+            let count = self.out_fds.len() + fds;
+            if count > self.max_fds {
+                return Err(TooManyFds { count, max: self.max_fds }.into());
+            }
+        }
         if !self.attempt_write_message(msg)? {
             // the attempt failed, there is not enough space in the buffer
             // we need to flush it
@@ -195,10 +347,55 @@ impl BufferedSocket {
                 }
             }
             if !self.attempt_write_message(msg)? {
-                // If this fails again, this means the message is too big
-                // to be transmitted at all
-                return Err(rustix::io::Errno::TOOBIG.into());
+                // If this fails again, the buffer must grow for the message to fit
+                let needed = self.out_data.get_contents().len() + size;
+                if needed > self.max_size {
+                    // the buffer is full of data the socket does not accept
+                    return Err(rustix::io::Errno::TOOBIG.into());
+                }
+                self.out_data.move_to_front();
+                self.out_data.grow(needed.next_power_of_two().min(self.max_size));
+                self.metrics.buffer_allocation();
+                if !self.attempt_write_message(msg)? {
+                    return Err(rustix::io::Errno::TOOBIG.into());
+                }
+            }
+        }
+        self.messages_out += 1;
+        Ok(())
+    }
+
+    /// The sequence number the next message written will have
+    ///
+    /// The messages are numbered from 1 in each direction, in the order they go through the socket.
+    pub fn next_sequence_out(&self) -> u64 {
+        self.messages_out + 1
+    }
+
+    /// The sequence number of the last message read, 0 if none was
+    pub fn last_sequence_in(&self) -> u64 {
+        self.messages_in
+    }
+
+    // internal method
+    //
+    // make room in the incoming buffer, growing it if it is full
+    fn prepare_incoming_buffer(&mut self) -> IoResult<()> {
+        self.in_data.move_to_front();
+        if self.in_data.get_writable_storage().is_empty() {
+            let size = self.in_data.storage.len();
+            if size >= self.max_size {
+                // the buffer is full of a single incomplete message
+                let data = self.in_data.get_contents();
+                let size = if data.len() >= 2 * 4 {
+                    (u32::from_ne_bytes([data[4], data[5], data[6], data[7]]) >> 16) as usize
+                } else {
+                    size
+                };
+                return Err(MessageTooLarge { size, max: self.max_size }.into());
             }
+            self.in_data.grow((2 * size).clamp(MAX_BYTES_OUT, self.max_size));
+            self.metrics.buffer_allocation();
         }
         Ok(())
     }
@@ -207,55 +404,395 @@ impl BufferedSocket {
     /// a new round of parsing.
     pub fn fill_incoming_buffers(&mut self) -> IoResult<()> {
         // reorganize the buffers
-        self.in_data.move_to_front();
+        self.prepare_incoming_buffer()?;
         // receive a message
+        let fds_before = self.in_fds.len();
         let in_bytes = {
             let bytes = self.in_data.get_writable_storage();
             self.socket.rcv_msg(bytes, &mut self.in_fds)?
         };
+        self.metrics.fds_queued(self.in_fds.len() - fds_before);
         if in_bytes == 0 {
             // the other end of the socket was closed
             return Err(rustix::io::Errno::PIPE.into());
         }
         // advance the storage
         self.in_data.advance(in_bytes);
+        self.metrics.bytes_in(in_bytes);
+        self.check_incoming_fds()
+    }
+
+    // internal method
+    //
+    // check that the peer did not send more fds than allowed
+    fn check_incoming_fds(&self) -> IoResult<()> {
+        if self.in_fds.len() > self.max_fds {
+            return Err(TooManyFds { count: self.in_fds.len(), max: self.max_fds }.into());
+        }
         Ok(())
     }
 
+    /// Check that a whole message is available in the incoming buffers socket
+    ///
+    /// Returns the signature of this message, as provided by the `signature` closure (see
+    /// [`read_one_message()`](BufferedSocket::read_one_message)), and its length in bytes.
+    pub fn peek_message<F>(
+        &self,
+        mut signature: F,
+    ) -> Result<(&'static [ArgumentType], usize), MessageParseError>
+    where
+        F: FnMut(u32, u16) -> Option<&'static [ArgumentType]>,
+    {
+        let data = self.in_data.get_contents();
+        if data.len() < 2 * 4 {
+            return Err(MessageParseError::MissingData);
+        }
+        let object_id = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
+        let word_2 = u32::from_ne_bytes([data[4], data[5], data[6], data[7]]);
+        let opcode = (word_2 & 0x0000_FFFF) as u16;
+        let len = (word_2 >> 16) as usize;
+        let sig = match signature(object_id, opcode) {
+            Some(sig) => sig,
+            // no signature found ?
+            None => {
+                return Err(MessageParseError::Malformed(WireError::UnknownOpcode {
+                    object_id,
+                    opcode,
+                }))
+            }
+        };
+        if len < 2 * 4 {
+            return Err(MessageParseError::Malformed(WireError::SizeMismatch {
+                object_id,
+                opcode,
+                size: len,
+            }));
+        } else if len > data.len() {
+            return Err(MessageParseError::MissingData);
+        }
+        let expected = sig.iter().filter(|x| matches!(x, ArgumentType::Fd)).count();
+        if expected > self.in_fds.len() {
+            // The file descriptors of a message are sent at the latest along with its last byte,
+            // they cannot arrive anymore once data following the message was received.
+            if len < data.len() {
+                return Err(MessageParseError::Malformed(WireError::FdCountMismatch {
+                    object_id,
+                    opcode,
+                    expected,
+                    received: self.in_fds.len(),
+                }));
+            }
+            return Err(MessageParseError::MissingFD);
+        }
+        Ok((sig, len))
+    }
+
+    /// Look for a complete message from `sender_id` with `opcode` in the incoming buffers, without
+    /// consuming anything
+    ///
+    /// The message is copied in `scratch`, as by [`read_one_message()`](Self::read_one_message).
+    /// Its signature must not contain file descriptors, as those cannot be attributed to it.
+    pub fn find_message<'s>(
+        &self,
+        sender_id: u32,
+        opcode: u16,
+        signature: &[ArgumentType],
+        scratch: &'s mut Vec<u8>,
+    ) -> Option<BorrowedMessage<'s, u32, OwnedFd>> {
+        let mut data = self.in_data.get_contents();
+        while data.len() >= 2 * 4 {
+            let object_id = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
+            let word_2 = u32::from_ne_bytes([data[4], data[5], data[6], data[7]]);
+            let len = (word_2 >> 16) as usize;
+            if len < 2 * 4 || len > data.len() {
+                return None;
+            }
+            if object_id == sender_id && (word_2 & 0x0000_FFFF) as u16 == opcode {
+                scratch.clear();
+                scratch.extend_from_slice(&data[..len]);
+                return parse_message(
+                    scratch,
+                    signature,
+                    &mut VecDeque::new(),
+                    smallvec::SmallVec::new(),
+                )
+                .ok()
+                .map(|(msg, _)| msg);
+            }
+            data = &data[len..];
+        }
+        None
+    }
+
     /// Read and deserialize a single message from the incoming buffers socket
     ///
     /// This method requires one closure that given an object id and an opcode,
     /// must provide the signature of the associated request/event, in the form of
     /// a `&'static [ArgumentType]`.
-    pub fn read_one_message<F>(
+    ///
+    /// The raw message is first copied in `scratch`, and the string and array arguments of the
+    /// returned message borrow it. Reusing the same `scratch` buffer for all messages thus avoids
+    /// any allocation once it is large enough. The argument lists too long to be stored inline are
+    /// taken from the [`pool`](BufferedSocket::pool), give them back with
+    /// [`MessagePool::recycle_args()`] once dispatched.
+    pub fn read_one_message<'s, F>(
         &mut self,
-        mut signature: F,
-    ) -> Result<Message<u32, OwnedFd>, MessageParseError>
+        signature: F,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<BorrowedMessage<'s, u32, OwnedFd>, MessageParseError>
     where
         F: FnMut(u32, u16) -> Option<&'static [ArgumentType]>,
     {
-        let (msg, read_data) = {
-            let data = self.in_data.get_contents();
-            if data.len() < 2 * 4 {
-                return Err(MessageParseError::MissingData);
-            }
-            let object_id = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
-            let word_2 = u32::from_ne_bytes([data[4], data[5], data[6], data[7]]);
-            let opcode = (word_2 & 0x0000_FFFF) as u16;
-            if let Some(sig) = signature(object_id, opcode) {
-                match parse_message(data, sig, &mut self.in_fds) {
-                    Ok((msg, rest_data)) => (msg, data.len() - rest_data.len()),
-                    Err(e) => return Err(e),
+        let (sig, len) = self.peek_message(signature)?;
+
+        scratch.clear();
+        scratch.extend_from_slice(&self.in_data.get_contents()[..len]);
+        let fds_before = self.in_fds.len();
+        let args = self.pool.take_args(sig.len());
+        let parsed = parse_message(scratch, sig, &mut self.in_fds, args);
+        self.metrics.fds_released(fds_before - self.in_fds.len());
+        let (msg, _) = parsed?;
+
+        self.in_data.offset(len);
+        self.messages_in += 1;
+        self.metrics.message_in(sig.iter().filter(|x| matches!(x, ArgumentType::Fd)).count());
+
+        Ok(msg)
+    }
+}
+
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+impl BufferedSocket {
+    /// Flush the outgoing buffers of several sockets, submitting all the writes at once
+    ///
+    /// Returns the result of the flush of each socket, in the same order as `sockets`. If the ring
+    /// fails, the writes it completed are still accounted for and the sockets it did not reach are
+    /// flushed with regular syscalls instead.
+    pub(crate) fn flush_batch(
+        ring: &mut Ring,
+        sockets: &mut [&mut BufferedSocket],
+    ) -> Batch<IoResult<()>> {
+        // the outcome of the sockets that are not part of the batch
+        let outcomes = sockets
+            .iter_mut()
+            .map(|socket| {
+                // send the extra fds first, whatever is left is sent with the batch
+                if let Err(e) = socket.flush_extra_fds() {
+                    return Some(Err(e));
                 }
-            } else {
-                // no signature found ?
-                return Err(MessageParseError::Malformed);
-            }
+                if socket.has_pending_output() {
+                    None
+                } else {
+                    Some(Ok(()))
+                }
+            })
+            .collect::<Vec<_>>();
+        let batched = || {
+            sockets.iter().zip(&outcomes).filter(|(_, outcome)| outcome.is_none()).map(|(s, _)| s)
         };
+        // the headers point into `slots`, it must not reallocate once they are set
+        let mut slots = Vec::with_capacity(batched().count());
+        for socket in batched() {
+            let (bytes, fds) = socket.next_chunk();
+            let mut control = vec![0usize; uring_cmsg::space(fds.len())];
+            uring_cmsg::write_fds(&mut control, fds);
+            slots.push((UringMsg::new(bytes.as_ptr() as *mut u8, bytes.len(), control), fds.len()));
+        }
 
-        self.in_data.offset(read_data);
+        let sent_fds = slots.iter().map(|(_, fds)| *fds).collect::<Vec<_>>();
+        let ops = slots
+            .iter_mut()
+            .zip(batched())
+            .map(|((slot, _), socket)| {
+                Op::SendMsg(
+                    socket.as_raw_fd(),
+                    slot.header(),
+                    SendFlags::DONTWAIT | SendFlags::NOSIGNAL,
+                )
+            })
+            .collect::<Vec<_>>();
+        // Safety: the sockets and the buffers of the slots outlive the operations
+        let batch = unsafe { ring.run(&ops) };
+        drop(ops);
+        drop(slots);
+
+        let mut sent = batch.results.into_iter().zip(sent_fds);
+        let results = sockets
+            .iter_mut()
+            .zip(outcomes)
+            .map(|(socket, outcome)| {
+                if let Some(outcome) = outcome {
+                    return outcome;
+                }
+                let (result, fds) = sent.next().unwrap();
+                // if the ring failed before this write, it is done by the flush below
+                if let Some(result) = result {
+                    socket.consume_chunk(result?, fds);
+                }
+                // write what the socket did not accept, if anything
+                socket.flush()
+            })
+            .collect();
+        Batch { results, error: batch.error }
+    }
 
-        Ok(msg)
+    /// Fill the incoming buffers of several sockets, submitting all the reads at once
+    ///
+    /// Returns the result of the read of each socket, in the same order as `sockets`. Like
+    /// [`fill_incoming_buffers()`](BufferedSocket::fill_incoming_buffers), this errors with
+    /// `WouldBlock` for sockets that had nothing to read. If the ring fails, the data it received is
+    /// still kept and the sockets it did not reach are read with regular syscalls instead.
+    pub(crate) fn fill_incoming_buffers_batch(
+        ring: &mut Ring,
+        sockets: &mut [&mut BufferedSocket],
+    ) -> Batch<IoResult<()>> {
+        let mut slots = Vec::with_capacity(sockets.len());
+        for socket in sockets.iter_mut() {
+            socket.in_data.move_to_front();
+            let storage = socket.in_data.get_writable_storage();
+            let control = vec![0usize; uring_cmsg::space(MAX_FDS_OUT)];
+            slots.push(UringMsg::new(storage.as_mut_ptr(), storage.len(), control));
+        }
+
+        let ops = slots
+            .iter_mut()
+            .zip(sockets.iter())
+            .map(|(slot, socket)| {
+                Op::RecvMsg(
+                    socket.as_raw_fd(),
+                    slot.header(),
+                    RecvFlags::DONTWAIT | RecvFlags::CMSG_CLOEXEC,
+                )
+            })
+            .collect::<Vec<_>>();
+        // Safety: the sockets and the buffers of the slots outlive the operations
+        let batch = unsafe { ring.run(&ops) };
+        drop(ops);
+
+        let results = sockets
+            .iter_mut()
+            .zip(slots)
+            .zip(batch.results)
+            .map(|((socket, slot), result)| {
+                let Some(result) = result else {
+                    // the ring failed before this read
+                    return socket.fill_incoming_buffers();
+                };
+                let in_bytes = result?;
+                // file descriptors may have been received even if the other end closed the socket
+                let fds_before = socket.in_fds.len();
+                uring_cmsg::read_fds(&slot.control, slot.hdr.msg_controllen, &mut socket.in_fds);
+                socket.metrics.fds_queued(socket.in_fds.len() - fds_before);
+                if in_bytes == 0 {
+                    // the other end of the socket was closed
+                    return Err(rustix::io::Errno::PIPE.into());
+                }
+                socket.in_data.advance(in_bytes);
+                socket.metrics.bytes_in(in_bytes);
+                socket.check_incoming_fds()
+            })
+            .collect();
+        Batch { results, error: batch.error }
+    }
+}
+
+// A socket message prepared for io_uring
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+struct UringMsg {
+    iov: IoSliceMut<'static>,
+    // stored as usize for the alignment of the control messages
+    control: Vec<usize>,
+    hdr: msghdr,
+}
+
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+impl UringMsg {
+    fn new(data: *mut u8, len: usize, control: Vec<usize>) -> Self {
+        Self {
+            // Safety: the message is only used while the buffer is borrowed by the caller
+            iov: IoSliceMut::new(unsafe { slice::from_raw_parts_mut(data, len) }),
+            control,
+            hdr: unsafe { std::mem::zeroed() },
+        }
+    }
+
+    /// Points the header to the buffers of this message, it must not move afterwards
+    fn header(&mut self) -> *mut msghdr {
+        self.hdr.msg_iov = &mut self.iov as *mut IoSliceMut as *mut _;
+        self.hdr.msg_iovlen = 1;
+        if self.control.is_empty() {
+            self.hdr.msg_control = std::ptr::null_mut();
+            self.hdr.msg_controllen = 0;
+        } else {
+            self.hdr.msg_control = self.control.as_mut_ptr().cast();
+            self.hdr.msg_controllen = (self.control.len() * std::mem::size_of::<usize>()) as _;
+        }
+        &mut self.hdr
+    }
+}
+
+// Encoding of SCM_RIGHTS control messages, see cmsg(3)
+#[cfg(all(feature = "io_uring", target_os = "linux"))]
+mod uring_cmsg {
+    use std::collections::VecDeque;
+    use std::mem::size_of;
+    use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+    use linux_raw_sys::net::{cmsghdr, SCM_RIGHTS, SOL_SOCKET};
+
+    const fn align(len: usize) -> usize {
+        (len + size_of::<usize>() - 1) & !(size_of::<usize>() - 1)
+    }
+
+    const HEADER_LEN: usize = align(size_of::<cmsghdr>());
+
+    /// Size of the control buffer needed for `fds` file descriptors, in `usize`
+    pub(super) fn space(fds: usize) -> usize {
+        if fds == 0 {
+            0
+        } else {
+            (HEADER_LEN + align(fds * size_of::<RawFd>())) / size_of::<usize>()
+        }
+    }
+
+    pub(super) fn write_fds(buffer: &mut [usize], fds: &[OwnedFd]) {
+        if fds.is_empty() {
+            return;
+        }
+        let base = buffer.as_mut_ptr().cast::<u8>();
+        unsafe {
+            base.cast::<cmsghdr>().write(cmsghdr {
+                cmsg_len: HEADER_LEN + fds.len() * size_of::<RawFd>(),
+                cmsg_level: SOL_SOCKET as _,
+                cmsg_type: SCM_RIGHTS as _,
+            });
+            let data = base.add(HEADER_LEN).cast::<RawFd>();
+            for (i, fd) in fds.iter().enumerate() {
+                data.add(i).write_unaligned(fd.as_raw_fd());
+            }
+        }
+    }
+
+    pub(super) fn read_fds(buffer: &[usize], len: usize, fds: &mut VecDeque<OwnedFd>) {
+        let len = len.min(std::mem::size_of_val(buffer));
+        let base = buffer.as_ptr().cast::<u8>();
+        let mut offset = 0;
+        while offset + HEADER_LEN <= len {
+            let header = unsafe { base.add(offset).cast::<cmsghdr>().read_unaligned() };
+            let cmsg_len = header.cmsg_len;
+            if cmsg_len < HEADER_LEN || offset + cmsg_len > len {
+                break;
+            }
+            if header.cmsg_level as u32 == SOL_SOCKET && header.cmsg_type as u32 == SCM_RIGHTS {
+                let count = (cmsg_len - HEADER_LEN) / size_of::<RawFd>();
+                let data = unsafe { base.add(offset + HEADER_LEN).cast::<RawFd>() };
+                for i in 0..count {
+                    let fd = unsafe { data.add(i).read_unaligned() };
+                    fds.push_back(unsafe { OwnedFd::from_raw_fd(fd) });
+                }
+            }
+            offset += align(cmsg_len);
+        }
     }
 }
 
@@ -265,6 +802,12 @@ impl AsRawFd for BufferedSocket {
     }
 }
 
+impl Drop for BufferedSocket {
+    fn drop(&mut self) {
+        self.metrics.fds_released(self.in_fds.len() + self.out_fds.len());
+    }
+}
+
 impl AsFd for BufferedSocket {
     fn as_fd(&self) -> BorrowedFd<'_> {
         self.socket.as_fd()
@@ -316,6 +859,13 @@ impl<T: Copy + Default> Buffer<T> {
         &mut self.storage[(self.occupied)..]
     }
 
+    /// Grow the storage of the buffer to `size`
+    fn grow(&mut self, size: usize) {
+        if size > self.storage.len() {
+            self.storage.resize(size, T::default());
+        }
+    }
+
     /// Move the unread contents of the buffer to the front, to ensure
     /// maximal write space availability
     fn move_to_front(&mut self) {
@@ -330,7 +880,9 @@ impl<T: Copy + Default> Buffer<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::{AllowNull, Argument, ArgumentType, Message};
+    use crate::protocol::{
+        AllowNull, Argument, ArgumentType, Message, MessageTooLarge, TooManyFds, WireError,
+    };
 
     use std::ffi::CString;
     use std::os::unix::io::BorrowedFd;
@@ -401,16 +953,19 @@ mod tests {
 
         server.fill_incoming_buffers().unwrap();
 
-        let ret_msg =
-            server
-                .read_one_message(|sender_id, opcode| {
+        let ret_msg = server
+            .read_one_message(
+                |sender_id, opcode| {
                     if sender_id == 42 && opcode == 7 {
                         Some(SIGNATURE)
                     } else {
                         None
                     }
-                })
-                .unwrap();
+                },
+                &mut Vec::new(),
+            )
+            .unwrap()
+            .into_owned();
 
         assert_eq_msgs(&msg.map_fd(|fd| fd.as_raw_fd()), &ret_msg.map_fd(IntoRawFd::into_raw_fd));
     }
@@ -421,8 +976,8 @@ mod tests {
             sender_id: 42,
             opcode: 7,
             args: smallvec![
-                Argument::Fd(1), // stdin
-                Argument::Fd(0), // stdout
+                Argument::Fd(rustix::stdio::stdout()),
+                Argument::Fd(rustix::stdio::stdin()),
             ],
         };
 
@@ -437,19 +992,215 @@ mod tests {
 
         server.fill_incoming_buffers().unwrap();
 
-        let ret_msg =
-            server
-                .read_one_message(|sender_id, opcode| {
+        let ret_msg = server
+            .read_one_message(
+                |sender_id, opcode| {
                     if sender_id == 42 && opcode == 7 {
                         Some(SIGNATURE)
                     } else {
                         None
                     }
-                })
-                .unwrap();
+                },
+                &mut Vec::new(),
+            )
+            .unwrap()
+            .into_owned();
         assert_eq_msgs(&msg.map_fd(|fd| fd.as_raw_fd()), &ret_msg.map_fd(IntoRawFd::into_raw_fd));
     }
 
+    fn is_cloexec(fd: &OwnedFd) -> bool {
+        rustix::io::fcntl_getfd(fd).unwrap().contains(rustix::io::FdFlags::CLOEXEC)
+    }
+
+    #[test]
+    fn fds_cloexec() {
+        let msg = Message {
+            sender_id: 42,
+            opcode: 7,
+            args: smallvec![Argument::Fd(rustix::stdio::stdin())],
+        };
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let mut client = BufferedSocket::new(Socket::from(client));
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        // neither the copies of the sent fds nor the received fds may be inherited by child processes
+        client.write_message(&msg).unwrap();
+        assert!(client.out_fds.iter().all(is_cloexec));
+        client.flush().unwrap();
+        server.fill_incoming_buffers().unwrap();
+        assert_eq!(server.in_fds.len(), 1);
+        assert!(server.in_fds.iter().all(is_cloexec));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn fds_in_flight() {
+        let msg = Message {
+            sender_id: 42,
+            opcode: 7,
+            args: smallvec![Argument::Fd(rustix::stdio::stdin())],
+        };
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Fd];
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let client_metrics = Arc::new(MetricsCounters::default());
+        let server_metrics = Arc::new(MetricsCounters::default());
+        let sizes = BufferSizes::default();
+        let mut client =
+            BufferedSocket::with_metrics(Socket::from(client), sizes, client_metrics.clone());
+        let mut server =
+            BufferedSocket::with_metrics(Socket::from(server), sizes, server_metrics.clone());
+        let in_flight = |metrics: &MetricsCounters| metrics.snapshot().fds_in_flight;
+
+        client.write_message(&msg).unwrap();
+        client.write_message(&msg).unwrap();
+        assert_eq!(in_flight(&client_metrics), 2);
+        client.flush().unwrap();
+        server.fill_incoming_buffers().unwrap();
+        assert_eq!(in_flight(&client_metrics), 0);
+        assert_eq!(in_flight(&server_metrics), 2);
+
+        server.read_one_message(|_, _| Some(SIGNATURE), &mut Vec::new()).unwrap();
+        assert_eq!(in_flight(&server_metrics), 1);
+
+        // the fds still buffered are closed with the sockets
+        client.write_message(&msg).unwrap();
+        drop((client, server));
+        assert_eq!(in_flight(&client_metrics), 0);
+        assert_eq!(in_flight(&server_metrics), 0);
+    }
+
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    #[test]
+    fn write_read_cycle_io_uring() {
+        let Ok(mut ring) = Ring::new() else {
+            // io_uring is not available in this environment
+            return;
+        };
+
+        let msg = Message {
+            sender_id: 42,
+            opcode: 7,
+            args: smallvec![
+                Argument::Uint(3),
+                Argument::Fd(rustix::stdio::stdout()),
+                Argument::Fd(rustix::stdio::stdin()),
+            ],
+        };
+        static SIGNATURE: &[ArgumentType] =
+            &[ArgumentType::Uint, ArgumentType::Fd, ArgumentType::Fd];
+
+        let (client1, server1) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let (client2, server2) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let mut client1 = BufferedSocket::new(Socket::from(client1));
+        let mut client2 = BufferedSocket::new(Socket::from(client2));
+        let mut server1 = BufferedSocket::new(Socket::from(server1));
+        let mut server2 = BufferedSocket::new(Socket::from(server2));
+
+        client1.write_message(&msg).unwrap();
+        client2.write_message(&msg).unwrap();
+        client2.write_message(&msg).unwrap();
+        let batch = BufferedSocket::flush_batch(&mut ring, &mut [&mut client1, &mut client2]);
+        assert!(batch.error.is_none());
+        assert!(batch.results.iter().all(Result::is_ok));
+        assert!(!client1.has_pending_output() && !client2.has_pending_output());
+
+        let batch = BufferedSocket::fill_incoming_buffers_batch(
+            &mut ring,
+            &mut [&mut server1, &mut server2],
+        );
+        assert!(batch.error.is_none());
+        assert!(batch.results.iter().all(Result::is_ok));
+
+        for (server, count) in [(&mut server1, 1), (&mut server2, 2)] {
+            for _ in 0..count {
+                let ret_msg = server
+                    .read_one_message(
+                        |sender_id, opcode| {
+                            if sender_id == 42 && opcode == 7 {
+                                Some(SIGNATURE)
+                            } else {
+                                None
+                            }
+                        },
+                        &mut Vec::new(),
+                    )
+                    .unwrap()
+                    .into_owned();
+                assert_eq_msgs(
+                    &msg.clone().map_fd(|fd| fd.as_raw_fd()),
+                    &ret_msg.map_fd(IntoRawFd::into_raw_fd),
+                );
+            }
+        }
+
+        // nothing left to read
+        let batch = BufferedSocket::fill_incoming_buffers_batch(&mut ring, &mut [&mut server1]);
+        assert_eq!(batch.results[0].as_ref().unwrap_err().kind(), ErrorKind::WouldBlock);
+    }
+
+    #[cfg(all(feature = "io_uring", target_os = "linux"))]
+    #[test]
+    fn io_uring_failing_partway() {
+        let (Ok(mut send_ring), Ok(mut recv_ring)) = (Ring::new(), Ring::new()) else {
+            // io_uring is not available in this environment
+            return;
+        };
+
+        let msg = Message {
+            sender_id: 42,
+            opcode: 7,
+            args: smallvec![Argument::Uint(3), Argument::Fd(rustix::stdio::stdout())],
+        };
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Uint, ArgumentType::Fd];
+
+        // more sockets than the ring has entries, so that the batch is submitted in two parts
+        let (mut clients, mut servers): (Vec<_>, Vec<_>) = (0..65)
+            .map(|_| {
+                let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+                (
+                    BufferedSocket::new(Socket::from(client)),
+                    BufferedSocket::new(Socket::from(server)),
+                )
+            })
+            .unzip();
+
+        // the first submission succeeds and the second one fails
+        send_ring.fail_after = Some(1);
+        for client in &mut clients {
+            client.write_message(&msg).unwrap();
+        }
+        let batch = BufferedSocket::flush_batch(
+            &mut send_ring,
+            &mut clients.iter_mut().collect::<Vec<_>>(),
+        );
+        assert!(batch.error.is_some());
+        assert!(batch.results.iter().all(Result::is_ok));
+        assert!(clients.iter().all(|client| !client.has_pending_output()));
+
+        recv_ring.fail_after = Some(1);
+        let batch = BufferedSocket::fill_incoming_buffers_batch(
+            &mut recv_ring,
+            &mut servers.iter_mut().collect::<Vec<_>>(),
+        );
+        assert!(batch.error.is_some());
+        assert!(batch.results.iter().all(Result::is_ok));
+
+        // every message was received exactly once, by the ring or by the fallback
+        for server in &mut servers {
+            let ret_msg = server
+                .read_one_message(|_, _| Some(SIGNATURE), &mut Vec::new())
+                .unwrap()
+                .into_owned();
+            assert_eq_msgs(
+                &msg.clone().map_fd(|fd| fd.as_raw_fd()),
+                &ret_msg.map_fd(IntoRawFd::into_raw_fd),
+            );
+            assert_eq!(server.fill_incoming_buffers().unwrap_err().kind(), ErrorKind::WouldBlock);
+        }
+    }
+
     #[test]
     fn write_read_cycle_multiple() {
         let messages = vec![
@@ -465,17 +1216,14 @@ mod tests {
                 sender_id: 42,
                 opcode: 1,
                 args: smallvec![
-                    Argument::Fd(1), // stdin
-                    Argument::Fd(0), // stdout
+                    Argument::Fd(rustix::stdio::stdout()),
+                    Argument::Fd(rustix::stdio::stdin()),
                 ],
             },
             Message {
                 sender_id: 42,
                 opcode: 2,
-                args: smallvec![
-                    Argument::Uint(3),
-                    Argument::Fd(2), // stderr
-                ],
+                args: smallvec![Argument::Uint(3), Argument::Fd(rustix::stdio::stderr()),],
             },
         ];
 
@@ -497,14 +1245,18 @@ mod tests {
         server.fill_incoming_buffers().unwrap();
 
         let mut recv_msgs = Vec::new();
-        while let Ok(message) = server.read_one_message(|sender_id, opcode| {
-            if sender_id == 42 {
-                Some(SIGNATURES[opcode as usize])
-            } else {
-                None
-            }
-        }) {
-            recv_msgs.push(message);
+        let mut scratch = Vec::new();
+        while let Ok(message) = server.read_one_message(
+            |sender_id, opcode| {
+                if sender_id == 42 {
+                    Some(SIGNATURES[opcode as usize])
+                } else {
+                    None
+                }
+            },
+            &mut scratch,
+        ) {
+            recv_msgs.push(message.into_owned());
         }
         assert_eq!(recv_msgs.len(), 3);
         for (msg1, msg2) in messages.into_iter().zip(recv_msgs.into_iter()) {
@@ -536,17 +1288,277 @@ mod tests {
 
         server.fill_incoming_buffers().unwrap();
 
-        let ret_msg =
-            server
-                .read_one_message(|sender_id, opcode| {
+        let ret_msg = server
+            .read_one_message(
+                |sender_id, opcode| {
                     if sender_id == 2 && opcode == 0 {
                         Some(SIGNATURE)
                     } else {
                         None
                     }
-                })
-                .unwrap();
+                },
+                &mut Vec::new(),
+            )
+            .unwrap()
+            .into_owned();
 
         assert_eq_msgs(&msg.map_fd(|fd| fd.as_raw_fd()), &ret_msg.map_fd(IntoRawFd::into_raw_fd));
     }
+
+    #[test]
+    fn write_read_cycle_large_message() {
+        let msg = Message {
+            sender_id: 42,
+            opcode: 0,
+            args: smallvec![Argument::Array(Box::new(vec![7; 20_000]))],
+        };
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let mut client = BufferedSocket::new(Socket::from(client));
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        // the buffers grow to fit the message
+        client.write_message(&msg).unwrap();
+        client.flush().unwrap();
+
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Array];
+
+        let mut scratch = Vec::new();
+        let ret_msg = loop {
+            match server.read_one_message(|_, _| Some(SIGNATURE), &mut scratch) {
+                Ok(msg) => break msg.into_owned(),
+                Err(MessageParseError::MissingData) => server.fill_incoming_buffers().unwrap(),
+                Err(e) => panic!("{}", e),
+            }
+        };
+
+        assert_eq_msgs(&msg.map_fd(|fd| fd.as_raw_fd()), &ret_msg.map_fd(IntoRawFd::into_raw_fd));
+    }
+
+    #[test]
+    fn message_too_large() {
+        let msg = Message {
+            sender_id: 42,
+            opcode: 0,
+            args: smallvec![Argument::Array(Box::new(vec![7; 10_000]))],
+        };
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let sizes = BufferSizes { max: 8192, ..Default::default() };
+        let mut client = BufferedSocket::with_buffer_sizes(Socket::from(client), sizes);
+        let mut server = BufferedSocket::with_buffer_sizes(Socket::from(server), sizes);
+
+        // the message cannot be sent
+        let err = client.write_message(&msg).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<MessageTooLarge>(),
+            Some(&MessageTooLarge { size: 10_012, max: 8192 })
+        );
+
+        // nor received from a peer with larger buffers
+        let mut client =
+            BufferedSocket::new(Socket::from(client.socket.stream.try_clone().unwrap()));
+        client.write_message(&msg).unwrap();
+        client.flush().unwrap();
+
+        let err = loop {
+            if let Err(e) = server.fill_incoming_buffers() {
+                break e;
+            }
+        };
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<MessageTooLarge>(),
+            Some(&MessageTooLarge { size: 10_012, max: 8192 })
+        );
+    }
+
+    #[test]
+    fn write_read_cycle_many_fds() {
+        // more fds than fit in a single socket message
+        let msg = Message {
+            sender_id: 42,
+            opcode: 0,
+            args: (0..2 * MAX_FDS_OUT + 3).map(|_| Argument::Fd(rustix::stdio::stdout())).collect(),
+        };
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let mut client = BufferedSocket::new(Socket::from(client));
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        client.write_message(&msg).unwrap();
+        client.flush().unwrap();
+        assert!(!client.has_pending_output());
+
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Fd; 2 * MAX_FDS_OUT + 3];
+
+        let mut scratch = Vec::new();
+        let ret_msg = loop {
+            match server.read_one_message(|_, _| Some(SIGNATURE), &mut scratch) {
+                Ok(msg) => break msg.into_owned(),
+                Err(MessageParseError::MissingData) | Err(MessageParseError::MissingFD) => {
+                    server.fill_incoming_buffers().unwrap()
+                }
+                Err(e) => panic!("{}", e),
+            }
+        };
+
+        assert_eq!(ret_msg.args.len(), 2 * MAX_FDS_OUT + 3);
+        for arg in ret_msg.args {
+            let Argument::Fd(fd) = arg else { panic!("Bad argument list !") };
+            assert!(same_file(fd.as_fd(), rustix::stdio::stdout()));
+        }
+    }
+
+    #[test]
+    fn too_many_fds() {
+        let msg = Message {
+            sender_id: 42,
+            opcode: 0,
+            args: smallvec![Argument::Fd(rustix::stdio::stdout())],
+        };
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let mut client = BufferedSocket::new(Socket::from(client));
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        // the pending fds are flushed to make room for new ones
+        client.set_max_fds(3);
+        for _ in 0..6 {
+            client.write_message(&msg).unwrap();
+        }
+        client.flush().unwrap();
+
+        // the receiving end accepts at most 4 queued fds
+        server.set_max_fds(4);
+        let err = loop {
+            if let Err(e) = server.fill_incoming_buffers() {
+                break e;
+            }
+        };
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<TooManyFds>(),
+            Some(&TooManyFds { count: 6, max: 4 })
+        );
+    }
+
+    #[test]
+    fn partial_flush() {
+        let msg = Message {
+            sender_id: 42,
+            opcode: 0,
+            args: smallvec![Argument::Array(Box::new(vec![7; 3000]))],
+        };
+        let msg_size = message_size(&msg);
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        rustix::net::sockopt::set_socket_send_buffer_size(&client, 4096).unwrap();
+        let sizes = BufferSizes { max: 1 << 20, ..Default::default() };
+        let mut client = BufferedSocket::with_buffer_sizes(Socket::from(client), sizes);
+        let mut server = BufferedSocket::new(Socket::from(server));
+
+        for _ in 0..20 {
+            client.write_message(&msg).unwrap();
+        }
+        // the socket cannot take everything, the rest stays buffered
+        let err = client.flush().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert!(client.pending_output_bytes() > 0);
+        assert!(client.pending_output_bytes() < 20 * msg_size);
+
+        static SIGNATURE: &[ArgumentType] = &[ArgumentType::Array];
+
+        let mut scratch = Vec::new();
+        let mut received = 0;
+        while received < 20 {
+            match server.read_one_message(|_, _| Some(SIGNATURE), &mut scratch) {
+                Ok(ret_msg) => {
+                    assert_eq_msgs(
+                        &msg.clone().map_fd(|fd| fd.as_raw_fd()),
+                        &ret_msg.into_owned().map_fd(IntoRawFd::into_raw_fd),
+                    );
+                    received += 1;
+                }
+                Err(MessageParseError::MissingData) => {
+                    // resume the flush where it stopped
+                    match client.flush() {
+                        Ok(()) => {}
+                        Err(e) => assert_eq!(e.kind(), ErrorKind::WouldBlock),
+                    }
+                    server.fill_incoming_buffers().unwrap();
+                }
+                Err(e) => panic!("{}", e),
+            }
+        }
+        assert_eq!(client.pending_output_bytes(), 0);
+    }
+
+    // receive the raw words in a socket, as if they were sent by a peer
+    fn receive_raw(words: &[u32]) -> BufferedSocket {
+        use std::io::Write;
+
+        let (client, server) = ::std::os::unix::net::UnixStream::pair().unwrap();
+        let bytes = words.iter().flat_map(|w| w.to_ne_bytes()).collect::<Vec<u8>>();
+        (&client).write_all(&bytes).unwrap();
+        let mut server = BufferedSocket::new(Socket::from(server));
+        server.fill_incoming_buffers().unwrap();
+        server
+    }
+
+    fn parse_error(
+        socket: &mut BufferedSocket,
+        signature: Option<&'static [ArgumentType]>,
+    ) -> WireError {
+        match socket.read_one_message(|_, _| signature, &mut Vec::new()) {
+            Err(MessageParseError::Malformed(e)) => e,
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(msg) => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn wire_errors() {
+        static UINT: &[ArgumentType] = &[ArgumentType::Uint];
+        static STR: &[ArgumentType] = &[ArgumentType::Str(AllowNull::No)];
+        static FD: &[ArgumentType] = &[ArgumentType::Fd];
+
+        let mut socket = receive_raw(&[3, (8 << 16) | 2]);
+        assert_eq!(
+            parse_error(&mut socket, None),
+            WireError::UnknownOpcode { object_id: 3, opcode: 2 }
+        );
+
+        let mut socket = receive_raw(&[3, (4 << 16) | 2]);
+        assert_eq!(
+            parse_error(&mut socket, Some(UINT)),
+            WireError::SizeMismatch { object_id: 3, opcode: 2, size: 4 }
+        );
+
+        // the argument does not fit in the declared size
+        let mut socket = receive_raw(&[3, (8 << 16) | 2, 42]);
+        assert_eq!(
+            parse_error(&mut socket, Some(UINT)),
+            WireError::SizeMismatch { object_id: 3, opcode: 2, size: 8 }
+        );
+
+        // a string without its nul terminator
+        let mut socket = receive_raw(&[3, (16 << 16) | 2, 4, u32::from_ne_bytes(*b"abcd")]);
+        assert_eq!(
+            parse_error(&mut socket, Some(STR)),
+            WireError::InvalidString { object_id: 3, opcode: 2, arg: 0 }
+        );
+
+        // the fd may still be on its way as long as nothing follows the message
+        let mut socket = receive_raw(&[3, (8 << 16) | 2]);
+        assert!(matches!(
+            socket.read_one_message(|_, _| Some(FD), &mut Vec::new()),
+            Err(MessageParseError::MissingFD)
+        ));
+        let mut socket = receive_raw(&[3, (8 << 16) | 2, 3, (8 << 16) | 2]);
+        assert_eq!(
+            parse_error(&mut socket, Some(FD)),
+            WireError::FdCountMismatch { object_id: 3, opcode: 2, expected: 1, received: 0 }
+        );
+    }
 }
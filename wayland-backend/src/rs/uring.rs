@@ -0,0 +1,172 @@
+//! Batched socket I/O using io_uring
+//!
+//! This is a minimal wrapper around a single io_uring instance, only able to submit a batch of
+//! `sendmsg`/`recvmsg` operations and wait for all of them to complete. The operations are expected
+//! to be non-blocking (`MSG_DONTWAIT`), so that waiting for their completion never actually blocks.
+
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::os::unix::io::{AsRawFd, BorrowedFd, RawFd};
+use std::ptr;
+
+use io_uring::{opcode, types, IoUring};
+use rustix::io::Errno;
+use rustix::io_uring::{io_uring_enter, msghdr, IoringEnterFlags, RecvFlags, SendFlags};
+
+/// Number of submission queue entries of the ring, larger batches are split
+const RING_ENTRIES: u32 = 64;
+
+/// A socket operation to submit to the ring
+///
+/// The `msghdr` and everything it points to must stay valid until [`Ring::run()`] returns.
+#[derive(Debug)]
+pub(crate) enum Op {
+    SendMsg(RawFd, *const msghdr, SendFlags),
+    RecvMsg(RawFd, *mut msghdr, RecvFlags),
+}
+
+/// The outcome of a batch of operations
+#[derive(Debug)]
+pub(crate) struct Batch<T> {
+    /// The result of each operation, in the order they were given
+    pub(crate) results: Vec<T>,
+    /// The failure of the ring, after which it should not be used anymore
+    pub(crate) error: Option<IoError>,
+}
+
+/// An io_uring instance used to batch socket operations
+pub(crate) struct Ring {
+    ring: IoUring,
+    // number of submissions that succeed before the next one fails, to test the recovery from a
+    // failing ring
+    #[cfg(test)]
+    pub(crate) fail_after: Option<usize>,
+}
+
+impl std::fmt::Debug for Ring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ring").field("fd", &self.ring.as_raw_fd()).finish_non_exhaustive()
+    }
+}
+
+impl Ring {
+    /// Create a new ring
+    ///
+    /// This fails if io_uring is not supported by the kernel or forbidden by the system configuration,
+    /// in which case the regular syscalls should be used instead.
+    pub(crate) fn new() -> IoResult<Self> {
+        Ok(Self {
+            ring: IoUring::new(RING_ENTRIES)?,
+            #[cfg(test)]
+            fail_after: None,
+        })
+    }
+
+    /// Submit all the operations and wait for their completion
+    ///
+    /// Returns the result of each operation, in the same order as `ops`. If the ring itself fails,
+    /// the operations that already reached the kernel are waited for and keep their result, while the
+    /// ones that never reached it are `None`. The ring should not be used anymore after a failure.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptors and message headers of the operations must be valid.
+    pub(crate) unsafe fn run(&mut self, ops: &[Op]) -> Batch<Option<IoResult<usize>>> {
+        let mut results = Vec::with_capacity(ops.len());
+        results.resize_with(ops.len(), || None);
+        let mut error = None;
+        for (chunk, chunk_results) in
+            ops.chunks(RING_ENTRIES as usize).zip(results.chunks_mut(RING_ENTRIES as usize))
+        {
+            // the following chunks never reach the kernel once the ring failed
+            if let Err(e) = unsafe { self.run_chunk(chunk, chunk_results) } {
+                error = Some(e);
+                break;
+            }
+        }
+        Batch { results, error }
+    }
+
+    unsafe fn run_chunk(
+        &mut self,
+        ops: &[Op],
+        results: &mut [Option<IoResult<usize>>],
+    ) -> IoResult<()> {
+        {
+            let mut sq = self.ring.submission();
+            for (i, op) in ops.iter().enumerate() {
+                let entry = match *op {
+                    Op::SendMsg(fd, msg, flags) => {
+                        opcode::SendMsg::new(types::Fd(fd), msg.cast()).flags(flags.bits()).build()
+                    }
+                    Op::RecvMsg(fd, msg, flags) => {
+                        opcode::RecvMsg::new(types::Fd(fd), msg.cast()).flags(flags.bits()).build()
+                    }
+                };
+                // the chunks are never larger than the submission queue, which is empty between runs
+                unsafe {
+                    sq.push(&entry.user_data(i as u64)).expect("io_uring submission queue full")
+                };
+            }
+        }
+
+        let count = ops.len();
+        let mut completed = 0;
+        while completed < count {
+            match self.submit_and_wait(count - completed) {
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    // the entries still in the submission queue never reached the kernel, they are
+                    // discarded with the ring
+                    let unsubmitted = self.ring.submission().len();
+                    completed += self.reap(results);
+                    // the kernel is still using the buffers of the submitted operations, they cannot
+                    // be given back to the caller before they complete
+                    self.drain(count - completed - unsubmitted, results);
+                    return Err(e);
+                }
+            }
+            completed += self.reap(results);
+        }
+        Ok(())
+    }
+
+    fn submit_and_wait(&mut self, want: usize) -> IoResult<usize> {
+        #[cfg(test)]
+        if let Some(ref mut remaining) = self.fail_after {
+            if *remaining == 0 {
+                return Err(Errno::NOMEM.into());
+            }
+            *remaining -= 1;
+        }
+        self.ring.submit_and_wait(want)
+    }
+
+    // Wait for the completion of the operations already submitted, without submitting anything else
+    fn drain(&mut self, mut in_flight: usize, results: &mut [Option<IoResult<usize>>]) {
+        // Safety: the fd of the ring is valid for as long as the ring exists
+        let fd = unsafe { BorrowedFd::borrow_raw(self.ring.as_raw_fd()) };
+        while in_flight > 0 {
+            // the operations do not block so their completion is imminent, and waiting for them only
+            // fails with transient errors (EINTR, EBUSY), which are retried
+            let _ = unsafe {
+                io_uring_enter(fd, 0, in_flight as u32, IoringEnterFlags::GETEVENTS, ptr::null(), 0)
+            };
+            in_flight = in_flight.saturating_sub(self.reap(results));
+        }
+    }
+
+    fn reap(&mut self, results: &mut [Option<IoResult<usize>>]) -> usize {
+        let mut count = 0;
+        for cqe in self.ring.completion() {
+            let index = cqe.user_data() as usize;
+            results[index] = Some(if cqe.result() < 0 {
+                Err(Errno::from_raw_os_error(-cqe.result()).into())
+            } else {
+                Ok(cqe.result() as usize)
+            });
+            count += 1;
+        }
+        count
+    }
+}
@@ -2,13 +2,17 @@
 
 use std::collections::VecDeque;
 use std::ffi::CStr;
-use std::os::unix::io::RawFd;
 use std::os::unix::io::{BorrowedFd, OwnedFd};
 
-use crate::protocol::{Argument, ArgumentType, Message};
+use crate::protocol::{
+    Argument, ArgumentType, BorrowedArgument, BorrowedMessage, Message, WireError, INLINE_ARGS,
+};
 
 use smallvec::SmallVec;
 
+/// The argument list of a message as decoded from the wire
+pub(crate) type WireArgs<'a> = SmallVec<[BorrowedArgument<'a, u32, OwnedFd>; INLINE_ARGS]>;
+
 /// Error generated when trying to serialize a message into buffers
 #[derive(Debug)]
 pub enum MessageWriteError {
@@ -46,7 +50,7 @@ pub enum MessageParseError {
     /// More data is needed to deserialize the message
     MissingData,
     /// The message is malformed and cannot be parsed
-    Malformed,
+    Malformed(WireError),
 }
 
 impl std::error::Error for MessageParseError {}
@@ -54,12 +58,12 @@ impl std::error::Error for MessageParseError {}
 impl std::fmt::Display for MessageParseError {
     #[cfg_attr(coverage, coverage(off))]
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
-        match *self {
+        match self {
             Self::MissingFD => {
                 f.write_str("The message references a FD but the buffer FD is empty.")
             }
             Self::MissingData => f.write_str("More data is needed to deserialize the message"),
-            Self::Malformed => f.write_str("The message is malformed and cannot be parsed"),
+            Self::Malformed(e) => write!(f, "The message is malformed and cannot be parsed: {}", e),
         }
     }
 }
@@ -68,9 +72,9 @@ impl std::fmt::Display for MessageParseError {
 ///
 /// Returns the number of elements written in each buffer
 ///
-/// Any serialized Fd will be `dup()`-ed in the process
+/// Any serialized Fd will be `dup()`-ed in the process, with the close-on-exec flag set
 pub fn write_to_buffers(
-    msg: &Message<u32, RawFd>,
+    msg: &Message<u32, BorrowedFd<'_>>,
     payload: &mut [u8],
     fds: &mut Vec<OwnedFd>,
 ) -> Result<usize, MessageWriteError> {
@@ -125,9 +129,9 @@ pub fn write_to_buffers(
             Argument::NewId(n) => write_buf(n, payload)?,
             Argument::Array(ref a) => write_array_to_payload(a, payload)?,
             Argument::Fd(fd) => {
-                let dup_fd = unsafe { BorrowedFd::borrow_raw(fd) }
-                    .try_clone_to_owned()
-                    .map_err(MessageWriteError::DupFdFailed)?;
+                // the copy must not leak into the child processes spawned before it is sent
+                let dup_fd = rustix::io::fcntl_dupfd_cloexec(fd, 0)
+                    .map_err(|e| MessageWriteError::DupFdFailed(e.into()))?;
                 fds.push(dup_fd);
                 payload
             }
@@ -141,6 +145,20 @@ pub fn write_to_buffers(
     Ok(orig_payload_len - payload.len())
 }
 
+/// Size of this message once serialized, in bytes
+pub fn message_size(msg: &Message<u32, BorrowedFd<'_>>) -> usize {
+    msg.args
+        .iter()
+        .map(|arg| match *arg {
+            Argument::Str(Some(ref s)) => 4 + next_multiple_of(s.as_bytes_with_nul().len(), 4),
+            Argument::Array(ref a) => 4 + next_multiple_of(a.len(), 4),
+            Argument::Fd(_) => 0,
+            _ => 4,
+        })
+        .sum::<usize>()
+        + 2 * 4
+}
+
 /// Attempts to parse a single wayland message with the given signature.
 ///
 /// If the buffers contains several messages, only the first one will be parsed,
@@ -148,24 +166,17 @@ pub fn write_to_buffers(
 /// the returned slices should thus be empty.
 ///
 /// Errors if the message is malformed.
+///
+/// The string and array arguments of the returned message borrow `raw`, use
+/// [`BorrowedMessage::into_owned()`] to keep them around. They are stored in `args`, which is
+/// expected to be empty, to reuse its allocation.
 #[allow(clippy::type_complexity)]
 pub fn parse_message<'a>(
     raw: &'a [u8],
     signature: &[ArgumentType],
     fds: &mut VecDeque<OwnedFd>,
-) -> Result<(Message<u32, OwnedFd>, &'a [u8]), MessageParseError> {
-    // helper function to read arrays
-    fn read_array_from_payload(
-        array_len: usize,
-        payload: &[u8],
-    ) -> Result<(&[u8], &[u8]), MessageParseError> {
-        let len = next_multiple_of(array_len, 4);
-        if len > payload.len() {
-            return Err(MessageParseError::MissingData);
-        }
-        Ok((&payload[..array_len], &payload[len..]))
-    }
-
+    mut args: WireArgs<'a>,
+) -> Result<(BorrowedMessage<'a, u32, OwnedFd>, &'a [u8]), MessageParseError> {
     if raw.len() < 2 * 4 {
         return Err(MessageParseError::MissingData);
     }
@@ -175,8 +186,26 @@ pub fn parse_message<'a>(
     let opcode = (word_2 & 0x0000_FFFF) as u16;
     let len = (word_2 >> 16) as usize;
 
+    // the arguments overflowing the size of the message
+    let size_mismatch = || {
+        MessageParseError::Malformed(WireError::SizeMismatch {
+            object_id: sender_id,
+            opcode,
+            size: len,
+        })
+    };
+
+    // helper function to read arrays
+    let read_array_from_payload = |array_len: usize, payload: &'a [u8]| {
+        let padded_len = next_multiple_of(array_len, 4);
+        if padded_len > payload.len() {
+            return Err(size_mismatch());
+        }
+        Ok((&payload[..array_len], &payload[padded_len..]))
+    };
+
     if len < 2 * 4 {
-        return Err(MessageParseError::Malformed);
+        return Err(size_mismatch());
     } else if len > raw.len() {
         return Err(MessageParseError::MissingData);
     }
@@ -189,13 +218,12 @@ pub fn parse_message<'a>(
     let (mut payload, rest) = raw.split_at(len);
     payload = &payload[2 * 4..];
 
-    let arguments = signature
-        .iter()
-        .map(|argtype| {
+    for (i, argtype) in signature.iter().enumerate() {
+        let arg = {
             if let ArgumentType::Fd = *argtype {
                 // don't consume input but fd
                 if let Some(front) = fds.pop_front() {
-                    Ok(Argument::Fd(front))
+                    Ok(BorrowedArgument::Fd(front))
                 } else {
                     Err(MessageParseError::MissingFD)
                 }
@@ -203,28 +231,34 @@ pub fn parse_message<'a>(
                 let (front, mut tail) = payload.split_at(4);
                 let front = u32::from_ne_bytes(front.try_into().unwrap());
                 let arg = match *argtype {
-                    ArgumentType::Int => Ok(Argument::Int(front as i32)),
-                    ArgumentType::Uint => Ok(Argument::Uint(front)),
-                    ArgumentType::Fixed => Ok(Argument::Fixed(front as i32)),
+                    ArgumentType::Int => Ok(BorrowedArgument::Int(front as i32)),
+                    ArgumentType::Uint => Ok(BorrowedArgument::Uint(front)),
+                    ArgumentType::Fixed => Ok(BorrowedArgument::Fixed(front as i32)),
                     ArgumentType::Str(_) => {
                         read_array_from_payload(front as usize, tail).and_then(|(v, rest)| {
                             tail = rest;
                             if !v.is_empty() {
                                 match CStr::from_bytes_with_nul(v) {
-                                    Ok(s) => Ok(Argument::Str(Some(Box::new(s.into())))),
-                                    Err(_) => Err(MessageParseError::Malformed),
+                                    Ok(s) => Ok(BorrowedArgument::Str(Some(s))),
+                                    Err(_) => Err(MessageParseError::Malformed(
+                                        WireError::InvalidString {
+                                            object_id: sender_id,
+                                            opcode,
+                                            arg: i,
+                                        },
+                                    )),
                                 }
                             } else {
-                                Ok(Argument::Str(None))
+                                Ok(BorrowedArgument::Str(None))
                             }
                         })
                     }
-                    ArgumentType::Object(_) => Ok(Argument::Object(front)),
-                    ArgumentType::NewId => Ok(Argument::NewId(front)),
+                    ArgumentType::Object(_) => Ok(BorrowedArgument::Object(front)),
+                    ArgumentType::NewId => Ok(BorrowedArgument::NewId(front)),
                     ArgumentType::Array => {
                         read_array_from_payload(front as usize, tail).map(|(v, rest)| {
                             tail = rest;
-                            Argument::Array(Box::new(v.into()))
+                            BorrowedArgument::Array(v)
                         })
                     }
                     ArgumentType::Fd => unreachable!(),
@@ -232,12 +266,13 @@ pub fn parse_message<'a>(
                 payload = tail;
                 arg
             } else {
-                Err(MessageParseError::MissingData)
+                Err(size_mismatch())
             }
-        })
-        .collect::<Result<SmallVec<_>, MessageParseError>>()?;
+        };
+        args.push(arg?);
+    }
 
-    let msg = Message { sender_id, opcode, args: arguments };
+    let msg = BorrowedMessage { sender_id, opcode, args };
     Ok((msg, rest))
 }
 
@@ -254,7 +289,10 @@ mod tests {
     use super::*;
     use crate::protocol::AllowNull;
     use smallvec::smallvec;
-    use std::{ffi::CString, os::unix::io::IntoRawFd};
+    use std::{
+        ffi::CString,
+        os::unix::io::{AsRawFd, IntoRawFd},
+    };
 
     #[test]
     fn into_from_raw_cycle() {
@@ -290,8 +328,12 @@ mod tests {
                 ArgumentType::Int,
             ],
             &mut fd_buffer,
+            SmallVec::new(),
         )
         .unwrap();
-        assert_eq!(rebuilt.map_fd(IntoRawFd::into_raw_fd), msg);
+        assert_eq!(
+            rebuilt.into_owned().map_fd(IntoRawFd::into_raw_fd),
+            msg.map_fd(|fd| fd.as_raw_fd())
+        );
     }
 }
@@ -1,13 +1,23 @@
 use std::{
+    any::Any,
     ffi::CString,
     fmt,
+    marker::PhantomData,
+    ops::Deref,
     os::unix::io::{BorrowedFd, OwnedFd},
-    os::unix::{io::RawFd, net::UnixStream},
+    os::unix::net::UnixStream,
     sync::Arc,
+    time::Duration,
 };
 
-use crate::protocol::{Interface, Message, ObjectInfo};
-pub use crate::types::server::{Credentials, DisconnectReason, GlobalInfo, InitError, InvalidId};
+pub use crate::debug::DebugSink;
+#[cfg(feature = "metrics")]
+pub use crate::metrics::Metrics;
+use crate::protocol::{BorrowedMessage, Interface, Message, ObjectInfo};
+pub use crate::types::server::{
+    BackendKind, Credentials, DisconnectReason, GlobalInfo, InitError, InvalidId, MemoryReport,
+    ResourceUsage,
+};
 
 use super::server_impl;
 
@@ -30,6 +40,21 @@ pub trait ObjectData<D>: downcast_rs::DowncastSync {
         client_id: ClientId,
         msg: Message<ObjectId, OwnedFd>,
     ) -> Option<Arc<dyn ObjectData<D>>>;
+    /// Dispatch a request for the associated object, without copying its arguments
+    ///
+    /// This is the method the backend actually invokes. The string and array arguments of the message
+    /// borrow the buffers of the backend and are only valid during this call. The default implementation
+    /// converts the message into an owned one and forwards it to [`request()`](ObjectData::request),
+    /// implement it to avoid these allocations in hot paths.
+    fn request_borrowed(
+        self: Arc<Self>,
+        handle: &Handle,
+        data: &mut D,
+        client_id: ClientId,
+        msg: BorrowedMessage<'_, ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData<D>>> {
+        self.request(handle, data, client_id, msg.into_owned())
+    }
     /// Notification that the object has been destroyed and is no longer active
     fn destroyed(
         self: Arc<Self>,
@@ -45,6 +70,13 @@ pub trait ObjectData<D>: downcast_rs::DowncastSync {
     fn debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ObjectData").finish_non_exhaustive()
     }
+    /// Helper for accessing user data
+    ///
+    /// By default, it returns `self` (via Downcast), but this may be overridden to allow downcasting
+    /// user data without needing to have access to the full type, see [`Handle::get_object_data_as()`].
+    fn data_as_any(&self) -> &dyn Any {
+        self.as_any()
+    }
 }
 
 downcast_rs::impl_downcast!(sync ObjectData<D>);
@@ -56,6 +88,48 @@ impl<D: 'static> std::fmt::Debug for dyn ObjectData<D> {
     }
 }
 
+/// The user data of an object, downcasted to a concrete type
+///
+/// It is obtained with [`Handle::get_object_data_as()`] and dereferences to the value returned by
+/// [`ObjectData::data_as_any()`]. It keeps the object data alive.
+pub struct DataRef<D, T> {
+    data: Arc<dyn ObjectData<D>>,
+    _type: PhantomData<T>,
+}
+
+impl<D: 'static, T: Any> DataRef<D, T> {
+    fn new(data: Arc<dyn ObjectData<D>>) -> Option<Self> {
+        data.data_as_any().is::<T>().then_some(Self { data, _type: PhantomData })
+    }
+
+    /// Access the object data the user data was taken from
+    pub fn object_data(&self) -> &Arc<dyn ObjectData<D>> {
+        &self.data
+    }
+}
+
+impl<D: 'static, T: Any> Deref for DataRef<D, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // the type was checked on creation
+        self.data.data_as_any().downcast_ref().unwrap()
+    }
+}
+
+impl<D, T> Clone for DataRef<D, T> {
+    fn clone(&self) -> Self {
+        Self { data: self.data.clone(), _type: PhantomData }
+    }
+}
+
+impl<D: 'static, T: Any + fmt::Debug> fmt::Debug for DataRef<D, T> {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
 /// A trait representing the handling of new bound globals
 pub trait GlobalHandler<D>: downcast_rs::DowncastSync {
     /// Check if given client is allowed to interact with given global
@@ -130,6 +204,84 @@ impl ClientData for () {}
 
 downcast_rs::impl_downcast!(sync ClientData);
 
+/// A hook seeing the messages exchanged with all clients
+///
+/// It sees the requests before they are dispatched, and the events before they are sent. Requests to
+/// `wl_display` and `wl_registry` and the events sent by the backend itself are not intercepted.
+///
+/// Requests are intercepted once the object they create, if any, exists, in the same order with both
+/// backends.
+///
+/// See [`Handle::set_interceptor()`].
+pub trait Interceptor: Send + Sync {
+    /// Inspect a request before it is given to the [`ObjectData`] of its target object
+    ///
+    /// Return the message, possibly with modified arguments, for it to be dispatched normally, or `None`
+    /// to consume it. The `sender_id` and `opcode` of the message must be left untouched.
+    ///
+    /// The default implementation lets all requests through.
+    fn intercept(
+        &self,
+        _handle: &Handle,
+        _client_id: ClientId,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Message<ObjectId, OwnedFd>> {
+        Some(msg)
+    }
+
+    /// Inspect a request along with its sequence number on the connection of its client
+    ///
+    /// The messages are numbered from 1 in each direction, in the order they go through the socket: the
+    /// `n`-th request received from a client is the `n`-th request it sent, which allows to correlate
+    /// the traces of both sides. The system backend only numbers the messages going through this crate, in the
+    /// order it handles them, not the ones libwayland handles itself.
+    ///
+    /// The default implementation forwards to [`intercept()`](Interceptor::intercept).
+    fn intercept_sequenced(
+        &self,
+        handle: &Handle,
+        client_id: ClientId,
+        _seq: u64,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Message<ObjectId, OwnedFd>> {
+        self.intercept(handle, client_id, msg)
+    }
+
+    /// Inspect an event before it is sent to a client
+    ///
+    /// Returns whether the event should be sent, events vetoed by returning `false` are silently
+    /// dropped. Events creating or destroying objects cannot be vetoed, as this would desynchronize the
+    /// server and the client: the return value is ignored for them.
+    ///
+    /// This may be invoked while the backend is internally locked, and must thus not call back into it.
+    /// The rust backend still allows the queries of [`Handle::object_info()`] and
+    /// [`Handle::get_object_data()`], which do not take this lock.
+    fn event(&self, _client_id: ClientId, _msg: &Message<ObjectId, BorrowedFd<'_>>) -> bool {
+        true
+    }
+
+    /// Inspect an event along with its sequence number on the connection of its client
+    ///
+    /// See [`intercept_sequenced()`](Interceptor::intercept_sequenced) for the numbering, a
+    /// vetoed event does not consume its number. The default implementation forwards to
+    /// [`event()`](Interceptor::event).
+    fn event_sequenced(
+        &self,
+        client_id: ClientId,
+        _seq: u64,
+        msg: &Message<ObjectId, BorrowedFd<'_>>,
+    ) -> bool {
+        self.event(client_id, msg)
+    }
+}
+
+impl std::fmt::Debug for dyn Interceptor {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interceptor").finish_non_exhaustive()
+    }
+}
+
 /// An ID representing a Wayland object
 ///
 /// The backend internally tracks which IDs are still valid, invalidates them when the protocol object they
@@ -182,6 +334,12 @@ impl ObjectId {
     }
 }
 
+impl crate::debug::DebugId for ObjectId {
+    fn debug_id(&self) -> Option<(&str, u32)> {
+        (!self.is_null()).then(|| (self.interface().name, self.protocol_id()))
+    }
+}
+
 impl fmt::Display for ObjectId {
     #[cfg_attr(coverage, coverage(off))]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -196,6 +354,15 @@ impl fmt::Debug for ObjectId {
     }
 }
 
+/// An object managed by the backend, as listed by [`Backend::all_objects()`]
+#[derive(Clone, Debug)]
+pub struct ObjectEntry {
+    /// The ID of the object
+    pub id: ObjectId,
+    /// The protocol information of the object
+    pub info: ObjectInfo,
+}
+
 /// An ID representing a Wayland client
 ///
 /// The backend internally tracks which IDs are still valid, invalidates them when the client they represent
@@ -263,6 +430,9 @@ impl Handle {
     /// Get the detailed protocol information about a wayland object
     ///
     /// Returns an error if the provided object ID is no longer valid.
+    ///
+    /// With the rust backend this does not wait for the dispatching of the requests. The system backend
+    /// serializes it with the dispatching, as libwayland-server is not thread-safe.
     #[inline]
     pub fn object_info(&self, id: ObjectId) -> Result<ObjectInfo, InvalidId> {
         self.handle.object_info(id.id)
@@ -280,12 +450,38 @@ impl Handle {
         Ok(ClientId { id: self.handle.insert_client(stream, data)? })
     }
 
+    /// Initializes a connection with a client from a socket file descriptor.
+    ///
+    /// This is the same as [`Handle::insert_client()`], for an already connected stream socket that
+    /// is not wrapped in a [`UnixStream`].
+    #[inline]
+    pub fn insert_client_fd(
+        &mut self,
+        fd: OwnedFd,
+        data: Arc<dyn ClientData>,
+    ) -> std::io::Result<ClientId> {
+        self.insert_client(UnixStream::from(fd), data)
+    }
+
     /// Returns the id of the client which owns the object.
     #[inline]
     pub fn get_client(&self, id: ObjectId) -> Result<ClientId, InvalidId> {
         self.handle.get_client(id.id)
     }
 
+    /// Set the interceptor seeing every request before it is dispatched and every event before it is
+    /// sent, or remove it with `None`
+    ///
+    /// The interceptor is invoked for all requests that would be given to an [`ObjectData`], requests to
+    /// `wl_display` and `wl_registry` are handled by the backend itself and are not intercepted.
+    ///
+    /// If the interceptor consumes a request creating an object, that object is still created, but all
+    /// its requests will be ignored. A consumed destructor request still destroys its object.
+    #[inline]
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.handle.set_interceptor(interceptor)
+    }
+
     /// Returns the data associated with a client.
     #[inline]
     pub fn get_client_data(&self, id: ClientId) -> Result<Arc<dyn ClientData>, InvalidId> {
@@ -293,11 +489,63 @@ impl Handle {
     }
 
     /// Retrive the [`Credentials`] of a client
+    ///
+    /// They are retrieved from the socket when the client connects. The pid is 0 on platforms where
+    /// it cannot be retrieved. With the rust backend, the credentials are all 0 on platforms where they
+    /// cannot be retrieved at all, and must not be trusted.
     #[inline]
     pub fn get_client_credentials(&self, id: ClientId) -> Result<Credentials, InvalidId> {
         self.handle.get_client_credentials(id.id)
     }
 
+    /// Retrieve the approximate [`ResourceUsage`] of a client
+    ///
+    /// **Note:** The buffers of the system backend are managed by libwayland and cannot be inspected, so
+    /// there the buffered bytes and fds are always reported as zero, and the memory only accounts for the
+    /// data of the objects.
+    #[inline]
+    pub fn get_client_resource_usage(&self, id: ClientId) -> Result<ResourceUsage, InvalidId> {
+        self.handle.get_client_resource_usage(id.id)
+    }
+
+    /// Set a human-readable label for a client
+    ///
+    /// This label is meant to help identifying the client when debugging, and is included in the
+    /// `WAYLAND_DEBUG` output of the rust backend, which otherwise identifies the client by its id. The
+    /// system backend stores it, but libwayland does not know about it.
+    #[inline]
+    pub fn set_client_label(&self, id: ClientId, label: String) -> Result<(), InvalidId> {
+        self.handle.set_client_label(id.id, label)
+    }
+
+    /// Retrieve the label of a client, if one was set using [`Handle::set_client_label()`]
+    #[inline]
+    pub fn get_client_label(&self, id: ClientId) -> Result<Option<String>, InvalidId> {
+        self.handle.get_client_label(id.id)
+    }
+
+    /// Checks whether a client is still connected
+    ///
+    /// This returns `false` as soon as the client has been disconnected, even if the backend did not
+    /// yet clean up its objects.
+    #[inline]
+    pub fn is_client_alive(&self, id: ClientId) -> bool {
+        self.handle.is_client_alive(id.id)
+    }
+
+    /// Register a callback to be invoked when a client is disconnected
+    ///
+    /// The callback is invoked right after [`ClientData::disconnected()`], in the same context. Any number
+    /// of callbacks can be registered for a client. Fails if the client is already disconnected.
+    #[inline]
+    pub fn add_client_disconnect_callback(
+        &self,
+        id: ClientId,
+        callback: Box<dyn FnOnce(ClientId) + Send>,
+    ) -> Result<(), InvalidId> {
+        self.handle.add_client_disconnect_callback(id.id, DisconnectCallback(callback))
+    }
+
     /// Invokes a closure for all clients connected to this server
     ///
     /// Note that while this method is running, an internal lock of the backend is held,
@@ -340,6 +588,9 @@ impl Handle {
     /// To ensure state coherence of the protocol, the created object should be immediately
     /// sent as a "New ID" argument in an event to the client.
     ///
+    /// If all the IDs of the server namespace of this client are used, the client is killed with a
+    /// `wl_display.no_memory` error and an error is returned.
+    ///
     /// # Panics
     ///
     /// This method will panic if the type parameter `D` is not same to the same type as the
@@ -359,6 +610,9 @@ impl Handle {
     ///
     /// Returns an error if the sender ID of the provided message is no longer valid.
     ///
+    /// The file descriptors of the message are duplicated by the backend, the caller keeps ownership
+    /// of the borrowed ones.
+    ///
     /// # Panics
     ///
     /// Checks against the protocol specification are done, and this method will panic if they do
@@ -367,10 +621,25 @@ impl Handle {
     /// - the message opcode must be valid for the sender interface
     /// - the argument list must match the prototype for the message associated with this opcode
     #[inline]
-    pub fn send_event(&self, msg: Message<ObjectId, RawFd>) -> Result<(), InvalidId> {
+    pub fn send_event(&self, msg: Message<ObjectId, BorrowedFd<'_>>) -> Result<(), InvalidId> {
         self.handle.send_event(msg)
     }
 
+    /// Queue an event to be sent to the client by the thread driving the [`Backend`]
+    ///
+    /// Unlike [`send_event()`](Handle::send_event), this method does not touch the protocol state and
+    /// is safe to use from any thread, for example to send `wl_buffer.release` from a rendering thread.
+    /// Queued events are sent in order by the next flush of the client buffers, so your event loop must
+    /// be woken up for them to actually reach the client.
+    ///
+    /// Returns an error if the sender ID of the provided message is no longer valid. If the object dies
+    /// before the event is sent, the event is silently dropped. The checks of
+    /// [`send_event()`](Handle::send_event) are done when the event is sent.
+    #[inline]
+    pub fn queue_event(&self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId> {
+        self.handle.queue_event(msg)
+    }
+
     /// Returns the data associated with an object.
     ///
     /// **Panic:** This method will panic if the type parameter `D` is not same to the same type as the
@@ -392,6 +661,17 @@ impl Handle {
         self.handle.get_object_data_any(id.id)
     }
 
+    /// Access the user data of an object as a concrete type
+    ///
+    /// This downcasts the value returned by [`ObjectData::data_as_any()`] for the object data of this
+    /// object. Returns `None` if the object ID is no longer valid or if its user data is not a `T`.
+    ///
+    /// **Panic:** This method will panic if the type parameter `D` is not same to the same type as the
+    /// one the backend was initialized with.
+    pub fn get_object_data_as<D: 'static, T: Any>(&self, id: ObjectId) -> Option<DataRef<D, T>> {
+        self.get_object_data::<D>(id).ok().and_then(DataRef::new)
+    }
+
     /// Sets the data associated with some object.
     ///
     /// **Panic:** This method will panic if the type parameter `D` is not same to the same type as the
@@ -405,6 +685,23 @@ impl Handle {
         self.handle.set_object_data(id.id, data)
     }
 
+    /// Replaces the data associated with some object, returning the previous one.
+    ///
+    /// Unlike a [`get_object_data()`](Handle::get_object_data) followed by a
+    /// [`set_object_data()`](Handle::set_object_data), no other thread can change the data in
+    /// between. This allows to wrap the data of an object, and to unwrap it later.
+    ///
+    /// **Panic:** This method will panic if the type parameter `D` is not same to the same type as the
+    /// one the backend was initialized with.
+    #[inline]
+    pub fn replace_object_data<D: 'static>(
+        &self,
+        id: ObjectId,
+        data: Arc<dyn ObjectData<D>>,
+    ) -> Result<Arc<dyn ObjectData<D>>, InvalidId> {
+        self.handle.replace_object_data(id.id, data)
+    }
+
     /// Posts a protocol error on an object. This will also disconnect the client which created the object.
     #[inline]
     pub fn post_error(&self, object_id: ObjectId, error_code: u32, message: CString) {
@@ -471,6 +768,27 @@ impl Handle {
         self.handle.remove_global::<D>(id.id)
     }
 
+    /// Restricts a global to the clients whose credentials are accepted by the provided filter
+    ///
+    /// The filter is checked in addition to [`GlobalHandler::can_view()`]: clients it rejects will not be
+    /// told about the global, and trying to bind it will raise a protocol error. Passing `None` removes
+    /// the restriction.
+    ///
+    /// Clients that already know about the global are not notified of a change of policy, so the
+    /// restriction should be set right after creating the global. Clients whose credentials cannot be
+    /// retrieved on this platform are always rejected.
+    ///
+    /// **Panic:** This method will panic if the type parameter `D` is not same to the same type as the
+    /// one the backend was initialized with.
+    #[inline]
+    pub fn restrict_global<D: 'static>(
+        &self,
+        id: GlobalId,
+        filter: Option<CredentialsFilter>,
+    ) -> Result<(), InvalidId> {
+        self.handle.restrict_global::<D>(id.id, filter.map(GlobalRestriction))
+    }
+
     /// Returns information about a global.
     #[inline]
     pub fn global_info(&self, id: GlobalId) -> Result<GlobalInfo, InvalidId> {
@@ -486,12 +804,34 @@ impl Handle {
         self.handle.get_global_handler(id.id)
     }
 
+    /// Sets a callback to be invoked when the server becomes idle
+    ///
+    /// The server is considered idle once all events have been written to the client sockets and no
+    /// client request is waiting to be dispatched. This is checked at the end of every flush of all
+    /// clients ([`Backend::flush()`] or [`Handle::flush()`] with `None`), and the callback is invoked
+    /// at most once after each period of activity (requests being dispatched or events being sent).
+    /// It is invoked without holding any internal lock, so it can freely use the backend.
+    ///
+    /// **Note:** libwayland does not report whether it could write all events to the sockets, so on the
+    /// system backend only pending requests are considered.
+    pub fn set_idle_callback(&self, cb: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.handle.set_idle_callback(cb)
+    }
+
     /// Flushes pending events destined for a client.
     ///
     /// If no client is specified, all pending events are flushed to all clients.
     pub fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
         self.handle.flush(client)
     }
+
+    /// Flushes pending events destined for a single client.
+    ///
+    /// Unlike [`flush(None)`](Handle::flush), the sockets of the other clients are not written to. Does
+    /// nothing if the client is no longer alive.
+    pub fn flush_client(&mut self, client: ClientId) -> std::io::Result<()> {
+        self.handle.flush(Some(client))
+    }
 }
 
 /// A backend object that represents the state of a wayland server.
@@ -510,6 +850,29 @@ impl<D> Backend<D> {
         Ok(Self { backend: server_impl::InnerBackend::new()? })
     }
 
+    /// Initialize a new Wayland backend, checking that it is of the expected kind
+    ///
+    /// This does not select the implementation, which is fixed at compile time by the `server_system`
+    /// cargo feature: it only fails early with an [`std::io::ErrorKind::Unsupported`] error if this build
+    /// uses another one than `kind`. Otherwise it behaves like [`Backend::new()`], and fails with
+    /// [`InitError::NoWaylandLib`] if the system backend is expected but `libwayland-server.so` could not
+    /// be loaded.
+    pub fn new_checked(kind: BackendKind) -> Result<Self, InitError> {
+        if kind != server_impl::BACKEND_KIND {
+            return Err(InitError::Io(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("the {kind:?} server backend is not available in this build"),
+            )));
+        }
+        Self::new()
+    }
+
+    /// The implementation used by this backend
+    #[inline]
+    pub fn kind(&self) -> BackendKind {
+        server_impl::BACKEND_KIND
+    }
+
     /// Flushes pending events destined for a client.
     ///
     /// If no client is specified, all pending events are flushed to all clients.
@@ -518,6 +881,16 @@ impl<D> Backend<D> {
         self.backend.flush(client)
     }
 
+    /// Flushes pending events destined for a single client.
+    ///
+    /// Unlike [`flush(None)`](Backend::flush), the sockets of the other clients are not written to.
+    /// On the system backend this is `wl_client_flush()`. Does nothing if the client is no longer
+    /// alive.
+    #[inline]
+    pub fn flush_client(&mut self, client: ClientId) -> std::io::Result<()> {
+        self.backend.flush(Some(client))
+    }
+
     /// Returns a handle which represents the server side state of the backend.
     ///
     /// The handle provides a variety of functionality, such as querying information about wayland objects,
@@ -533,6 +906,11 @@ impl<D> Backend<D> {
     /// When it becomes readable, this means there are pending messages that would be dispatched if you call
     /// [`Backend::dispatch_all_clients`].
     ///
+    /// It covers the sockets of all clients: the backend adds and removes them as clients connect and
+    /// disconnect, so there is no need to monitor each client separately. On the rust backend it is an
+    /// epoll (or kqueue) instance, from which [`Backend::dispatch_all_clients`] only reads the clients
+    /// that are ready.
+    ///
     /// The file descriptor should not be used for any other purpose than monitoring it.
     #[inline]
     pub fn poll_fd(&self) -> BorrowedFd {
@@ -561,6 +939,30 @@ impl<D> Backend<D> {
         self.backend.dispatch_client(data, client_id.id)
     }
 
+    /// Dispatches the pending messages of a single client, for at most `timeout`
+    ///
+    /// This is like [`Backend::dispatch_single_client()`], but stops dispatching once `timeout` has
+    /// elapsed, so that a compositor can bound the time spent on the backlog of a client, for example to
+    /// keep up its frame rate. The deadline is checked between requests, so a slow request handler can
+    /// make this method exceed it, and at least one request is dispatched if any is pending.
+    ///
+    /// Returns the number of dispatched messages, and whether some messages may remain pending. In that case
+    /// they may already be buffered by the backend and not signaled by the file descriptor of the client
+    /// or by [`Backend::poll_fd()`], so this method should be invoked again without waiting for them to
+    /// become readable.
+    ///
+    /// **Note:** On the system backend, libwayland dispatches all the messages it reads, this method does
+    /// the same as [`Backend::dispatch_all_clients()`] and never reports pending messages.
+    #[inline]
+    pub fn dispatch_single_client_timeout(
+        &mut self,
+        data: &mut D,
+        client_id: ClientId,
+        timeout: Duration,
+    ) -> std::io::Result<(usize, bool)> {
+        self.backend.dispatch_client_timeout(data, client_id.id, timeout)
+    }
+
     /// Dispatches all pending messages from all clients.
     ///
     /// This method will not block if there are no pending messages.
@@ -570,10 +972,261 @@ impl<D> Backend<D> {
     /// For performance reasons, use of this function should be integrated with an event loop, monitoring the
     /// file descriptor retrieved by [`Backend::poll_fd`] and only calling this method when messages are
     /// available.
+    ///
+    /// The rust backend dispatches the clients in turns, a limited number of requests each, so that a
+    /// client sending many requests does not delay the handling of the others.
     #[inline]
     pub fn dispatch_all_clients(&mut self, data: &mut D) -> std::io::Result<usize> {
         self.backend.dispatch_all_clients(data)
     }
+
+    /// Dispatches at most `max_requests` pending messages from the clients
+    ///
+    /// This is like [`Backend::dispatch_all_clients()`], but stops once `max_requests` messages have been
+    /// dispatched, so that a latency-sensitive event loop can interleave other work with the processing of
+    /// the requests.
+    ///
+    /// Returns the number of dispatched messages, and whether some messages may remain pending. In that case
+    /// they may already be buffered by the backend and not signaled by [`Backend::poll_fd()`], so this
+    /// method should be invoked again without waiting for the fd to become readable.
+    ///
+    /// **Note:** On the system backend, libwayland dispatches all the messages it reads, this method does
+    /// the same as [`Backend::dispatch_all_clients()`] and never reports pending messages.
+    #[inline]
+    pub fn dispatch_all_clients_limited(
+        &mut self,
+        data: &mut D,
+        max_requests: usize,
+    ) -> std::io::Result<(usize, bool)> {
+        self.backend.dispatch_all_clients_limited(data, max_requests)
+    }
+
+    /// Sets the delay after which newly connected clients that did not send any request are disconnected
+    ///
+    /// This protects the server from connections that never start talking the protocol, which would
+    /// otherwise hold on to a file descriptor forever. Setting it to `None` (the default) disables the
+    /// timeout.
+    ///
+    /// The timeout is checked whenever [`Backend::dispatch_all_clients()`] is invoked, so an event loop
+    /// using it should make sure to dispatch regularly even if no client activity is reported.
+    ///
+    /// **Note:** On the system backend, a client is considered to have completed its handshake once it
+    /// owns a protocol object other than its `wl_display` (typically its `wl_registry`).
+    #[inline]
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.backend.set_handshake_timeout(timeout)
+    }
+
+    /// Set the maximum number of file descriptors queued for each client
+    ///
+    /// This limits both the file descriptors received from a client and not yet dispatched, and those
+    /// sent to it and not yet flushed. If this limit is exceeded in either direction, the client is
+    /// disconnected. The default is 1024.
+    ///
+    /// **Note:** This is ignored by the system backend, libwayland has its own fixed limit.
+    #[inline]
+    pub fn set_max_client_fds(&mut self, max_fds: usize) {
+        self.backend.set_max_client_fds(max_fds)
+    }
+
+    /// Set the maximum size the buffers of each client connection can grow to, in bytes
+    ///
+    /// This applies to the clients already connected and to the future ones. Messages that do not fit
+    /// in the buffers fail with a [`MessageTooLarge`](crate::protocol::MessageTooLarge) error, and the
+    /// client is disconnected. The default is 64 KiB.
+    ///
+    /// **Note:** The system backend only supports it if the `libwayland_1_23` cargo feature is enabled,
+    /// which requires libwayland 1.23. A size of `0` then means unlimited.
+    #[inline]
+    pub fn set_max_client_buffer_size(&mut self, size: usize) {
+        self.backend.set_max_client_buffer_size(size)
+    }
+
+    /// Set the maximum number of objects each client may own, or remove the limit with `None` (the default)
+    ///
+    /// This protects the server from clients exhausting its memory by creating objects. Only the objects
+    /// created by the client count towards the limit, including its `wl_display`. A client trying to
+    /// create an object beyond the limit is killed with a `no_memory` protocol error, unless the callback
+    /// set with [`Backend::set_object_limit_callback()`] allows it.
+    ///
+    /// **Note:** This is ignored by the system backend.
+    #[inline]
+    pub fn set_max_client_objects(&mut self, max_objects: Option<usize>) {
+        self.backend.set_max_client_objects(max_objects)
+    }
+
+    /// Set the callback deciding whether a client may exceed the limit set with
+    /// [`Backend::set_max_client_objects()`], or remove it with `None`
+    ///
+    /// See [`ObjectLimitCallback`].
+    ///
+    /// **Note:** This is ignored by the system backend.
+    #[inline]
+    pub fn set_object_limit_callback(&mut self, callback: Option<ObjectLimitCallback>) {
+        self.backend.set_object_limit_callback(callback)
+    }
+
+    /// Retrieve the performance counters of this backend, covering all clients
+    ///
+    /// **Note:** The system backend only counts the messages, file descriptors and dispatch time, see
+    /// [`Metrics`] for details.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    pub fn metrics(&self) -> Metrics {
+        self.backend.metrics()
+    }
+
+    /// List all the objects managed by this backend, for all clients
+    ///
+    /// This is meant for debugging, like detecting leaked objects. The server forgets objects as soon as
+    /// they are destroyed, so only live objects are listed. The client owning an object can be
+    /// retrieved with [`Handle::get_client()`]. The system backend does not list the `wl_display` and
+    /// `wl_registry` objects, which are handled by libwayland.
+    pub fn all_objects(&self) -> Vec<ObjectEntry> {
+        let handle = self.handle();
+        let mut ids = Vec::new();
+        handle.with_all_clients(|client_id| {
+            ids.push(client_id);
+        });
+        let mut objects = Vec::new();
+        for client_id in ids {
+            // the client may have been disconnected in the meantime
+            let _ = handle.with_all_objects_for(client_id, |id| objects.push(id));
+        }
+        // the backend is locked while iterating, the info can only be retrieved afterwards
+        objects
+            .into_iter()
+            .filter_map(|id| {
+                let info = handle.object_info(id.clone()).ok()?;
+                Some(ObjectEntry { id, info })
+            })
+            .collect()
+    }
+
+    /// Report the clients, objects and buffers held by the backend
+    ///
+    /// This sums the [`ResourceUsage`] of all the clients, see
+    /// [`Handle::get_client_resource_usage()`]. A steady growth of these numbers in a long-running
+    /// server reveals protocol state that is never released.
+    ///
+    /// **Note:** The system backend only accounts for the objects, the messages and buffers of
+    /// libwayland are not exposed.
+    pub fn memory_report(&self) -> MemoryReport {
+        let handle = self.handle();
+        let mut ids = Vec::new();
+        handle.with_all_clients(|client_id| {
+            ids.push(client_id);
+        });
+        let mut report = MemoryReport::default();
+        for client_id in ids {
+            // the client may have been disconnected in the meantime
+            if let Ok(usage) = handle.get_client_resource_usage(client_id) {
+                report.clients += 1;
+                report.objects += usage.objects;
+                report.buffered_bytes += usage.buffered_bytes;
+                report.buffered_fds += usage.buffered_fds;
+                report.memory += usage.memory;
+            }
+        }
+        report
+    }
+
+    /// Set a sink receiving the debug output of all clients, or remove it with `None`
+    ///
+    /// Each message sent or received is given to the sink as a line in the same format as the
+    /// `WAYLAND_DEBUG` output of libwayland, without its trailing newline. While a sink is set the output
+    /// is no longer printed to stderr, and it is produced even if `WAYLAND_DEBUG` is not set.
+    ///
+    /// **Note:** The system backend gives the messages logged by libwayland to the sink, but libwayland
+    /// still prints its own output to stderr if `WAYLAND_DEBUG` is set.
+    #[inline]
+    pub fn set_debug_sink(&mut self, sink: Option<DebugSink>) {
+        self.backend.set_debug_sink(sink)
+    }
+
+    /// Set the interceptor seeing the messages exchanged with all clients, or remove it with `None`
+    ///
+    /// This is a shorthand for [`Handle::set_interceptor()`], see it for details.
+    #[inline]
+    pub fn set_interceptor(&mut self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.handle().set_interceptor(interceptor)
+    }
+}
+
+/// A filter deciding from their credentials which clients may access a global
+///
+/// See [`Handle::restrict_global()`].
+pub type CredentialsFilter = Arc<dyn Fn(&Credentials) -> bool + Send + Sync>;
+
+/// A policy deciding whether a client may create an object beyond the limit on its number of objects
+///
+/// It is given the client and the number of objects it currently owns, and returns `true` to allow the
+/// creation. It is invoked while the backend is locked, so it must not use the [`Handle`].
+///
+/// See [`Backend::set_object_limit_callback()`].
+pub type ObjectLimitCallback = Arc<dyn Fn(ClientId, usize) -> bool + Send + Sync>;
+
+// Storage of a `CredentialsFilter` in the global data of the backends
+pub(crate) struct GlobalRestriction(pub(crate) CredentialsFilter);
+
+impl GlobalRestriction {
+    // clients whose credentials are unavailable are never allowed
+    pub(crate) fn allows(&self, credentials: Option<&Credentials>) -> bool {
+        credentials.map_or(false, |credentials| (self.0)(credentials))
+    }
+}
+
+impl fmt::Debug for GlobalRestriction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GlobalRestriction").finish_non_exhaustive()
+    }
+}
+
+// Storage of the idle callback in the state of the backends
+pub(crate) struct IdleCallback(pub(crate) Arc<dyn Fn() + Send + Sync>);
+
+impl fmt::Debug for IdleCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IdleCallback").finish_non_exhaustive()
+    }
+}
+
+// Storage of the disconnect callbacks of a client
+pub(crate) struct DisconnectCallback(pub(crate) Box<dyn FnOnce(ClientId) + Send>);
+
+impl fmt::Debug for DisconnectCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DisconnectCallback").finish_non_exhaustive()
+    }
+}
+
+// The data of objects whose creating request was consumed by a `Interceptor`
+pub(crate) struct InertObjectData;
+
+impl<D> ObjectData<D> for InertObjectData {
+    fn request(
+        self: Arc<Self>,
+        _handle: &Handle,
+        _data: &mut D,
+        _client_id: ClientId,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData<D>>> {
+        // objects created from an inert object are inert as well
+        if msg.args.iter().any(|arg| matches!(arg, crate::protocol::Argument::NewId(_))) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn destroyed(
+        self: Arc<Self>,
+        _handle: &Handle,
+        _: &mut D,
+        _client_id: ClientId,
+        _object_id: ObjectId,
+    ) {
+    }
 }
 
 pub(crate) struct DumbObjectData;
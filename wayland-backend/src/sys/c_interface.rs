@@ -0,0 +1,126 @@
+//! Descriptions of the interfaces for libwayland, generated at runtime
+//!
+//! The interfaces generated by `wayland-scanner` carry their `wl_interface` in `c_ptr`, but the ones
+//! built by hand or loaded at runtime may not. Their description is then generated the first time
+//! they are used, and leaked so that libwayland can keep pointers to it.
+
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    os::raw::{c_char, c_int},
+    ptr::null,
+    sync::Mutex,
+};
+
+use crate::protocol::{
+    same_interface, AllowNull, ArgumentType, Interface, MessageDesc, ANONYMOUS_INTERFACE,
+};
+use wayland_sys::common::{wl_interface, wl_message};
+
+// The generated descriptions, keyed by the address of their interface
+struct Generated(HashMap<usize, *const wl_interface>);
+
+// Safety: the descriptions are leaked, and never modified once generated
+unsafe impl Send for Generated {}
+
+static GENERATED: Mutex<Option<Generated>> = Mutex::new(None);
+
+/// The libwayland description of an interface, generating it if the interface has no `c_ptr`
+pub(crate) fn c_interface(interface: &'static Interface) -> &'static wl_interface {
+    if let Some(c_ptr) = interface.c_ptr {
+        return c_ptr;
+    }
+    let mut generated = GENERATED.lock().unwrap();
+    let generated = &mut generated.get_or_insert_with(|| Generated(HashMap::new())).0;
+    // Safety: the description is complete once the outermost generation returns, the lock is held
+    // until then
+    unsafe { &*generate(interface, generated) }
+}
+
+fn generate(
+    interface: &'static Interface,
+    generated: &mut HashMap<usize, *const wl_interface>,
+) -> *const wl_interface {
+    if let Some(c_ptr) = interface.c_ptr {
+        return c_ptr;
+    }
+    let key = interface as *const Interface as usize;
+    if let Some(&c_ptr) = generated.get(&key) {
+        return c_ptr;
+    }
+
+    // registered before generating the messages, which may refer back to this interface
+    let c_ptr = Box::into_raw(Box::new(wl_interface {
+        name: leak_c_str(interface.name),
+        version: interface.version as c_int,
+        request_count: interface.requests.len() as c_int,
+        requests: null(),
+        event_count: interface.events.len() as c_int,
+        events: null(),
+    }));
+    generated.insert(key, c_ptr);
+    let requests = generate_messages(interface.requests, generated);
+    let events = generate_messages(interface.events, generated);
+    // Safety: the pointer comes from the box leaked above, libwayland does not see it yet
+    unsafe {
+        (*c_ptr).requests = requests;
+        (*c_ptr).events = events;
+    }
+    c_ptr
+}
+
+fn generate_messages(
+    messages: &'static [MessageDesc],
+    generated: &mut HashMap<usize, *const wl_interface>,
+) -> *const wl_message {
+    if messages.is_empty() {
+        return null();
+    }
+    let mut c_messages = Vec::with_capacity(messages.len());
+    for message in messages {
+        let mut signature = String::new();
+        if message.since > 1 {
+            signature.push_str(&message.since.to_string());
+        }
+        // the interface of each argument, only set for the objects
+        let mut types = Vec::with_capacity(message.signature.len());
+        let mut arg_interfaces = message.arg_interfaces.iter();
+        for arg in message.signature {
+            let mut arg_interface = None;
+            match *arg {
+                ArgumentType::Int => signature.push('i'),
+                ArgumentType::Uint => signature.push('u'),
+                ArgumentType::Fixed => signature.push('f'),
+                ArgumentType::Str(AllowNull::Yes) => signature.push_str("?s"),
+                ArgumentType::Str(AllowNull::No) => signature.push('s'),
+                ArgumentType::Object(allow_null) => {
+                    if allow_null == AllowNull::Yes {
+                        signature.push('?');
+                    }
+                    signature.push('o');
+                    arg_interface = arg_interfaces
+                        .next()
+                        .copied()
+                        .filter(|iface| !same_interface(iface, &ANONYMOUS_INTERFACE));
+                }
+                ArgumentType::NewId => {
+                    signature.push('n');
+                    arg_interface = message.child_interface;
+                }
+                ArgumentType::Array => signature.push('a'),
+                ArgumentType::Fd => signature.push('h'),
+            }
+            types.push(arg_interface.map_or(null(), |iface| generate(iface, generated)));
+        }
+        c_messages.push(wl_message {
+            name: leak_c_str(message.name),
+            signature: leak_c_str(&signature),
+            types: Box::leak(types.into_boxed_slice()).as_ptr(),
+        });
+    }
+    Box::leak(c_messages.into_boxed_slice()).as_ptr()
+}
+
+fn leak_c_str(s: &str) -> *const c_char {
+    CString::new(s).expect("Interface and message names cannot contain a nul byte.").into_raw()
+}
@@ -1,35 +1,38 @@
 //! Client-side implementation of a Wayland protocol backend using `libwayland`
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ffi::CStr,
     os::raw::{c_int, c_void},
     os::unix::io::{BorrowedFd, OwnedFd},
     os::unix::{
-        io::{FromRawFd, IntoRawFd, RawFd},
+        io::{AsRawFd, FromRawFd, IntoRawFd},
         net::UnixStream,
     },
+    panic::{self, AssertUnwindSafe},
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc, Mutex, MutexGuard, Weak,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, RwLock, Weak,
     },
 };
 
 use crate::{
     core_interfaces::WL_DISPLAY_INTERFACE,
-    debug,
-    debug::has_debug_client_env,
+    debug::{self, has_debug_client_env, DebugOutput},
+    metrics::MetricsCounters,
     protocol::{
-        check_for_signature, same_interface, AllowNull, Argument, ArgumentType, Interface, Message,
-        ObjectInfo, ProtocolError, ANONYMOUS_INTERFACE,
+        can_veto, check_for_signature, same_interface, AllowNull, Argument, ArgumentType,
+        BorrowedArgument, BorrowedMessage, Interface, Message, ObjectInfo, ProtocolError,
+        ANONYMOUS_INTERFACE,
     },
+    wakeup::Wakeup,
 };
 use scoped_tls::scoped_thread_local;
 use smallvec::SmallVec;
 
 use wayland_sys::{client::*, common::*, ffi_dispatch};
 
-use super::{free_arrays, RUST_MANAGED};
+use super::{c_interface::c_interface, count_fds, free_arrays, RUST_MANAGED};
 
 use super::client::*;
 
@@ -104,15 +107,8 @@ impl InnerObjectId {
         let ptr_iface_name = unsafe {
             CStr::from_ptr(ffi_dispatch!(wayland_client_handle(), wl_proxy_get_class, ptr))
         };
-        // Safety: the code generated by wayland-scanner is valid
-        let provided_iface_name = unsafe {
-            CStr::from_ptr(
-                interface
-                    .c_ptr
-                    .expect("[wayland-backend-sys] Cannot use Interface without c_ptr!")
-                    .name,
-            )
-        };
+        // Safety: the description of the interface is valid
+        let provided_iface_name = unsafe { CStr::from_ptr(c_interface(interface).name) };
         if ptr_iface_name != provided_iface_name {
             return Err(InvalidId);
         }
@@ -144,6 +140,25 @@ impl InnerObjectId {
             std::ptr::null_mut()
         }
     }
+
+    #[cfg(feature = "libwayland_1_18")]
+    pub fn set_tag(&self, tag: *const *const std::os::raw::c_char) -> Result<(), InvalidId> {
+        let ptr = self.as_ptr();
+        if ptr.is_null() {
+            return Err(InvalidId);
+        }
+        unsafe { ffi_dispatch!(wayland_client_1_18_handle(), wl_proxy_set_tag, ptr, tag) };
+        Ok(())
+    }
+
+    #[cfg(feature = "libwayland_1_18")]
+    pub fn tag(&self) -> Result<*const *const std::os::raw::c_char, InvalidId> {
+        let ptr = self.as_ptr();
+        if ptr.is_null() {
+            return Err(InvalidId);
+        }
+        Ok(unsafe { ffi_dispatch!(wayland_client_1_18_handle(), wl_proxy_get_tag, ptr) })
+    }
 }
 
 impl std::fmt::Display for InnerObjectId {
@@ -166,27 +181,67 @@ struct ProxyUserData {
     interface: &'static Interface,
 }
 
+/// Object data attached to a proxy not managed by this backend
+///
+/// libwayland does not notify us when such a proxy is destroyed, so the protocol id and interface are
+/// recorded to detect when its pointer has been reused by another proxy.
 #[derive(Debug)]
-struct ConnectionState {
-    display: *mut wl_display,
-    evq: *mut wl_event_queue,
-    display_id: InnerObjectId,
-    last_error: Option<WaylandError>,
+struct ExternalData {
+    id: u32,
+    interface: &'static Interface,
+    data: Arc<dyn ObjectData>,
+}
+
+/// The proxies managed by this backend, and the object data attached to the others
+///
+/// The proxies are spread across `PROXY_SHARDS` of these according to their pointer, so that threads
+/// creating and destroying objects rarely contend on the same lock.
+#[derive(Debug, Default)]
+struct ProxyState {
     known_proxies: HashSet<*mut wl_proxy>,
+    external_data: HashMap<*mut wl_proxy, ExternalData>,
 }
 
-unsafe impl Send for ConnectionState {}
+/// Number of shards of the proxy state, a power of two
+const PROXY_SHARDS: usize = 16;
+
+// the low bits of the pointers are usually zero because of the alignment of the allocations
+fn proxy_shard(ptr: *mut wl_proxy) -> usize {
+    (ptr as usize >> 4) & (PROXY_SHARDS - 1)
+}
 
 #[derive(Debug)]
 struct Dispatcher;
 
+// The state is split across several locks so that threads sending requests do not serialize on a
+// single one: libwayland synchronizes the display itself, and the pointers are never modified.
 #[derive(Debug)]
 struct Inner {
-    state: Mutex<ConnectionState>,
+    display: *mut wl_display,
+    evq: *mut wl_event_queue,
+    display_id: InnerObjectId,
+    // set once `last_error` is, so that the error can be checked without locking
+    has_error: AtomicBool,
+    last_error: Mutex<Option<WaylandError>>,
+    proxies: [Mutex<ProxyState>; PROXY_SHARDS],
+    interceptor: RwLock<Option<Arc<dyn Interceptor>>>,
     dispatch_lock: Mutex<Dispatcher>,
     debug: bool,
+    wakeup: Wakeup,
+    metrics: MetricsCounters,
+    // the number of requests sent and events dispatched by this crate, counted on the host of a guest,
+    // the lock of `requests_out` is held while sending a request so that they are numbered in order
+    requests_out: Mutex<u64>,
+    events_in: AtomicU64,
+    // the backend owning the connection of a guest, kept alive until its queue is destroyed
+    host: Option<Arc<Inner>>,
+    // last, so that the callbacks run once the connection is closed by `Drop for Inner`
+    drop_callbacks: DropCallbacks,
 }
 
+unsafe impl Send for Inner {}
+unsafe impl Sync for Inner {}
+
 #[derive(Clone, Debug)]
 pub struct InnerBackend {
     inner: Arc<Inner>,
@@ -198,8 +253,9 @@ pub struct WeakInnerBackend {
 }
 
 impl InnerBackend {
-    fn lock_state(&self) -> MutexGuard<ConnectionState> {
-        self.inner.state.lock().unwrap()
+    /// Lock the shard of the proxy state holding the given proxy
+    fn lock_proxies(&self, ptr: *mut wl_proxy) -> MutexGuard<ProxyState> {
+        self.inner.proxies[proxy_shard(ptr)].lock().unwrap()
     }
 
     pub fn downgrade(&self) -> WeakInnerBackend {
@@ -207,7 +263,17 @@ impl InnerBackend {
     }
 
     pub fn display_ptr(&self) -> *mut wl_display {
-        self.inner.state.lock().unwrap().display
+        self.inner.display
+    }
+
+    pub fn remove_external_data(
+        &self,
+        ObjectId { id }: ObjectId,
+    ) -> Result<Arc<dyn ObjectData>, InvalidId> {
+        let mut proxies = self.lock_proxies(id.ptr);
+        let data = proxies.external_data(&id)?;
+        proxies.external_data.remove(&id.ptr);
+        Ok(data)
     }
 }
 
@@ -215,6 +281,24 @@ impl WeakInnerBackend {
     pub fn upgrade(&self) -> Option<InnerBackend> {
         Weak::upgrade(&self.inner).map(|inner| InnerBackend { inner })
     }
+
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
+    }
+
+    pub fn on_drop(&self, callback: DropCallback) {
+        match Weak::upgrade(&self.inner) {
+            Some(inner) => inner.drop_callbacks.push(callback),
+            None => (callback.0)(),
+        }
+    }
+}
+
+impl InnerBackend {
+    #[cfg(feature = "debug-checks")]
+    pub(crate) fn connection_key(&self) -> usize {
+        Arc::as_ptr(&self.inner) as usize
+    }
 }
 
 impl PartialEq for InnerBackend {
@@ -229,6 +313,14 @@ unsafe impl Send for InnerBackend {}
 unsafe impl Sync for InnerBackend {}
 
 impl InnerBackend {
+    pub fn connect_with_buffer_sizes(
+        stream: UnixStream,
+        _sizes: BufferSizes,
+    ) -> Result<Self, NoWaylandLib> {
+        // libwayland manages its own buffers
+        Self::connect(stream)
+    }
+
     pub fn connect(stream: UnixStream) -> Result<Self, NoWaylandLib> {
         if !is_lib_available() {
             return Err(NoWaylandLib);
@@ -240,7 +332,7 @@ impl InnerBackend {
             panic!("[wayland-backend-sys] libwayland reported an allocation failure.");
         }
         // set the log trampoline
-        #[cfg(feature = "log")]
+        #[cfg(any(feature = "log", feature = "client_system", feature = "server_system"))]
         unsafe {
             ffi_dispatch!(
                 wayland_client_handle(),
@@ -249,89 +341,188 @@ impl InnerBackend {
             );
         }
         let display_alive = Arc::new(AtomicBool::new(true));
-        Ok(Self {
-            inner: Arc::new(Inner {
-                state: Mutex::new(ConnectionState {
-                    display,
-                    evq: std::ptr::null_mut(),
-                    display_id: InnerObjectId {
-                        id: 1,
-                        ptr: display as *mut wl_proxy,
-                        alive: Some(display_alive),
-                        interface: &WL_DISPLAY_INTERFACE,
-                    },
-                    last_error: None,
-                    known_proxies: HashSet::new(),
-                }),
-                debug: has_debug_client_env(),
-                dispatch_lock: Mutex::new(Dispatcher),
-            }),
-        })
+        Ok(Self { inner: Arc::new(Inner::new(display, std::ptr::null_mut(), Some(display_alive))) })
     }
 
     pub unsafe fn from_foreign_display(display: *mut wl_display) -> Self {
         let evq =
             unsafe { ffi_dispatch!(wayland_client_handle(), wl_display_create_queue, display) };
-        Self {
-            inner: Arc::new(Inner {
-                state: Mutex::new(ConnectionState {
-                    display,
-                    evq,
-                    display_id: InnerObjectId {
-                        id: 1,
-                        ptr: display as *mut wl_proxy,
-                        alive: None,
-                        interface: &WL_DISPLAY_INTERFACE,
-                    },
-                    last_error: None,
-                    known_proxies: HashSet::new(),
-                }),
-                debug: has_debug_client_env(),
-                dispatch_lock: Mutex::new(Dispatcher),
-            }),
-        }
+        Self { inner: Arc::new(Inner::new(display, evq, None)) }
+    }
+
+    pub fn create_guest(&self) -> Self {
+        let display = self.inner.display;
+        let evq =
+            unsafe { ffi_dispatch!(wayland_client_handle(), wl_display_create_queue, display) };
+        let mut inner = Inner::new(display, evq, self.inner.display_id.alive.clone());
+        inner.host = Some(self.inner.clone());
+        Self { inner: Arc::new(inner) }
     }
 
     pub fn flush(&self) -> Result<(), WaylandError> {
-        let mut guard = self.lock_state();
-        guard.no_last_error()?;
+        self.inner.no_last_error()?;
         let ret =
-            unsafe { ffi_dispatch!(wayland_client_handle(), wl_display_flush, guard.display) };
+            unsafe { ffi_dispatch!(wayland_client_handle(), wl_display_flush, self.inner.display) };
         if ret < 0 {
-            Err(guard.store_if_not_wouldblock_and_return_error(std::io::Error::last_os_error()))
+            Err(self
+                .inner
+                .store_if_not_wouldblock_and_return_error(std::io::Error::last_os_error()))
         } else {
             Ok(())
         }
     }
 
+    pub fn pending_output_bytes(&self) -> usize {
+        // libwayland does not expose its buffers
+        0
+    }
+
+    pub fn wakeup(&self) -> std::io::Result<()> {
+        self.inner.wakeup.wake()
+    }
+
+    pub fn wakeup_fd(&self) -> std::io::Result<BorrowedFd> {
+        self.inner.wakeup.fd()
+    }
+
+    pub fn take_wakeup(&self) -> bool {
+        self.inner.wakeup.take()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.inner.metrics.snapshot()
+    }
+
+    pub fn dead_object_events(&self) -> HashMap<&'static str, u64> {
+        // libwayland discards these events before they reach the dispatcher
+        HashMap::new()
+    }
+
+    pub fn memory_report(&self) -> MemoryReport {
+        let (mut objects, mut objects_memory) = (0, 0);
+        for shard in &self.inner.proxies {
+            let proxies = shard.lock().unwrap();
+            objects += proxies.known_proxies.len();
+            objects_memory += proxies.known_proxies.len() * std::mem::size_of::<ProxyUserData>()
+                + proxies.known_proxies.capacity() * std::mem::size_of::<*mut wl_proxy>()
+                + proxies.external_data.capacity()
+                    * std::mem::size_of::<(*mut wl_proxy, ExternalData)>();
+        }
+        // libwayland does not expose its queues and buffers
+        MemoryReport {
+            objects,
+            objects_memory,
+            queued_messages: 0,
+            buffered_bytes: 0,
+            buffered_fds: 0,
+            memory: std::mem::size_of::<Inner>() + objects_memory,
+        }
+    }
+
     pub fn poll_fd(&self) -> BorrowedFd {
-        let guard = self.lock_state();
         unsafe {
             BorrowedFd::borrow_raw(ffi_dispatch!(
                 wayland_client_handle(),
                 wl_display_get_fd,
-                guard.display
+                self.inner.display
             ))
         }
     }
 
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        *self.inner.interceptor.write().unwrap() = interceptor;
+    }
+
+    pub fn set_debug_sink(&self, _sink: Option<DebugSink>) {
+        // libwayland prints its own debug output
+    }
+
+    pub fn set_max_buffer_size(&self, _size: usize) {
+        #[cfg(feature = "libwayland_1_23")]
+        unsafe {
+            ffi_dispatch!(
+                wayland_client_1_23_handle(),
+                wl_display_set_max_buffer_size,
+                self.inner.display,
+                _size
+            )
+        }
+    }
+
     pub fn dispatch_inner_queue(&self) -> Result<usize, WaylandError> {
-        self.inner.dispatch_lock.lock().unwrap().dispatch_pending(self.inner.clone())
+        self.inner.dispatch()
     }
 }
 
-impl ConnectionState {
+impl Inner {
+    fn new(
+        display: *mut wl_display,
+        evq: *mut wl_event_queue,
+        display_alive: Option<Arc<AtomicBool>>,
+    ) -> Self {
+        Self {
+            display,
+            evq,
+            display_id: InnerObjectId {
+                id: 1,
+                ptr: display as *mut wl_proxy,
+                alive: display_alive,
+                interface: &WL_DISPLAY_INTERFACE,
+            },
+            has_error: AtomicBool::new(false),
+            last_error: Mutex::new(None),
+            proxies: Default::default(),
+            interceptor: RwLock::new(None),
+            dispatch_lock: Mutex::new(Dispatcher),
+            debug: has_debug_client_env(),
+            wakeup: Wakeup::default(),
+            metrics: MetricsCounters::default(),
+            requests_out: Mutex::new(0),
+            events_in: AtomicU64::new(0),
+            host: None,
+            drop_callbacks: DropCallbacks::default(),
+        }
+    }
+
+    /// The backend owning the connection, the host of a guest
+    fn connection(&self) -> &Inner {
+        self.host.as_deref().map_or(self, Inner::connection)
+    }
+
+    fn dispatch(self: &Arc<Self>) -> Result<usize, WaylandError> {
+        #[cfg(feature = "debug-checks")]
+        let _mark = crate::debug_checks::enter(
+            crate::debug_checks::Activity::Dispatching,
+            Arc::as_ptr(self) as usize,
+            "The events of a connection were dispatched from one of its event callbacks, the system \
+             backend would deadlock.",
+        );
+        self.dispatch_lock.lock().unwrap().dispatch_pending(self.clone())
+    }
+
+    fn interceptor(&self) -> Option<Arc<dyn Interceptor>> {
+        self.interceptor.read().unwrap().clone()
+    }
+
+    fn last_error(&self) -> Option<WaylandError> {
+        if self.has_error.load(Ordering::Acquire) {
+            self.last_error.lock().unwrap().clone()
+        } else {
+            None
+        }
+    }
+
     #[inline]
     fn no_last_error(&self) -> Result<(), WaylandError> {
-        if let Some(ref err) = self.last_error {
-            Err(err.clone())
-        } else {
-            Ok(())
+        match self.last_error() {
+            Some(err) => Err(err),
+            None => Ok(()),
         }
     }
 
     #[inline]
-    fn store_and_return_error(&mut self, err: std::io::Error) -> WaylandError {
+    fn store_and_return_error(&self, err: std::io::Error) -> WaylandError {
         // check if it was actually a protocol error
         let err = if err.raw_os_error() == Some(rustix::io::Errno::PROTO.raw_os_error()) {
             let mut object_id = 0;
@@ -360,15 +551,16 @@ impl ConnectionState {
                 message: String::new(),
             })
         } else {
-            WaylandError::Io(err)
+            err.into()
         };
         crate::log_error!("{}", err);
-        self.last_error = Some(err.clone());
+        *self.last_error.lock().unwrap() = Some(err.clone());
+        self.has_error.store(true, Ordering::Release);
         err
     }
 
     #[inline]
-    fn store_if_not_wouldblock_and_return_error(&mut self, e: std::io::Error) -> WaylandError {
+    fn store_if_not_wouldblock_and_return_error(&self, e: std::io::Error) -> WaylandError {
         if e.kind() != std::io::ErrorKind::WouldBlock {
             self.store_and_return_error(e)
         } else {
@@ -379,14 +571,13 @@ impl ConnectionState {
 
 impl Dispatcher {
     fn dispatch_pending(&self, inner: Arc<Inner>) -> Result<usize, WaylandError> {
-        let (display, evq) = {
-            let guard = inner.state.lock().unwrap();
-            (guard.display, guard.evq)
-        };
+        let (display, evq) = (inner.display, inner.evq);
         let backend = Backend { backend: InnerBackend { inner } };
 
         // We erase the lifetime of the Handle to be able to store it in the tls,
         // it's safe as it'll only last until the end of this function call anyway
+        // the display reports its own errors, but not the ones stored by the backend
+        backend.backend.inner.no_last_error()?;
         let ret = BACKEND.set(&backend, || unsafe {
             if evq.is_null() {
                 ffi_dispatch!(wayland_client_handle(), wl_display_dispatch_pending, display)
@@ -403,11 +594,10 @@ impl Dispatcher {
             Err(backend
                 .backend
                 .inner
-                .state
-                .lock()
-                .unwrap()
                 .store_if_not_wouldblock_and_return_error(std::io::Error::last_os_error()))
         } else {
+            // an event callback may have panicked
+            backend.backend.inner.no_last_error()?;
             Ok(ret as usize)
         }
     }
@@ -422,10 +612,7 @@ pub struct InnerReadEventsGuard {
 
 impl InnerReadEventsGuard {
     pub fn try_new(backend: InnerBackend) -> Option<Self> {
-        let (display, evq) = {
-            let guard = backend.lock_state();
-            (guard.display, guard.evq)
-        };
+        let (display, evq) = (backend.inner.display, backend.inner.evq);
 
         let ret = unsafe {
             if evq.is_null() {
@@ -459,13 +646,10 @@ impl InnerReadEventsGuard {
             // we have done the reading, and there is an error
             Err(self
                 .inner
-                .state
-                .lock()
-                .unwrap()
                 .store_if_not_wouldblock_and_return_error(std::io::Error::last_os_error()))
         } else {
             // the read occured, dispatch pending events
-            self.inner.dispatch_lock.lock().unwrap().dispatch_pending(self.inner.clone())
+            self.inner.dispatch()
         }
     }
 }
@@ -482,11 +666,11 @@ impl Drop for InnerReadEventsGuard {
 
 impl InnerBackend {
     pub fn display_id(&self) -> ObjectId {
-        ObjectId { id: self.lock_state().display_id.clone() }
+        ObjectId { id: self.inner.display_id.clone() }
     }
 
     pub fn last_error(&self) -> Option<WaylandError> {
-        self.lock_state().last_error.clone()
+        self.inner.last_error()
     }
 
     pub fn info(&self, ObjectId { id }: ObjectId) -> Result<ObjectInfo, InvalidId> {
@@ -505,6 +689,45 @@ impl InnerBackend {
         Ok(ObjectInfo { id: id.id, interface: id.interface, version })
     }
 
+    pub fn all_objects(&self) -> Vec<ObjectEntry> {
+        let shards =
+            self.inner.proxies.iter().map(|shard| shard.lock().unwrap()).collect::<Vec<_>>();
+        let mut objects = Vec::with_capacity(
+            shards.iter().map(|shard| shard.known_proxies.len()).sum::<usize>() + 1,
+        );
+        if self.inner.display_id.alive.is_some() {
+            objects.push(ObjectEntry {
+                id: ObjectId { id: self.inner.display_id.clone() },
+                info: ObjectInfo { id: 1, interface: &WL_DISPLAY_INTERFACE, version: 1 },
+                alive: true,
+            });
+        }
+        for &ptr in shards.iter().flat_map(|shard| shard.known_proxies.iter()) {
+            // Safety: the known proxies are rust-managed, their user data is valid
+            let (udata, id, version) = unsafe {
+                (
+                    &*(ffi_dispatch!(wayland_client_handle(), wl_proxy_get_user_data, ptr)
+                        as *mut ProxyUserData),
+                    ffi_dispatch!(wayland_client_handle(), wl_proxy_get_id, ptr),
+                    ffi_dispatch!(wayland_client_handle(), wl_proxy_get_version, ptr),
+                )
+            };
+            objects.push(ObjectEntry {
+                id: ObjectId {
+                    id: InnerObjectId {
+                        id,
+                        ptr,
+                        alive: Some(udata.alive.clone()),
+                        interface: udata.interface,
+                    },
+                },
+                info: ObjectInfo { id, interface: udata.interface, version },
+                alive: udata.alive.load(Ordering::Acquire),
+            });
+        }
+        objects
+    }
+
     pub fn null_id() -> ObjectId {
         ObjectId {
             id: InnerObjectId {
@@ -518,11 +741,10 @@ impl InnerBackend {
 
     pub fn send_request(
         &self,
-        Message { sender_id: ObjectId { id }, opcode, args }: Message<ObjectId, RawFd>,
+        Message { sender_id: ObjectId { id }, opcode, mut args }: Message<ObjectId, BorrowedFd<'_>>,
         data: Option<Arc<dyn ObjectData>>,
         child_spec: Option<(&'static Interface, u32)>,
     ) -> Result<ObjectId, InvalidId> {
-        let mut guard = self.lock_state();
         // check that the argument list is valid
         let message_desc = match id.interface.requests.get(opcode as usize) {
             Some(msg) => msg,
@@ -534,7 +756,13 @@ impl InnerBackend {
         if !id.alive.as_ref().map(|a| a.load(Ordering::Acquire)).unwrap_or(true) || id.ptr.is_null()
         {
             if self.inner.debug {
-                debug::print_send_message(id.interface.name, id.id, message_desc.name, &args, true);
+                debug::print_discarded_message(
+                    &DebugOutput::Stderr,
+                    id.interface.name,
+                    id.id,
+                    message_desc.name,
+                    &args,
+                );
             }
             return Err(InvalidId);
         }
@@ -552,6 +780,20 @@ impl InnerBackend {
             );
         }
 
+        // requests creating an object are intercepted once it exists, so that its id is known
+        let creates_object =
+            message_desc.signature.iter().any(|arg| matches!(arg, ArgumentType::NewId));
+        let interceptor = self.inner.interceptor();
+        let mut requests_out = self.inner.connection().requests_out.lock().unwrap();
+        let seq = *requests_out + 1;
+        if let (Some(interceptor), false) = (interceptor.as_deref(), creates_object) {
+            let msg = Message { sender_id: ObjectId { id: id.clone() }, opcode, args };
+            if !interceptor.request_sequenced(seq, &msg) && can_veto(message_desc) {
+                return Ok(Self::null_id());
+            }
+            args = msg.args;
+        }
+
         // Prepare the child object data
         let child_spec = if message_desc
             .signature
@@ -598,10 +840,7 @@ impl InnerBackend {
 
         let child_interface_ptr = child_spec
             .as_ref()
-            .map(|(i, _)| {
-                i.c_ptr.expect("[wayland-backend-sys] Cannot use Interface without c_ptr!")
-                    as *const _
-            })
+            .map(|(i, _)| c_interface(i) as *const _)
             .unwrap_or(std::ptr::null());
         let child_version = child_spec.as_ref().map(|(_, v)| *v).unwrap_or(parent_version);
 
@@ -613,7 +852,7 @@ impl InnerBackend {
                 Argument::Uint(u) => argument_list.push(wl_argument { u }),
                 Argument::Int(i) => argument_list.push(wl_argument { i }),
                 Argument::Fixed(f) => argument_list.push(wl_argument { f }),
-                Argument::Fd(h) => argument_list.push(wl_argument { h }),
+                Argument::Fd(fd) => argument_list.push(wl_argument { h: fd.as_raw_fd() }),
                 Argument::Array(ref a) => {
                     let a = Box::new(wl_array {
                         size: a.len(),
@@ -649,7 +888,8 @@ impl InnerBackend {
             }
         }
 
-        let ret = if guard.evq.is_null() || child_spec.is_none() {
+        let evq = self.inner.evq;
+        let ret = if evq.is_null() || child_spec.is_none() {
             unsafe {
                 ffi_dispatch!(
                     wayland_client_handle(),
@@ -666,7 +906,7 @@ impl InnerBackend {
             unsafe {
                 let wrapped_ptr =
                     ffi_dispatch!(wayland_client_handle(), wl_proxy_create_wrapper, id.ptr);
-                ffi_dispatch!(wayland_client_handle(), wl_proxy_set_queue, wrapped_ptr, guard.evq);
+                ffi_dispatch!(wayland_client_handle(), wl_proxy_set_queue, wrapped_ptr, evq);
                 let ret = ffi_dispatch!(
                     wayland_client_handle(),
                     wl_proxy_marshal_array_constructor_versioned,
@@ -686,8 +926,12 @@ impl InnerBackend {
         }
 
         if ret.is_null() && child_spec.is_some() {
-            panic!("[wayland-backend-sys] libwayland reported an allocation failure.");
+            // the ids of the client namespace are exhausted, or the allocation failed, and the
+            // request was not sent
+            return Err(InvalidId);
         }
+        self.inner.metrics.message_out(count_fds(message_desc.signature));
+        *requests_out = seq;
 
         // initialize the proxy
         let child_id = if let Some((child_interface, _)) = child_spec {
@@ -715,7 +959,10 @@ impl InnerBackend {
                     );
                 }
             };
-            guard.known_proxies.insert(ret);
+            let mut proxies = self.lock_proxies(ret);
+            proxies.known_proxies.insert(ret);
+            proxies.external_data.remove(&ret);
+            drop(proxies);
             unsafe {
                 ffi_dispatch!(
                     wayland_client_handle(),
@@ -731,6 +978,21 @@ impl InnerBackend {
             Self::null_id()
         };
 
+        if let (Some(interceptor), true) = (interceptor.as_deref(), creates_object) {
+            let args = args
+                .into_iter()
+                .map(|arg| match arg {
+                    Argument::NewId(_) => Argument::NewId(child_id.clone()),
+                    arg => arg,
+                })
+                .collect();
+            interceptor.request_sequenced(
+                seq,
+                &Message { sender_id: ObjectId { id: id.clone() }, opcode, args },
+            );
+        }
+        drop(requests_out);
+
         if message_desc.is_destructor {
             if let Some(ref alive) = id.alive {
                 let udata = unsafe {
@@ -751,7 +1013,7 @@ impl InnerBackend {
                 alive.store(false, Ordering::Release);
                 udata.data.destroyed(ObjectId { id: id.clone() });
             }
-            guard.known_proxies.remove(&id.ptr);
+            self.lock_proxies(id.ptr).known_proxies.remove(&id.ptr);
             unsafe {
                 ffi_dispatch!(wayland_client_handle(), wl_proxy_destroy, id.ptr);
             }
@@ -761,7 +1023,11 @@ impl InnerBackend {
     }
 
     pub fn get_data(&self, ObjectId { id }: ObjectId) -> Result<Arc<dyn ObjectData>, InvalidId> {
-        if !id.alive.as_ref().map(|a| a.load(Ordering::Acquire)).unwrap_or(false) {
+        let alive = match id.alive {
+            Some(ref alive) => alive.load(Ordering::Acquire),
+            None => return self.lock_proxies(id.ptr).external_data(&id),
+        };
+        if !alive {
             return Err(InvalidId);
         }
 
@@ -782,7 +1048,11 @@ impl InnerBackend {
         ObjectId { id }: ObjectId,
         data: Arc<dyn ObjectData>,
     ) -> Result<(), InvalidId> {
-        if !id.alive.as_ref().map(|a| a.load(Ordering::Acquire)).unwrap_or(false) {
+        let alive = match id.alive {
+            Some(ref alive) => alive.load(Ordering::Acquire),
+            None => return self.set_external_data(&id, data),
+        };
+        if !alive {
             return Err(InvalidId);
         }
 
@@ -800,6 +1070,32 @@ impl InnerBackend {
 
         Ok(())
     }
+
+    pub fn replace_data(
+        &self,
+        ObjectId { id }: ObjectId,
+        data: Arc<dyn ObjectData>,
+    ) -> Result<Arc<dyn ObjectData>, InvalidId> {
+        let alive = match id.alive {
+            Some(ref alive) => alive.load(Ordering::Acquire),
+            None => return self.replace_external_data(&id, data),
+        };
+        if !alive {
+            return Err(InvalidId);
+        }
+
+        // Cannot touch the user_data of the display
+        if id.id == 1 {
+            return Err(InvalidId);
+        }
+
+        let udata = unsafe {
+            &mut *(ffi_dispatch!(wayland_client_handle(), wl_proxy_get_user_data, id.ptr)
+                as *mut ProxyUserData)
+        };
+
+        Ok(std::mem::replace(&mut udata.data, data))
+    }
 }
 
 unsafe extern "C" fn dispatcher_func(
@@ -809,8 +1105,42 @@ unsafe extern "C" fn dispatcher_func(
     _: *const wl_message,
     args: *const wl_argument,
 ) -> c_int {
-    let proxy = proxy as *mut wl_proxy;
+    // a panic must not unwind through the frames of libwayland, it is turned into a fatal error of the
+    // backend, and the remaining events of the dispatch are skipped
+    if BACKEND.is_set()
+        && BACKEND.with(|backend| backend.backend.inner.has_error.load(Ordering::Acquire))
+    {
+        return -1;
+    }
+    let payload = match panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+        dispatch_event(proxy as *mut wl_proxy, opcode, args)
+    })) {
+        Ok(ret) => return ret,
+        Err(payload) => payload,
+    };
+    let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(msg), _) => msg,
+        (None, Some(msg)) => msg.as_str(),
+        (None, None) => "Box<dyn Any>",
+    };
+    if !BACKEND.is_set() {
+        // the events were dispatched by foreign code, there is no backend to report the error to
+        crate::log_error!(
+            "An event callback panicked outside of the backend dispatching: {}",
+            message
+        );
+        std::process::abort();
+    }
+    BACKEND.with(|backend| {
+        backend.backend.inner.store_and_return_error(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("An event callback panicked: {}", message),
+        ))
+    });
+    -1
+}
 
+unsafe fn dispatch_event(proxy: *mut wl_proxy, opcode: u32, args: *const wl_argument) -> c_int {
     // Safety: if our dispatcher fun is called, then the associated proxy must be rust_managed and have a valid user_data
     let udata_ptr = unsafe {
         ffi_dispatch!(wayland_client_handle(), wl_proxy_get_user_data, proxy) as *mut ProxyUserData
@@ -826,34 +1156,40 @@ unsafe extern "C" fn dispatcher_func(
         }
     };
 
-    let mut parsed_args =
-        SmallVec::<[Argument<ObjectId, OwnedFd>; 4]>::with_capacity(message_desc.signature.len());
+    let mut parsed_args = SmallVec::<[BorrowedArgument<ObjectId, OwnedFd>; 4]>::with_capacity(
+        message_desc.signature.len(),
+    );
     let mut arg_interfaces = message_desc.arg_interfaces.iter().copied();
     let mut created = None;
     // Safety (args deference): the args array provided by libwayland is well-formed
     for (i, typ) in message_desc.signature.iter().enumerate() {
         match typ {
-            ArgumentType::Uint => parsed_args.push(Argument::Uint(unsafe { (*args.add(i)).u })),
-            ArgumentType::Int => parsed_args.push(Argument::Int(unsafe { (*args.add(i)).i })),
-            ArgumentType::Fixed => parsed_args.push(Argument::Fixed(unsafe { (*args.add(i)).f })),
-            ArgumentType::Fd => {
-                parsed_args.push(Argument::Fd(unsafe { OwnedFd::from_raw_fd((*args.add(i)).h) }))
+            ArgumentType::Uint => {
+                parsed_args.push(BorrowedArgument::Uint(unsafe { (*args.add(i)).u }))
+            }
+            ArgumentType::Int => {
+                parsed_args.push(BorrowedArgument::Int(unsafe { (*args.add(i)).i }))
+            }
+            ArgumentType::Fixed => {
+                parsed_args.push(BorrowedArgument::Fixed(unsafe { (*args.add(i)).f }))
             }
+            ArgumentType::Fd => parsed_args
+                .push(BorrowedArgument::Fd(unsafe { OwnedFd::from_raw_fd((*args.add(i)).h) })),
             ArgumentType::Array => {
                 let array = unsafe { &*((*args.add(i)).a) };
                 // Safety: the array provided by libwayland must be valid
                 let content =
                     unsafe { std::slice::from_raw_parts(array.data as *mut u8, array.size) };
-                parsed_args.push(Argument::Array(Box::new(content.into())));
+                parsed_args.push(BorrowedArgument::Array(content));
             }
             ArgumentType::Str(_) => {
                 let ptr = unsafe { (*args.add(i)).s };
                 // Safety: the c-string provided by libwayland must be valid
                 if !ptr.is_null() {
                     let cstr = unsafe { std::ffi::CStr::from_ptr(ptr) };
-                    parsed_args.push(Argument::Str(Some(Box::new(cstr.into()))));
+                    parsed_args.push(BorrowedArgument::Str(Some(cstr)));
                 } else {
-                    parsed_args.push(Argument::Str(None));
+                    parsed_args.push(BorrowedArgument::Str(None));
                 }
             }
             ArgumentType::Object(_) => {
@@ -882,7 +1218,7 @@ unsafe extern "C" fn dispatcher_func(
                             );
                             return -1;
                         }
-                        parsed_args.push(Argument::Object(ObjectId {
+                        parsed_args.push(BorrowedArgument::Object(ObjectId {
                             id: InnerObjectId {
                                 alive: Some(obj_udata.alive.clone()),
                                 ptr: obj,
@@ -891,7 +1227,7 @@ unsafe extern "C" fn dispatcher_func(
                             },
                         }));
                     } else {
-                        parsed_args.push(Argument::Object(ObjectId {
+                        parsed_args.push(BorrowedArgument::Object(ObjectId {
                             id: InnerObjectId {
                                 alive: None,
                                 id: obj_id,
@@ -902,7 +1238,7 @@ unsafe extern "C" fn dispatcher_func(
                     }
                 } else {
                     // libwayland-client.so checks nulls for us
-                    parsed_args.push(Argument::Object(ObjectId {
+                    parsed_args.push(BorrowedArgument::Object(ObjectId {
                         id: InnerObjectId {
                             alive: None,
                             id: 0,
@@ -945,9 +1281,9 @@ unsafe extern "C" fn dispatcher_func(
                         &RUST_MANAGED as *const u8 as *const c_void,
                         child_udata as *mut c_void
                     );
-                    parsed_args.push(Argument::NewId(ObjectId { id: child_id }));
+                    parsed_args.push(BorrowedArgument::NewId(ObjectId { id: child_id }));
                 } else {
-                    parsed_args.push(Argument::NewId(ObjectId {
+                    parsed_args.push(BorrowedArgument::NewId(ObjectId {
                         id: InnerObjectId {
                             id: 0,
                             ptr: std::ptr::null_mut(),
@@ -971,18 +1307,28 @@ unsafe extern "C" fn dispatcher_func(
     };
 
     let ret = BACKEND.with(|backend| {
-        let mut guard = backend.backend.lock_state();
         if let Some((ref new_id, _)) = created {
-            guard.known_proxies.insert(new_id.ptr);
+            let mut proxies = backend.backend.lock_proxies(new_id.ptr);
+            proxies.known_proxies.insert(new_id.ptr);
+            proxies.external_data.remove(&new_id.ptr);
         }
         if message_desc.is_destructor {
-            guard.known_proxies.remove(&proxy);
+            backend.backend.lock_proxies(proxy).known_proxies.remove(&proxy);
+        }
+        let interceptor = backend.backend.inner.interceptor();
+        let msg =
+            BorrowedMessage { sender_id: id.clone(), opcode: opcode as u16, args: parsed_args };
+        let metrics = &backend.backend.inner.metrics;
+        metrics.message_in(count_fds(message_desc.signature));
+        let seq = backend.backend.inner.connection().events_in.fetch_add(1, Ordering::Relaxed) + 1;
+        match interceptor {
+            Some(interceptor)
+                if !interceptor.event_sequenced(seq, &msg) && can_veto(message_desc) =>
+            {
+                None
+            }
+            _ => metrics.time_dispatch(|| udata.data.clone().event_borrowed(backend, msg)),
         }
-        std::mem::drop(guard);
-        udata.data.clone().event(
-            backend,
-            Message { sender_id: id.clone(), opcode: opcode as u16, args: parsed_args },
-        )
     });
 
     if message_desc.is_destructor {
@@ -1013,16 +1359,64 @@ unsafe extern "C" fn dispatcher_func(
     0
 }
 
-#[cfg(feature = "log")]
+#[cfg(any(feature = "log", feature = "client_system", feature = "server_system"))]
 extern "C" {
     fn wl_log_trampoline_to_rust_client(fmt: *const std::os::raw::c_char, list: *const c_void);
 }
 
-impl Drop for ConnectionState {
+impl ProxyState {
+    fn external_data(&self, id: &InnerObjectId) -> Result<Arc<dyn ObjectData>, InvalidId> {
+        match self.external_data.get(&id.ptr) {
+            Some(external)
+                if external.id == id.id && same_interface(external.interface, id.interface) =>
+            {
+                Ok(external.data.clone())
+            }
+            _ => Err(InvalidId),
+        }
+    }
+}
+
+impl InnerBackend {
+    fn set_external_data(
+        &self,
+        id: &InnerObjectId,
+        data: Arc<dyn ObjectData>,
+    ) -> Result<(), InvalidId> {
+        // the user data of the display and of null objects cannot be touched
+        if id.ptr.is_null() || id.ptr == self.inner.display as *mut wl_proxy {
+            return Err(InvalidId);
+        }
+        self.lock_proxies(id.ptr)
+            .external_data
+            .insert(id.ptr, ExternalData { id: id.id, interface: id.interface, data });
+        Ok(())
+    }
+
+    fn replace_external_data(
+        &self,
+        id: &InnerObjectId,
+        data: Arc<dyn ObjectData>,
+    ) -> Result<Arc<dyn ObjectData>, InvalidId> {
+        let mut proxies = self.lock_proxies(id.ptr);
+        // only an object data previously set can be replaced
+        let previous = proxies.external_data(id)?;
+        proxies
+            .external_data
+            .insert(id.ptr, ExternalData { id: id.id, interface: id.interface, data });
+        Ok(previous)
+    }
+}
+
+impl Drop for Inner {
     fn drop(&mut self) {
         // Cleanup the objects we know about, libwayland will discard any future message
         // they receive.
-        for proxy_ptr in self.known_proxies.drain() {
+        let known_proxies = self
+            .proxies
+            .iter_mut()
+            .flat_map(|shard| shard.get_mut().unwrap().known_proxies.drain());
+        for proxy_ptr in known_proxies {
             let _ = unsafe {
                 Box::from_raw(ffi_dispatch!(
                     wayland_client_handle(),
@@ -0,0 +1,417 @@
+//! Event sources of the `wl_event_loop` of the system server backend
+//!
+//! libwayland runs the server on its own event loop, whose file descriptor is given by
+//! [`Backend::poll_fd()`](server::Backend::poll_fd). Additional event sources can be added to it, they
+//! are then dispatched by [`Backend::dispatch_all_clients()`](server::Backend::dispatch_all_clients)
+//! along with the clients, without the need of a second poll loop.
+
+use std::{
+    collections::HashMap,
+    io,
+    os::raw::{c_int, c_void},
+    os::unix::io::{AsRawFd, BorrowedFd},
+    time::Duration,
+};
+
+use wayland_sys::{ffi_dispatch, server::*};
+
+use super::server;
+
+/// The readiness a file descriptor source waits for
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Interest {
+    /// Wait for the file descriptor to be readable
+    pub readable: bool,
+    /// Wait for the file descriptor to be writable
+    pub writable: bool,
+}
+
+impl Interest {
+    /// Wait for the file descriptor to be readable
+    pub const READ: Self = Self { readable: true, writable: false };
+    /// Wait for the file descriptor to be writable
+    pub const WRITE: Self = Self { readable: false, writable: true };
+    /// Wait for the file descriptor to be readable or writable
+    pub const BOTH: Self = Self { readable: true, writable: true };
+
+    fn mask(self) -> u32 {
+        let mut mask = 0;
+        if self.readable {
+            mask |= WL_EVENT_READABLE;
+        }
+        if self.writable {
+            mask |= WL_EVENT_WRITABLE;
+        }
+        mask
+    }
+}
+
+/// The readiness of a file descriptor reported to its source
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Readiness {
+    /// The file descriptor is readable
+    pub readable: bool,
+    /// The file descriptor is writable
+    pub writable: bool,
+    /// The peer of the file descriptor hung up
+    pub hangup: bool,
+    /// An error occurred on the file descriptor
+    pub error: bool,
+}
+
+impl Readiness {
+    fn from_mask(mask: u32) -> Self {
+        Self {
+            readable: mask & WL_EVENT_READABLE != 0,
+            writable: mask & WL_EVENT_WRITABLE != 0,
+            hangup: mask & WL_EVENT_HANGUP != 0,
+            error: mask & WL_EVENT_ERROR != 0,
+        }
+    }
+}
+
+/// The identifier of an event source added to the event loop
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceId(u64);
+
+enum Callback {
+    Fd(Box<dyn FnMut(Readiness) + Send>),
+    Timer(Box<dyn FnMut() -> Option<Duration> + Send>),
+    Signal(Box<dyn FnMut() + Send>),
+    // taken when the source fires
+    Idle(Option<Box<dyn FnOnce() + Send>>),
+}
+
+/// The user data of an event source
+struct SourceData {
+    ptr: *mut wl_event_source,
+    callback: Callback,
+}
+
+impl SourceData {
+    /// Whether the source is still registered, libwayland destroys idle sources once they fired
+    fn is_registered(&self) -> bool {
+        !matches!(self.callback, Callback::Idle(None))
+    }
+}
+
+/// The event sources added to the event loop by the user, removed when dropped
+pub(crate) struct EventSources {
+    event_loop: *mut wl_event_loop,
+    sources: HashMap<SourceId, *mut SourceData>,
+    next_id: u64,
+}
+
+impl std::fmt::Debug for EventSources {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSources").field("sources", &self.sources.len()).finish_non_exhaustive()
+    }
+}
+
+impl EventSources {
+    pub(crate) fn new(event_loop: *mut wl_event_loop) -> Self {
+        Self { event_loop, sources: HashMap::new(), next_id: 0 }
+    }
+
+    fn insert(
+        &mut self,
+        callback: Callback,
+        add: impl FnOnce(*mut wl_event_loop, *mut c_void) -> *mut wl_event_source,
+    ) -> io::Result<SourceId> {
+        // forget the idle sources that already fired
+        self.sources.retain(|_, &mut data| {
+            // Safety: the data stays valid until it is removed from the map
+            let registered = unsafe { (*data).is_registered() };
+            if !registered {
+                let _ = unsafe { Box::from_raw(data) };
+            }
+            registered
+        });
+
+        let data = Box::into_raw(Box::new(SourceData { ptr: std::ptr::null_mut(), callback }));
+        let ptr = add(self.event_loop, data as *mut c_void);
+        if ptr.is_null() {
+            let err = io::Error::last_os_error();
+            // Safety: libwayland did not keep the data
+            let _ = unsafe { Box::from_raw(data) };
+            return Err(err);
+        }
+        // Safety: the data was just allocated
+        unsafe { (*data).ptr = ptr };
+
+        let id = SourceId(self.next_id);
+        self.next_id += 1;
+        self.sources.insert(id, data);
+        Ok(id)
+    }
+
+    fn get(&mut self, id: SourceId) -> io::Result<&mut SourceData> {
+        match self.sources.get(&id) {
+            // Safety: the data stays valid until it is removed from the map
+            Some(&data) => Ok(unsafe { &mut *data }),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "unknown event source")),
+        }
+    }
+
+    fn add_fd(
+        &mut self,
+        fd: BorrowedFd<'_>,
+        interest: Interest,
+        callback: Box<dyn FnMut(Readiness) + Send>,
+    ) -> io::Result<SourceId> {
+        // libwayland duplicates the file descriptor
+        self.insert(Callback::Fd(callback), |event_loop, data| unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_loop_add_fd,
+                event_loop,
+                fd.as_raw_fd(),
+                interest.mask(),
+                fd_dispatch,
+                data
+            )
+        })
+    }
+
+    fn add_timer(
+        &mut self,
+        callback: Box<dyn FnMut() -> Option<Duration> + Send>,
+    ) -> io::Result<SourceId> {
+        self.insert(Callback::Timer(callback), |event_loop, data| unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_loop_add_timer,
+                event_loop,
+                timer_dispatch,
+                data
+            )
+        })
+    }
+
+    fn add_signal(
+        &mut self,
+        signal: i32,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> io::Result<SourceId> {
+        self.insert(Callback::Signal(callback), |event_loop, data| unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_loop_add_signal,
+                event_loop,
+                signal,
+                signal_dispatch,
+                data
+            )
+        })
+    }
+
+    fn add_idle(&mut self, callback: Box<dyn FnOnce() + Send>) -> io::Result<SourceId> {
+        self.insert(Callback::Idle(Some(callback)), |event_loop, data| unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_loop_add_idle,
+                event_loop,
+                idle_dispatch,
+                data
+            )
+        })
+    }
+
+    fn update_fd(&mut self, id: SourceId, interest: Interest) -> io::Result<()> {
+        let data = self.get(id)?;
+        let Callback::Fd(_) = data.callback else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a file descriptor source",
+            ));
+        };
+        let ret = unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_source_fd_update,
+                data.ptr,
+                interest.mask()
+            )
+        };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn update_timer(&mut self, id: SourceId, delay: Option<Duration>) -> io::Result<()> {
+        let data = self.get(id)?;
+        let Callback::Timer(_) = data.callback else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a timer source"));
+        };
+        let ret = unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_source_timer_update,
+                data.ptr,
+                timer_delay(delay)
+            )
+        };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn remove(&mut self, id: SourceId) {
+        if let Some(data) = self.sources.remove(&id) {
+            // Safety: the data is valid and no longer referenced by the map
+            unsafe { remove_source(data) };
+        }
+    }
+}
+
+impl Drop for EventSources {
+    fn drop(&mut self) {
+        for (_, data) in self.sources.drain() {
+            // Safety: the data is valid and no longer referenced by the map
+            unsafe { remove_source(data) };
+        }
+    }
+}
+
+unsafe fn remove_source(data: *mut SourceData) {
+    // Safety: the data was allocated by EventSources::insert
+    let data = unsafe { Box::from_raw(data) };
+    if data.is_registered() {
+        unsafe {
+            ffi_dispatch!(wayland_server_handle(), wl_event_source_remove, data.ptr);
+        }
+    }
+}
+
+/// The delay in milliseconds of a timer, 0 disarms it
+fn timer_delay(delay: Option<Duration>) -> c_int {
+    match delay {
+        // round up to not fire early, nor disarm the timer with a sub-millisecond delay
+        Some(delay) => {
+            ((delay.as_nanos() + 999_999) / 1_000_000).clamp(1, c_int::MAX as u128) as c_int
+        }
+        None => 0,
+    }
+}
+
+unsafe extern "C" fn fd_dispatch(_fd: c_int, mask: u32, data: *mut c_void) -> c_int {
+    // Safety: the data of a source is valid as long as it is registered
+    let data = unsafe { &mut *(data as *mut SourceData) };
+    if let Callback::Fd(ref mut callback) = data.callback {
+        callback(Readiness::from_mask(mask));
+    }
+    0
+}
+
+unsafe extern "C" fn timer_dispatch(data: *mut c_void) -> c_int {
+    // Safety: the data of a source is valid as long as it is registered
+    let data = unsafe { &mut *(data as *mut SourceData) };
+    if let Callback::Timer(ref mut callback) = data.callback {
+        // timers are one-shot, re-arm it with the delay returned by the callback
+        let delay = timer_delay(callback());
+        unsafe {
+            ffi_dispatch!(wayland_server_handle(), wl_event_source_timer_update, data.ptr, delay);
+        }
+    }
+    0
+}
+
+unsafe extern "C" fn signal_dispatch(_signal: c_int, data: *mut c_void) -> c_int {
+    // Safety: the data of a source is valid as long as it is registered
+    let data = unsafe { &mut *(data as *mut SourceData) };
+    if let Callback::Signal(ref mut callback) = data.callback {
+        callback();
+    }
+    0
+}
+
+unsafe extern "C" fn idle_dispatch(data: *mut c_void) {
+    // Safety: the data of a source is valid as long as it is registered
+    let data = unsafe { &mut *(data as *mut SourceData) };
+    // libwayland destroys the source once this returns
+    if let Callback::Idle(ref mut callback) = data.callback {
+        if let Some(callback) = callback.take() {
+            callback();
+        }
+    }
+}
+
+impl<D> server::Backend<D> {
+    /// Add a file descriptor to the event loop
+    ///
+    /// The callback is invoked with the readiness of the file descriptor when it becomes ready for the
+    /// given interest, or when it hangs up or errors. The file descriptor is duplicated by libwayland,
+    /// the source does not need to be removed before closing it.
+    pub fn add_fd_source<F>(
+        &mut self,
+        fd: BorrowedFd<'_>,
+        interest: Interest,
+        callback: F,
+    ) -> io::Result<SourceId>
+    where
+        F: FnMut(Readiness) + Send + 'static,
+    {
+        self.backend.event_sources().add_fd(fd, interest, Box::new(callback))
+    }
+
+    /// Add a timer to the event loop
+    ///
+    /// The timer is initially disarmed, it is armed with [`Backend::update_timer()`](server::Backend::update_timer).
+    /// When it fires the callback is invoked, and the timer is re-armed with the delay it returns, if any.
+    /// The delays are rounded up to the millisecond.
+    pub fn add_timer_source<F>(&mut self, callback: F) -> io::Result<SourceId>
+    where
+        F: FnMut() -> Option<Duration> + Send + 'static,
+    {
+        self.backend.event_sources().add_timer(Box::new(callback))
+    }
+
+    /// Add a signal to the event loop
+    ///
+    /// The callback is invoked when the process receives the signal. The signal is blocked in the
+    /// calling thread by libwayland, so that it is delivered to the event loop.
+    pub fn add_signal_source<F>(&mut self, signal: i32, callback: F) -> io::Result<SourceId>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.backend.event_sources().add_signal(signal, Box::new(callback))
+    }
+
+    /// Add an idle callback to the event loop
+    ///
+    /// The callback is invoked once, at the start of the next dispatch of the event loop. Adding it does
+    /// not wake up the event loop.
+    pub fn add_idle_source<F>(&mut self, callback: F) -> io::Result<SourceId>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.backend.event_sources().add_idle(Box::new(callback))
+    }
+
+    /// Change the interest of a file descriptor source
+    ///
+    /// Fails if the source does not exist or is not a file descriptor source.
+    pub fn update_fd_interest(&mut self, id: SourceId, interest: Interest) -> io::Result<()> {
+        self.backend.event_sources().update_fd(id, interest)
+    }
+
+    /// Arm a timer source to fire after the given delay, or disarm it with `None`
+    ///
+    /// Fails if the source does not exist or is not a timer source.
+    pub fn update_timer(&mut self, id: SourceId, delay: Option<Duration>) -> io::Result<()> {
+        self.backend.event_sources().update_timer(id, delay)
+    }
+
+    /// Remove an event source from the event loop
+    ///
+    /// Does nothing if the source does not exist, or is an idle source that already fired. All the sources
+    /// are removed when the backend is dropped.
+    pub fn remove_source(&mut self, id: SourceId) {
+        self.backend.event_sources().remove(id)
+    }
+}
@@ -1,8 +1,12 @@
 //! Implementations of the Wayland backends using the system `libwayland`
 
+#[cfg(all(any(test, feature = "client_system"), feature = "libwayland_1_18"))]
+use std::{ffi::CStr, os::raw::c_char};
+
 use crate::protocol::ArgumentType;
 use wayland_sys::common::{wl_argument, wl_array};
 
+mod c_interface;
 #[cfg(any(test, feature = "client_system"))]
 mod client_impl;
 #[cfg(any(test, feature = "server_system"))]
@@ -23,6 +27,11 @@ unsafe fn free_arrays(signature: &[ArgumentType], arglist: &[wl_argument]) {
     }
 }
 
+/// Number of file descriptors in a message of given signature, for the metrics
+fn count_fds(signature: &[ArgumentType]) -> usize {
+    signature.iter().filter(|arg| matches!(arg, ArgumentType::Fd)).count()
+}
+
 /// Client-side implementation of a Wayland protocol backend using `libwayland`
 ///
 /// Entrypoints are:
@@ -61,6 +70,72 @@ impl client::ObjectId {
     }
 }
 
+#[cfg(all(any(test, feature = "client_system"), feature = "libwayland_1_18"))]
+impl client::ObjectId {
+    /// Set the tag of the underlying proxy, or remove it
+    ///
+    /// Fails if the object is no longer alive. See [`ProxyTag`](crate::sys::ProxyTag) for details.
+    pub fn set_tag(&self, tag: Option<&'static ProxyTag>) -> Result<(), client::InvalidId> {
+        let tag = tag.map_or(std::ptr::null(), |tag| &tag.0 as *const _);
+        self.id.set_tag(tag)
+    }
+
+    /// Checks whether the underlying proxy has given tag
+    ///
+    /// Returns `false` if the object is no longer alive.
+    pub fn has_tag(&self, tag: &'static ProxyTag) -> bool {
+        self.tag_ptr().map_or(false, |ptr| std::ptr::eq(ptr, &tag.0))
+    }
+
+    /// Get the raw tag of the underlying proxy, as returned by `wl_proxy_get_tag()`
+    ///
+    /// This can be used to compare it with the tags of C libraries. Fails if the object is no longer
+    /// alive.
+    pub fn tag_ptr(&self) -> Result<*const *const c_char, client::InvalidId> {
+        self.id.tag()
+    }
+}
+
+/// A tag to recognize the proxies of a library sharing its connection with other code
+///
+/// This wraps the tags of `libwayland-client` (see `wl_proxy_set_tag()`), the common mechanism used by C
+/// libraries to recognize their own proxies among the ones they receive. A tag is identified by its
+/// address, so a library should declare its own `static` tag:
+///
+/// ```no_run
+/// use std::ffi::CStr;
+/// use wayland_backend::sys::ProxyTag;
+///
+/// static TAG: ProxyTag =
+///     ProxyTag::new(unsafe { CStr::from_bytes_with_nul_unchecked(b"my-library\0") });
+/// ```
+///
+/// It has the same layout as the `const char *` whose address is used as tag in C. Proxy tags were added
+/// in libwayland 1.18, and are only available with the `libwayland_1_18` cargo feature.
+#[cfg(all(any(test, feature = "client_system"), feature = "libwayland_1_18"))]
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct ProxyTag(*const c_char);
+
+// Safety: the pointer is a `&'static CStr`
+#[cfg(all(any(test, feature = "client_system"), feature = "libwayland_1_18"))]
+unsafe impl Send for ProxyTag {}
+#[cfg(all(any(test, feature = "client_system"), feature = "libwayland_1_18"))]
+unsafe impl Sync for ProxyTag {}
+
+#[cfg(all(any(test, feature = "client_system"), feature = "libwayland_1_18"))]
+impl ProxyTag {
+    /// Create a tag with given name
+    pub const fn new(name: &'static CStr) -> Self {
+        Self(name.as_ptr())
+    }
+
+    /// The name of this tag
+    pub fn name(&self) -> &'static CStr {
+        unsafe { CStr::from_ptr(self.0) }
+    }
+}
+
 #[cfg(any(test, feature = "client_system"))]
 impl client::Backend {
     /// Creates a Backend from a foreign `*mut wl_display`.
@@ -90,6 +165,21 @@ impl client::Backend {
     pub fn display_ptr(&self) -> *mut wayland_sys::client::wl_display {
         self.backend.display_ptr()
     }
+
+    /// Remove the object data attached to a proxy not managed by this backend
+    ///
+    /// libwayland does not notify the backend when such a proxy is destroyed, so the code owning it must
+    /// remove its data before destroying it. Otherwise the data is kept until the backend is dropped,
+    /// and a proxy later allocated at the same address with the same id and interface would be found
+    /// with it.
+    ///
+    /// Returns the removed data, or an error if the proxy had none.
+    pub fn remove_data(
+        &self,
+        id: client::ObjectId,
+    ) -> Result<std::sync::Arc<dyn client::ObjectData>, client::InvalidId> {
+        self.backend.remove_external_data(id)
+    }
 }
 
 // SAFETY:
@@ -112,6 +202,9 @@ unsafe impl raw_window_handle::HasRawDisplayHandle for client::Backend {
 #[path = "../server_api.rs"]
 pub mod server;
 
+#[cfg(any(test, feature = "server_system"))]
+pub mod event_loop;
+
 #[cfg(any(test, feature = "server_system"))]
 impl server::ObjectId {
     /// Creates an object from a C pointer.
@@ -140,10 +233,49 @@ impl server::ObjectId {
     }
 }
 
+/// A global created by another library sharing the `wl_display` of the server
+///
+/// It is handed to the filter set with
+/// [`Handle::set_foreign_global_filter()`](server::Handle::set_foreign_global_filter), and only lives for
+/// the duration of its invocation, as the owner of the global may destroy it at any time afterwards.
+#[cfg(any(test, feature = "server_system"))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct ForeignGlobal(*mut wayland_sys::server::wl_global);
+
+#[cfg(any(test, feature = "server_system"))]
+impl ForeignGlobal {
+    /// Returns the pointer of this global, to recognize the globals created by another library
+    ///
+    /// The pointer is only guaranteed to be valid during the invocation of the filter.
+    pub fn as_ptr(&self) -> *mut wayland_sys::server::wl_global {
+        self.0
+    }
+}
+
+/// A filter of the globals not created by the backend, see
+/// [`Handle::set_foreign_global_filter()`](server::Handle::set_foreign_global_filter)
+#[cfg(any(test, feature = "server_system"))]
+pub type ForeignGlobalFilterFn =
+    std::sync::Arc<dyn Fn(server::ClientId, &ForeignGlobal) -> bool + Send + Sync>;
+
 #[cfg(any(test, feature = "server_system"))]
 impl server::Handle {
     /// Access the underlying `*mut wl_display` pointer
     pub fn display_ptr(&self) -> *mut wayland_sys::server::wl_display {
         self.handle.display_ptr()
     }
+
+    /// Set a filter deciding which clients can see the globals not created by this backend
+    ///
+    /// The globals created by other libraries sharing the `wl_display` (with `wl_global_create()`) are
+    /// unknown to the backend, so neither [`GlobalHandler::can_view()`](server::GlobalHandler::can_view)
+    /// nor [`Handle::restrict_global()`](server::Handle::restrict_global) apply to them. The filter is
+    /// invoked for these globals instead, from the `wl_display_set_global_filter()` callback installed by
+    /// the backend, with the client and the [`ForeignGlobal`]. It is only invoked for the clients inserted
+    /// in the backend, the other ones always see these globals.
+    ///
+    /// Passing `None` removes the filter, all clients can then see these globals, which is the default.
+    pub fn set_foreign_global_filter(&self, filter: Option<ForeignGlobalFilterFn>) {
+        self.handle.set_foreign_global_filter(filter.map(server_impl::ForeignGlobalFilter))
+    }
 }
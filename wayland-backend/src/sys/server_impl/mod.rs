@@ -3,30 +3,42 @@
 use std::{
     ffi::{CStr, CString},
     os::raw::{c_int, c_void},
-    os::unix::io::{BorrowedFd, OwnedFd},
+    os::unix::io::{AsFd, AsRawFd, BorrowedFd, OwnedFd},
     os::unix::{
-        io::{FromRawFd, IntoRawFd, RawFd},
+        io::{FromRawFd, IntoRawFd},
         net::UnixStream,
     },
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, Mutex, Weak,
     },
+    time::{Duration, Instant},
 };
 
+use crate::debug::{print_dispatched_message, print_send_message, DebugOutput, RawId};
+use crate::metrics::MetricsCounters;
 use crate::protocol::{
-    check_for_signature, same_interface, AllowNull, Argument, ArgumentType, Interface, Message,
-    ObjectInfo, ANONYMOUS_INTERFACE,
+    can_veto, check_for_signature, same_interface, AllowNull, Argument, ArgumentType,
+    BorrowedArgument, BorrowedMessage, Direction, Interface, Message, ObjectInfo, ProtocolError,
+    ANONYMOUS_INTERFACE, INLINE_ARGS,
 };
+use rustix::event::{PollFd, PollFlags};
 use scoped_tls::scoped_thread_local;
 use smallvec::SmallVec;
 
 use wayland_sys::{common::*, ffi_dispatch, server::*};
 
-use super::{free_arrays, server::*, RUST_MANAGED};
+use super::{
+    c_interface::c_interface, count_fds, event_loop::EventSources, free_arrays, server::*,
+    RUST_MANAGED,
+};
 
 #[allow(unused_imports)]
-pub use crate::types::server::{Credentials, DisconnectReason, GlobalInfo, InitError, InvalidId};
+pub use crate::types::server::{
+    Credentials, DisconnectReason, GlobalInfo, InitError, InvalidId, ResourceUsage,
+};
+
+pub(crate) const BACKEND_KIND: BackendKind = BackendKind::Sys;
 
 scoped_thread_local! {
     // scoped_tls does not allow unsafe_op_in_unsafe_fn internally
@@ -155,13 +167,12 @@ impl InnerObjectId {
             }
             Ok(InnerObjectId { id, ptr, alive, interface: udata_iface })
         } else if let Some(interface) = interface {
-            let iface_c_ptr =
-                interface.c_ptr.expect("[wayland-backend-sys] Cannot use Interface without c_ptr!");
+            let iface_c_ptr = c_interface(interface);
             // Safety: the provided pointer must be a valid wayland object
             let ptr_iface_name = unsafe {
                 CStr::from_ptr(ffi_dispatch!(wayland_server_handle(), wl_resource_get_class, ptr))
             };
-            // Safety: the code generated by wayland-scanner is valid
+            // Safety: the description of the interface is valid
             let provided_iface_name = unsafe { CStr::from_ptr(iface_c_ptr.name) };
             if ptr_iface_name != provided_iface_name {
                 return Err(InvalidId);
@@ -284,6 +295,11 @@ struct ResourceUserData<D> {
 struct ClientUserData {
     data: Arc<dyn ClientData>,
     alive: Arc<AtomicBool>,
+    label: Option<String>,
+    disconnect_callbacks: Vec<DisconnectCallback>,
+    // the number of requests dispatched and events sent by this crate
+    requests_in: AtomicU64,
+    events_out: AtomicU64,
 }
 
 struct GlobalUserData<D> {
@@ -291,6 +307,7 @@ struct GlobalUserData<D> {
     interface: &'static Interface,
     version: u32,
     disabled: bool,
+    restriction: Option<GlobalRestriction>,
     alive: Arc<AtomicBool>,
     ptr: *mut wl_global,
 }
@@ -302,12 +319,50 @@ pub struct State<D: 'static> {
     timer_source: *mut wl_event_source,
     _data: std::marker::PhantomData<fn(&mut D)>,
     known_globals: Vec<InnerGlobalId>,
+    handshake_timeout: Option<Duration>,
+    pending_handshakes: Vec<(InnerClientId, Instant)>,
+    interceptor: Option<Arc<dyn Interceptor>>,
+    queued_events: Vec<Message<ObjectId, OwnedFd>>,
+    idle_callback: Option<IdleCallback>,
+    // whether some activity happened since the idle callback was last invoked
+    busy: bool,
+    protocol_logger: Option<ProtocolLogger>,
+    metrics: Arc<MetricsCounters>,
+    foreign_global_filter: Option<ForeignGlobalFilter>,
 }
 
 unsafe impl<D> Send for State<D> {}
 
+/// The filter of the globals not created by the backend, see `Handle::set_foreign_global_filter()`
+#[derive(Clone)]
+pub(crate) struct ForeignGlobalFilter(pub(crate) super::ForeignGlobalFilterFn);
+
+impl std::fmt::Debug for ForeignGlobalFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ForeignGlobalFilter").finish_non_exhaustive()
+    }
+}
+
+/// A `wl_protocol_logger` giving the messages of all clients to a debug sink
+#[derive(Debug)]
+struct ProtocolLogger {
+    ptr: *mut wl_protocol_logger,
+    // read by libwayland through its user data, boxed so that its address stays stable
+    _output: Box<DebugOutput>,
+}
+
+impl Drop for ProtocolLogger {
+    fn drop(&mut self) {
+        unsafe {
+            ffi_dispatch!(wayland_server_handle(), wl_protocol_logger_destroy, self.ptr);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct InnerBackend<D: 'static> {
+    // declared first to remove the sources before the state may destroy the display
+    event_sources: EventSources,
     state: Arc<Mutex<State<D>>>,
     display_ptr: *mut wl_display,
 }
@@ -326,7 +381,7 @@ impl<D> InnerBackend<D> {
             panic!("[wayland-backend-sys] libwayland reported an allocation failure.");
         }
 
-        #[cfg(feature = "log")]
+        #[cfg(any(feature = "log", feature = "client_system", feature = "server_system"))]
         unsafe {
             ffi_dispatch!(
                 wayland_server_handle(),
@@ -350,9 +405,10 @@ impl<D> InnerBackend<D> {
             0
         }
 
-        let timer_source = unsafe {
-            let evl = ffi_dispatch!(wayland_server_handle(), wl_display_get_event_loop, display);
+        let evl =
+            unsafe { ffi_dispatch!(wayland_server_handle(), wl_display_get_event_loop, display) };
 
+        let timer_source = unsafe {
             ffi_dispatch!(
                 wayland_server_handle(),
                 wl_event_loop_add_timer,
@@ -363,19 +419,37 @@ impl<D> InnerBackend<D> {
         };
 
         Ok(Self {
+            event_sources: EventSources::new(evl),
             state: Arc::new(Mutex::new(State {
                 display,
                 pending_destructors: Vec::new(),
                 timer_source,
                 _data: std::marker::PhantomData,
                 known_globals: Vec::new(),
+                handshake_timeout: None,
+                pending_handshakes: Vec::new(),
+                interceptor: None,
+                queued_events: Vec::new(),
+                idle_callback: None,
+                busy: false,
+                protocol_logger: None,
+                metrics: Arc::default(),
+                foreign_global_filter: None,
             })),
             display_ptr: display,
         })
     }
 
     pub fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
-        self.state.lock().unwrap().flush(client)
+        let idle_callback = {
+            let mut state = self.state.lock().unwrap();
+            state.flush(client.clone())?;
+            client.map_or_else(|| state.take_idle_callback(), |_| None)
+        };
+        if let Some(cb) = idle_callback {
+            cb();
+        }
+        Ok(())
     }
 
     pub fn handle(&self) -> Handle {
@@ -402,7 +476,25 @@ impl<D> InnerBackend<D> {
         self.dispatch_all_clients(data)
     }
 
+    pub fn dispatch_client_timeout(
+        &mut self,
+        data: &mut D,
+        _client_id: InnerClientId,
+        _timeout: Duration,
+    ) -> std::io::Result<(usize, bool)> {
+        // libwayland dispatches all the requests it reads
+        self.dispatch_all_clients(data).map(|dispatched| (dispatched, false))
+    }
+
     pub fn dispatch_all_clients(&mut self, data: &mut D) -> std::io::Result<usize> {
+        self.state.lock().unwrap().kill_stalled_clients();
+
+        // wl_event_loop_dispatch does not report what it did, record activity for the idle callback
+        // if something is about to be dispatched
+        let poll_fd = self.poll_fd();
+        let mut fds = [PollFd::new(&poll_fd, PollFlags::IN)];
+        let has_pending = !matches!(rustix::event::poll(&mut fds, 0), Ok(0));
+
         let state = self.state.clone() as Arc<Mutex<dyn ErasedState + Send>>;
         let display = self.display_ptr;
         let ret = HANDLE.set(&(state, data as *mut _ as *mut c_void), || unsafe {
@@ -411,8 +503,13 @@ impl<D> InnerBackend<D> {
             ffi_dispatch!(wayland_server_handle(), wl_event_loop_dispatch, evl_ptr, 0)
         });
 
-        let pending_destructors =
-            std::mem::take(&mut self.state.lock().unwrap().pending_destructors);
+        let pending_destructors = {
+            let mut state = self.state.lock().unwrap();
+            if has_pending {
+                state.busy = true;
+            }
+            std::mem::take(&mut state.pending_destructors)
+        };
         for (object, client_id, object_id) in pending_destructors {
             let handle = self.handle();
             object.clone().destroyed(&handle, data, client_id, object_id);
@@ -424,6 +521,127 @@ impl<D> InnerBackend<D> {
             Ok(ret as usize)
         }
     }
+
+    pub fn dispatch_all_clients_limited(
+        &mut self,
+        data: &mut D,
+        _max_requests: usize,
+    ) -> std::io::Result<(usize, bool)> {
+        // libwayland dispatches all the requests it reads
+        self.dispatch_all_clients(data).map(|dispatched| (dispatched, false))
+    }
+
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.state.lock().unwrap().handshake_timeout = timeout;
+    }
+
+    pub fn set_max_client_fds(&mut self, _max_fds: usize) {
+        // libwayland has its own fixed limit
+    }
+
+    pub fn set_max_client_buffer_size(&mut self, _size: usize) {
+        #[cfg(feature = "libwayland_1_23")]
+        unsafe {
+            let display = self.display_ptr;
+            ffi_dispatch!(
+                wayland_server_1_23_handle(),
+                wl_display_set_default_max_buffer_size,
+                display,
+                _size
+            );
+            // the default only applies to the clients connecting later
+            let client_list =
+                ffi_dispatch!(wayland_server_handle(), wl_display_get_client_list, display);
+            let mut link = (*client_list).next;
+            while link != client_list {
+                let client = ffi_dispatch!(wayland_server_handle(), wl_client_from_link, link);
+                ffi_dispatch!(
+                    wayland_server_1_23_handle(),
+                    wl_client_set_max_buffer_size,
+                    client,
+                    _size
+                );
+                link = (*link).next;
+            }
+        }
+    }
+
+    pub fn set_max_client_objects(&mut self, _max_objects: Option<usize>) {
+        // objects are created by libwayland, which does not limit them
+    }
+
+    pub fn set_object_limit_callback(&mut self, _callback: Option<ObjectLimitCallback>) {
+        // objects are created by libwayland, which does not limit them
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.state.lock().unwrap().metrics.snapshot()
+    }
+
+    pub(crate) fn event_sources(&mut self) -> &mut EventSources {
+        &mut self.event_sources
+    }
+
+    pub fn set_debug_sink(&mut self, sink: Option<DebugSink>) {
+        let mut state = self.state.lock().unwrap();
+        // destroy the previous logger before its output is dropped
+        state.protocol_logger = None;
+        state.protocol_logger = sink.map(|sink| {
+            let output = Box::new(DebugOutput::Sink(sink));
+            let ptr = unsafe {
+                ffi_dispatch!(
+                    wayland_server_handle(),
+                    wl_display_add_protocol_logger,
+                    state.display,
+                    protocol_logger,
+                    &*output as *const DebugOutput as *mut c_void
+                )
+            };
+            ProtocolLogger { ptr, _output: output }
+        });
+    }
+}
+
+impl<D> State<D> {
+    /// Kill the clients that did not complete their handshake within the timeout
+    ///
+    /// libwayland handles the `wl_display` requests internally, so a client is considered to have
+    /// completed its handshake once it owns any object other than its `wl_display`.
+    fn kill_stalled_clients(&mut self) {
+        let Some(timeout) = self.handshake_timeout else { return };
+
+        unsafe extern "C" fn iterator_func(_: *mut wl_resource, user_data: *mut c_void) -> c_int {
+            let count = unsafe { &mut *(user_data as *mut usize) };
+            *count += 1;
+            // stop iterating (WL_ITERATOR_STOP) once a second object has been found
+            (*count < 2) as c_int
+        }
+
+        for (client_id, connected_at) in std::mem::take(&mut self.pending_handshakes) {
+            if !client_id.alive.load(Ordering::Acquire) {
+                continue;
+            }
+            let mut count = 0usize;
+            unsafe {
+                ffi_dispatch!(
+                    wayland_server_handle(),
+                    wl_client_for_each_resource,
+                    client_id.ptr,
+                    iterator_func,
+                    &mut count as *mut usize as *mut c_void,
+                )
+            }
+            if count > 1 {
+                continue;
+            }
+            if connected_at.elapsed() >= timeout {
+                self.kill_client(client_id, DisconnectReason::Killed);
+            } else {
+                self.pending_handshakes.push((client_id, connected_at));
+            }
+        }
+    }
 }
 
 impl<D> Drop for State<D> {
@@ -447,6 +665,9 @@ impl<D> Drop for State<D> {
             }
         }
 
+        // libwayland does not destroy the protocol loggers along with the display
+        self.protocol_logger = None;
+
         unsafe {
             ffi_dispatch!(wayland_server_handle(), wl_display_destroy, self.display);
         }
@@ -487,6 +708,8 @@ impl InnerHandle {
     }
 
     pub fn object_info(&self, id: InnerObjectId) -> Result<ObjectInfo, InvalidId> {
+        // unlike the rust backend, this needs the lock: libwayland-server is not thread-safe, so the
+        // accesses to the `wl_resource`s must be serialized with the dispatching
         self.state.lock().unwrap().object_info(id)
     }
 
@@ -510,6 +733,42 @@ impl InnerHandle {
         self.state.lock().unwrap().get_client_credentials(id)
     }
 
+    pub fn get_client_resource_usage(&self, id: InnerClientId) -> Result<ResourceUsage, InvalidId> {
+        self.state.lock().unwrap().get_client_resource_usage(id)
+    }
+
+    pub fn set_client_label(&self, id: InnerClientId, label: String) -> Result<(), InvalidId> {
+        self.state.lock().unwrap().set_client_label(id, label)
+    }
+
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.state.lock().unwrap().set_interceptor(interceptor)
+    }
+
+    pub fn set_idle_callback(&self, cb: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.state.lock().unwrap().set_idle_callback(cb)
+    }
+
+    pub fn set_foreign_global_filter(&self, filter: Option<ForeignGlobalFilter>) {
+        self.state.lock().unwrap().set_foreign_global_filter(filter)
+    }
+
+    pub fn get_client_label(&self, id: InnerClientId) -> Result<Option<String>, InvalidId> {
+        self.state.lock().unwrap().get_client_label(id)
+    }
+
+    pub fn is_client_alive(&self, id: InnerClientId) -> bool {
+        id.alive.load(Ordering::Acquire)
+    }
+
+    pub fn add_client_disconnect_callback(
+        &self,
+        id: InnerClientId,
+        callback: DisconnectCallback,
+    ) -> Result<(), InvalidId> {
+        self.state.lock().unwrap().add_client_disconnect_callback(id, callback)
+    }
+
     pub fn with_all_clients(&self, mut f: impl FnMut(ClientId)) {
         self.state.lock().unwrap().with_all_clients(&mut f)
     }
@@ -548,8 +807,7 @@ impl InnerHandle {
             return Err(InvalidId);
         }
 
-        let interface_ptr =
-            interface.c_ptr.expect("Interface without c_ptr are unsupported by the sys backend.");
+        let interface_ptr = c_interface(interface);
 
         let resource = unsafe {
             ffi_dispatch!(
@@ -562,6 +820,14 @@ impl InnerHandle {
             )
         };
 
+        if resource.is_null() {
+            // the server namespace of this client is full, or the allocation failed
+            unsafe {
+                ffi_dispatch!(wayland_server_handle(), wl_client_post_no_memory, client.ptr);
+            }
+            return Err(InvalidId);
+        }
+
         Ok(ObjectId { id: unsafe { init_resource(resource, interface, Some(data)).0 } })
     }
 
@@ -576,10 +842,14 @@ impl InnerHandle {
         }
     }
 
-    pub fn send_event(&self, msg: Message<ObjectId, RawFd>) -> Result<(), InvalidId> {
+    pub fn send_event(&self, msg: Message<ObjectId, BorrowedFd<'_>>) -> Result<(), InvalidId> {
         self.state.lock().unwrap().send_event(msg)
     }
 
+    pub fn queue_event(&self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId> {
+        self.state.lock().unwrap().queue_event(msg)
+    }
+
     pub fn get_object_data<D: 'static>(
         &self,
         id: InnerObjectId,
@@ -594,8 +864,7 @@ impl InnerHandle {
             return Err(InvalidId);
         }
 
-        let iface_c_ptr =
-            id.interface.c_ptr.expect("[wayland-backend-sys] Cannot use Interface without c_ptr!");
+        let iface_c_ptr = c_interface(id.interface);
         let is_managed = unsafe {
             ffi_dispatch!(
                 wayland_server_handle(),
@@ -629,18 +898,25 @@ impl InnerHandle {
         id: InnerObjectId,
         data: Arc<dyn ObjectData<D>>,
     ) -> Result<(), InvalidId> {
+        self.replace_object_data(id, data).map(drop)
+    }
+
+    pub fn replace_object_data<D: 'static>(
+        &self,
+        id: InnerObjectId,
+        data: Arc<dyn ObjectData<D>>,
+    ) -> Result<Arc<dyn ObjectData<D>>, InvalidId> {
         let mut state = self.state.lock().unwrap();
         // Keep this guard alive while the code is run to protect the C state
         let _state = (&mut *state as &mut dyn ErasedState)
             .downcast_mut::<State<D>>()
-            .expect("Wrong type parameter passed to Handle::set_object_data().");
+            .expect("Wrong type parameter passed to Handle::replace_object_data().");
 
         if !id.alive.load(Ordering::Acquire) {
             return Err(InvalidId);
         }
 
-        let iface_c_ptr =
-            id.interface.c_ptr.expect("[wayland-backend-sys] Cannot use Interface without c_ptr!");
+        let iface_c_ptr = c_interface(id.interface);
         let is_managed = unsafe {
             ffi_dispatch!(
                 wayland_server_handle(),
@@ -659,9 +935,7 @@ impl InnerHandle {
                 as *mut ResourceUserData<D>)
         };
 
-        udata.data = data;
-
-        Ok(())
+        Ok(std::mem::replace(&mut udata.data, data))
     }
 
     pub fn post_error(&self, object_id: InnerObjectId, error_code: u32, message: CString) {
@@ -688,8 +962,7 @@ impl InnerHandle {
 
         let alive = Arc::new(AtomicBool::new(true));
 
-        let interface_ptr =
-            interface.c_ptr.expect("Interface without c_ptr are unsupported by the sys backend.");
+        let interface_ptr = c_interface(interface);
 
         let udata = Box::into_raw(Box::new(GlobalUserData {
             handler,
@@ -697,6 +970,7 @@ impl InnerHandle {
             interface,
             version,
             disabled: false,
+            restriction: None,
             ptr: std::ptr::null_mut(),
         }));
 
@@ -764,6 +1038,31 @@ impl InnerHandle {
         }
     }
 
+    pub fn restrict_global<D: 'static>(
+        &self,
+        id: InnerGlobalId,
+        restriction: Option<GlobalRestriction>,
+    ) -> Result<(), InvalidId> {
+        // check that `D` is correct
+        {
+            let mut state = self.state.lock().unwrap();
+            let _state = (&mut *state as &mut dyn ErasedState)
+                .downcast_mut::<State<D>>()
+                .expect("Wrong type parameter passed to Handle::restrict_global().");
+        }
+
+        if !id.alive.load(Ordering::Acquire) {
+            return Err(InvalidId);
+        }
+
+        let udata = unsafe {
+            &mut *(ffi_dispatch!(wayland_server_handle(), wl_global_get_user_data, id.ptr)
+                as *mut GlobalUserData<D>)
+        };
+        udata.restriction = restriction;
+        Ok(())
+    }
+
     pub fn remove_global<D: 'static>(&self, id: InnerGlobalId) {
         {
             let mut state = self.state.lock().unwrap();
@@ -815,7 +1114,15 @@ impl InnerHandle {
     }
 
     pub fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
-        self.state.lock().unwrap().flush(client)
+        let idle_callback = {
+            let mut state = self.state.lock().unwrap();
+            state.flush(client.clone())?;
+            client.map_or_else(|| state.take_idle_callback(), |_| None)
+        };
+        if let Some(cb) = idle_callback {
+            cb();
+        }
+        Ok(())
     }
 
     pub fn display_ptr(&self) -> *mut wl_display {
@@ -826,12 +1133,23 @@ impl InnerHandle {
 pub(crate) trait ErasedState: downcast_rs::Downcast {
     fn object_info(&self, id: InnerObjectId) -> Result<ObjectInfo, InvalidId>;
     fn insert_client(
-        &self,
+        &mut self,
         stream: UnixStream,
         data: Arc<dyn ClientData>,
     ) -> std::io::Result<InnerClientId>;
     fn get_client(&self, id: InnerObjectId) -> Result<ClientId, InvalidId>;
     fn get_client_credentials(&self, id: InnerClientId) -> Result<Credentials, InvalidId>;
+    fn get_client_resource_usage(&self, id: InnerClientId) -> Result<ResourceUsage, InvalidId>;
+    fn set_client_label(&mut self, id: InnerClientId, label: String) -> Result<(), InvalidId>;
+    fn set_interceptor(&mut self, interceptor: Option<Arc<dyn Interceptor>>);
+    fn interceptor(&self) -> Option<Arc<dyn Interceptor>>;
+    fn metrics(&self) -> Arc<MetricsCounters>;
+    fn get_client_label(&self, id: InnerClientId) -> Result<Option<String>, InvalidId>;
+    fn add_client_disconnect_callback(
+        &mut self,
+        id: InnerClientId,
+        callback: DisconnectCallback,
+    ) -> Result<(), InvalidId>;
     fn get_client_data(&self, id: InnerClientId) -> Result<Arc<dyn ClientData>, InvalidId>;
     fn with_all_clients(&self, f: &mut dyn FnMut(ClientId));
     fn with_all_objects_for(
@@ -849,12 +1167,17 @@ pub(crate) trait ErasedState: downcast_rs::Downcast {
         &self,
         id: InnerObjectId,
     ) -> Result<Arc<dyn std::any::Any + Send + Sync>, InvalidId>;
-    fn send_event(&mut self, msg: Message<ObjectId, RawFd>) -> Result<(), InvalidId>;
+    fn send_event(&mut self, msg: Message<ObjectId, BorrowedFd<'_>>) -> Result<(), InvalidId>;
+    fn queue_event(&mut self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId>;
     fn post_error(&mut self, object_id: InnerObjectId, error_code: u32, message: CString);
     fn kill_client(&mut self, client_id: InnerClientId, reason: DisconnectReason);
     fn global_info(&self, id: InnerGlobalId) -> Result<GlobalInfo, InvalidId>;
     fn is_known_global(&self, global_ptr: *const wl_global) -> bool;
+    fn foreign_global_filter(&self) -> Option<ForeignGlobalFilter>;
+    fn set_foreign_global_filter(&mut self, filter: Option<ForeignGlobalFilter>);
     fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()>;
+    fn take_idle_callback(&mut self) -> Option<Arc<dyn Fn() + Send + Sync>>;
+    fn set_idle_callback(&mut self, cb: Option<Arc<dyn Fn() + Send + Sync>>);
     fn display_ptr(&self) -> *mut wl_display;
 }
 
@@ -874,7 +1197,7 @@ impl<D: 'static> ErasedState for State<D> {
     }
 
     fn insert_client(
-        &self,
+        &mut self,
         stream: UnixStream,
         data: Arc<dyn ClientData>,
     ) -> std::io::Result<InnerClientId> {
@@ -891,7 +1214,11 @@ impl<D: 'static> ErasedState for State<D> {
             return Err(std::io::Error::last_os_error());
         }
 
-        Ok(unsafe { init_client(ret, data) })
+        let id = unsafe { init_client(ret, data) };
+        if self.handshake_timeout.is_some() {
+            self.pending_handshakes.push((id.clone(), Instant::now()));
+        }
+        Ok(id)
     }
 
     fn get_client(&self, id: InnerObjectId) -> Result<ClientId, InvalidId> {
@@ -941,19 +1268,113 @@ impl<D: 'static> ErasedState for State<D> {
         Ok(creds)
     }
 
+    fn get_client_resource_usage(&self, id: InnerClientId) -> Result<ResourceUsage, InvalidId> {
+        if !id.alive.load(Ordering::Acquire) {
+            return Err(InvalidId);
+        }
+
+        unsafe extern "C" fn iterator_func(_: *mut wl_resource, user_data: *mut c_void) -> c_int {
+            let count = unsafe { &mut *(user_data as *mut usize) };
+            *count += 1;
+            // WL_ITERATOR_CONTINUE
+            1
+        }
+
+        let mut objects = 0usize;
+        unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_client_for_each_resource,
+                id.ptr,
+                iterator_func,
+                &mut objects as *mut usize as *mut c_void,
+            )
+        }
+
+        Ok(ResourceUsage {
+            objects,
+            buffered_bytes: 0,
+            pending_output_bytes: 0,
+            buffered_fds: 0,
+            memory: std::mem::size_of::<ClientUserData>()
+                + objects * std::mem::size_of::<ResourceUserData<D>>(),
+        })
+    }
+
+    fn set_client_label(&mut self, id: InnerClientId, label: String) -> Result<(), InvalidId> {
+        if !id.alive.load(Ordering::Acquire) {
+            return Err(InvalidId);
+        }
+
+        let data = unsafe {
+            match client_user_data(id.ptr) {
+                Some(ptr) => &mut *ptr,
+                None => return Err(InvalidId),
+            }
+        };
+        data.label = Some(label);
+        Ok(())
+    }
+
+    fn get_client_label(&self, id: InnerClientId) -> Result<Option<String>, InvalidId> {
+        if !id.alive.load(Ordering::Acquire) {
+            return Err(InvalidId);
+        }
+
+        let data = unsafe {
+            match client_user_data(id.ptr) {
+                Some(ptr) => &*ptr,
+                None => return Err(InvalidId),
+            }
+        };
+        Ok(data.label.clone())
+    }
+
+    fn add_client_disconnect_callback(
+        &mut self,
+        id: InnerClientId,
+        callback: DisconnectCallback,
+    ) -> Result<(), InvalidId> {
+        if !id.alive.load(Ordering::Acquire) {
+            return Err(InvalidId);
+        }
+
+        let data = unsafe {
+            match client_user_data(id.ptr) {
+                Some(ptr) => &mut *ptr,
+                None => return Err(InvalidId),
+            }
+        };
+        data.disconnect_callbacks.push(callback);
+        Ok(())
+    }
+
+    fn set_interceptor(&mut self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.interceptor = interceptor;
+    }
+
+    fn interceptor(&self) -> Option<Arc<dyn Interceptor>> {
+        self.interceptor.clone()
+    }
+
+    fn metrics(&self) -> Arc<MetricsCounters> {
+        self.metrics.clone()
+    }
+
     fn with_all_clients(&self, f: &mut dyn FnMut(ClientId)) {
-        let mut client_list = unsafe {
+        let client_list = unsafe {
             ffi_dispatch!(wayland_server_handle(), wl_display_get_client_list, self.display)
         };
         unsafe {
-            while (*client_list).next != client_list {
-                let client =
-                    ffi_dispatch!(wayland_server_handle(), wl_client_from_link, client_list);
+            // the list head is not a client, iterate over the links until coming back to it
+            let mut link = (*client_list).next;
+            while link != client_list {
+                let client = ffi_dispatch!(wayland_server_handle(), wl_client_from_link, link);
                 if let Some(id) = client_id_from_ptr(client) {
                     f(ClientId { id })
                 }
 
-                client_list = (*client_list).next;
+                link = (*link).next;
             }
         }
     }
@@ -1024,8 +1445,7 @@ impl<D: 'static> ErasedState for State<D> {
             return Err(InvalidId);
         }
 
-        let iface_c_ptr =
-            id.interface.c_ptr.expect("[wayland-backend-sys] Cannot use Interface without c_ptr!");
+        let iface_c_ptr = c_interface(id.interface);
         let is_managed = unsafe {
             ffi_dispatch!(
                 wayland_server_handle(),
@@ -1049,11 +1469,12 @@ impl<D: 'static> ErasedState for State<D> {
 
     fn send_event(
         &mut self,
-        Message { sender_id: ObjectId { id }, opcode, args }: Message<ObjectId, RawFd>,
+        Message { sender_id: ObjectId { id }, opcode, mut args }: Message<ObjectId, BorrowedFd<'_>>,
     ) -> Result<(), InvalidId> {
         if !id.alive.load(Ordering::Acquire) || id.ptr.is_null() {
             return Err(InvalidId);
         }
+        self.busy = true;
 
         // check that the argument list is valid
         let message_desc = match id.interface.events.get(opcode as usize) {
@@ -1069,6 +1490,20 @@ impl<D: 'static> ErasedState for State<D> {
             );
         }
 
+        let client =
+            unsafe { ffi_dispatch!(wayland_server_handle(), wl_resource_get_client, id.ptr) };
+        if let Some(interceptor) = self.interceptor.as_deref() {
+            let client_id = self.get_client(id.clone())?;
+            // Safety: the resource is alive, and so is its client
+            let seq = unsafe { client_user_data(client) }
+                .map_or(0, |udata| unsafe { (*udata).events_out.load(Ordering::Relaxed) } + 1);
+            let msg = Message { sender_id: ObjectId { id: id.clone() }, opcode, args };
+            if !interceptor.event_sequenced(client_id, seq, &msg) && can_veto(message_desc) {
+                return Ok(());
+            }
+            args = msg.args;
+        }
+
         let mut argument_list = SmallVec::<[wl_argument; 4]>::with_capacity(args.len());
         let mut arg_interfaces = message_desc.arg_interfaces.iter();
         for (i, arg) in args.iter().enumerate() {
@@ -1076,7 +1511,7 @@ impl<D: 'static> ErasedState for State<D> {
                 Argument::Uint(u) => argument_list.push(wl_argument { u }),
                 Argument::Int(i) => argument_list.push(wl_argument { i }),
                 Argument::Fixed(f) => argument_list.push(wl_argument { f }),
-                Argument::Fd(h) => argument_list.push(wl_argument { h }),
+                Argument::Fd(fd) => argument_list.push(wl_argument { h: fd.as_raw_fd() }),
                 Argument::Array(ref a) => {
                     let a = Box::new(wl_array {
                         size: a.len(),
@@ -1153,6 +1588,11 @@ impl<D: 'static> ErasedState for State<D> {
                 argument_list.as_mut_ptr()
             );
         }
+        self.metrics.message_out(count_fds(message_desc.signature));
+        // Safety: the resource is alive, and so is its client
+        if let Some(udata) = unsafe { client_user_data(client) } {
+            unsafe { (*udata).events_out.fetch_add(1, Ordering::Relaxed) };
+        }
 
         unsafe {
             free_arrays(message_desc.signature, &argument_list);
@@ -1180,7 +1620,7 @@ impl<D: 'static> ErasedState for State<D> {
         let client =
             unsafe { ffi_dispatch!(wayland_server_handle(), wl_resource_get_client, id.ptr) };
         let client_id = unsafe { client_id_from_ptr(client) }.unwrap();
-        // mark the client as dead
+        // mark the client as dead, libwayland will destroy it once the error is flushed
         client_id.alive.store(false, Ordering::Release);
 
         unsafe {
@@ -1192,6 +1632,21 @@ impl<D: 'static> ErasedState for State<D> {
                 message.as_ptr()
             )
         }
+
+        // the client being marked as dead, client_destroy_notify will not notify it again
+        if let Some(udata) = unsafe { client_user_data(client) } {
+            let udata = unsafe { &mut *udata };
+            let reason = DisconnectReason::ProtocolError(ProtocolError {
+                code: error_code,
+                object_id: id.id,
+                object_interface: id.interface.name.into(),
+                message: message.to_string_lossy().into_owned(),
+            });
+            udata.data.disconnected(ClientId { id: client_id.clone() }, reason);
+            for DisconnectCallback(cb) in std::mem::take(&mut udata.disconnect_callbacks) {
+                cb(ClientId { id: client_id.clone() });
+            }
+        }
     }
 
     fn kill_client(&mut self, client_id: InnerClientId, reason: DisconnectReason) {
@@ -1199,9 +1654,12 @@ impl<D: 'static> ErasedState for State<D> {
             return;
         }
         if let Some(udata) = unsafe { client_user_data(client_id.ptr) } {
-            let udata = unsafe { &*udata };
+            let udata = unsafe { &mut *udata };
             udata.alive.store(false, Ordering::Release);
             udata.data.disconnected(ClientId { id: client_id.clone() }, reason);
+            for DisconnectCallback(cb) in std::mem::take(&mut udata.disconnect_callbacks) {
+                cb(ClientId { id: client_id.clone() });
+            }
         }
 
         // wl_client_destroy invokes destructors
@@ -1230,7 +1688,61 @@ impl<D: 'static> ErasedState for State<D> {
         self.known_globals.iter().any(|ginfo| (ginfo.ptr as *const wl_global) == global_ptr)
     }
 
+    fn foreign_global_filter(&self) -> Option<ForeignGlobalFilter> {
+        self.foreign_global_filter.clone()
+    }
+
+    fn set_foreign_global_filter(&mut self, filter: Option<ForeignGlobalFilter>) {
+        self.foreign_global_filter = filter;
+    }
+
+    fn take_idle_callback(&mut self) -> Option<Arc<dyn Fn() + Send + Sync>> {
+        if !self.busy
+            || self.idle_callback.is_none()
+            || !self.queued_events.is_empty()
+            || !self.pending_destructors.is_empty()
+        {
+            return None;
+        }
+        // libwayland does not tell whether all events could be written, so only check that no
+        // request is waiting to be dispatched
+        let fd = unsafe {
+            let evl_ptr =
+                ffi_dispatch!(wayland_server_handle(), wl_display_get_event_loop, self.display);
+            BorrowedFd::borrow_raw(ffi_dispatch!(
+                wayland_server_handle(),
+                wl_event_loop_get_fd,
+                evl_ptr
+            ))
+        };
+        let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+        if !matches!(rustix::event::poll(&mut fds, 0), Ok(0)) {
+            return None;
+        }
+        self.busy = false;
+        self.idle_callback.as_ref().map(|cb| cb.0.clone())
+    }
+
+    fn set_idle_callback(&mut self, cb: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.idle_callback = cb.map(IdleCallback);
+    }
+
+    fn queue_event(&mut self, msg: Message<ObjectId, OwnedFd>) -> Result<(), InvalidId> {
+        if !msg.sender_id.id.alive.load(Ordering::Acquire) {
+            return Err(InvalidId);
+        }
+        self.queued_events.push(msg);
+        Ok(())
+    }
+
     fn flush(&mut self, client: Option<ClientId>) -> std::io::Result<()> {
+        for msg in std::mem::take(&mut self.queued_events) {
+            // the fds are kept open until the message is serialized
+            let mut fds = Vec::new();
+            let msg = msg.map_fd(|fd| fds.push(fd));
+            let mut fds = fds.iter();
+            let _ = self.send_event(msg.map_fd(|()| fds.next().unwrap().as_fd()));
+        }
         if let Some(ClientId { id: client_id }) = client {
             if client_id.alive.load(Ordering::Acquire) {
                 unsafe { ffi_dispatch!(wayland_server_handle(), wl_client_flush, client_id.ptr) }
@@ -1266,7 +1778,14 @@ impl<D: 'static> ErasedState for State<D> {
 
 unsafe fn init_client(client: *mut wl_client, data: Arc<dyn ClientData>) -> InnerClientId {
     let alive = Arc::new(AtomicBool::new(true));
-    let client_data = Box::into_raw(Box::new(ClientUserData { alive: alive.clone(), data }));
+    let client_data = Box::into_raw(Box::new(ClientUserData {
+        alive: alive.clone(),
+        data,
+        label: None,
+        disconnect_callbacks: Vec::new(),
+        requests_in: AtomicU64::new(0),
+        events_out: AtomicU64::new(0),
+    }));
 
     let listener = signal::rust_listener_create(client_destroy_notify);
     // Safety: we just created listener and client_data, they are valid
@@ -1307,7 +1826,7 @@ unsafe fn client_user_data(client: *mut wl_client) -> Option<*mut ClientUserData
 
 unsafe extern "C" fn client_destroy_notify(listener: *mut wl_listener, client_ptr: *mut c_void) {
     // Safety: if this function is invoked by libwayland its arguments must be valid
-    let data = unsafe {
+    let mut data = unsafe {
         Box::from_raw(signal::rust_listener_get_user_data(listener) as *mut ClientUserData)
     };
     unsafe {
@@ -1316,12 +1835,13 @@ unsafe extern "C" fn client_destroy_notify(listener: *mut wl_listener, client_pt
     // only notify the killing if it was not already
     if data.alive.load(Ordering::Acquire) {
         data.alive.store(false, Ordering::Release);
-        data.data.disconnected(
-            ClientId {
-                id: InnerClientId { ptr: client_ptr as *mut wl_client, alive: data.alive.clone() },
-            },
-            DisconnectReason::ConnectionClosed,
-        );
+        let client_id =
+            InnerClientId { ptr: client_ptr as *mut wl_client, alive: data.alive.clone() };
+        data.data
+            .disconnected(ClientId { id: client_id.clone() }, DisconnectReason::ConnectionClosed);
+        for DisconnectCallback(cb) in std::mem::take(&mut data.disconnect_callbacks) {
+            cb(ClientId { id: client_id.clone() });
+        }
     }
 }
 
@@ -1342,8 +1862,7 @@ unsafe extern "C" fn global_bind<D: 'static>(
         None => return,
     };
 
-    // this must be Some(), checked at creation of the global
-    let interface_ptr = global_udata.interface.c_ptr.unwrap();
+    let interface_ptr = c_interface(global_udata.interface);
 
     HANDLE.with(|&(ref state_arc, data_ptr)| {
         // Safety: the data_ptr is a valid pointer that live outside code put there
@@ -1384,19 +1903,30 @@ unsafe extern "C" fn global_filter<D: 'static>(
         return false;
     }
 
-    // Safety: skip processing globals that do not belong to us
-    let is_known_global = HANDLE.with(|(state_arc, _)| {
+    let (is_known_global, foreign_filter) = HANDLE.with(|(state_arc, _)| {
         let guard = state_arc.lock().unwrap();
-        guard.is_known_global(global)
+        (guard.is_known_global(global), guard.foreign_global_filter())
     });
+
+    let client_udata =
+        unsafe { client_user_data(client as *mut _) }.map(|udata| unsafe { &*udata });
+
+    // Safety: skip processing globals that do not belong to us, only the foreign global filter applies
+    // to them, for the clients we know
     if !is_known_global {
-        return true;
+        return match (foreign_filter, client_udata) {
+            (Some(ForeignGlobalFilter(filter)), Some(client_udata)) => {
+                let client_id =
+                    InnerClientId { ptr: client as *mut _, alive: client_udata.alive.clone() };
+                filter(ClientId { id: client_id }, &super::ForeignGlobal(global as *mut _))
+            }
+            _ => true,
+        };
     }
 
     // Safety: if we are invoked here, the client is a valid client initialized by us
-    let client_udata = match unsafe { client_user_data(client as *mut _) } {
-        Some(id) => unsafe { &*id },
-        None => return false,
+    let Some(client_udata) = client_udata else {
+        return false;
     };
 
     let client_id = InnerClientId { ptr: client as *mut _, alive: client_udata.alive.clone() };
@@ -1410,6 +1940,23 @@ unsafe extern "C" fn global_filter<D: 'static>(
     let global_id =
         InnerGlobalId { ptr: global as *mut wl_global, alive: global_udata.alive.clone() };
 
+    if let Some(ref restriction) = global_udata.restriction {
+        let mut creds = Credentials { pid: 0, uid: 0, gid: 0 };
+        unsafe {
+            ffi_dispatch!(
+                wayland_server_handle(),
+                wl_client_get_credentials,
+                client as *mut _,
+                &mut creds.pid,
+                &mut creds.uid,
+                &mut creds.gid
+            );
+        }
+        if !restriction.allows(Some(&creds)) {
+            return false;
+        }
+    }
+
     global_udata.handler.can_view(
         ClientId { id: client_id },
         &client_udata.data,
@@ -1467,34 +2014,40 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
         }
     };
 
-    let mut parsed_args =
-        SmallVec::<[Argument<ObjectId, OwnedFd>; 4]>::with_capacity(message_desc.signature.len());
+    let mut parsed_args = SmallVec::<[BorrowedArgument<ObjectId, OwnedFd>; 4]>::with_capacity(
+        message_desc.signature.len(),
+    );
     let mut arg_interfaces = message_desc.arg_interfaces.iter().copied();
     let mut created = None;
     // Safety (args deference): the args array provided by libwayland is well-formed
     for (i, typ) in message_desc.signature.iter().enumerate() {
         match typ {
-            ArgumentType::Uint => parsed_args.push(Argument::Uint(unsafe { (*args.add(i)).u })),
-            ArgumentType::Int => parsed_args.push(Argument::Int(unsafe { (*args.add(i)).i })),
-            ArgumentType::Fixed => parsed_args.push(Argument::Fixed(unsafe { (*args.add(i)).f })),
-            ArgumentType::Fd => {
-                parsed_args.push(Argument::Fd(unsafe { OwnedFd::from_raw_fd((*args.add(i)).h) }))
+            ArgumentType::Uint => {
+                parsed_args.push(BorrowedArgument::Uint(unsafe { (*args.add(i)).u }))
+            }
+            ArgumentType::Int => {
+                parsed_args.push(BorrowedArgument::Int(unsafe { (*args.add(i)).i }))
             }
+            ArgumentType::Fixed => {
+                parsed_args.push(BorrowedArgument::Fixed(unsafe { (*args.add(i)).f }))
+            }
+            ArgumentType::Fd => parsed_args
+                .push(BorrowedArgument::Fd(unsafe { OwnedFd::from_raw_fd((*args.add(i)).h) })),
             ArgumentType::Array => {
                 let array = unsafe { &*((*args.add(i)).a) };
                 // Safety: the wl_array provided by libwayland is valid
                 let content =
                     unsafe { std::slice::from_raw_parts(array.data as *mut u8, array.size) };
-                parsed_args.push(Argument::Array(Box::new(content.into())));
+                parsed_args.push(BorrowedArgument::Array(content));
             }
             ArgumentType::Str(_) => {
                 let ptr = unsafe { (*args.add(i)).s };
                 // Safety: the c-string provided by libwayland is valid
                 if !ptr.is_null() {
                     let cstr = unsafe { std::ffi::CStr::from_ptr(ptr) };
-                    parsed_args.push(Argument::Str(Some(Box::new(cstr.into()))));
+                    parsed_args.push(BorrowedArgument::Str(Some(cstr)));
                 } else {
-                    parsed_args.push(Argument::Str(None));
+                    parsed_args.push(BorrowedArgument::Str(None));
                 }
             }
             ArgumentType::Object(_) => {
@@ -1509,7 +2062,7 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
                     // libwayland-server.so checks nulls for us
                     InnerHandle::null_id()
                 };
-                parsed_args.push(Argument::Object(id))
+                parsed_args.push(BorrowedArgument::Object(id))
             }
             ArgumentType::NewId => {
                 let new_id = unsafe { (*args.add(i)).n };
@@ -1524,7 +2077,7 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
                         wayland_server_handle(),
                         wl_resource_create,
                         client,
-                        child_interface.c_ptr.unwrap(),
+                        c_interface(child_interface),
                         version,
                         new_id
                     );
@@ -1532,9 +2085,9 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
                     let (child_id, child_data_ptr) =
                         unsafe { init_resource::<D>(resource, child_interface, None) };
                     created = Some((child_id.clone(), child_data_ptr));
-                    parsed_args.push(Argument::NewId(ObjectId { id: child_id }));
+                    parsed_args.push(BorrowedArgument::NewId(ObjectId { id: child_id }));
                 } else {
-                    parsed_args.push(Argument::NewId(InnerHandle::null_id()))
+                    parsed_args.push(BorrowedArgument::NewId(InnerHandle::null_id()))
                 }
             }
         }
@@ -1555,12 +2108,46 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
     let ret = HANDLE.with(|&(ref state_arc, data_ptr)| {
         // Safety: the data pointer has been set by outside code and is valid
         let data = unsafe { &mut *(data_ptr as *mut D) };
-        udata.data.clone().request(
-            &Handle { handle: InnerHandle { state: state_arc.clone() } },
-            data,
-            ClientId { id: client_id.clone() },
-            Message { sender_id: object_id.clone(), opcode: opcode as u16, args: parsed_args },
-        )
+        let handle = Handle { handle: InnerHandle { state: state_arc.clone() } };
+        let message = BorrowedMessage {
+            sender_id: object_id.clone(),
+            opcode: opcode as u16,
+            args: parsed_args,
+        };
+        let (interceptor, metrics) = {
+            let state = state_arc.lock().unwrap();
+            (state.interceptor(), state.metrics())
+        };
+        metrics.message_in(count_fds(message_desc.signature));
+        // Safety: the client of the resource is alive while its requests are dispatched
+        let seq = unsafe { client_user_data(client) }
+            .map_or(0, |udata| unsafe { (*udata).requests_in.fetch_add(1, Ordering::Relaxed) } + 1);
+        metrics.time_dispatch(|| match interceptor {
+            // the interceptor works on owned messages
+            Some(interceptor) => match interceptor.intercept_sequenced(
+                &handle,
+                ClientId { id: client_id.clone() },
+                seq,
+                message.into_owned(),
+            ) {
+                Some(message) => udata.data.clone().request(
+                    &handle,
+                    data,
+                    ClientId { id: client_id.clone() },
+                    message,
+                ),
+                // the request was consumed, a created object is left inert
+                None => {
+                    created.as_ref().map(|_| Arc::new(InertObjectData) as Arc<dyn ObjectData<D>>)
+                }
+            },
+            None => udata.data.clone().request_borrowed(
+                &handle,
+                data,
+                ClientId { id: client_id.clone() },
+                message,
+            ),
+        })
     });
 
     if message_desc.is_destructor {
@@ -1568,9 +2155,13 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
     }
 
     match (created, ret) {
-        (Some((_, child_udata_ptr)), Some(child_data)) => unsafe {
-            (*child_udata_ptr).data = child_data;
-        },
+        (Some((child_id, child_udata_ptr)), Some(child_data)) => {
+            // the object may already have been destroyed by the callback, for example by firing a
+            // `wl_callback` right away, in which case its user data has been freed
+            if child_id.alive.load(Ordering::Acquire) {
+                unsafe { (*child_udata_ptr).data = child_data };
+            }
+        }
         (Some((child_id, _)), None) => {
             // Accept a missing object data if a protocol error occurred (and the object is already dead)
             if client_id.alive.load(Ordering::Acquire) {
@@ -1586,6 +2177,108 @@ unsafe extern "C" fn resource_dispatcher<D: 'static>(
     0
 }
 
+unsafe extern "C" fn protocol_logger(
+    user_data: *mut c_void,
+    direction: wl_protocol_logger_type,
+    message: *const wl_protocol_logger_message,
+) {
+    // Safety: the user data is the output of the ProtocolLogger, which outlives the logger, and the
+    // message provided by libwayland is valid for the duration of the call
+    let (output, message) = unsafe { (&*(user_data as *const DebugOutput), &*message) };
+    let resource = message.resource;
+    // Safety: libwayland gives a valid resource and message description, whose interfaces and
+    // names are static
+    let (interface, id, name, label) = unsafe {
+        let interface =
+            CStr::from_ptr(ffi_dispatch!(wayland_server_handle(), wl_resource_get_class, resource));
+        let id = ffi_dispatch!(wayland_server_handle(), wl_resource_get_id, resource);
+        let name = CStr::from_ptr((*message.message).name);
+        let client = ffi_dispatch!(wayland_server_handle(), wl_resource_get_client, resource);
+        let label = client_user_data(client).and_then(|data| (*data).label.as_deref());
+        (interface.to_string_lossy(), id, name.to_string_lossy(), label)
+    };
+    // Safety: the arguments match the signature of the message
+    let args = unsafe {
+        logged_arguments(&*message.message, message.arguments, message.arguments_count as usize)
+    };
+    match direction {
+        wl_protocol_logger_type::WL_PROTOCOL_LOGGER_REQUEST => print_dispatched_message(
+            output,
+            Direction::Request,
+            &interface,
+            id,
+            &name,
+            &args,
+            label,
+        ),
+        wl_protocol_logger_type::WL_PROTOCOL_LOGGER_EVENT => {
+            print_send_message(output, Direction::Event, &interface, id, &name, &args, label)
+        }
+    }
+}
+
+/// Convert the arguments of a message given to a protocol logger for the debug output
+///
+/// Objects are given by libwayland as pointers to their resource, and new objects by their id.
+unsafe fn logged_arguments<'a>(
+    message: &'a wl_message,
+    arguments: *const wl_argument,
+    count: usize,
+) -> SmallVec<[BorrowedArgument<'a, RawId, BorrowedFd<'a>>; INLINE_ARGS]> {
+    // Safety: the message description and the arguments are valid, and their interfaces static
+    unsafe {
+        let signature = CStr::from_ptr(message.signature).to_bytes();
+        signature
+            .iter()
+            .filter(|c| !c.is_ascii_digit() && **c != b'?')
+            .zip(0..count)
+            .map(|(c, i)| {
+                let arg = &*arguments.add(i);
+                match c {
+                    b'i' => BorrowedArgument::Int(arg.i),
+                    b'u' => BorrowedArgument::Uint(arg.u),
+                    b'f' => BorrowedArgument::Fixed(arg.f),
+                    b's' => {
+                        BorrowedArgument::Str((!arg.s.is_null()).then(|| CStr::from_ptr(arg.s)))
+                    }
+                    b'o' => BorrowedArgument::Object(resource_raw_id(arg.o as *mut wl_resource)),
+                    b'n' => {
+                        let interface = *message.types.add(i);
+                        BorrowedArgument::NewId(RawId {
+                            interface: (!interface.is_null())
+                                .then(|| CStr::from_ptr((*interface).name).to_str().ok())
+                                .flatten(),
+                            id: arg.n,
+                        })
+                    }
+                    b'a' => BorrowedArgument::Array(match arg.a.as_ref() {
+                        Some(array) if array.size > 0 => {
+                            std::slice::from_raw_parts(array.data as *const u8, array.size)
+                        }
+                        _ => &[],
+                    }),
+                    _ => BorrowedArgument::Fd(BorrowedFd::borrow_raw(arg.h)),
+                }
+            })
+            .collect()
+    }
+}
+
+unsafe fn resource_raw_id(resource: *mut wl_resource) -> RawId {
+    if resource.is_null() {
+        return RawId { interface: None, id: 0 };
+    }
+    // Safety: the resource is valid, and its interface static
+    unsafe {
+        let interface =
+            CStr::from_ptr(ffi_dispatch!(wayland_server_handle(), wl_resource_get_class, resource));
+        RawId {
+            interface: interface.to_str().ok(),
+            id: ffi_dispatch!(wayland_server_handle(), wl_resource_get_id, resource),
+        }
+    }
+}
+
 unsafe extern "C" fn resource_destructor<D: 'static>(resource: *mut wl_resource) {
     // Safety: if this destructor is called resource is valid and initialized by us
     let udata = unsafe {
@@ -1628,7 +2321,7 @@ unsafe extern "C" fn resource_destructor<D: 'static>(resource: *mut wl_resource)
     }
 }
 
-#[cfg(feature = "log")]
+#[cfg(any(feature = "log", feature = "client_system", feature = "server_system"))]
 extern "C" {
     fn wl_log_trampoline_to_rust_server(fmt: *const std::os::raw::c_char, list: *const c_void);
 }
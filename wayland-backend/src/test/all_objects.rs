@@ -0,0 +1,207 @@
+use std::ffi::CString;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+
+// an object data for all objects, including the ones created by requests
+struct ServerData;
+
+macro_rules! impl_server_objectdata {
+    ($server_backend:tt) => {
+        impl $server_backend::GlobalHandler<()> for ServerData {
+            fn bind(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::GlobalId,
+                _: $server_backend::ObjectId,
+            ) -> Arc<dyn $server_backend::ObjectData<()>> {
+                self
+            }
+        }
+
+        impl $server_backend::ObjectData<()> for ServerData {
+            fn request(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                msg: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $server_backend::ObjectData<()>>> {
+                msg.args.iter().any(|arg| matches!(arg, Argument::NewId(_))).then_some(self)
+            }
+
+            fn destroyed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::ObjectId,
+            ) {
+            }
+        }
+    };
+}
+
+impl_server_objectdata!(server_rs);
+impl_server_objectdata!(server_sys);
+
+expand_test!(all_objects, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        5,
+        Arc::new(ServerData),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(3),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 3)),
+        )
+        .unwrap();
+    let secondary_id = client
+        .send_request(
+            message!(
+                test_global_id.clone(),
+                1,
+                [Argument::NewId(client_backend::ObjectId::null())]
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::SECONDARY_INTERFACE, 3)),
+        )
+        .unwrap();
+    client
+        .send_request(
+            message!(test_global_id, 2, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TERTIARY_INTERFACE, 3)),
+        )
+        .unwrap();
+    client.send_request(message!(secondary_id.clone(), 0, []), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    let client_objects = client.all_objects();
+    // the rust backend still knows the secondary until the server acknowledges its destruction
+    assert!(client_objects.iter().all(|object| !object.alive || object.id != secondary_id));
+    let mut alive = client_objects
+        .iter()
+        .filter(|object| object.alive)
+        .map(|object| (object.info.interface.name, object.info.version))
+        .collect::<Vec<_>>();
+    alive.sort();
+    assert_eq!(alive, [("tertiary", 3), ("test_global", 3), ("wl_display", 1), ("wl_registry", 1)]);
+
+    // the objects handled by libwayland are not listed by the system backend
+    let mut server_objects = server
+        .all_objects()
+        .into_iter()
+        .map(|object| (object.info.interface.name, object.info.version))
+        .filter(|(name, _)| !["wl_display", "wl_registry"].contains(name))
+        .collect::<Vec<_>>();
+    server_objects.sort();
+    assert_eq!(server_objects, [("tertiary", 3), ("test_global", 3)]);
+});
+
+struct CountData(AtomicUsize);
+
+impl client_rs::ObjectData for CountData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_rs::Backend,
+        _: Message<client_rs::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_rs::ObjectData>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_rs::ObjectId) {}
+}
+
+impl client_sys::ObjectData for CountData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_sys::Backend,
+        _: Message<client_sys::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_sys::ObjectData>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_sys::ObjectId) {}
+}
+
+// the objects created and destroyed from several threads at once are all tracked
+expand_test!(all_objects_from_threads, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let count_data = Arc::new(CountData(AtomicUsize::new(0)));
+    let threads = (0..4)
+        .map(|_| {
+            let client = client.clone();
+            let count_data = count_data.clone();
+            std::thread::spawn(move || {
+                for _ in 0..25 {
+                    client
+                        .send_request(
+                            message!(
+                                client.display_id(),
+                                0,
+                                [Argument::NewId(client_backend::ObjectId::null())]
+                            ),
+                            Some(count_data.clone()),
+                            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+                        )
+                        .unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    let callbacks = |client: &client_backend::Backend| {
+        client
+            .all_objects()
+            .iter()
+            .filter(|object| object.alive && object.info.interface.name == "wl_callback")
+            .count()
+    };
+    assert_eq!(callbacks(&client), 100);
+
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    while count_data.0.load(Ordering::SeqCst) < 100 {
+        client.prepare_read().unwrap().read().unwrap();
+    }
+    // the callbacks are destroyed by their done event
+    assert_eq!(callbacks(&client), 0);
+});
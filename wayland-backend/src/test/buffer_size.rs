@@ -0,0 +1,112 @@
+use std::ffi::CString;
+use std::os::unix::io::BorrowedFd;
+
+use super::*;
+
+// the many_args request of test_global, with an array of `len` bytes
+fn many_args(
+    id: client_rs::ObjectId,
+    len: usize,
+) -> Message<client_rs::ObjectId, BorrowedFd<'static>> {
+    message!(
+        id,
+        0,
+        [
+            Argument::Uint(0),
+            Argument::Int(0),
+            Argument::Fixed(0),
+            Argument::Array(Box::new(vec![7; len])),
+            Argument::Str(Some(Box::new(CString::new("").unwrap()))),
+            Argument::Fd(rustix::stdio::stdin()),
+        ],
+    )
+}
+
+fn bind_test_global(client: &client_rs::Backend) -> client_rs::ObjectId {
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(3),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 3)),
+        )
+        .unwrap()
+}
+
+// the system backend only supports it with the libwayland_1_23 cargo feature
+#[test]
+fn client_max_buffer_size() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(DoNothingData),
+    );
+
+    let test_global_id = bind_test_global(&client);
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    // the incoming buffer already holds 8192 bytes, the maximum cannot be smaller
+    client.set_max_buffer_size(9000);
+    client.send_request(many_args(test_global_id.clone(), 4_000), None, None).unwrap();
+    assert!(client.last_error().is_none());
+
+    // the stored error does not keep the MessageTooLarge payload, only its kind
+    client.send_request(many_args(test_global_id, 10_000), None, None).unwrap();
+    match client.last_error() {
+        Some(client_rs::WaylandError::Io(err)) => {
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData)
+        }
+        err => panic!("Bad error: {:?}", err),
+    }
+}
+
+#[test]
+fn server_max_client_buffer_size() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(DoNothingData),
+    );
+
+    let test_global_id = bind_test_global(&client);
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    // the limit applies to the clients already connected
+    server.set_max_client_buffer_size(8192);
+    client.send_request(many_args(test_global_id.clone(), 4_000), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert!(server.handle().is_client_alive(client_id.clone()));
+
+    client.send_request(many_args(test_global_id, 10_000), None, None).unwrap();
+    client.flush().unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+    assert!(!server.handle().is_client_alive(client_id));
+}
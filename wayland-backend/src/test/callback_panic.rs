@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+
+// Panics on its first event, and counts the others
+struct PanicData(AtomicUsize);
+
+impl client_sys::ObjectData for PanicData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_sys::Backend,
+        _: Message<client_sys::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_sys::ObjectData>> {
+        if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+            panic!("callback failure");
+        }
+        None
+    }
+
+    fn destroyed(&self, _: client_sys::ObjectId) {}
+}
+
+// a panic in a callback cannot unwind through libwayland, it is reported as a fatal error instead
+#[test]
+fn panic_in_sys_callback() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_sys::Backend::connect(tx).unwrap();
+
+    let data = Arc::new(PanicData(AtomicUsize::new(0)));
+    let client_display = client.display_id();
+    for _ in 0..2 {
+        client
+            .send_request(
+                message!(
+                    client_display.clone(),
+                    0,
+                    [Argument::NewId(client_sys::ObjectId::null())]
+                ),
+                Some(data.clone()),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    let err = client.prepare_read().unwrap().read().unwrap_err();
+    assert!(err.to_string().contains("callback failure"));
+    // the events following the panic are not dispatched
+    assert_eq!(data.0.load(Ordering::SeqCst), 1);
+    assert!(client.last_error().is_some());
+    assert!(client.dispatch_inner_queue().is_err());
+}
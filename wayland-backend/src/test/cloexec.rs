@@ -0,0 +1,171 @@
+use std::{
+    ffi::CString,
+    os::unix::io::{AsRawFd, RawFd},
+    process::Command,
+    sync::Mutex,
+};
+
+use super::*;
+
+// stores the fds received in the many_args request or event
+struct FdStore(Mutex<Vec<OwnedFd>>);
+
+impl FdStore {
+    fn store(&self, msg: Message<impl std::fmt::Debug, OwnedFd>) {
+        for arg in msg.args {
+            if let Argument::Fd(fd) = arg {
+                self.0.lock().unwrap().push(fd);
+            }
+        }
+    }
+
+    fn raw_fds(&self) -> Vec<RawFd> {
+        self.0.lock().unwrap().iter().map(AsRawFd::as_raw_fd).collect()
+    }
+}
+
+macro_rules! impl_fd_store {
+    ($server_backend:tt, $client_backend:tt) => {
+        impl $server_backend::GlobalHandler<()> for FdStore {
+            fn bind(
+                self: Arc<Self>,
+                handle: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::GlobalId,
+                object_id: $server_backend::ObjectId,
+            ) -> Arc<dyn $server_backend::ObjectData<()>> {
+                handle.send_event(many_args!(object_id)).unwrap();
+                self
+            }
+        }
+
+        impl $server_backend::ObjectData<()> for FdStore {
+            fn request(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                msg: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $server_backend::ObjectData<()>>> {
+                self.store(msg);
+                None
+            }
+
+            fn destroyed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::ObjectId,
+            ) {
+            }
+        }
+
+        impl $client_backend::ObjectData for FdStore {
+            fn event(
+                self: Arc<Self>,
+                _: &$client_backend::Backend,
+                msg: Message<$client_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                self.store(msg);
+                None
+            }
+
+            fn destroyed(&self, _: $client_backend::ObjectId) {}
+        }
+    };
+}
+
+// the many_args request and event of test_global, sending stdin
+macro_rules! many_args {
+    ($id:expr) => {
+        message!(
+            $id,
+            0,
+            [
+                Argument::Uint(0),
+                Argument::Int(0),
+                Argument::Fixed(0),
+                Argument::Array(Box::default()),
+                Argument::Str(Some(Box::new(CString::new("").unwrap()))),
+                Argument::Fd(rustix::stdio::stdin()),
+            ],
+        )
+    };
+}
+
+impl_fd_store!(server_rs, client_rs);
+impl_fd_store!(server_sys, client_sys);
+
+// The fds among `fds` that are open in a child process
+fn inherited_fds(fds: &[RawFd]) -> Vec<RawFd> {
+    let script =
+        fds.iter().map(|fd| format!("test -e /dev/fd/{fd} && echo {fd};")).collect::<String>();
+    let output = Command::new("sh").arg("-c").arg(script).output().unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .split_whitespace()
+        .map(|fd| fd.parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn inherited_fds_detection() {
+    let inheritable = rustix::io::dup(std::io::stdin()).unwrap();
+    let cloexec = rustix::io::fcntl_dupfd_cloexec(std::io::stdin(), 0).unwrap();
+    let fds = [inheritable.as_raw_fd(), cloexec.as_raw_fd()];
+    assert_eq!(inherited_fds(&fds), [inheritable.as_raw_fd()]);
+}
+
+expand_test!(no_fd_leak_into_children, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    let server_fds = Arc::new(FdStore(Mutex::new(Vec::new())));
+    let client_fds = Arc::new(FdStore(Mutex::new(Vec::new())));
+    server.handle().create_global::<()>(&interfaces::TEST_GLOBAL_INTERFACE, 1, server_fds.clone());
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(client_fds.clone()),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.send_request(many_args!(test_global_id), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+
+    let mut fds = vec![
+        client.poll_fd().as_raw_fd(),
+        client.wakeup_fd().unwrap().as_raw_fd(),
+        server.poll_fd().as_raw_fd(),
+    ];
+    assert_eq!(server_fds.raw_fds().len(), 1);
+    assert_eq!(client_fds.raw_fds().len(), 1);
+    fds.extend(server_fds.raw_fds());
+    fds.extend(client_fds.raw_fds());
+    assert_eq!(inherited_fds(&fds), []);
+});
@@ -0,0 +1,169 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Barrier,
+};
+
+use super::*;
+
+// counts the received events, and prepares a new read from the callback if asked to
+struct ReadData {
+    events: AtomicUsize,
+    nested_read: bool,
+}
+
+impl ReadData {
+    fn new(nested_read: bool) -> Arc<Self> {
+        Arc::new(Self { events: AtomicUsize::new(0), nested_read })
+    }
+
+    fn count(&self) -> usize {
+        self.events.load(Ordering::SeqCst)
+    }
+}
+
+impl client_rs::ObjectData for ReadData {
+    fn event(
+        self: Arc<Self>,
+        handle: &client_rs::Backend,
+        _: Message<client_rs::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_rs::ObjectData>> {
+        if self.nested_read {
+            if let Some(guard) = handle.prepare_read() {
+                // the events being dispatched are left to the outer read
+                assert_eq!(guard.read().unwrap(), 0);
+            }
+        }
+        self.events.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_rs::ObjectId) {}
+}
+
+impl client_sys::ObjectData for ReadData {
+    fn event(
+        self: Arc<Self>,
+        handle: &client_sys::Backend,
+        _: Message<client_sys::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_sys::ObjectData>> {
+        if self.nested_read {
+            // libwayland would perform an actual read, only cancel it
+            std::mem::drop(handle.prepare_read());
+        }
+        self.events.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_sys::ObjectId) {}
+}
+
+// the events are read once all threads reading concurrently invoked `read()`
+expand_test!(concurrent_read, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let read_data = ReadData::new(false);
+    for _ in 0..10 {
+        client
+            .send_request(
+                message!(
+                    client.display_id(),
+                    0,
+                    [Argument::NewId(client_backend::ObjectId::null())]
+                ),
+                Some(read_data.clone()),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    let prepared = Arc::new(Barrier::new(4));
+    let threads = (0..4)
+        .map(|_| {
+            let client = client.clone();
+            let prepared = prepared.clone();
+            std::thread::spawn(move || {
+                let guard = client.prepare_read().unwrap();
+                prepared.wait();
+                guard.read()
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap().unwrap();
+    }
+
+    // the events were dispatched once, by the last thread to read
+    assert_eq!(read_data.count(), 10);
+});
+
+// a pending read waits for all the guards to be consumed or dropped
+expand_test!(concurrent_read_cancel, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let read_data = ReadData::new(false);
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(read_data.clone()),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    let other_guard = client.prepare_read().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let reader = {
+        let client = client.clone();
+        std::thread::spawn(move || {
+            let ret = client.prepare_read().unwrap().read();
+            sender.send(()).unwrap();
+            ret
+        })
+    };
+
+    // the reader waits for the other guard
+    assert!(receiver.recv_timeout(std::time::Duration::from_millis(50)).is_err());
+    assert_eq!(read_data.count(), 0);
+
+    // dropping it cancels the read, like libwayland the reader returns without reading
+    std::mem::drop(other_guard);
+    assert_eq!(reader.join().unwrap().unwrap(), 0);
+    assert_eq!(read_data.count(), 0);
+
+    client.prepare_read().unwrap().read().unwrap();
+    assert_eq!(read_data.count(), 1);
+});
+
+// the callbacks can prepare new reads while the events are dispatched
+expand_test!(nested_read, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let read_data = ReadData::new(true);
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(read_data.clone()),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    client.prepare_read().unwrap().read().unwrap();
+    assert_eq!(read_data.count(), 1);
+});
@@ -0,0 +1,55 @@
+use std::os::unix::io::OwnedFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+
+struct SyncData(AtomicBool);
+
+impl client_rs::ObjectData for SyncData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_rs::Backend,
+        _: Message<client_rs::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_rs::ObjectData>> {
+        self.0.store(true, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_rs::ObjectId) {}
+}
+
+impl client_sys::ObjectData for SyncData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_sys::Backend,
+        _: Message<client_sys::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_sys::ObjectData>> {
+        self.0.store(true, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_sys::ObjectId) {}
+}
+
+// both ends of the connection are given as plain file descriptors
+expand_test!(connect_fd, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client_fd(OwnedFd::from(rx), Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect_fd(OwnedFd::from(tx)).unwrap();
+
+    let sync_data = Arc::new(SyncData(AtomicBool::new(false)));
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(sync_data.clone()),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    client.prepare_read().unwrap().read().unwrap();
+    assert!(sync_data.0.load(Ordering::SeqCst));
+});
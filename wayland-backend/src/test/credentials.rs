@@ -0,0 +1,17 @@
+use super::*;
+
+expand_test!(client_credentials, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let _client = client_backend::Backend::connect(tx).unwrap();
+
+    // the client runs in this process
+    let credentials = server.handle().get_client_credentials(client_id).unwrap();
+    // the pid is not always available on FreeBSD and DragonFly
+    if !cfg!(any(target_os = "freebsd", target_os = "dragonfly")) {
+        assert_eq!(credentials.pid, rustix::process::getpid().as_raw_nonzero().get());
+    }
+    assert_eq!(credentials.uid, rustix::process::getuid().as_raw());
+    assert_eq!(credentials.gid, rustix::process::getgid().as_raw());
+});
@@ -0,0 +1,181 @@
+use std::ffi::CString;
+
+use super::*;
+
+// an object data exposing only its inner value as user data
+struct UserData(u32);
+
+macro_rules! impl_user_data {
+    ($server_backend:tt, $client_backend:tt) => {
+        impl $server_backend::GlobalHandler<()> for UserData {
+            fn bind(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::GlobalId,
+                _: $server_backend::ObjectId,
+            ) -> Arc<dyn $server_backend::ObjectData<()>> {
+                self
+            }
+        }
+
+        impl $server_backend::ObjectData<()> for UserData {
+            fn request(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $server_backend::ObjectData<()>>> {
+                None
+            }
+
+            fn destroyed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::ObjectId,
+            ) {
+            }
+
+            fn data_as_any(&self) -> &dyn std::any::Any {
+                &self.0
+            }
+        }
+
+        impl $client_backend::ObjectData for UserData {
+            fn event(
+                self: Arc<Self>,
+                _: &$client_backend::Backend,
+                _: Message<$client_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                None
+            }
+
+            fn destroyed(&self, _: $client_backend::ObjectId) {}
+
+            fn data_as_any(&self) -> &dyn std::any::Any {
+                &self.0
+            }
+        }
+    };
+}
+
+impl_user_data!(server_rs, client_rs);
+impl_user_data!(server_sys, client_sys);
+
+expand_test!(get_data_as, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(UserData(42)),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id.clone(),
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(3),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(UserData(7))),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 3)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    assert_eq!(*client.get_data_as::<u32>(test_global_id.clone()).unwrap(), 7);
+    assert!(client.get_data_as::<UserData>(test_global_id).is_none());
+    // by default, the user data is the object data itself
+    assert!(client.get_data_as::<DoNothingData>(registry_id).is_some());
+    assert!(client.get_data_as::<u32>(client_backend::ObjectId::null()).is_none());
+
+    let server_global_id = server
+        .all_objects()
+        .into_iter()
+        .find(|object| object.info.interface.name == "test_global")
+        .unwrap()
+        .id;
+    let data = server.handle().get_object_data_as::<(), u32>(server_global_id.clone()).unwrap();
+    assert_eq!(*data, 42);
+    assert!(server.handle().get_object_data_as::<(), UserData>(server_global_id).is_none());
+});
+
+expand_test!(replace_data, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(UserData(4)),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(3),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(UserData(1))),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 3)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    let previous = client.replace_data(test_global_id.clone(), Arc::new(UserData(2))).unwrap();
+    assert_eq!(previous.data_as_any().downcast_ref::<u32>(), Some(&1));
+    assert_eq!(*client.get_data_as::<u32>(test_global_id).unwrap(), 2);
+    assert!(client.replace_data(client_backend::ObjectId::null(), Arc::new(UserData(3))).is_err());
+
+    let server_global_id = server
+        .all_objects()
+        .into_iter()
+        .find(|object| object.info.interface.name == "test_global")
+        .unwrap()
+        .id;
+    let previous = server
+        .handle()
+        .replace_object_data::<()>(server_global_id.clone(), Arc::new(UserData(5)))
+        .unwrap();
+    assert_eq!(previous.data_as_any().downcast_ref::<u32>(), Some(&4));
+    assert_eq!(*server.handle().get_object_data_as::<(), u32>(server_global_id).unwrap(), 5);
+});
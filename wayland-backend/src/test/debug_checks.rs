@@ -0,0 +1,57 @@
+use std::ffi::CString;
+
+use super::*;
+
+// reading from the second guard would wait for the first one forever
+expand_test!(panic prepare_read_twice, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let _guard = client.prepare_read().unwrap();
+    let _ = client.prepare_read();
+});
+
+// sending a request on a destroyed object while the connection is alive is a bug of the caller
+expand_test!(panic request_on_dead_object, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        4,
+        Arc::new(DoNothingData),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(4),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 4)),
+        )
+        .unwrap();
+
+    // the destroy request
+    client.send_request(message!(test_global_id.clone(), 4, []), None, None).unwrap();
+    let _ = client.send_request(message!(test_global_id, 4, []), None, None);
+});
@@ -0,0 +1,224 @@
+use std::{ffi::CString, sync::Mutex};
+
+use super::*;
+
+fn sink(lines: &Arc<Mutex<Vec<String>>>) -> Option<client_rs::DebugSink> {
+    let lines = lines.clone();
+    Some(Arc::new(move |line: &str| {
+        // strip the timestamp
+        lines.lock().unwrap().push(line.split_once("] ").unwrap().1.to_owned())
+    }))
+}
+
+// the rust backends give their debug output in the libwayland format to the sink, the server
+// identifying the client without a label by its id
+#[test]
+fn debug_sink() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    let server_lines = Arc::new(Mutex::new(Vec::new()));
+    let client_lines = Arc::new(Mutex::new(Vec::new()));
+    server.set_debug_sink(sink(&server_lines));
+    client.set_debug_sink(sink(&client_lines));
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        1,
+        Arc::new(DoNothingData),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+
+    assert_eq!(
+        *client_lines.lock().unwrap(),
+        [
+            " -> wl_display@1.get_registry(new id wl_registry@2)",
+            " -> wl_registry@2.bind(1, \"test_global\", 1, new id test_global@3)",
+            "wl_registry@2.global(1, \"test_global\", 1)",
+        ]
+    );
+    assert_eq!(
+        *server_lines.lock().unwrap(),
+        [
+            "[client 0] wl_display@1.get_registry(new id wl_registry@2)",
+            "[client 0]  -> wl_registry@2.global(1, \"test_global\", 1)",
+            "[client 0] wl_registry@2.bind(1, \"test_global\", 1, new id [unknown]@3)",
+        ]
+    );
+
+    // without a sink nor WAYLAND_DEBUG, nothing is printed anymore
+    client.set_debug_sink(None);
+    client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    assert_eq!(client_lines.lock().unwrap().len(), 3);
+}
+
+// the arguments referring to a registered enum are shown by the names of its entries
+#[test]
+fn debug_sink_enums() {
+    crate::protocol::EnumDesc::register(interfaces::ARG_ENUMS);
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    client.set_debug_sink(sink(&lines));
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        5,
+        Arc::new(DoNothingData),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(5),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 5)),
+        )
+        .unwrap();
+    let tertiary_id = client
+        .send_request(
+            message!(global_id, 2, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TERTIARY_INTERFACE, 5)),
+        )
+        .unwrap();
+    client
+        .send_request(
+            message!(tertiary_id, 1, [Argument::Uint(3), Argument::Uint(1), Argument::Uint(1)],),
+            None,
+            None,
+        )
+        .unwrap();
+
+    // the capabilities refer to `wl_seat.capability`, from another protocol: the `ARG_ENUMS` of this
+    // one do not list the argument, whatever the enums registered by the other protocols
+    assert_eq!(
+        lines.lock().unwrap().last().unwrap(),
+        " -> tertiary@4.set_flags(first|second, already_linked, 1)"
+    );
+}
+
+// both server backends give the same output to the sink, with the label of the client
+expand_test!(server_debug_sink, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    server.handle().set_client_label(client_id, "test".into()).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    server.set_debug_sink(sink(&lines));
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        1,
+        Arc::new(DoNothingData),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        [
+            "[test] wl_display@1.get_registry(new id wl_registry@2)",
+            "[test]  -> wl_registry@2.global(1, \"test_global\", 1)",
+            "[test] wl_registry@2.bind(1, \"test_global\", 1, new id [unknown]@3)",
+        ]
+    );
+
+    // once the sink is removed, nothing is given to it anymore
+    server.set_debug_sink(None);
+    client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    assert_eq!(lines.lock().unwrap().len(), 3);
+});
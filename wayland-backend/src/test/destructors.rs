@@ -2,7 +2,7 @@ use std::{
     ffi::CString,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Mutex,
     },
 };
 
@@ -190,6 +190,89 @@ expand_test!(destructor_cleanup, {
     assert!(server_data.0.load(Ordering::Acquire));
 });
 
+// the server object of the last bind of the global
+struct BoundGlobal(Mutex<Option<server_rs::ObjectId>>);
+
+impl server_rs::GlobalHandler<()> for BoundGlobal {
+    fn bind(
+        self: Arc<Self>,
+        _: &server_rs::Handle,
+        _: &mut (),
+        _: server_rs::ClientId,
+        _: server_rs::GlobalId,
+        object_id: server_rs::ObjectId,
+    ) -> Arc<dyn server_rs::ObjectData<()>> {
+        *self.0.lock().unwrap() = Some(object_id);
+        Arc::new(DoNothingData)
+    }
+}
+
+// libwayland discards the events to dead objects without notice, only the rust backend counts them
+#[test]
+fn dead_object_events() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    let bound = Arc::new(BoundGlobal(Mutex::new(None)));
+    server.handle().create_global(&interfaces::TEST_GLOBAL_INTERFACE, 4, bound.clone());
+
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(4),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 4)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    // the client destroys the global while the server sends it an event
+    client.send_request(message!(test_global_id, 4, []), None, None).unwrap();
+    let server_global = bound.0.lock().unwrap().take().unwrap();
+    server
+        .handle()
+        .send_event(message!(
+            server_global,
+            0,
+            [
+                Argument::Uint(1),
+                Argument::Int(2),
+                Argument::Fixed(3),
+                Argument::Array(Box::new(vec![1, 2, 3])),
+                Argument::Str(Some(Box::new(CString::new("dead".as_bytes()).unwrap()))),
+                Argument::Fd(rustix::stdio::stdout()),
+            ],
+        ))
+        .unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+
+    let events = client.dead_object_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[interfaces::TEST_GLOBAL_INTERFACE.name], 1);
+}
+
 struct ServerClientData(AtomicBool);
 
 macro_rules! impl_server_clientdata {
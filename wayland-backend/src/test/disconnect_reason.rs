@@ -0,0 +1,182 @@
+use std::{
+    ffi::CString,
+    sync::{Arc, Mutex},
+};
+
+use crate::protocol::WireError;
+use crate::rs::socket::{BufferedSocket, Socket};
+use crate::types::server::DisconnectReason;
+
+use super::*;
+
+#[derive(Default)]
+struct ReasonRecorder(Mutex<Vec<&'static str>>);
+
+impl ReasonRecorder {
+    fn record(&self, reason: DisconnectReason) {
+        let reason = match reason {
+            DisconnectReason::ConnectionClosed => "closed",
+            DisconnectReason::ProtocolError(err) => {
+                assert_eq!(err.code, 42);
+                assert_eq!(err.object_interface, "test_global");
+                assert_eq!(err.message, "I don't like you.");
+                "protocol_error"
+            }
+            DisconnectReason::Killed => "killed",
+            DisconnectReason::SendFailed(_) => "send_failed",
+            DisconnectReason::InvalidMessage(err) => {
+                assert_eq!(err, WireError::UnknownOpcode { object_id: 1, opcode: 42 });
+                "invalid_message"
+            }
+        };
+        self.0.lock().unwrap().push(reason);
+    }
+
+    fn reasons(&self) -> Vec<&'static str> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl server_rs::ClientData for ReasonRecorder {
+    fn disconnected(&self, _: server_rs::ClientId, reason: DisconnectReason) {
+        self.record(reason);
+    }
+}
+
+impl server_sys::ClientData for ReasonRecorder {
+    fn disconnected(&self, _: server_sys::ClientId, reason: DisconnectReason) {
+        self.record(reason);
+    }
+}
+
+struct ServerData<Id>(Arc<Mutex<Option<Id>>>);
+
+impl server_rs::GlobalHandler<()> for ServerData<server_rs::ObjectId> {
+    fn bind(
+        self: Arc<Self>,
+        _: &server_rs::Handle,
+        _: &mut (),
+        _: server_rs::ClientId,
+        _: server_rs::GlobalId,
+        object_id: server_rs::ObjectId,
+    ) -> Arc<dyn server_rs::ObjectData<()>> {
+        *(self.0.lock().unwrap()) = Some(object_id);
+        Arc::new(DoNothingData)
+    }
+}
+
+impl server_sys::GlobalHandler<()> for ServerData<server_sys::ObjectId> {
+    fn bind(
+        self: Arc<Self>,
+        _: &server_sys::Handle,
+        _: &mut (),
+        _: server_sys::ClientId,
+        _: server_sys::GlobalId,
+        object_id: server_sys::ObjectId,
+    ) -> Arc<dyn server_sys::ObjectData<()>> {
+        *(self.0.lock().unwrap()) = Some(object_id);
+        Arc::new(DoNothingData)
+    }
+}
+
+expand_test!(disconnect_reason_hang_up, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let recorder = Arc::new(ReasonRecorder::default());
+    server.handle().insert_client(rx, recorder.clone()).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    ::std::mem::drop(client);
+    let _ = server.dispatch_all_clients(&mut ());
+
+    assert_eq!(recorder.reasons(), ["closed"]);
+});
+
+expand_test!(disconnect_reason_protocol_error, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let recorder = Arc::new(ReasonRecorder::default());
+    server.handle().insert_client(rx, recorder.clone()).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let object_id = Arc::new(Mutex::new(None));
+    server.handle().create_global(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(ServerData(object_id.clone())),
+    );
+
+    // bind the test global
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(3),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 3)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    let oid = object_id.lock().unwrap().clone().unwrap();
+    server.handle().post_error(oid, 42, CString::new("I don't like you.".as_bytes()).unwrap());
+    let _ = server.flush(None);
+    let _ = server.dispatch_all_clients(&mut ());
+
+    // the client is only notified once, even after it is destroyed
+    assert_eq!(recorder.reasons(), ["protocol_error"]);
+});
+
+expand_test!(disconnect_reason_handshake_timeout, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    // a zero timeout expires on the first dispatch
+    server.set_handshake_timeout(Some(std::time::Duration::ZERO));
+    let recorder = Arc::new(ReasonRecorder::default());
+    server.handle().insert_client(rx, recorder.clone()).unwrap();
+    let _client = client_backend::Backend::connect(tx).unwrap();
+
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    assert_eq!(recorder.reasons(), ["killed"]);
+});
+
+// libwayland does not expose the reason, only the rust backend reports invalid messages
+#[test]
+fn disconnect_reason_invalid_message() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let recorder = Arc::new(ReasonRecorder::default());
+    server.handle().insert_client(rx, recorder.clone()).unwrap();
+
+    let mut socket = BufferedSocket::new(Socket::from(tx));
+    socket
+        .write_message(&Message {
+            sender_id: 1, // wl_display
+            opcode: 42,   // inexistant
+            args: smallvec::smallvec![],
+        })
+        .unwrap();
+    socket.flush().unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+
+    assert_eq!(recorder.reasons(), ["invalid_message"]);
+}
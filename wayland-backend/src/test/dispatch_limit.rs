@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use super::*;
+
+#[test]
+fn dispatch_all_clients_limited() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    for _ in 0..5 {
+        client
+            .send_request(
+                message!(client.display_id(), 0, [Argument::NewId(client_rs::ObjectId::null())]),
+                Some(Arc::new(DoNothingData)),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+    client.flush().unwrap();
+
+    assert_eq!(server.dispatch_all_clients_limited(&mut (), 2).unwrap(), (2, true));
+    // the remaining requests are already buffered and do not wake the poll fd up
+    assert!(!is_readable(server.poll_fd()));
+    assert_eq!(server.dispatch_all_clients_limited(&mut (), 2).unwrap(), (2, true));
+    assert_eq!(server.dispatch_all_clients_limited(&mut (), 2).unwrap(), (1, false));
+    assert_eq!(server.dispatch_all_clients_limited(&mut (), 2).unwrap(), (0, false));
+}
+
+#[test]
+fn dispatch_single_client_timeout() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    for _ in 0..5 {
+        client
+            .send_request(
+                message!(client.display_id(), 0, [Argument::NewId(client_rs::ObjectId::null())]),
+                Some(Arc::new(DoNothingData)),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+    client.flush().unwrap();
+
+    // one request is dispatched even if the deadline is already reached
+    let ret = server.dispatch_single_client_timeout(&mut (), client_id.clone(), Duration::ZERO);
+    assert_eq!(ret.unwrap(), (1, true));
+    let ret = server.dispatch_single_client_timeout(&mut (), client_id, Duration::from_secs(60));
+    assert_eq!(ret.unwrap(), (4, false));
+}
+
+// a client sending many requests does not prevent the others from being dispatched
+#[test]
+fn dispatch_fairness() {
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let mut clients = Vec::new();
+    for count in [100, 1] {
+        let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+        let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+        let client = client_rs::Backend::connect(tx).unwrap();
+        for _ in 0..count {
+            // wl_display.get_registry, creating an object per request
+            client
+                .send_request(
+                    message!(
+                        client.display_id(),
+                        1,
+                        [Argument::NewId(client_rs::ObjectId::null())]
+                    ),
+                    Some(Arc::new(DoNothingData)),
+                    Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+                )
+                .unwrap();
+        }
+        client.flush().unwrap();
+        clients.push((client_id, client));
+    }
+
+    assert_eq!(server.dispatch_all_clients_limited(&mut (), 50).unwrap(), (50, true));
+    let handle = server.handle();
+    let objects =
+        |id: &server_rs::ClientId| handle.get_client_resource_usage(id.clone()).unwrap().objects;
+    // the quiet client was dispatched, whichever client came first
+    assert_eq!(objects(&clients[1].0), 2);
+    assert_eq!(objects(&clients[0].0), 50);
+
+    assert_eq!(server.dispatch_all_clients(&mut ()).unwrap(), 51);
+    assert_eq!(objects(&clients[0].0), 101);
+}
@@ -0,0 +1,84 @@
+use std::{
+    io::Write,
+    os::unix::{io::AsFd, net::UnixStream},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::sys::event_loop::{Interest, Readiness};
+
+use super::*;
+
+fn recorder<T: Send + 'static>() -> (Arc<Mutex<Vec<T>>>, impl FnMut(T) + Send + 'static) {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let records2 = records.clone();
+    (records, move |value| records2.lock().unwrap().push(value))
+}
+
+#[test]
+fn fd_source() {
+    let mut server = server_sys::Backend::<()>::new().unwrap();
+    let (mut tx, rx) = UnixStream::pair().unwrap();
+
+    let (records, record) = recorder();
+    let id = server.add_fd_source(rx.as_fd(), Interest::READ, record).unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert!(records.lock().unwrap().is_empty());
+
+    tx.write_all(b"a").unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(*records.lock().unwrap(), [Readiness { readable: true, ..Default::default() }]);
+
+    // the source is no longer dispatched once removed
+    server.remove_source(id);
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(records.lock().unwrap().len(), 1);
+    assert!(server.update_fd_interest(id, Interest::WRITE).is_err());
+}
+
+#[test]
+fn timer_source() {
+    let mut server = server_sys::Backend::<()>::new().unwrap();
+
+    let (records, mut record) = recorder();
+    let mut count = 0;
+    let id = server
+        .add_timer_source(move || {
+            record(());
+            count += 1;
+            // re-arm the timer once
+            (count < 2).then_some(Duration::from_millis(1))
+        })
+        .unwrap();
+    assert!(server.update_fd_interest(id, Interest::READ).is_err());
+    server.update_timer(id, Some(Duration::from_micros(10))).unwrap();
+
+    // wait for the event loop to report each expiration of the timer
+    for expected in 1..=2 {
+        let poll_fd = server.poll_fd();
+        let mut fds = [rustix::event::PollFd::new(&poll_fd, rustix::event::PollFlags::IN)];
+        rustix::event::poll(&mut fds, -1).unwrap();
+        server.dispatch_all_clients(&mut ()).unwrap();
+        assert_eq!(records.lock().unwrap().len(), expected);
+    }
+    assert!(!is_readable(server.poll_fd()));
+}
+
+#[test]
+fn idle_source() {
+    let mut server = server_sys::Backend::<()>::new().unwrap();
+
+    let (records, mut record) = recorder();
+    let id = server.add_idle_source(move || record(())).unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(records.lock().unwrap().len(), 1);
+
+    // removing an idle source that fired does nothing, and a removed one never fires
+    server.remove_source(id);
+    let (records, mut record) = recorder();
+    let id = server.add_idle_source(move || record(())).unwrap();
+    server.remove_source(id);
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert!(records.lock().unwrap().is_empty());
+}
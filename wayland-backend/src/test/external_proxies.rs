@@ -0,0 +1,83 @@
+use wayland_sys::{client::*, common::wl_argument, ffi_dispatch};
+
+use super::*;
+
+// object data can be attached to the proxies created by another library sharing the connection
+#[test]
+fn external_proxy_data() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_sys::Backend::connect(tx).unwrap();
+
+    // create a registry behind the back of the backend, as another library would
+    let registry_ptr = create_external_registry(&client);
+    assert!(!registry_ptr.is_null());
+    let registry_id = unsafe {
+        client_sys::ObjectId::from_ptr(&interfaces::WL_REGISTRY_INTERFACE, registry_ptr).unwrap()
+    };
+
+    assert!(client.get_data(registry_id.clone()).is_err());
+    let data: Arc<dyn client_sys::ObjectData> = Arc::new(DoNothingData);
+    client.set_data(registry_id, data.clone()).unwrap();
+
+    // the data is found from any id of the proxy
+    let registry_id = unsafe {
+        client_sys::ObjectId::from_ptr(&interfaces::WL_REGISTRY_INTERFACE, registry_ptr).unwrap()
+    };
+    let found = client.get_data(registry_id).unwrap();
+    assert_eq!(Arc::as_ptr(&found) as *const u8, Arc::as_ptr(&data) as *const u8);
+
+    // the display still cannot be given a data
+    assert!(client.set_data(client.display_id(), data.clone()).is_err());
+
+    let registry_id = unsafe {
+        client_sys::ObjectId::from_ptr(&interfaces::WL_REGISTRY_INTERFACE, registry_ptr).unwrap()
+    };
+    let removed = client.remove_data(registry_id.clone()).unwrap();
+    assert_eq!(Arc::as_ptr(&removed) as *const u8, Arc::as_ptr(&data) as *const u8);
+    assert!(client.get_data(registry_id.clone()).is_err());
+    assert!(client.remove_data(registry_id).is_err());
+
+    unsafe { ffi_dispatch!(wayland_client_handle(), wl_proxy_destroy, registry_ptr) };
+}
+
+fn create_external_registry(client: &client_sys::Backend) -> *mut wl_proxy {
+    let mut args = [wl_argument { n: 0 }];
+    unsafe {
+        ffi_dispatch!(
+            wayland_client_handle(),
+            wl_proxy_marshal_array_constructor,
+            client.display_ptr() as *mut wl_proxy,
+            1,
+            args.as_mut_ptr(),
+            interfaces::WL_REGISTRY_INTERFACE.c_ptr.unwrap()
+        )
+    }
+}
+
+// once removed, the data of a destroyed proxy is not found for a new proxy reusing its address and id
+#[test]
+fn external_proxy_reuse() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_sys::Backend::connect(tx).unwrap();
+
+    let registry_ptr = create_external_registry(&client);
+    let registry_id = unsafe {
+        client_sys::ObjectId::from_ptr(&interfaces::WL_REGISTRY_INTERFACE, registry_ptr).unwrap()
+    };
+    client.set_data(registry_id.clone(), Arc::new(DoNothingData)).unwrap();
+    client.remove_data(registry_id).unwrap();
+    unsafe { ffi_dispatch!(wayland_client_handle(), wl_proxy_destroy, registry_ptr) };
+
+    // the allocator and libwayland are free to hand the same address and id to the next proxy
+    let new_ptr = create_external_registry(&client);
+    let new_id = unsafe {
+        client_sys::ObjectId::from_ptr(&interfaces::WL_REGISTRY_INTERFACE, new_ptr).unwrap()
+    };
+    assert!(client.get_data(new_id).is_err());
+
+    unsafe { ffi_dispatch!(wayland_client_handle(), wl_proxy_destroy, new_ptr) };
+}
@@ -0,0 +1,102 @@
+use std::{os::raw::c_void, sync::Mutex};
+
+use wayland_sys::{ffi_dispatch, server::*};
+
+use super::*;
+
+// records the interfaces of the advertised globals
+struct RegistryData(Mutex<Vec<String>>);
+
+impl client_rs::ObjectData for RegistryData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_rs::Backend,
+        msg: Message<client_rs::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_rs::ObjectData>> {
+        if let [_, Argument::Str(Some(ref interface)), _] = msg.args[..] {
+            self.0.lock().unwrap().push(interface.to_string_lossy().into_owned());
+        }
+        None
+    }
+
+    fn destroyed(&self, _: client_rs::ObjectId) {}
+}
+
+unsafe extern "C" fn bind_noop(_: *mut wl_client, _: *mut c_void, _: u32, _: u32) {}
+
+// the globals created by C code sharing the display are filtered by the foreign global filter
+#[test]
+fn foreign_global_filter() {
+    let mut server = server_sys::Backend::<()>::new().unwrap();
+    let (tx1, rx1) = std::os::unix::net::UnixStream::pair().unwrap();
+    let (tx2, rx2) = std::os::unix::net::UnixStream::pair().unwrap();
+    let hidden_client_id = server.handle().insert_client(rx1, Arc::new(())).unwrap();
+    let _client_id = server.handle().insert_client(rx2, Arc::new(())).unwrap();
+    let clients =
+        [client_rs::Backend::connect(tx1).unwrap(), client_rs::Backend::connect(tx2).unwrap()];
+
+    let foreign_global = unsafe {
+        ffi_dispatch!(
+            wayland_server_handle(),
+            wl_global_create,
+            server.handle().display_ptr(),
+            interfaces::TEST_GLOBAL_INTERFACE.c_ptr.unwrap(),
+            1,
+            std::ptr::null_mut(),
+            bind_noop
+        )
+    };
+    assert!(!foreign_global.is_null());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen2 = seen.clone();
+    server.handle().set_foreign_global_filter(Some(Arc::new(move |client_id, global| {
+        seen2.lock().unwrap().push(global.as_ptr() as usize);
+        client_id != hidden_client_id
+    })));
+
+    let registries = clients
+        .iter()
+        .map(|client| {
+            let data = Arc::new(RegistryData(Mutex::new(Vec::new())));
+            client
+                .send_request(
+                    message!(
+                        client.display_id(),
+                        1,
+                        [Argument::NewId(client_rs::ObjectId::null())]
+                    ),
+                    Some(data.clone()),
+                    Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+                )
+                .unwrap();
+            // so that there is something to read even if no global is advertised
+            client
+                .send_request(
+                    message!(
+                        client.display_id(),
+                        0,
+                        [Argument::NewId(client_rs::ObjectId::null())]
+                    ),
+                    Some(Arc::new(DoNothingData)),
+                    Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+                )
+                .unwrap();
+            client.flush().unwrap();
+            data
+        })
+        .collect::<Vec<_>>();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    for client in &clients {
+        client.prepare_read().unwrap().read().unwrap();
+    }
+
+    let name = interfaces::TEST_GLOBAL_INTERFACE.name;
+    assert!(!registries[0].0.lock().unwrap().iter().any(|interface| interface == name));
+    assert!(registries[1].0.lock().unwrap().iter().any(|interface| interface == name));
+    let seen = seen.lock().unwrap();
+    assert!(!seen.is_empty());
+    assert!(seen.iter().all(|&global| global == foreign_global as usize));
+
+    unsafe { ffi_dispatch!(wayland_server_handle(), wl_global_destroy, foreign_global) };
+}
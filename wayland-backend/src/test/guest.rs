@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+
+struct DoneData(AtomicBool);
+
+macro_rules! impl_done_data {
+    ($client_backend:tt) => {
+        impl $client_backend::ObjectData for DoneData {
+            fn event(
+                self: Arc<Self>,
+                _: &$client_backend::Backend,
+                msg: Message<$client_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                assert_eq!(msg.opcode, 0);
+                self.0.store(true, Ordering::SeqCst);
+                None
+            }
+
+            fn destroyed(&self, _: $client_backend::ObjectId) {}
+        }
+    };
+}
+
+impl_done_data!(client_rs);
+impl_done_data!(client_sys);
+
+// the objects of a guest are only dispatched by the guest, whoever reads the connection
+expand_test!(guest_dispatches_its_objects, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let host = client_backend::Backend::connect(tx).unwrap();
+    let guest = host.create_guest();
+    assert!(guest != host);
+
+    let host_done = Arc::new(DoneData(AtomicBool::new(false)));
+    let guest_done = Arc::new(DoneData(AtomicBool::new(false)));
+    for (backend, data) in [(&host, &host_done), (&guest, &guest_done)] {
+        backend
+            .send_request(
+                message!(
+                    backend.display_id(),
+                    0,
+                    [Argument::NewId(client_backend::ObjectId::null())]
+                ),
+                Some(data.clone()),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+    host.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    // the host reads both answers, but only dispatches its own
+    host.prepare_read().unwrap().read().unwrap();
+    assert!(host_done.0.load(Ordering::SeqCst));
+    assert!(!guest_done.0.load(Ordering::SeqCst));
+
+    // the guest cannot read before dispatching what was read for it
+    assert!(guest.prepare_read().is_none());
+    assert_eq!(guest.dispatch_inner_queue().unwrap(), 1);
+    assert!(guest_done.0.load(Ordering::SeqCst));
+    assert!(guest.prepare_read().is_some());
+});
@@ -0,0 +1,38 @@
+use super::*;
+
+// a client that never sends anything is disconnected once the handshake timeout expires
+expand_test!(handshake_timeout, {
+    let (tx_silent, rx_silent) = std::os::unix::net::UnixStream::pair().unwrap();
+    let (tx_active, rx_active) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    // the timeout is compared to the time elapsed since the connection whenever the clients are
+    // dispatched, it is lowered to zero once the active client made its request
+    server.set_handshake_timeout(Some(std::time::Duration::from_secs(3600)));
+    let silent_id = server.handle().insert_client(rx_silent, Arc::new(())).unwrap();
+    let active_id = server.handle().insert_client(rx_active, Arc::new(())).unwrap();
+    let _silent_client = client_backend::Backend::connect(tx_silent).unwrap();
+    let active_client = client_backend::Backend::connect(tx_active).unwrap();
+
+    // get the registry with the active client
+    let client_display = active_client.display_id();
+    active_client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    active_client.flush().unwrap();
+
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    // both clients are still alive before the timeout
+    assert!(server.handle().get_client_data(silent_id.clone()).is_ok());
+    assert!(server.handle().get_client_data(active_id.clone()).is_ok());
+
+    server.set_handshake_timeout(Some(std::time::Duration::ZERO));
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    assert!(server.handle().get_client_data(silent_id).is_err());
+    assert!(server.handle().get_client_data(active_id).is_ok());
+});
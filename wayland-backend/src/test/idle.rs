@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+
+// the idle callback is invoked once after each period of activity, once nothing is pending
+expand_test!(idle_callback, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let idle_count = Arc::new(AtomicUsize::new(0));
+    let count = idle_count.clone();
+    server.handle().set_idle_callback(Some(Arc::new(move || {
+        count.fetch_add(1, Ordering::SeqCst);
+    })));
+
+    // nothing happened yet
+    server.flush(None).unwrap();
+    assert_eq!(idle_count.load(Ordering::SeqCst), 0);
+
+    // get the registry client-side
+    let client_display = client.display_id();
+    client
+        .send_request(
+            message!(
+                client_display.clone(),
+                1,
+                [Argument::NewId(client_backend::ObjectId::null())],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+
+    // a request is waiting to be dispatched
+    server.dispatch_all_clients(&mut ()).unwrap();
+    client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.flush(None).unwrap();
+    assert_eq!(idle_count.load(Ordering::SeqCst), 0);
+
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    assert_eq!(idle_count.load(Ordering::SeqCst), 1);
+
+    // no new activity
+    server.flush(None).unwrap();
+    assert_eq!(idle_count.load(Ordering::SeqCst), 1);
+});
@@ -0,0 +1,597 @@
+use std::{
+    ffi::CString,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use std::os::unix::io::BorrowedFd;
+
+use crate::protocol::{same_interface, BorrowedArgument, BorrowedMessage, Message};
+
+use super::*;
+
+struct ServerData {
+    requests: AtomicUsize,
+    last_uint: AtomicU32,
+}
+
+struct Interceptor(AtomicUsize);
+
+macro_rules! serverdata_impls {
+    ($server_backend:tt) => {
+        impl $server_backend::ObjectData<()> for ServerData {
+            fn request(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                msg: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $server_backend::ObjectData<()>>> {
+                if let Argument::Uint(u) = msg.args[0] {
+                    self.last_uint.store(u, Ordering::SeqCst);
+                }
+                self.requests.fetch_add(1, Ordering::SeqCst);
+                None
+            }
+
+            fn destroyed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::ObjectId,
+            ) {
+            }
+        }
+
+        impl $server_backend::GlobalHandler<()> for ServerData {
+            fn bind(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::GlobalId,
+                _: $server_backend::ObjectId,
+            ) -> Arc<dyn $server_backend::ObjectData<()>> {
+                self
+            }
+        }
+
+        impl $server_backend::Interceptor for Interceptor {
+            fn intercept(
+                &self,
+                _: &$server_backend::Handle,
+                _: $server_backend::ClientId,
+                mut msg: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Message<$server_backend::ObjectId, OwnedFd>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                if msg.opcode != 0 {
+                    return Some(msg);
+                }
+                match msg.args[0] {
+                    // consume the request
+                    Argument::Uint(0) => None,
+                    // rewrite the request
+                    Argument::Uint(1) => {
+                        msg.args[0] = Argument::Uint(2);
+                        Some(msg)
+                    }
+                    _ => Some(msg),
+                }
+            }
+        }
+    };
+}
+
+serverdata_impls!(server_rs);
+serverdata_impls!(server_sys);
+
+struct ClientData(AtomicUsize);
+
+// Vetoes the `many_args` messages whose first argument is `value`
+struct Veto {
+    value: u32,
+    seen: AtomicUsize,
+}
+
+impl Veto {
+    fn check(&self, opcode: u16, first_uint: Option<u32>) -> bool {
+        self.seen.fetch_add(1, Ordering::SeqCst);
+        !(opcode == 0 && first_uint == Some(self.value))
+    }
+
+    fn check_owned<Id, Fd>(&self, msg: &Message<Id, Fd>) -> bool {
+        match msg.args.first() {
+            Some(Argument::Uint(u)) => self.check(msg.opcode, Some(*u)),
+            _ => self.check(msg.opcode, None),
+        }
+    }
+
+    fn check_borrowed<Id, Fd>(&self, msg: &BorrowedMessage<'_, Id, Fd>) -> bool {
+        match msg.args.first() {
+            Some(BorrowedArgument::Uint(u)) => self.check(msg.opcode, Some(*u)),
+            _ => self.check(msg.opcode, None),
+        }
+    }
+}
+
+macro_rules! server_veto_impls {
+    ($server_backend:tt) => {
+        impl $server_backend::Interceptor for Veto {
+            fn intercept(
+                &self,
+                _: &$server_backend::Handle,
+                _: $server_backend::ClientId,
+                msg: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Message<$server_backend::ObjectId, OwnedFd>> {
+                self.check_owned(&msg).then_some(msg)
+            }
+
+            fn event(
+                &self,
+                _: $server_backend::ClientId,
+                msg: &Message<$server_backend::ObjectId, BorrowedFd<'_>>,
+            ) -> bool {
+                self.check_owned(msg)
+            }
+        }
+    };
+}
+
+server_veto_impls!(server_rs);
+server_veto_impls!(server_sys);
+
+macro_rules! client_veto_impls {
+    ($client_backend:tt) => {
+        impl $client_backend::ObjectData for ClientData {
+            fn event(
+                self: Arc<Self>,
+                _: &$client_backend::Backend,
+                _: Message<$client_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                None
+            }
+
+            fn destroyed(&self, _: $client_backend::ObjectId) {}
+        }
+
+        impl $client_backend::Interceptor for Veto {
+            fn request(&self, msg: &Message<$client_backend::ObjectId, BorrowedFd<'_>>) -> bool {
+                self.check_owned(msg)
+            }
+
+            fn event(&self, msg: &BorrowedMessage<'_, $client_backend::ObjectId, OwnedFd>) -> bool {
+                self.check_borrowed(msg)
+            }
+        }
+    };
+}
+
+client_veto_impls!(client_rs);
+client_veto_impls!(client_sys);
+
+macro_rules! many_args {
+    ($id:expr, $value:expr) => {
+        message!(
+            $id,
+            0,
+            [
+                Argument::Uint($value),
+                Argument::Int(0),
+                Argument::Fixed(0),
+                Argument::Array(Box::default()),
+                Argument::Str(Some(Box::new(CString::new("foo".as_bytes()).unwrap()))),
+                Argument::Fd(rustix::stdio::stdin()),
+            ],
+        )
+    };
+}
+
+// the interceptor can consume, rewrite or pass through requests before they are dispatched
+expand_test!(request_interceptor, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let server_data =
+        Arc::new(ServerData { requests: AtomicUsize::new(0), last_uint: AtomicU32::new(0) });
+    let interceptor = Arc::new(Interceptor(AtomicUsize::new(0)));
+    server.handle().create_global(&interfaces::TEST_GLOBAL_INTERFACE, 1, server_data.clone());
+
+    // get the registry client-side
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    // create the test global
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    server.set_interceptor(Some(interceptor.clone()));
+
+    client.send_request(many_args!(test_global_id.clone(), 0), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(interceptor.0.load(Ordering::SeqCst), 1);
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 0);
+
+    client.send_request(many_args!(test_global_id.clone(), 1), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(interceptor.0.load(Ordering::SeqCst), 2);
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 1);
+    assert_eq!(server_data.last_uint.load(Ordering::SeqCst), 2);
+
+    client.send_request(many_args!(test_global_id.clone(), 5), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(interceptor.0.load(Ordering::SeqCst), 3);
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 2);
+    assert_eq!(server_data.last_uint.load(Ordering::SeqCst), 5);
+
+    // once removed, requests reach the object untouched
+    server.handle().set_interceptor(None);
+    client.send_request(many_args!(test_global_id, 0), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(interceptor.0.load(Ordering::SeqCst), 3);
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 3);
+});
+
+// both backends can observe and veto the messages in both directions
+expand_test!(message_interceptors, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let server_data =
+        Arc::new(ServerData { requests: AtomicUsize::new(0), last_uint: AtomicU32::new(0) });
+    let client_data = Arc::new(ClientData(AtomicUsize::new(0)));
+    server.handle().create_global(&interfaces::TEST_GLOBAL_INTERFACE, 1, server_data.clone());
+
+    // get the registry client-side
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    // create the test global
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(client_data.clone()),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    let server_global_id = server
+        .handle()
+        .object_for_protocol_id(
+            client_id,
+            &interfaces::TEST_GLOBAL_INTERFACE,
+            test_global_id.protocol_id(),
+        )
+        .unwrap();
+
+    let client_veto = Arc::new(Veto { value: 0, seen: AtomicUsize::new(0) });
+    let server_veto = Arc::new(Veto { value: 1, seen: AtomicUsize::new(0) });
+    client.set_interceptor(Some(client_veto.clone()));
+    server.handle().set_interceptor(Some(server_veto.clone()));
+
+    // requests vetoed by the client are never sent
+    client.send_request(many_args!(test_global_id.clone(), 0), None, None).unwrap();
+    client.send_request(many_args!(test_global_id.clone(), 1), None, None).unwrap();
+    client.send_request(many_args!(test_global_id.clone(), 2), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(client_veto.seen.load(Ordering::SeqCst), 3);
+    assert_eq!(server_veto.seen.load(Ordering::SeqCst), 2);
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 1);
+    assert_eq!(server_data.last_uint.load(Ordering::SeqCst), 2);
+
+    // events vetoed by the server are never sent
+    server.handle().send_event(many_args!(server_global_id.clone(), 0)).unwrap();
+    server.handle().send_event(many_args!(server_global_id.clone(), 1)).unwrap();
+    server.handle().send_event(many_args!(server_global_id, 2)).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+    assert_eq!(server_veto.seen.load(Ordering::SeqCst), 5);
+    // the client also sees the pending wl_registry.global event
+    assert_eq!(client_veto.seen.load(Ordering::SeqCst), 6);
+    assert_eq!(client_data.0.load(Ordering::SeqCst), 1);
+
+    // once removed, all messages go through
+    client.set_interceptor(None);
+    server.handle().set_interceptor(None);
+    client.send_request(many_args!(test_global_id, 1), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 2);
+    assert_eq!(server_data.last_uint.load(Ordering::SeqCst), 1);
+});
+
+// Records the sequence numbers of the `many_args` messages it lets through, vetoing the ones whose
+// first argument is 0
+#[derive(Default)]
+struct Trace {
+    requests: Mutex<Vec<(u64, u32)>>,
+    events: Mutex<Vec<(u64, u32)>>,
+}
+
+impl Trace {
+    fn record(
+        &self,
+        log: &Mutex<Vec<(u64, u32)>>,
+        seq: u64,
+        opcode: u16,
+        first_uint: Option<u32>,
+    ) -> bool {
+        match (opcode, first_uint) {
+            (0, Some(0)) => false,
+            (0, Some(value)) => {
+                log.lock().unwrap().push((seq, value));
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+impl client_rs::Interceptor for Trace {
+    fn request_sequenced(
+        &self,
+        seq: u64,
+        msg: &Message<client_rs::ObjectId, BorrowedFd<'_>>,
+    ) -> bool {
+        if !same_interface(msg.sender_id.interface(), &interfaces::TEST_GLOBAL_INTERFACE) {
+            return true;
+        }
+        let first_uint = match msg.args.first() {
+            Some(Argument::Uint(u)) => Some(*u),
+            _ => None,
+        };
+        self.record(&self.requests, seq, msg.opcode, first_uint)
+    }
+
+    fn event_sequenced(
+        &self,
+        seq: u64,
+        msg: &BorrowedMessage<'_, client_rs::ObjectId, OwnedFd>,
+    ) -> bool {
+        if !same_interface(msg.sender_id.interface(), &interfaces::TEST_GLOBAL_INTERFACE) {
+            return true;
+        }
+        let first_uint = match msg.args.first() {
+            Some(BorrowedArgument::Uint(u)) => Some(*u),
+            _ => None,
+        };
+        self.record(&self.events, seq, msg.opcode, first_uint)
+    }
+}
+
+impl server_rs::Interceptor for Trace {
+    fn intercept_sequenced(
+        &self,
+        _: &server_rs::Handle,
+        _: server_rs::ClientId,
+        seq: u64,
+        msg: Message<server_rs::ObjectId, OwnedFd>,
+    ) -> Option<Message<server_rs::ObjectId, OwnedFd>> {
+        let first_uint = match msg.args.first() {
+            Some(Argument::Uint(u)) => Some(*u),
+            _ => None,
+        };
+        self.record(&self.requests, seq, msg.opcode, first_uint).then_some(msg)
+    }
+
+    fn event_sequenced(
+        &self,
+        _: server_rs::ClientId,
+        seq: u64,
+        msg: &Message<server_rs::ObjectId, BorrowedFd<'_>>,
+    ) -> bool {
+        let first_uint = match msg.args.first() {
+            Some(Argument::Uint(u)) => Some(*u),
+            _ => None,
+        };
+        self.record(&self.events, seq, msg.opcode, first_uint)
+    }
+}
+
+// the sequence numbers seen on both sides of a connection match, the system backends only number
+// the messages going through this crate and are not checked
+#[test]
+fn sequence_numbers() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    let server_data =
+        Arc::new(ServerData { requests: AtomicUsize::new(0), last_uint: AtomicU32::new(0) });
+    let client_data = Arc::new(ClientData(AtomicUsize::new(0)));
+    server.handle().create_global(&interfaces::TEST_GLOBAL_INTERFACE, 1, server_data.clone());
+
+    let client_trace = Arc::new(Trace::default());
+    let server_trace = Arc::new(Trace::default());
+    client.set_interceptor(Some(client_trace.clone()));
+    server.handle().set_interceptor(Some(server_trace.clone()));
+
+    // requests 1 and 2, the registry and the global
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(client_data.clone()),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    // a vetoed request does not consume its number
+    client.send_request(many_args!(test_global_id.clone(), 0), None, None).unwrap();
+    client.send_request(many_args!(test_global_id.clone(), 1), None, None).unwrap();
+    client.send_request(many_args!(test_global_id.clone(), 2), None, None).unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert_eq!(*client_trace.requests.lock().unwrap(), [(3, 1), (4, 2)]);
+    assert_eq!(*server_trace.requests.lock().unwrap(), [(3, 1), (4, 2)]);
+    assert_eq!(server_data.requests.load(Ordering::SeqCst), 2);
+
+    // event 1 is the wl_registry.global sent by the backend itself
+    let server_global_id = server
+        .handle()
+        .object_for_protocol_id(
+            client_id,
+            &interfaces::TEST_GLOBAL_INTERFACE,
+            test_global_id.protocol_id(),
+        )
+        .unwrap();
+    server.handle().send_event(many_args!(server_global_id.clone(), 0)).unwrap();
+    server.handle().send_event(many_args!(server_global_id.clone(), 1)).unwrap();
+    server.handle().send_event(many_args!(server_global_id, 2)).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+    assert_eq!(*server_trace.events.lock().unwrap(), [(2, 1), (3, 2)]);
+    assert_eq!(*client_trace.events.lock().unwrap(), [(2, 1), (3, 2)]);
+    assert_eq!(client_data.0.load(Ordering::SeqCst), 2);
+}
+
+// queries the objects while the client is sending a request
+#[derive(Default)]
+struct Queries {
+    backend: Mutex<Option<client_rs::WeakBackend>>,
+    versions: Mutex<Vec<u32>>,
+}
+
+impl client_rs::Interceptor for Queries {
+    fn request(&self, msg: &Message<client_rs::ObjectId, BorrowedFd<'_>>) -> bool {
+        let backend = self.backend.lock().unwrap().as_ref().unwrap().upgrade().unwrap();
+        let info = backend.info(msg.sender_id.clone()).unwrap();
+        backend.get_data(msg.sender_id.clone()).unwrap();
+        self.versions.lock().unwrap().push(info.version);
+        true
+    }
+}
+
+// the objects can be queried without waiting for the sending or dispatching of messages
+#[test]
+fn queries_while_sending() {
+    let (tx, _rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+    let queries = Arc::new(Queries::default());
+    *queries.backend.lock().unwrap() = Some(client.downgrade());
+    client.set_interceptor(Some(queries.clone()));
+
+    client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    assert_eq!(*queries.versions.lock().unwrap(), [1]);
+}
+
+// queries the objects while the server is sending an event
+#[derive(Default)]
+struct ServerQueries {
+    handle: Mutex<Option<server_rs::WeakHandle>>,
+    versions: Mutex<Vec<u32>>,
+}
+
+impl server_rs::Interceptor for ServerQueries {
+    fn event(
+        &self,
+        _: server_rs::ClientId,
+        msg: &Message<server_rs::ObjectId, BorrowedFd<'_>>,
+    ) -> bool {
+        let handle = self.handle.lock().unwrap().as_ref().unwrap().upgrade().unwrap();
+        let info = handle.object_info(msg.sender_id.clone()).unwrap();
+        handle.get_object_data::<()>(msg.sender_id.clone()).unwrap();
+        handle.get_object_data_any(msg.sender_id.clone()).unwrap();
+        self.versions.lock().unwrap().push(info.version);
+        true
+    }
+}
+
+// the rust server can also be queried while it is internally locked
+#[test]
+fn server_queries_while_sending() {
+    let (_tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let queries = Arc::new(ServerQueries::default());
+    *queries.handle.lock().unwrap() = Some(server.handle().downgrade());
+    server.set_interceptor(Some(queries.clone()));
+
+    let object_id = server
+        .handle()
+        .create_object::<()>(
+            client_id,
+            &interfaces::TEST_GLOBAL_INTERFACE,
+            3,
+            Arc::new(DoNothingData),
+        )
+        .unwrap();
+    server.handle().send_event(many_args!(object_id, 0)).unwrap();
+    assert_eq!(*queries.versions.lock().unwrap(), [3]);
+}
@@ -0,0 +1,34 @@
+use std::{io::Write, sync::Mutex};
+
+use crate::LogLevel;
+
+use super::*;
+
+// the messages of the backend reach the log handler instead of the default output
+#[test]
+fn log_handler() {
+    let (mut tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let sink = messages.clone();
+    crate::set_log_handler(Some(Arc::new(move |level, text: &str| {
+        sink.lock().unwrap().push((level, text.to_owned()));
+    })));
+
+    // a request to an object that does not exist, the client is killed
+    let mut request = Vec::new();
+    request.extend_from_slice(&42u32.to_ne_bytes());
+    request.extend_from_slice(&(8u32 << 16).to_ne_bytes());
+    tx.write_all(&request).unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+
+    crate::set_log_handler(None);
+    // other tests may log concurrently
+    assert!(messages
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|(level, text)| *level == LogLevel::Warn && text.contains("Killing client")));
+}
@@ -3,7 +3,7 @@ use std::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
-use crate::protocol::Message;
+use crate::protocol::{BorrowedArgument, BorrowedMessage, Message};
 
 use super::*;
 
@@ -71,7 +71,7 @@ macro_rules! serverdata_impls {
                             Argument::Fixed(9823),
                             Argument::Array(Box::new(vec![10, 20, 30, 40, 50, 60, 70, 80, 90])),
                             Argument::Str(Some(Box::new(CString::new("I want cake".as_bytes()).unwrap()))),
-                            Argument::Fd(1), // stdout
+                            Argument::Fd(rustix::stdio::stdout()),
                         ],
                     ))
                     .unwrap();
@@ -184,7 +184,201 @@ expand_test!(many_args, {
                     Argument::Str(Some(Box::new(
                         CString::new("I like trains".as_bytes()).unwrap()
                     ))),
-                    Argument::Fd(0), // stdin
+                    Argument::Fd(rustix::stdio::stdin()),
+                ],
+            ),
+            None,
+            None,
+        )
+        .unwrap();
+    client.flush().unwrap();
+
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    assert!(server_data.0.load(Ordering::SeqCst));
+});
+
+struct BorrowedServerData(AtomicBool);
+
+macro_rules! borrowed_serverdata_impls {
+    ($server_backend:tt) => {
+        impl $server_backend::ObjectData<()> for BorrowedServerData {
+            fn request(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: Message<$server_backend::ObjectId, OwnedFd>
+            )
+                -> Option<Arc<dyn $server_backend::ObjectData<()>>>
+            {
+                panic!("Requests should be dispatched borrowed")
+            }
+
+            fn request_borrowed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                msg: BorrowedMessage<'_, $server_backend::ObjectId, OwnedFd>
+            )
+                -> Option<Arc<dyn $server_backend::ObjectData<()>>>
+            {
+                assert_eq!(msg.opcode, 0);
+                if let [BorrowedArgument::Uint(42), BorrowedArgument::Int(-13), BorrowedArgument::Fixed(4589), BorrowedArgument::Array(a), BorrowedArgument::Str(Some(s)), BorrowedArgument::Fd(_)] =
+                    &msg.args[..]
+                {
+                    assert_eq!(*a, &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+                    assert_eq!(*s, CStr::from_bytes_with_nul(b"I like trains\0").unwrap());
+                } else {
+                    panic!("Bad argument list !")
+                }
+                self.0.store(true, Ordering::SeqCst);
+                None
+            }
+
+            fn destroyed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::ObjectId
+            ) {
+            }
+        }
+
+        impl $server_backend::GlobalHandler<()> for BorrowedServerData {
+            fn bind(
+                self: Arc<Self>,
+                handle: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::GlobalId,
+                object_id: $server_backend::ObjectId,
+            ) -> Arc<dyn $server_backend::ObjectData<()>> {
+                handle
+                    .send_event(message!(
+                        object_id,
+                        0,
+                        [
+                            Argument::Uint(1337),
+                            Argument::Int(-53),
+                            Argument::Fixed(9823),
+                            Argument::Array(Box::new(vec![10, 20, 30, 40, 50, 60, 70, 80, 90])),
+                            Argument::Str(Some(Box::new(CString::new("I want cake".as_bytes()).unwrap()))),
+                            Argument::Fd(rustix::stdio::stdout()),
+                        ],
+                    ))
+                    .unwrap();
+                self
+            }
+        }
+    }
+}
+
+borrowed_serverdata_impls!(server_rs);
+borrowed_serverdata_impls!(server_sys);
+
+struct BorrowedClientData(AtomicBool);
+
+macro_rules! borrowed_clientdata_impls {
+    ($client_backend:tt) => {
+        impl $client_backend::ObjectData for BorrowedClientData {
+            fn event(
+                self: Arc<Self>,
+                _handle: & $client_backend::Backend,
+                _: Message<$client_backend::ObjectId, OwnedFd>
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                panic!("Events should be dispatched borrowed")
+            }
+            fn event_borrowed(
+                self: Arc<Self>,
+                _handle: & $client_backend::Backend,
+                msg: BorrowedMessage<'_, $client_backend::ObjectId, OwnedFd>
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                assert_eq!(msg.opcode, 0);
+                if let [BorrowedArgument::Uint(1337), BorrowedArgument::Int(-53), BorrowedArgument::Fixed(9823), BorrowedArgument::Array(a), BorrowedArgument::Str(Some(s)), BorrowedArgument::Fd(_)] =
+                    &msg.args[..]
+                {
+                    assert_eq!(*a, &[10, 20, 30, 40, 50, 60, 70, 80, 90]);
+                    assert_eq!(*s, CStr::from_bytes_with_nul(b"I want cake\0").unwrap());
+                } else {
+                    panic!("Bad argument list !")
+                }
+                self.0.store(true, Ordering::SeqCst);
+                None
+            }
+            fn destroyed(&self, _object_id: $client_backend::ObjectId) {}
+        }
+    }
+}
+
+borrowed_clientdata_impls!(client_rs);
+borrowed_clientdata_impls!(client_sys);
+
+// same as many_args, but with object data handling the messages without copying them
+expand_test!(many_args_borrowed, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let server_data = Arc::new(BorrowedServerData(AtomicBool::new(false)));
+    let client_data = Arc::new(BorrowedClientData(AtomicBool::new(false)));
+
+    // Prepare a global
+    server.handle().create_global(&interfaces::TEST_GLOBAL_INTERFACE, 1, server_data.clone());
+
+    // get the registry client-side
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    // create the test global
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(client_data.clone()),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+    assert!(client_data.0.load(Ordering::SeqCst));
+
+    // send the many_args request
+    client
+        .send_request(
+            message!(
+                test_global_id,
+                0,
+                [
+                    Argument::Uint(42),
+                    Argument::Int(-13),
+                    Argument::Fixed(4589),
+                    Argument::Array(Box::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9])),
+                    Argument::Str(Some(Box::new(
+                        CString::new("I like trains".as_bytes()).unwrap()
+                    ))),
+                    Argument::Fd(rustix::stdio::stdin()),
                 ],
             ),
             None,
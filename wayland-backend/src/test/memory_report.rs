@@ -0,0 +1,58 @@
+use super::*;
+
+// the objects appear in the report of the client while they are alive
+expand_test!(memory_report, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let before = client.memory_report();
+    let client_display = client.display_id();
+    for _ in 0..3 {
+        client
+            .send_request(
+                message!(
+                    client_display.clone(),
+                    0,
+                    [Argument::NewId(client_backend::ObjectId::null())]
+                ),
+                Some(Arc::new(DoNothingData)),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+    let report = client.memory_report();
+    assert_eq!(report.objects, before.objects + 3);
+    assert!(report.memory >= report.objects_memory);
+
+    // the callbacks are destroyed once done
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+    let report = client.memory_report();
+    assert_eq!(report.objects, before.objects);
+    assert_eq!(report.queued_messages, 0);
+});
+
+// the clients appear in the report of the server while they are connected
+expand_test!(server_memory_report, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    assert_eq!(server.memory_report(), server_backend::MemoryReport::default());
+
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    let report = server.memory_report();
+    assert_eq!(report.clients, 1);
+    assert_eq!(
+        report.objects,
+        server.handle().get_client_resource_usage(client_id.clone()).unwrap().objects
+    );
+    assert!(report.memory > 0);
+
+    server.handle().kill_client(client_id, server_backend::DisconnectReason::ConnectionClosed);
+    assert_eq!(server.memory_report().clients, 0);
+    drop(client);
+});
@@ -0,0 +1,140 @@
+use super::*;
+
+// a wl_display.sync request and its answer are counted by the client
+expand_test!(metrics_messages, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+
+    // the server side of the exchange is handled by libwayland on the system backend
+    let metrics = client.metrics();
+    assert_eq!(metrics.messages_out, 1);
+    // wl_callback.done, followed by wl_display.delete_id which libwayland handles by itself
+    assert!(metrics.messages_in >= 1);
+    assert_eq!(metrics.fds_out + metrics.fds_in, 0);
+});
+
+#[test]
+fn metrics_bytes() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_rs::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+
+    let client_metrics = client.metrics();
+    let server_metrics = server.metrics();
+    assert_eq!(client_metrics.messages_out, 1);
+    assert_eq!(server_metrics.messages_in, 1);
+    // the header and the new id of the request
+    assert_eq!(client_metrics.bytes_out, 12);
+    assert_eq!(server_metrics.bytes_in, 12);
+    // wl_callback.done and wl_display.delete_id, with one argument each
+    assert_eq!(server_metrics.messages_out, 2);
+    assert_eq!(server_metrics.bytes_out, 24);
+    assert_eq!(client_metrics.messages_in, 2);
+    assert_eq!(client_metrics.bytes_in, 24);
+    // the incoming and outgoing buffers of each socket
+    assert_eq!(client_metrics.buffer_allocations, 2);
+    assert_eq!(server_metrics.buffer_allocations, 2);
+}
+
+// once warm, the pool of the rust backend provides the buffers to decode the requests
+#[test]
+fn metrics_message_pool() {
+    use std::ffi::CString;
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        1,
+        Arc::new(DoNothingData),
+    );
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(1),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    let before = server.metrics();
+
+    for _ in 0..10 {
+        // 6 arguments, more than fit inline
+        client
+            .send_request(
+                message!(
+                    test_global_id.clone(),
+                    0,
+                    [
+                        Argument::Uint(42),
+                        Argument::Int(-13),
+                        Argument::Fixed(4242),
+                        Argument::Array(Box::new(vec![1, 2, 3])),
+                        Argument::Str(Some(Box::new(CString::new("I like trains").unwrap()))),
+                        Argument::Fd(rustix::stdio::stdout()),
+                    ],
+                ),
+                None,
+                None,
+            )
+            .unwrap();
+        client.flush().unwrap();
+        server.dispatch_all_clients(&mut ()).unwrap();
+    }
+
+    let after = server.metrics();
+    assert_eq!(after.messages_in - before.messages_in, 10);
+    // only the argument list of the first request was allocated
+    assert_eq!(after.pool_allocations - before.pool_allocations, 1);
+    // a scratch buffer for each dispatch, and the argument list of the 9 other requests
+    assert!(after.pool_reuses - before.pool_reuses >= 19);
+}
@@ -1,6 +1,6 @@
 #![allow(dead_code, non_snake_case)]
 
-use std::os::fd::OwnedFd;
+use std::os::fd::{BorrowedFd, OwnedFd};
 use std::sync::Arc;
 
 use crate::protocol::{Argument, Message};
@@ -55,12 +55,46 @@ mod interfaces {
     );
 }
 
+mod all_objects;
+mod buffer_size;
+mod callback_panic;
+mod cloexec;
+mod concurrent_read;
+mod connect_fd;
+mod credentials;
+mod data_as;
+#[cfg(feature = "debug-checks")]
+mod debug_checks;
+mod debug_sink;
 mod destructors;
+mod disconnect_reason;
+mod dispatch_limit;
+mod event_loop;
+mod external_proxies;
+mod foreign_globals;
+mod guest;
+mod handshake;
+mod idle;
+mod interceptor;
+mod log_handler;
 mod many_args;
+mod memory_report;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod object_args;
+mod object_ids;
+mod object_limit;
+mod poll_fd;
 mod protocol_error;
+#[cfg(feature = "protocol_xml")]
+mod protocol_xml;
+#[cfg(feature = "libwayland_1_18")]
+mod proxy_tags;
+mod runtime_interfaces;
 mod server_created_objects;
 mod sync;
+mod wakeup;
+mod weak_backend;
 
 /*
  * Assertion of Send/Sync for all relevant objects
@@ -96,6 +130,12 @@ fn send_sync_server_sys() {
     ensure_both::<server_sys::ClientId>();
 }
 
+// whether reading from a file descriptor would not block
+fn is_readable(fd: BorrowedFd) -> bool {
+    let mut fds = [rustix::event::PollFd::new(&fd, rustix::event::PollFlags::IN)];
+    rustix::event::poll(&mut fds, 0).unwrap() > 0
+}
+
 /*
  * A "do nothing" data as a helper
  */
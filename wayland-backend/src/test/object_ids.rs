@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::protocol::{AllowNull, ArgumentType, BorrowedArgument};
+use crate::rs::socket::{BufferedSocket, Socket};
+
+use super::sync::CountData;
+use super::*;
+
+// the ids of destroyed objects are reused, so that creating many short-lived objects never exhausts
+// the namespace
+expand_test!(short_lived_objects_reuse_ids, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let count_data = Arc::new(CountData(AtomicUsize::new(0)));
+    let mut max_id = 0;
+    // the answers of a batch fit in the buffers of libwayland
+    for batch in 1..=1000 {
+        for _ in 0..100 {
+            let id = client
+                .send_request(
+                    message!(
+                        client.display_id(),
+                        0,
+                        [Argument::NewId(client_backend::ObjectId::null())]
+                    ),
+                    Some(count_data.clone()),
+                    Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+                )
+                .unwrap();
+            max_id = max_id.max(id.protocol_id());
+        }
+        client.flush().unwrap();
+        server.dispatch_all_clients(&mut ()).unwrap();
+        server.flush(None).unwrap();
+        while count_data.0.load(Ordering::SeqCst) < batch * 100 {
+            client.prepare_read().unwrap().read().unwrap();
+        }
+    }
+
+    // the display and a single batch of callbacks were alive at once
+    assert!(max_id <= 101, "max id: {}", max_id);
+});
+
+// write a wl_display.sync request creating an object with the first id of the server namespace,
+// libwayland does not allow to choose the id
+fn sync_with_server_id(socket: &mut BufferedSocket) {
+    socket
+        .write_message(&Message {
+            sender_id: 1, // wl_display
+            opcode: 0,    // sync
+            args: smallvec::smallvec![Argument::NewId(0xFF00_0000)],
+        })
+        .unwrap();
+    socket.flush().unwrap();
+}
+
+// a client creating an object with an id of the server namespace is killed
+expand_test!(client_id_in_server_namespace, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+
+    let mut socket = BufferedSocket::new(Socket::from(tx));
+    sync_with_server_id(&mut socket);
+    let _ = server.dispatch_all_clients(&mut ());
+
+    assert!(!server.handle().is_client_alive(client_id));
+});
+
+// libwayland disconnects the client without an error, only the rust backend posts one
+#[test]
+fn client_id_in_server_namespace_error() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+
+    let mut socket = BufferedSocket::new(Socket::from(tx));
+    sync_with_server_id(&mut socket);
+    let _ = server.dispatch_all_clients(&mut ());
+    let _ = server.flush(None);
+
+    // the server posted a wl_display.invalid_object error
+    socket.fill_incoming_buffers().unwrap();
+    let error = socket
+        .find_message(
+            1,
+            0,
+            &[
+                ArgumentType::Object(AllowNull::No),
+                ArgumentType::Uint,
+                ArgumentType::Str(AllowNull::No),
+            ],
+            &mut Vec::new(),
+        )
+        .map(|msg| matches!(msg.args[1], BorrowedArgument::Uint(0)));
+    assert_eq!(error, Some(true));
+}
@@ -0,0 +1,113 @@
+use std::{
+    ffi::CString,
+    sync::{Arc, Mutex},
+};
+
+use super::*;
+
+struct ServerData;
+
+impl server_rs::GlobalHandler<()> for ServerData {
+    fn bind(
+        self: Arc<Self>,
+        _: &server_rs::Handle,
+        _: &mut (),
+        _: server_rs::ClientId,
+        _: server_rs::GlobalId,
+        _: server_rs::ObjectId,
+    ) -> Arc<dyn server_rs::ObjectData<()>> {
+        self
+    }
+}
+
+impl server_rs::ObjectData<()> for ServerData {
+    fn request(
+        self: Arc<Self>,
+        _: &server_rs::Handle,
+        _: &mut (),
+        _: server_rs::ClientId,
+        msg: Message<server_rs::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn server_rs::ObjectData<()>>> {
+        msg.args.iter().any(|arg| matches!(arg, Argument::NewId(_))).then_some(self)
+    }
+
+    fn destroyed(
+        self: Arc<Self>,
+        _: &server_rs::Handle,
+        _: &mut (),
+        _: server_rs::ClientId,
+        _: server_rs::ObjectId,
+    ) {
+    }
+}
+
+#[test]
+fn max_client_objects() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(ServerData),
+    );
+
+    // allow a single object beyond the limit
+    let counts = Arc::new(Mutex::new(Vec::new()));
+    let counts2 = counts.clone();
+    server.set_max_client_objects(Some(4));
+    server.set_object_limit_callback(Some(Arc::new(move |_, count| {
+        counts2.lock().unwrap().push(count);
+        count < 5
+    })));
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let test_global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(interfaces::TEST_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(3),
+                    Argument::NewId(client_rs::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::TEST_GLOBAL_INTERFACE, 3)),
+        )
+        .unwrap();
+    for _ in 0..3 {
+        client
+            .send_request(
+                message!(test_global_id.clone(), 2, [Argument::NewId(client_rs::ObjectId::null())]),
+                Some(Arc::new(DoNothingData)),
+                Some((&interfaces::TERTIARY_INTERFACE, 3)),
+            )
+            .unwrap();
+    }
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    // the client owns its display, registry, global and first tertiary when reaching the limit
+    assert_eq!(*counts.lock().unwrap(), [4, 5]);
+    match client.prepare_read().unwrap().read() {
+        Err(client_rs::WaylandError::Protocol(err)) => {
+            assert_eq!(err.code, 2);
+            assert_eq!(err.object_id, 1);
+            assert_eq!(err.object_interface, "wl_display");
+        }
+        ret => panic!("Bad ret: {:?}", ret),
+    }
+}
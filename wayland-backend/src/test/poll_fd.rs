@@ -0,0 +1,69 @@
+use super::*;
+
+// the poll fd of the server covers all clients, as they connect and disconnect
+expand_test!(poll_fd_all_clients, {
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    assert!(!is_readable(server.poll_fd()));
+
+    let (tx1, rx1) = std::os::unix::net::UnixStream::pair().unwrap();
+    let (tx2, rx2) = std::os::unix::net::UnixStream::pair().unwrap();
+    server.handle().insert_client(rx1, Arc::new(())).unwrap();
+    server.handle().insert_client(rx2, Arc::new(())).unwrap();
+    let client1 = client_backend::Backend::connect(tx1).unwrap();
+    let client2 = client_backend::Backend::connect(tx2).unwrap();
+    assert!(!is_readable(server.poll_fd()));
+
+    // a request of the second client wakes the server up
+    client2
+        .send_request(
+            message!(client2.display_id(), 0, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client2.flush().unwrap();
+    assert!(is_readable(server.poll_fd()));
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert!(!is_readable(server.poll_fd()));
+
+    // and so does the disconnection of the first one
+    drop(client1);
+    assert!(is_readable(server.poll_fd()));
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert!(!is_readable(server.poll_fd()));
+});
+
+// the poll fd is level-triggered, it stays readable until every ready client is dispatched
+//
+// only on the rust backend, where clients can be dispatched separately
+#[test]
+fn poll_fd_level_triggered() {
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+
+    let (tx1, rx1) = std::os::unix::net::UnixStream::pair().unwrap();
+    let (tx2, rx2) = std::os::unix::net::UnixStream::pair().unwrap();
+    let client1_id = server.handle().insert_client(rx1, Arc::new(())).unwrap();
+    server.handle().insert_client(rx2, Arc::new(())).unwrap();
+    let client1 = client_rs::Backend::connect(tx1).unwrap();
+    let client2 = client_rs::Backend::connect(tx2).unwrap();
+
+    for client in [&client1, &client2] {
+        client
+            .send_request(
+                message!(client.display_id(), 0, [Argument::NewId(client_rs::ObjectId::null())]),
+                Some(Arc::new(DoNothingData)),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+        client.flush().unwrap();
+    }
+    // polling does not consume the readiness
+    assert!(is_readable(server.poll_fd()));
+    assert!(is_readable(server.poll_fd()));
+
+    // the second client is still ready once the first one is dispatched
+    server.dispatch_single_client(&mut (), client1_id).unwrap();
+    assert!(is_readable(server.poll_fd()));
+    server.dispatch_all_clients(&mut ()).unwrap();
+    assert!(!is_readable(server.poll_fd()));
+}
@@ -317,3 +317,230 @@ expand_test!(protocol_error_in_request_without_object_init, {
     // the server should not panic, and gracefull accept that the user did not provide any object data for
     // the already destroyed object
 });
+
+// the rust client reports the data it cannot decode as a wire error
+#[test]
+fn client_wire_error() {
+    use std::io::Write;
+
+    use crate::protocol::WireError;
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    // a wl_display.error event whose message is not nul-terminated
+    let words = [1, 24 << 16, 1, 0, 4, u32::from_ne_bytes(*b"oops")];
+    (&rx).write_all(&words.iter().flat_map(|w| w.to_ne_bytes()).collect::<Vec<u8>>()).unwrap();
+
+    match client.prepare_read().unwrap().read() {
+        Err(client_rs::WaylandError::Wire(err)) => {
+            assert_eq!(err, WireError::InvalidString { object_id: 1, opcode: 0, arg: 2 });
+        }
+        ret => panic!("Bad ret: {:?}", ret),
+    }
+    // the error is sticky
+    assert!(matches!(client.last_error(), Some(client_rs::WaylandError::Wire(_))));
+}
+
+expand_test!(server_disconnected, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    ::std::mem::drop(server);
+
+    match client.prepare_read().unwrap().read() {
+        Err(client_backend::WaylandError::Disconnected(None)) => {}
+        ret => panic!("Bad ret: {:?}", ret),
+    }
+    assert!(matches!(client.last_error(), Some(client_backend::WaylandError::Disconnected(None))));
+});
+
+#[test]
+fn client_disconnected_after_protocol_error() {
+    use std::io::Write;
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    // the server sends a wl_display.error event and closes the connection right away
+    let words = [1, 24 << 16, 1, 3, 4, u32::from_ne_bytes(*b"bye\0")];
+    (&rx).write_all(&words.iter().flat_map(|w| w.to_ne_bytes()).collect::<Vec<u8>>()).unwrap();
+    ::std::mem::drop(rx);
+
+    // the client notices while sending a request, before reading the error
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_rs::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    match client.flush() {
+        Err(client_rs::WaylandError::Disconnected(Some(err))) => {
+            assert_eq!(err.code, 3);
+            assert_eq!(err.object_id, 1);
+            assert_eq!(err.object_interface, "wl_display");
+            assert_eq!(err.message, "bye");
+        }
+        ret => panic!("Bad ret: {:?}", ret),
+    }
+}
+
+#[test]
+fn client_protocol_error_unknown_object() {
+    use std::io::Write;
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let client = client_rs::Backend::connect(tx).unwrap();
+
+    // a wl_display.error event about object 50, which the client does not know
+    let words = [1, 28 << 16, 50, 7, 5, u32::from_ne_bytes(*b"gone"), 0];
+    (&rx).write_all(&words.iter().flat_map(|w| w.to_ne_bytes()).collect::<Vec<u8>>()).unwrap();
+
+    match client.prepare_read().unwrap().read() {
+        Err(client_rs::WaylandError::Protocol(err)) => {
+            assert_eq!(err.code, 7);
+            assert_eq!(err.object_id, 50);
+            assert_eq!(err.object_interface, "");
+            assert_eq!(err.message, "gone");
+        }
+        ret => panic!("Bad ret: {:?}", ret),
+    }
+}
+
+// the code and message of the wl_display.error received by a client using a raw socket
+fn received_error(socket: &mut BufferedSocket) -> Option<(u32, String)> {
+    use crate::protocol::{AllowNull, ArgumentType, BorrowedArgument};
+
+    let _ = socket.fill_incoming_buffers();
+    let mut scratch = Vec::new();
+    let signature =
+        [ArgumentType::Object(AllowNull::No), ArgumentType::Uint, ArgumentType::Str(AllowNull::No)];
+    let msg = socket.find_message(1, 0, &signature, &mut scratch)?;
+    match msg.args[..] {
+        [_, BorrowedArgument::Uint(code), BorrowedArgument::Str(Some(message))] => {
+            Some((code, message.to_string_lossy().into_owned()))
+        }
+        _ => None,
+    }
+}
+
+// a raw client socket which bound the test global as object 3
+fn bind_test_global_raw(server: &mut server_rs::Backend<()>) -> BufferedSocket {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    server.handle().insert_client(rx, Arc::new(())).unwrap();
+    server.handle().create_global::<()>(
+        &interfaces::TEST_GLOBAL_INTERFACE,
+        3,
+        Arc::new(DoNothingData),
+    );
+
+    let mut socket = BufferedSocket::new(Socket::from(tx));
+    socket
+        .write_message(&message!(1, 1, [Argument::NewId(2)])) // wl_display.get_registry
+        .unwrap();
+    socket
+        .write_message(&message!(
+            2,
+            0, // wl_registry.bind
+            [
+                Argument::Uint(1),
+                Argument::Str(Some(Box::new(CString::new("test_global").unwrap()))),
+                Argument::Uint(3),
+                Argument::NewId(3),
+            ],
+        ))
+        .unwrap();
+    socket
+}
+
+// the errors posted by the rust server for malformed requests describe what is wrong with them
+#[test]
+fn server_unknown_opcode_error() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    server.handle().insert_client(rx, Arc::new(())).unwrap();
+
+    let mut socket = BufferedSocket::new(Socket::from(tx));
+    socket.write_message(&message!(1, 42, [])).unwrap();
+    socket.flush().unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+
+    assert_eq!(
+        received_error(&mut socket),
+        Some((1, "Unknown opcode 42 for wl_display@1.".into()))
+    );
+}
+
+#[test]
+fn server_invalid_string_error() {
+    use std::io::Write;
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    server.handle().insert_client(rx, Arc::new(())).unwrap();
+
+    // wl_display.get_registry, then a wl_registry.bind whose interface is not nul-terminated
+    let words = [1, 12 << 16 | 1, 2, 2, 28 << 16, 1, 4, u32::from_ne_bytes(*b"oops"), 1, 3];
+    (&tx).write_all(&words.iter().flat_map(|w| w.to_ne_bytes()).collect::<Vec<u8>>()).unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+
+    let mut socket = BufferedSocket::new(Socket::from(tx));
+    assert_eq!(
+        received_error(&mut socket),
+        Some((
+            1,
+            "Invalid arguments for wl_registry@2.bind(uint, string, uint, new_id): \
+             argument 1 is not a valid string."
+                .into()
+        ))
+    );
+}
+
+#[test]
+fn server_wrong_object_interface_error() {
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let mut socket = bind_test_global_raw(&mut server);
+    // test_global.link with the test global itself instead of a secondary
+    socket
+        .write_message(&message!(
+            3,
+            3,
+            [Argument::Object(3), Argument::Object(0), Argument::Uint(0)]
+        ))
+        .unwrap();
+    socket.flush().unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+
+    assert_eq!(
+        received_error(&mut socket),
+        Some((
+            0,
+            "Invalid arguments for test_global@3.link: argument 0 must be a secondary, but 3 is a \
+             test_global."
+                .into()
+        ))
+    );
+}
+
+#[test]
+fn server_null_object_error() {
+    let mut server = server_rs::Backend::<()>::new().unwrap();
+    let mut socket = bind_test_global_raw(&mut server);
+    // test_global.reverse_link with a null tertiary
+    socket.write_message(&message!(3, 5, [Argument::Object(0), Argument::Object(0)])).unwrap();
+    socket.flush().unwrap();
+    let _ = server.dispatch_all_clients(&mut ());
+
+    assert_eq!(
+        received_error(&mut socket),
+        Some((
+            0,
+            "Invalid arguments for test_global@3.reverse_link: argument 1 must be a tertiary, but is \
+             null."
+                .into()
+        ))
+    );
+}
@@ -0,0 +1,109 @@
+use crate::protocol::{Interface, MessageDesc};
+use crate::protocol_xml::{load, parse, LoadError};
+
+use super::*;
+
+fn assert_same_messages(loaded: &[MessageDesc], generated: &[MessageDesc]) {
+    assert_eq!(loaded.len(), generated.len());
+    for (loaded, generated) in loaded.iter().zip(generated) {
+        assert_eq!(loaded.name, generated.name);
+        assert_eq!(loaded.signature, generated.signature);
+        assert_eq!(loaded.since, generated.since);
+        assert_eq!(loaded.is_destructor, generated.is_destructor);
+        assert_eq!(
+            loaded.child_interface.map(|i| i.name),
+            generated.child_interface.map(|i| i.name)
+        );
+        assert_eq!(
+            loaded.arg_interfaces.iter().map(|i| i.name).collect::<Vec<_>>(),
+            generated.arg_interfaces.iter().map(|i| i.name).collect::<Vec<_>>()
+        );
+    }
+}
+
+// the interfaces loaded at runtime are the same as the ones generated by the scanner
+#[test]
+fn load_test_protocol() {
+    let xml = std::fs::File::open("../wayland-scanner/tests/scanner_assets/test-protocol.xml");
+    let loaded = load(xml.unwrap()).unwrap();
+
+    assert_eq!(loaded.len(), interfaces::INTERFACES.len());
+    for (loaded, generated) in loaded.iter().zip(interfaces::INTERFACES) {
+        assert_eq!(loaded.name, generated.name);
+        assert_eq!(loaded.version, generated.version);
+        assert!(loaded.c_ptr.is_none());
+        assert_same_messages(loaded.requests, generated.requests);
+        assert_same_messages(loaded.events, generated.events);
+    }
+    // the interfaces refer to the loaded ones rather than to the generated ones
+    let get_secondary = &loaded[3].requests[1];
+    assert!(std::ptr::eq(get_secondary.child_interface.unwrap(), loaded[4]));
+}
+
+#[test]
+fn load_errors() {
+    let load = |xml: &str| load(xml.as_bytes()).map(|_: Vec<&'static Interface>| ());
+
+    let unknown = r#"<protocol name="unknown">
+        <interface name="xml_unknown" version="1">
+            <request name="get"><arg name="id" type="new_id" interface="xml_missing"/></request>
+        </interface>
+    </protocol>"#;
+    assert_eq!(load(unknown), Err(LoadError::UnknownInterface("xml_missing".into())));
+
+    let bad_type = r#"<protocol name="bad">
+        <interface name="xml_bad" version="1">
+            <event name="evt"><arg name="value" type="double"/></event>
+        </interface>
+    </protocol>"#;
+    assert!(matches!(load(bad_type), Err(LoadError::Parse(_))));
+
+    let mismatched = r#"<protocol name="mismatched"><interface name="xml_a"></protocol>"#;
+    assert!(matches!(load(mismatched), Err(LoadError::Parse(_))));
+}
+
+fn error_of(xml: &str) -> String {
+    let error = parse(xml.as_bytes()).unwrap_err();
+    error.describe("test.xml", xml.as_bytes())
+}
+
+#[test]
+fn parse_errors() {
+    let unknown_type = r#"<?xml version="1.0" encoding="UTF-8"?>
+<protocol name="test">
+  <interface name="test_iface" version="1">
+    <request name="req">
+      <arg name="a" type="float"/>
+    </request>
+  </interface>
+</protocol>
+"#;
+    assert_eq!(
+        error_of(unknown_type),
+        "test.xml:5:7: Unexpected type: float, in protocol `test` > interface `test_iface` > \
+         request `req`"
+    );
+
+    let duplicate = r#"<protocol name="test">
+  <interface name="test_iface" version="1">
+    <event name="ev"/>
+    <event name="ev"/>
+  </interface>
+</protocol>
+"#;
+    assert_eq!(
+        error_of(duplicate),
+        "test.xml:4:5: Duplicate event `ev`, in protocol `test` > interface `test_iface`"
+    );
+
+    let bad_attribute = r#"<protocol name="test">
+  <interface name="test_iface" version="one"/>
+</protocol>
+"#;
+    assert!(error_of(bad_attribute).starts_with("test.xml:2:3: Invalid value 'one'"));
+
+    let unclosed = r#"<protocol name="test">
+  <interface name="test_iface" version="1">
+"#;
+    assert!(error_of(unclosed).starts_with("test.xml:3:1: Ill-formed protocol file"));
+}
@@ -0,0 +1,48 @@
+use std::ffi::CStr;
+
+use wayland_sys::{client::*, ffi_dispatch};
+
+use crate::sys::ProxyTag;
+
+use super::*;
+
+static TAG: ProxyTag = ProxyTag::new(unsafe { CStr::from_bytes_with_nul_unchecked(b"tag\0") });
+static OTHER_TAG: ProxyTag =
+    ProxyTag::new(unsafe { CStr::from_bytes_with_nul_unchecked(b"other\0") });
+
+#[test]
+fn proxy_tags() {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_rs::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_sys::Backend::connect(tx).unwrap();
+
+    let registry_id = client
+        .send_request(
+            message!(client.display_id(), 1, [Argument::NewId(client_sys::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    assert!(registry_id.tag_ptr().unwrap().is_null());
+    assert!(!registry_id.has_tag(&TAG));
+
+    registry_id.set_tag(Some(&TAG)).unwrap();
+    assert!(registry_id.has_tag(&TAG));
+    assert!(!registry_id.has_tag(&OTHER_TAG));
+    assert_eq!(TAG.name().to_bytes(), b"tag");
+
+    // the tag is the one seen by C code
+    let tag = unsafe {
+        ffi_dispatch!(wayland_client_1_18_handle(), wl_proxy_get_tag, registry_id.as_ptr())
+    };
+    assert_eq!(unsafe { CStr::from_ptr(*tag) }, TAG.name());
+
+    registry_id.set_tag(None).unwrap();
+    assert!(!registry_id.has_tag(&TAG));
+    assert!(registry_id.tag_ptr().unwrap().is_null());
+
+    let null_id = client_sys::ObjectId::null();
+    assert!(null_id.set_tag(Some(&TAG)).is_err());
+    assert!(!null_id.has_tag(&TAG));
+}
@@ -0,0 +1,187 @@
+use std::{ffi::CString, sync::Mutex};
+
+use crate::protocol::{same_interface, AllowNull, ArgumentType, Interface, MessageDesc};
+
+use super::*;
+
+// Interfaces without a C description, referring to each other
+
+static RUNTIME_GLOBAL_INTERFACE: Interface = Interface {
+    name: "runtime_global",
+    version: 2,
+    requests: &[MessageDesc {
+        name: "create_child",
+        signature: &[ArgumentType::NewId, ArgumentType::Object(AllowNull::Yes)],
+        since: 2,
+        is_destructor: false,
+        child_interface: Some(&RUNTIME_CHILD_INTERFACE),
+        arg_interfaces: &[&RUNTIME_GLOBAL_INTERFACE],
+    }],
+    events: &[],
+    c_ptr: None,
+};
+
+static RUNTIME_CHILD_INTERFACE: Interface = Interface {
+    name: "runtime_child",
+    version: 2,
+    requests: &[],
+    events: &[MessageDesc {
+        name: "parent",
+        signature: &[ArgumentType::Object(AllowNull::No), ArgumentType::Str(AllowNull::No)],
+        since: 1,
+        is_destructor: false,
+        child_interface: None,
+        arg_interfaces: &[&RUNTIME_GLOBAL_INTERFACE],
+    }],
+    c_ptr: None,
+};
+
+// the child created by the client, and the object it was given as parent
+struct ServerData<Id>(Mutex<Option<(Id, Id)>>);
+
+macro_rules! serverdata_impls {
+    ($server_backend:tt) => {
+        impl $server_backend::ObjectData<()> for ServerData<$server_backend::ObjectId> {
+            fn request(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                msg: Message<$server_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $server_backend::ObjectData<()>>> {
+                assert_eq!(msg.opcode, 0);
+                if let [Argument::NewId(child), Argument::Object(parent)] = &msg.args[..] {
+                    assert!(same_interface(child.interface(), &RUNTIME_CHILD_INTERFACE));
+                    assert!(same_interface(parent.interface(), &RUNTIME_GLOBAL_INTERFACE));
+                    *self.0.lock().unwrap() = Some((child.clone(), parent.clone()));
+                } else {
+                    panic!("Bad argument list !")
+                }
+                Some(Arc::new(DoNothingData))
+            }
+
+            fn destroyed(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::ObjectId,
+            ) {
+            }
+        }
+
+        impl $server_backend::GlobalHandler<()> for ServerData<$server_backend::ObjectId> {
+            fn bind(
+                self: Arc<Self>,
+                _: &$server_backend::Handle,
+                _: &mut (),
+                _: $server_backend::ClientId,
+                _: $server_backend::GlobalId,
+                _: $server_backend::ObjectId,
+            ) -> Arc<dyn $server_backend::ObjectData<()>> {
+                self
+            }
+        }
+    };
+}
+
+serverdata_impls!(server_rs);
+serverdata_impls!(server_sys);
+
+struct ClientData(Mutex<Option<String>>);
+
+macro_rules! clientdata_impls {
+    ($client_backend:tt) => {
+        impl $client_backend::ObjectData for ClientData {
+            fn event(
+                self: Arc<Self>,
+                _: &$client_backend::Backend,
+                msg: Message<$client_backend::ObjectId, OwnedFd>,
+            ) -> Option<Arc<dyn $client_backend::ObjectData>> {
+                assert_eq!(msg.opcode, 0);
+                if let [Argument::Object(parent), Argument::Str(Some(s))] = &msg.args[..] {
+                    assert!(same_interface(parent.interface(), &RUNTIME_GLOBAL_INTERFACE));
+                    *self.0.lock().unwrap() = Some(s.to_string_lossy().into_owned());
+                } else {
+                    panic!("Bad argument list !")
+                }
+                None
+            }
+
+            fn destroyed(&self, _: $client_backend::ObjectId) {}
+        }
+    };
+}
+
+clientdata_impls!(client_rs);
+clientdata_impls!(client_sys);
+
+// use interfaces without c_ptr in both directions, with a new_id and object arguments
+expand_test!(runtime_interfaces, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let server_data = Arc::new(ServerData::<server_backend::ObjectId>(Mutex::new(None)));
+    server.handle().create_global(&RUNTIME_GLOBAL_INTERFACE, 2, server_data.clone());
+
+    let client_display = client.display_id();
+    let registry_id = client
+        .send_request(
+            message!(client_display, 1, [Argument::NewId(client_backend::ObjectId::null())],),
+            Some(Arc::new(DoNothingData)),
+            Some((&interfaces::WL_REGISTRY_INTERFACE, 1)),
+        )
+        .unwrap();
+    let global_id = client
+        .send_request(
+            message!(
+                registry_id,
+                0,
+                [
+                    Argument::Uint(1),
+                    Argument::Str(Some(Box::new(
+                        CString::new(RUNTIME_GLOBAL_INTERFACE.name.as_bytes()).unwrap(),
+                    ))),
+                    Argument::Uint(2),
+                    Argument::NewId(client_backend::ObjectId::null()),
+                ],
+            ),
+            Some(Arc::new(DoNothingData)),
+            Some((&RUNTIME_GLOBAL_INTERFACE, 2)),
+        )
+        .unwrap();
+    let client_data = Arc::new(ClientData(Mutex::new(None)));
+    client
+        .send_request(
+            message!(
+                global_id.clone(),
+                0,
+                [Argument::NewId(client_backend::ObjectId::null()), Argument::Object(global_id),],
+            ),
+            Some(client_data.clone()),
+            None,
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    // send the parent back on the child
+    let (child, parent) = server_data.0.lock().unwrap().take().unwrap();
+    server
+        .handle()
+        .send_event(message!(
+            child,
+            0,
+            [
+                Argument::Object(parent),
+                Argument::Str(Some(Box::new(CString::new("hello".as_bytes()).unwrap()))),
+            ],
+        ))
+        .unwrap();
+    server.flush(None).unwrap();
+    client.prepare_read().unwrap().read().unwrap();
+
+    assert_eq!(client_data.0.lock().unwrap().as_deref(), Some("hello"));
+});
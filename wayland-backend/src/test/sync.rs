@@ -1,6 +1,7 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use super::*;
+
 struct SyncData(AtomicBool);
 
 impl client_rs::ObjectData for SyncData {
@@ -67,6 +68,162 @@ expand_test!(sync, {
     assert!(client.get_data(sync_id).is_err());
 });
 
+// counts the events received by its objects, also used by the other tests
+pub(super) struct CountData(pub(super) AtomicUsize);
+
+impl client_rs::ObjectData for CountData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_rs::Backend,
+        _: Message<client_rs::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_rs::ObjectData>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_rs::ObjectId) {}
+}
+
+impl client_sys::ObjectData for CountData {
+    fn event(
+        self: Arc<Self>,
+        _: &client_sys::Backend,
+        _: Message<client_sys::ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn client_sys::ObjectData>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        None
+    }
+
+    fn destroyed(&self, _: client_sys::ObjectId) {}
+}
+
+// send wl_display.sync requests from several threads at once
+expand_test!(sync_from_threads, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let count_data = Arc::new(CountData(AtomicUsize::new(0)));
+    let threads = (0..4)
+        .map(|_| {
+            let client = client.clone();
+            let count_data = count_data.clone();
+            std::thread::spawn(move || {
+                for _ in 0..50 {
+                    client
+                        .send_request(
+                            message!(
+                                client.display_id(),
+                                0,
+                                [Argument::NewId(client_backend::ObjectId::null())]
+                            ),
+                            Some(count_data.clone()),
+                            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+                        )
+                        .unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    client.flush().unwrap();
+
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+
+    // the answers may not fit in a single read
+    while count_data.0.load(Ordering::SeqCst) < 200 {
+        client.prepare_read().unwrap().read().unwrap();
+    }
+    assert_eq!(count_data.0.load(Ordering::SeqCst), 200);
+});
+
+// the callback given to Backend::sync() is invoked once the server answered
+expand_test!(backend_sync, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let done2 = done.clone();
+    let sync_id = client.sync(move |_| done2.store(true, Ordering::SeqCst)).unwrap();
+    client.flush().unwrap();
+
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    assert!(!done.load(Ordering::SeqCst));
+
+    client.prepare_read().unwrap().read().unwrap();
+    assert!(done.load(Ordering::SeqCst));
+    assert!(client.get_data(sync_id).is_err());
+});
+
+// Backend::roundtrip() returns once the server answered all the previous requests
+expand_test!(backend_roundtrip, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    let count_data = Arc::new(CountData(AtomicUsize::new(0)));
+    for _ in 0..3 {
+        client
+            .send_request(
+                message!(
+                    client.display_id(),
+                    0,
+                    [Argument::NewId(client_backend::ObjectId::null())]
+                ),
+                Some(count_data.clone()),
+                Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+            )
+            .unwrap();
+    }
+
+    let client_thread = std::thread::spawn(move || client.roundtrip().unwrap());
+    while !client_thread.is_finished() {
+        server.dispatch_all_clients(&mut ()).unwrap();
+        server.flush(None).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    // the three callbacks and the one of the roundtrip were dispatched, libwayland also counts the
+    // wl_display.delete_id events
+    assert!(client_thread.join().unwrap() >= 4);
+    assert_eq!(count_data.0.load(Ordering::SeqCst), 3);
+});
+
+// flushing a single client leaves the events of the others in their buffers
+expand_test!(flush_single_client, {
+    let mut server = server_backend::Backend::new().unwrap();
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+        let client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+        let client = client_backend::Backend::connect(tx).unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let done2 = done.clone();
+        client.sync(move |_| done2.store(true, Ordering::SeqCst)).unwrap();
+        client.flush().unwrap();
+        clients.push((client_id, client, done));
+    }
+    server.dispatch_all_clients(&mut ()).unwrap();
+
+    server.flush_client(clients[0].0.clone()).unwrap();
+    assert!(is_readable(clients[0].1.poll_fd()));
+    assert!(!is_readable(clients[1].1.poll_fd()));
+    clients[0].1.prepare_read().unwrap().read().unwrap();
+    assert!(clients[0].2.load(Ordering::SeqCst));
+
+    server.flush_client(clients[1].0.clone()).unwrap();
+    clients[1].1.prepare_read().unwrap().read().unwrap();
+    assert!(clients[1].2.load(Ordering::SeqCst));
+});
+
 expand_test!(panic test_bad_placeholder, {
     let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
     let mut server = server_backend::Backend::new().unwrap();
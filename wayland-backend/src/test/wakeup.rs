@@ -0,0 +1,53 @@
+use super::*;
+
+expand_test!(client_wakeup, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    assert!(!client.take_wakeup());
+    assert!(!is_readable(client.wakeup_fd().unwrap()));
+
+    // several wakeups are consumed at once
+    client.wakeup().unwrap();
+    client.clone().wakeup().unwrap();
+    assert!(is_readable(client.wakeup_fd().unwrap()));
+    assert!(client.take_wakeup());
+    assert!(!is_readable(client.wakeup_fd().unwrap()));
+    assert!(!client.take_wakeup());
+});
+
+expand_test!(blocking_read_wakeup, {
+    use super::sync::CountData;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let mut server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+
+    // a wakeup without any event cancels the read and is consumed
+    client.wakeup().unwrap();
+    let guard = client.prepare_read().unwrap();
+    assert_eq!(guard.blocking_read(Some(&client)).unwrap(), 0);
+    assert!(!is_readable(client.wakeup_fd().unwrap()));
+
+    // the events are read first, and the wakeup is kept for the next wait
+    let count_data = Arc::new(CountData(AtomicUsize::new(0)));
+    client
+        .send_request(
+            message!(client.display_id(), 0, [Argument::NewId(client_backend::ObjectId::null())]),
+            Some(count_data.clone()),
+            Some((&interfaces::WL_CALLBACK_INTERFACE, 1)),
+        )
+        .unwrap();
+    client.flush().unwrap();
+    server.dispatch_all_clients(&mut ()).unwrap();
+    server.flush(None).unwrap();
+    client.wakeup().unwrap();
+    let guard = client.prepare_read().unwrap();
+    guard.blocking_read(Some(&client)).unwrap();
+    assert_eq!(count_data.0.load(Ordering::SeqCst), 1);
+    assert!(client.take_wakeup());
+});
@@ -0,0 +1,30 @@
+use std::sync::mpsc;
+
+use super::*;
+
+expand_test!(weak_backend_on_drop, {
+    let (tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+    let server = server_backend::Backend::<()>::new().unwrap();
+    let _client_id = server.handle().insert_client(rx, Arc::new(())).unwrap();
+    let client = client_backend::Backend::connect(tx).unwrap();
+    let weak = client.downgrade();
+    assert!(weak.is_alive());
+
+    // a helper thread waiting for the backend to be dropped
+    let (sender, receiver) = mpsc::channel();
+    weak.on_drop(Box::new(move || sender.send(()).unwrap()));
+    let helper = std::thread::spawn(move || receiver.recv().unwrap());
+
+    let clone = client.clone();
+    drop(client);
+    assert!(weak.is_alive());
+    drop(clone);
+    helper.join().unwrap();
+    assert!(!weak.is_alive());
+    assert!(weak.upgrade().is_none());
+
+    // the callback is invoked immediately once the backend is dropped
+    let (sender, receiver) = mpsc::channel();
+    weak.on_drop(Box::new(move || sender.send(()).unwrap()));
+    assert!(receiver.try_recv().is_ok());
+});
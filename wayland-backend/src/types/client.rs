@@ -18,6 +18,21 @@ pub enum WaylandError {
     Io(std::io::Error),
     /// The connection encountered a protocol error
     Protocol(crate::protocol::ProtocolError),
+    /// The server sent data that could not be decoded into a message
+    ///
+    /// Only the rust backend reports this error, libwayland reports such data as a protocol error.
+    Wire(crate::protocol::WireError),
+    /// The server closed the connection
+    ///
+    /// If the server sent a protocol error right before closing the connection, which is how it
+    /// kills a client, it is provided here. Otherwise the server went away on its own, because it
+    /// was shut down or crashed.
+    ///
+    /// A protocol error dispatched before the connection is found closed is reported as
+    /// [`Protocol`](Self::Protocol) instead. Only the rust backend looks for a protocol error
+    /// still waiting in its buffers when it finds the connection closed, the system backend always
+    /// provides `None`.
+    Disconnected(Option<crate::protocol::ProtocolError>),
 }
 
 impl std::error::Error for WaylandError {
@@ -26,6 +41,9 @@ impl std::error::Error for WaylandError {
         match self {
             Self::Io(e) => Some(e),
             Self::Protocol(e) => Some(e),
+            Self::Wire(e) => Some(e),
+            Self::Disconnected(Some(e)) => Some(e),
+            Self::Disconnected(None) => None,
         }
     }
 }
@@ -36,6 +54,9 @@ impl std::fmt::Display for WaylandError {
         match self {
             Self::Io(e) => write!(f, "Io error: {}", e),
             Self::Protocol(e) => std::fmt::Display::fmt(e, f),
+            Self::Wire(e) => std::fmt::Display::fmt(e, f),
+            Self::Disconnected(Some(e)) => write!(f, "Disconnected by the server after: {}", e),
+            Self::Disconnected(None) => f.write_str("Disconnected by the server"),
         }
     }
 }
@@ -45,11 +66,14 @@ impl Clone for WaylandError {
     fn clone(&self) -> Self {
         match self {
             Self::Protocol(e) => Self::Protocol(e.clone()),
+            Self::Wire(e) => Self::Wire(e.clone()),
+            Self::Disconnected(e) => Self::Disconnected(e.clone()),
             Self::Io(e) => {
                 if let Some(code) = e.raw_os_error() {
                     Self::Io(std::io::Error::from_raw_os_error(code))
                 } else {
-                    Self::Io(std::io::Error::new(e.kind(), ""))
+                    // keep the message of the custom errors
+                    Self::Io(std::io::Error::new(e.kind(), e.to_string()))
                 }
             }
         }
@@ -63,10 +87,22 @@ impl From<crate::protocol::ProtocolError> for WaylandError {
     }
 }
 
+impl From<crate::protocol::WireError> for WaylandError {
+    #[cfg_attr(coverage, coverage(off))]
+    fn from(err: crate::protocol::WireError) -> Self {
+        Self::Wire(err)
+    }
+}
+
 impl From<std::io::Error> for WaylandError {
     #[cfg_attr(coverage, coverage(off))]
     fn from(err: std::io::Error) -> Self {
-        Self::Io(err)
+        match err.kind() {
+            std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::ConnectionReset => {
+                Self::Disconnected(None)
+            }
+            _ => Self::Io(err),
+        }
     }
 }
 
@@ -82,3 +118,53 @@ impl std::fmt::Display for InvalidId {
         write!(f, "Invalid ObjectId")
     }
 }
+
+/// Sizes of the buffers of a connection
+///
+/// The buffers start at their initial size and grow as needed, up to `max` bytes. A message that
+/// does not fit in `max` bytes cannot be sent or received, and results in a
+/// [`MessageTooLarge`](crate::protocol::MessageTooLarge) error.
+///
+/// This is only used by the rust backend, the system library manages its own buffers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BufferSizes {
+    /// Initial size of the incoming buffer, in bytes
+    pub incoming: usize,
+    /// Initial size of the outgoing buffer, in bytes
+    pub outgoing: usize,
+    /// Maximum size of both buffers, in bytes
+    pub max: usize,
+    /// Maximum number of file descriptors queued in each direction
+    ///
+    /// Exceeding it results in a [`TooManyFds`](crate::protocol::TooManyFds) error.
+    pub max_fds: usize,
+}
+
+impl Default for BufferSizes {
+    fn default() -> Self {
+        // The incoming buffer is twice as big in order to be able to store leftover data, the
+        // default maximum fits the largest message the protocol can represent. The default maximum
+        // number of file descriptors matches the size of the fd buffers of libwayland.
+        Self { incoming: 2 * 4096, outgoing: 4096, max: 1 << 16, max_fds: 1024 }
+    }
+}
+
+/// Approximate memory used by the state of a connection in the client backend
+///
+/// See [`Backend::memory_report()`](crate::client::Backend::memory_report).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryReport {
+    /// Number of protocol objects tracked by the backend
+    pub objects: usize,
+    /// Approximate memory used to track the objects and their data, in bytes
+    pub objects_memory: usize,
+    /// Number of events received and waiting to be dispatched
+    pub queued_messages: usize,
+    /// Size in bytes of the messages buffered to or from the server
+    pub buffered_bytes: usize,
+    /// Number of file descriptors buffered to or from the server
+    pub buffered_fds: usize,
+    /// Approximate memory used by the backend for this connection, in bytes
+    pub memory: usize,
+}
@@ -11,6 +11,18 @@ pub struct GlobalInfo {
     pub disabled: bool,
 }
 
+/// The implementations of the server backend.
+///
+/// The implementation is chosen at compile time by the `server_system` cargo feature, a program can
+/// only ever run the one it was compiled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackendKind {
+    /// The pure rust implementation
+    Rust,
+    /// The implementation based on `libwayland-server.so`
+    Sys,
+}
+
 /// An error type representing the failure to initialize a backend
 #[derive(Debug)]
 pub enum InitError {
@@ -55,11 +67,26 @@ impl std::fmt::Display for InvalidId {
 
 /// Describes why a client has been disconnected from the server.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DisconnectReason {
-    /// The connection has been closed by the server or client.
+    /// The client hung up, or its connection could not be read from anymore.
     ConnectionClosed,
     /// The server has sent the client a protocol error, terminating the connection.
     ProtocolError(crate::protocol::ProtocolError),
+    /// The server killed the client without sending it a protocol error, for example because it
+    /// did not complete its handshake in time.
+    Killed,
+    /// The messages to the client could not be sent, typically because it does not read them and
+    /// the buffers overflowed.
+    ///
+    /// **Note:** the system backend reports these as [`ConnectionClosed`](Self::ConnectionClosed),
+    /// libwayland does not expose the reason.
+    SendFailed(std::io::Error),
+    /// The client sent data that could not be decoded into a message.
+    ///
+    /// **Note:** the system backend reports these as [`ConnectionClosed`](Self::ConnectionClosed),
+    /// libwayland does not expose the reason.
+    InvalidMessage(crate::protocol::WireError),
 }
 
 /// Holds the client credentials
@@ -72,3 +99,37 @@ pub struct Credentials {
     /// gid of the client
     pub gid: rustix::process::RawGid,
 }
+
+/// Approximate memory used by the state of all the clients in the server backend
+///
+/// See [`Backend::memory_report()`](crate::server::Backend::memory_report).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryReport {
+    /// Number of connected clients
+    pub clients: usize,
+    /// Number of protocol objects owned by the clients
+    pub objects: usize,
+    /// Size in bytes of the messages buffered to or from the clients
+    pub buffered_bytes: usize,
+    /// Number of file descriptors buffered to or from the clients
+    pub buffered_fds: usize,
+    /// Approximate memory used by the backend for the clients, in bytes
+    pub memory: usize,
+}
+
+/// Approximate resources used by a client in the server backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResourceUsage {
+    /// Number of protocol objects owned by the client
+    pub objects: usize,
+    /// Size in bytes of the messages buffered to or from the client
+    pub buffered_bytes: usize,
+    /// Size in bytes of the events not yet written to the client socket, included in `buffered_bytes`
+    pub pending_output_bytes: usize,
+    /// Number of file descriptors buffered to or from the client
+    pub buffered_fds: usize,
+    /// Approximate memory used by the backend for this client, in bytes
+    pub memory: usize,
+}
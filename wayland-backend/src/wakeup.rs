@@ -0,0 +1,66 @@
+//! Wakeup of the threads waiting for the events of a client connection
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    os::unix::{
+        io::{AsRawFd, BorrowedFd},
+        net::UnixStream,
+    },
+    sync::Mutex,
+};
+
+/// A pipe that is readable while a wakeup is pending
+///
+/// It is created on first use, and never replaced afterwards.
+#[derive(Debug, Default)]
+pub(crate) struct Wakeup {
+    pipe: Mutex<Option<(UnixStream, UnixStream)>>,
+}
+
+impl Wakeup {
+    fn with_pipe<T>(&self, f: impl FnOnce(&UnixStream, &UnixStream) -> T) -> io::Result<T> {
+        let mut pipe = self.pipe.lock().unwrap();
+        if pipe.is_none() {
+            let (read, write) = UnixStream::pair()?;
+            read.set_nonblocking(true)?;
+            write.set_nonblocking(true)?;
+            *pipe = Some((read, write));
+        }
+        let (read, write) = pipe.as_ref().unwrap();
+        Ok(f(read, write))
+    }
+
+    pub(crate) fn wake(&self) -> io::Result<()> {
+        self.with_pipe(|_, mut write| match write.write(&[0]) {
+            Ok(_) => Ok(()),
+            // the pipe is full of pending wakeups
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(e) => Err(e),
+        })?
+    }
+
+    pub(crate) fn fd(&self) -> io::Result<BorrowedFd<'_>> {
+        let raw_fd = self.with_pipe(|read, _| read.as_raw_fd())?;
+        // The pipe lives as long as self once created, so the lifetime of the BorrowedFd can be
+        // tied to it rather than to the lock guard
+        Ok(unsafe { BorrowedFd::borrow_raw(raw_fd) })
+    }
+
+    pub(crate) fn take(&self) -> bool {
+        let pipe = self.pipe.lock().unwrap();
+        let Some((read, _)) = pipe.as_ref() else {
+            return false;
+        };
+        let mut read: &UnixStream = read;
+        let mut woken = false;
+        let mut buffer = [0; 64];
+        // read until WouldBlock
+        while let Ok(count) = read.read(&mut buffer) {
+            if count == 0 {
+                break;
+            }
+            woken = true;
+        }
+        woken
+    }
+}
@@ -0,0 +1,132 @@
+//! Encoding and decoding of messages in the Wayland wire format
+//!
+//! This is the codec of the rust backend, exposed for the tools working on raw protocol streams, such
+//! as protocol analyzers, fuzzers or test harnesses. The objects are identified by their protocol id,
+//! and the file descriptors are carried separately from the bytes, as they are on the socket.
+//!
+//! ```
+//! use std::collections::VecDeque;
+//! use wayland_backend::protocol::{Argument, ArgumentType, Message};
+//! use wayland_backend::wire;
+//!
+//! let msg: Message<u32, std::os::unix::io::BorrowedFd> = Message {
+//!     sender_id: 3,
+//!     opcode: 1,
+//!     args: [Argument::Uint(42), Argument::Int(-7)].into_iter().collect(),
+//! };
+//! let (bytes, fds) = wire::encode(&msg).unwrap();
+//!
+//! let (sender_id, opcode, size) = wire::header(&bytes).unwrap();
+//! assert_eq!((sender_id, opcode, size), (3, 1, bytes.len()));
+//! let (decoded, rest) =
+//!     wire::decode(&bytes, &mut VecDeque::from(fds), &[ArgumentType::Uint, ArgumentType::Int])
+//!         .unwrap();
+//! assert_eq!(decoded.args[..], [Argument::Uint(42), Argument::Int(-7)]);
+//! assert!(rest.is_empty());
+//! ```
+
+use std::collections::VecDeque;
+use std::os::unix::io::{BorrowedFd, OwnedFd};
+
+use smallvec::SmallVec;
+
+use crate::protocol::{ArgumentType, Message};
+use crate::rs::wire::{message_size, parse_message, write_to_buffers};
+
+pub use crate::rs::wire::{MessageParseError, MessageWriteError};
+
+/// Encode a message, returning its bytes and its file descriptors
+///
+/// The file descriptors are `dup()`-ed, with the close-on-exec flag set.
+pub fn encode(
+    msg: &Message<u32, BorrowedFd<'_>>,
+) -> Result<(Vec<u8>, Vec<OwnedFd>), MessageWriteError> {
+    let mut bytes = vec![0; message_size(msg)];
+    let mut fds = Vec::new();
+    write_to_buffers(msg, &mut bytes, &mut fds)?;
+    Ok((bytes, fds))
+}
+
+/// Read the header of the first message of `bytes`
+///
+/// Returns the id of the sender, the opcode and the size of the message in bytes, or `None` if `bytes`
+/// is shorter than a header. This is what is needed to look up the signature to [`decode`] the message.
+pub fn header(bytes: &[u8]) -> Option<(u32, u16, usize)> {
+    if bytes.len() < 2 * 4 {
+        return None;
+    }
+    let sender_id = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let word_2 = u32::from_ne_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    Some((sender_id, (word_2 & 0x0000_FFFF) as u16, (word_2 >> 16) as usize))
+}
+
+/// Decode the first message of `bytes`, with the given signature
+///
+/// Returns the message and the bytes following it. The file descriptors of the message are taken
+/// from the front of `fds`.
+///
+/// A [`MessageParseError::MissingData`] or [`MessageParseError::MissingFD`] error means that the
+/// message is incomplete, and can be decoded once more data is available. Malformed messages are
+/// reported as errors, this never panics whatever the contents of `bytes`.
+pub fn decode<'a>(
+    bytes: &'a [u8],
+    fds: &mut VecDeque<OwnedFd>,
+    signature: &[ArgumentType],
+) -> Result<(Message<u32, OwnedFd>, &'a [u8]), MessageParseError> {
+    parse_message(bytes, signature, fds, SmallVec::new())
+        .map(|(msg, rest)| (msg.into_owned(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{AllowNull, Argument};
+    use smallvec::smallvec;
+    use std::ffi::CString;
+
+    #[test]
+    fn encode_decode_fds() {
+        let msg = Message {
+            sender_id: 12,
+            opcode: 2,
+            args: smallvec![
+                Argument::Fd(rustix::stdio::stdin()),
+                Argument::Str(Some(Box::new(CString::new("hello").unwrap()))),
+                Argument::Object(0),
+            ],
+        };
+        let (bytes, fds) = encode(&msg).unwrap();
+        assert_eq!(fds.len(), 1);
+        assert_eq!(header(&bytes), Some((12, 2, bytes.len())));
+
+        let signature = [
+            ArgumentType::Fd,
+            ArgumentType::Str(AllowNull::No),
+            ArgumentType::Object(AllowNull::Yes),
+        ];
+        // the message cannot be decoded before all of it is available
+        assert!(matches!(
+            decode(&bytes[..bytes.len() - 1], &mut VecDeque::new(), &signature),
+            Err(MessageParseError::MissingData)
+        ));
+        assert!(matches!(
+            decode(&bytes, &mut VecDeque::new(), &signature),
+            Err(MessageParseError::MissingFD)
+        ));
+
+        let mut fds = VecDeque::from(fds);
+        let (decoded, rest) = decode(&bytes, &mut fds, &signature).unwrap();
+        assert!(rest.is_empty() && fds.is_empty());
+        assert_eq!(decoded.sender_id, 12);
+        assert_eq!(decoded.opcode, 2);
+        match &decoded.args[..] {
+            [Argument::Fd(fd), Argument::Str(Some(s)), Argument::Object(0)] => {
+                let stat1 = rustix::fs::fstat(fd).unwrap();
+                let stat2 = rustix::fs::fstat(rustix::stdio::stdin()).unwrap();
+                assert_eq!((stat1.st_dev, stat1.st_ino), (stat2.st_dev, stat2.st_ino));
+                assert_eq!(s.to_bytes(), b"hello");
+            }
+            args => panic!("Unexpected arguments: {:?}", args),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Generation of arbitrary requests, for the code generated by `wayland-scanner` with its `arbitrary`
+//! option
+
+use std::os::unix::io::BorrowedFd;
+
+use wayland_backend::{
+    __arbitrary::arbitrary::{Result, Unstructured},
+    client::ObjectId,
+    protocol::Interface,
+};
+
+use crate::Proxy;
+
+pub use wayland_backend::__arbitrary::*;
+
+/// The objects and file descriptors given to the arbitrary requests
+///
+/// The `Request::arbitrary()` functions generated by the `arbitrary` option of `wayland-scanner` take
+/// the arguments of the requests which refer to objects and file descriptors from it, so that a fuzzer
+/// only sends requests about the objects it created.
+pub trait ArbitraryObjects<'a> {
+    /// Pick an existing object of the interface `I`
+    ///
+    /// An error like [`Error::IncorrectFormat`](arbitrary::Error::IncorrectFormat) should be returned if there is none.
+    fn object<I: Proxy>(&mut self, u: &mut Unstructured<'_>) -> Result<I>;
+
+    /// Pick an existing object of any interface, for the arguments whose interface is not specified
+    fn object_id(&mut self, u: &mut Unstructured<'_>) -> Result<ObjectId>;
+
+    /// Pick the interface and version of the object created by a request whose `new_id` argument has
+    /// no interface, like `wl_registry.bind`
+    fn new_object_interface(
+        &mut self,
+        u: &mut Unstructured<'_>,
+    ) -> Result<(&'static Interface, u32)>;
+
+    /// Pick a file descriptor to send
+    fn fd(&mut self, u: &mut Unstructured<'_>) -> Result<BorrowedFd<'a>>;
+}
@@ -1,22 +1,31 @@
 use std::{
     env, fmt,
-    io::ErrorKind,
-    os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    os::unix::io::{AsFd, BorrowedFd, FromRawFd, OwnedFd},
     os::unix::net::UnixStream,
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, Once,
     },
 };
 
 use wayland_backend::{
     client::{Backend, InvalidId, ObjectData, ObjectId, ReadEventsGuard, WaylandError},
-    protocol::{ObjectInfo, ProtocolError},
+    protocol::{EnumDesc, Interface, ObjectInfo, ProtocolError},
 };
 
 use crate::{protocol::wl_display::WlDisplay, EventQueue, Proxy};
 
+// make the interfaces of the core protocol known to `Interface::find()`, and the enums of its
+// arguments to the debug output
+fn register_core_protocol() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        Interface::register(crate::protocol::__interfaces::INTERFACES);
+        EnumDesc::register(crate::protocol::__interfaces::ARG_ENUMS);
+    });
+}
+
 /// The Wayland connection
 ///
 /// This is the main type representing your connection to the Wayland server, though most of the interaction
@@ -87,13 +96,13 @@ impl Connection {
         };
 
         let backend = Backend::connect(stream).map_err(|_| ConnectError::NoWaylandLib)?;
-        Ok(Self { backend })
+        Ok(Self::from_backend(backend))
     }
 
     /// Initialize a Wayland connection from an already existing Unix stream
     pub fn from_socket(stream: UnixStream) -> Result<Self, ConnectError> {
         let backend = Backend::connect(stream).map_err(|_| ConnectError::NoWaylandLib)?;
-        Ok(Self { backend })
+        Ok(Self::from_backend(backend))
     }
 
     /// Get the `WlDisplay` associated with this connection
@@ -109,6 +118,7 @@ impl Connection {
 
     /// Wrap an existing [`Backend`] into a [`Connection`]
     pub fn from_backend(backend: Backend) -> Self {
+        register_core_protocol();
         Self { backend }
     }
 
@@ -146,33 +156,7 @@ impl Connection {
     ///
     /// See [`EventQueue::roundtrip()`] for a version that includes the dispatching of the event queue.
     pub fn roundtrip(&self) -> Result<usize, WaylandError> {
-        let done = Arc::new(SyncData::default());
-        let display = self.display();
-        self.send_request(
-            &display,
-            crate::protocol::wl_display::Request::Sync {},
-            Some(done.clone()),
-        )
-        .map_err(|_| WaylandError::Io(rustix::io::Errno::PIPE.into()))?;
-
-        let mut dispatched = 0;
-
-        loop {
-            self.backend.flush()?;
-
-            if let Some(guard) = self.backend.prepare_read() {
-                dispatched += blocking_read(guard)?;
-            } else {
-                dispatched += self.backend.dispatch_inner_queue()?;
-            }
-
-            // see if the successful read included our callback
-            if done.done.load(Ordering::Relaxed) {
-                break;
-            }
-        }
-
-        Ok(dispatched)
+        self.backend.roundtrip()
     }
 
     /// Retrieve the protocol error that occured on the connection if any
@@ -181,7 +165,8 @@ impl Connection {
     pub fn protocol_error(&self) -> Option<ProtocolError> {
         match self.backend.last_error()? {
             WaylandError::Protocol(err) => Some(err),
-            WaylandError::Io(_) => None,
+            WaylandError::Disconnected(err) => err,
+            WaylandError::Io(_) | WaylandError::Wire(_) => None,
         }
     }
 
@@ -197,7 +182,6 @@ impl Connection {
         data: Option<Arc<dyn ObjectData>>,
     ) -> Result<ObjectId, InvalidId> {
         let (msg, child_spec) = proxy.write_request(self, request)?;
-        let msg = msg.map_fd(|fd| fd.as_raw_fd());
         self.backend.send_request(msg, data, child_spec)
     }
 
@@ -216,30 +200,6 @@ impl Connection {
     }
 }
 
-pub(crate) fn blocking_read(guard: ReadEventsGuard) -> Result<usize, WaylandError> {
-    let fd = guard.connection_fd();
-    let mut fds = [rustix::event::PollFd::new(
-        &fd,
-        rustix::event::PollFlags::IN | rustix::event::PollFlags::ERR,
-    )];
-
-    loop {
-        match rustix::event::poll(&mut fds, -1) {
-            Ok(_) => break,
-            Err(rustix::io::Errno::INTR) => continue,
-            Err(e) => return Err(WaylandError::Io(e.into())),
-        }
-    }
-
-    // at this point the fd is ready
-    match guard.read() {
-        Ok(n) => Ok(n),
-        // if we are still "wouldblock", just return 0; the caller will retry.
-        Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => Ok(0),
-        Err(e) => Err(e),
-    }
-}
-
 /// An error when trying to establish a Wayland connection.
 #[derive(Debug)]
 pub enum ConnectError {
@@ -321,6 +321,9 @@ pub(crate) struct EventQueueInner<State> {
     queue: VecDeque<QueueEvent<State>>,
     freeze_count: usize,
     waker: Option<task::Waker>,
+    // the threads which created the live freeze guards, once per guard
+    #[cfg(feature = "debug-checks")]
+    freezing_threads: Vec<std::thread::ThreadId>,
 }
 
 impl<State> EventQueueInner<State> {
@@ -363,6 +366,8 @@ impl<State> EventQueue<State> {
             queue: VecDeque::new(),
             freeze_count: 0,
             waker: None,
+            #[cfg(feature = "debug-checks")]
+            freezing_threads: Vec::new(),
         }));
         Self { handle: QueueHandle { inner }, conn }
     }
@@ -381,7 +386,25 @@ impl<State> EventQueue<State> {
     ///
     /// Note: this may block if another thread has frozen the queue.
     pub fn dispatch_pending(&mut self, data: &mut State) -> Result<usize, DispatchError> {
-        Self::dispatching_impl(&self.conn, &self.handle, data)
+        Self::dispatching_impl(&self.conn, &self.handle, data, usize::MAX)
+    }
+
+    /// Dispatch at most `max_events` pending events
+    ///
+    /// This method is similar to [`dispatch_pending`](EventQueue::dispatch_pending), but stops after
+    /// dispatching `max_events` events of this queue, so that a latency-sensitive loop can interleave other
+    /// work, like rendering, with the processing of the events. It returns the number of dispatched events,
+    /// and the number of events remaining in the queue.
+    ///
+    /// Note: this may block if another thread has frozen the queue.
+    pub fn dispatch_pending_limited(
+        &mut self,
+        data: &mut State,
+        max_events: usize,
+    ) -> Result<(usize, usize), DispatchError> {
+        let dispatched = Self::dispatching_impl(&self.conn, &self.handle, data, max_events)?;
+        let remaining = self.handle.inner.lock().unwrap().queue.len();
+        Ok((dispatched, remaining))
     }
 
     /// Block waiting for events and dispatch them
@@ -391,7 +414,18 @@ impl<State> EventQueue<State> {
     /// event.
     ///
     /// A simple app event loop can consist of invoking this method in a loop.
+    ///
+    /// Another thread can interrupt the wait with [`Backend::wakeup()`](wayland_backend::client::Backend::wakeup)
+    /// on the backend of the connection, in which case this returns without having dispatched any event.
     pub fn blocking_dispatch(&mut self, data: &mut State) -> Result<usize, DispatchError> {
+        self.blocking_dispatch_impl(data, true)
+    }
+
+    fn blocking_dispatch_impl(
+        &mut self,
+        data: &mut State,
+        interruptible: bool,
+    ) -> Result<usize, DispatchError> {
         let dispatched = self.dispatch_pending(data)?;
         if dispatched > 0 {
             return Ok(dispatched);
@@ -400,7 +434,7 @@ impl<State> EventQueue<State> {
         self.conn.flush()?;
 
         if let Some(guard) = self.conn.prepare_read() {
-            crate::conn::blocking_read(guard)?;
+            guard.blocking_read(interruptible.then_some(&self.conn.backend))?;
         }
 
         self.dispatch_pending(data)
@@ -423,12 +457,13 @@ impl<State> EventQueue<State> {
                 crate::protocol::wl_display::Request::Sync {},
                 Some(done.clone()),
             )
-            .map_err(|_| WaylandError::Io(rustix::io::Errno::PIPE.into()))?;
+            .map_err(|_| WaylandError::Disconnected(self.conn.protocol_error()))?;
 
         let mut dispatched = 0;
 
+        // the wakeups are left for the next blocking_dispatch
         while !done.done.load(Ordering::Relaxed) {
-            dispatched += self.blocking_dispatch(data)?;
+            dispatched += self.blocking_dispatch_impl(data, false)?;
         }
 
         Ok(dispatched)
@@ -465,6 +500,7 @@ impl<State> EventQueue<State> {
         backend: &Connection,
         qhandle: &QueueHandle<State>,
         data: &mut State,
+        max_events: usize,
     ) -> Result<usize, DispatchError> {
         // This call will most of the time do nothing, but ensure that if the Connection is in guest mode
         // from some external connection, only invoking `EventQueue::dispatch_pending()` will be enough to
@@ -474,7 +510,10 @@ impl<State> EventQueue<State> {
         // lose events, and the potential socket error will be caught in other places anyway.
         let mut dispatched = backend.backend.dispatch_inner_queue().unwrap_or_default();
 
-        while let Some(QueueEvent(cb, msg, odata)) = Self::try_next(&qhandle.inner) {
+        for _ in 0..max_events {
+            let Some(QueueEvent(cb, msg, odata)) = Self::try_next(&qhandle.inner) else {
+                break;
+            };
             cb(backend, msg, data, odata, qhandle)?;
             dispatched += 1;
         }
@@ -484,6 +523,14 @@ impl<State> EventQueue<State> {
     fn try_next(inner: &Mutex<EventQueueInner<State>>) -> Option<QueueEvent<State>> {
         let mut lock = inner.lock().unwrap();
         if lock.freeze_count != 0 && !lock.queue.is_empty() {
+            #[cfg(feature = "debug-checks")]
+            if lock.freezing_threads.contains(&std::thread::current().id()) {
+                drop(lock);
+                panic!(
+                    "[wayland-client] An event queue was dispatched while the current thread holds \
+                     a freeze guard of it, the dispatching would wait for the guard forever."
+                );
+            }
             let waker = Arc::new(DispatchWaker { cond: Condvar::new() });
             while lock.freeze_count != 0 {
                 lock.waker = Some(waker.clone().into());
@@ -583,6 +630,8 @@ pub struct QueueHandle<State> {
 #[derive(Debug)]
 pub struct QueueFreezeGuard<'a, State> {
     qh: &'a QueueHandle<State>,
+    #[cfg(feature = "debug-checks")]
+    thread: std::thread::ThreadId,
 }
 
 impl<State> std::fmt::Debug for QueueHandle<State> {
@@ -623,8 +672,15 @@ impl<State: 'static> QueueHandle<State> {
     /// This will cause the associated queue to block (or return `NotReady` to poll) until all
     /// [`QueueFreezeGuard`]s associated with the queue are dropped.
     pub fn freeze(&self) -> QueueFreezeGuard<State> {
-        self.inner.lock().unwrap().freeze_count += 1;
-        QueueFreezeGuard { qh: self }
+        let mut lock = self.inner.lock().unwrap();
+        lock.freeze_count += 1;
+        #[cfg(feature = "debug-checks")]
+        lock.freezing_threads.push(std::thread::current().id());
+        QueueFreezeGuard {
+            qh: self,
+            #[cfg(feature = "debug-checks")]
+            thread: std::thread::current().id(),
+        }
     }
 }
 
@@ -632,6 +688,10 @@ impl<'a, State> Drop for QueueFreezeGuard<'a, State> {
     fn drop(&mut self) {
         let mut lock = self.qh.inner.lock().unwrap();
         lock.freeze_count -= 1;
+        #[cfg(feature = "debug-checks")]
+        if let Some(idx) = lock.freezing_threads.iter().position(|&thread| thread == self.thread) {
+            lock.freezing_threads.swap_remove(idx);
+        }
         if lock.freeze_count == 0 && !lock.queue.is_empty() {
             if let Some(waker) = lock.waker.take() {
                 waker.wake();
@@ -0,0 +1,166 @@
+//! Receiving the events of an object as an asynchronous stream
+
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    marker::PhantomData,
+    os::unix::io::OwnedFd,
+    sync::{Arc, Mutex},
+    task,
+};
+
+use wayland_backend::{
+    client::{Backend, ObjectData, ObjectId},
+    protocol::{Argument, Message},
+};
+
+use crate::{Connection, Proxy};
+
+/// A stream of the events of an object
+///
+/// This is an alternative to the [`Dispatch`](crate::Dispatch) trait for async code: the events of the
+/// object are buffered as they are read from the socket, and retrieved by polling the stream. The
+/// streams are created along their object, by the `<request>_stream()` methods that `wayland-scanner`
+/// generates with its `event_streams` option, or with [`EventStream::new()`] and
+/// [`Proxy::send_constructor()`].
+///
+/// The stream does not read the socket by itself, this is still done by the reading methods of
+/// [`Connection`] or [`EventQueue`](crate::EventQueue), for example by a task awaiting the readability
+/// of the connection and calling [`Connection::prepare_read()`]. It ends once the object is destroyed
+/// and its remaining events were retrieved.
+///
+/// The objects created by the events of this object, like the `wl_data_offer` of a
+/// `wl_data_device`, ignore their own events.
+pub struct EventStream<I: Proxy> {
+    data: Arc<StreamData<I>>,
+}
+
+impl<I> EventStream<I>
+where
+    I: Proxy + Send + 'static,
+    I::Event: Send,
+{
+    /// Create a stream, along with the object data of the object whose events it receives
+    ///
+    /// The object data is meant for [`Proxy::send_constructor()`], or the lower-level APIs of
+    /// `wayland-backend`.
+    pub fn new() -> (Self, Arc<dyn ObjectData>) {
+        let data = Arc::new(StreamData {
+            state: Mutex::new(StreamState {
+                events: VecDeque::new(),
+                waker: None,
+                destroyed: false,
+            }),
+            _phantom: PhantomData,
+        });
+        (Self { data: data.clone() }, data)
+    }
+
+    /// Attempt to retrieve the next event, registering the current task for wakeup if none is pending
+    ///
+    /// This returns `Ready(None)` once the object is destroyed and all its events were retrieved.
+    pub fn poll_next(&mut self, cx: &mut task::Context) -> task::Poll<Option<I::Event>> {
+        let mut state = self.data.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            task::Poll::Ready(Some(event))
+        } else if state.destroyed {
+            task::Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            task::Poll::Pending
+        }
+    }
+
+    /// Wait for the next event
+    ///
+    /// Returns `None` once the object is destroyed and all its events were retrieved.
+    pub async fn next(&mut self) -> Option<I::Event> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    /// Retrieve the next event if one is pending, without waiting
+    pub fn try_next(&mut self) -> Option<I::Event> {
+        self.data.state.lock().unwrap().events.pop_front()
+    }
+}
+
+impl<I: Proxy> std::fmt::Debug for EventStream<I> {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.data.state.lock().unwrap();
+        f.debug_struct("EventStream")
+            .field("interface", &I::interface().name)
+            .field("pending", &state.events.len())
+            .field("destroyed", &state.destroyed)
+            .finish()
+    }
+}
+
+struct StreamState<E> {
+    events: VecDeque<E>,
+    waker: Option<task::Waker>,
+    destroyed: bool,
+}
+
+struct StreamData<I: Proxy> {
+    state: Mutex<StreamState<I::Event>>,
+    _phantom: PhantomData<fn(&I)>,
+}
+
+impl<I> ObjectData for StreamData<I>
+where
+    I: Proxy + Send + 'static,
+    I::Event: Send,
+{
+    fn event(
+        self: Arc<Self>,
+        backend: &Backend,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData>> {
+        let new_data = msg
+            .args
+            .iter()
+            .any(|arg| matches!(arg, Argument::NewId(id) if !id.is_null()))
+            .then(|| Arc::new(IgnoreData) as Arc<dyn ObjectData>);
+
+        let conn = Connection::from_backend(backend.clone());
+        match I::parse_event(&conn, msg) {
+            Ok((_, event)) => {
+                let mut state = self.state.lock().unwrap();
+                state.events.push_back(event);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            }
+            Err(e) => crate::log_warn!("Dropping an event of an event stream: {}", e),
+        }
+
+        new_data
+    }
+
+    fn destroyed(&self, _: ObjectId) {
+        let mut state = self.state.lock().unwrap();
+        state.destroyed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// the object data of the objects created by the events of a stream
+struct IgnoreData;
+
+impl ObjectData for IgnoreData {
+    fn event(
+        self: Arc<Self>,
+        _: &Backend,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData>> {
+        msg.args
+            .iter()
+            .any(|arg| matches!(arg, Argument::NewId(id) if !id.is_null()))
+            .then_some(self as Arc<dyn ObjectData>)
+    }
+
+    fn destroyed(&self, _: ObjectId) {}
+}
@@ -173,7 +173,7 @@ impl GlobalList {
 
         // To get the version to bind, take the lower of the version advertised by the server and the maximum
         // requested version.
-        let version = version.min(version_end);
+        let version = I::negotiate_version(version).min(version_end);
 
         Ok(self.registry.bind(name, version, qh, udata))
     }
@@ -159,6 +159,13 @@
 //!   method (see `wayland-backend` docs), and then make it into a [`Connection`] using
 //!   [`Connection::from_backend()`]. Similarly, you can make [`ObjectId`]s from the `*mut wl_proxy` pointers
 //!   using `ObjectId::from_ptr()`, and then make the proxies using [`Proxy::from_id`].
+//!
+//! ### Debug checks
+//!
+//! The `debug-checks` cargo feature turns some misuses that would otherwise deadlock or silently do nothing
+//! into panics explaining them, to help finding them during development. In addition to the checks of the
+//! `debug-checks` feature of `wayland-backend`, dispatching an [`EventQueue`] while the same thread holds a
+//! [`QueueFreezeGuard`] of it panics instead of waiting forever.
 
 #![allow(clippy::needless_doctest_main)]
 #![warn(missing_docs, missing_debug_implementations)]
@@ -180,8 +187,26 @@ use wayland_backend::{
 
 mod conn;
 mod event_queue;
+mod event_stream;
 pub mod globals;
 
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod __serde;
+
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+pub mod __arbitrary;
+
+// used by the code generated by `wayland-scanner` with its `enum_results` option
+#[doc(hidden)]
+pub use wayland_backend::__enum_results;
+
+// used by the code generated by `wayland-scanner` with its `tracing` option
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
+
 /// Backend reexports
 pub mod backend {
     pub use wayland_backend::client::{
@@ -192,10 +217,14 @@ pub mod backend {
     pub use wayland_backend::smallvec;
 }
 
-pub use wayland_backend::protocol::WEnum;
+pub use wayland_backend::protocol::{UnknownVariant, WEnum};
+
+#[cfg(feature = "arbitrary")]
+pub use __arbitrary::ArbitraryObjects;
 
 pub use conn::{ConnectError, Connection};
 pub use event_queue::{Dispatch, EventQueue, QueueFreezeGuard, QueueHandle, QueueProxyData};
+pub use event_stream::EventStream;
 
 // internal imports for dispatching logging depending on the `log` feature
 #[cfg(feature = "log")]
@@ -218,7 +247,7 @@ pub mod protocol {
     pub mod __interfaces {
         wayland_scanner::generate_interfaces!("wayland.xml");
     }
-    wayland_scanner::generate_client_code!("wayland.xml");
+    wayland_scanner::generate_client_code!("wayland.xml", serde = "serde", arbitrary = "arbitrary");
 }
 
 /// Trait representing a Wayland interface
@@ -231,6 +260,14 @@ pub trait Proxy: Clone + std::fmt::Debug + Sized {
     /// The interface description
     fn interface() -> &'static Interface;
 
+    /// The version to bind a global of this interface advertised with the given version
+    ///
+    /// This is the lower of the advertised version and the version of the interface known by the
+    /// generated code, from the protocol file it was generated from.
+    fn negotiate_version(advertised: u32) -> u32 {
+        advertised.min(Self::interface().version)
+    }
+
     /// The ID of this object
     fn id(&self) -> ObjectId;
 
@@ -6,6 +6,10 @@
 //! Each protocol module contains a `client` and a `server` submodules, for each side of the
 //! protocol. The creation of these modules (and the dependency on the associated crate) is
 //! controlled by the two cargo features `client` and `server`.
+//!
+//! The `c_interfaces` cargo feature, enabled by default, generates the `wl_interface` tables of
+//! libwayland, as described in the documentation of
+//! [wayland-protocols](https://docs.rs/wayland-protocols).
 
 #![warn(missing_docs)]
 #![forbid(improper_ctypes, unsafe_op_in_unsafe_fn)]
@@ -36,6 +36,13 @@
 //! are still under that umbrella. We can expect them to be replaced by staging and stable protocols in the
 //! long term, but in the meantime you can enable them with the `unstable` cargo feature.
 //!
+//! ## Tables of libwayland
+//!
+//! The `c_interfaces` cargo feature, enabled by default, generates the `wl_interface` tables describing
+//! the interfaces to libwayland. The system backends of `wayland-backend` generate these tables when
+//! they are missing, so the applications only using the rust backends can disable the default features
+//! of this crate to save compile time and binary size.
+//!
 //! ## Other protocols
 //!
 //! Additionally, more protocol extensions are provided here:
@@ -21,7 +21,10 @@ macro_rules! wayland_protocol(
                 pub mod __interfaces {
                     use wayland_client::protocol::__interfaces::*;
                     $(use $imports::{client::__interfaces::*};)*
+                    #[cfg(feature = "c_interfaces")]
                     wayland_scanner::generate_interfaces!($path);
+                    #[cfg(not(feature = "c_interfaces"))]
+                    wayland_scanner::generate_interfaces!($path, no_c_interfaces);
                 }
                 use self::__interfaces::*;
 
@@ -38,7 +41,10 @@ macro_rules! wayland_protocol(
                 pub mod __interfaces {
                     use wayland_server::protocol::__interfaces::*;
                     $(use $imports::{server::__interfaces::*};)*
+                    #[cfg(feature = "c_interfaces")]
                     wayland_scanner::generate_interfaces!($path);
+                    #[cfg(not(feature = "c_interfaces"))]
+                    wayland_scanner::generate_interfaces!($path, no_c_interfaces);
                 }
                 use self::__interfaces::*;
 
@@ -0,0 +1,123 @@
+//! Caching of the parsed protocol files and of the generated code
+//!
+//! The macros are expanded by the process of the compiler, or by the long-running proc-macro server
+//! of an IDE, which loads this crate once and expands all the macros using it. The same protocol
+//! files are thus parsed for each of the macros generating their interfaces, client and server code,
+//! and the unchanged code is generated again on each expansion by the IDE. Both are kept here for
+//! the lifetime of the process, only the latest contents of each file being kept: an edited file
+//! replaces its previous entries, so the cache does not grow as the files are edited.
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use proc_macro2::TokenStream;
+
+use crate::{parse::ParseError, protocol::Protocol};
+
+// the name of a macro, its options and the paths of its files
+type MacroKey = (String, String, Vec<PathBuf>);
+// the contents of a protocol file and its parsed protocol
+type ParsedFile = (Vec<u8>, Protocol);
+// the contents of the files of a macro and the code generated for them
+type MacroCode = (Vec<Vec<u8>>, String);
+
+// the latest parsed contents of the protocol files, by their path
+static PROTOCOLS: Mutex<Option<HashMap<PathBuf, ParsedFile>>> = Mutex::new(None);
+// the code generated by the macros for the latest contents of their files; the token streams cannot
+// be shared between threads, so the code is kept as a string
+static GENERATED: Mutex<Option<HashMap<MacroKey, MacroCode>>> = Mutex::new(None);
+
+/// Parse the contents of a protocol file, unless these contents of the file were parsed already
+///
+/// Only the valid files are kept, the errors are reported again until the file is fixed.
+pub fn parse(path: &Path, contents: &[u8]) -> Result<Protocol, ParseError> {
+    let mut protocols = PROTOCOLS.lock().unwrap();
+    match protocols.get_or_insert_with(HashMap::new).get(path) {
+        Some((cached, protocol)) if cached[..] == *contents => return Ok(protocol.clone()),
+        _ => {}
+    }
+    drop(protocols);
+    // the lock is not held while parsing, a file may rarely be parsed by several threads at once
+    let protocol = crate::parse::parse(contents)?;
+    PROTOCOLS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(path.to_owned(), (contents.to_owned(), protocol.clone()));
+    Ok(protocol)
+}
+
+/// Generate the code of a macro with the given options for the given protocol files, unless it was
+/// generated already for these contents of the files
+pub fn generate(
+    macro_name: &str,
+    options: &impl Debug,
+    files: &[(PathBuf, Vec<u8>)],
+    generate: impl FnOnce() -> TokenStream,
+) -> TokenStream {
+    let paths = files.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>();
+    let key = (macro_name.to_owned(), format!("{:?}", options), paths);
+    let mut generated = GENERATED.lock().unwrap();
+    if let Some((contents, code)) = generated.get_or_insert_with(HashMap::new).get(&key) {
+        if contents.iter().eq(files.iter().map(|(_, contents)| contents)) {
+            return code.parse().expect("Failed to parse the cached code");
+        }
+    }
+    drop(generated);
+    let code = generate();
+    let contents = files.iter().map(|(_, contents)| contents.clone()).collect();
+    GENERATED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(key, (contents, code.to_string()));
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    #[test]
+    fn cached_generation() {
+        let path = PathBuf::from("./tests/scanner_assets/test-protocol.xml");
+        let contents = std::fs::read(&path).unwrap();
+        let files = vec![(path.clone(), contents.clone())];
+
+        let protocol = super::parse(&path, &contents).unwrap();
+        assert_eq!(
+            protocol.interfaces.len(),
+            super::parse(&path, &contents).unwrap().interfaces.len()
+        );
+
+        let generated =
+            super::generate("test", &true, &files, || crate::interfaces::generate(&protocol, true));
+        let cached = super::generate("test", &true, &files, || unreachable!());
+        assert_eq!(generated.to_string(), cached.to_string());
+        // but not for other options
+        let mut generated_again = false;
+        super::generate("test", &false, &files, || {
+            generated_again = true;
+            crate::interfaces::generate(&protocol, false)
+        });
+        assert!(generated_again);
+
+        // the edited file replaces the entries of its previous contents
+        let edited = String::from_utf8(contents.clone()).unwrap().replace("test_global", "edited");
+        let edited_protocol = super::parse(&path, edited.as_bytes()).unwrap();
+        assert_eq!(edited_protocol.interfaces[3].name, "edited");
+        let edited_files = vec![(path.clone(), edited.into_bytes())];
+        let mut generated_again = false;
+        super::generate("test", &true, &edited_files, || {
+            generated_again = true;
+            crate::interfaces::generate(&edited_protocol, true)
+        });
+        assert!(generated_again);
+        assert_eq!(super::GENERATED.lock().unwrap().as_ref().unwrap().len(), 2);
+        assert_eq!(super::PROTOCOLS.lock().unwrap().as_ref().unwrap().len(), 1);
+    }
+}
@@ -3,40 +3,81 @@ use proc_macro2::{Ident, Span, TokenStream};
 use quote::{format_ident, quote};
 
 use crate::{
-    protocol::{Interface, Protocol, Type},
-    util::{description_to_doc_attr, dotted_to_relname, is_keyword, snake_to_camel, to_doc_attr},
-    Side,
+    protocol::{Interface, Message, Protocol, Type},
+    util::{deprecated_attr, description_to_doc_attr, is_keyword, snake_to_camel, to_doc_attr},
+    Options, Side,
 };
 
-pub fn generate_client_objects(protocol: &Protocol) -> TokenStream {
-    protocol.interfaces.iter().map(generate_objects_for).collect()
+pub fn generate_client_objects(protocol: &Protocol, options: &Options) -> TokenStream {
+    protocol
+        .interfaces
+        .iter()
+        .map(|interface| generate_objects_for(protocol, interface, options))
+        .collect()
 }
 
-fn generate_objects_for(interface: &Interface) -> TokenStream {
+fn generate_objects_for(
+    protocol: &Protocol,
+    interface: &Interface,
+    options: &Options,
+) -> TokenStream {
     let mod_name = Ident::new(&interface.name, Span::call_site());
-    let mod_doc = interface.description.as_ref().map(description_to_doc_attr);
+    let (mod_head, mod_reexports) = crate::common::gen_module_layout(interface, options);
     let iface_name = Ident::new(&snake_to_camel(&interface.name), Span::call_site());
     let iface_const_name = format_ident!("{}_INTERFACE", interface.name.to_ascii_uppercase());
 
-    let enums = crate::common::generate_enums_for(interface);
-    let sinces = crate::common::gen_msg_constants(&interface.requests, &interface.events);
+    let enums = if options.shared_enums {
+        crate::common::reexport_enums_for(interface)
+    } else {
+        crate::common::generate_enums_for(interface)
+    };
+    let sinces =
+        crate::common::gen_msg_constants(Side::Client, &interface.requests, &interface.events);
 
     let requests = crate::common::gen_message_enum(
         &format_ident!("Request"),
         Side::Client,
         false,
-        &interface.requests,
+        false,
+        interface,
+        options,
     );
     let events = crate::common::gen_message_enum(
         &format_ident!("Event"),
         Side::Client,
         true,
-        &interface.events,
+        false,
+        interface,
+        options,
     );
 
-    let parse_body = crate::common::gen_parse_body(interface, Side::Client);
-    let write_body = crate::common::gen_write_body(interface, Side::Client);
-    let methods = gen_methods(interface);
+    let parse_body = crate::common::gen_parse_body(interface, Side::Client, false, options);
+    let parse_span =
+        options.tracing.then(|| crate::common::gen_trace_span(interface, Side::Client, true));
+    let write_span =
+        options.tracing.then(|| crate::common::gen_trace_span(interface, Side::Client, false));
+    let borrowed_events =
+        options.borrowed_events.then(|| gen_borrowed_events(interface, &parse_span, options));
+    let write_body = crate::common::gen_write_body(interface, Side::Client, options);
+    let arbitrary = options.arbitrary.is_on().then(|| {
+        crate::common::gen_arbitrary_messages(
+            protocol,
+            interface,
+            Side::Client,
+            options.enum_results,
+            options.arbitrary.cfg(),
+        )
+    });
+    let methods = gen_methods(interface, options.event_streams, options.enum_results);
+    let builders = options.builders.then(|| gen_builders(interface, &iface_name));
+    let stream_type = options.event_streams.then(|| {
+        let stream_name = format_ident!("{}EventStream", iface_name);
+        let doc_attr = to_doc_attr(&format!("A stream of the events of a [`{}`]", iface_name));
+        quote! {
+            #doc_attr
+            pub type #stream_name = super::wayland_client::EventStream<#iface_name>;
+        }
+    });
 
     let event_ref = if interface.events.is_empty() {
         "This interface has no events."
@@ -50,8 +91,7 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
     let doc_attr = to_doc_attr(&docs);
 
     quote! {
-        #mod_doc
-        pub mod #mod_name {
+        #mod_head mod #mod_name {
             use std::sync::Arc;
             use std::os::unix::io::OwnedFd;
 
@@ -67,6 +107,7 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
             #sinces
             #requests
             #events
+            #arbitrary
 
             #doc_attr
             #[derive(Debug, Clone)]
@@ -165,10 +206,12 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
                 }
 
                 fn parse_event(conn: &Connection, msg: Message<ObjectId, OwnedFd>) -> Result<(Self, Self::Event), DispatchError> {
+                    #parse_span
                     #parse_body
                 }
 
                 fn write_request<'a>(&self, conn: &Connection, msg: Self::Request<'a>) -> Result<(Message<ObjectId, std::os::unix::io::BorrowedFd<'a>>, Option<(&'static Interface, u32)>), InvalidId> {
+                    #write_span
                     #write_body
                 }
             }
@@ -176,16 +219,219 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
             impl #iface_name {
                 #methods
             }
+
+            #stream_type
+            #borrowed_events
+            #builders
         }
+
+        #mod_reexports
     }
 }
 
-fn gen_methods(interface: &Interface) -> TokenStream {
+fn gen_borrowed_events(
+    interface: &Interface,
+    parse_span: &Option<TokenStream>,
+    options: &Options,
+) -> TokenStream {
+    let iface_name = Ident::new(&snake_to_camel(&interface.name), Span::call_site());
+    let events = crate::common::gen_message_enum(
+        &format_ident!("BorrowedEvent"),
+        Side::Client,
+        true,
+        true,
+        interface,
+        options,
+    );
+    let parse_body = crate::common::gen_parse_body(interface, Side::Client, true, options);
+
+    let arms = interface.events.iter().map(|msg| {
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+        if msg.args.is_empty() {
+            return quote! { BorrowedEvent::#msg_name => Event::#msg_name };
+        }
+        let arg_names = msg
+            .args
+            .iter()
+            .map(|arg| {
+                format_ident!(
+                    "{}{}",
+                    if is_keyword(&arg.rust_name) { "_" } else { "" },
+                    arg.rust_name
+                )
+            })
+            .collect::<Vec<_>>();
+        let conversions = msg.args.iter().zip(&arg_names).map(|(arg, arg_name)| {
+            match (arg.typ, arg.allow_null) {
+                _ if arg.enum_.is_some() => quote! { #arg_name },
+                (Type::String, false) => quote! { #arg_name: #arg_name.into_owned() },
+                (Type::String, true) => quote! { #arg_name: #arg_name.map(|s| s.into_owned()) },
+                (Type::Array, false) => quote! { #arg_name: #arg_name.to_vec() },
+                (Type::Array, true) => quote! { #arg_name: #arg_name.map(|a| a.to_vec()) },
+                _ => quote! { #arg_name },
+            }
+        });
+        quote! {
+            BorrowedEvent::#msg_name { #(#arg_names),* } => Event::#msg_name { #(#conversions),* }
+        }
+    });
+
+    let doc_attr = to_doc_attr(&format!(
+        "Parse an event of a [`{}`], borrowing its strings and arrays from the message
+
+This is meant to be called from [`ObjectData::event_borrowed()`], which receives the message from \
+the buffers of the backend without copying it, to dispatch the events without allocating.",
+        iface_name
+    ));
+
+    quote! {
+        #events
+
+        impl<'a> BorrowedEvent<'a> {
+            #[doc="Convert this event into an owned [`Event`], copying its strings and arrays"]
+            pub fn into_owned(self) -> Event {
+                match self {
+                    #(#arms,)*
+                    BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+                }
+            }
+        }
+
+        impl #iface_name {
+            #doc_attr
+            pub fn parse_borrowed_event<'a>(
+                conn: &Connection,
+                msg: super::wayland_client::backend::protocol::BorrowedMessage<'a, ObjectId, OwnedFd>,
+            ) -> Result<(Self, BorrowedEvent<'a>), DispatchError> {
+                #parse_span
+                #parse_body
+            }
+        }
+    }
+}
+
+// the minimal number of arguments of the requests getting a builder
+const BUILDER_MIN_ARGS: usize = 4;
+
+fn gen_builders(interface: &Interface, iface_name: &Ident) -> TokenStream {
+    interface
+        .requests
+        .iter()
+        .filter(|request| request.args.len() >= BUILDER_MIN_ARGS)
+        // the requests creating an object take the type of its user data, they keep their method
+        .filter(|request| request.args.iter().all(|arg| arg.typ != Type::NewId))
+        .map(|request| {
+            let method_name = format_ident!(
+                "{}{}",
+                if is_keyword(&request.rust_name) { "_" } else { "" },
+                request.rust_name
+            );
+            let builder_method = format_ident!("{}_builder", request.rust_name);
+            let builder_name = format_ident!("{}Builder", snake_to_camel(&request.rust_name));
+            let deprecated_attr = deprecated_attr(request.deprecated_since);
+
+            let arg_names = request
+                .args
+                .iter()
+                .map(|arg| format_ident!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name))
+                .collect::<Vec<_>>();
+            let lifetime = quote! { 'a };
+            let arg_types = request
+                .args
+                .iter()
+                .map(|arg| crate::common::gen_method_arg_type(arg, Some(&lifetime)))
+                .collect::<Vec<_>>();
+
+            // the required arguments are type parameters, which are `()` until they are set, the
+            // nullable ones start as `None`
+            let params = request
+                .args
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| (!arg.allow_null).then(|| format_ident!("__A{}", i)))
+                .collect::<Vec<_>>();
+            let field_types = params.iter().zip(&arg_types).map(|(param, typ)| match param {
+                Some(param) => quote! { #param },
+                None => typ.clone(),
+            }).collect::<Vec<_>>();
+            let generics = params.iter().flatten().collect::<Vec<_>>();
+            let unset = params.iter().flatten().map(|_| quote! { () });
+            let set = params.iter().zip(&arg_types).filter(|(param, _)| param.is_some()).map(|(_, typ)| typ);
+            let initial_values = params.iter().zip(&arg_names).map(|(param, arg_name)| match param {
+                Some(_) => quote! { #arg_name: () },
+                None => quote! { #arg_name: None },
+            });
+
+            let setters = request.args.iter().enumerate().map(|(i, arg)| {
+                let arg_name = &arg_names[i];
+                let arg_type = &arg_types[i];
+                let doc_attr = to_doc_attr(&match arg.summary {
+                    Some(ref summary) => format!("Set the `{}` argument: {}", arg.rust_name, summary),
+                    None => format!("Set the `{}` argument", arg.rust_name),
+                });
+                let result_params = params.iter().enumerate().flat_map(|(j, param)| {
+                    param.as_ref().map(|param| if i == j { arg_type.clone() } else { quote! { #param } })
+                });
+                let fields = arg_names.iter().map(|name| {
+                    if name == arg_name { quote! { #name } } else { quote! { #name: self.#name } }
+                });
+                quote! {
+                    #doc_attr
+                    pub fn #arg_name(self, #arg_name: #arg_type) -> #builder_name<'a, #(#result_params),*> {
+                        #builder_name { __proxy: self.__proxy, #(#fields),* }
+                    }
+                }
+            });
+
+            let doc_attr = to_doc_attr(&format!(
+                "A builder for the `{}` request of a [`{}`]\n\nCreated by [`{}::{}()`], the request can only \
+                 be sent once all its non-nullable arguments are set.",
+                request.rust_name, iface_name, iface_name, builder_method
+            ));
+            let method_doc_attr = to_doc_attr(&format!(
+                "Build the `{}` request, setting its arguments by name\n\nSee also [`{}::{}()`].",
+                request.rust_name, iface_name, method_name
+            ));
+            let send_doc_attr = to_doc_attr(&format!("Send the `{}` request", request.rust_name));
+
+            quote! {
+                #doc_attr
+                #[must_use]
+                pub struct #builder_name<'a, #(#generics),*> {
+                    __proxy: &'a #iface_name,
+                    #(#arg_names: #field_types,)*
+                }
+
+                impl #iface_name {
+                    #method_doc_attr
+                    #deprecated_attr
+                    pub fn #builder_method(&self) -> #builder_name<'_, #(#unset),*> {
+                        #builder_name { __proxy: self, #(#initial_values),* }
+                    }
+                }
+
+                impl<'a, #(#generics),*> #builder_name<'a, #(#generics),*> {
+                    #(#setters)*
+                }
+
+                impl<'a> #builder_name<'a, #(#set),*> {
+                    #send_doc_attr
+                    #[allow(deprecated)]
+                    pub fn send(self) {
+                        self.__proxy.#method_name(#(self.#arg_names),*)
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+fn gen_methods(interface: &Interface, event_streams: bool, enum_results: bool) -> TokenStream {
     interface.requests.iter().map(|request| {
         let created_interface = request.args.iter().find(|arg| arg.typ == Type::NewId).map(|arg| &arg.interface);
 
-        let method_name = format_ident!("{}{}", if is_keyword(&request.name) { "_" } else { "" }, request.name);
-        let enum_variant = Ident::new(&snake_to_camel(&request.name), Span::call_site());
+        let method_name = format_ident!("{}{}", if is_keyword(&request.rust_name) { "_" } else { "" }, request.rust_name);
+        let enum_variant = Ident::new(&snake_to_camel(&request.rust_name), Span::call_site());
 
         let fn_args = request.args.iter().flat_map(|arg| {
             if arg.typ == Type::NewId {
@@ -200,39 +446,20 @@ fn gen_methods(interface: &Interface) -> TokenStream {
                 }
             }
 
-            let arg_name = format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
+            let arg_name = format_ident!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name);
 
-            let arg_type =  if let Some(ref enu) = arg.enum_ {
-                let enum_type = dotted_to_relname(enu);
-                quote! { #enum_type }
-            } else {
-                match arg.typ {
-                    Type::Uint => quote! { u32 },
-                    Type::Int => quote! { i32 },
-                    Type::Fixed => quote! { f64 },
-                    Type::String => if arg.allow_null { quote!{ Option<String> } } else { quote!{ String } },
-                    Type::Array => if arg.allow_null { quote!{ Option<Vec<u8>> } } else { quote!{ Vec<u8> } },
-                    Type::Fd => quote! { ::std::os::unix::io::BorrowedFd<'_> },
-                    Type::Object => {
-                        let iface = arg.interface.as_ref().unwrap();
-                        let iface_mod = Ident::new(iface, Span::call_site());
-                        let iface_type =
-                            Ident::new(&snake_to_camel(iface), Span::call_site());
-                        if arg.allow_null { quote! { Option<&super::#iface_mod::#iface_type> } } else { quote! { &super::#iface_mod::#iface_type } }
-                    },
-                    Type::NewId => unreachable!(),
-                    Type::Destructor => panic!("An argument cannot have type \"destructor\"."),
-                }
-            };
+            let arg_type = crate::common::gen_method_arg_type(arg, None);
 
             Some(quote! {
                 #arg_name: #arg_type
             })
-        });
+        }).collect::<Vec<_>>();
 
         let enum_args = request.args.iter().flat_map(|arg| {
-            let arg_name = format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
-            if arg.enum_.is_some() {
+            let arg_name = format_ident!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name);
+            if arg.enum_.is_some() && !arg.bitfield && enum_results {
+                Some(quote! { #arg_name: Ok(#arg_name) })
+            } else if arg.enum_.is_some() && !arg.bitfield {
                 Some(quote! { #arg_name: WEnum::Value(#arg_name) })
             } else if arg.typ == Type::NewId {
                 if arg.interface.is_none() {
@@ -249,20 +476,22 @@ fn gen_methods(interface: &Interface) -> TokenStream {
             } else {
                 Some(quote! { #arg_name })
             }
-        });
+        }).collect::<Vec<_>>();
 
         let doc_attr = request
             .description
             .as_ref()
             .map(description_to_doc_attr);
+        let deprecated_attr = deprecated_attr(request.deprecated_since);
 
         match created_interface {
             Some(Some(ref created_interface)) => {
                 // a regular creating request
                 let created_iface_mod = Ident::new(created_interface, Span::call_site());
                 let created_iface_type = Ident::new(&snake_to_camel(created_interface), Span::call_site());
-                quote! {
+                let constructor = quote! {
                     #doc_attr
+                    #deprecated_attr
                     #[allow(clippy::too_many_arguments)]
                     pub fn #method_name<U: Send + Sync + 'static, D: Dispatch<super::#created_iface_mod::#created_iface_type, U> + 'static>(&self, #(#fn_args,)* qh: &QueueHandle<D>, udata: U) -> super::#created_iface_mod::#created_iface_type {
                         self.send_constructor(
@@ -272,12 +501,28 @@ fn gen_methods(interface: &Interface) -> TokenStream {
                             qh.make_data::<super::#created_iface_mod::#created_iface_type, U>(udata),
                         ).unwrap_or_else(|_| Proxy::inert(self.backend.clone()))
                     }
+                };
+                if !event_streams {
+                    return constructor;
+                }
+                let stream_method = gen_stream_method(
+                    request,
+                    &fn_args,
+                    &enum_args,
+                    quote! {},
+                    quote! { super::#created_iface_mod::#created_iface_type },
+                    quote! {},
+                );
+                quote! {
+                    #constructor
+                    #stream_method
                 }
             },
             Some(None) => {
                 // a bind-like request
-                quote! {
+                let constructor = quote! {
                     #doc_attr
+                    #deprecated_attr
                     #[allow(clippy::too_many_arguments)]
                     pub fn #method_name<I: Proxy + 'static, U: Send + Sync + 'static, D: Dispatch<I, U> + 'static>(&self, #(#fn_args,)* qh: &QueueHandle<D>, udata: U) -> I {
                         self.send_constructor(
@@ -287,12 +532,28 @@ fn gen_methods(interface: &Interface) -> TokenStream {
                             qh.make_data::<I, U>(udata),
                         ).unwrap_or_else(|_| Proxy::inert(self.backend.clone()))
                     }
+                };
+                if !event_streams {
+                    return constructor;
+                }
+                let stream_method = gen_stream_method(
+                    request,
+                    &fn_args,
+                    &enum_args,
+                    quote! { <I: Proxy + Send + 'static> },
+                    quote! { I },
+                    quote! { where I::Event: Send },
+                );
+                quote! {
+                    #constructor
+                    #stream_method
                 }
             },
             None => {
                 // a non-creating request
                 quote! {
                     #doc_attr
+                    #deprecated_attr
                     #[allow(clippy::too_many_arguments)]
                     pub fn #method_name(&self, #(#fn_args),*) {
                         let backend = match self.backend.upgrade() {
@@ -314,24 +575,102 @@ fn gen_methods(interface: &Interface) -> TokenStream {
     }).collect()
 }
 
+// the `<request>_stream()` method of a creating request, whose object has type `proxy_type`
+fn gen_stream_method(
+    request: &Message,
+    fn_args: &[TokenStream],
+    enum_args: &[TokenStream],
+    generics: TokenStream,
+    proxy_type: TokenStream,
+    where_clause: TokenStream,
+) -> TokenStream {
+    let method_name = format_ident!("{}_stream", request.rust_name);
+    let enum_variant = Ident::new(&snake_to_camel(&request.rust_name), Span::call_site());
+    let doc_attr = to_doc_attr(&format!(
+        "Send the `{}` request, the events of the created object going to the returned stream\n\n\
+         Fails if this object is no longer alive, in which case no request is sent.",
+        request.rust_name
+    ));
+    let deprecated_attr = deprecated_attr(request.deprecated_since);
+    quote! {
+        #doc_attr
+        #deprecated_attr
+        #[allow(clippy::too_many_arguments)]
+        pub fn #method_name #generics(&self, #(#fn_args),*) -> Result<(#proxy_type, super::wayland_client::EventStream<#proxy_type>), InvalidId> #where_clause {
+            let (stream, data) = super::wayland_client::EventStream::new();
+            let proxy = self.send_constructor(
+                Request::#enum_variant {
+                    #(#enum_args),*
+                },
+                data,
+            )?;
+            Ok((proxy, stream))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
     fn client_gen() {
         let protocol_file =
             std::fs::File::open("./tests/scanner_assets/test-protocol.xml").unwrap();
-        let protocol_parsed = crate::parse::parse(protocol_file);
-        let generated: String = super::generate_client_objects(&protocol_parsed).to_string();
-        let generated = crate::format_rust_code(&generated);
-
-        let reference =
-            std::fs::read_to_string("./tests/scanner_assets/test-client-code.rs").unwrap();
-        let reference = crate::format_rust_code(&reference);
+        let protocol_parsed = crate::parse::parse(protocol_file).unwrap();
+        crate::check_reference(
+            "test-client-code.rs",
+            &super::generate_client_objects(&protocol_parsed, &Default::default()),
+        );
+    }
 
-        if reference != generated {
-            let diff = similar::TextDiff::from_lines(&reference, &generated);
-            print!("{}", diff.unified_diff().context_radius(10).header("reference", "generated"));
-            panic!("Generated does not match reference!")
+    #[test]
+    fn client_gen_options() {
+        // each option is checked on its own, against `options/client-<option>.rs`
+        let options = [
+            ("event_streams", None),
+            ("serde", Some("serde")),
+            ("borrowed_events", None),
+            ("builders", None),
+            ("tracing", None),
+            ("enum_results", None),
+            ("arbitrary", Some("arbitrary")),
+            ("visibility", Some("pub(crate)")),
+            ("strip_prefix", Some("opt_")),
+            ("reexport_types", None),
+            ("rename", Some("opt_widget.enum.state=status")),
+        ];
+        for (name, value) in options {
+            let options = crate::Options::parse(
+                &[(name.to_owned(), value.map(str::to_owned))],
+                &[crate::Side::Client],
+            );
+            let protocol = crate::options_protocol(&options);
+            crate::check_reference(
+                &format!("options/client-{}.rs", name),
+                &super::generate_client_objects(&protocol, &options),
+            );
         }
     }
+
+    #[test]
+    fn client_gen_features() {
+        let protocol_file =
+            std::fs::File::open("./tests/scanner_assets/test-protocol.xml").unwrap();
+        let protocol_parsed = crate::parse::parse(protocol_file).unwrap();
+        let mut options = crate::Options {
+            features: vec![("test_global".into(), "global".into()), ("quad".into(), "quad".into())],
+            ..Default::default()
+        };
+        crate::common::propagate_features(&protocol_parsed, &mut options.features);
+        let generated: String =
+            super::generate_client_objects(&protocol_parsed, &options).to_string();
+        let generated = crate::format_rust_code(&generated);
+
+        // `test_global` refers to `quad`, and `tertiary` to the enums of `test_global`
+        assert!(generated.contains("#[cfg(feature = \"quad\")]\npub mod quad {"));
+        let both = "#[cfg(all(feature = \"global\", feature = \"quad\"))]";
+        assert!(generated.contains(&format!("{}\npub mod test_global {{", both)));
+        assert!(generated.contains(&format!("{}\npub mod tertiary {{", both)));
+        assert!(generated.contains("}\npub mod secondary {"));
+        assert_eq!(generated.matches("#[cfg(").count(), 3);
+    }
 }
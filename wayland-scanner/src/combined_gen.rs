@@ -0,0 +1,64 @@
+use proc_macro2::{Ident, Span, TokenStream};
+
+use quote::quote;
+
+use crate::{protocol::Protocol, util::to_doc_attr, Options};
+
+pub fn generate_combined_objects(protocol: &Protocol, options: &Options) -> TokenStream {
+    let options = &Options { shared_enums: true, ..options.clone() };
+    let vis = options.visibility;
+
+    let enums = protocol.interfaces.iter().filter(|interface| !interface.enums.is_empty()).map(
+        |interface| {
+            let mod_name = Ident::new(&interface.name, Span::call_site());
+            let doc_attr = to_doc_attr(&format!("The enums of the {} interface", interface.name));
+            let enums = crate::common::generate_enums_for(interface);
+            let cfg_attr = crate::common::gen_cfg_attr(interface, options);
+            quote! {
+                #doc_attr
+                #cfg_attr
+                pub mod #mod_name {
+                    #enums
+                }
+            }
+        },
+    );
+    let client = crate::client_gen::generate_client_objects(protocol, options);
+    let server = crate::server_gen::generate_server_objects(protocol, options);
+
+    quote! {
+        /// The enums of the protocol, re-exported by the interface modules of both sides
+        #vis mod enums {
+            #(#enums)*
+        }
+
+        /// The client-side API of the protocol
+        #vis mod client {
+            use super::*;
+            use super::wayland_client::protocol::*;
+
+            #client
+        }
+
+        /// The server-side API of the protocol
+        #vis mod server {
+            use super::*;
+            use super::wayland_server::protocol::*;
+
+            #server
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn combined_gen() {
+        // the enums generated once and shared by both sides
+        let protocol = crate::options_protocol(&Default::default());
+        crate::check_reference(
+            "options/combined.rs",
+            &super::generate_combined_objects(&protocol, &Default::default()),
+        );
+    }
+}
@@ -4,19 +4,179 @@ use proc_macro2::{Ident, Literal, Span, TokenStream};
 
 use quote::{format_ident, quote, ToTokens};
 
-use crate::{protocol::*, util::*, Side};
+use crate::{protocol::*, util::*, Gate, Options, Side};
 
 pub(crate) fn generate_enums_for(interface: &Interface) -> TokenStream {
     interface.enums.iter().map(ToTokens::into_token_stream).collect()
 }
 
+// the span entered by the generated code while parsing a message received by an object, or while
+// writing a message sent by it, with the `tracing` option
+pub(crate) fn gen_trace_span(interface: &Interface, side: Side, receiving: bool) -> TokenStream {
+    let tracing = match side {
+        Side::Client => quote!(super::wayland_client::__tracing),
+        Side::Server => quote!(super::wayland_server::__tracing),
+    };
+    let iface_name = &interface.name;
+    let iface_const_name = format_ident!("{}_INTERFACE", interface.name.to_ascii_uppercase());
+    let kind = match (side, receiving) {
+        (Side::Client, true) | (Side::Server, false) => "event",
+        (Side::Client, false) | (Side::Server, true) => "request",
+    };
+    let kind_ident = Ident::new(kind, Span::call_site());
+    if receiving {
+        let span_name = format!("parse_{}", kind);
+        let messages = format_ident!("{}s", kind);
+        quote! {
+            let __span = #tracing::trace_span!(
+                #span_name,
+                interface = #iface_name,
+                #kind_ident = super::#iface_const_name.#messages.get(msg.opcode as usize).map_or("<unknown>", |desc| desc.name),
+                id = %msg.sender_id,
+            ).entered();
+        }
+    } else {
+        let span_name = format!("write_{}", kind);
+        quote! {
+            let __span = #tracing::trace_span!(
+                #span_name,
+                interface = #iface_name,
+                #kind_ident = msg.name(),
+                id = %self.id,
+            ).entered();
+        }
+    }
+}
+
+// the attributes and visibility of the module of an interface, and the items re-exported alongside
+// it, following the layout options: a module whose name is stripped of the prefix is hidden and
+// re-exported under the new name
+pub(crate) fn gen_module_layout(
+    interface: &Interface,
+    options: &Options,
+) -> (TokenStream, TokenStream) {
+    let vis = options.visibility;
+    let mod_name = Ident::new(&interface.name, Span::call_site());
+    let mod_doc = interface.description.as_ref().map(description_to_doc_attr);
+    let iface_name = Ident::new(&snake_to_camel(&interface.name), Span::call_site());
+    let cfg_attr = gen_cfg_attr(interface, options);
+
+    let reexport_type = options.reexport_types.then(|| {
+        quote! {
+            #cfg_attr
+            #vis use self::#mod_name::#iface_name;
+        }
+    });
+    let stripped = options
+        .strip_prefix
+        .as_deref()
+        .and_then(|prefix| interface.name.strip_prefix(prefix))
+        .filter(|name| !name.is_empty());
+    match stripped {
+        Some(name) => {
+            let new_name = Ident::new(name, Span::call_site());
+            let head = quote! {
+                #[doc(hidden)]
+                #cfg_attr
+                #vis
+            };
+            let reexports = quote! {
+                #mod_doc
+                #cfg_attr
+                #vis use self::#mod_name as #new_name;
+                #reexport_type
+            };
+            (head, reexports)
+        }
+        None => {
+            let head = quote! {
+                #mod_doc
+                #cfg_attr
+                #vis
+            };
+            (head, reexport_type.unwrap_or_default())
+        }
+    }
+}
+
+// the `cfg` attribute gating the code of an interface behind its cargo features, if any
+pub(crate) fn gen_cfg_attr(interface: &Interface, options: &Options) -> Option<TokenStream> {
+    let features = options
+        .features
+        .iter()
+        .filter(|(gated, _)| *gated == interface.name)
+        .map(|(_, feature)| feature)
+        .collect::<Vec<_>>();
+    match features[..] {
+        [] => None,
+        [feature] => Some(quote! { #[cfg(feature = #feature)] }),
+        _ => Some(quote! { #[cfg(all(#(feature = #features),*))] }),
+    }
+}
+
+// gate the interfaces referring to a gated interface, by the type of an argument or by one of its
+// enums, behind the features of this interface as well, as their code would not build without it
+pub(crate) fn propagate_features(protocol: &Protocol, features: &mut Vec<(String, String)>) {
+    for (gated, feature) in features.iter() {
+        assert!(
+            protocol.interfaces.iter().any(|interface| interface.name == *gated),
+            "Cannot gate unknown interface {} behind feature {}",
+            gated,
+            feature
+        );
+    }
+    loop {
+        let mut added = Vec::new();
+        for interface in &protocol.interfaces {
+            let referred = interface
+                .requests
+                .iter()
+                .chain(&interface.events)
+                .flat_map(|msg| &msg.args)
+                .flat_map(|arg| {
+                    let enum_interface =
+                        arg.enum_.as_deref().and_then(|enu| enu.split_once('.')).map(|(i, _)| i);
+                    arg.interface.as_deref().into_iter().chain(enum_interface)
+                });
+            for referred in referred {
+                for (_, feature) in features.iter().filter(|(gated, _)| gated == referred) {
+                    let propagated = (interface.name.clone(), feature.clone());
+                    if !features.contains(&propagated) && !added.contains(&propagated) {
+                        added.push(propagated);
+                    }
+                }
+            }
+        }
+        if added.is_empty() {
+            return;
+        }
+        features.extend(added);
+    }
+}
+
+// re-export the enums of an interface from the module generated once for the client and server
+// code by `generate_client_and_server_code!`
+pub(crate) fn reexport_enums_for(interface: &Interface) -> TokenStream {
+    if interface.enums.is_empty() {
+        return TokenStream::new();
+    }
+    let mod_name = Ident::new(&interface.name, Span::call_site());
+    let names = interface
+        .enums
+        .iter()
+        .map(|enu| Ident::new(&snake_to_camel(&enu.rust_name), Span::call_site()));
+    quote! {
+        pub use super::super::enums::#mod_name::{#(#names),*};
+    }
+}
+
 impl ToTokens for Enum {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let enum_decl;
         let enum_impl;
 
         let doc_attr = self.description.as_ref().map(description_to_doc_attr);
-        let ident = Ident::new(&snake_to_camel(&self.name), Span::call_site());
+        let ident = Ident::new(&snake_to_camel(&self.rust_name), Span::call_site());
 
         if self.bitfield {
             let entries = self.entries.iter().map(|entry| {
@@ -26,13 +186,20 @@ impl ToTokens for Enum {
                     .map(description_to_doc_attr)
                     .or_else(|| entry.summary.as_ref().map(|s| to_doc_attr(s)));
 
-                let prefix = if entry.name.chars().next().unwrap().is_numeric() { "_" } else { "" };
-                let ident = format_ident!("{}{}", prefix, snake_to_camel(&entry.name));
+                let prefix =
+                    if entry.rust_name.chars().next().unwrap().is_numeric() { "_" } else { "" };
+                let ident = format_ident!("{}{}", prefix, snake_to_camel(&entry.rust_name));
 
                 let value = Literal::u32_unsuffixed(entry.value);
 
+                // the flags are not marked as deprecated, the bitflags macro uses them all
+                let deprecated_doc_attr = entry.deprecated_since.map(|version| {
+                    to_doc_attr(&format!("\nDeprecated since version {} of the interface", version))
+                });
+
                 quote! {
                     #doc_attr
+                    #deprecated_doc_attr
                     const #ident = #value;
                 }
             });
@@ -47,13 +214,13 @@ impl ToTokens for Enum {
                 }
             };
             enum_impl = quote! {
-                impl std::convert::TryFrom<u32> for #ident {
+                impl core::convert::TryFrom<u32> for #ident {
                     type Error = ();
                     fn try_from(val: u32) -> Result<#ident, ()> {
                         #ident::from_bits(val).ok_or(())
                     }
                 }
-                impl std::convert::From<#ident> for u32 {
+                impl core::convert::From<#ident> for u32 {
                     fn from(val: #ident) -> u32 {
                         val.bits()
                     }
@@ -67,13 +234,16 @@ impl ToTokens for Enum {
                     .map(description_to_doc_attr)
                     .or_else(|| entry.summary.as_ref().map(|s| to_doc_attr(s)));
 
-                let prefix = if entry.name.chars().next().unwrap().is_numeric() { "_" } else { "" };
-                let variant = format_ident!("{}{}", prefix, snake_to_camel(&entry.name));
+                let prefix =
+                    if entry.rust_name.chars().next().unwrap().is_numeric() { "_" } else { "" };
+                let variant = format_ident!("{}{}", prefix, snake_to_camel(&entry.rust_name));
 
                 let value = Literal::u32_unsuffixed(entry.value);
+                let deprecated_attr = deprecated_attr(entry.deprecated_since.map(u32::from));
 
                 quote! {
                     #doc_attr
+                    #deprecated_attr
                     #variant = #value
                 }
             });
@@ -91,8 +261,9 @@ impl ToTokens for Enum {
             let match_arms = self.entries.iter().map(|entry| {
                 let value = Literal::u32_unsuffixed(entry.value);
 
-                let prefix = if entry.name.chars().next().unwrap().is_numeric() { "_" } else { "" };
-                let variant = format_ident!("{}{}", prefix, snake_to_camel(&entry.name));
+                let prefix =
+                    if entry.rust_name.chars().next().unwrap().is_numeric() { "_" } else { "" };
+                let variant = format_ident!("{}{}", prefix, snake_to_camel(&entry.rust_name));
 
                 quote! {
                     #value => Ok(#ident::#variant)
@@ -100,8 +271,9 @@ impl ToTokens for Enum {
             });
 
             enum_impl = quote! {
-                impl std::convert::TryFrom<u32> for #ident {
+                impl core::convert::TryFrom<u32> for #ident {
                     type Error = ();
+                    #[allow(deprecated)]
                     fn try_from(val: u32) -> Result<#ident, ()> {
                         match val {
                             #(#match_arms,)*
@@ -109,7 +281,7 @@ impl ToTokens for Enum {
                         }
                     }
                 }
-                impl std::convert::From<#ident> for u32 {
+                impl core::convert::From<#ident> for u32 {
                     fn from(val: #ident) -> u32 {
                         val as u32
                     }
@@ -122,29 +294,48 @@ impl ToTokens for Enum {
     }
 }
 
-pub(crate) fn gen_msg_constants(requests: &[Message], events: &[Message]) -> TokenStream {
+pub(crate) fn gen_msg_constants(
+    side: Side,
+    requests: &[Message],
+    events: &[Message],
+) -> TokenStream {
+    // the protocol module of wayland-backend, as re-exported by wayland-client or wayland-server
+    let protocol_path = match side {
+        Side::Client => quote!(super::wayland_client::backend::protocol),
+        Side::Server => quote!(super::wayland_server::backend::protocol),
+    };
     let req_constants = requests.iter().enumerate().map(|(opcode, msg)| {
-        let since_cstname = format_ident!("REQ_{}_SINCE", msg.name.to_ascii_uppercase());
-        let opcode_cstname = format_ident!("REQ_{}_OPCODE", msg.name.to_ascii_uppercase());
+        let since_cstname = format_ident!("REQ_{}_SINCE", msg.rust_name.to_ascii_uppercase());
+        let opcode_cstname = format_ident!("REQ_{}_OPCODE", msg.rust_name.to_ascii_uppercase());
+        let signature_cstname =
+            format_ident!("REQ_{}_SIGNATURE", msg.rust_name.to_ascii_uppercase());
         let since = msg.since;
         let opcode = opcode as u16;
+        let signature = gen_signature(msg, &protocol_path);
         quote! {
             /// The minimal object version supporting this request
             pub const #since_cstname: u32 = #since;
             /// The wire opcode for this request
             pub const #opcode_cstname: u16 = #opcode;
+            /// The wire signature of this request
+            pub const #signature_cstname: &[#protocol_path::ArgumentType] = #signature;
         }
     });
     let evt_constants = events.iter().enumerate().map(|(opcode, msg)| {
-        let since_cstname = format_ident!("EVT_{}_SINCE", msg.name.to_ascii_uppercase());
-        let opcode_cstname = format_ident!("EVT_{}_OPCODE", msg.name.to_ascii_uppercase());
+        let since_cstname = format_ident!("EVT_{}_SINCE", msg.rust_name.to_ascii_uppercase());
+        let opcode_cstname = format_ident!("EVT_{}_OPCODE", msg.rust_name.to_ascii_uppercase());
+        let signature_cstname =
+            format_ident!("EVT_{}_SIGNATURE", msg.rust_name.to_ascii_uppercase());
         let since = msg.since;
         let opcode = opcode as u16;
+        let signature = gen_signature(msg, &protocol_path);
         quote! {
             /// The minimal object version supporting this event
             pub const #since_cstname: u32 = #since;
             /// The wire opcode for this event
             pub const #opcode_cstname: u16 = #opcode;
+            /// The wire signature of this event
+            pub const #signature_cstname: &[#protocol_path::ArgumentType] = #signature;
         }
     });
 
@@ -154,12 +345,122 @@ pub(crate) fn gen_msg_constants(requests: &[Message], events: &[Message]) -> Tok
     }
 }
 
+// the `ArgumentType`s of the arguments of a message, as a slice, given the path of the protocol
+// module of wayland-backend
+pub(crate) fn gen_signature(message: &Message, protocol_path: &TokenStream) -> TokenStream {
+    let signature = message.args.iter().map(|arg| {
+        if arg.typ == Type::NewId && arg.interface.is_none() {
+            // this is a special generic message, it expands to multiple arguments
+            quote! {
+                #protocol_path::ArgumentType::Str(#protocol_path::AllowNull::No),
+                #protocol_path::ArgumentType::Uint,
+                #protocol_path::ArgumentType::NewId
+            }
+        } else {
+            let typ = arg.typ.common_type();
+            if arg.typ.nullable() {
+                if arg.allow_null {
+                    quote! { #protocol_path::ArgumentType::#typ(#protocol_path::AllowNull::Yes) }
+                } else {
+                    quote! { #protocol_path::ArgumentType::#typ(#protocol_path::AllowNull::No) }
+                }
+            } else {
+                quote! { #protocol_path::ArgumentType::#typ }
+            }
+        }
+    });
+    quote! { &[ #(#signature),* ] }
+}
+
+// the type of an argument of the methods sending a message, the file descriptors and objects are
+// borrowed for the given lifetime, or for an elided one
+pub(crate) fn gen_method_arg_type(arg: &Arg, lifetime: Option<&TokenStream>) -> TokenStream {
+    if let Some(ref enu) = arg.rust_enum {
+        return dotted_to_relname(enu);
+    }
+    match arg.typ {
+        Type::Uint => quote! { u32 },
+        Type::Int => quote! { i32 },
+        Type::Fixed => quote! { f64 },
+        Type::String => {
+            if arg.allow_null {
+                quote! { Option<String> }
+            } else {
+                quote! { String }
+            }
+        }
+        Type::Array => {
+            if arg.allow_null {
+                quote! { Option<Vec<u8>> }
+            } else {
+                quote! { Vec<u8> }
+            }
+        }
+        Type::Fd => {
+            let lifetime = lifetime.cloned().unwrap_or_else(|| quote! { '_ });
+            quote! { ::std::os::unix::io::BorrowedFd<#lifetime> }
+        }
+        Type::Object | Type::NewId => {
+            let iface = arg.interface.as_ref().unwrap();
+            let iface_mod = Ident::new(iface, Span::call_site());
+            let iface_type = Ident::new(&snake_to_camel(iface), Span::call_site());
+            if arg.allow_null {
+                quote! { Option<&#lifetime super::#iface_mod::#iface_type> }
+            } else {
+                quote! { &#lifetime super::#iface_mod::#iface_type }
+            }
+        }
+        Type::Destructor => panic!("An argument cannot have type \"destructor\"."),
+    }
+}
+
+// the path of `UnknownVariant`, the error of the enum arguments generated with the `enum_results` option
+pub(crate) fn unknown_variant_path(side: Side) -> TokenStream {
+    match side {
+        Side::Client => quote! { super::wayland_client::UnknownVariant },
+        Side::Server => quote! { super::wayland_server::UnknownVariant },
+    }
+}
+
+// the module of the conversions of the enum arguments generated with the `enum_results` option
+fn enum_results_helpers(side: Side) -> TokenStream {
+    match side {
+        Side::Client => quote! { super::wayland_client::__enum_results },
+        Side::Server => quote! { super::wayland_server::__enum_results },
+    }
+}
+
 pub(crate) fn gen_message_enum(
     name: &Ident,
     side: Side,
     receiver: bool,
-    messages: &[Message],
+    borrowed: bool,
+    interface: &Interface,
+    options: &Options,
 ) -> TokenStream {
+    // the requests are sent by the clients and received by the servers
+    let is_request = (side == Side::Client) != receiver;
+    let (messages, messages_field) = if is_request {
+        (&interface.requests, format_ident!("requests"))
+    } else {
+        (&interface.events, format_ident!("events"))
+    };
+    let object = Ident::new(&snake_to_camel(&interface.name), Span::call_site());
+    let iface_const_name = format_ident!("{}_INTERFACE", interface.name.to_ascii_uppercase());
+    let protocol = match side {
+        Side::Client => quote!(super::wayland_client::backend::protocol),
+        Side::Server => quote!(super::wayland_server::backend::protocol),
+    };
+    // the borrowed messages are not serialized
+    let serde = if borrowed { &Gate::Off } else { &options.serde };
+    let enum_results = options.enum_results;
+    // the module of the serialization helpers of wayland-client or wayland-server
+    let serde_helpers = match side {
+        Side::Client => "super::wayland_client::__serde",
+        Side::Server => "super::wayland_server::__serde",
+    };
+    let unknown_variant = unknown_variant_path(side);
+
     let variants = messages
         .iter()
         .map(|msg| {
@@ -179,101 +480,154 @@ pub(crate) fn gen_message_enum(
                 write!(docs, "\nOnly available since version {} of the interface", msg.since)
                     .unwrap();
             }
+            if let Some(version) = msg.deprecated_since {
+                write!(docs, "\nDeprecated since version {} of the interface", version).unwrap();
+            }
 
             let doc_attr = to_doc_attr(&docs);
-            let msg_name = Ident::new(&snake_to_camel(&msg.name), Span::call_site());
-            let msg_variant_decl =
-                if msg.args.is_empty() {
-                    msg_name.into_token_stream()
-                } else {
-                    let fields = msg.args.iter().flat_map(|arg| {
-                let field_name =
-                    format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
-                let field_type_inner = if let Some(ref enu) = arg.enum_ {
-                    let enum_type = dotted_to_relname(enu);
-                    quote! { WEnum<#enum_type> }
-                } else {
-                    match arg.typ {
-                        Type::Uint => quote! { u32 },
-                        Type::Int => quote! { i32 },
-                        Type::Fixed => quote! { f64 },
-                        Type::String => quote! { String },
-                        Type::Array => quote! { Vec<u8> },
-                        Type::Fd => {
-                            if receiver {
-                                quote! { OwnedFd }
-                            } else {
-                                quote! { std::os::unix::io::BorrowedFd<'a> }
-                            }
+            let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+            let msg_variant_decl = if msg.args.is_empty() {
+                msg_name.into_token_stream()
+            } else {
+                let mut fd_index = 0u32;
+                let fields = msg.args.iter().flat_map(|arg| {
+                    let field_name = format_ident!(
+                        "{}{}",
+                        if is_keyword(&arg.rust_name) { "_" } else { "" },
+                        arg.rust_name
+                    );
+                    let field_type_inner = if let Some(ref enu) = arg.rust_enum {
+                        let enum_type = dotted_to_relname(enu);
+                        if arg.bitfield {
+                            quote! { #enum_type }
+                        } else if enum_results {
+                            quote! { Result<#enum_type, #unknown_variant> }
+                        } else {
+                            quote! { WEnum<#enum_type> }
                         }
-                        Type::Object => {
-                            if let Some(ref iface) = arg.interface {
-                                let iface_mod = Ident::new(iface, Span::call_site());
-                                let iface_type =
-                                    Ident::new(&snake_to_camel(iface), Span::call_site());
-                                quote! { super::#iface_mod::#iface_type }
-                            } else if side == Side::Client {
-                                quote! { super::wayland_client::ObjectId }
-                            } else {
-                                quote! { super::wayland_server::ObjectId }
+                    } else {
+                        match arg.typ {
+                            Type::Uint => quote! { u32 },
+                            Type::Int => quote! { i32 },
+                            Type::Fixed => quote! { f64 },
+                            Type::String if borrowed => quote! { std::borrow::Cow<'a, str> },
+                            Type::String => quote! { String },
+                            Type::Array if borrowed => quote! { &'a [u8] },
+                            Type::Array => quote! { Vec<u8> },
+                            Type::Fd => {
+                                if receiver {
+                                    quote! { OwnedFd }
+                                } else {
+                                    quote! { std::os::unix::io::BorrowedFd<'a> }
+                                }
                             }
-                        }
-                        Type::NewId if !receiver && side == Side::Client => {
-                            // Client-side sending does not have a pre-existing object
-                            // so skip serializing it
-                            if arg.interface.is_some() {
-                                return None;
-                            } else {
-                                quote! { (&'static Interface, u32) }
+                            Type::Object => {
+                                if let Some(ref iface) = arg.interface {
+                                    let iface_mod = Ident::new(iface, Span::call_site());
+                                    let iface_type =
+                                        Ident::new(&snake_to_camel(iface), Span::call_site());
+                                    quote! { super::#iface_mod::#iface_type }
+                                } else {
+                                    quote! { ObjectId }
+                                }
                             }
-                        }
-                        Type::NewId => {
-                            if let Some(ref iface) = arg.interface {
-                                let iface_mod = Ident::new(iface, Span::call_site());
-                                let iface_type =
-                                    Ident::new(&snake_to_camel(iface), Span::call_site());
-                                if receiver && side == Side::Server {
-                                    quote! { New<super::#iface_mod::#iface_type> }
+                            Type::NewId if !receiver && side == Side::Client => {
+                                // Client-side sending does not have a pre-existing object
+                                // so skip serializing it
+                                if arg.interface.is_some() {
+                                    return None;
                                 } else {
-                                    quote! { super::#iface_mod::#iface_type }
+                                    quote! { (&'static Interface, u32) }
                                 }
-                            } else {
-                                // bind-like function
-                                if side == Side::Client {
-                                    quote! { (String, u32, super::wayland_client::ObjectId) }
+                            }
+                            Type::NewId => {
+                                if let Some(ref iface) = arg.interface {
+                                    let iface_mod = Ident::new(iface, Span::call_site());
+                                    let iface_type =
+                                        Ident::new(&snake_to_camel(iface), Span::call_site());
+                                    if receiver && side == Side::Server {
+                                        quote! { New<super::#iface_mod::#iface_type> }
+                                    } else {
+                                        quote! { super::#iface_mod::#iface_type }
+                                    }
                                 } else {
-                                    quote! { (String, u32, super::wayland_server::ObjectId) }
+                                    // bind-like function
+                                    quote! { (String, u32, ObjectId) }
                                 }
                             }
+                            Type::Destructor => {
+                                panic!("An argument cannot have type \"destructor\".")
+                            }
                         }
-                        Type::Destructor => panic!("An argument cannot have type \"destructor\"."),
-                    }
-                };
+                    };
 
-                let field_type = if arg.allow_null {
-                    quote! { Option<#field_type_inner> }
-                } else {
-                    field_type_inner.into_token_stream()
-                };
+                    let field_type = if arg.allow_null {
+                        quote! { Option<#field_type_inner> }
+                    } else {
+                        field_type_inner.into_token_stream()
+                    };
 
-                let doc_attr = arg
-                    .description
-                    .as_ref()
-                    .map(description_to_doc_attr)
-                    .or_else(|| arg.summary.as_ref().map(|s| to_doc_attr(s)));
+                    let doc_attr = arg
+                        .description
+                        .as_ref()
+                        .map(description_to_doc_attr)
+                        .or_else(|| arg.summary.as_ref().map(|s| to_doc_attr(s)));
 
-                Some(quote! {
-                    #doc_attr
-                    #field_name: #field_type
-                })
-            });
+                    let serde_attr = if serde.is_on() {
+                        let helper = if arg.enum_.is_some() && enum_results && !arg.bitfield {
+                            Some("enum_result".to_owned())
+                        } else if arg.enum_.is_some() {
+                            Some("enum_value".to_owned())
+                        } else {
+                            match arg.typ {
+                                Type::Fd => {
+                                    fd_index += 1;
+                                    Some(format!("fd::<{}, _, _>", fd_index - 1))
+                                }
+                                Type::Object if arg.interface.is_some() => Some(
+                                    if arg.allow_null { "optional_object" } else { "object" }
+                                        .to_owned(),
+                                ),
+                                Type::Object => Some(
+                                    if arg.allow_null { "optional_object_id" } else { "object_id" }
+                                        .to_owned(),
+                                ),
+                                Type::NewId if arg.interface.is_none() => Some(
+                                    if side == Side::Client && !receiver {
+                                        "interface_version"
+                                    } else {
+                                        "bind_args"
+                                    }
+                                    .to_owned(),
+                                ),
+                                Type::NewId if receiver && side == Side::Server => {
+                                    Some("new_object".to_owned())
+                                }
+                                Type::NewId => Some("object".to_owned()),
+                                _ => None,
+                            }
+                        };
+                        helper.and_then(|helper| {
+                            let path = format!("{}::{}", serde_helpers, helper);
+                            serde.attr(quote! { serde(serialize_with = #path) })
+                        })
+                    } else {
+                        None
+                    };
 
-                    quote! {
-                        #msg_name {
-                            #(#fields,)*
-                        }
+                    Some(quote! {
+                        #doc_attr
+                        #serde_attr
+                        #field_name: #field_type
+                    })
+                });
+
+                quote! {
+                    #msg_name {
+                        #(#fields,)*
                     }
-                };
+                }
+            };
 
             quote! {
                 #doc_attr
@@ -283,7 +637,7 @@ pub(crate) fn gen_message_enum(
         .collect::<Vec<_>>();
 
     let opcodes = messages.iter().enumerate().map(|(opcode, msg)| {
-        let msg_name = Ident::new(&snake_to_camel(&msg.name), Span::call_site());
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
         let opcode = opcode as u16;
         if msg.args.is_empty() {
             quote! {
@@ -296,21 +650,57 @@ pub(crate) fn gen_message_enum(
         }
     });
 
+    let names = messages.iter().map(|msg| {
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+        let name_str = &msg.name;
+        if msg.args.is_empty() {
+            quote! {
+                #name::#msg_name => #name_str
+            }
+        } else {
+            quote! {
+                #name::#msg_name { .. } => #name_str
+            }
+        }
+    });
+
+    let sinces = messages.iter().map(|msg| {
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+        let since = msg.since;
+        if msg.args.is_empty() {
+            quote! {
+                #name::#msg_name => #since
+            }
+        } else {
+            quote! {
+                #name::#msg_name { .. } => #since
+            }
+        }
+    });
+
     // Placeholder to allow generic argument to be added later, without ABI
     // break.
     // TODO Use never type.
-    let (generic, phantom_variant, phantom_case) = if !receiver {
+    let phantom_serde_attr = serde.attr(quote! { serde(skip) });
+    let (generic, phantom_variant, phantom_case) = if !receiver || borrowed {
         (
             quote! { 'a },
-            quote! { #[doc(hidden)] __phantom_lifetime { phantom: std::marker::PhantomData<&'a ()>, never: std::convert::Infallible } },
+            quote! { #[doc(hidden)] #phantom_serde_attr __phantom_lifetime { phantom: std::marker::PhantomData<&'a ()>, never: std::convert::Infallible } },
             quote! { #name::__phantom_lifetime { never, .. } => match never {} },
         )
     } else {
         (quote! {}, quote! {}, quote! {})
     };
 
+    let serde_crate = format!("{}::serde", serde_helpers);
+    let derive: TokenStream = format!("{}::serde::Serialize", serde_helpers).parse().unwrap();
+    let serde_derive = serde.attr(quote! { derive(#derive) });
+    let serde_crate = serde.attr(quote! { serde(crate = #serde_crate) });
+
     quote! {
         #[derive(Debug)]
+        #serde_derive
+        #serde_crate
         #[non_exhaustive]
         pub enum #name<#generic> {
             #(#variants,)*
@@ -325,11 +715,60 @@ pub(crate) fn gen_message_enum(
                     #phantom_case
                 }
             }
+
+            #[doc="Get the name of this message, as in the protocol description"]
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    #(#names,)*
+                    #phantom_case
+                }
+            }
+
+            #[doc="Get the minimal object version supporting this message"]
+            pub fn since(&self) -> u32 {
+                match *self {
+                    #(#sinces,)*
+                    #phantom_case
+                }
+            }
+
+            #[doc="Check whether the version of this object supports this message"]
+            pub fn is_supported(&self, object: &#object) -> bool {
+                object.version() >= self.since()
+            }
+        }
+
+        impl<#generic> #protocol::MessageInfo for #name<#generic> {
+            fn interface(&self) -> &'static #protocol::Interface {
+                &super::#iface_const_name
+            }
+
+            fn desc(&self) -> &'static #protocol::MessageDesc {
+                &super::#iface_const_name.#messages_field[Self::opcode(self) as usize]
+            }
+
+            fn opcode(&self) -> u16 {
+                Self::opcode(self)
+            }
+
+            fn name(&self) -> &'static str {
+                Self::name(self)
+            }
+
+            fn since(&self) -> u32 {
+                Self::since(self)
+            }
         }
     }
 }
 
-pub(crate) fn gen_parse_body(interface: &Interface, side: Side) -> TokenStream {
+pub(crate) fn gen_parse_body(
+    interface: &Interface,
+    side: Side,
+    borrowed: bool,
+    options: &Options,
+) -> TokenStream {
+    let enum_results = options.enum_results;
     let msgs = match side {
         Side::Client => &interface.events,
         Side::Server => &interface.requests,
@@ -343,29 +782,35 @@ pub(crate) fn gen_parse_body(interface: &Interface, side: Side) -> TokenStream {
     );
     let msg_type = Ident::new(
         match side {
+            Side::Client if borrowed => "BorrowedEvent",
             Side::Client => "Event",
             Side::Server => "Request",
         },
         Span::call_site(),
     );
+    let arg_type = if borrowed {
+        quote! { super::wayland_client::backend::protocol::BorrowedArgument }
+    } else {
+        quote! { Argument }
+    };
 
     let match_arms = msgs.iter().enumerate().map(|(opcode, msg)| {
         let opcode = opcode as u16;
-        let msg_name = Ident::new(&snake_to_camel(&msg.name), Span::call_site());
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
         let args_pat = msg.args.iter().map(|arg| {
             let arg_name = Ident::new(
-                &format!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name),
+                &format!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name),
                 Span::call_site(),
             );
             match arg.typ {
-                Type::Uint => quote!{ Some(Argument::Uint(#arg_name)) },
-                Type::Int => quote!{ Some(Argument::Int(#arg_name)) },
-                Type::String => quote!{ Some(Argument::Str(#arg_name)) },
-                Type::Fixed => quote!{ Some(Argument::Fixed(#arg_name)) },
-                Type::Array => quote!{ Some(Argument::Array(#arg_name)) },
-                Type::Object => quote!{ Some(Argument::Object(#arg_name)) },
-                Type::NewId => quote!{ Some(Argument::NewId(#arg_name)) },
-                Type::Fd => quote!{ Some(Argument::Fd(#arg_name)) },
+                Type::Uint => quote!{ Some(#arg_type::Uint(#arg_name)) },
+                Type::Int => quote!{ Some(#arg_type::Int(#arg_name)) },
+                Type::String => quote!{ Some(#arg_type::Str(#arg_name)) },
+                Type::Fixed => quote!{ Some(#arg_type::Fixed(#arg_name)) },
+                Type::Array => quote!{ Some(#arg_type::Array(#arg_name)) },
+                Type::Object => quote!{ Some(#arg_type::Object(#arg_name)) },
+                Type::NewId => quote!{ Some(#arg_type::NewId(#arg_name)) },
+                Type::Fd => quote!{ Some(#arg_type::Fd(#arg_name)) },
                 Type::Destructor => panic!("Argument {}.{}.{} has type destructor ?!", interface.name, msg.name, arg.name),
             }
         });
@@ -373,13 +818,31 @@ pub(crate) fn gen_parse_body(interface: &Interface, side: Side) -> TokenStream {
         let args_iter = msg.args.iter().map(|_| quote!{ arg_iter.next() });
 
         let arg_names = msg.args.iter().map(|arg| {
-            let arg_name = format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
-            if arg.enum_.is_some() {
+            let arg_name = format_ident!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name);
+            if let (Some(ref enu), true) = (&arg.rust_enum, arg.bitfield) {
+                // the unknown bits are kept rather than rejecting the whole value
+                let enum_type = dotted_to_relname(enu);
+                quote! { #arg_name: #enum_type::from_bits_retain(#arg_name as u32) }
+            } else if arg.enum_.is_some() && enum_results {
+                let helpers = enum_results_helpers(side);
+                quote! { #arg_name: #helpers::parse(#arg_name as u32) }
+            } else if arg.enum_.is_some() {
                 quote! { #arg_name: From::from(#arg_name as u32) }
             } else {
                 match arg.typ {
                     Type::Uint | Type::Int | Type::Fd => quote!{ #arg_name },
                     Type::Fixed => quote!{ #arg_name: (#arg_name as f64) / 256.},
+                    Type::String if borrowed => {
+                        if arg.allow_null {
+                            quote! {
+                                #arg_name: #arg_name.map(|s| String::from_utf8_lossy(s.to_bytes()))
+                            }
+                        } else {
+                            quote! {
+                                #arg_name: String::from_utf8_lossy(#arg_name.unwrap().to_bytes())
+                            }
+                        }
+                    },
                     Type::String => {
                         if arg.allow_null {
                             quote! {
@@ -457,6 +920,7 @@ pub(crate) fn gen_parse_body(interface: &Interface, side: Side) -> TokenStream {
                             }
                         }
                     },
+                    Type::Array if borrowed => quote! { #arg_name },
                     Type::Array => {
                         if arg.allow_null {
                             quote! { if #arg_name.len() == 0 { None } else { Some(*#arg_name) } }
@@ -490,7 +954,8 @@ pub(crate) fn gen_parse_body(interface: &Interface, side: Side) -> TokenStream {
     }
 }
 
-pub(crate) fn gen_write_body(interface: &Interface, side: Side) -> TokenStream {
+pub(crate) fn gen_write_body(interface: &Interface, side: Side, options: &Options) -> TokenStream {
+    let enum_results = options.enum_results;
     let msgs = match side {
         Side::Client => &interface.requests,
         Side::Server => &interface.events,
@@ -503,18 +968,26 @@ pub(crate) fn gen_write_body(interface: &Interface, side: Side) -> TokenStream {
         Span::call_site(),
     );
     let arms = msgs.iter().enumerate().map(|(opcode, msg)| {
-        let msg_name = Ident::new(&snake_to_camel(&msg.name), Span::call_site());
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
         let opcode = opcode as u16;
         let arg_names = msg.args.iter().flat_map(|arg| {
             if arg.typ == Type::NewId && arg.interface.is_some() && side == Side::Client {
                 None
             } else {
-                Some(format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name))
+                Some(format_ident!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name))
             }
         });
         let mut child_spec = None;
         let args = msg.args.iter().flat_map(|arg| {
-            let arg_name = format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
+            let arg_name = format_ident!("{}{}", if is_keyword(&arg.rust_name) { "_" } else { "" }, arg.rust_name);
+
+            if arg.enum_.is_some() && !arg.bitfield && enum_results {
+                let helpers = enum_results_helpers(side);
+                return match arg.typ {
+                    Type::Int => vec![quote!{ Argument::Int(#helpers::to_raw(#arg_name) as i32) }],
+                    _ => vec![quote!{ Argument::Uint(#helpers::to_raw(#arg_name)) }],
+                };
+            }
 
             match arg.typ {
                 Type::Int => vec![if arg.enum_.is_some() { quote!{ Argument::Int(Into::<u32>::into(#arg_name) as i32) } } else { quote!{ Argument::Int(#arg_name) } }],
@@ -629,3 +1102,146 @@ pub(crate) fn gen_write_body(interface: &Interface, side: Side) -> TokenStream {
         }
     }
 }
+
+// the `arbitrary()` function of the enum of the messages sent by a side, with the `arbitrary` option
+pub(crate) fn gen_arbitrary_messages(
+    protocol: &Protocol,
+    interface: &Interface,
+    side: Side,
+    enum_results: bool,
+    cfg: Option<TokenStream>,
+) -> TokenStream {
+    let (msgs, msg_type, doc) = match side {
+        Side::Client => (
+            &interface.requests,
+            Ident::new("Request", Span::call_site()),
+            "Generate an arbitrary request supported by the given version of this interface",
+        ),
+        Side::Server => (
+            &interface.events,
+            Ident::new("Event", Span::call_site()),
+            "Generate an arbitrary event supported by the given version of this interface",
+        ),
+    };
+    let helpers = match side {
+        Side::Client => quote! { super::wayland_client::__arbitrary },
+        Side::Server => quote! { super::wayland_server::__arbitrary },
+    };
+    let doc_attr = to_doc_attr(&format!(
+        "{}\n\nThe objects and file descriptors of its arguments are taken from `objects`, and the \
+         values of its other arguments are valid for the protocol.",
+        doc
+    ));
+
+    if msgs.is_empty() {
+        return quote! {
+            #cfg
+            impl<'a> #msg_type<'a> {
+                #doc_attr
+                pub fn arbitrary(
+                    _u: &mut #helpers::arbitrary::Unstructured<'_>,
+                    _version: u32,
+                    _objects: &mut impl #helpers::ArbitraryObjects<'a>,
+                ) -> #helpers::arbitrary::Result<Self> {
+                    Err(#helpers::arbitrary::Error::EmptyChoose)
+                }
+            }
+        };
+    }
+
+    let sinces = msgs.iter().map(|msg| msg.since);
+    let count = msgs.len();
+    let uses_objects = msgs.iter().flat_map(|msg| &msg.args).any(|arg| {
+        matches!(arg.typ, Type::Object | Type::Fd)
+            || (arg.typ == Type::NewId && (side == Side::Server) == arg.interface.is_some())
+    });
+    let objects = if uses_objects { format_ident!("objects") } else { format_ident!("_objects") };
+    let arms = msgs.iter().enumerate().map(|(opcode, msg)| {
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+        let fields = msg.args.iter().flat_map(|arg| {
+            let field_name = format_ident!(
+                "{}{}",
+                if is_keyword(&arg.rust_name) { "_" } else { "" },
+                arg.rust_name
+            );
+            // the result of generating the value of the argument
+            let value = if let (Some(enu), Some(rust_enum)) = (&arg.enum_, &arg.rust_enum) {
+                let enum_type = dotted_to_relname(rust_enum);
+                if arg.bitfield {
+                    quote! { u.arbitrary().map(#enum_type::from_bits_truncate) }
+                } else {
+                    // the enums of the protocols not given to the macro are only known by their type
+                    let values = find_enum(protocol, interface, enu)
+                        .into_iter()
+                        .flat_map(|enu| enu.entries.iter().map(|entry| entry.value));
+                    let value = quote! { #helpers::enum_value::<#enum_type>(u, &[#(#values),*]) };
+                    if enum_results {
+                        quote! { #value.map(Ok) }
+                    } else {
+                        quote! { #value.map(WEnum::Value) }
+                    }
+                }
+            } else {
+                match arg.typ {
+                    Type::Int | Type::Uint | Type::Array => quote! { u.arbitrary() },
+                    Type::Fixed => quote! { #helpers::fixed(u) },
+                    Type::String => quote! { #helpers::string(u) },
+                    Type::Fd => quote! { objects.fd(u) },
+                    Type::Object if arg.interface.is_some() => quote! { objects.object(u) },
+                    Type::Object => quote! { objects.object_id(u) },
+                    Type::NewId if side == Side::Client => {
+                        if arg.interface.is_some() {
+                            // the object is created when sending the request
+                            return None;
+                        }
+                        quote! { objects.new_object_interface(u) }
+                    }
+                    Type::NewId if arg.interface.is_some() => quote! { objects.new_object(u) },
+                    // no protocol creates objects of any interface from an event
+                    Type::NewId => quote! { Err(#helpers::arbitrary::Error::IncorrectFormat) },
+                    Type::Destructor => unreachable!(),
+                }
+            };
+            let value = if arg.allow_null {
+                quote! { #helpers::optional(u, |u| #value)? }
+            } else {
+                quote! { #value? }
+            };
+            Some(quote! { #field_name: #value })
+        });
+        quote! {
+            #opcode => #msg_type::#msg_name { #(#fields,)* }
+        }
+    });
+
+    quote! {
+        #cfg
+        impl<'a> #msg_type<'a> {
+            #doc_attr
+            pub fn arbitrary(
+                u: &mut #helpers::arbitrary::Unstructured<'_>,
+                version: u32,
+                #objects: &mut impl #helpers::ArbitraryObjects<'a>,
+            ) -> #helpers::arbitrary::Result<Self> {
+                let sinces: [u32; #count] = [#(#sinces),*];
+                let supported =
+                    (0..#count).filter(|&opcode| sinces[opcode] <= version).collect::<Vec<_>>();
+                Ok(match *u.choose(&supported)? {
+                    #(#arms,)*
+                    _ => unreachable!(),
+                })
+            }
+        }
+    }
+}
+
+// find an enum referred to by an argument of an interface, as `name` or `interface.name`
+fn find_enum<'a>(protocol: &'a Protocol, interface: &'a Interface, name: &str) -> Option<&'a Enum> {
+    let (interface, name) = match name.split_once('.') {
+        Some((iface, name)) => {
+            (protocol.interfaces.iter().find(|other| other.name == iface)?, name)
+        }
+        None => (interface, name),
+    };
+    interface.enums.iter().find(|enu| enu.name == name)
+}
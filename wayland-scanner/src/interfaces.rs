@@ -7,14 +7,108 @@ use quote::{format_ident, quote};
 pub fn generate(protocol: &Protocol, with_c_interfaces: bool) -> TokenStream {
     let interfaces =
         protocol.interfaces.iter().map(|iface| generate_interface(iface, with_c_interfaces));
+    let list = generate_interface_list(protocol);
+    let arg_enums = generate_arg_enums(protocol);
     if with_c_interfaces {
         let prefix = super::c_interfaces::generate_interfaces_prefix(protocol);
         quote! {
             #prefix
             #(#interfaces)*
+            #list
+            #arg_enums
         }
     } else {
-        interfaces.collect()
+        quote! {
+            #(#interfaces)*
+            #list
+            #arg_enums
+        }
+    }
+}
+
+// The list of the interfaces of the protocol, to register them for `Interface::find()`
+fn generate_interface_list(protocol: &Protocol) -> TokenStream {
+    let const_names = protocol
+        .interfaces
+        .iter()
+        .map(|iface| format_ident!("{}_INTERFACE", iface.name.to_ascii_uppercase()));
+    quote! {
+        /// All the interfaces of this protocol
+        pub static INTERFACES: &[&wayland_backend::protocol::Interface] = &[ #(&#const_names),* ];
+    }
+}
+
+// The enums of the message arguments, to register them for the debug output
+fn generate_arg_enums(protocol: &Protocol) -> TokenStream {
+    // the enums of this protocol, by their `interface.enum` name
+    let enums = protocol
+        .interfaces
+        .iter()
+        .flat_map(|iface| {
+            iface.enums.iter().map(move |enu| (format!("{}.{}", iface.name, enu.name), enu))
+        })
+        .collect::<Vec<_>>();
+
+    let mut used = Vec::new();
+    let mut arg_enums = Vec::new();
+    for iface in &protocol.interfaces {
+        let requests = iface.requests.iter().map(|msg| (quote!(Request), msg));
+        let events = iface.events.iter().map(|msg| (quote!(Event), msg));
+        for (direction, msg) in requests.chain(events) {
+            // the index of the argument on the wire, the untyped new_id ones span three arguments
+            let mut index = 0usize;
+            for arg in &msg.args {
+                if let Some(ref enu) = arg.enum_ {
+                    let name = if enu.contains('.') {
+                        enu.clone()
+                    } else {
+                        format!("{}.{}", iface.name, enu)
+                    };
+                    // the enums of other protocols are not known
+                    if let Some((name, _)) = enums.iter().find(|(known, _)| *known == name) {
+                        let static_name =
+                            format_ident!("{}_ENUM", name.replace('.', "_").to_ascii_uppercase());
+                        let (iface_name, msg_name) = (&iface.name, &msg.name);
+                        arg_enums.push(quote! {
+                            wayland_backend::protocol::ArgEnum {
+                                interface: #iface_name,
+                                direction: wayland_backend::protocol::Direction::#direction,
+                                message: #msg_name,
+                                arg: #index,
+                                desc: &#static_name,
+                            }
+                        });
+                        if !used.contains(name) {
+                            used.push(name.clone());
+                        }
+                    }
+                }
+                index += if arg.typ == Type::NewId && arg.interface.is_none() { 3 } else { 1 };
+            }
+        }
+    }
+
+    let descs = used.iter().map(|name| {
+        let enu = enums.iter().find(|(known, _)| known == name).unwrap().1;
+        let static_name = format_ident!("{}_ENUM", name.replace('.', "_").to_ascii_uppercase());
+        let bitfield = enu.bitfield;
+        let entries = enu.entries.iter().map(|entry| {
+            let (entry_name, value) = (&entry.name, entry.value);
+            quote! { (#entry_name, #value) }
+        });
+        quote! {
+            static #static_name: wayland_backend::protocol::EnumDesc = wayland_backend::protocol::EnumDesc {
+                name: #name,
+                bitfield: #bitfield,
+                entries: &[ #(#entries),* ],
+            };
+        }
+    });
+
+    quote! {
+        #(#descs)*
+        /// The message arguments of this protocol referring to one of its enums
+        pub static ARG_ENUMS: &[wayland_backend::protocol::ArgEnum] = &[ #(#arg_enums),* ];
     }
 }
 
@@ -58,27 +152,7 @@ fn build_messagedesc_list(list: &[Message]) -> TokenStream {
         let name = &message.name;
         let since = message.since;
         let is_destructor = message.typ == Some(Type::Destructor);
-        let signature = message.args.iter().map(|arg| {
-            if arg.typ == Type::NewId && arg.interface.is_none() {
-                // this is a special generic message, it expands to multiple arguments
-                quote! {
-                    wayland_backend::protocol::ArgumentType::Str(wayland_backend::protocol::AllowNull::No),
-                    wayland_backend::protocol::ArgumentType::Uint,
-                    wayland_backend::protocol::ArgumentType::NewId
-                }
-            } else {
-                let typ = arg.typ.common_type();
-                if arg.typ.nullable() {
-                    if arg.allow_null {
-                        quote! { wayland_backend::protocol::ArgumentType::#typ(wayland_backend::protocol::AllowNull::Yes) }
-                    } else {
-                        quote! { wayland_backend::protocol::ArgumentType::#typ(wayland_backend::protocol::AllowNull::No) }
-                    }
-                } else {
-                    quote! { wayland_backend::protocol::ArgumentType::#typ }
-                }
-            }
-        });
+        let signature = crate::common::gen_signature(message, &quote!(wayland_backend::protocol));
         let child_interface = match message
             .args
             .iter()
@@ -105,7 +179,7 @@ fn build_messagedesc_list(list: &[Message]) -> TokenStream {
         quote! {
             wayland_backend::protocol::MessageDesc {
                 name: #name,
-                signature: &[ #(#signature),* ],
+                signature: #signature,
                 since: #since,
                 is_destructor: #is_destructor,
                 child_interface: #child_interface,
@@ -125,18 +199,22 @@ mod tests {
     fn interface_gen() {
         let protocol_file =
             std::fs::File::open("./tests/scanner_assets/test-protocol.xml").unwrap();
-        let protocol_parsed = crate::parse::parse(protocol_file);
-        let generated: String = super::generate(&protocol_parsed, true).to_string();
-        let generated = crate::format_rust_code(&generated);
+        let protocol_parsed = crate::parse::parse(protocol_file).unwrap();
+        crate::check_reference("test-interfaces.rs", &super::generate(&protocol_parsed, true));
+    }
 
-        let reference =
-            std::fs::read_to_string("./tests/scanner_assets/test-interfaces.rs").unwrap();
-        let reference = crate::format_rust_code(&reference);
+    #[test]
+    fn interface_gen_without_c_interfaces() {
+        let protocol_file =
+            std::fs::File::open("./tests/scanner_assets/test-protocol.xml").unwrap();
+        let protocol_parsed = crate::parse::parse(protocol_file).unwrap();
+        let generated: String = super::generate(&protocol_parsed, false).to_string();
+        let generated = crate::format_rust_code(&generated);
 
-        if reference != generated {
-            let diff = similar::TextDiff::from_lines(&reference, &generated);
-            print!("{}", diff.unified_diff().context_radius(10).header("reference", "generated"));
-            panic!("Generated does not match reference!")
-        }
+        // the tables of libwayland are omitted, but not the descriptions of the interfaces
+        assert!(!generated.contains("wl_interface"));
+        assert!(!generated.contains("wl_message"));
+        assert_eq!(generated.matches("c_ptr: None").count(), protocol_parsed.interfaces.len());
+        assert!(generated.contains("pub static INTERFACES"));
     }
 }
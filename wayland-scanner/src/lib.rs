@@ -35,11 +35,130 @@
 //!     wayland_scanner::generate_client_code!("./path/to/the/protocol.xml");
 //! }
 //! ```
+//!
+//! ## Interfaces of other crates
+//!
+//! The generated code refers to the interfaces it does not define by name, relatively to its parent
+//! module: the `xdg_surface` module for the `XdgSurface` type and its enums, and the
+//! `XDG_SURFACE_INTERFACE` and `xdg_surface_interface` statics in the `__interfaces` module. A
+//! protocol extending the ones of another crate thus only needs to import them, rather than
+//! generating them a second time:
+//!
+//! ```rust,ignore
+//! pub mod my_protocol {
+//!     use wayland_client;
+//!     use wayland_client::protocol::*;
+//!     use wayland_protocols::xdg::shell::client::*;
+//!
+//!     pub mod __interfaces {
+//!         use wayland_client::protocol::__interfaces::*;
+//!         use wayland_protocols::xdg::shell::client::__interfaces::*;
+//!         wayland_scanner::generate_interfaces!("./path/to/the/protocol.xml");
+//!     }
+//!     use self::__interfaces::*;
+//!
+//!     wayland_scanner::generate_client_code!("./path/to/the/protocol.xml");
+//! }
+//! ```
+//!
+//! Single interfaces can be imported the same way, for example with
+//! `use wayland_protocols::xdg::shell::client::xdg_surface;`.
+//!
+//! ## Protocol suites
+//!
+//! The macros also accept a list of XML files instead of a single one, for the protocols referring
+//! to the interfaces and enums of each other. The files are processed as a single protocol, whose
+//! code is generated in the same module, so the files can be given in any order:
+//!
+//! ```rust,ignore
+//! pub mod my_suite {
+//!     use wayland_client;
+//!     use wayland_client::protocol::*;
+//!
+//!     pub mod __interfaces {
+//!         use wayland_client::protocol::__interfaces::*;
+//!         wayland_scanner::generate_interfaces!(["./protocols/suite-a.xml", "./protocols/suite-b.xml"]);
+//!     }
+//!     use self::__interfaces::*;
+//!
+//!     wayland_scanner::generate_client_code!(["./protocols/suite-a.xml", "./protocols/suite-b.xml"]);
+//! }
+//! ```
+//!
+//! An interface cannot be defined by several of the files. The protocol-wide items, such as the
+//! `delegate_<protocol>!` macro of the `delegates` option, are named after the first file.
+//!
+//! ## Layout of the generated code
+//!
+//! The code generation macros accept options shaping the API of the generated modules, rather than
+//! re-exporting them by hand:
+//!
+//! - `visibility = "pub(crate)"`: declare the modules of the interfaces as `pub(crate)` instead of
+//!   `pub`, for the crates using a protocol internally without exposing it.
+//! - `strip_prefix = "<prefix>"`: remove a prefix from the names of the modules of the interfaces,
+//!   for example `strip_prefix = "zwp_"` exposes the `zwp_linux_dmabuf_v1` module as `linux_dmabuf_v1`.
+//!   The module is still generated under its original name, hidden from the documentation, as the
+//!   generated code refers to it by this name.
+//! - `reexport_types`: re-export the type of each interface alongside its module, for example
+//!   `wl_output::WlOutput` as `WlOutput`.
+//!
+//! ```rust,ignore
+//! wayland_scanner::generate_client_code!(
+//!     "./path/to/the/protocol.xml",
+//!     visibility = "pub(crate)",
+//!     strip_prefix = "zwp_",
+//!     reexport_types
+//! );
+//! ```
+//!
+//! ## Renaming the items of a protocol
+//!
+//! The names of the generated items are derived from the protocol file: the names which are rust
+//! keywords are prefixed by `_`, as are the enum entries starting with a digit, and the names
+//! differing only by their case or their underscores are rejected as they would convert to the same
+//! camel case name. The `rename = "<path>=<new_name>"` option of the code generation macros, which
+//! can be given several times, renames an item of the protocol before generating its code. The path
+//! of the item is `<interface>.request.<name>`, `<interface>.event.<name>` or
+//! `<interface>.enum.<name>`, optionally followed by the name of an argument or an entry, using the
+//! names of the protocol file, and the new name is given in snake case like the names of the file:
+//!
+//! ```rust,ignore
+//! wayland_scanner::generate_client_code!(
+//!     "./path/to/the/protocol.xml",
+//!     rename = "xdg_toplevel.request.move=start_move",
+//!     rename = "xdg_toplevel.enum.resize_edge.none=no_edge",
+//! );
+//! ```
+//!
+//! The renames only apply to the names of the generated rust items: the interfaces generated by
+//! [`generate_interfaces!`], the `name()` of the messages and the debug output keep the names of the
+//! protocol file.
+//!
+//! ## Gating interfaces behind cargo features
+//!
+//! The `feature = "<interface>=<feature>"` option of the code generation macros, which can be given
+//! several times, generates the module of an interface under a `#[cfg(feature = "<feature>")]`
+//! attribute, so that a crate providing many protocols can offer a cargo feature for some of their
+//! interfaces without splitting the protocol files. The features are propagated: the interfaces
+//! referring to a gated interface, through the type of an argument or one of its enums, are gated
+//! behind its features as well.
+//!
+//! ```rust,ignore
+//! wayland_scanner::generate_client_code!(
+//!     "./path/to/the/protocol.xml",
+//!     feature = "zwp_tablet_pad_v2=tablet-pad",
+//! );
+//! ```
+//!
+//! The interfaces generated by [`generate_interfaces!`] are not gated, and this option cannot be
+//! combined with the `delegates` option of [`generate_server_code!`].
 
 use std::{ffi::OsString, path::PathBuf};
 
 mod c_interfaces;
+mod cache;
 mod client_gen;
+mod combined_gen;
 mod common;
 mod interfaces;
 mod parse;
@@ -47,62 +166,304 @@ mod protocol;
 mod server_gen;
 mod token;
 mod util;
+mod wire_gen;
 
 /// Proc-macro for generating low-level interfaces associated with an XML specification
+///
+/// Along with the [`Interface`](https://docs.rs/wayland-backend/*/wayland_backend/protocol/struct.Interface.html)
+/// of each interface, this generates the static `wl_interface` and `wl_message` tables libwayland
+/// expects, referenced by their `c_ptr`. Custom protocols can thus be used with the system backends
+/// of `wayland-backend` without writing these tables by hand.
+///
+/// It also generates the `INTERFACES` and `ARG_ENUMS` statics, listing the interfaces of the protocol
+/// and the message arguments referring to its enums, to be given to `Interface::register()` and
+/// `EnumDesc::register()`.
+///
+/// The path to the XML file can be replaced by a list of paths, as described in the
+/// [crate documentation](crate), and followed by the `no_c_interfaces` option, which omits the
+/// `wl_interface` and `wl_message` tables. The interfaces then have no `c_ptr`, the system backends
+/// generate their tables when first using them, so this only saves compile time and binary size
+/// for the applications using the rust backends. The tables of the protocols referring to these
+/// interfaces must be omitted as well, as they refer to their `wl_interface`s.
+///
+/// ```rust,ignore
+/// wayland_scanner::generate_interfaces!("./path/to/the/protocol.xml", no_c_interfaces);
+/// ```
 #[proc_macro]
 pub fn generate_interfaces(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let path: OsString = token::parse_lit_str_token(stream).into();
-    let path = if let Some(manifest_dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
-        let mut buf = PathBuf::from(manifest_dir);
-        buf.push(path);
-        buf
-    } else {
-        path.into()
-    };
-    let file = match std::fs::File::open(&path) {
-        Ok(file) => file,
-        Err(e) => panic!("Failed to open protocol file {}: {}", path.display(), e),
-    };
-    let protocol = parse::parse(file);
-    interfaces::generate(&protocol, true).into()
+    let (paths, options) = token::parse_paths_and_options(stream);
+    let mut with_c_interfaces = true;
+    for (name, value) in &options {
+        match (name.as_str(), value) {
+            ("no_c_interfaces", None) => with_c_interfaces = false,
+            _ => panic!("Unknown option `{}` for the interfaces", name),
+        }
+    }
+    let files = read_protocol_files(paths);
+    cache::generate("generate_interfaces", &with_c_interfaces, &files, || {
+        match load_protocol(&files) {
+            Ok(protocol) => interfaces::generate(&protocol, with_c_interfaces),
+            Err(error) => error,
+        }
+    })
+    .into()
 }
 
 /// Proc-macro for generating client-side API associated with an XML specification
+///
+/// The path to the XML file, or a list of paths as described in the [crate documentation](crate),
+/// can be followed by options:
+///
+/// - `event_streams`: also generate an `<Interface>EventStream` type for each interface, and a
+///   `<request>_stream()` method for each request creating an object, which returns the object along
+///   with the [`EventStream`](https://docs.rs/wayland-client/*/wayland_client/struct.EventStream.html)
+///   of its events instead of dispatching them to an event queue, or an `InvalidId` error if the
+///   request could not be sent.
+/// - `serde`: derive `serde::Serialize` for the `Request` and `Event` enums, which requires the `serde`
+///   cargo feature of `wayland-client`. The objects are serialized as their protocol id, and the file
+///   descriptors as a `{ "fd": <index> }` placeholder, their index among the file descriptors of the
+///   message. The serialization is one-way, for logging and tracing: `Deserialize` is not derived, as
+///   the objects and file descriptors cannot be recreated from their serialized form. Given as
+///   `serde = "<feature>"`, `Serialize` is only derived when the given cargo feature of the crate using
+///   the macro is enabled.
+/// - `borrowed_events`: also generate a `BorrowedEvent<'a>` enum for each interface, whose string and
+///   array arguments are a `Cow<'a, str>` and a `&'a [u8]` borrowing the buffers of the backend, and a
+///   `parse_borrowed_event()` function parsing them from the message given to
+///   [`ObjectData::event_borrowed()`](https://docs.rs/wayland-client/*/wayland_client/backend/trait.ObjectData.html#method.event_borrowed).
+///   This allows dispatching the events of chatty interfaces without allocating, the `Event` enum
+///   and the event queues are unchanged.
+/// - `builders`: also generate a `<Request>Builder` type for each request with at least 4 arguments
+///   which does not create an object, and a `<request>_builder()` method creating it. Its arguments
+///   are set by name, the nullable ones default to `None`, and it can only be sent once all the other
+///   ones are set, which is checked at compile time.
+/// - `tracing`: enter a `tracing` trace span while parsing each event and writing each request, named
+///   `parse_event` and `write_request`, with the name of the interface, the name of the message and
+///   the id of the object as fields. This requires the `tracing` cargo feature of `wayland-client`,
+///   the code generated without this option is not instrumented.
+/// - `enum_results`: represent the arguments of the messages whose type is a non-bitfield enum as a
+///   `Result<Enum, UnknownVariant>` rather than a `WEnum<Enum>`, the `UnknownVariant` error holding the
+///   raw value when it does not match any variant of the enum. This allows handling these arguments
+///   with the methods of `Result` and the `?` operator, the methods sending the messages still take
+///   the enum itself.
+/// - `arbitrary`: also generate a `Request::arbitrary()` function for each interface, generating a
+///   request supported by the given version of the interface from an `arbitrary::Unstructured`, to
+///   fuzz the compositors. This requires the `arbitrary` cargo feature of `wayland-client`. The values
+///   of the arguments are valid for the protocol, and the objects and file descriptors they refer to
+///   are picked by the given
+///   [`ArbitraryObjects`](https://docs.rs/wayland-client/*/wayland_client/trait.ArbitraryObjects.html).
+///   The messages do not implement `arbitrary::Arbitrary`, whose values are generated from the
+///   `Unstructured` alone: a message is only valid for a version of its interface, and refers to the
+///   objects of a connection. Given as `arbitrary = "<feature>"`, the functions are only generated when
+///   the given cargo feature of the crate using the macro is enabled.
+/// - the `visibility`, `strip_prefix` and `reexport_types` options shaping the generated modules, as
+///   described in the [crate documentation](crate#layout-of-the-generated-code).
+/// - the `rename` option renaming the items of the protocol, as described in the
+///   [crate documentation](crate#renaming-the-items-of-a-protocol).
+/// - the `feature` option gating the interfaces behind cargo features, as described in the
+///   [crate documentation](crate#gating-interfaces-behind-cargo-features).
+///
+/// ```rust,ignore
+/// wayland_scanner::generate_client_code!("./path/to/the/protocol.xml", event_streams, serde);
+/// ```
 #[proc_macro]
 pub fn generate_client_code(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let path: OsString = token::parse_lit_str_token(stream).into();
-    let path = if let Some(manifest_dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
-        let mut buf = PathBuf::from(manifest_dir);
-        buf.push(path);
-        buf
-    } else {
-        path.into()
-    };
-    let file = match std::fs::File::open(&path) {
-        Ok(file) => file,
-        Err(e) => panic!("Failed to open protocol file {}: {}", path.display(), e),
-    };
-    let protocol = parse::parse(file);
-    client_gen::generate_client_objects(&protocol).into()
+    let (paths, options) = token::parse_paths_and_options(stream);
+    let mut options = Options::parse(&options, &[Side::Client]);
+    let files = read_protocol_files(paths);
+    cache::generate("generate_client_code", &options.clone(), &files, || {
+        let mut protocol = match load_protocol(&files) {
+            Ok(protocol) => protocol,
+            Err(error) => return error,
+        };
+        parse::rename(&mut protocol, &options.renames);
+        common::propagate_features(&protocol, &mut options.features);
+        client_gen::generate_client_objects(&protocol, &options)
+    })
+    .into()
 }
 
 /// Proc-macro for generating server-side API associated with an XML specification
+///
+/// The path to the XML file, or a list of paths as described in the [crate documentation](crate),
+/// can be followed by options:
+///
+/// - `serde`: derive `serde::Serialize` for the `Request` and `Event` enums, which requires the `serde`
+///   cargo feature of `wayland-server`, like the option of [`generate_client_code!`].
+/// - `mocks`: also generate a `Mock<Interface>` type for each interface, a mock implementation of the
+///   interface for testing clients, see the
+///   [`mock`](https://docs.rs/wayland-server/*/wayland_server/mock/index.html) module of
+///   `wayland-server`. The interfaces of the objects created by the requests must have been generated
+///   with this option as well, like the ones of the core protocol. It requires the `mocks` cargo
+///   feature of `wayland-server`. Given as `mocks = "<feature>"`, the mocks are only generated when
+///   the given cargo feature of the crate using the macro is enabled.
+/// - `delegates`: also generate a `delegate_<protocol>!` macro, exported at the root of the crate,
+///   delegating the `Dispatch` implementations of all the interfaces of the protocol and the
+///   `GlobalDispatch` implementations of its globals to another type, for a given user data type.
+///   The generated macro refers to `wayland-server` as `::wayland_server`, the crates using it must
+///   depend on it under this name.
+/// - `tracing`: enter a `tracing` trace span while parsing each request and writing each event, named
+///   `parse_request` and `write_event`, like the option of [`generate_client_code!`]. This requires
+///   the `tracing` cargo feature of `wayland-server`.
+/// - `enum_results`: represent the arguments whose type is a non-bitfield enum as a
+///   `Result<Enum, UnknownVariant>` rather than a `WEnum<Enum>`, like the option of
+///   [`generate_client_code!`].
+/// - `arbitrary`: also generate an `Event::arbitrary()` function for each interface, generating an
+///   event for the objects given by an
+///   [`ArbitraryObjects`](https://docs.rs/wayland-server/*/wayland_server/trait.ArbitraryObjects.html)
+///   to fuzz the clients, like the option of [`generate_client_code!`]. This requires the `arbitrary`
+///   cargo feature of `wayland-server`.
+/// - the `visibility`, `strip_prefix` and `reexport_types` options shaping the generated modules, as
+///   described in the [crate documentation](crate#layout-of-the-generated-code).
+/// - the `rename` option renaming the items of the protocol, as described in the
+///   [crate documentation](crate#renaming-the-items-of-a-protocol).
+/// - the `feature` option gating the interfaces behind cargo features, as described in the
+///   [crate documentation](crate#gating-interfaces-behind-cargo-features).
 #[proc_macro]
 pub fn generate_server_code(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let path: OsString = token::parse_lit_str_token(stream).into();
-    let path = if let Some(manifest_dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
-        let mut buf = PathBuf::from(manifest_dir);
-        buf.push(path);
-        buf
-    } else {
-        path.into()
-    };
-    let file = match std::fs::File::open(&path) {
-        Ok(file) => file,
-        Err(e) => panic!("Failed to open protocol file {}: {}", path.display(), e),
-    };
-    let protocol = parse::parse(file);
-    server_gen::generate_server_objects(&protocol).into()
+    let (paths, options) = token::parse_paths_and_options(stream);
+    let mut options = Options::parse(&options, &[Side::Server]);
+    let files = read_protocol_files(paths);
+    cache::generate("generate_server_code", &options.clone(), &files, || {
+        let mut protocol = match load_protocol(&files) {
+            Ok(protocol) => protocol,
+            Err(error) => return error,
+        };
+        parse::rename(&mut protocol, &options.renames);
+        common::propagate_features(&protocol, &mut options.features);
+        server_gen::generate_server_objects(&protocol, &options)
+    })
+    .into()
+}
+
+/// Proc-macro for generating both the client-side and server-side API associated with an XML
+/// specification
+///
+/// This generates a `client` and a `server` module, with the code of [`generate_client_code!`] and
+/// [`generate_server_code!`], and an `enums` module with the enums of the protocol. The interface
+/// modules of both sides re-export these enums rather than defining their own, so that
+/// `client::wl_output::Mode` and `server::wl_output::Mode` are the same type, which is convenient
+/// for the crates using both sides such as test harnesses.
+///
+/// The `client` and `server` modules import all the items of the parent module, which must thus have
+/// `wayland_client`, `wayland_server` and the interfaces of the protocol in scope, along with the
+/// `enums` and interface modules of the other protocols it refers to. The interfaces of the core
+/// protocol are imported from `wayland_client::protocol` and `wayland_server::protocol`
+/// respectively, the parent module should not import them itself.
+///
+/// The path to the XML file can be followed by the options of both macros, the ones of a single side
+/// only apply to it. The `visibility` option also applies to the `client`, `server` and `enums`
+/// modules.
+///
+/// ```rust,ignore
+/// pub mod my_protocol {
+///     use wayland_client;
+///     use wayland_server;
+///
+///     pub mod __interfaces {
+///         use wayland_client::protocol::__interfaces::*;
+///         wayland_scanner::generate_interfaces!("./path/to/the/protocol.xml");
+///     }
+///     use self::__interfaces::*;
+///
+///     wayland_scanner::generate_client_and_server_code!("./path/to/the/protocol.xml", mocks);
+/// }
+/// ```
+#[proc_macro]
+pub fn generate_client_and_server_code(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (paths, options) = token::parse_paths_and_options(stream);
+    let mut options = Options::parse(&options, &[Side::Client, Side::Server]);
+    let files = read_protocol_files(paths);
+    cache::generate("generate_client_and_server_code", &options.clone(), &files, || {
+        let mut protocol = match load_protocol(&files) {
+            Ok(protocol) => protocol,
+            Err(error) => return error,
+        };
+        parse::rename(&mut protocol, &options.renames);
+        common::propagate_features(&protocol, &mut options.features);
+        combined_gen::generate_combined_objects(&protocol, &options)
+    })
+    .into()
+}
+
+/// Proc-macro for generating the messages of an XML specification as they are on the wire
+///
+/// This generates a module for each interface with its enums, and `Request` and `Event` enums whose
+/// arguments are the raw values of the protocol: the objects are their protocol id, the objects
+/// created by a message are their new protocol id, the file descriptors are their index among the
+/// ones of the message, and the non-bitfield enums are a `Result` holding the raw value when it does
+/// not match any variant. These types do not depend on `wayland-backend`, `wayland-client` or
+/// `wayland-server`, nor on `std`: the generated code only refers to `core` and `alloc`, as well as
+/// `bitflags` for the bitfield enums, for the `no_std` crates such as protocol analyzers.
+///
+/// The path to the XML file, or a list of paths as described in the [crate documentation](crate),
+/// can be followed by the `visibility`, `strip_prefix`, `rename` and `feature` options of the other
+/// macros.
+///
+/// ```rust,ignore
+/// extern crate alloc;
+///
+/// pub mod my_protocol {
+///     wayland_scanner::generate_wire_types!("./path/to/the/protocol.xml");
+/// }
+/// ```
+#[proc_macro]
+pub fn generate_wire_types(stream: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (paths, options) = token::parse_paths_and_options(stream);
+    for (name, _) in &options {
+        if !["visibility", "strip_prefix", "rename", "feature"].contains(&name.as_str()) {
+            panic!("Unknown option `{}` for the wire types", name);
+        }
+    }
+    let mut options = Options::parse(&options, &[]);
+    let files = read_protocol_files(paths);
+    cache::generate("generate_wire_types", &options.clone(), &files, || {
+        let mut protocol = match load_protocol(&files) {
+            Ok(protocol) => protocol,
+            Err(error) => return error,
+        };
+        parse::rename(&mut protocol, &options.renames);
+        common::propagate_features(&protocol, &mut options.features);
+        wire_gen::generate_wire_types(&protocol, &options)
+    })
+    .into()
+}
+
+// read the protocol files given to a macro, relative to the crate root
+fn read_protocol_files(paths: Vec<String>) -> Vec<(PathBuf, Vec<u8>)> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let path: OsString = path.into();
+            let path = if let Some(manifest_dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
+                let mut buf = PathBuf::from(manifest_dir);
+                buf.push(path);
+                buf
+            } else {
+                path.into()
+            };
+            match std::fs::read(&path) {
+                Ok(contents) => (path, contents),
+                Err(e) => panic!("Failed to open protocol file {}: {}", path.display(), e),
+            }
+        })
+        .collect()
+}
+
+// parse the contents of the protocol files given to a macro into a single protocol, an invalid
+// file is reported as a compilation error at the invocation of the macro
+fn load_protocol(
+    files: &[(PathBuf, Vec<u8>)],
+) -> Result<protocol::Protocol, proc_macro2::TokenStream> {
+    let protocols = files
+        .iter()
+        .map(|(path, contents)| {
+            cache::parse(path, contents).map_err(|error| {
+                let message = error.describe(&path.display().to_string(), contents);
+                quote::quote!(compile_error!(#message);)
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(parse::merge(protocols))
 }
 
 #[cfg(test)]
@@ -132,6 +493,32 @@ fn format_rust_code(code: &str) -> String {
     panic!("Rustfmt failed!");
 }
 
+// compare the generated code with its reference file in `tests/scanner_assets`
+#[cfg(test)]
+fn check_reference(reference: &str, generated: &proc_macro2::TokenStream) {
+    let generated = format_rust_code(&generated.to_string());
+
+    let reference_path = format!("./tests/scanner_assets/{}", reference);
+    let reference = std::fs::read_to_string(&reference_path).unwrap();
+    let reference = format_rust_code(&reference);
+
+    if reference != generated {
+        let diff = similar::TextDiff::from_lines(&reference, &generated);
+        print!("{}", diff.unified_diff().context_radius(10).header(&reference_path, "generated"));
+        panic!("Generated does not match reference {}!", reference_path)
+    }
+}
+
+// the protocol of the snapshots of the options, with the renames of the options applied like the
+// macros do
+#[cfg(test)]
+fn options_protocol(options: &Options) -> protocol::Protocol {
+    let protocol_file = std::fs::File::open("./tests/scanner_assets/options-protocol.xml").unwrap();
+    let mut protocol = parse::parse(protocol_file).unwrap();
+    parse::rename(&mut protocol, &options.renames);
+    protocol
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 enum Side {
     /// wayland client applications
@@ -139,3 +526,146 @@ enum Side {
     /// wayland compositors
     Server,
 }
+
+/// The options of the code generation macros
+#[derive(Clone, Default, Debug)]
+struct Options {
+    /// generate the event streams, client-side only
+    event_streams: bool,
+    /// derive `Serialize` for the messages
+    serde: Gate,
+    /// generate the mock implementations, server-side only
+    mocks: Gate,
+    /// generate the events borrowing their strings and arrays, client-side only
+    borrowed_events: bool,
+    /// generate the builders of the requests with many arguments, client-side only
+    builders: bool,
+    /// generate the delegation macro of the protocol, server-side only
+    delegates: bool,
+    /// re-export the enums shared by both sides instead of generating them, set by
+    /// `generate_client_and_server_code!`
+    shared_enums: bool,
+    /// the visibility of the generated modules
+    visibility: Visibility,
+    /// the prefix removed from the names of the interface modules
+    strip_prefix: Option<String>,
+    /// re-export the interface types alongside their modules
+    reexport_types: bool,
+    /// instrument the parsing and writing of the messages with tracing spans
+    tracing: bool,
+    /// represent the enum arguments as a `Result<T, UnknownVariant>` instead of a `WEnum<T>`
+    enum_results: bool,
+    /// generate an `arbitrary()` function for the messages sent by each side
+    arbitrary: Gate,
+    /// the new names of the items of the protocol, by their path
+    renames: Vec<(String, String)>,
+    /// the cargo features the interfaces are gated behind, including the ones of the interfaces
+    /// they refer to once propagated
+    features: Vec<(String, String)>,
+}
+
+impl Options {
+    fn parse(names: &[(String, Option<String>)], sides: &[Side]) -> Options {
+        let client = sides.contains(&Side::Client);
+        let server = sides.contains(&Side::Server);
+        let mut options = Options::default();
+        for (name, value) in names {
+            match (name.as_str(), value.as_deref()) {
+                ("event_streams", None) if client => options.event_streams = true,
+                ("serde", feature) => options.serde = Gate::new(feature),
+                ("mocks", feature) if server => options.mocks = Gate::new(feature),
+                ("borrowed_events", None) if client => options.borrowed_events = true,
+                ("builders", None) if client => options.builders = true,
+                ("delegates", None) if server => options.delegates = true,
+                ("visibility", Some("pub")) => options.visibility = Visibility::Public,
+                ("visibility", Some("pub(crate)")) => options.visibility = Visibility::Crate,
+                ("strip_prefix", Some(prefix)) => options.strip_prefix = Some(prefix.to_owned()),
+                ("reexport_types", None) => options.reexport_types = true,
+                ("tracing", None) => options.tracing = true,
+                ("enum_results", None) => options.enum_results = true,
+                ("arbitrary", feature) => options.arbitrary = Gate::new(feature),
+                ("rename", Some(rename)) => match rename.split_once('=') {
+                    Some((path, new_name)) => {
+                        options.renames.push((path.trim().to_owned(), new_name.trim().to_owned()))
+                    }
+                    None => panic!("Invalid rename `{}`, expected `<path>=<new name>`", rename),
+                },
+                ("feature", Some(feature)) => match feature.split_once('=') {
+                    Some((interface, feature)) => options
+                        .features
+                        .push((interface.trim().to_owned(), feature.trim().to_owned())),
+                    None => {
+                        panic!("Invalid feature `{}`, expected `<interface>=<feature>`", feature)
+                    }
+                },
+                (_, None) => panic!("Unknown option `{}` for the {:?} code", name, sides),
+                (_, Some(value)) => {
+                    panic!("Unknown option `{} = {:?}` for the {:?} code", name, value, sides)
+                }
+            }
+        }
+        options
+    }
+}
+
+/// An optional part of the generated code
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+enum Gate {
+    /// not generated
+    #[default]
+    Off,
+    /// generated
+    On,
+    /// generated under a cargo feature of the crate using the macro
+    Feature(String),
+}
+
+impl Gate {
+    /// the gate of an option given as `name`, or as `name = "feature"`
+    fn new(feature: Option<&str>) -> Gate {
+        match feature {
+            Some(feature) => Gate::Feature(feature.to_owned()),
+            None => Gate::On,
+        }
+    }
+
+    fn is_on(&self) -> bool {
+        *self != Gate::Off
+    }
+
+    /// the `cfg` attribute of the generated items
+    fn cfg(&self) -> Option<proc_macro2::TokenStream> {
+        match self {
+            Gate::Feature(feature) => Some(quote::quote!(#[cfg(feature = #feature)])),
+            Gate::Off | Gate::On => None,
+        }
+    }
+
+    /// an attribute of the generated items, only applied when the gate is on
+    fn attr(&self, attr: proc_macro2::TokenStream) -> Option<proc_macro2::TokenStream> {
+        match self {
+            Gate::Feature(feature) => Some(quote::quote!(#[cfg_attr(feature = #feature, #attr)])),
+            Gate::On => Some(quote::quote!(#[#attr])),
+            Gate::Off => None,
+        }
+    }
+}
+
+/// The visibility of the generated modules
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+enum Visibility {
+    /// `pub`
+    #[default]
+    Public,
+    /// `pub(crate)`
+    Crate,
+}
+
+impl quote::ToTokens for Visibility {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(match self {
+            Visibility::Public => quote::quote!(pub),
+            Visibility::Crate => quote::quote!(pub(crate)),
+        })
+    }
+}
@@ -1,368 +1,221 @@
 use super::protocol::*;
 use std::{
-    io::{BufRead, BufReader, Read},
-    str::FromStr,
+    collections::{HashMap, HashSet},
+    io::Read,
 };
 
-use quick_xml::{
-    events::{attributes::Attributes, Event},
-    Reader,
-};
-
-macro_rules! extract_from(
-    ($it: expr => $pattern: pat => $result: tt) => (
-        match $it.read_event_into(&mut Vec::new()) {
-            Ok($pattern) => { $result },
-            e => panic!("Ill-formed protocol file: {:?}", e)
-        }
-    )
-);
+use wayland_backend::protocol_xml;
 
-macro_rules! extract_end_tag(
-    ($it: expr => $tag: expr) => (
-        extract_from!($it => Event::End(bytes) => {
-            assert!(bytes.name().into_inner() == $tag.as_bytes(), "Ill-formed protocol file");
-        });
-    )
-);
-
-pub fn parse<S: Read>(stream: S) -> Protocol {
-    let mut reader = Reader::from_reader(BufReader::new(stream));
-    reader.trim_text(true).expand_empty_elements(true);
-    // Skip first <?xml ... ?> event
-    let _ = reader.read_event_into(&mut Vec::new());
-    parse_protocol(reader)
-}
+pub use wayland_backend::protocol_xml::ParseError;
 
-fn decode_utf8_or_panic(txt: Vec<u8>) -> String {
-    match String::from_utf8(txt) {
-        Ok(txt) => txt,
-        Err(e) => panic!("Invalid UTF8: '{}'", String::from_utf8_lossy(&e.into_bytes())),
-    }
+pub fn parse<S: Read>(stream: S) -> Result<Protocol, ParseError> {
+    let mut protocol = Protocol::from(protocol_xml::parse(stream)?);
+    resolve_bitfields(&mut protocol);
+    Ok(protocol)
 }
 
-fn parse_or_panic<T: FromStr>(txt: &[u8]) -> T {
-    match std::str::from_utf8(txt).ok().and_then(|val| val.parse().ok()) {
-        Some(version) => version,
-        None => panic!(
-            "Invalid value '{}' for parsing type '{}'",
-            String::from_utf8_lossy(txt),
-            std::any::type_name::<T>()
-        ),
-    }
+// merge the protocols of several files given to a single macro invocation, their interfaces can
+// refer to each other as if they were defined in the same file
+pub fn merge(protocols: Vec<Protocol>) -> Protocol {
+    let mut protocols = protocols.into_iter();
+    let mut merged = protocols.next().expect("expected at least one protocol file");
+    let mut others = protocols.peekable();
+    if others.peek().is_none() {
+        return merged;
+    }
+    for protocol in others {
+        for interface in protocol.interfaces {
+            assert!(
+                merged.interfaces.iter().all(|other| other.name != interface.name),
+                "Interface {} of protocol {} is defined by several of the protocol files",
+                interface.name,
+                protocol.name
+            );
+            merged.interfaces.push(interface);
+        }
+    }
+    // the enums may be defined by another file
+    resolve_bitfields(&mut merged);
+    merged
 }
 
-fn parse_protocol<R: BufRead>(mut reader: Reader<R>) -> Protocol {
-    let mut protocol = extract_from!(
-        reader => Event::Start(bytes) => {
-            assert!(bytes.name().into_inner() == b"protocol", "Missing protocol toplevel tag");
-            if let Some(attr) = bytes.attributes().filter_map(|res| res.ok()).find(|attr| attr.key.into_inner() == b"name") {
-                Protocol::new(decode_utf8_or_panic(attr.value.into_owned()))
-            } else {
-                panic!("Protocol must have a name");
-            }
-        }
-    );
-
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => {
-                match bytes.name().into_inner() {
-                    b"copyright" => {
-                        // parse the copyright
-                        let copyright = match reader.read_event_into(&mut Vec::new()) {
-                            Ok(Event::Text(copyright)) => {
-                                copyright.unescape().ok().map(|x| x.to_string())
-                            }
-                            Ok(Event::CData(copyright)) => {
-                                String::from_utf8(copyright.into_inner().into()).ok()
-                            }
-                            e => panic!("Ill-formed protocol file: {:?}", e),
-                        };
-
-                        extract_end_tag!(reader => "copyright");
-                        protocol.copyright = copyright
+// rename the items of a protocol following the `rename` options of a macro, given as
+// `interface.request.name`, `interface.event.name` or `interface.enum.name`, optionally followed by
+// the name of an argument or an entry, and check that no two items end up with the same rust name
+//
+// only the rust names are changed, the names of the protocol file are still used on the wire
+pub fn rename(protocol: &mut Protocol, renames: &[(String, String)]) {
+    for (path, new_name) in renames {
+        let parts = path.split('.').collect::<Vec<_>>();
+        let interface = protocol
+            .interfaces
+            .iter_mut()
+            .find(|interface| interface.name == parts[0])
+            .unwrap_or_else(|| panic!("Cannot rename `{}`: unknown interface {}", path, parts[0]));
+        let iface_name = interface.name.clone();
+        let (kind, name, item) =
+            (parts.get(1).copied(), parts.get(2).copied(), parts.get(3).copied());
+        let target = match (kind, name, item, parts.len()) {
+            (Some(kind @ ("request" | "event")), Some(name), arg, 3 | 4) => {
+                let messages =
+                    if kind == "request" { &mut interface.requests } else { &mut interface.events };
+                messages.iter_mut().find(|msg| msg.name == name).and_then(|msg| match arg {
+                    Some(arg) => {
+                        msg.args.iter_mut().find(|a| a.name == arg).map(|a| &mut a.rust_name)
                     }
-                    b"interface" => {
-                        protocol.interfaces.push(parse_interface(&mut reader, bytes.attributes()));
+                    None => Some(&mut msg.rust_name),
+                })
+            }
+            (Some("enum"), Some(name), entry, 3 | 4) => {
+                interface.enums.iter_mut().find(|enu| enu.name == name).and_then(|enu| {
+                    match entry {
+                        Some(entry) => enu
+                            .entries
+                            .iter_mut()
+                            .find(|e| e.name == entry)
+                            .map(|e| &mut e.rust_name),
+                        None => Some(&mut enu.rust_name),
                     }
-                    b"description" => {
-                        protocol.description =
-                            Some(parse_description(&mut reader, bytes.attributes()));
+                })
+            }
+            _ => panic!(
+                "Invalid rename `{}`, expected `<interface>.<request|event|enum>.<name>`, optionally \
+                 followed by the name of an argument or an entry",
+                path
+            ),
+        };
+        let target = target.unwrap_or_else(|| panic!("Cannot rename `{}`: no such item", path));
+        *target = new_name.clone();
+
+        // the arguments referring to a renamed enum are of its renamed type
+        if parts[1] == "enum" && parts.len() == 3 {
+            let old_path = format!("{}.{}", iface_name, parts[2]);
+            let new_path = format!("{}.{}", iface_name, new_name);
+            for interface in &mut protocol.interfaces {
+                let local = interface.name == iface_name;
+                for msg in interface.requests.iter_mut().chain(interface.events.iter_mut()) {
+                    for arg in &mut msg.args {
+                        if arg.enum_.as_ref() == Some(&old_path) {
+                            arg.rust_enum = Some(new_path.clone());
+                        } else if local && arg.enum_.as_deref() == Some(parts[2]) {
+                            arg.rust_enum = Some(new_name.clone());
+                        }
                     }
-                    name => panic!(
-                        "Ill-formed protocol file: unexpected token `{}` in protocol {}",
-                        String::from_utf8_lossy(name),
-                        protocol.name
-                    ),
                 }
             }
-            Ok(Event::End(bytes)) => {
-                let name = bytes.name().into_inner();
-                assert!(
-                    name == b"protocol",
-                    "Unexpected closing token `{}`",
-                    String::from_utf8_lossy(name)
-                );
-                break;
-            }
-            // ignore comments
-            Ok(Event::Comment(_)) => {}
-            e => panic!("Ill-formed protocol file: unexpected token {:?}", e),
-        }
-    }
-
-    protocol
-}
-
-fn parse_interface<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Interface {
-    let mut interface = Interface::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        match attr.key.into_inner() {
-            b"name" => interface.name = decode_utf8_or_panic(attr.value.into_owned()),
-            b"version" => interface.version = parse_or_panic(&attr.value),
-            _ => {}
         }
     }
 
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => match bytes.name().into_inner() {
-                b"description" => {
-                    interface.description = Some(parse_description(reader, bytes.attributes()))
-                }
-                b"request" => interface.requests.push(parse_request(reader, bytes.attributes())),
-                b"event" => interface.events.push(parse_event(reader, bytes.attributes())),
-                b"enum" => interface.enums.push(parse_enum(reader, bytes.attributes())),
-                name => panic!("Unexpected token: `{}`", String::from_utf8_lossy(name)),
-            },
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"interface" => break,
-            _ => {}
+    for interface in &protocol.interfaces {
+        check_unique_names(
+            &format!("requests of {}", interface.name),
+            interface.requests.iter().map(|msg| &msg.rust_name[..]),
+        );
+        check_unique_names(
+            &format!("events of {}", interface.name),
+            interface.events.iter().map(|msg| &msg.rust_name[..]),
+        );
+        check_unique_names(
+            &format!("enums of {}", interface.name),
+            interface.enums.iter().map(|enu| &enu.rust_name[..]),
+        );
+        for enu in &interface.enums {
+            check_unique_names(
+                &format!("entries of {}.{}", interface.name, enu.name),
+                enu.entries.iter().map(|entry| &entry.rust_name[..]),
+            );
         }
     }
-
-    interface
 }
 
-fn parse_description<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> (String, String) {
-    let mut summary = String::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        if attr.key.into_inner() == b"summary" {
-            summary = String::from_utf8_lossy(&attr.value)
-                .split_whitespace()
-                .collect::<Vec<_>>()
-                .join(" ");
-        }
-    }
-
-    let mut description = String::new();
-    // Some protocols have comments inside their descriptions, so we need to parse them in a loop and
-    // concatenate the parts into a single block of text
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Text(bytes)) => {
-                if !description.is_empty() {
-                    description.push_str("\n\n");
-                }
-                description.push_str(&bytes.unescape().unwrap_or_default())
-            }
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"description" => break,
-            Ok(Event::Comment(_)) => {}
-            e => panic!("Ill-formed protocol file: {:?}", e),
+// the names differing only by their case or underscores are converted to the same camel case name
+fn check_unique_names<'a>(what: &str, names: impl Iterator<Item = &'a str>) {
+    let mut seen = HashMap::new();
+    for name in names {
+        let camel = crate::util::snake_to_camel(name);
+        if let Some(other) = seen.insert(camel.clone(), name) {
+            panic!(
+                "The {} `{}` and `{}` are both named `{}` in rust, use the `rename` option to \
+                 rename one of them",
+                what, other, name, camel
+            );
         }
     }
-
-    (summary, description)
 }
 
-fn parse_request<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Message {
-    let mut request = Message::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        match attr.key.into_inner() {
-            b"name" => request.name = decode_utf8_or_panic(attr.value.into_owned()),
-            b"type" => request.typ = Some(parse_type(&attr.value)),
-            b"since" => request.since = parse_or_panic(&attr.value),
-            _ => {}
-        }
-    }
-
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => match bytes.name().into_inner() {
-                b"description" => {
-                    request.description = Some(parse_description(reader, bytes.attributes()))
-                }
-                b"arg" => request.args.push(parse_arg(reader, bytes.attributes())),
-                name => panic!("Unexpected token: `{}`", String::from_utf8_lossy(name)),
-            },
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"request" => break,
-            _ => {}
-        }
-    }
+// the core protocol, whose enums the protocol extensions refer to without defining them
+const CORE_PROTOCOL: &str = include_str!("../wayland.xml");
 
-    request
-}
-
-fn parse_enum<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Enum {
-    let mut enu = Enum::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        match attr.key.into_inner() {
-            b"name" => enu.name = decode_utf8_or_panic(attr.value.into_owned()),
-            b"since" => enu.since = parse_or_panic(&attr.value),
-            b"bitfield" => {
-                if &attr.value[..] == b"true" {
-                    enu.bitfield = true
-                }
-            }
-            _ => {}
-        }
-    }
-
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => match bytes.name().into_inner() {
-                b"description" => {
-                    enu.description = Some(parse_description(reader, bytes.attributes()))
-                }
-                b"entry" => enu.entries.push(parse_entry(reader, bytes.attributes())),
-                name => panic!("Unexpected token: `{}`", String::from_utf8_lossy(name)),
-            },
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"enum" => break,
-            _ => {}
-        }
-    }
-
-    enu
-}
-
-fn parse_event<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Message {
-    let mut event = Message::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        match attr.key.into_inner() {
-            b"name" => event.name = decode_utf8_or_panic(attr.value.into_owned()),
-            b"type" => event.typ = Some(parse_type(&attr.value)),
-            b"since" => event.since = parse_or_panic(&attr.value),
-            _ => {}
-        }
-    }
-
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => match bytes.name().into_inner() {
-                b"description" => {
-                    event.description = Some(parse_description(reader, bytes.attributes()))
-                }
-                b"arg" => event.args.push(parse_arg(reader, bytes.attributes())),
-                name => panic!("Unexpected token: `{}`", String::from_utf8_lossy(name)),
-            },
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"event" => break,
-            _ => {}
-        }
-    }
-
-    event
+// the bitfield enums defined by the interfaces of a protocol, by their `interface.enum` name
+fn bitfields_of(protocol: &Protocol) -> HashSet<String> {
+    protocol
+        .interfaces
+        .iter()
+        .flat_map(|interface| {
+            interface
+                .enums
+                .iter()
+                .filter(|enu| enu.bitfield)
+                .map(move |enu| format!("{}.{}", interface.name, enu.name))
+        })
+        .collect()
 }
 
-fn parse_arg<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Arg {
-    let mut arg = Arg::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        match attr.key.into_inner() {
-            b"name" => arg.name = decode_utf8_or_panic(attr.value.into_owned()),
-            b"type" => arg.typ = parse_type(&attr.value),
-            b"summary" => {
-                arg.summary = Some(
-                    String::from_utf8_lossy(&attr.value)
-                        .split_whitespace()
-                        .collect::<Vec<_>>()
-                        .join(" "),
-                )
-            }
-            b"interface" => arg.interface = Some(parse_or_panic(&attr.value)),
-            b"allow-null" => {
-                if &*attr.value == b"true" {
-                    arg.allow_null = true
+// flag the arguments whose enum is a bitfield, the enum may be defined by another interface of the
+// protocol or of the core protocol as `interface.enum`
+fn resolve_bitfields(protocol: &mut Protocol) {
+    let mut bitfields = bitfields_of(protocol);
+
+    // the core protocol is only parsed for the protocols referring to the enums of interfaces they
+    // do not define, it refers to none itself
+    let refers_outside = protocol.interfaces.iter().any(|interface| {
+        interface.requests.iter().chain(&interface.events).flat_map(|msg| &msg.args).any(|arg| {
+            arg.enum_.as_ref().and_then(|enu| enu.split_once('.')).map_or(false, |(other, _)| {
+                !protocol.interfaces.iter().any(|known| known.name == other)
+            })
+        })
+    });
+    if refers_outside {
+        let core = parse(CORE_PROTOCOL.as_bytes()).expect("Invalid core protocol file");
+        bitfields.extend(bitfields_of(&core));
+    }
+
+    for interface in &mut protocol.interfaces {
+        let name = interface.name.clone();
+        for msg in interface.requests.iter_mut().chain(interface.events.iter_mut()) {
+            for arg in &mut msg.args {
+                if let Some(ref enu) = arg.enum_ {
+                    arg.bitfield = if enu.contains('.') {
+                        bitfields.contains(enu)
+                    } else {
+                        bitfields.contains(&format!("{}.{}", name, enu))
+                    };
                 }
             }
-            b"enum" => arg.enum_ = Some(decode_utf8_or_panic(attr.value.into_owned())),
-            _ => {}
         }
     }
-
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => match bytes.name().into_inner() {
-                b"description" => {
-                    arg.description = Some(parse_description(reader, bytes.attributes()))
-                }
-                name => panic!("Unexpected token: `{}`", String::from_utf8_lossy(name)),
-            },
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"arg" => break,
-            _ => {}
-        }
-    }
-
-    arg
 }
 
-fn parse_type(txt: &[u8]) -> Type {
-    match txt {
-        b"int" => Type::Int,
-        b"uint" => Type::Uint,
-        b"fixed" => Type::Fixed,
-        b"string" => Type::String,
-        b"object" => Type::Object,
-        b"new_id" => Type::NewId,
-        b"array" => Type::Array,
-        b"fd" => Type::Fd,
-        b"destructor" => Type::Destructor,
-        e => panic!("Unexpected type: {}", String::from_utf8_lossy(e)),
-    }
-}
-
-fn parse_entry<R: BufRead>(reader: &mut Reader<R>, attrs: Attributes) -> Entry {
-    let mut entry = Entry::new();
-    for attr in attrs.filter_map(|res| res.ok()) {
-        match attr.key.into_inner() {
-            b"name" => entry.name = decode_utf8_or_panic(attr.value.into_owned()),
-            b"value" => {
-                entry.value = if attr.value.starts_with(b"0x") {
-                    if let Some(val) = std::str::from_utf8(&attr.value[2..])
-                        .ok()
-                        .and_then(|s| u32::from_str_radix(s, 16).ok())
-                    {
-                        val
-                    } else {
-                        panic!("Invalid number: {}", String::from_utf8_lossy(&attr.value))
-                    }
-                } else {
-                    parse_or_panic(&attr.value)
-                };
-            }
-            b"since" => entry.since = parse_or_panic(&attr.value),
-            b"summary" => {
-                entry.summary = Some(
-                    String::from_utf8_lossy(&attr.value)
-                        .split_whitespace()
-                        .collect::<Vec<_>>()
-                        .join(" "),
-                )
-            }
-            _ => {}
-        }
-    }
-
-    loop {
-        match reader.read_event_into(&mut Vec::new()) {
-            Ok(Event::Start(bytes)) => match bytes.name().into_inner() {
-                b"description" => {
-                    entry.description = Some(parse_description(reader, bytes.attributes()))
-                }
-                name => panic!("Unexpected token: `{}`", String::from_utf8_lossy(name)),
-            },
-            Ok(Event::End(bytes)) if bytes.name().into_inner() == b"entry" => break,
-            _ => {}
-        }
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn core_bitfields() {
+        let xml = r#"<protocol name="test">
+  <interface name="test_iface" version="1">
+    <request name="req">
+      <arg name="capabilities" type="uint" enum="wl_seat.capability"/>
+      <arg name="format" type="uint" enum="wl_shm.format"/>
+      <arg name="unknown" type="uint" enum="unknown_iface.flags"/>
+    </request>
+  </interface>
+</protocol>
+"#;
+        let protocol = super::parse(xml.as_bytes()).unwrap();
+        let bitfields = protocol.interfaces[0].requests[0]
+            .args
+            .iter()
+            .map(|arg| arg.bitfield)
+            .collect::<Vec<_>>();
+        assert_eq!(bitfields, [true, false, false]);
     }
-
-    entry
 }
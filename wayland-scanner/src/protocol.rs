@@ -1,17 +1,19 @@
 use proc_macro2::TokenStream;
 use quote::quote;
+use wayland_backend::protocol_xml;
 
 #[derive(Clone, Debug)]
 pub struct Protocol {
     pub name: String,
-    pub copyright: Option<String>,
-    pub description: Option<(String, String)>,
     pub interfaces: Vec<Interface>,
 }
 
-impl Protocol {
-    pub fn new(name: String) -> Protocol {
-        Protocol { name, copyright: None, description: None, interfaces: Vec::new() }
+impl From<protocol_xml::Protocol> for Protocol {
+    fn from(protocol: protocol_xml::Protocol) -> Protocol {
+        Protocol {
+            name: protocol.name,
+            interfaces: protocol.interfaces.into_iter().map(Interface::from).collect(),
+        }
     }
 }
 
@@ -25,33 +27,47 @@ pub struct Interface {
     pub enums: Vec<Enum>,
 }
 
-impl Interface {
-    pub fn new() -> Interface {
+impl From<protocol_xml::Interface> for Interface {
+    fn from(interface: protocol_xml::Interface) -> Interface {
         Interface {
-            name: String::new(),
-            version: 1,
-            description: None,
-            requests: Vec::new(),
-            events: Vec::new(),
-            enums: Vec::new(),
+            name: interface.name,
+            version: interface.version,
+            description: interface.description,
+            requests: interface.requests.into_iter().map(Message::from).collect(),
+            events: interface.events.into_iter().map(Message::from).collect(),
+            enums: interface.enums.into_iter().map(Enum::from).collect(),
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Message {
+    // the name of the protocol file, used on the wire and in the debug output
     pub name: String,
+    // the name the rust items are derived from, as renamed by the `rename` option
+    pub rust_name: String,
     pub typ: Option<Type>,
     pub since: u32,
+    pub deprecated_since: Option<u32>,
     pub description: Option<(String, String)>,
     pub args: Vec<Arg>,
 }
 
-impl Message {
-    pub fn new() -> Message {
-        Message { name: String::new(), typ: None, since: 1, description: None, args: Vec::new() }
+impl From<protocol_xml::Message> for Message {
+    fn from(message: protocol_xml::Message) -> Message {
+        Message {
+            rust_name: message.name.clone(),
+            name: message.name,
+            typ: message.is_destructor.then_some(Type::Destructor),
+            since: message.since,
+            deprecated_since: message.deprecated_since,
+            description: message.description,
+            args: message.args.into_iter().map(Arg::from).collect(),
+        }
     }
+}
 
+impl Message {
     pub fn all_null(&self) -> bool {
         self.args
             .iter()
@@ -62,24 +78,33 @@ impl Message {
 #[derive(Clone, Debug)]
 pub struct Arg {
     pub name: String,
+    pub rust_name: String,
     pub typ: Type,
     pub interface: Option<String>,
     pub summary: Option<String>,
     pub description: Option<(String, String)>,
     pub allow_null: bool,
     pub enum_: Option<String>,
+    // `enum_` naming the rust type of the enum, as renamed by the `rename` option
+    pub rust_enum: Option<String>,
+    // whether `enum_` refers to a bitfield enum of the protocol
+    pub bitfield: bool,
 }
 
-impl Arg {
-    pub fn new() -> Arg {
+impl From<protocol_xml::Arg> for Arg {
+    fn from(arg: protocol_xml::Arg) -> Arg {
         Arg {
-            name: String::new(),
-            typ: Type::Object,
-            interface: None,
-            summary: None,
-            description: None,
-            allow_null: false,
-            enum_: None,
+            rust_name: arg.name.clone(),
+            name: arg.name,
+            typ: arg.typ.into(),
+            interface: arg.interface,
+            summary: arg.summary,
+            description: arg.description,
+            allow_null: arg.allow_null,
+            rust_enum: arg.enum_.clone(),
+            enum_: arg.enum_,
+            // resolved once all the enums of the protocol are known
+            bitfield: false,
         }
     }
 }
@@ -87,20 +112,20 @@ impl Arg {
 #[derive(Clone, Debug)]
 pub struct Enum {
     pub name: String,
-    pub since: u16,
+    pub rust_name: String,
     pub description: Option<(String, String)>,
     pub entries: Vec<Entry>,
     pub bitfield: bool,
 }
 
-impl Enum {
-    pub fn new() -> Enum {
+impl From<protocol_xml::Enum> for Enum {
+    fn from(enu: protocol_xml::Enum) -> Enum {
         Enum {
-            name: String::new(),
-            since: 1,
-            description: None,
-            entries: Vec::new(),
-            bitfield: false,
+            rust_name: enu.name.clone(),
+            name: enu.name,
+            description: enu.description,
+            entries: enu.entries.into_iter().map(Entry::from).collect(),
+            bitfield: enu.bitfield,
         }
     }
 }
@@ -108,15 +133,23 @@ impl Enum {
 #[derive(Clone, Debug)]
 pub struct Entry {
     pub name: String,
+    pub rust_name: String,
     pub value: u32,
-    pub since: u16,
+    pub deprecated_since: Option<u16>,
     pub description: Option<(String, String)>,
     pub summary: Option<String>,
 }
 
-impl Entry {
-    pub fn new() -> Entry {
-        Entry { name: String::new(), value: 0, since: 1, description: None, summary: None }
+impl From<protocol_xml::Entry> for Entry {
+    fn from(entry: protocol_xml::Entry) -> Entry {
+        Entry {
+            rust_name: entry.name.clone(),
+            name: entry.name,
+            value: entry.value,
+            deprecated_since: entry.deprecated_since,
+            description: entry.description,
+            summary: entry.summary,
+        }
     }
 }
 
@@ -133,6 +166,21 @@ pub enum Type {
     Destructor,
 }
 
+impl From<protocol_xml::ArgType> for Type {
+    fn from(typ: protocol_xml::ArgType) -> Type {
+        match typ {
+            protocol_xml::ArgType::Int => Type::Int,
+            protocol_xml::ArgType::Uint => Type::Uint,
+            protocol_xml::ArgType::Fixed => Type::Fixed,
+            protocol_xml::ArgType::String => Type::String,
+            protocol_xml::ArgType::Object => Type::Object,
+            protocol_xml::ArgType::NewId => Type::NewId,
+            protocol_xml::ArgType::Array => Type::Array,
+            protocol_xml::ArgType::Fd => Type::Fd,
+        }
+    }
+}
+
 impl Type {
     pub fn nullable(self) -> bool {
         matches!(self, Type::String | Type::Object)
@@ -4,44 +4,180 @@ use quote::{format_ident, quote};
 
 use crate::{
     protocol::{Interface, Protocol, Type},
-    util::{description_to_doc_attr, dotted_to_relname, is_keyword, snake_to_camel, to_doc_attr},
-    Side,
+    util::{deprecated_attr, description_to_doc_attr, is_keyword, snake_to_camel, to_doc_attr},
+    Options, Side,
 };
 
-pub fn generate_server_objects(protocol: &Protocol) -> TokenStream {
-    protocol
+pub fn generate_server_objects(protocol: &Protocol, options: &Options) -> TokenStream {
+    let objects: TokenStream = protocol
         .interfaces
         .iter()
         .filter(|iface| iface.name != "wl_display" && iface.name != "wl_registry")
-        .map(generate_objects_for)
-        .collect()
+        .map(|interface| generate_objects_for(protocol, interface, options))
+        .collect();
+    // the exported macro would check the features of the crates using it
+    assert!(
+        !options.delegates || options.features.is_empty(),
+        "The `delegates` option cannot be used along with the `feature` option"
+    );
+    let delegate_macro = options.delegates.then(|| gen_delegate_macro(protocol));
+    quote! {
+        #objects
+        #delegate_macro
+    }
+}
+
+fn gen_delegate_macro(protocol: &Protocol) -> TokenStream {
+    let macro_name = format_ident!("delegate_{}", protocol.name.replace('-', "_"));
+    let doc_attr = to_doc_attr(&format!(
+        "Delegate the [`Dispatch`](wayland_server::Dispatch) implementations of all the interfaces of the \
+`{}` protocol to another type
+
+The [`GlobalDispatch`](wayland_server::GlobalDispatch) implementations of the interfaces no message \
+creates are delegated as well, these are the globals of the protocol. The same type is used as the \
+user data of all the objects and as the data of all the globals.
+
+Its arguments are the path to the module of the generated code with the user data type, and the \
+delegate type. Its usage is otherwise similar to the \
+[`delegate_dispatch!`](wayland_server::delegate_dispatch) macro:
+
+```ignore
+{}!(State: [path::to::protocol: UserData] => Delegate);
+```",
+        protocol.name, macro_name
+    ));
+
+    // the interfaces created by a request or event are not globals
+    let created = protocol
+        .interfaces
+        .iter()
+        .flat_map(|iface| iface.requests.iter().chain(iface.events.iter()))
+        .flat_map(|msg| msg.args.iter())
+        .filter(|arg| arg.typ == Type::NewId)
+        .filter_map(|arg| arg.interface.as_deref())
+        .collect::<Vec<_>>();
+
+    let impls = protocol
+        .interfaces
+        .iter()
+        .filter(|iface| iface.name != "wl_display" && iface.name != "wl_registry")
+        .map(|interface| {
+            let iface_mod = Ident::new(&interface.name, Span::call_site());
+            let iface_name = Ident::new(&snake_to_camel(&interface.name), Span::call_site());
+            let iface = quote! { $($module)::+::#iface_mod::#iface_name };
+            let global_impl = (!created.contains(&interface.name.as_str())).then(|| {
+                quote! {
+                    impl$(< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? ::wayland_server::GlobalDispatch<#iface, $udata> for $dispatch_from
+                    where
+                        $dispatch_to: ::wayland_server::GlobalDispatch<#iface, $udata, Self>,
+                    {
+                        fn bind(
+                            state: &mut Self,
+                            dhandle: &::wayland_server::DisplayHandle,
+                            client: &::wayland_server::Client,
+                            resource: ::wayland_server::New<#iface>,
+                            global_data: &$udata,
+                            data_init: &mut ::wayland_server::DataInit<'_, Self>,
+                        ) {
+                            <$dispatch_to as ::wayland_server::GlobalDispatch<#iface, $udata, Self>>::bind(state, dhandle, client, resource, global_data, data_init)
+                        }
+
+                        fn can_view(client: ::wayland_server::Client, global_data: &$udata) -> bool {
+                            <$dispatch_to as ::wayland_server::GlobalDispatch<#iface, $udata, Self>>::can_view(client, global_data)
+                        }
+                    }
+                }
+            });
+            quote! {
+                impl$(< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? ::wayland_server::Dispatch<#iface, $udata> for $dispatch_from
+                where
+                    $dispatch_to: ::wayland_server::Dispatch<#iface, $udata, Self>,
+                {
+                    fn request(
+                        state: &mut Self,
+                        client: &::wayland_server::Client,
+                        resource: &#iface,
+                        request: <#iface as ::wayland_server::Resource>::Request,
+                        data: &$udata,
+                        dhandle: &::wayland_server::DisplayHandle,
+                        data_init: &mut ::wayland_server::DataInit<'_, Self>,
+                    ) {
+                        <$dispatch_to as ::wayland_server::Dispatch<#iface, $udata, Self>>::request(state, client, resource, request, data, dhandle, data_init)
+                    }
+
+                    fn destroyed(state: &mut Self, client: ::wayland_server::backend::ClientId, resource: &#iface, data: &$udata) {
+                        <$dispatch_to as ::wayland_server::Dispatch<#iface, $udata, Self>>::destroyed(state, client, resource, data)
+                    }
+                }
+
+                #global_impl
+            }
+        });
+
+    quote! {
+        #doc_attr
+        #[macro_export]
+        macro_rules! #macro_name {
+            ($(@< $( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+ >)? $dispatch_from:ty : [$($module:ident)::+ : $udata:ty] => $dispatch_to:ty) => {
+                #(#impls)*
+            };
+        }
+    }
 }
 
-fn generate_objects_for(interface: &Interface) -> TokenStream {
+fn generate_objects_for(
+    protocol: &Protocol,
+    interface: &Interface,
+    options: &Options,
+) -> TokenStream {
     let mod_name = Ident::new(&interface.name, Span::call_site());
-    let mod_doc = interface.description.as_ref().map(description_to_doc_attr);
+    let (mod_head, mod_reexports) = crate::common::gen_module_layout(interface, options);
     let iface_name = Ident::new(&snake_to_camel(&interface.name), Span::call_site());
     let iface_const_name = format_ident!("{}_INTERFACE", interface.name.to_ascii_uppercase());
 
-    let enums = crate::common::generate_enums_for(interface);
-    let msg_constants = crate::common::gen_msg_constants(&interface.requests, &interface.events);
+    let enums = if options.shared_enums {
+        crate::common::reexport_enums_for(interface)
+    } else {
+        crate::common::generate_enums_for(interface)
+    };
+    let msg_constants =
+        crate::common::gen_msg_constants(Side::Server, &interface.requests, &interface.events);
 
     let requests = crate::common::gen_message_enum(
         &format_ident!("Request"),
         Side::Server,
         true,
-        &interface.requests,
+        false,
+        interface,
+        options,
     );
     let events = crate::common::gen_message_enum(
         &format_ident!("Event"),
         Side::Server,
         false,
-        &interface.events,
+        false,
+        interface,
+        options,
     );
 
-    let parse_body = crate::common::gen_parse_body(interface, Side::Server);
-    let write_body = crate::common::gen_write_body(interface, Side::Server);
-    let methods = gen_methods(interface);
+    let parse_span =
+        options.tracing.then(|| crate::common::gen_trace_span(interface, Side::Server, true));
+    let write_span =
+        options.tracing.then(|| crate::common::gen_trace_span(interface, Side::Server, false));
+    let parse_body = crate::common::gen_parse_body(interface, Side::Server, false, options);
+    let write_body = crate::common::gen_write_body(interface, Side::Server, options);
+    let arbitrary = options.arbitrary.is_on().then(|| {
+        crate::common::gen_arbitrary_messages(
+            protocol,
+            interface,
+            Side::Server,
+            options.enum_results,
+            options.arbitrary.cfg(),
+        )
+    });
+    let methods = gen_methods(interface, options.enum_results);
+    let error_methods = gen_error_methods(interface);
+    let mock = options.mocks.is_on().then(|| gen_mock(interface, &iface_name, options.mocks.cfg()));
 
     let event_ref = if interface.requests.is_empty() {
         "This interface has no requests."
@@ -55,8 +191,7 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
     let doc_attr = to_doc_attr(&docs);
 
     quote! {
-        #mod_doc
-        pub mod #mod_name {
+        #mod_head mod #mod_name {
             use std::sync::Arc;
             use std::os::unix::io::OwnedFd;
 
@@ -72,6 +207,7 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
             #msg_constants
             #requests
             #events
+            #arbitrary
 
             #doc_attr
             #[derive(Debug, Clone)]
@@ -157,10 +293,12 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
                 }
 
                 fn parse_request(conn: &DisplayHandle, msg: Message<ObjectId, OwnedFd>) -> Result<(Self, Self::Request), DispatchError> {
+                    #parse_span
                     #parse_body
                 }
 
                 fn write_event<'a>(&self, conn: &DisplayHandle, msg: Self::Event<'a>) -> Result<Message<ObjectId, std::os::unix::io::BorrowedFd<'a>>, InvalidId> {
+                    #write_span
                     #write_body
                 }
 
@@ -171,63 +309,70 @@ fn generate_objects_for(interface: &Interface) -> TokenStream {
 
             impl #iface_name {
                 #methods
+                #error_methods
+            }
+
+            #mock
+        }
+
+        #mod_reexports
+    }
+}
+
+// The mock implementation, with the object data of the children of the mocked objects
+fn gen_mock(interface: &Interface, iface_name: &Ident, cfg: Option<TokenStream>) -> TokenStream {
+    let mock_name = format_ident!("Mock{}", iface_name);
+    let doc_attr = to_doc_attr(&format!(
+        "A mock implementation of [`{}`], recording the requests it receives",
+        iface_name
+    ));
+    let children = interface.requests.iter().enumerate().filter_map(|(opcode, request)| {
+        let child = request.args.iter().find(|arg| arg.typ == Type::NewId)?.interface.as_ref()?;
+        let opcode = opcode as u16;
+        let child_mod = Ident::new(child, Span::call_site());
+        let child_type = Ident::new(&snake_to_camel(child), Span::call_site());
+        Some(quote! {
+            #opcode => Some(log.child::<super::#child_mod::#child_type, D>())
+        })
+    });
+
+    quote! {
+        #doc_attr
+        #cfg
+        pub type #mock_name = super::wayland_server::mock::Mock<#iface_name>;
+
+        #cfg
+        impl super::wayland_server::mock::Mockable for #iface_name {
+            fn __child_data<D: 'static>(opcode: u16, log: &super::wayland_server::mock::MockLog) -> Option<Arc<dyn ObjectData<D>>> {
+                match opcode {
+                    #(#children,)*
+                    _ => None,
+                }
             }
         }
     }
 }
 
-fn gen_methods(interface: &Interface) -> TokenStream {
+fn gen_methods(interface: &Interface, enum_results: bool) -> TokenStream {
     interface
         .events
         .iter()
         .map(|request| {
             let method_name = format_ident!(
                 "{}{}",
-                if is_keyword(&request.name) { "_" } else { "" },
-                request.name
+                if is_keyword(&request.rust_name) { "_" } else { "" },
+                request.rust_name
             );
-            let enum_variant = Ident::new(&snake_to_camel(&request.name), Span::call_site());
+            let enum_variant = Ident::new(&snake_to_camel(&request.rust_name), Span::call_site());
 
             let fn_args = request.args.iter().flat_map(|arg| {
-                let arg_name =
-                    format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
+                let arg_name = format_ident!(
+                    "{}{}",
+                    if is_keyword(&arg.rust_name) { "_" } else { "" },
+                    arg.rust_name
+                );
 
-                let arg_type = if let Some(ref enu) = arg.enum_ {
-                    let enum_type = dotted_to_relname(enu);
-                    quote! { #enum_type }
-                } else {
-                    match arg.typ {
-                        Type::Uint => quote! { u32 },
-                        Type::Int => quote! { i32 },
-                        Type::Fixed => quote! { f64 },
-                        Type::String => {
-                            if arg.allow_null {
-                                quote! { Option<String> }
-                            } else {
-                                quote! { String }
-                            }
-                        }
-                        Type::Array => {
-                            if arg.allow_null {
-                                quote! { Option<Vec<u8>> }
-                            } else {
-                                quote! { Vec<u8> }
-                            }
-                        }
-                        Type::Fd => quote! { ::std::os::unix::io::BorrowedFd<'_> },
-                        Type::Object | Type::NewId => {
-                            let iface = arg.interface.as_ref().unwrap();
-                            let iface_mod = Ident::new(iface, Span::call_site());
-                            let iface_type = Ident::new(&snake_to_camel(iface), Span::call_site());
-                            if arg.allow_null {
-                                quote! { Option<&super::#iface_mod::#iface_type> }
-                            } else {
-                                quote! { &super::#iface_mod::#iface_type }
-                            }
-                        }
-                        Type::Destructor => panic!("An argument cannot have type \"destructor\"."),
-                    }
-                };
+                let arg_type = crate::common::gen_method_arg_type(arg, None);
 
                 Some(quote! {
                     #arg_name: #arg_type
@@ -235,9 +380,14 @@ fn gen_methods(interface: &Interface) -> TokenStream {
             });
 
             let enum_args = request.args.iter().flat_map(|arg| {
-                let arg_name =
-                    format_ident!("{}{}", if is_keyword(&arg.name) { "_" } else { "" }, arg.name);
-                if arg.enum_.is_some() {
+                let arg_name = format_ident!(
+                    "{}{}",
+                    if is_keyword(&arg.rust_name) { "_" } else { "" },
+                    arg.rust_name
+                );
+                if arg.enum_.is_some() && !arg.bitfield && enum_results {
+                    Some(quote! { #arg_name: Ok(#arg_name) })
+                } else if arg.enum_.is_some() && !arg.bitfield {
                     Some(quote! { #arg_name: WEnum::Value(#arg_name) })
                 } else if arg.typ == Type::Object || arg.typ == Type::NewId {
                     if arg.allow_null {
@@ -251,9 +401,11 @@ fn gen_methods(interface: &Interface) -> TokenStream {
             });
 
             let doc_attr = request.description.as_ref().map(description_to_doc_attr);
+            let deprecated_attr = deprecated_attr(request.deprecated_since);
 
             quote! {
                 #doc_attr
+                #deprecated_attr
                 #[allow(clippy::too_many_arguments)]
                 pub fn #method_name(&self, #(#fn_args),*) {
                     let _ = self.send_event(
@@ -267,24 +419,74 @@ fn gen_methods(interface: &Interface) -> TokenStream {
         .collect()
 }
 
+// a `post_<entry>_error()` method for each entry of the `error` enum of the interface
+fn gen_error_methods(interface: &Interface) -> TokenStream {
+    let Some(error_enum) = interface.enums.iter().find(|enu| enu.name == "error") else {
+        return TokenStream::new();
+    };
+
+    error_enum
+        .entries
+        .iter()
+        .map(|entry| {
+            let method_name = format_ident!("post_{}_error", entry.rust_name);
+            let prefix =
+                if entry.rust_name.chars().next().unwrap().is_numeric() { "_" } else { "" };
+            let variant = format_ident!("{}{}", prefix, snake_to_camel(&entry.rust_name));
+
+            let doc = match entry.summary {
+                Some(ref summary) => format!(
+                    "Post the `{}` protocol error on this object\n\n{}",
+                    entry.name, summary
+                ),
+                None => format!("Post the `{}` protocol error on this object", entry.name),
+            };
+            let doc_attr = to_doc_attr(&doc);
+
+            quote! {
+                #doc_attr
+                pub fn #method_name(&self, error: impl Into<String>) {
+                    Resource::post_error(self, Error::#variant, error)
+                }
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
     fn server_gen() {
         let protocol_file =
             std::fs::File::open("./tests/scanner_assets/test-protocol.xml").unwrap();
-        let protocol_parsed = crate::parse::parse(protocol_file);
-        let generated: String = super::generate_server_objects(&protocol_parsed).to_string();
-        let generated = crate::format_rust_code(&generated);
-
-        let reference =
-            std::fs::read_to_string("./tests/scanner_assets/test-server-code.rs").unwrap();
-        let reference = crate::format_rust_code(&reference);
-
-        if reference != generated {
-            let diff = similar::TextDiff::from_lines(&reference, &generated);
-            print!("{}", diff.unified_diff().context_radius(10).header("reference", "generated"));
-            panic!("Generated does not match reference!")
+        let protocol_parsed = crate::parse::parse(protocol_file).unwrap();
+        crate::check_reference(
+            "test-server-code.rs",
+            &super::generate_server_objects(&protocol_parsed, &Default::default()),
+        );
+    }
+
+    #[test]
+    fn server_gen_options() {
+        // each option is checked on its own, against `options/server-<option>.rs`
+        let options = [
+            ("mocks", Some("mocks")),
+            ("delegates", None),
+            ("serde", Some("serde")),
+            ("tracing", None),
+            ("enum_results", None),
+            ("arbitrary", Some("arbitrary")),
+        ];
+        for (name, value) in options {
+            let options = crate::Options::parse(
+                &[(name.to_owned(), value.map(str::to_owned))],
+                &[crate::Side::Server],
+            );
+            let protocol = crate::options_protocol(&options);
+            crate::check_reference(
+                &format!("options/server-{}.rs", name),
+                &super::generate_server_objects(&protocol, &options),
+            );
         }
     }
 }
@@ -184,3 +184,72 @@ pub fn parse_lit_str_token(mut stream: proc_macro::TokenStream) -> String {
         return parse_lit_str(&literal.to_string());
     }
 }
+
+// the path arguments of a code generation macro: a string, or a bracketed list of strings
+fn parse_paths(token: proc_macro::TokenTree) -> Vec<String> {
+    match token {
+        proc_macro::TokenTree::Literal(_) => vec![parse_lit_str_token(token.into())],
+        proc_macro::TokenTree::Group(group)
+            if group.delimiter() == proc_macro::Delimiter::Bracket =>
+        {
+            let mut paths = Vec::new();
+            let mut iter = group.stream().into_iter();
+            while let Some(token) = iter.next() {
+                paths.push(parse_lit_str_token(token.into()));
+                match iter.next() {
+                    Some(proc_macro::TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+                    None => break,
+                    Some(token) => panic!("expected `,` found `{:?}`", token),
+                }
+            }
+            assert!(!paths.is_empty(), "expected at least one protocol file");
+            paths
+        }
+        // the invisible group of a macro_rules fragment
+        proc_macro::TokenTree::Group(group) => {
+            let mut iter = group.stream().into_iter();
+            let token = iter.next().expect("expected string argument");
+            assert!(iter.next().is_none(), "unexpected trailing token");
+            parse_paths(token)
+        }
+        token => panic!("expected string argument found `{:?}`", token),
+    }
+}
+
+// the path arguments of a code generation macro, followed by its comma-separated options, either a
+// name or a `name = "value"` pair
+pub fn parse_paths_and_options(
+    stream: proc_macro::TokenStream,
+) -> (Vec<String>, Vec<(String, Option<String>)>) {
+    let mut iter = stream.into_iter().peekable();
+    let paths = parse_paths(iter.next().expect("expected string argument"));
+    let mut options = Vec::new();
+    while let Some(token) = iter.next() {
+        match token {
+            proc_macro::TokenTree::Punct(punct) if punct.as_char() == ',' => {}
+            token => panic!("expected `,` found `{:?}`", token),
+        }
+        let name = match iter.next() {
+            Some(proc_macro::TokenTree::Ident(ident)) => ident.to_string(),
+            // a trailing comma
+            None => break,
+            Some(token) => panic!("expected option name found `{:?}`", token),
+        };
+        let value = match iter.peek() {
+            Some(proc_macro::TokenTree::Punct(punct)) if punct.as_char() == '=' => {
+                iter.next();
+                match iter.next() {
+                    Some(token @ proc_macro::TokenTree::Literal(_)) => {
+                        Some(parse_lit_str_token(token.into()))
+                    }
+                    token => {
+                        panic!("expected string value of option `{}` found `{:?}`", name, token)
+                    }
+                }
+            }
+            _ => None,
+        };
+        options.push((name, value));
+    }
+    (paths, options)
+}
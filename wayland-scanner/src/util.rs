@@ -12,6 +12,13 @@ pub(crate) fn description_to_doc_attr((short, long): &(String, String)) -> Token
     to_doc_attr(&format!("{}\n\n{}", short, long))
 }
 
+pub(crate) fn deprecated_attr(deprecated_since: Option<u32>) -> Option<TokenStream> {
+    deprecated_since.map(|version| {
+        let note = format!("Deprecated since version {} of the interface", version);
+        quote!(#[deprecated(note = #note)])
+    })
+}
+
 pub fn is_keyword(txt: &str) -> bool {
     matches!(
         txt,
@@ -0,0 +1,204 @@
+use std::fmt::Write;
+
+use proc_macro2::{Ident, Span, TokenStream};
+
+use quote::{format_ident, quote};
+
+use crate::{
+    protocol::{Interface, Message, Protocol, Type},
+    util::{dotted_to_relname, is_keyword, snake_to_camel, to_doc_attr},
+    Options,
+};
+
+pub fn generate_wire_types(protocol: &Protocol, options: &Options) -> TokenStream {
+    protocol.interfaces.iter().map(|interface| generate_types_for(interface, options)).collect()
+}
+
+fn generate_types_for(interface: &Interface, options: &Options) -> TokenStream {
+    let mod_name = Ident::new(&interface.name, Span::call_site());
+    let (mod_head, mod_reexports) = crate::common::gen_module_layout(interface, options);
+    let version = interface.version;
+
+    let enums = crate::common::generate_enums_for(interface);
+    let requests = gen_wire_enum(&format_ident!("Request"), &interface.requests);
+    let events = gen_wire_enum(&format_ident!("Event"), &interface.events);
+
+    quote! {
+        #mod_head mod #mod_name {
+            /// The version of this interface in the protocol file
+            pub const VERSION: u32 = #version;
+
+            #enums
+            #requests
+            #events
+        }
+        #mod_reexports
+    }
+}
+
+// the messages of an interface as they are on the wire, only using the types of `core` and `alloc`
+fn gen_wire_enum(name: &Ident, messages: &[Message]) -> TokenStream {
+    let variants = messages.iter().map(|msg| {
+        let mut docs = String::new();
+        if let Some((ref short, ref long)) = msg.description {
+            write!(docs, "{}\n\n{}\n", short, long.trim()).unwrap();
+        }
+        if msg.since > 1 {
+            write!(docs, "\nOnly available since version {} of the interface", msg.since).unwrap();
+        }
+        let doc_attr = to_doc_attr(&docs);
+        let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+        if msg.args.is_empty() {
+            return quote! { #doc_attr #msg_name };
+        }
+
+        let fields = msg.args.iter().map(|arg| {
+            let field_name = format_ident!(
+                "{}{}",
+                if is_keyword(&arg.rust_name) { "_" } else { "" },
+                arg.rust_name
+            );
+            let field_type = if let Some(ref enu) = arg.rust_enum {
+                let enum_type = dotted_to_relname(enu);
+                if arg.bitfield {
+                    quote! { #enum_type }
+                } else {
+                    quote! { ::core::result::Result<#enum_type, u32> }
+                }
+            } else {
+                match arg.typ {
+                    Type::Int => quote! { i32 },
+                    Type::Uint | Type::Object | Type::Fd => quote! { u32 },
+                    Type::Fixed => quote! { f64 },
+                    Type::String => quote! { ::alloc::string::String },
+                    Type::Array => quote! { ::alloc::vec::Vec<u8> },
+                    Type::NewId if arg.interface.is_some() => quote! { u32 },
+                    Type::NewId => quote! { (::alloc::string::String, u32, u32) },
+                    Type::Destructor => panic!("An argument cannot have type \"destructor\"."),
+                }
+            };
+            let field_type = if arg.allow_null {
+                quote! { ::core::option::Option<#field_type> }
+            } else {
+                field_type
+            };
+
+            let mut docs = arg
+                .description
+                .as_ref()
+                .map(|(short, long)| format!("{}\n\n{}", short, long))
+                .or_else(|| arg.summary.clone())
+                .unwrap_or_default();
+            let wire_doc = match arg.typ {
+                Type::Object => Some("the protocol id of the object"),
+                Type::NewId if arg.interface.is_some() => Some("the protocol id of the new object"),
+                Type::NewId => Some("the interface, version and protocol id of the new object"),
+                Type::Fd => Some("the index of the file descriptor among the ones of the message"),
+                _ if arg.enum_.is_some() && !arg.bitfield => {
+                    Some("the value, or the raw value if it is not a variant of the enum")
+                }
+                _ => None,
+            };
+            if let Some(wire_doc) = wire_doc {
+                write!(docs, "\n\nOn the wire: {}", wire_doc).unwrap();
+            }
+            let doc_attr = to_doc_attr(docs.trim_start());
+
+            quote! {
+                #doc_attr
+                #field_name: #field_type
+            }
+        });
+        quote! {
+            #doc_attr
+            #msg_name { #(#fields,)* }
+        }
+    });
+
+    let arms = |value: fn(usize, &Message) -> TokenStream| {
+        messages
+            .iter()
+            .enumerate()
+            .map(move |(opcode, msg)| {
+                let msg_name = Ident::new(&snake_to_camel(&msg.rust_name), Span::call_site());
+                let value = value(opcode, msg);
+                if msg.args.is_empty() {
+                    quote! { #name::#msg_name => #value }
+                } else {
+                    quote! { #name::#msg_name { .. } => #value }
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let opcodes = arms(|opcode, _| {
+        let opcode = opcode as u16;
+        quote! { #opcode }
+    });
+    let names = arms(|_, msg| {
+        let name = &msg.name;
+        quote! { #name }
+    });
+    let sinces = arms(|_, msg| {
+        let since = msg.since;
+        quote! { #since }
+    });
+    let doc_attr =
+        to_doc_attr(&format!("The {}s of this interface", name.to_string().to_lowercase()));
+
+    quote! {
+        #doc_attr
+        #[derive(Debug, Clone, PartialEq)]
+        #[non_exhaustive]
+        pub enum #name {
+            #(#variants,)*
+        }
+
+        impl #name {
+            #[doc="Get the opcode number of this message"]
+            pub fn opcode(&self) -> u16 {
+                match *self {
+                    #(#opcodes,)*
+                }
+            }
+
+            #[doc="Get the name of this message, as in the protocol description"]
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    #(#names,)*
+                }
+            }
+
+            #[doc="Get the minimal object version supporting this message"]
+            pub fn since(&self) -> u32 {
+                match *self {
+                    #(#sinces,)*
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn wire_gen_no_std() {
+        let protocol_file =
+            std::fs::File::open("./tests/scanner_assets/test-protocol.xml").unwrap();
+        let protocol_parsed = crate::parse::parse(protocol_file).unwrap();
+        let generated = super::generate_wire_types(&protocol_parsed, &Default::default());
+
+        // the generated code must build in the crates without `std`
+        fn refers_to_std(tokens: proc_macro2::TokenStream) -> bool {
+            tokens.into_iter().any(|token| match token {
+                proc_macro2::TokenTree::Ident(ident) => ident == "std",
+                proc_macro2::TokenTree::Group(group) => refers_to_std(group.stream()),
+                _ => false,
+            })
+        }
+        assert!(!refers_to_std(generated.clone()));
+
+        let generated = crate::format_rust_code(&generated.to_string());
+        assert!(generated.contains("pub enum Request {"));
+        assert!(generated.contains("::alloc::string::String"));
+    }
+}
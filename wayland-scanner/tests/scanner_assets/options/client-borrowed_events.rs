@@ -0,0 +1,955 @@
+#[doc = "a global creating widgets\n\nThe global creating the widgets."]
+pub mod opt_manager {
+    use super::wayland_client::{
+        backend::{
+            protocol::{same_interface, Argument, Interface, Message, WEnum},
+            smallvec, Backend, InvalidId, ObjectData, ObjectId, WeakBackend,
+        },
+        Connection, Dispatch, DispatchError, Proxy, QueueHandle, QueueProxyData, Weak,
+    };
+    use std::os::unix::io::OwnedFd;
+    use std::sync::Arc;
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_GET_WIDGET_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_GET_WIDGET_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_WIDGET_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::NewId,
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::Yes,
+        ),
+    ];
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_DESTROY_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_DESTROY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[];
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Request<'a> {
+        #[doc = "create a widget"]
+        GetWidget { parent: Option<super::opt_widget::OptWidget> },
+        #[doc = "destroy the manager\n\n\n\nThis is a destructor, once sent this object cannot be used any longer."]
+        Destroy,
+        #[doc(hidden)]
+        __phantom_lifetime {
+            phantom: std::marker::PhantomData<&'a ()>,
+            never: std::convert::Infallible,
+        },
+    }
+    impl<'a> Request<'a> {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Request::GetWidget { .. } => 0u16,
+                Request::Destroy => 1u16,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::GetWidget { .. } => "get_widget",
+                Request::Destroy => "destroy",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::GetWidget { .. } => 1u32,
+                Request::Destroy => 1u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptManager) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::OPT_MANAGER_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Event {}
+    impl Event {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {}
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {}
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {}
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptManager) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::OPT_MANAGER_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[doc = "a global creating widgets\n\nThe global creating the widgets.\n\nThis interface has no events."]
+    #[derive(Debug, Clone)]
+    pub struct OptManager {
+        id: ObjectId,
+        version: u32,
+        data: Option<Arc<dyn ObjectData>>,
+        backend: WeakBackend,
+    }
+    impl std::cmp::PartialEq for OptManager {
+        fn eq(&self, other: &OptManager) -> bool {
+            self.id == other.id
+        }
+    }
+    impl std::cmp::Eq for OptManager {}
+    impl PartialEq<Weak<OptManager>> for OptManager {
+        fn eq(&self, other: &Weak<OptManager>) -> bool {
+            self.id == other.id()
+        }
+    }
+    impl std::borrow::Borrow<ObjectId> for OptManager {
+        fn borrow(&self) -> &ObjectId {
+            &self.id
+        }
+    }
+    impl std::hash::Hash for OptManager {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state)
+        }
+    }
+    impl super::wayland_client::Proxy for OptManager {
+        type Request<'request> = Request<'request>;
+        type Event = Event;
+        #[inline]
+        fn interface() -> &'static Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        #[inline]
+        fn id(&self) -> ObjectId {
+            self.id.clone()
+        }
+        #[inline]
+        fn version(&self) -> u32 {
+            self.version
+        }
+        #[inline]
+        fn data<U: Send + Sync + 'static>(&self) -> Option<&U> {
+            self.data.as_ref().and_then(|arc| arc.data_as_any().downcast_ref::<U>())
+        }
+        fn object_data(&self) -> Option<&Arc<dyn ObjectData>> {
+            self.data.as_ref()
+        }
+        fn backend(&self) -> &WeakBackend {
+            &self.backend
+        }
+        fn send_request(&self, req: Self::Request<'_>) -> Result<(), InvalidId> {
+            let conn = Connection::from_backend(self.backend.upgrade().ok_or(InvalidId)?);
+            let id = conn.send_request(self, req, None)?;
+            debug_assert!(id.is_null());
+            Ok(())
+        }
+        fn send_constructor<I: Proxy>(
+            &self,
+            req: Self::Request<'_>,
+            data: Arc<dyn ObjectData>,
+        ) -> Result<I, InvalidId> {
+            let conn = Connection::from_backend(self.backend.upgrade().ok_or(InvalidId)?);
+            let id = conn.send_request(self, req, Some(data))?;
+            Proxy::from_id(&conn, id)
+        }
+        #[inline]
+        fn from_id(conn: &Connection, id: ObjectId) -> Result<Self, InvalidId> {
+            if !same_interface(id.interface(), Self::interface()) && !id.is_null() {
+                return Err(InvalidId);
+            }
+            let version = conn.object_info(id.clone()).map(|info| info.version).unwrap_or(0);
+            let data = conn.get_object_data(id.clone()).ok();
+            let backend = conn.backend().downgrade();
+            Ok(OptManager { id, data, version, backend })
+        }
+        #[inline]
+        fn inert(backend: WeakBackend) -> Self {
+            OptManager { id: ObjectId::null(), data: None, version: 0, backend }
+        }
+        fn parse_event(
+            conn: &Connection,
+            msg: Message<ObjectId, OwnedFd>,
+        ) -> Result<(Self, Self::Event), DispatchError> {
+            let me = Self::from_id(conn, msg.sender_id.clone()).unwrap();
+            let mut arg_iter = msg.args.into_iter();
+            match msg.opcode {
+                _ => Err(DispatchError::BadMessage {
+                    sender_id: msg.sender_id,
+                    interface: Self::interface().name,
+                    opcode: msg.opcode,
+                }),
+            }
+        }
+        fn write_request<'a>(
+            &self,
+            conn: &Connection,
+            msg: Self::Request<'a>,
+        ) -> Result<
+            (
+                Message<ObjectId, std::os::unix::io::BorrowedFd<'a>>,
+                Option<(&'static Interface, u32)>,
+            ),
+            InvalidId,
+        > {
+            match msg {
+                Request::GetWidget { parent } => {
+                    let child_spec = {
+                        let my_info = conn.object_info(self.id())?;
+                        Some((super::opt_widget::OptWidget::interface(), my_info.version))
+                    };
+                    let args = {
+                        let mut vec = smallvec::SmallVec::new();
+                        vec.push(Argument::NewId(ObjectId::null()));
+                        vec.push(if let Some(obj) = parent {
+                            Argument::Object(Proxy::id(&obj))
+                        } else {
+                            Argument::Object(ObjectId::null())
+                        });
+                        vec
+                    };
+                    Ok((Message { sender_id: self.id.clone(), opcode: 0u16, args }, child_spec))
+                }
+                Request::Destroy {} => {
+                    let child_spec = None;
+                    let args = smallvec::SmallVec::new();
+                    Ok((Message { sender_id: self.id.clone(), opcode: 1u16, args }, child_spec))
+                }
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+    }
+    impl OptManager {
+        #[doc = "create a widget"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn get_widget<
+            U: Send + Sync + 'static,
+            D: Dispatch<super::opt_widget::OptWidget, U> + 'static,
+        >(
+            &self,
+            parent: Option<&super::opt_widget::OptWidget>,
+            qh: &QueueHandle<D>,
+            udata: U,
+        ) -> super::opt_widget::OptWidget {
+            self.send_constructor(
+                Request::GetWidget { parent: parent.cloned() },
+                qh.make_data::<super::opt_widget::OptWidget, U>(udata),
+            )
+            .unwrap_or_else(|_| Proxy::inert(self.backend.clone()))
+        }
+        #[doc = "destroy the manager"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn destroy(&self) {
+            let backend = match self.backend.upgrade() {
+                Some(b) => b,
+                None => return,
+            };
+            let conn = Connection::from_backend(backend);
+            let _ = conn.send_request(self, Request::Destroy {}, None);
+        }
+    }
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum BorrowedEvent<'a> {
+        #[doc(hidden)]
+        __phantom_lifetime {
+            phantom: std::marker::PhantomData<&'a ()>,
+            never: std::convert::Infallible,
+        },
+    }
+    impl<'a> BorrowedEvent<'a> {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptManager) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for BorrowedEvent<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::OPT_MANAGER_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    impl<'a> BorrowedEvent<'a> {
+        #[doc = "Convert this event into an owned [`Event`], copying its strings and arrays"]
+        pub fn into_owned(self) -> Event {
+            match self {
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+    }
+    impl OptManager {
+        #[doc = "Parse an event of a [`OptManager`], borrowing its strings and arrays from the message\n\nThis is meant to be called from [`ObjectData::event_borrowed()`], which receives the message from the buffers of the backend without copying it, to dispatch the events without allocating."]
+        pub fn parse_borrowed_event<'a>(
+            conn: &Connection,
+            msg: super::wayland_client::backend::protocol::BorrowedMessage<'a, ObjectId, OwnedFd>,
+        ) -> Result<(Self, BorrowedEvent<'a>), DispatchError> {
+            let me = Self::from_id(conn, msg.sender_id.clone()).unwrap();
+            let mut arg_iter = msg.args.into_iter();
+            match msg.opcode {
+                _ => Err(DispatchError::BadMessage {
+                    sender_id: msg.sender_id,
+                    interface: Self::interface().name,
+                    opcode: msg.opcode,
+                }),
+            }
+        }
+    }
+}
+#[doc = "a widget\n\nA widget with a state and a title."]
+pub mod opt_widget {
+    use super::wayland_client::{
+        backend::{
+            protocol::{same_interface, Argument, Interface, Message, WEnum},
+            smallvec, Backend, InvalidId, ObjectData, ObjectId, WeakBackend,
+        },
+        Connection, Dispatch, DispatchError, Proxy, QueueHandle, QueueProxyData, Weak,
+    };
+    use std::os::unix::io::OwnedFd;
+    use std::sync::Arc;
+    #[repr(u32)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[non_exhaustive]
+    pub enum State {
+        #[doc = "the widget is off"]
+        Off = 0,
+        #[doc = "the widget is on"]
+        On = 1,
+    }
+    impl core::convert::TryFrom<u32> for State {
+        type Error = ();
+        #[allow(deprecated)]
+        fn try_from(val: u32) -> Result<State, ()> {
+            match val {
+                0 => Ok(State::Off),
+                1 => Ok(State::On),
+                _ => Err(()),
+            }
+        }
+    }
+    impl core::convert::From<State> for u32 {
+        fn from(val: State) -> u32 {
+            val as u32
+        }
+    }
+    bitflags::bitflags! { # [derive (Clone , Copy , Debug , Eq , Hash , PartialEq)] pub struct Caps : u32 { # [doc = "the widget can be resized"] const Resize = 1 ; # [doc = "the widget can be moved"] const Move = 2 ; } }
+    impl core::convert::TryFrom<u32> for Caps {
+        type Error = ();
+        fn try_from(val: u32) -> Result<Caps, ()> {
+            Caps::from_bits(val).ok_or(())
+        }
+    }
+    impl core::convert::From<Caps> for u32 {
+        fn from(val: Caps) -> u32 {
+            val.bits()
+        }
+    }
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_CONFIGURE_SINCE: u32 = 2u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_CONFIGURE_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_CONFIGURE_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[
+            super::wayland_client::backend::protocol::ArgumentType::Uint,
+            super::wayland_client::backend::protocol::ArgumentType::Str(
+                super::wayland_client::backend::protocol::AllowNull::No,
+            ),
+            super::wayland_client::backend::protocol::ArgumentType::Array,
+            super::wayland_client::backend::protocol::ArgumentType::Fd,
+            super::wayland_client::backend::protocol::ArgumentType::Fixed,
+        ];
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_DESTROY_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_DESTROY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[];
+    #[doc = r" The minimal object version supporting this event"]
+    pub const EVT_STATE_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this event"]
+    pub const EVT_STATE_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_STATE_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::Str(
+            super::wayland_client::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Array,
+    ];
+    #[doc = r" The minimal object version supporting this event"]
+    pub const EVT_CHILD_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this event"]
+    pub const EVT_CHILD_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_CHILD_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::NewId];
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Request<'a> {
+        #[doc = "configure the widget\n\n\n\nOnly available since version 2 of the interface"]
+        Configure {
+            state: WEnum<State>,
+            title: String,
+            data: Vec<u8>,
+            fd: std::os::unix::io::BorrowedFd<'a>,
+            scale: f64,
+        },
+        #[doc = "destroy the widget\n\n\n\nThis is a destructor, once sent this object cannot be used any longer."]
+        Destroy,
+        #[doc(hidden)]
+        __phantom_lifetime {
+            phantom: std::marker::PhantomData<&'a ()>,
+            never: std::convert::Infallible,
+        },
+    }
+    impl<'a> Request<'a> {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Request::Configure { .. } => 0u16,
+                Request::Destroy => 1u16,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Configure { .. } => "configure",
+                Request::Destroy => "destroy",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Configure { .. } => 2u32,
+                Request::Destroy => 1u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptWidget) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::OPT_WIDGET_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Event {
+        #[doc = "the state of the widget"]
+        State { state: WEnum<State>, caps: Caps, title: Option<String>, data: Vec<u8> },
+        #[doc = "a child of the widget"]
+        Child { id: super::opt_widget::OptWidget },
+    }
+    impl Event {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Event::State { .. } => 0u16,
+                Event::Child { .. } => 1u16,
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::State { .. } => "state",
+                Event::Child { .. } => "child",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::State { .. } => 1u32,
+                Event::Child { .. } => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptWidget) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::OPT_WIDGET_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[doc = "a widget\n\nA widget with a state and a title.\n\nSee also the [Event] enum for this interface."]
+    #[derive(Debug, Clone)]
+    pub struct OptWidget {
+        id: ObjectId,
+        version: u32,
+        data: Option<Arc<dyn ObjectData>>,
+        backend: WeakBackend,
+    }
+    impl std::cmp::PartialEq for OptWidget {
+        fn eq(&self, other: &OptWidget) -> bool {
+            self.id == other.id
+        }
+    }
+    impl std::cmp::Eq for OptWidget {}
+    impl PartialEq<Weak<OptWidget>> for OptWidget {
+        fn eq(&self, other: &Weak<OptWidget>) -> bool {
+            self.id == other.id()
+        }
+    }
+    impl std::borrow::Borrow<ObjectId> for OptWidget {
+        fn borrow(&self) -> &ObjectId {
+            &self.id
+        }
+    }
+    impl std::hash::Hash for OptWidget {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state)
+        }
+    }
+    impl super::wayland_client::Proxy for OptWidget {
+        type Request<'request> = Request<'request>;
+        type Event = Event;
+        #[inline]
+        fn interface() -> &'static Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        #[inline]
+        fn id(&self) -> ObjectId {
+            self.id.clone()
+        }
+        #[inline]
+        fn version(&self) -> u32 {
+            self.version
+        }
+        #[inline]
+        fn data<U: Send + Sync + 'static>(&self) -> Option<&U> {
+            self.data.as_ref().and_then(|arc| arc.data_as_any().downcast_ref::<U>())
+        }
+        fn object_data(&self) -> Option<&Arc<dyn ObjectData>> {
+            self.data.as_ref()
+        }
+        fn backend(&self) -> &WeakBackend {
+            &self.backend
+        }
+        fn send_request(&self, req: Self::Request<'_>) -> Result<(), InvalidId> {
+            let conn = Connection::from_backend(self.backend.upgrade().ok_or(InvalidId)?);
+            let id = conn.send_request(self, req, None)?;
+            debug_assert!(id.is_null());
+            Ok(())
+        }
+        fn send_constructor<I: Proxy>(
+            &self,
+            req: Self::Request<'_>,
+            data: Arc<dyn ObjectData>,
+        ) -> Result<I, InvalidId> {
+            let conn = Connection::from_backend(self.backend.upgrade().ok_or(InvalidId)?);
+            let id = conn.send_request(self, req, Some(data))?;
+            Proxy::from_id(&conn, id)
+        }
+        #[inline]
+        fn from_id(conn: &Connection, id: ObjectId) -> Result<Self, InvalidId> {
+            if !same_interface(id.interface(), Self::interface()) && !id.is_null() {
+                return Err(InvalidId);
+            }
+            let version = conn.object_info(id.clone()).map(|info| info.version).unwrap_or(0);
+            let data = conn.get_object_data(id.clone()).ok();
+            let backend = conn.backend().downgrade();
+            Ok(OptWidget { id, data, version, backend })
+        }
+        #[inline]
+        fn inert(backend: WeakBackend) -> Self {
+            OptWidget { id: ObjectId::null(), data: None, version: 0, backend }
+        }
+        fn parse_event(
+            conn: &Connection,
+            msg: Message<ObjectId, OwnedFd>,
+        ) -> Result<(Self, Self::Event), DispatchError> {
+            let me = Self::from_id(conn, msg.sender_id.clone()).unwrap();
+            let mut arg_iter = msg.args.into_iter();
+            match msg.opcode {
+                0u16 => {
+                    if let (
+                        Some(Argument::Uint(state)),
+                        Some(Argument::Uint(caps)),
+                        Some(Argument::Str(title)),
+                        Some(Argument::Array(data)),
+                    ) = (arg_iter.next(), arg_iter.next(), arg_iter.next(), arg_iter.next())
+                    {
+                        Ok((
+                            me,
+                            Event::State {
+                                state: From::from(state as u32),
+                                caps: Caps::from_bits_retain(caps as u32),
+                                title: title
+                                    .as_ref()
+                                    .map(|s| String::from_utf8_lossy(s.as_bytes()).into_owned()),
+                                data: *data,
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                1u16 => {
+                    if let (Some(Argument::NewId(id))) = (arg_iter.next()) {
+                        Ok((
+                            me,
+                            Event::Child {
+                                id: match <super::opt_widget::OptWidget as Proxy>::from_id(
+                                    conn,
+                                    id.clone(),
+                                ) {
+                                    Ok(p) => p,
+                                    Err(_) => {
+                                        return Err(DispatchError::BadMessage {
+                                            sender_id: msg.sender_id,
+                                            interface: Self::interface().name,
+                                            opcode: msg.opcode,
+                                        })
+                                    }
+                                },
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                _ => Err(DispatchError::BadMessage {
+                    sender_id: msg.sender_id,
+                    interface: Self::interface().name,
+                    opcode: msg.opcode,
+                }),
+            }
+        }
+        fn write_request<'a>(
+            &self,
+            conn: &Connection,
+            msg: Self::Request<'a>,
+        ) -> Result<
+            (
+                Message<ObjectId, std::os::unix::io::BorrowedFd<'a>>,
+                Option<(&'static Interface, u32)>,
+            ),
+            InvalidId,
+        > {
+            match msg {
+                Request::Configure { state, title, data, fd, scale } => {
+                    let child_spec = None;
+                    let args = smallvec::SmallVec::from_vec(vec![
+                        Argument::Uint(state.into()),
+                        Argument::Str(Some(Box::new(std::ffi::CString::new(title).unwrap()))),
+                        Argument::Array(Box::new(data)),
+                        Argument::Fd(fd),
+                        Argument::Fixed((scale * 256.) as i32),
+                    ]);
+                    Ok((Message { sender_id: self.id.clone(), opcode: 0u16, args }, child_spec))
+                }
+                Request::Destroy {} => {
+                    let child_spec = None;
+                    let args = smallvec::SmallVec::new();
+                    Ok((Message { sender_id: self.id.clone(), opcode: 1u16, args }, child_spec))
+                }
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+    }
+    impl OptWidget {
+        #[doc = "configure the widget"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn configure(
+            &self,
+            state: State,
+            title: String,
+            data: Vec<u8>,
+            fd: ::std::os::unix::io::BorrowedFd<'_>,
+            scale: f64,
+        ) {
+            let backend = match self.backend.upgrade() {
+                Some(b) => b,
+                None => return,
+            };
+            let conn = Connection::from_backend(backend);
+            let _ = conn.send_request(
+                self,
+                Request::Configure { state: WEnum::Value(state), title, data, fd, scale },
+                None,
+            );
+        }
+        #[doc = "destroy the widget"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn destroy(&self) {
+            let backend = match self.backend.upgrade() {
+                Some(b) => b,
+                None => return,
+            };
+            let conn = Connection::from_backend(backend);
+            let _ = conn.send_request(self, Request::Destroy {}, None);
+        }
+    }
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum BorrowedEvent<'a> {
+        #[doc = "the state of the widget"]
+        State {
+            state: WEnum<State>,
+            caps: Caps,
+            title: Option<std::borrow::Cow<'a, str>>,
+            data: &'a [u8],
+        },
+        #[doc = "a child of the widget"]
+        Child { id: super::opt_widget::OptWidget },
+        #[doc(hidden)]
+        __phantom_lifetime {
+            phantom: std::marker::PhantomData<&'a ()>,
+            never: std::convert::Infallible,
+        },
+    }
+    impl<'a> BorrowedEvent<'a> {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                BorrowedEvent::State { .. } => 0u16,
+                BorrowedEvent::Child { .. } => 1u16,
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                BorrowedEvent::State { .. } => "state",
+                BorrowedEvent::Child { .. } => "child",
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                BorrowedEvent::State { .. } => 1u32,
+                BorrowedEvent::Child { .. } => 1u32,
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptWidget) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for BorrowedEvent<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::OPT_WIDGET_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    impl<'a> BorrowedEvent<'a> {
+        #[doc = "Convert this event into an owned [`Event`], copying its strings and arrays"]
+        pub fn into_owned(self) -> Event {
+            match self {
+                BorrowedEvent::State { state, caps, title, data } => Event::State {
+                    state,
+                    caps,
+                    title: title.map(|s| s.into_owned()),
+                    data: data.to_vec(),
+                },
+                BorrowedEvent::Child { id } => Event::Child { id },
+                BorrowedEvent::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+    }
+    impl OptWidget {
+        #[doc = "Parse an event of a [`OptWidget`], borrowing its strings and arrays from the message\n\nThis is meant to be called from [`ObjectData::event_borrowed()`], which receives the message from the buffers of the backend without copying it, to dispatch the events without allocating."]
+        pub fn parse_borrowed_event<'a>(
+            conn: &Connection,
+            msg: super::wayland_client::backend::protocol::BorrowedMessage<'a, ObjectId, OwnedFd>,
+        ) -> Result<(Self, BorrowedEvent<'a>), DispatchError> {
+            let me = Self::from_id(conn, msg.sender_id.clone()).unwrap();
+            let mut arg_iter = msg.args.into_iter();
+            match msg.opcode {
+                0u16 => {
+                    if let (
+                        Some(super::wayland_client::backend::protocol::BorrowedArgument::Uint(
+                            state,
+                        )),
+                        Some(super::wayland_client::backend::protocol::BorrowedArgument::Uint(
+                            caps,
+                        )),
+                        Some(super::wayland_client::backend::protocol::BorrowedArgument::Str(
+                            title,
+                        )),
+                        Some(super::wayland_client::backend::protocol::BorrowedArgument::Array(
+                            data,
+                        )),
+                    ) = (arg_iter.next(), arg_iter.next(), arg_iter.next(), arg_iter.next())
+                    {
+                        Ok((
+                            me,
+                            BorrowedEvent::State {
+                                state: From::from(state as u32),
+                                caps: Caps::from_bits_retain(caps as u32),
+                                title: title.map(|s| String::from_utf8_lossy(s.to_bytes())),
+                                data,
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                1u16 => {
+                    if let (Some(
+                        super::wayland_client::backend::protocol::BorrowedArgument::NewId(id),
+                    )) = (arg_iter.next())
+                    {
+                        Ok((
+                            me,
+                            BorrowedEvent::Child {
+                                id: match <super::opt_widget::OptWidget as Proxy>::from_id(
+                                    conn,
+                                    id.clone(),
+                                ) {
+                                    Ok(p) => p,
+                                    Err(_) => {
+                                        return Err(DispatchError::BadMessage {
+                                            sender_id: msg.sender_id,
+                                            interface: Self::interface().name,
+                                            opcode: msg.opcode,
+                                        })
+                                    }
+                                },
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                _ => Err(DispatchError::BadMessage {
+                    sender_id: msg.sender_id,
+                    interface: Self::interface().name,
+                    opcode: msg.opcode,
+                }),
+            }
+        }
+    }
+}
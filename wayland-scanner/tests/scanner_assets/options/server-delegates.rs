@@ -0,0 +1,684 @@
+#[doc = "a global creating widgets\n\nThe global creating the widgets."]
+pub mod opt_manager {
+    use super::wayland_server::{
+        backend::{
+            protocol::{same_interface, Argument, Interface, Message, WEnum},
+            smallvec, InvalidId, ObjectData, ObjectId, WeakHandle,
+        },
+        Dispatch, DispatchError, DisplayHandle, New, Resource, ResourceData, Weak,
+    };
+    use std::os::unix::io::OwnedFd;
+    use std::sync::Arc;
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_GET_WIDGET_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_GET_WIDGET_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_WIDGET_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::NewId,
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::Yes,
+        ),
+    ];
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_DESTROY_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_DESTROY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[];
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Request {
+        #[doc = "create a widget"]
+        GetWidget {
+            id: New<super::opt_widget::OptWidget>,
+            parent: Option<super::opt_widget::OptWidget>,
+        },
+        #[doc = "destroy the manager\n\n\n\nThis is a destructor, once received this object cannot be used any longer."]
+        Destroy,
+    }
+    impl Request {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Request::GetWidget { .. } => 0u16,
+                Request::Destroy => 1u16,
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::GetWidget { .. } => "get_widget",
+                Request::Destroy => "destroy",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::GetWidget { .. } => 1u32,
+                Request::Destroy => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptManager) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::OPT_MANAGER_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Event<'a> {
+        #[doc(hidden)]
+        __phantom_lifetime {
+            phantom: std::marker::PhantomData<&'a ()>,
+            never: std::convert::Infallible,
+        },
+    }
+    impl<'a> Event<'a> {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptManager) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::OPT_MANAGER_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[doc = "a global creating widgets\n\nThe global creating the widgets.\n\nSee also the [Request] enum for this interface."]
+    #[derive(Debug, Clone)]
+    pub struct OptManager {
+        id: ObjectId,
+        version: u32,
+        data: Option<Arc<dyn std::any::Any + Send + Sync + 'static>>,
+        handle: WeakHandle,
+    }
+    impl std::cmp::PartialEq for OptManager {
+        fn eq(&self, other: &OptManager) -> bool {
+            self.id == other.id
+        }
+    }
+    impl std::cmp::Eq for OptManager {}
+    impl PartialEq<Weak<OptManager>> for OptManager {
+        fn eq(&self, other: &Weak<OptManager>) -> bool {
+            self.id == other.id()
+        }
+    }
+    impl std::borrow::Borrow<ObjectId> for OptManager {
+        fn borrow(&self) -> &ObjectId {
+            &self.id
+        }
+    }
+    impl std::hash::Hash for OptManager {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state)
+        }
+    }
+    impl super::wayland_server::Resource for OptManager {
+        type Request = Request;
+        type Event<'event> = Event<'event>;
+        #[inline]
+        fn interface() -> &'static Interface {
+            &super::OPT_MANAGER_INTERFACE
+        }
+        #[inline]
+        fn id(&self) -> ObjectId {
+            self.id.clone()
+        }
+        #[inline]
+        fn version(&self) -> u32 {
+            self.version
+        }
+        #[inline]
+        fn data<U: 'static>(&self) -> Option<&U> {
+            self.data
+                .as_ref()
+                .and_then(|arc| (&**arc).downcast_ref::<ResourceData<Self, U>>())
+                .map(|data| &data.udata)
+        }
+        #[inline]
+        fn object_data(&self) -> Option<&Arc<dyn std::any::Any + Send + Sync>> {
+            self.data.as_ref()
+        }
+        fn handle(&self) -> &WeakHandle {
+            &self.handle
+        }
+        #[inline]
+        fn from_id(conn: &DisplayHandle, id: ObjectId) -> Result<Self, InvalidId> {
+            if !same_interface(id.interface(), Self::interface()) && !id.is_null() {
+                return Err(InvalidId);
+            }
+            let version = conn.object_info(id.clone()).map(|info| info.version).unwrap_or(0);
+            let data = conn.get_object_data(id.clone()).ok();
+            Ok(OptManager { id, data, version, handle: conn.backend_handle().downgrade() })
+        }
+        fn send_event(&self, evt: Self::Event<'_>) -> Result<(), InvalidId> {
+            let handle = DisplayHandle::from(self.handle.upgrade().ok_or(InvalidId)?);
+            handle.send_event(self, evt)
+        }
+        fn parse_request(
+            conn: &DisplayHandle,
+            msg: Message<ObjectId, OwnedFd>,
+        ) -> Result<(Self, Self::Request), DispatchError> {
+            let me = Self::from_id(conn, msg.sender_id.clone()).unwrap();
+            let mut arg_iter = msg.args.into_iter();
+            match msg.opcode {
+                0u16 => {
+                    if let (Some(Argument::NewId(id)), Some(Argument::Object(parent))) =
+                        (arg_iter.next(), arg_iter.next())
+                    {
+                        Ok((
+                            me,
+                            Request::GetWidget {
+                                id: New::wrap(
+                                    match <super::opt_widget::OptWidget as Resource>::from_id(
+                                        conn,
+                                        id.clone(),
+                                    ) {
+                                        Ok(p) => p,
+                                        Err(_) => {
+                                            return Err(DispatchError::BadMessage {
+                                                sender_id: msg.sender_id,
+                                                interface: Self::interface().name,
+                                                opcode: msg.opcode,
+                                            })
+                                        }
+                                    },
+                                ),
+                                parent: if parent.is_null() {
+                                    None
+                                } else {
+                                    Some(match <super::opt_widget::OptWidget as Resource>::from_id(
+                                        conn,
+                                        parent.clone(),
+                                    ) {
+                                        Ok(p) => p,
+                                        Err(_) => {
+                                            return Err(DispatchError::BadMessage {
+                                                sender_id: msg.sender_id,
+                                                interface: Self::interface().name,
+                                                opcode: msg.opcode,
+                                            })
+                                        }
+                                    })
+                                },
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                1u16 => {
+                    if let () = () {
+                        Ok((me, Request::Destroy {}))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                _ => Err(DispatchError::BadMessage {
+                    sender_id: msg.sender_id,
+                    interface: Self::interface().name,
+                    opcode: msg.opcode,
+                }),
+            }
+        }
+        fn write_event<'a>(
+            &self,
+            conn: &DisplayHandle,
+            msg: Self::Event<'a>,
+        ) -> Result<Message<ObjectId, std::os::unix::io::BorrowedFd<'a>>, InvalidId> {
+            match msg {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        fn __set_object_data(
+            &mut self,
+            odata: std::sync::Arc<dyn std::any::Any + Send + Sync + 'static>,
+        ) {
+            self.data = Some(odata);
+        }
+    }
+    impl OptManager {}
+}
+#[doc = "a widget\n\nA widget with a state and a title."]
+pub mod opt_widget {
+    use super::wayland_server::{
+        backend::{
+            protocol::{same_interface, Argument, Interface, Message, WEnum},
+            smallvec, InvalidId, ObjectData, ObjectId, WeakHandle,
+        },
+        Dispatch, DispatchError, DisplayHandle, New, Resource, ResourceData, Weak,
+    };
+    use std::os::unix::io::OwnedFd;
+    use std::sync::Arc;
+    #[repr(u32)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[non_exhaustive]
+    pub enum State {
+        #[doc = "the widget is off"]
+        Off = 0,
+        #[doc = "the widget is on"]
+        On = 1,
+    }
+    impl core::convert::TryFrom<u32> for State {
+        type Error = ();
+        #[allow(deprecated)]
+        fn try_from(val: u32) -> Result<State, ()> {
+            match val {
+                0 => Ok(State::Off),
+                1 => Ok(State::On),
+                _ => Err(()),
+            }
+        }
+    }
+    impl core::convert::From<State> for u32 {
+        fn from(val: State) -> u32 {
+            val as u32
+        }
+    }
+    bitflags::bitflags! { # [derive (Clone , Copy , Debug , Eq , Hash , PartialEq)] pub struct Caps : u32 { # [doc = "the widget can be resized"] const Resize = 1 ; # [doc = "the widget can be moved"] const Move = 2 ; } }
+    impl core::convert::TryFrom<u32> for Caps {
+        type Error = ();
+        fn try_from(val: u32) -> Result<Caps, ()> {
+            Caps::from_bits(val).ok_or(())
+        }
+    }
+    impl core::convert::From<Caps> for u32 {
+        fn from(val: Caps) -> u32 {
+            val.bits()
+        }
+    }
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_CONFIGURE_SINCE: u32 = 2u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_CONFIGURE_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_CONFIGURE_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[
+            super::wayland_server::backend::protocol::ArgumentType::Uint,
+            super::wayland_server::backend::protocol::ArgumentType::Str(
+                super::wayland_server::backend::protocol::AllowNull::No,
+            ),
+            super::wayland_server::backend::protocol::ArgumentType::Array,
+            super::wayland_server::backend::protocol::ArgumentType::Fd,
+            super::wayland_server::backend::protocol::ArgumentType::Fixed,
+        ];
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_DESTROY_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_DESTROY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[];
+    #[doc = r" The minimal object version supporting this event"]
+    pub const EVT_STATE_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this event"]
+    pub const EVT_STATE_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_STATE_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::Uint,
+        super::wayland_server::backend::protocol::ArgumentType::Uint,
+        super::wayland_server::backend::protocol::ArgumentType::Str(
+            super::wayland_server::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_server::backend::protocol::ArgumentType::Array,
+    ];
+    #[doc = r" The minimal object version supporting this event"]
+    pub const EVT_CHILD_SINCE: u32 = 1u32;
+    #[doc = r" The wire opcode for this event"]
+    pub const EVT_CHILD_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_CHILD_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[super::wayland_server::backend::protocol::ArgumentType::NewId];
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Request {
+        #[doc = "configure the widget\n\n\n\nOnly available since version 2 of the interface"]
+        Configure { state: WEnum<State>, title: String, data: Vec<u8>, fd: OwnedFd, scale: f64 },
+        #[doc = "destroy the widget\n\n\n\nThis is a destructor, once received this object cannot be used any longer."]
+        Destroy,
+    }
+    impl Request {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Request::Configure { .. } => 0u16,
+                Request::Destroy => 1u16,
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Configure { .. } => "configure",
+                Request::Destroy => "destroy",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Configure { .. } => 2u32,
+                Request::Destroy => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptWidget) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::OPT_WIDGET_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[derive(Debug)]
+    #[non_exhaustive]
+    pub enum Event<'a> {
+        #[doc = "the state of the widget"]
+        State { state: WEnum<State>, caps: Caps, title: Option<String>, data: Vec<u8> },
+        #[doc = "a child of the widget"]
+        Child { id: super::opt_widget::OptWidget },
+        #[doc(hidden)]
+        __phantom_lifetime {
+            phantom: std::marker::PhantomData<&'a ()>,
+            never: std::convert::Infallible,
+        },
+    }
+    impl<'a> Event<'a> {
+        #[doc = "Get the opcode number of this message"]
+        pub fn opcode(&self) -> u16 {
+            match *self {
+                Event::State { .. } => 0u16,
+                Event::Child { .. } => 1u16,
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::State { .. } => "state",
+                Event::Child { .. } => "child",
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::State { .. } => 1u32,
+                Event::Child { .. } => 1u32,
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &OptWidget) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::OPT_WIDGET_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
+    }
+    #[doc = "a widget\n\nA widget with a state and a title.\n\nSee also the [Request] enum for this interface."]
+    #[derive(Debug, Clone)]
+    pub struct OptWidget {
+        id: ObjectId,
+        version: u32,
+        data: Option<Arc<dyn std::any::Any + Send + Sync + 'static>>,
+        handle: WeakHandle,
+    }
+    impl std::cmp::PartialEq for OptWidget {
+        fn eq(&self, other: &OptWidget) -> bool {
+            self.id == other.id
+        }
+    }
+    impl std::cmp::Eq for OptWidget {}
+    impl PartialEq<Weak<OptWidget>> for OptWidget {
+        fn eq(&self, other: &Weak<OptWidget>) -> bool {
+            self.id == other.id()
+        }
+    }
+    impl std::borrow::Borrow<ObjectId> for OptWidget {
+        fn borrow(&self) -> &ObjectId {
+            &self.id
+        }
+    }
+    impl std::hash::Hash for OptWidget {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.id.hash(state)
+        }
+    }
+    impl super::wayland_server::Resource for OptWidget {
+        type Request = Request;
+        type Event<'event> = Event<'event>;
+        #[inline]
+        fn interface() -> &'static Interface {
+            &super::OPT_WIDGET_INTERFACE
+        }
+        #[inline]
+        fn id(&self) -> ObjectId {
+            self.id.clone()
+        }
+        #[inline]
+        fn version(&self) -> u32 {
+            self.version
+        }
+        #[inline]
+        fn data<U: 'static>(&self) -> Option<&U> {
+            self.data
+                .as_ref()
+                .and_then(|arc| (&**arc).downcast_ref::<ResourceData<Self, U>>())
+                .map(|data| &data.udata)
+        }
+        #[inline]
+        fn object_data(&self) -> Option<&Arc<dyn std::any::Any + Send + Sync>> {
+            self.data.as_ref()
+        }
+        fn handle(&self) -> &WeakHandle {
+            &self.handle
+        }
+        #[inline]
+        fn from_id(conn: &DisplayHandle, id: ObjectId) -> Result<Self, InvalidId> {
+            if !same_interface(id.interface(), Self::interface()) && !id.is_null() {
+                return Err(InvalidId);
+            }
+            let version = conn.object_info(id.clone()).map(|info| info.version).unwrap_or(0);
+            let data = conn.get_object_data(id.clone()).ok();
+            Ok(OptWidget { id, data, version, handle: conn.backend_handle().downgrade() })
+        }
+        fn send_event(&self, evt: Self::Event<'_>) -> Result<(), InvalidId> {
+            let handle = DisplayHandle::from(self.handle.upgrade().ok_or(InvalidId)?);
+            handle.send_event(self, evt)
+        }
+        fn parse_request(
+            conn: &DisplayHandle,
+            msg: Message<ObjectId, OwnedFd>,
+        ) -> Result<(Self, Self::Request), DispatchError> {
+            let me = Self::from_id(conn, msg.sender_id.clone()).unwrap();
+            let mut arg_iter = msg.args.into_iter();
+            match msg.opcode {
+                0u16 => {
+                    if let (
+                        Some(Argument::Uint(state)),
+                        Some(Argument::Str(title)),
+                        Some(Argument::Array(data)),
+                        Some(Argument::Fd(fd)),
+                        Some(Argument::Fixed(scale)),
+                    ) = (
+                        arg_iter.next(),
+                        arg_iter.next(),
+                        arg_iter.next(),
+                        arg_iter.next(),
+                        arg_iter.next(),
+                    ) {
+                        Ok((
+                            me,
+                            Request::Configure {
+                                state: From::from(state as u32),
+                                title: String::from_utf8_lossy(title.as_ref().unwrap().as_bytes())
+                                    .into_owned(),
+                                data: *data,
+                                fd,
+                                scale: (scale as f64) / 256.,
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                1u16 => {
+                    if let () = () {
+                        Ok((me, Request::Destroy {}))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
+                _ => Err(DispatchError::BadMessage {
+                    sender_id: msg.sender_id,
+                    interface: Self::interface().name,
+                    opcode: msg.opcode,
+                }),
+            }
+        }
+        fn write_event<'a>(
+            &self,
+            conn: &DisplayHandle,
+            msg: Self::Event<'a>,
+        ) -> Result<Message<ObjectId, std::os::unix::io::BorrowedFd<'a>>, InvalidId> {
+            match msg {
+                Event::State { state, caps, title, data } => Ok(Message {
+                    sender_id: self.id.clone(),
+                    opcode: 0u16,
+                    args: {
+                        let mut vec = smallvec::SmallVec::new();
+                        vec.push(Argument::Uint(state.into()));
+                        vec.push(Argument::Uint(caps.into()));
+                        vec.push(Argument::Str(
+                            title.map(|s| Box::new(std::ffi::CString::new(s).unwrap())),
+                        ));
+                        vec.push(Argument::Array(Box::new(data)));
+                        vec
+                    },
+                }),
+                Event::Child { id } => Ok(Message {
+                    sender_id: self.id.clone(),
+                    opcode: 1u16,
+                    args: {
+                        let mut vec = smallvec::SmallVec::new();
+                        vec.push(Argument::NewId(Resource::id(&id)));
+                        vec
+                    },
+                }),
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        fn __set_object_data(
+            &mut self,
+            odata: std::sync::Arc<dyn std::any::Any + Send + Sync + 'static>,
+        ) {
+            self.data = Some(odata);
+        }
+    }
+    impl OptWidget {
+        #[doc = "the state of the widget"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn state(&self, state: State, caps: Caps, title: Option<String>, data: Vec<u8>) {
+            let _ = self.send_event(Event::State { state: WEnum::Value(state), caps, title, data });
+        }
+        #[doc = "a child of the widget"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn child(&self, id: &super::opt_widget::OptWidget) {
+            let _ = self.send_event(Event::Child { id: id.clone() });
+        }
+    }
+}
+#[doc = "Delegate the [`Dispatch`](wayland_server::Dispatch) implementations of all the interfaces of the `options_protocol` protocol to another type\n\nThe [`GlobalDispatch`](wayland_server::GlobalDispatch) implementations of the interfaces no message creates are delegated as well, these are the globals of the protocol. The same type is used as the user data of all the objects and as the data of all the globals.\n\nIts arguments are the path to the module of the generated code with the user data type, and the delegate type. Its usage is otherwise similar to the [`delegate_dispatch!`](wayland_server::delegate_dispatch) macro:\n\n```ignore\ndelegate_options_protocol!(State: [path::to::protocol: UserData] => Delegate);\n```"]
+#[macro_export]
+macro_rules ! delegate_options_protocol { ($ (@ < $ ($ lt : tt $ (: $ clt : tt $ (+ $ dlt : tt) *) ?) , + >) ? $ dispatch_from : ty : [$ ($ module : ident) :: + : $ udata : ty] => $ dispatch_to : ty) => { impl $ (< $ ($ lt $ (: $ clt $ (+ $ dlt) *) ?) , + >) ? :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata > for $ dispatch_from where $ dispatch_to : :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata , Self > , { fn request (state : & mut Self , client : & :: wayland_server :: Client , resource : & $ ($ module) :: + :: opt_manager :: OptManager , request : < $ ($ module) :: + :: opt_manager :: OptManager as :: wayland_server :: Resource > :: Request , data : & $ udata , dhandle : & :: wayland_server :: DisplayHandle , data_init : & mut :: wayland_server :: DataInit < '_ , Self > ,) { < $ dispatch_to as :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata , Self >> :: request (state , client , resource , request , data , dhandle , data_init) } fn destroyed (state : & mut Self , client : :: wayland_server :: backend :: ClientId , resource : & $ ($ module) :: + :: opt_manager :: OptManager , data : & $ udata) { < $ dispatch_to as :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata , Self >> :: destroyed (state , client , resource , data) } } impl $ (< $ ($ lt $ (: $ clt $ (+ $ dlt) *) ?) , + >) ? :: wayland_server :: GlobalDispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata > for $ dispatch_from where $ dispatch_to : :: wayland_server :: GlobalDispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata , Self > , { fn bind (state : & mut Self , dhandle : & :: wayland_server :: DisplayHandle , client : & :: wayland_server :: Client , resource : :: wayland_server :: New < $ ($ module) :: + :: opt_manager :: OptManager > , global_data : & $ udata , data_init : & mut :: wayland_server :: DataInit < '_ , Self > ,) { < $ dispatch_to as :: wayland_server :: GlobalDispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata , Self >> :: bind (state , dhandle , client , resource , global_data , data_init) } fn can_view (client : :: wayland_server :: Client , global_data : & $ udata) -> bool { < $ dispatch_to as :: wayland_server :: GlobalDispatch < $ ($ module) :: + :: opt_manager :: OptManager , $ udata , Self >> :: can_view (client , global_data) } } impl $ (< $ ($ lt $ (: $ clt $ (+ $ dlt) *) ?) , + >) ? :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_widget :: OptWidget , $ udata > for $ dispatch_from where $ dispatch_to : :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_widget :: OptWidget , $ udata , Self > , { fn request (state : & mut Self , client : & :: wayland_server :: Client , resource : & $ ($ module) :: + :: opt_widget :: OptWidget , request : < $ ($ module) :: + :: opt_widget :: OptWidget as :: wayland_server :: Resource > :: Request , data : & $ udata , dhandle : & :: wayland_server :: DisplayHandle , data_init : & mut :: wayland_server :: DataInit < '_ , Self > ,) { < $ dispatch_to as :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_widget :: OptWidget , $ udata , Self >> :: request (state , client , resource , request , data , dhandle , data_init) } fn destroyed (state : & mut Self , client : :: wayland_server :: backend :: ClientId , resource : & $ ($ module) :: + :: opt_widget :: OptWidget , data : & $ udata) { < $ dispatch_to as :: wayland_server :: Dispatch < $ ($ module) :: + :: opt_widget :: OptWidget , $ udata , Self >> :: destroyed (state , client , resource , data) } } } ; }
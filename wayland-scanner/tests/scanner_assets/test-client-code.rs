@@ -23,8 +23,9 @@ pub mod wl_display {
         #[doc = "implementation error in compositor"]
         Implementation = 3,
     }
-    impl std::convert::TryFrom<u32> for Error {
+    impl core::convert::TryFrom<u32> for Error {
         type Error = ();
+        #[allow(deprecated)]
         fn try_from(val: u32) -> Result<Error, ()> {
             match val {
                 0 => Ok(Error::InvalidObject),
@@ -35,7 +36,7 @@ pub mod wl_display {
             }
         }
     }
-    impl std::convert::From<Error> for u32 {
+    impl core::convert::From<Error> for u32 {
         fn from(val: Error) -> u32 {
             val as u32
         }
@@ -44,18 +45,38 @@ pub mod wl_display {
     pub const REQ_SYNC_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_SYNC_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_SYNC_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::NewId];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_GET_REGISTRY_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_GET_REGISTRY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_REGISTRY_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::NewId];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_ERROR_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_ERROR_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_ERROR_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::Str(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_DELETE_ID_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_DELETE_ID_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_DELETE_ID_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::Uint];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
@@ -78,6 +99,43 @@ pub mod wl_display {
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Sync { .. } => "sync",
+                Request::GetRegistry { .. } => "get_registry",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Sync { .. } => 1u32,
+                Request::GetRegistry { .. } => 1u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlDisplay) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::WL_DISPLAY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::WL_DISPLAY_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -85,7 +143,7 @@ pub mod wl_display {
         #[doc = "fatal error event\n\nThe error event is sent out when a fatal (non-recoverable)\nerror has occurred.  The object_id argument is the object\nwhere the error occurred, most often in response to a request\nto that object.  The code identifies the error and is defined\nby the object interface.  As such, each interface defines its\nown set of error codes.  The message is a brief description\nof the error, for (debugging) convenience."]
         Error {
             #[doc = "object where the error occurred"]
-            object_id: super::wayland_client::ObjectId,
+            object_id: ObjectId,
             #[doc = "error code"]
             code: u32,
             #[doc = "error description"]
@@ -105,6 +163,41 @@ pub mod wl_display {
                 Event::DeleteId { .. } => 1u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::Error { .. } => "error",
+                Event::DeleteId { .. } => "delete_id",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::Error { .. } => 1u32,
+                Event::DeleteId { .. } => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlDisplay) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::WL_DISPLAY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::WL_DISPLAY_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "core global object\n\nThe core global object.  This is a special singleton object.  It\nis used for internal Wayland protocol features.\n\nSee also the [Event] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -327,14 +420,35 @@ pub mod wl_registry {
     pub const REQ_BIND_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_BIND_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_BIND_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::Str(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::NewId,
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_GLOBAL_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_GLOBAL_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_GLOBAL_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::Str(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_GLOBAL_REMOVE_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_GLOBAL_REMOVE_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_GLOBAL_REMOVE_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::Uint];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
@@ -359,6 +473,41 @@ pub mod wl_registry {
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Bind { .. } => "bind",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Bind { .. } => 1u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlRegistry) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::WL_REGISTRY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::WL_REGISTRY_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -386,6 +535,41 @@ pub mod wl_registry {
                 Event::GlobalRemove { .. } => 1u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::Global { .. } => "global",
+                Event::GlobalRemove { .. } => "global_remove",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::Global { .. } => 1u32,
+                Event::GlobalRemove { .. } => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlRegistry) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::WL_REGISTRY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::WL_REGISTRY_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "global registry object\n\nThe singleton global registry object.  The server has a number of\nglobal objects that are available to all clients.  These objects\ntypically represent an actual object in the server (for example,\nan input device) or they are singleton objects that provide\nextension functionality.\n\nWhen a client creates a registry object, the registry object\nwill emit a global event for each global currently in the\nregistry.  Globals come and go as a result of device or\nmonitor hotplugs, reconfiguration or other events, and the\nregistry will send out global and global_remove events to\nkeep the client up to date with the changes.  To mark the end\nof the initial burst of events, the client can use the\nwl_display.sync request immediately after calling\nwl_display.get_registry.\n\nA client can bind to a global object by using the bind\nrequest.  This creates a client-side handle that lets the object\nemit events to the client and lets the client invoke requests on\nthe object.\n\nSee also the [Event] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -581,6 +765,9 @@ pub mod wl_callback {
     pub const EVT_DONE_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_DONE_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_DONE_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::Uint];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
@@ -597,6 +784,39 @@ pub mod wl_callback {
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlCallback) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::WL_CALLBACK_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::WL_CALLBACK_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -614,6 +834,39 @@ pub mod wl_callback {
                 Event::Done { .. } => 0u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::Done { .. } => "done",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::Done { .. } => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlCallback) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::WL_CALLBACK_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::WL_CALLBACK_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "callback object\n\nClients can handle the 'done' event to get notified when\nthe related request is done.\n\nSee also the [Event] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -748,53 +1001,171 @@ pub mod test_global {
     };
     use std::sync::Arc;
     use std::os::unix::io::OwnedFd;
+    #[doc = "test_global errors"]
+    #[repr(u32)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[non_exhaustive]
+    pub enum Error {
+        #[doc = "the flags are not valid"]
+        InvalidFlags = 0,
+        #[deprecated(note = "Deprecated since version 5 of the interface")]
+        AlreadyLinked = 1,
+    }
+    impl core::convert::TryFrom<u32> for Error {
+        type Error = ();
+        #[allow(deprecated)]
+        fn try_from(val: u32) -> Result<Error, ()> {
+            match val {
+                0 => Ok(Error::InvalidFlags),
+                1 => Ok(Error::AlreadyLinked),
+                _ => Err(()),
+            }
+        }
+    }
+    impl core::convert::From<Error> for u32 {
+        fn from(val: Error) -> u32 {
+            val as u32
+        }
+    }
+    bitflags::bitflags! { # [doc = "a bitfield"] # [derive (Clone , Copy , Debug , Eq , Hash , PartialEq)] pub struct Flags : u32 { # [doc = "the first flag"] const First = 1 ; # [doc = "the second flag"] const Second = 2 ; # [doc = "the third flag"] # [doc = "Deprecated since version 5 of the interface"] const Third = 4 ; } }
+    impl core::convert::TryFrom<u32> for Flags {
+        type Error = ();
+        fn try_from(val: u32) -> Result<Flags, ()> {
+            Flags::from_bits(val).ok_or(())
+        }
+    }
+    impl core::convert::From<Flags> for u32 {
+        fn from(val: Flags) -> u32 {
+            val.bits()
+        }
+    }
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_MANY_ARGS_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_MANY_ARGS_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_MANY_ARGS_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[
+            super::wayland_client::backend::protocol::ArgumentType::Uint,
+            super::wayland_client::backend::protocol::ArgumentType::Int,
+            super::wayland_client::backend::protocol::ArgumentType::Fixed,
+            super::wayland_client::backend::protocol::ArgumentType::Array,
+            super::wayland_client::backend::protocol::ArgumentType::Str(
+                super::wayland_client::backend::protocol::AllowNull::No,
+            ),
+            super::wayland_client::backend::protocol::ArgumentType::Fd,
+        ];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_GET_SECONDARY_SINCE: u32 = 2u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_GET_SECONDARY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_SECONDARY_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::NewId];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_GET_TERTIARY_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_GET_TERTIARY_OPCODE: u16 = 2u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_TERTIARY_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::NewId];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_LINK_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_LINK_OPCODE: u16 = 3u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_LINK_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+    ];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_DESTROY_SINCE: u32 = 4u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 4u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_REVERSE_LINK_SINCE: u32 = 5u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_REVERSE_LINK_OPCODE: u16 = 5u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_REVERSE_LINK_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+    ];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_NEWID_AND_ALLOW_NULL_SINCE: u32 = 5u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_NEWID_AND_ALLOW_NULL_OPCODE: u16 = 6u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_NEWID_AND_ALLOW_NULL_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::NewId,
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_MANY_ARGS_EVT_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_MANY_ARGS_EVT_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_MANY_ARGS_EVT_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::Uint,
+        super::wayland_client::backend::protocol::ArgumentType::Int,
+        super::wayland_client::backend::protocol::ArgumentType::Fixed,
+        super::wayland_client::backend::protocol::ArgumentType::Array,
+        super::wayland_client::backend::protocol::ArgumentType::Str(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_client::backend::protocol::ArgumentType::Fd,
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_ACK_SECONDARY_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_ACK_SECONDARY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_ACK_SECONDARY_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::No,
+        )];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_CYCLE_QUAD_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_CYCLE_QUAD_OPCODE: u16 = 2u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_CYCLE_QUAD_SIGNATURE:
+        &[super::wayland_client::backend::protocol::ArgumentType] = &[
+        super::wayland_client::backend::protocol::ArgumentType::NewId,
+        super::wayland_client::backend::protocol::ArgumentType::Object(
+            super::wayland_client::backend::protocol::AllowNull::Yes,
+        ),
+    ];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
         #[doc = "a request with every possible non-object arg"]
         ManyArgs {
             #[doc = "an unsigned int"]
-            unsigned_int: u32,
+            unsigned_int: Flags,
             #[doc = "a singed int"]
             signed_int: i32,
             #[doc = "a fixed point number"]
@@ -808,7 +1179,7 @@ pub mod test_global {
         },
         #[doc = "Only available since version 2 of the interface"]
         GetSecondary {},
-        #[doc = "Only available since version 3 of the interface"]
+        #[doc = "Only available since version 3 of the interface\nDeprecated since version 4 of the interface"]
         GetTertiary {},
         #[doc = "link a secondary and a tertiary\n\n\n\nOnly available since version 3 of the interface"]
         Link { sec: super::secondary::Secondary, ter: Option<super::tertiary::Tertiary>, time: u32 },
@@ -841,6 +1212,53 @@ pub mod test_global {
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::ManyArgs { .. } => "many_args",
+                Request::GetSecondary { .. } => "get_secondary",
+                Request::GetTertiary { .. } => "get_tertiary",
+                Request::Link { .. } => "link",
+                Request::Destroy => "destroy",
+                Request::ReverseLink { .. } => "reverse_link",
+                Request::NewidAndAllowNull { .. } => "newid_and_allow_null",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::ManyArgs { .. } => 1u32,
+                Request::GetSecondary { .. } => 2u32,
+                Request::GetTertiary { .. } => 3u32,
+                Request::Link { .. } => 3u32,
+                Request::Destroy => 4u32,
+                Request::ReverseLink { .. } => 5u32,
+                Request::NewidAndAllowNull { .. } => 5u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &TestGlobal) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::TEST_GLOBAL_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::TEST_GLOBAL_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -848,7 +1266,7 @@ pub mod test_global {
         #[doc = "an event with every possible non-object arg"]
         ManyArgsEvt {
             #[doc = "an unsigned int"]
-            unsigned_int: u32,
+            unsigned_int: Flags,
             #[doc = "a singed int"]
             signed_int: i32,
             #[doc = "a fixed point number"]
@@ -860,7 +1278,7 @@ pub mod test_global {
             #[doc = "a file descriptor"]
             file_descriptor: OwnedFd,
         },
-        #[doc = "acking the creation of a secondary"]
+        #[doc = "acking the creation of a secondary\n\n\n\nDeprecated since version 5 of the interface"]
         AckSecondary { sec: super::secondary::Secondary },
         #[doc = "create a new quad optionally replacing a previous one"]
         CycleQuad { new_quad: super::quad::Quad, old_quad: Option<super::quad::Quad> },
@@ -874,6 +1292,43 @@ pub mod test_global {
                 Event::CycleQuad { .. } => 2u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::ManyArgsEvt { .. } => "many_args_evt",
+                Event::AckSecondary { .. } => "ack_secondary",
+                Event::CycleQuad { .. } => "cycle_quad",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::ManyArgsEvt { .. } => 1u32,
+                Event::AckSecondary { .. } => 1u32,
+                Event::CycleQuad { .. } => 1u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &TestGlobal) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::TEST_GLOBAL_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::TEST_GLOBAL_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "test_global\n\nSee also the [Event] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -984,7 +1439,7 @@ pub mod test_global {
                         Ok((
                             me,
                             Event::ManyArgsEvt {
-                                unsigned_int,
+                                unsigned_int: Flags::from_bits_retain(unsigned_int as u32),
                                 signed_int,
                                 fixed_point: (fixed_point as f64) / 256.,
                                 number_array: *number_array,
@@ -1106,7 +1561,7 @@ pub mod test_global {
                 } => {
                     let child_spec = None;
                     let args = smallvec::SmallVec::from_vec(vec![
-                        Argument::Uint(unsigned_int),
+                        Argument::Uint(unsigned_int.into()),
                         Argument::Int(signed_int),
                         Argument::Fixed((fixed_point * 256.) as i32),
                         Argument::Array(Box::new(number_array)),
@@ -1200,7 +1655,7 @@ pub mod test_global {
         #[allow(clippy::too_many_arguments)]
         pub fn many_args(
             &self,
-            unsigned_int: u32,
+            unsigned_int: Flags,
             signed_int: i32,
             fixed_point: f64,
             number_array: Vec<u8>,
@@ -1240,6 +1695,7 @@ pub mod test_global {
             )
             .unwrap_or_else(|_| Proxy::inert(self.backend.clone()))
         }
+        #[deprecated(note = "Deprecated since version 4 of the interface")]
         #[allow(clippy::too_many_arguments)]
         pub fn get_tertiary<
             U: Send + Sync + 'static,
@@ -1335,6 +1791,9 @@ pub mod secondary {
     pub const REQ_DESTROY_SINCE: u32 = 2u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
@@ -1354,6 +1813,41 @@ pub mod secondary {
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Destroy => "destroy",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Destroy => 2u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Secondary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::SECONDARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::SECONDARY_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -1363,6 +1857,35 @@ pub mod secondary {
         pub fn opcode(&self) -> u16 {
             match *self {}
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {}
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {}
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Secondary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::SECONDARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::SECONDARY_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "secondary\n\nThis interface has no events."]
     #[derive(Debug, Clone)]
@@ -1505,11 +2028,31 @@ pub mod tertiary {
     pub const REQ_DESTROY_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[];
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_SET_FLAGS_SINCE: u32 = 5u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_SET_FLAGS_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_SET_FLAGS_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[
+            super::wayland_client::backend::protocol::ArgumentType::Uint,
+            super::wayland_client::backend::protocol::ArgumentType::Uint,
+            super::wayland_client::backend::protocol::ArgumentType::Uint,
+        ];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
         #[doc = "This is a destructor, once sent this object cannot be used any longer.\nOnly available since version 3 of the interface"]
         Destroy,
+        #[doc = "refer to the enums of other interfaces\n\n\n\nOnly available since version 5 of the interface"]
+        SetFlags {
+            flags: super::test_global::Flags,
+            error: WEnum<super::test_global::Error>,
+            capabilities: super::wl_seat::Capability,
+        },
         #[doc(hidden)]
         __phantom_lifetime {
             phantom: std::marker::PhantomData<&'a ()>,
@@ -1521,9 +2064,47 @@ pub mod tertiary {
         pub fn opcode(&self) -> u16 {
             match *self {
                 Request::Destroy => 0u16,
+                Request::SetFlags { .. } => 1u16,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Destroy => "destroy",
+                Request::SetFlags { .. } => "set_flags",
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Destroy => 3u32,
+                Request::SetFlags { .. } => 5u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Tertiary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::TERTIARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::TERTIARY_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -1533,6 +2114,35 @@ pub mod tertiary {
         pub fn opcode(&self) -> u16 {
             match *self {}
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {}
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {}
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Tertiary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::TERTIARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::TERTIARY_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "tertiary\n\nThis interface has no events."]
     #[derive(Debug, Clone)]
@@ -1645,6 +2255,17 @@ pub mod tertiary {
                     let args = smallvec::SmallVec::new();
                     Ok((Message { sender_id: self.id.clone(), opcode: 0u16, args }, child_spec))
                 }
+                Request::SetFlags { flags, error, capabilities } => {
+                    let child_spec = None;
+                    let args = {
+                        let mut vec = smallvec::SmallVec::new();
+                        vec.push(Argument::Uint(flags.into()));
+                        vec.push(Argument::Uint(error.into()));
+                        vec.push(Argument::Uint(capabilities.into()));
+                        vec
+                    };
+                    Ok((Message { sender_id: self.id.clone(), opcode: 1u16, args }, child_spec))
+                }
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
@@ -1659,6 +2280,25 @@ pub mod tertiary {
             let conn = Connection::from_backend(backend);
             let _ = conn.send_request(self, Request::Destroy {}, None);
         }
+        #[doc = "refer to the enums of other interfaces"]
+        #[allow(clippy::too_many_arguments)]
+        pub fn set_flags(
+            &self,
+            flags: super::test_global::Flags,
+            error: super::test_global::Error,
+            capabilities: super::wl_seat::Capability,
+        ) {
+            let backend = match self.backend.upgrade() {
+                Some(b) => b,
+                None => return,
+            };
+            let conn = Connection::from_backend(backend);
+            let _ = conn.send_request(
+                self,
+                Request::SetFlags { flags, error: WEnum::Value(error), capabilities },
+                None,
+            );
+        }
     }
 }
 pub mod quad {
@@ -1675,6 +2315,9 @@ pub mod quad {
     pub const REQ_DESTROY_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_client::backend::protocol::ArgumentType] =
+        &[];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request<'a> {
@@ -1694,6 +2337,41 @@ pub mod quad {
                 Request::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Destroy => "destroy",
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Destroy => 3u32,
+                Request::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Quad) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_client::backend::protocol::MessageInfo for Request<'a> {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::QUAD_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::QUAD_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -1703,6 +2381,35 @@ pub mod quad {
         pub fn opcode(&self) -> u16 {
             match *self {}
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {}
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {}
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Quad) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_client::backend::protocol::MessageInfo for Event {
+        fn interface(&self) -> &'static super::wayland_client::backend::protocol::Interface {
+            &super::QUAD_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_client::backend::protocol::MessageDesc {
+            &super::QUAD_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "quad\n\nThis interface has no events."]
     #[derive(Debug, Clone)]
@@ -466,28 +466,48 @@ pub static TERTIARY_INTERFACE: wayland_backend::protocol::Interface =
     wayland_backend::protocol::Interface {
         name: "tertiary",
         version: 5u32,
-        requests: &[wayland_backend::protocol::MessageDesc {
-            name: "destroy",
-            signature: &[],
-            since: 3u32,
-            is_destructor: true,
-            child_interface: None,
-            arg_interfaces: &[],
-        }],
+        requests: &[
+            wayland_backend::protocol::MessageDesc {
+                name: "destroy",
+                signature: &[],
+                since: 3u32,
+                is_destructor: true,
+                child_interface: None,
+                arg_interfaces: &[],
+            },
+            wayland_backend::protocol::MessageDesc {
+                name: "set_flags",
+                signature: &[
+                    wayland_backend::protocol::ArgumentType::Uint,
+                    wayland_backend::protocol::ArgumentType::Uint,
+                    wayland_backend::protocol::ArgumentType::Uint,
+                ],
+                since: 5u32,
+                is_destructor: false,
+                child_interface: None,
+                arg_interfaces: &[],
+            },
+        ],
         events: &[],
         c_ptr: Some(unsafe { &tertiary_interface }),
     };
-static tertiary_requests: SyncWrapper<[wayland_backend::protocol::wl_message; 1]> =
-    SyncWrapper([wayland_backend::protocol::wl_message {
+static tertiary_requests: SyncWrapper<[wayland_backend::protocol::wl_message; 2]> = SyncWrapper([
+    wayland_backend::protocol::wl_message {
         name: b"destroy\0" as *const u8 as *const std::os::raw::c_char,
         signature: b"3\0" as *const u8 as *const std::os::raw::c_char,
         types: types_null.0.as_ptr(),
-    }]);
+    },
+    wayland_backend::protocol::wl_message {
+        name: b"set_flags\0" as *const u8 as *const std::os::raw::c_char,
+        signature: b"5uuu\0" as *const u8 as *const std::os::raw::c_char,
+        types: types_null.0.as_ptr(),
+    },
+]);
 pub static tertiary_interface: wayland_backend::protocol::wl_interface =
     wayland_backend::protocol::wl_interface {
         name: b"tertiary\0" as *const u8 as *const std::os::raw::c_char,
         version: 5,
-        request_count: 1,
+        request_count: 2,
         requests: tertiary_requests.0.as_ptr(),
         event_count: 0,
         events: null::<wayland_backend::protocol::wl_message>(),
@@ -522,3 +542,56 @@ pub static quad_interface: wayland_backend::protocol::wl_interface =
         event_count: 0,
         events: null::<wayland_backend::protocol::wl_message>(),
     };
+#[doc = r" All the interfaces of this protocol"]
+pub static INTERFACES: &[&wayland_backend::protocol::Interface] = &[
+    &WL_DISPLAY_INTERFACE,
+    &WL_REGISTRY_INTERFACE,
+    &WL_CALLBACK_INTERFACE,
+    &TEST_GLOBAL_INTERFACE,
+    &SECONDARY_INTERFACE,
+    &TERTIARY_INTERFACE,
+    &QUAD_INTERFACE,
+];
+static TEST_GLOBAL_FLAGS_ENUM: wayland_backend::protocol::EnumDesc =
+    wayland_backend::protocol::EnumDesc {
+        name: "test_global.flags",
+        bitfield: true,
+        entries: &[("first", 1u32), ("second", 2u32), ("third", 4u32)],
+    };
+static TEST_GLOBAL_ERROR_ENUM: wayland_backend::protocol::EnumDesc =
+    wayland_backend::protocol::EnumDesc {
+        name: "test_global.error",
+        bitfield: false,
+        entries: &[("invalid_flags", 0u32), ("already_linked", 1u32)],
+    };
+#[doc = r" The message arguments of this protocol referring to one of its enums"]
+pub static ARG_ENUMS: &[wayland_backend::protocol::ArgEnum] = &[
+    wayland_backend::protocol::ArgEnum {
+        interface: "test_global",
+        direction: wayland_backend::protocol::Direction::Request,
+        message: "many_args",
+        arg: 0usize,
+        desc: &TEST_GLOBAL_FLAGS_ENUM,
+    },
+    wayland_backend::protocol::ArgEnum {
+        interface: "test_global",
+        direction: wayland_backend::protocol::Direction::Event,
+        message: "many_args_evt",
+        arg: 0usize,
+        desc: &TEST_GLOBAL_FLAGS_ENUM,
+    },
+    wayland_backend::protocol::ArgEnum {
+        interface: "tertiary",
+        direction: wayland_backend::protocol::Direction::Request,
+        message: "set_flags",
+        arg: 0usize,
+        desc: &TEST_GLOBAL_FLAGS_ENUM,
+    },
+    wayland_backend::protocol::ArgEnum {
+        interface: "tertiary",
+        direction: wayland_backend::protocol::Direction::Request,
+        message: "set_flags",
+        arg: 1usize,
+        desc: &TEST_GLOBAL_ERROR_ENUM,
+    },
+];
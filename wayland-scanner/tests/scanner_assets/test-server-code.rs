@@ -13,6 +13,9 @@ pub mod wl_callback {
     pub const EVT_DONE_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_DONE_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_DONE_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[super::wayland_server::backend::protocol::ArgumentType::Uint];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request {}
@@ -21,6 +24,35 @@ pub mod wl_callback {
         pub fn opcode(&self) -> u16 {
             match *self {}
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {}
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {}
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlCallback) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::WL_CALLBACK_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::WL_CALLBACK_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -44,6 +76,41 @@ pub mod wl_callback {
                 Event::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::Done { .. } => "done",
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::Done { .. } => 1u32,
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &WlCallback) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::WL_CALLBACK_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::WL_CALLBACK_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "callback object\n\nClients can handle the 'done' event to get notified when\nthe related request is done.\n\nThis interface has no requests."]
     #[derive(Debug, Clone)]
@@ -173,53 +240,171 @@ pub mod test_global {
     };
     use std::sync::Arc;
     use std::os::unix::io::OwnedFd;
+    #[doc = "test_global errors"]
+    #[repr(u32)]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[non_exhaustive]
+    pub enum Error {
+        #[doc = "the flags are not valid"]
+        InvalidFlags = 0,
+        #[deprecated(note = "Deprecated since version 5 of the interface")]
+        AlreadyLinked = 1,
+    }
+    impl core::convert::TryFrom<u32> for Error {
+        type Error = ();
+        #[allow(deprecated)]
+        fn try_from(val: u32) -> Result<Error, ()> {
+            match val {
+                0 => Ok(Error::InvalidFlags),
+                1 => Ok(Error::AlreadyLinked),
+                _ => Err(()),
+            }
+        }
+    }
+    impl core::convert::From<Error> for u32 {
+        fn from(val: Error) -> u32 {
+            val as u32
+        }
+    }
+    bitflags::bitflags! { # [doc = "a bitfield"] # [derive (Clone , Copy , Debug , Eq , Hash , PartialEq)] pub struct Flags : u32 { # [doc = "the first flag"] const First = 1 ; # [doc = "the second flag"] const Second = 2 ; # [doc = "the third flag"] # [doc = "Deprecated since version 5 of the interface"] const Third = 4 ; } }
+    impl core::convert::TryFrom<u32> for Flags {
+        type Error = ();
+        fn try_from(val: u32) -> Result<Flags, ()> {
+            Flags::from_bits(val).ok_or(())
+        }
+    }
+    impl core::convert::From<Flags> for u32 {
+        fn from(val: Flags) -> u32 {
+            val.bits()
+        }
+    }
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_MANY_ARGS_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_MANY_ARGS_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_MANY_ARGS_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[
+            super::wayland_server::backend::protocol::ArgumentType::Uint,
+            super::wayland_server::backend::protocol::ArgumentType::Int,
+            super::wayland_server::backend::protocol::ArgumentType::Fixed,
+            super::wayland_server::backend::protocol::ArgumentType::Array,
+            super::wayland_server::backend::protocol::ArgumentType::Str(
+                super::wayland_server::backend::protocol::AllowNull::No,
+            ),
+            super::wayland_server::backend::protocol::ArgumentType::Fd,
+        ];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_GET_SECONDARY_SINCE: u32 = 2u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_GET_SECONDARY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_SECONDARY_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[super::wayland_server::backend::protocol::ArgumentType::NewId];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_GET_TERTIARY_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_GET_TERTIARY_OPCODE: u16 = 2u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_GET_TERTIARY_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[super::wayland_server::backend::protocol::ArgumentType::NewId];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_LINK_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_LINK_OPCODE: u16 = 3u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_LINK_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_server::backend::protocol::ArgumentType::Uint,
+    ];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_DESTROY_SINCE: u32 = 4u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 4u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_REVERSE_LINK_SINCE: u32 = 5u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_REVERSE_LINK_OPCODE: u16 = 5u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_REVERSE_LINK_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::No,
+        ),
+    ];
     #[doc = r" The minimal object version supporting this request"]
     pub const REQ_NEWID_AND_ALLOW_NULL_SINCE: u32 = 5u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_NEWID_AND_ALLOW_NULL_OPCODE: u16 = 6u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_NEWID_AND_ALLOW_NULL_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::NewId,
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::Yes,
+        ),
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::No,
+        ),
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_MANY_ARGS_EVT_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_MANY_ARGS_EVT_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_MANY_ARGS_EVT_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::Uint,
+        super::wayland_server::backend::protocol::ArgumentType::Int,
+        super::wayland_server::backend::protocol::ArgumentType::Fixed,
+        super::wayland_server::backend::protocol::ArgumentType::Array,
+        super::wayland_server::backend::protocol::ArgumentType::Str(
+            super::wayland_server::backend::protocol::AllowNull::No,
+        ),
+        super::wayland_server::backend::protocol::ArgumentType::Fd,
+    ];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_ACK_SECONDARY_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_ACK_SECONDARY_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_ACK_SECONDARY_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::No,
+        )];
     #[doc = r" The minimal object version supporting this event"]
     pub const EVT_CYCLE_QUAD_SINCE: u32 = 1u32;
     #[doc = r" The wire opcode for this event"]
     pub const EVT_CYCLE_QUAD_OPCODE: u16 = 2u16;
+    #[doc = r" The wire signature of this event"]
+    pub const EVT_CYCLE_QUAD_SIGNATURE:
+        &[super::wayland_server::backend::protocol::ArgumentType] = &[
+        super::wayland_server::backend::protocol::ArgumentType::NewId,
+        super::wayland_server::backend::protocol::ArgumentType::Object(
+            super::wayland_server::backend::protocol::AllowNull::Yes,
+        ),
+    ];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request {
         #[doc = "a request with every possible non-object arg"]
         ManyArgs {
             #[doc = "an unsigned int"]
-            unsigned_int: u32,
+            unsigned_int: Flags,
             #[doc = "a singed int"]
             signed_int: i32,
             #[doc = "a fixed point number"]
@@ -236,7 +421,7 @@ pub mod test_global {
             #[doc = "create a secondary"]
             sec: New<super::secondary::Secondary>,
         },
-        #[doc = "Only available since version 3 of the interface"]
+        #[doc = "Only available since version 3 of the interface\nDeprecated since version 4 of the interface"]
         GetTertiary {
             #[doc = "create a tertiary"]
             ter: New<super::tertiary::Tertiary>,
@@ -267,6 +452,51 @@ pub mod test_global {
                 Request::NewidAndAllowNull { .. } => 6u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::ManyArgs { .. } => "many_args",
+                Request::GetSecondary { .. } => "get_secondary",
+                Request::GetTertiary { .. } => "get_tertiary",
+                Request::Link { .. } => "link",
+                Request::Destroy => "destroy",
+                Request::ReverseLink { .. } => "reverse_link",
+                Request::NewidAndAllowNull { .. } => "newid_and_allow_null",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::ManyArgs { .. } => 1u32,
+                Request::GetSecondary { .. } => 2u32,
+                Request::GetTertiary { .. } => 3u32,
+                Request::Link { .. } => 3u32,
+                Request::Destroy => 4u32,
+                Request::ReverseLink { .. } => 5u32,
+                Request::NewidAndAllowNull { .. } => 5u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &TestGlobal) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::TEST_GLOBAL_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::TEST_GLOBAL_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -274,7 +504,7 @@ pub mod test_global {
         #[doc = "an event with every possible non-object arg"]
         ManyArgsEvt {
             #[doc = "an unsigned int"]
-            unsigned_int: u32,
+            unsigned_int: Flags,
             #[doc = "a singed int"]
             signed_int: i32,
             #[doc = "a fixed point number"]
@@ -286,7 +516,7 @@ pub mod test_global {
             #[doc = "a file descriptor"]
             file_descriptor: std::os::unix::io::BorrowedFd<'a>,
         },
-        #[doc = "acking the creation of a secondary"]
+        #[doc = "acking the creation of a secondary\n\n\n\nDeprecated since version 5 of the interface"]
         AckSecondary { sec: super::secondary::Secondary },
         #[doc = "create a new quad optionally replacing a previous one"]
         CycleQuad { new_quad: super::quad::Quad, old_quad: Option<super::quad::Quad> },
@@ -306,6 +536,45 @@ pub mod test_global {
                 Event::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::ManyArgsEvt { .. } => "many_args_evt",
+                Event::AckSecondary { .. } => "ack_secondary",
+                Event::CycleQuad { .. } => "cycle_quad",
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::ManyArgsEvt { .. } => 1u32,
+                Event::AckSecondary { .. } => 1u32,
+                Event::CycleQuad { .. } => 1u32,
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &TestGlobal) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::TEST_GLOBAL_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::TEST_GLOBAL_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "test_global\n\nSee also the [Request] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -404,7 +673,7 @@ pub mod test_global {
                         Ok((
                             me,
                             Request::ManyArgs {
-                                unsigned_int,
+                                unsigned_int: Flags::from_bits_retain(unsigned_int as u32),
                                 signed_int,
                                 fixed_point: (fixed_point as f64) / 256.,
                                 number_array: *number_array,
@@ -687,7 +956,7 @@ pub mod test_global {
                     sender_id: self.id.clone(),
                     opcode: 0u16,
                     args: smallvec::SmallVec::from_vec(vec![
-                        Argument::Uint(unsigned_int),
+                        Argument::Uint(unsigned_int.into()),
                         Argument::Int(signed_int),
                         Argument::Fixed((fixed_point * 256.) as i32),
                         Argument::Array(Box::new(number_array)),
@@ -733,7 +1002,7 @@ pub mod test_global {
         #[allow(clippy::too_many_arguments)]
         pub fn many_args_evt(
             &self,
-            unsigned_int: u32,
+            unsigned_int: Flags,
             signed_int: i32,
             fixed_point: f64,
             number_array: Vec<u8>,
@@ -750,6 +1019,7 @@ pub mod test_global {
             });
         }
         #[doc = "acking the creation of a secondary"]
+        #[deprecated(note = "Deprecated since version 5 of the interface")]
         #[allow(clippy::too_many_arguments)]
         pub fn ack_secondary(&self, sec: &super::secondary::Secondary) {
             let _ = self.send_event(Event::AckSecondary { sec: sec.clone() });
@@ -766,6 +1036,14 @@ pub mod test_global {
                 old_quad: old_quad.cloned(),
             });
         }
+        #[doc = "Post the `invalid_flags` protocol error on this object\n\nthe flags are not valid"]
+        pub fn post_invalid_flags_error(&self, error: impl Into<String>) {
+            Resource::post_error(self, Error::InvalidFlags, error)
+        }
+        #[doc = "Post the `already_linked` protocol error on this object"]
+        pub fn post_already_linked_error(&self, error: impl Into<String>) {
+            Resource::post_error(self, Error::AlreadyLinked, error)
+        }
     }
 }
 pub mod secondary {
@@ -782,6 +1060,9 @@ pub mod secondary {
     pub const REQ_DESTROY_SINCE: u32 = 2u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request {
@@ -795,6 +1076,39 @@ pub mod secondary {
                 Request::Destroy => 0u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Destroy => "destroy",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Destroy => 2u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Secondary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::SECONDARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::SECONDARY_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -812,6 +1126,39 @@ pub mod secondary {
                 Event::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Secondary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::SECONDARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::SECONDARY_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "secondary\n\nSee also the [Request] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -941,19 +1288,75 @@ pub mod tertiary {
     pub const REQ_DESTROY_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[];
+    #[doc = r" The minimal object version supporting this request"]
+    pub const REQ_SET_FLAGS_SINCE: u32 = 5u32;
+    #[doc = r" The wire opcode for this request"]
+    pub const REQ_SET_FLAGS_OPCODE: u16 = 1u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_SET_FLAGS_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[
+            super::wayland_server::backend::protocol::ArgumentType::Uint,
+            super::wayland_server::backend::protocol::ArgumentType::Uint,
+            super::wayland_server::backend::protocol::ArgumentType::Uint,
+        ];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request {
         #[doc = "This is a destructor, once received this object cannot be used any longer.\nOnly available since version 3 of the interface"]
         Destroy,
+        #[doc = "refer to the enums of other interfaces\n\n\n\nOnly available since version 5 of the interface"]
+        SetFlags {
+            flags: super::test_global::Flags,
+            error: WEnum<super::test_global::Error>,
+            capabilities: super::wl_seat::Capability,
+        },
     }
     impl Request {
         #[doc = "Get the opcode number of this message"]
         pub fn opcode(&self) -> u16 {
             match *self {
                 Request::Destroy => 0u16,
+                Request::SetFlags { .. } => 1u16,
+            }
+        }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Destroy => "destroy",
+                Request::SetFlags { .. } => "set_flags",
             }
         }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Destroy => 3u32,
+                Request::SetFlags { .. } => 5u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Tertiary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::TERTIARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::TERTIARY_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -971,6 +1374,39 @@ pub mod tertiary {
                 Event::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Tertiary) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::TERTIARY_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::TERTIARY_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "tertiary\n\nSee also the [Request] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -1061,6 +1497,31 @@ pub mod tertiary {
                         })
                     }
                 }
+                1u16 => {
+                    if let (
+                        Some(Argument::Uint(flags)),
+                        Some(Argument::Uint(error)),
+                        Some(Argument::Uint(capabilities)),
+                    ) = (arg_iter.next(), arg_iter.next(), arg_iter.next())
+                    {
+                        Ok((
+                            me,
+                            Request::SetFlags {
+                                flags: super::test_global::Flags::from_bits_retain(flags as u32),
+                                error: From::from(error as u32),
+                                capabilities: super::wl_seat::Capability::from_bits_retain(
+                                    capabilities as u32,
+                                ),
+                            },
+                        ))
+                    } else {
+                        Err(DispatchError::BadMessage {
+                            sender_id: msg.sender_id,
+                            interface: Self::interface().name,
+                            opcode: msg.opcode,
+                        })
+                    }
+                }
                 _ => Err(DispatchError::BadMessage {
                     sender_id: msg.sender_id,
                     interface: Self::interface().name,
@@ -1100,6 +1561,9 @@ pub mod quad {
     pub const REQ_DESTROY_SINCE: u32 = 3u32;
     #[doc = r" The wire opcode for this request"]
     pub const REQ_DESTROY_OPCODE: u16 = 0u16;
+    #[doc = r" The wire signature of this request"]
+    pub const REQ_DESTROY_SIGNATURE: &[super::wayland_server::backend::protocol::ArgumentType] =
+        &[];
     #[derive(Debug)]
     #[non_exhaustive]
     pub enum Request {
@@ -1113,6 +1577,39 @@ pub mod quad {
                 Request::Destroy => 0u16,
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Request::Destroy => "destroy",
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Request::Destroy => 3u32,
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Quad) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl super::wayland_server::backend::protocol::MessageInfo for Request {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::QUAD_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::QUAD_INTERFACE.requests[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[derive(Debug)]
     #[non_exhaustive]
@@ -1130,6 +1627,39 @@ pub mod quad {
                 Event::__phantom_lifetime { never, .. } => match never {},
             }
         }
+        #[doc = "Get the name of this message, as in the protocol description"]
+        pub fn name(&self) -> &'static str {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Get the minimal object version supporting this message"]
+        pub fn since(&self) -> u32 {
+            match *self {
+                Event::__phantom_lifetime { never, .. } => match never {},
+            }
+        }
+        #[doc = "Check whether the version of this object supports this message"]
+        pub fn is_supported(&self, object: &Quad) -> bool {
+            object.version() >= self.since()
+        }
+    }
+    impl<'a> super::wayland_server::backend::protocol::MessageInfo for Event<'a> {
+        fn interface(&self) -> &'static super::wayland_server::backend::protocol::Interface {
+            &super::QUAD_INTERFACE
+        }
+        fn desc(&self) -> &'static super::wayland_server::backend::protocol::MessageDesc {
+            &super::QUAD_INTERFACE.events[Self::opcode(self) as usize]
+        }
+        fn opcode(&self) -> u16 {
+            Self::opcode(self)
+        }
+        fn name(&self) -> &'static str {
+            Self::name(self)
+        }
+        fn since(&self) -> u32 {
+            Self::since(self)
+        }
     }
     #[doc = "quad\n\nSee also the [Request] enum for this interface."]
     #[derive(Debug, Clone)]
@@ -0,0 +1,35 @@
+//! Generation of arbitrary events, for the code generated by `wayland-scanner` with its `arbitrary`
+//! option
+
+use std::os::unix::io::BorrowedFd;
+
+use wayland_backend::{
+    __arbitrary::arbitrary::{Result, Unstructured},
+    server::ObjectId,
+};
+
+use crate::Resource;
+
+pub use wayland_backend::__arbitrary::*;
+
+/// The objects and file descriptors given to the arbitrary events
+///
+/// The `Event::arbitrary()` functions generated by the `arbitrary` option of `wayland-scanner` take
+/// the arguments of the events which refer to objects and file descriptors from it, so that a fuzzer
+/// only sends events about the objects of the client.
+pub trait ArbitraryObjects<'a> {
+    /// Pick an existing object of the interface `I`
+    ///
+    /// An error like [`Error::IncorrectFormat`](arbitrary::Error::IncorrectFormat) should be returned if there is none.
+    fn object<I: Resource>(&mut self, u: &mut Unstructured<'_>) -> Result<I>;
+
+    /// Pick an existing object of any interface, for the arguments whose interface is not specified
+    fn object_id(&mut self, u: &mut Unstructured<'_>) -> Result<ObjectId>;
+
+    /// Create an object of the interface `I` for the client, to be created by an event like
+    /// `wl_data_device.data_offer`
+    fn new_object<I: Resource>(&mut self, u: &mut Unstructured<'_>) -> Result<I>;
+
+    /// Pick a file descriptor to send
+    fn fd(&mut self, u: &mut Unstructured<'_>) -> Result<BorrowedFd<'a>>;
+}
@@ -0,0 +1,25 @@
+//! Serialization of the messages, for the code generated by `wayland-scanner` with its `serde` option
+
+use wayland_backend::__serde::serde::{Serialize, Serializer};
+
+use crate::{New, Resource};
+
+pub use wayland_backend::__serde::*;
+
+pub fn object<I: Resource, S: Serializer>(object: &I, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(object.id().protocol_id())
+}
+
+pub fn optional_object<I: Resource, S: Serializer>(
+    object: &Option<I>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    object.as_ref().map(|object| object.id().protocol_id()).serialize(serializer)
+}
+
+pub fn new_object<I: Resource, S: Serializer>(
+    object: &New<I>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(object.id.id().protocol_id())
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use wayland_backend::{
     protocol::ProtocolError,
-    server::{ClientData, ClientId, DisconnectReason, InvalidId, ObjectData},
+    server::{ClientData, ClientId, DisconnectReason, InvalidId, ObjectData, WeakHandle},
 };
 
 use crate::{dispatch::ResourceData, Dispatch, DisplayHandle, Resource};
@@ -12,12 +12,13 @@ use crate::{dispatch::ResourceData, Dispatch, DisplayHandle, Resource};
 pub struct Client {
     pub(crate) id: ClientId,
     pub(crate) data: Arc<dyn ClientData>,
+    pub(crate) handle: WeakHandle,
 }
 
 impl Client {
     pub(crate) fn from_id(handle: &DisplayHandle, id: ClientId) -> Result<Self, InvalidId> {
         let data = handle.handle.get_client_data(id.clone())?;
-        Ok(Self { id, data })
+        Ok(Self { id, data, handle: handle.handle.downgrade() })
     }
 
     /// The backend [`ClientId`] of this client
@@ -32,6 +33,30 @@ impl Client {
         (*self.data).downcast_ref()
     }
 
+    /// Checks whether this client is still connected
+    ///
+    /// Helpers holding resources of a client can use this to stop sending events to it once it is gone.
+    /// This returns `false` as soon as the client is disconnected, even though its resources may still
+    /// be alive until the [`Display`](crate::Display) cleans them up.
+    pub fn is_alive(&self) -> bool {
+        self.handle.upgrade().map(|handle| handle.is_client_alive(self.id.clone())).unwrap_or(false)
+    }
+
+    /// Register a callback to be invoked once this client is disconnected
+    ///
+    /// The callback is invoked at the same time as [`ClientData::disconnected()`], while the state of the
+    /// backend is being updated, so it should only record the disconnection (for example by setting a flag
+    /// or sending on a channel) and must not use the [`DisplayHandle`].
+    ///
+    /// Fails if the client is already disconnected.
+    pub fn on_disconnect<F>(&self, callback: F) -> Result<(), InvalidId>
+    where
+        F: FnOnce(ClientId) + Send + 'static,
+    {
+        let handle = self.handle.upgrade().ok_or(InvalidId)?;
+        handle.add_client_disconnect_callback(self.id.clone(), Box::new(callback))
+    }
+
     /// Access the pid/uid/gid of this client
     ///
     /// **Note:** You should be careful if you plan tu use this for security purposes, as it is possible for
@@ -46,6 +71,37 @@ impl Client {
         handle.handle.get_client_credentials(self.id.clone())
     }
 
+    /// Retrieve the approximate resources used by this client in the backend
+    ///
+    /// This includes the number of objects it owns and the messages and file descriptors buffered for it,
+    /// and can be used to enforce limits on clients or to track down leaks. See
+    /// [`Handle::get_client_resource_usage()`](crate::backend::Handle::get_client_resource_usage) for the
+    /// limitations of the system backend.
+    pub fn resource_usage(
+        &self,
+        handle: &DisplayHandle,
+    ) -> Result<crate::backend::ResourceUsage, InvalidId> {
+        handle.handle.get_client_resource_usage(self.id.clone())
+    }
+
+    /// Set a human-readable label for this client
+    ///
+    /// The label is meant to make logs easier to read (for example `"firefox (pid 1234)"`), it is included in
+    /// the `WAYLAND_DEBUG` output of the rust backend and in the error messages of this crate concerning
+    /// this client.
+    pub fn set_label(
+        &self,
+        handle: &DisplayHandle,
+        label: impl Into<String>,
+    ) -> Result<(), InvalidId> {
+        handle.handle.set_client_label(self.id.clone(), label.into())
+    }
+
+    /// The label of this client, if one was set using [`set_label()`](Client::set_label)
+    pub fn label(&self, handle: &DisplayHandle) -> Option<String> {
+        handle.handle.get_client_label(self.id.clone()).ok().flatten()
+    }
+
     /// Create a new Wayland object in the protocol state of this client
     ///
     /// The newly created resource should be immediately sent to the client through an associated event with
@@ -65,7 +121,7 @@ impl Client {
             self.id.clone(),
             I::interface(),
             version,
-            Arc::new(ResourceData::<I, U>::new(user_data)) as Arc<_>,
+            Arc::new(ResourceData::<I, U>::new::<D>(user_data)) as Arc<_>,
         )?;
         I::from_id(handle, id)
     }
@@ -1,12 +1,16 @@
+use std::any::{Any, TypeId};
 use std::os::unix::io::OwnedFd;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
 
 use wayland_backend::{
     protocol::ProtocolError,
-    server::{ClientId, DisconnectReason, ObjectData, ObjectId},
+    server::{ClientId, DisconnectReason, InvalidId, ObjectData, ObjectId},
 };
 
-use crate::{Client, DisplayHandle, Resource};
+use crate::{protocol::wl_callback::WlCallback, Client, DisplayHandle, Resource};
 
 /// A trait which provides an implementation for handling a client's requests from a resource with some type
 /// of associated user data.
@@ -126,13 +130,37 @@ pub struct ResourceData<I, U> {
     marker: std::marker::PhantomData<fn(I)>,
     /// The user-data associated with this object
     pub udata: U,
+    // The type of the state of the `Display` this object belongs to
+    state: TypeId,
+    // Whether `delegate` was ever set, so that dispatching does not lock it otherwise
+    retargeted: AtomicBool,
+    // A `Delegate<I, U, D>` if dispatching of this object has been retargeted
+    delegate: Mutex<Option<Box<dyn Any + Send + Sync>>>,
+}
+
+type DelegatedRequest<I, U, D> =
+    fn(&mut D, &Client, &I, <I as Resource>::Request, &U, &DisplayHandle, &mut DataInit<'_, D>);
+type DelegatedDestroyed<I, U, D> = fn(&mut D, ClientId, &I, &U);
+
+// The dispatching functions of the type an object was retargeted to
+struct Delegate<I: Resource, U, D: 'static> {
+    request: DelegatedRequest<I, U, D>,
+    destroyed: DelegatedDestroyed<I, U, D>,
 }
 
+impl<I: Resource, U, D> Clone for Delegate<I, U, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I: Resource, U, D> Copy for Delegate<I, U, D> {}
+
 /// A newly created object that needs to be initialized. See [`DataInit`].
 #[derive(Debug)]
 #[must_use = "The protocol object must be initialized using DataInit"]
 pub struct New<I> {
-    id: I,
+    pub(crate) id: I,
 }
 
 impl<I> New<I> {
@@ -166,7 +194,7 @@ impl<'a, D> DataInit<'a, D> {
     where
         D: Dispatch<I, U> + 'static,
     {
-        let arc = Arc::new(ResourceData::<I, _>::new(data));
+        let arc = Arc::new(ResourceData::<I, _>::new::<D>(data));
         *self.store = Some(arc.clone() as Arc<_>);
         let mut obj = resource.id;
         obj.__set_object_data(arc);
@@ -190,6 +218,24 @@ impl<'a, D> DataInit<'a, D> {
         obj
     }
 
+    /// Initialize a `wl_callback` object
+    ///
+    /// `wl_callback` has no requests, so this does not require implementing [`Dispatch`] for it. The
+    /// returned callback can be stored and fired later using
+    /// [`WlCallback::done()`](crate::protocol::wl_callback::WlCallback::done), which also destroys it.
+    pub fn init_callback(&mut self, callback: New<WlCallback>) -> WlCallback {
+        self.custom_init(callback, Arc::new(CallbackData))
+    }
+
+    /// Initialize a `wl_callback` object and immediately fire it
+    ///
+    /// This is a shorthand for [`init_callback()`](DataInit::init_callback) followed by
+    /// [`WlCallback::done()`](crate::protocol::wl_callback::WlCallback::done), as is typically needed to
+    /// answer sync-style requests.
+    pub fn fire_callback(&mut self, callback: New<WlCallback>, callback_data: u32) {
+        self.init_callback(callback).done(callback_data);
+    }
+
     /// Post an error on an uninitialized object.
     ///
     /// This is only meant to be used in [`GlobalDispatch`](crate::GlobalDispatch) where a global protocol
@@ -207,13 +253,77 @@ impl<'a, D> DataInit<'a, D> {
     }
 }
 
+// The object data of `wl_callback` objects initialized by `DataInit::init_callback()`
+struct CallbackData;
+
+impl<D> ObjectData<D> for CallbackData {
+    fn request(
+        self: Arc<Self>,
+        _: &wayland_backend::server::Handle,
+        _: &mut D,
+        _: ClientId,
+        _: wayland_backend::protocol::Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData<D>>> {
+        // wl_callback has no requests
+        None
+    }
+
+    fn destroyed(
+        self: Arc<Self>,
+        _: &wayland_backend::server::Handle,
+        _: &mut D,
+        _: ClientId,
+        _: ObjectId,
+    ) {
+    }
+}
+
 /*
  * Dispatch delegation helpers.
  */
 
 impl<I, U> ResourceData<I, U> {
-    pub(crate) fn new(udata: U) -> Self {
-        ResourceData { marker: std::marker::PhantomData, udata }
+    pub(crate) fn new<D: 'static>(udata: U) -> Self {
+        ResourceData {
+            marker: std::marker::PhantomData,
+            udata,
+            state: TypeId::of::<D>(),
+            retargeted: AtomicBool::new(false),
+            delegate: Mutex::new(None),
+        }
+    }
+}
+
+impl<I: Resource + 'static, U: 'static> ResourceData<I, U> {
+    /// Route the requests of the associated object to the [`Dispatch`] implementation of `DelegateTo`
+    ///
+    /// Future requests and the destruction of the object will be processed by
+    /// `<DelegateTo as Dispatch<I, U, D>>` instead of the implementation the object was initialized with. See
+    /// [`DisplayHandle::retarget_resource()`] for a more convenient way to use this.
+    ///
+    /// Returns an error and leaves the object unchanged if `D` is not the state type of the
+    /// [`Display`](crate::Display) the object belongs to.
+    pub fn retarget<D: 'static, DelegateTo: Dispatch<I, U, D> + 'static>(
+        &self,
+    ) -> Result<(), InvalidId> {
+        if self.state != TypeId::of::<D>() {
+            return Err(InvalidId);
+        }
+        let delegate = Delegate::<I, U, D> {
+            request: <DelegateTo as Dispatch<I, U, D>>::request,
+            destroyed: <DelegateTo as Dispatch<I, U, D>>::destroyed,
+        };
+        *self.delegate.lock().unwrap() = Some(Box::new(delegate));
+        self.retargeted.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn delegate<D: 'static>(&self) -> Option<Delegate<I, U, D>> {
+        if !self.retargeted.load(Ordering::Acquire) {
+            return None;
+        }
+        // `retarget()` only stores delegates for the state type of the object
+        self.delegate.lock().unwrap().as_ref().and_then(|d| d.downcast_ref().copied())
     }
 }
 
@@ -241,7 +351,16 @@ impl<I: Resource + 'static, U: Send + Sync + 'static, D: Dispatch<I, U> + 'stati
         let (resource, request) = match I::parse_request(&dhandle, msg) {
             Ok(v) => v,
             Err(e) => {
-                crate::log_warn!("Dispatching error encountered: {:?}, killing client.", e);
+                match client.label(&dhandle) {
+                    Some(label) => crate::log_warn!(
+                        "Dispatching error encountered: {:?}, killing client '{}'.",
+                        e,
+                        label
+                    ),
+                    None => {
+                        crate::log_warn!("Dispatching error encountered: {:?}, killing client.", e)
+                    }
+                }
                 handle.kill_client(
                     client.id(),
                     DisconnectReason::ProtocolError(ProtocolError {
@@ -260,17 +379,29 @@ impl<I: Resource + 'static, U: Send + Sync + 'static, D: Dispatch<I, U> + 'stati
         let udata = resource.data::<U>().expect("Wrong user_data value for object");
 
         let mut new_data = None;
+        // The error is None since the creating object posts an error.
+        let mut data_init = DataInit { store: &mut new_data, error: &mut None };
 
-        <D as Dispatch<I, U>>::request(
-            data,
-            &client,
-            &resource,
-            request,
-            udata,
-            &dhandle,
-            // The error is None since the creating object posts an error.
-            &mut DataInit { store: &mut new_data, error: &mut None },
-        );
+        match self.delegate::<D>() {
+            Some(delegate) => (delegate.request)(
+                data,
+                &client,
+                &resource,
+                request,
+                udata,
+                &dhandle,
+                &mut data_init,
+            ),
+            None => <D as Dispatch<I, U>>::request(
+                data,
+                &client,
+                &resource,
+                request,
+                udata,
+                &dhandle,
+                &mut data_init,
+            ),
+        }
 
         new_data
     }
@@ -289,7 +420,14 @@ impl<I: Resource + 'static, U: Send + Sync + 'static, D: Dispatch<I, U> + 'stati
         // therefore manually initialize the data associated with protocol object wrapper.
         resource.__set_object_data(self.clone());
 
-        <D as Dispatch<I, U>>::destroyed(data, client_id, &resource, &self.udata)
+        match self.delegate::<D>() {
+            Some(delegate) => (delegate.destroyed)(data, client_id, &resource, &self.udata),
+            None => <D as Dispatch<I, U>>::destroyed(data, client_id, &resource, &self.udata),
+        }
+    }
+
+    fn data_as_any(&self) -> &dyn std::any::Any {
+        &self.udata
     }
 }
 
@@ -1,19 +1,33 @@
 use std::{
-    os::unix::io::{AsFd, AsRawFd, BorrowedFd},
+    os::unix::io::{AsFd, BorrowedFd},
     os::unix::net::UnixStream,
-    sync::Arc,
+    sync::{Arc, Once},
+    time::Duration,
 };
 
 use wayland_backend::{
-    protocol::ObjectInfo,
-    server::{Backend, ClientData, GlobalId, Handle, InitError, InvalidId, ObjectId},
+    protocol::{Argument, EnumDesc, Interface, ObjectInfo},
+    server::{
+        Backend, BackendKind, ClientData, ClientId, GlobalId, Handle, InitError, Interceptor,
+        InvalidId, ObjectId,
+    },
 };
 
 use crate::{
-    global::{GlobalData, GlobalDispatch},
-    Client, Resource,
+    global::{GlobalData, GlobalDispatch, GlobalPolicy},
+    Client, Dispatch, Resource, ResourceData,
 };
 
+// make the interfaces of the core protocol known to `Interface::find()`, and the enums of its
+// arguments to the debug output
+fn register_core_protocol() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        Interface::register(crate::protocol::__interfaces::INTERFACES);
+        EnumDesc::register(crate::protocol::__interfaces::ARG_ENUMS);
+    });
+}
+
 /// The Wayland display
 ///
 /// This struct is the core of your Wayland compositor. You'll use it in your event loop to drive the
@@ -41,9 +55,25 @@ impl<State: 'static> Display<State> {
     /// Can only fail if both the `server_system` and `dlopen` features of `wayland-backend` were enabled,
     /// and the `libwayland-server.so` library could not be found.
     pub fn new() -> Result<Display<State>, InitError> {
+        register_core_protocol();
         Ok(Display { backend: Backend::new()? })
     }
 
+    /// Create a new Wayland display, checking that its backend is of the expected kind
+    ///
+    /// This does not select the backend, which is fixed at compile time by the `server_system` feature of
+    /// `wayland-backend`: it fails with an [`std::io::ErrorKind::Unsupported`] error when `kind` is not
+    /// the one this program was built with, and otherwise behaves like [`Display::new()`].
+    pub fn new_checked(kind: BackendKind) -> Result<Display<State>, InitError> {
+        register_core_protocol();
+        Ok(Display { backend: Backend::new_checked(kind)? })
+    }
+
+    /// The backend implementation used by this display
+    pub fn backend_kind(&self) -> BackendKind {
+        self.backend.kind()
+    }
+
     /// Retrieve a [`DisplayHandle`] for this [`Display`].
     ///
     /// This is the type with which all of your interactions with the Wayland protocol are done.
@@ -59,11 +89,35 @@ impl<State: 'static> Display<State> {
         self.backend.dispatch_all_clients(state)
     }
 
+    /// Dispatch at most `max_requests` requests received from clients to their respective callbacks.
+    ///
+    /// This allows interleaving other work, like rendering, with the processing of the requests. It returns
+    /// the number of dispatched requests, and whether some requests may remain pending, in which case it
+    /// should be invoked again without waiting for the poll fd of the display to be readable.
+    ///
+    /// See [`Backend::dispatch_all_clients_limited()`](wayland_backend::server::Backend::dispatch_all_clients_limited).
+    pub fn dispatch_clients_limited(
+        &mut self,
+        state: &mut State,
+        max_requests: usize,
+    ) -> std::io::Result<(usize, bool)> {
+        self.backend.dispatch_all_clients_limited(state, max_requests)
+    }
+
     /// Flush outgoing buffers into their respective sockets.
     pub fn flush_clients(&mut self) -> std::io::Result<()> {
         self.backend.flush(None)
     }
 
+    /// Disconnect clients that have not sent any request within `timeout` after connecting
+    ///
+    /// This guards against stalled or hostile connections exhausting the file descriptors of your
+    /// compositor. The timeout is enforced during [`dispatch_clients()`](Display::dispatch_clients). See
+    /// [`Backend::set_handshake_timeout()`] for details.
+    pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.backend.set_handshake_timeout(timeout)
+    }
+
     /// Access the underlying [`Backend`] of this [`Display`]
     pub fn backend(&mut self) -> &mut Backend<State> {
         &mut self.backend
@@ -108,7 +162,7 @@ impl DisplayHandle {
         data: Arc<dyn ClientData>,
     ) -> std::io::Result<Client> {
         let id = self.handle.insert_client(stream, data.clone())?;
-        Ok(Client { id, data })
+        Ok(Client { id, data, handle: self.handle.downgrade() })
     }
 
     /// Retrieve the [`Client`] which owns the object represented by the given ID
@@ -134,7 +188,11 @@ impl DisplayHandle {
         self.handle.create_global::<State>(
             I::interface(),
             version,
-            Arc::new(GlobalData { data, _types: std::marker::PhantomData }),
+            Arc::new(GlobalData {
+                data,
+                handle: self.handle.downgrade(),
+                _types: std::marker::PhantomData,
+            }),
         )
     }
 
@@ -161,6 +219,23 @@ impl DisplayHandle {
         self.handle.remove_global::<State>(id)
     }
 
+    /// Restrict this global to the clients whose credentials match the given policy
+    ///
+    /// This is a convenience for gating privileged protocols (like virtual keyboards or screen capture)
+    /// without implementing [`GlobalDispatch::can_view()`]. Clients rejected by the policy will not see the
+    /// global and will get a protocol error if they try to bind it. The policy is checked in addition to
+    /// [`GlobalDispatch::can_view()`].
+    ///
+    /// Clients that were already told about the global are not notified, so the policy should be set right
+    /// after creating the global. Returns an error if the global has been removed.
+    pub fn restrict_global<State: 'static>(
+        &self,
+        id: GlobalId,
+        policy: GlobalPolicy,
+    ) -> Result<(), InvalidId> {
+        self.handle.restrict_global::<State>(id, Some(policy.into_filter()))
+    }
+
     /// Access the protocol information for a Wayland object
     ///
     /// Returns an error if the object is no longer valid.
@@ -178,10 +253,31 @@ impl DisplayHandle {
         event: I::Event<'_>,
     ) -> Result<(), InvalidId> {
         let msg = resource.write_event(self, event)?;
-        let msg = msg.map_fd(|fd| fd.as_raw_fd());
         self.handle.send_event(msg)
     }
 
+    /// Queue an event to be sent to given Wayland object
+    ///
+    /// The event is queued without touching the protocol state and will be sent by the next flush of the
+    /// client buffers (such as [`Display::flush_clients()`]), which makes this method safe to use from
+    /// threads other than the one dispatching the [`Display`]. Your event loop needs to be woken up for
+    /// the event to actually be sent.
+    ///
+    /// The file descriptors of the event are duplicated, so the originals can be closed right away.
+    pub fn queue_event<I: Resource>(
+        &self,
+        resource: &I,
+        event: I::Event<'_>,
+    ) -> Result<(), InvalidId> {
+        let msg = resource.write_event(self, event)?;
+        let msg = msg.map_fd(|fd| fd.try_clone_to_owned());
+        if msg.args.iter().any(|arg| matches!(arg, Argument::Fd(Err(_)))) {
+            crate::log_error!("Failed to duplicate a file descriptor, dropping queued event.");
+            return Ok(());
+        }
+        self.handle.queue_event(msg.map_fd(Result::unwrap))
+    }
+
     /// Trigger a protocol error on this object
     ///
     /// This is intended to be a low-level method. See [`Resource::post_error()`], for a more convenient
@@ -201,10 +297,95 @@ impl DisplayHandle {
         self.handle.get_object_data_any(id)
     }
 
+    /// Route the requests of an already initialized object to another [`Dispatch`] implementation
+    ///
+    /// From now on, requests to this object and its destruction will be processed by the
+    /// `Dispatch<I, U, State>` implementation of `DelegateTo` rather than the one it was created with. This
+    /// allows a protocol helper to hand over an object to another module, for example when a surface is
+    /// given a role.
+    ///
+    /// Returns an error if the object is no longer alive, if its user data is not of type `U` or if `State` is
+    /// not the state type of this display.
+    pub fn retarget_resource<State, DelegateTo, I, U>(&self, resource: &I) -> Result<(), InvalidId>
+    where
+        State: Dispatch<I, U> + 'static,
+        DelegateTo: Dispatch<I, U, State> + 'static,
+        I: Resource + 'static,
+        U: Send + Sync + 'static,
+    {
+        let data = self.get_object_data(resource.id())?;
+        let data = data.downcast_ref::<ResourceData<I, U>>().ok_or(InvalidId)?;
+        data.retarget::<State, DelegateTo>()
+    }
+
+    /// Set or remove the interceptor of the messages exchanged with clients
+    ///
+    /// The interceptor sees every request sent by clients before it is dispatched to your [`Dispatch`]
+    /// implementations, and may pass it through, modify it or consume it. It also sees the events before
+    /// they are sent, and may veto them. See
+    /// [`Handle::set_interceptor()`] for details.
+    pub fn set_interceptor(&self, interceptor: Option<Arc<dyn Interceptor>>) {
+        self.handle.set_interceptor(interceptor)
+    }
+
+    /// Set a callback to be invoked when the display becomes idle
+    ///
+    /// The display is idle once all events have been flushed to the clients and no request is waiting to
+    /// be dispatched, which can be used to schedule low-priority work. The callback is invoked at the end of
+    /// [`Display::flush_clients()`] or [`DisplayHandle::flush_clients()`], at most once after each period
+    /// of activity. It replaces any previously set callback. See [`Handle::set_idle_callback()`] for details.
+    pub fn on_idle<F>(&self, callback: F)
+    where
+        F: Fn(&DisplayHandle) + Send + Sync + 'static,
+    {
+        // a weak handle avoids a reference cycle between the backend and the callback
+        let handle = self.handle.downgrade();
+        self.handle.set_idle_callback(Some(Arc::new(move || {
+            if let Some(handle) = handle.upgrade() {
+                callback(&DisplayHandle::from(handle));
+            }
+        })));
+    }
+
     /// Flush outgoing buffers into their respective sockets.
     pub fn flush_clients(&mut self) -> std::io::Result<()> {
         self.handle.flush(None)
     }
+
+    /// Flush the outgoing buffer of a single client into its socket.
+    ///
+    /// This can be used to send events to a specific client (such as the one holding input focus) as soon
+    /// as possible, without waiting for the buffers of all other clients to be written as well. Does nothing
+    /// if the client is no longer alive.
+    pub fn flush_client(&mut self, client: &Client) -> std::io::Result<()> {
+        self.handle.flush_client(client.id())
+    }
+
+    /// Flush outgoing buffers into their respective sockets, except for the listed clients.
+    ///
+    /// All the other clients are flushed even if some of them fail, the errors are then returned along
+    /// with the ids of their clients. A `WouldBlock` error means that the socket of the client is full,
+    /// its events stay buffered until the next flush.
+    pub fn flush_clients_except(
+        &mut self,
+        except: &[ClientId],
+    ) -> Result<(), Vec<(ClientId, std::io::Error)>> {
+        let mut clients = Vec::new();
+        self.handle.with_all_clients(|id| {
+            if !except.contains(&id) {
+                clients.push(id);
+            }
+        });
+        let errors = clients
+            .into_iter()
+            .filter_map(|id| self.handle.flush_client(id.clone()).err().map(|err| (id, err)))
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 impl From<Handle> for DisplayHandle {
@@ -2,13 +2,15 @@ use std::os::unix::io::OwnedFd;
 use std::sync::Arc;
 
 use wayland_backend::server::{
-    ClientData, ClientId, GlobalHandler, GlobalId, Handle, ObjectData, ObjectId,
+    ClientData, ClientId, Credentials, CredentialsFilter, GlobalHandler, GlobalId, Handle,
+    ObjectData, ObjectId, WeakHandle,
 };
 
 use crate::{Client, DataInit, DisplayHandle, New, Resource};
 
 pub(crate) struct GlobalData<I, U, D> {
     pub(crate) data: U,
+    pub(crate) handle: WeakHandle,
     pub(crate) _types: std::marker::PhantomData<(I, D)>,
 }
 
@@ -19,7 +21,7 @@ impl<I: Resource + 'static, U: Send + Sync + 'static, D: GlobalDispatch<I, U> +
     GlobalHandler<D> for GlobalData<I, U, D>
 {
     fn can_view(&self, id: ClientId, data: &Arc<dyn ClientData>, _: GlobalId) -> bool {
-        let client = Client { id, data: data.clone() };
+        let client = Client { id, data: data.clone(), handle: self.handle.clone() };
         <D as GlobalDispatch<I, U>>::can_view(client, &self.data)
     }
 
@@ -121,6 +123,37 @@ pub trait GlobalDispatch<I: Resource, GlobalData, State = Self>: Sized {
     }
 }
 
+/// A policy restricting which clients may access a global, based on their credentials
+///
+/// Clients whose credentials cannot be retrieved on the current platform are always denied access.
+///
+/// See [`DisplayHandle::restrict_global()`].
+#[derive(Clone)]
+pub enum GlobalPolicy {
+    /// Only clients running as one of these user ids may access the global
+    UidIn(Vec<u32>),
+    /// Only clients whose credentials are accepted by this callback may access the global
+    Callback(CredentialsFilter),
+}
+
+impl GlobalPolicy {
+    pub(crate) fn into_filter(self) -> CredentialsFilter {
+        match self {
+            Self::UidIn(uids) => Arc::new(move |creds: &Credentials| uids.contains(&creds.uid)),
+            Self::Callback(cb) => cb,
+        }
+    }
+}
+
+impl std::fmt::Debug for GlobalPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UidIn(uids) => f.debug_tuple("UidIn").field(uids).finish(),
+            Self::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
+}
+
 /*
  * Dispatch delegation helpers
  */
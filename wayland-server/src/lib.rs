@@ -89,25 +89,48 @@ mod client;
 mod dispatch;
 mod display;
 mod global;
+#[cfg(feature = "mocks")]
+pub mod mock;
 mod socket;
 
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod __serde;
+
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+pub mod __arbitrary;
+
+// used by the code generated by `wayland-scanner` with its `enum_results` option
+#[doc(hidden)]
+pub use wayland_backend::__enum_results;
+
+// used by the code generated by `wayland-scanner` with its `tracing` option
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing as __tracing;
+
 pub use client::Client;
 pub use dispatch::{DataInit, Dispatch, New, ResourceData};
 pub use display::{Display, DisplayHandle};
-pub use global::GlobalDispatch;
+pub use global::{GlobalDispatch, GlobalPolicy};
 pub use socket::{BindError, ListeningSocket};
 
 /// Backend reexports
 pub mod backend {
     pub use wayland_backend::protocol;
     pub use wayland_backend::server::{
-        Backend, ClientData, ClientId, Credentials, DisconnectReason, GlobalHandler, GlobalId,
-        Handle, InitError, InvalidId, ObjectData, ObjectId, WeakHandle,
+        Backend, BackendKind, ClientData, ClientId, Credentials, CredentialsFilter,
+        DisconnectReason, GlobalHandler, GlobalId, Handle, InitError, Interceptor, InvalidId,
+        ObjectData, ObjectId, ResourceUsage, WeakHandle,
     };
     pub use wayland_backend::smallvec;
 }
 
-pub use wayland_backend::protocol::WEnum;
+pub use wayland_backend::protocol::{UnknownVariant, WEnum};
+
+#[cfg(feature = "arbitrary")]
+pub use __arbitrary::ArbitraryObjects;
 
 /// Generated protocol definitions
 ///
@@ -120,7 +143,12 @@ pub mod protocol {
     pub mod __interfaces {
         wayland_scanner::generate_interfaces!("wayland.xml");
     }
-    wayland_scanner::generate_server_code!("wayland.xml");
+    wayland_scanner::generate_server_code!(
+        "wayland.xml",
+        mocks = "mocks",
+        serde = "serde",
+        arbitrary = "arbitrary"
+    );
 }
 
 // internal imports for dispatching logging depending on the `log` feature
@@ -148,7 +176,9 @@ pub trait Resource: Clone + std::fmt::Debug + Sized {
 
     /// The client owning this object
     ///
-    /// Returns [`None`] if the object is no longer alive.
+    /// Returns [`None`] if the object is no longer alive. The returned [`Client`] can be kept around, and
+    /// its [`is_alive()`](Client::is_alive) and [`on_disconnect()`](Client::on_disconnect) methods used to
+    /// know when the client goes away.
     fn client(&self) -> Option<Client> {
         let handle = self.handle().upgrade()?;
         let client_id = handle.get_client(self.id()).ok()?;
@@ -193,6 +223,17 @@ pub trait Resource: Clone + std::fmt::Debug + Sized {
     /// Send an event to this object
     fn send_event(&self, evt: Self::Event<'_>) -> Result<(), InvalidId>;
 
+    /// Queue an event to be sent to this object by the thread dispatching the [`Display`]
+    ///
+    /// Unlike [`send_event()`](Resource::send_event), this can safely be used from other threads (like a
+    /// rendering thread sending `wl_buffer.release` events) with both backends. See
+    /// [`DisplayHandle::queue_event()`] for details.
+    #[inline]
+    fn queue_event(&self, evt: Self::Event<'_>) -> Result<(), InvalidId> {
+        let dh = self.handle().upgrade().map(DisplayHandle::from).ok_or(InvalidId)?;
+        dh.queue_event(self, evt)
+    }
+
     /// Trigger a protocol error on this object
     ///
     /// The `code` is intended to be from the `Error` enum declared alongside that object interface.
@@ -0,0 +1,244 @@
+//! Mock implementations of globals, for testing clients
+//!
+//! A [`Mock`] advertises a global without any [`Dispatch`](crate::Dispatch) implementation: it
+//! records the requests sent to the objects created from it, and the tests script the events sent to
+//! the client through the generated methods of these objects. This allows testing the event handling
+//! of a client, or of a protocol extension, against a [`Display`](crate::Display) running in the test
+//! itself rather than a real compositor.
+//!
+//! This module requires the `mocks` cargo feature. The `MockXxx` aliases of the protocol objects are
+//! generated by `wayland-scanner` with its `mocks` option, like the ones of the core protocol:
+//!
+//! ```no_run
+//! use wayland_server::protocol::wl_seat;
+//! # fn test(dh: &wayland_server::DisplayHandle) {
+//!
+//! // the state of the `Display`, with no handler for the seat
+//! struct State;
+//!
+//! let seat = wl_seat::MockWlSeat::new::<State>(dh, 7);
+//!
+//! // ... the client under test binds the seat, and the display dispatches its requests
+//!
+//! for seat in seat.objects::<wl_seat::WlSeat>(dh) {
+//!     seat.capabilities(wl_seat::Capability::Keyboard);
+//! }
+//! for (_, request) in seat.take_requests::<wl_seat::WlSeat>() {
+//!     assert!(matches!(request, wl_seat::Request::GetKeyboard { .. }));
+//! }
+//! # }
+//! ```
+
+use std::{
+    any::Any,
+    marker::PhantomData,
+    os::unix::io::OwnedFd,
+    sync::{Arc, Mutex},
+};
+
+use wayland_backend::{
+    protocol::{same_interface, Argument, Message},
+    server::{ClientId, GlobalHandler, GlobalId, Handle, ObjectData, ObjectId},
+};
+
+use crate::{DisplayHandle, Resource};
+
+/// A mock implementation of a global, recording the requests it receives
+///
+/// The objects created by the requests of its objects, like the `wl_pointer` of a `wl_seat`, are
+/// mocked as well, their requests are recorded along the ones of the global.
+pub struct Mock<I> {
+    log: MockLog,
+    global: GlobalId,
+    _phantom: PhantomData<fn(I)>,
+}
+
+impl<I> Mock<I>
+where
+    I: Mockable + Send + 'static,
+    I::Request: Send + 'static,
+{
+    /// Create the global, in a [`Display`](crate::Display) whose state is `D`
+    pub fn new<D: 'static>(dh: &DisplayHandle, version: u32) -> Self {
+        let log = MockLog::default();
+        let global = dh.backend_handle().create_global::<D>(
+            I::interface(),
+            version,
+            Arc::new(MockGlobal::<I> { log: log.clone(), _phantom: PhantomData }),
+        );
+        Mock { log, global, _phantom: PhantomData }
+    }
+
+    /// The id of the global
+    pub fn global(&self) -> &GlobalId {
+        &self.global
+    }
+
+    /// The live objects of interface `C` created from this global
+    ///
+    /// This includes the objects binding the global, and the objects created from them.
+    pub fn objects<C: Resource + 'static>(&self, dh: &DisplayHandle) -> Vec<C> {
+        self.log
+            .0
+            .lock()
+            .unwrap()
+            .objects
+            .iter()
+            .filter(|id| same_interface(id.interface(), C::interface()))
+            .filter(|id| dh.object_info((*id).clone()).is_ok())
+            .filter_map(|id| C::from_id(dh, id.clone()).ok())
+            .collect()
+    }
+
+    /// Take the requests received so far by the objects of interface `C`, in order
+    pub fn take_requests<C>(&self) -> Vec<(C, C::Request)>
+    where
+        C: Resource + Send + 'static,
+        C::Request: Send + 'static,
+    {
+        let mut state = self.log.0.lock().unwrap();
+        let (taken, kept) = std::mem::take(&mut state.requests)
+            .into_iter()
+            .partition::<Vec<_>, _>(|request| request.is::<(C, C::Request)>());
+        state.requests = kept;
+        taken.into_iter().map(|request| *request.downcast::<(C, C::Request)>().unwrap()).collect()
+    }
+}
+
+impl<I: Resource> std::fmt::Debug for Mock<I> {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.log.0.lock().unwrap();
+        f.debug_struct("Mock")
+            .field("interface", &I::interface().name)
+            .field("global", &self.global)
+            .field("objects", &state.objects.len())
+            .field("pending_requests", &state.requests.len())
+            .finish()
+    }
+}
+
+/// An interface that can be mocked
+///
+/// It is implemented by the code generated by `wayland-scanner` with its `mocks` option.
+pub trait Mockable: Resource {
+    /// The object data of the object created by the request with this opcode, if any
+    #[doc(hidden)]
+    fn __child_data<D: 'static>(opcode: u16, log: &MockLog) -> Option<Arc<dyn ObjectData<D>>>;
+}
+
+#[derive(Default)]
+struct MockState {
+    objects: Vec<ObjectId>,
+    // the `(C, C::Request)` tuples of the requests
+    requests: Vec<Box<dyn Any + Send>>,
+}
+
+/// The record of the objects and requests of a [`Mock`]
+#[doc(hidden)]
+#[derive(Clone, Default)]
+pub struct MockLog(Arc<Mutex<MockState>>);
+
+impl MockLog {
+    // This is only to be used by code generated by wayland-scanner
+    #[doc(hidden)]
+    pub fn child<C, D>(&self) -> Arc<dyn ObjectData<D>>
+    where
+        C: Mockable + Send + 'static,
+        C::Request: Send + 'static,
+        D: 'static,
+    {
+        Arc::new(MockData::<C> { log: self.clone(), _phantom: PhantomData })
+    }
+}
+
+impl std::fmt::Debug for MockLog {
+    #[cfg_attr(coverage, coverage(off))]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockLog").finish_non_exhaustive()
+    }
+}
+
+struct MockGlobal<I> {
+    log: MockLog,
+    _phantom: PhantomData<fn(I)>,
+}
+
+impl<I, D: 'static> GlobalHandler<D> for MockGlobal<I>
+where
+    I: Mockable + Send + 'static,
+    I::Request: Send + 'static,
+{
+    fn bind(
+        self: Arc<Self>,
+        _: &Handle,
+        _: &mut D,
+        _: ClientId,
+        _: GlobalId,
+        object_id: ObjectId,
+    ) -> Arc<dyn ObjectData<D>> {
+        self.log.0.lock().unwrap().objects.push(object_id);
+        self.log.child::<I, D>()
+    }
+}
+
+struct MockData<I> {
+    log: MockLog,
+    _phantom: PhantomData<fn(I)>,
+}
+
+impl<I, D: 'static> ObjectData<D> for MockData<I>
+where
+    I: Mockable + Send + 'static,
+    I::Request: Send + 'static,
+{
+    fn request(
+        self: Arc<Self>,
+        handle: &Handle,
+        _: &mut D,
+        _: ClientId,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData<D>>> {
+        let new_id = msg.args.iter().find_map(|arg| match arg {
+            Argument::NewId(id) => Some(id.clone()),
+            _ => None,
+        });
+        let child_data = new_id.map(|id| {
+            self.log.0.lock().unwrap().objects.push(id);
+            I::__child_data::<D>(msg.opcode, &self.log)
+                .unwrap_or_else(|| Arc::new(IgnoreData) as Arc<dyn ObjectData<D>>)
+        });
+
+        let dh = DisplayHandle::from(handle.clone());
+        match I::parse_request(&dh, msg) {
+            Ok((resource, request)) => {
+                self.log.0.lock().unwrap().requests.push(Box::new((resource, request)))
+            }
+            Err(e) => crate::log_warn!("Dropping a request of a mock object: {}", e),
+        }
+
+        child_data
+    }
+
+    fn destroyed(self: Arc<Self>, _: &Handle, _: &mut D, _: ClientId, _: ObjectId) {}
+}
+
+// the object data of the objects whose interface is not known when they are created
+struct IgnoreData;
+
+impl<D> ObjectData<D> for IgnoreData {
+    fn request(
+        self: Arc<Self>,
+        _: &Handle,
+        _: &mut D,
+        _: ClientId,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData<D>>> {
+        msg.args
+            .iter()
+            .any(|arg| matches!(arg, Argument::NewId(_)))
+            .then_some(self as Arc<dyn ObjectData<D>>)
+    }
+
+    fn destroyed(self: Arc<Self>, _: &Handle, _: &mut D, _: ClientId, _: ObjectId) {}
+}
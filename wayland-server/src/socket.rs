@@ -72,8 +72,13 @@ impl ListeningSocket {
     ///
     /// The socket will be created at the specified path, and this method will acquire an associatet lockfile
     /// alongside it.
+    ///
+    /// The lockfile is handled the same way as libwayland does (its path is the one of the socket with
+    /// `.lock` appended), so that servers using either implementation never pick the same socket name.
     pub fn bind_absolute(socket_path: PathBuf) -> Result<Self, BindError> {
-        let lock_path = socket_path.with_extension("lock");
+        let mut lock_path = socket_path.clone().into_os_string();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
         let mut _lock;
 
         // The locking code uses a loop to avoid an open()-flock() race condition, described in more
@@ -118,14 +123,20 @@ impl ListeningSocket {
         }
 
         // check if an old socket exists, and cleanup if relevant
-        match socket_path.try_exists() {
-            Ok(false) => {
+        //
+        // As we hold the lock, any socket at this path has been left behind by a server that did not
+        // clean up after itself. Like libwayland, only remove it if it is writable by its owner or group.
+        match fs::symlink_metadata(&socket_path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
                 // none exist, good
             }
-            Ok(true) => {
-                // one exist, remove it
+            Ok(meta) if meta.mode() & 0o220 != 0 => {
+                // a stale one exist, remove it
                 fs::remove_file(&socket_path).map_err(|_| BindError::AlreadyInUse)?;
             }
+            Ok(_) => {
+                // the bind below will fail with the appropriate error
+            }
             Err(e) => {
                 // some error stat-ing the socket?
                 return Err(BindError::Io(e));
@@ -230,3 +241,31 @@ impl std::fmt::Display for BindError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BindError, ListeningSocket};
+
+    #[test]
+    fn lockfile_naming_and_exclusion() {
+        let dir =
+            std::env::temp_dir().join(format!("wayland-server-socket-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket_path = dir.join("wayland-1.5");
+
+        // stale socket left behind by a dead server
+        drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+
+        let socket = ListeningSocket::bind_absolute(socket_path.clone()).unwrap();
+        assert!(dir.join("wayland-1.5.lock").exists());
+        assert!(matches!(
+            ListeningSocket::bind_absolute(socket_path.clone()),
+            Err(BindError::AlreadyInUse)
+        ));
+
+        drop(socket);
+        assert!(!socket_path.exists());
+        assert!(!dir.join("wayland-1.5.lock").exists());
+        std::fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -112,6 +112,43 @@ pub fn wayland_client_handle() -> &'static WaylandClient {
     &WAYLAND_CLIENT_HANDLE
 }
 
+// Functions added in libwayland 1.18, which the main library does not require
+#[cfg(all(feature = "client", feature = "libwayland_1_18"))]
+external_library!(WaylandClient118, "wayland-client",
+    functions:
+        fn wl_proxy_set_tag(*mut wl_proxy, *const *const c_char) -> (),
+        fn wl_proxy_get_tag(*mut wl_proxy) -> *const *const c_char,
+);
+
+#[cfg(all(feature = "client", feature = "libwayland_1_18", feature = "dlopen"))]
+pub fn wayland_client_1_18_handle() -> &'static WaylandClient118 {
+    static WAYLAND_CLIENT_1_18_HANDLE: Lazy<WaylandClient118> = Lazy::new(|| {
+        unsafe { WaylandClient118::open("libwayland-client.so.0") }
+            .or_else(|_| unsafe { WaylandClient118::open("libwayland-client.so") })
+            .expect("Library libwayland-client.so could not be loaded, or is older than 1.18.")
+    });
+
+    &WAYLAND_CLIENT_1_18_HANDLE
+}
+
+// Functions added in libwayland 1.23, which the main library does not require
+#[cfg(all(feature = "client", feature = "libwayland_1_23"))]
+external_library!(WaylandClient123, "wayland-client",
+    functions:
+        fn wl_display_set_max_buffer_size(*mut wl_display, usize) -> (),
+);
+
+#[cfg(all(feature = "client", feature = "libwayland_1_23", feature = "dlopen"))]
+pub fn wayland_client_1_23_handle() -> &'static WaylandClient123 {
+    static WAYLAND_CLIENT_1_23_HANDLE: Lazy<WaylandClient123> = Lazy::new(|| {
+        unsafe { WaylandClient123::open("libwayland-client.so.0") }
+            .or_else(|_| unsafe { WaylandClient123::open("libwayland-client.so") })
+            .expect("Library libwayland-client.so could not be loaded, or is older than 1.23.")
+    });
+
+    &WAYLAND_CLIENT_1_23_HANDLE
+}
+
 #[cfg(all(feature = "client", not(feature = "dlopen")))]
 pub fn is_lib_available() -> bool {
     true
@@ -18,9 +18,15 @@ pub enum wl_display {}
 pub enum wl_event_loop {}
 pub enum wl_event_source {}
 pub enum wl_global {}
+pub enum wl_protocol_logger {}
 pub enum wl_resource {}
 pub enum wl_shm_buffer {}
 
+pub const WL_EVENT_READABLE: u32 = 0x01;
+pub const WL_EVENT_WRITABLE: u32 = 0x02;
+pub const WL_EVENT_HANGUP: u32 = 0x04;
+pub const WL_EVENT_ERROR: u32 = 0x08;
+
 pub type wl_event_loop_fd_func_t = unsafe extern "C" fn(c_int, u32, *mut c_void) -> c_int;
 pub type wl_event_loop_timer_func_t = unsafe extern "C" fn(*mut c_void) -> c_int;
 pub type wl_event_loop_signal_func_t = unsafe extern "C" fn(c_int, *mut c_void) -> c_int;
@@ -32,6 +38,24 @@ pub type wl_display_global_filter_func_t =
     unsafe extern "C" fn(*const wl_client, *const wl_global, *mut c_void) -> bool;
 pub type wl_client_for_each_resource_iterator_func_t =
     unsafe extern "C" fn(*mut wl_resource, *mut c_void) -> c_int;
+pub type wl_protocol_logger_func_t =
+    unsafe extern "C" fn(*mut c_void, wl_protocol_logger_type, *const wl_protocol_logger_message) -> ();
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum wl_protocol_logger_type {
+    WL_PROTOCOL_LOGGER_REQUEST = 0,
+    WL_PROTOCOL_LOGGER_EVENT = 1,
+}
+
+#[repr(C)]
+pub struct wl_protocol_logger_message {
+    pub resource: *mut wl_resource,
+    pub message_opcode: c_int,
+    pub message: *const wl_message,
+    pub arguments_count: c_int,
+    pub arguments: *const wl_argument,
+}
 
 #[repr(C)]
 pub struct wl_listener {
@@ -81,6 +105,9 @@ external_library!(WaylandServer, "wayland-server",
         fn wl_display_init_shm(*mut wl_display) -> c_int,
         fn wl_display_add_client_created_listener(*mut wl_display, *mut wl_listener) -> (),
         fn wl_display_set_global_filter(*mut wl_display, wl_display_global_filter_func_t, *mut c_void) -> (),
+        fn wl_display_add_protocol_logger(*mut wl_display, wl_protocol_logger_func_t, *mut c_void) -> *mut wl_protocol_logger,
+    // wl_protocol_logger
+        fn wl_protocol_logger_destroy(*mut wl_protocol_logger) -> (),
         fn wl_display_get_client_list(*mut wl_display) -> *mut wl_list,
     // wl_event_loop
         fn wl_event_loop_create() -> *mut wl_event_loop,
@@ -180,6 +207,25 @@ pub fn wayland_server_handle() -> &'static WaylandServer {
     &WAYLAND_SERVER_HANDLE
 }
 
+// Functions added in libwayland 1.23, which the main library does not require
+#[cfg(all(feature = "server", feature = "libwayland_1_23"))]
+external_library!(WaylandServer123, "wayland-server",
+    functions:
+        fn wl_display_set_default_max_buffer_size(*mut wl_display, usize) -> (),
+        fn wl_client_set_max_buffer_size(*mut wl_client, usize) -> (),
+);
+
+#[cfg(all(feature = "server", feature = "libwayland_1_23", feature = "dlopen"))]
+pub fn wayland_server_1_23_handle() -> &'static WaylandServer123 {
+    static WAYLAND_SERVER_1_23_HANDLE: Lazy<WaylandServer123> = Lazy::new(|| {
+        unsafe { WaylandServer123::open("libwayland-server.so.0") }
+            .or_else(|_| unsafe { WaylandServer123::open("libwayland-server.so") })
+            .expect("Library libwayland-server.so could not be loaded, or is older than 1.23.")
+    });
+
+    &WAYLAND_SERVER_1_23_HANDLE
+}
+
 #[cfg(all(feature = "server", not(feature = "dlopen")))]
 pub fn is_lib_available() -> bool {
     true
@@ -22,12 +22,14 @@ fn attach_null() {
         .display
         .handle()
         .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(1, ());
-    let mut server_ddata = ServerHandler { buffer_found: None, fd_found: None };
+    let mut server_ddata =
+        ServerHandler { buffer_found: None, fd_found: None, callbacks: Vec::new() };
 
     // Client setup
     //
     let (_, mut client) = server.add_client();
-    let mut client_ddata = ClientHandler { globals: globals::GlobalList::new() };
+    let mut client_ddata =
+        ClientHandler { globals: globals::GlobalList::new(), callbacks_done: Vec::new() };
 
     let registry = client.display.get_registry(&client.event_queue.handle(), ());
 
@@ -51,6 +53,50 @@ fn attach_null() {
     assert_eq!(server_ddata.buffer_found, Some(None));
 }
 
+#[test]
+fn frame_callbacks() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(1, ());
+    let mut server_ddata =
+        ServerHandler { buffer_found: None, fd_found: None, callbacks: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata =
+        ClientHandler { globals: globals::GlobalList::new(), callbacks_done: Vec::new() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let compositor = client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_compositor::WlCompositor, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+    let surface = compositor.create_surface(&client.event_queue.handle(), ());
+    surface.frame(&client.event_queue.handle(), ());
+    surface.frame(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    // only the second callback was fired
+    assert_eq!(client_ddata.callbacks_done, vec![7]);
+
+    // fire the stored one
+    server_ddata.callbacks.pop().unwrap().done(42);
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    assert_eq!(client_ddata.callbacks_done, vec![7, 42]);
+}
+
 #[test]
 fn attach_buffer() {
     // Server setup
@@ -61,12 +107,14 @@ fn attach_buffer() {
         .handle()
         .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(1, ());
     server.display.handle().create_global::<ServerHandler, ways::protocol::wl_shm::WlShm, _>(1, ());
-    let mut server_ddata = ServerHandler { buffer_found: None, fd_found: None };
+    let mut server_ddata =
+        ServerHandler { buffer_found: None, fd_found: None, callbacks: Vec::new() };
 
     // Client setup
     //
     let (_, mut client) = server.add_client();
-    let mut client_ddata = ClientHandler { globals: globals::GlobalList::new() };
+    let mut client_ddata =
+        ClientHandler { globals: globals::GlobalList::new(), callbacks_done: Vec::new() };
 
     let registry = client.display.get_registry(&client.event_queue.handle(), ());
 
@@ -122,6 +170,7 @@ fn attach_buffer() {
 struct ServerHandler {
     buffer_found: Option<Option<ServerBuffer>>,
     fd_found: Option<(OwnedFd, Option<ServerBuffer>)>,
+    callbacks: Vec<ways::protocol::wl_callback::WlCallback>,
 }
 
 impl ways::Dispatch<ways::protocol::wl_compositor::WlCompositor, ()> for ServerHandler {
@@ -150,15 +199,25 @@ impl ways::Dispatch<ways::protocol::wl_surface::WlSurface, ()> for ServerHandler
         request: ways::protocol::wl_surface::Request,
         _: &(),
         _: &ways::DisplayHandle,
-        _: &mut ways::DataInit<'_, Self>,
+        init: &mut ways::DataInit<'_, Self>,
     ) {
-        if let ways::protocol::wl_surface::Request::Attach { buffer, x, y } = request {
-            assert_eq!(x, 0);
-            assert_eq!(y, 0);
-            assert!(state.buffer_found.is_none());
-            state.buffer_found = Some(buffer);
-        } else {
-            panic!("Unexpected request!");
+        match request {
+            ways::protocol::wl_surface::Request::Attach { buffer, x, y } => {
+                assert_eq!(x, 0);
+                assert_eq!(y, 0);
+                assert!(state.buffer_found.is_none());
+                state.buffer_found = Some(buffer);
+            }
+            // the first frame callback is kept for later, the next ones are fired immediately
+            ways::protocol::wl_surface::Request::Frame { callback }
+                if state.callbacks.is_empty() =>
+            {
+                state.callbacks.push(init.init_callback(callback));
+            }
+            ways::protocol::wl_surface::Request::Frame { callback } => {
+                init.fire_callback(callback, 7);
+            }
+            _ => panic!("Unexpected request!"),
         }
     }
 }
@@ -216,6 +275,22 @@ server_ignore_global_impl!(ServerHandler => [
  */
 struct ClientHandler {
     globals: globals::GlobalList,
+    callbacks_done: Vec<u32>,
+}
+
+impl wayc::Dispatch<wayc::protocol::wl_callback::WlCallback, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &wayc::protocol::wl_callback::WlCallback,
+        event: wayc::protocol::wl_callback::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        if let wayc::protocol::wl_callback::Event::Done { callback_data } = event {
+            state.callbacks_done.push(callback_data);
+        }
+    }
 }
 
 impl AsMut<globals::GlobalList> for ClientHandler {
@@ -30,3 +30,79 @@ fn client_roundtrip() {
 
     server_thread.join().unwrap();
 }
+
+#[test]
+fn client_dispatch_wakeup() {
+    let mut server = TestServer::<()>::new();
+
+    let (_, mut client) = server.add_client::<()>();
+    let backend = client.conn.backend();
+
+    // a pending wakeup interrupts the next wait
+    backend.wakeup().unwrap();
+    assert_eq!(client.event_queue.blocking_dispatch(&mut ()).unwrap(), 0);
+
+    // as well as a wakeup from another thread while waiting
+    let client_thread =
+        ::std::thread::spawn(move || client.event_queue.blocking_dispatch(&mut ()).unwrap());
+    ::std::thread::sleep(::std::time::Duration::from_millis(50));
+    backend.wakeup().unwrap();
+    assert_eq!(client_thread.join().unwrap(), 0);
+}
+
+struct CallbackCounter(usize);
+
+impl wayc::Dispatch<wayc::protocol::wl_callback::WlCallback, ()> for CallbackCounter {
+    fn event(
+        state: &mut Self,
+        _: &wayc::protocol::wl_callback::WlCallback,
+        _: wayc::protocol::wl_callback::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        state.0 += 1;
+    }
+}
+
+#[test]
+fn client_dispatch_pending_limited() {
+    let mut server = TestServer::<()>::new();
+
+    let (_, mut client) = server.add_client::<CallbackCounter>();
+    let qh = client.event_queue.handle();
+
+    for _ in 0..3 {
+        client.display.sync(&qh, ());
+    }
+    client.conn.flush().unwrap();
+    server.answer(&mut ());
+
+    // queue the events without dispatching them
+    client.conn.prepare_read().unwrap().read().unwrap();
+
+    let mut counter = CallbackCounter(0);
+    assert_eq!(client.event_queue.dispatch_pending_limited(&mut counter, 2).unwrap(), (2, 1));
+    assert_eq!(counter.0, 2);
+    assert_eq!(client.event_queue.dispatch_pending_limited(&mut counter, 2).unwrap(), (1, 0));
+    assert_eq!(counter.0, 3);
+}
+
+// with the debug checks, dispatching a queue frozen by the same thread panics instead of deadlocking
+#[cfg(feature = "debug-checks")]
+#[test]
+#[should_panic(expected = "freeze guard")]
+fn client_dispatch_frozen_queue() {
+    let mut server = TestServer::<()>::new();
+
+    let (_, mut client) = server.add_client::<CallbackCounter>();
+    let qh = client.event_queue.handle();
+
+    client.display.sync(&qh, ());
+    client.conn.flush().unwrap();
+    server.answer(&mut ());
+    client.conn.prepare_read().unwrap().read().unwrap();
+
+    let _guard = qh.freeze();
+    client.event_queue.dispatch_pending(&mut CallbackCounter(0)).unwrap();
+}
@@ -0,0 +1,109 @@
+use std::{
+    borrow::Cow,
+    os::unix::io::OwnedFd,
+    sync::{Arc, Mutex},
+};
+
+use crate::helpers::{roundtrip, wayc, ways, TestServer};
+
+use wayc::backend::{
+    protocol::{BorrowedMessage, Message},
+    Backend, ObjectData, ObjectId,
+};
+
+// the core protocol, generated with the borrowed events
+mod borrowed {
+    use wayland_client;
+    use wayland_client::protocol::__interfaces::*;
+    wayland_scanner::generate_client_code!("../wayland-client/wayland.xml", borrowed_events);
+}
+
+use borrowed::wl_registry;
+
+#[derive(Default)]
+struct RegistryData {
+    globals: Mutex<Vec<(String, u32)>>,
+}
+
+impl ObjectData for RegistryData {
+    fn event(
+        self: Arc<Self>,
+        _: &Backend,
+        msg: Message<ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData>> {
+        panic!("Unexpected owned event {:?}", msg);
+    }
+
+    fn event_borrowed(
+        self: Arc<Self>,
+        backend: &Backend,
+        msg: BorrowedMessage<'_, ObjectId, OwnedFd>,
+    ) -> Option<Arc<dyn ObjectData>> {
+        let conn = wayc::Connection::from_backend(backend.clone());
+        let (_, event) = wl_registry::WlRegistry::parse_borrowed_event(&conn, msg).unwrap();
+        match event {
+            wl_registry::BorrowedEvent::Global { interface, version, .. } => {
+                // the name of the interface is not copied out of the message
+                assert!(matches!(interface, Cow::Borrowed(_)));
+                self.globals.lock().unwrap().push((interface.into_owned(), version));
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+        None
+    }
+
+    fn destroyed(&self, _: ObjectId) {}
+}
+
+#[test]
+fn registry_borrowed_events() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(3, ());
+
+    let (_, mut client) = server.add_client::<()>();
+
+    let data = Arc::new(RegistryData::default());
+    client
+        .conn
+        .send_request(
+            &client.display,
+            wayc::protocol::wl_display::Request::GetRegistry {},
+            Some(data.clone()),
+        )
+        .unwrap();
+
+    roundtrip(&mut client, &mut server, &mut (), &mut ServerHandler).unwrap();
+
+    assert_eq!(*data.globals.lock().unwrap(), vec![("wl_compositor".to_owned(), 3)]);
+}
+
+#[test]
+fn borrowed_event_into_owned() {
+    let event = wl_registry::BorrowedEvent::GlobalRemove { name: 42 };
+    assert_eq!(event.name(), "global_remove");
+    assert!(matches!(event.into_owned(), wl_registry::Event::GlobalRemove { name: 42 }));
+
+    let event = wl_registry::BorrowedEvent::Global {
+        name: 1,
+        interface: Cow::Borrowed("wl_shm"),
+        version: 2,
+    };
+    match event.into_owned() {
+        wl_registry::Event::Global { name, interface, version } => {
+            assert_eq!((name, interface.as_str(), version), (1, "wl_shm", 2));
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+}
+
+struct ServerHandler;
+
+server_ignore_impl!(ServerHandler => [
+    ways::protocol::wl_compositor::WlCompositor
+]);
+server_ignore_global_impl!(ServerHandler => [
+    ways::protocol::wl_compositor::WlCompositor
+]);
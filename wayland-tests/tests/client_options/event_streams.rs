@@ -0,0 +1,102 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task,
+};
+
+use crate::helpers::{roundtrip, wayc, ways, TestServer};
+
+use wayc::Proxy;
+
+// the core protocol, generated with the event streams
+mod streams {
+    use wayland_client;
+    use wayland_client::protocol::__interfaces::*;
+    wayland_scanner::generate_client_code!("../wayland-client/wayland.xml", event_streams);
+}
+
+struct FlagWaker(AtomicBool);
+
+impl task::Wake for FlagWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn registry_event_stream() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(3, ());
+
+    let (_, mut client) = server.add_client::<()>();
+
+    let display =
+        streams::wl_display::WlDisplay::from_id(&client.conn, client.display.id()).unwrap();
+    let (_registry, mut stream): (_, streams::wl_registry::WlRegistryEventStream) =
+        display.get_registry_stream().unwrap();
+
+    let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+    let waker = task::Waker::from(flag.clone());
+    assert!(stream.poll_next(&mut task::Context::from_waker(&waker)).is_pending());
+
+    roundtrip(&mut client, &mut server, &mut (), &mut ServerHandler).unwrap();
+
+    // the task polling the stream is woken up by the events
+    assert!(flag.0.load(Ordering::SeqCst));
+    match stream.poll_next(&mut task::Context::from_waker(&waker)) {
+        task::Poll::Ready(Some(streams::wl_registry::Event::Global {
+            interface, version, ..
+        })) => {
+            assert_eq!(interface, "wl_compositor");
+            assert_eq!(version, 3);
+        }
+        event => panic!("Unexpected event {:?}", event),
+    }
+    assert!(stream.try_next().is_none());
+}
+
+#[test]
+fn event_stream_ends_with_object() {
+    let mut server = TestServer::new();
+
+    let (_, mut client) = server.add_client::<()>();
+
+    let display =
+        streams::wl_display::WlDisplay::from_id(&client.conn, client.display.id()).unwrap();
+    let (_callback, mut stream) = display.sync_stream().unwrap();
+
+    roundtrip(&mut client, &mut server, &mut (), &mut ServerHandler).unwrap();
+
+    // the callback is destroyed by its done event, which ends the stream
+    assert!(matches!(stream.try_next(), Some(streams::wl_callback::Event::Done { .. })));
+    let waker = task::Waker::from(Arc::new(FlagWaker(AtomicBool::new(false))));
+    assert!(matches!(
+        stream.poll_next(&mut task::Context::from_waker(&waker)),
+        task::Poll::Ready(None)
+    ));
+}
+
+#[test]
+fn event_stream_of_dead_object() {
+    let mut server = TestServer::<ServerHandler>::new();
+
+    let (_, client) = server.add_client::<()>();
+
+    // no stream is created for an object that cannot send the request
+    let display = streams::wl_display::WlDisplay::inert(client.conn.backend().downgrade());
+    assert!(display.sync_stream().is_err());
+}
+
+struct ServerHandler;
+
+server_ignore_impl!(ServerHandler => [
+    ways::protocol::wl_compositor::WlCompositor
+]);
+server_ignore_global_impl!(ServerHandler => [
+    ways::protocol::wl_compositor::WlCompositor
+]);
@@ -0,0 +1,9 @@
+// The options of `generate_client_code!` only available on the client side, one module per option
+
+#[macro_use]
+#[path = "../helpers/mod.rs"]
+mod helpers;
+
+mod borrowed_events;
+mod event_streams;
+mod request_builders;
@@ -0,0 +1,104 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+use wayc::Proxy;
+
+// the core protocol, generated with the request builders
+mod builders {
+    use wayland_client;
+    use wayland_client::protocol::__interfaces::*;
+    wayland_scanner::generate_client_code!("../wayland-client/wayland.xml", builders);
+}
+
+#[test]
+fn request_builders() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(4, ());
+    let mut server_ddata = ServerHandler { requests: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let compositor = client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_compositor::WlCompositor, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            4..5,
+            (),
+        )
+        .unwrap();
+    let surface = compositor.create_surface(&client.event_queue.handle(), ());
+    let surface = builders::wl_surface::WlSurface::from_id(&client.conn, surface.id()).unwrap();
+
+    // the arguments are set by name, in any order
+    surface.damage_buffer_builder().height(4).width(3).x(1).y(2).send();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    assert!(matches!(
+        server_ddata.requests[..],
+        [ways::protocol::wl_surface::Request::DamageBuffer { x: 1, y: 2, width: 3, height: 4 }]
+    ));
+}
+
+struct ServerHandler {
+    requests: Vec<ways::protocol::wl_surface::Request>,
+}
+
+impl ways::Dispatch<ways::protocol::wl_compositor::WlCompositor, ()> for ServerHandler {
+    fn request(
+        _: &mut Self,
+        _: &ways::Client,
+        _: &ways::protocol::wl_compositor::WlCompositor,
+        request: ways::protocol::wl_compositor::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        if let ways::protocol::wl_compositor::Request::CreateSurface { id } = request {
+            data_init.init(id, ());
+        }
+    }
+}
+
+impl ways::Dispatch<ways::protocol::wl_surface::WlSurface, ()> for ServerHandler {
+    fn request(
+        state: &mut Self,
+        _: &ways::Client,
+        _: &ways::protocol::wl_surface::WlSurface,
+        request: ways::protocol::wl_surface::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, Self>,
+    ) {
+        state.requests.push(request);
+    }
+}
+
+server_ignore_global_impl!(ServerHandler => [
+    ways::protocol::wl_compositor::WlCompositor
+]);
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [
+    wayc::protocol::wl_compositor::WlCompositor,
+    wayc::protocol::wl_surface::WlSurface
+]);
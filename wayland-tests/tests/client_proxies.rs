@@ -51,6 +51,86 @@ fn proxy_equals() {
     assert!(compositor2 != compositor3);
 }
 
+#[test]
+fn core_interfaces_registered() {
+    let (client_socket, _server_socket) = std::os::unix::net::UnixStream::pair().unwrap();
+    let _conn = wayc::Connection::from_socket(client_socket).unwrap();
+
+    let surface = wayc::backend::protocol::Interface::find("wl_surface").unwrap();
+    assert!(wayc::backend::protocol::same_interface(
+        surface,
+        wayc::protocol::wl_surface::WlSurface::interface()
+    ));
+}
+
+#[test]
+fn proxy_supported_messages() {
+    use wayc::protocol::wl_output;
+
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_output::WlOutput, _>(2, ());
+    let mut server_ddata = ServerHandler { output: None };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let output = client_ddata
+        .globals
+        .bind::<wl_output::WlOutput, _, _>(&client.event_queue.handle(), &registry, 1..5, ())
+        .unwrap();
+    assert_eq!(output.version(), 2);
+
+    // the highest version known to the generated code caps the negotiation
+    assert_eq!(wl_output::WlOutput::interface().version, 4);
+    assert_eq!(wl_output::WlOutput::negotiate_version(2), 2);
+    assert_eq!(wl_output::WlOutput::negotiate_version(7), 4);
+
+    let release = wl_output::Request::Release;
+    assert_eq!(release.since(), wl_output::REQ_RELEASE_SINCE);
+    assert!(!release.is_supported(&output));
+    assert!(wl_output::Event::Done.is_supported(&output));
+    assert!(!wl_output::Event::Name { name: "out".into() }.is_supported(&output));
+
+    // the name of a message, from the enum or from its opcode
+    assert_eq!(release.name(), "release");
+    let desc = wl_output::WlOutput::interface().request(release.opcode()).unwrap();
+    assert_eq!(desc.name, "release");
+    assert!(wl_output::WlOutput::interface().event(wl_output::EVT_NAME_OPCODE + 10).is_none());
+
+    // the same through the reflection trait, as a logger would
+    fn describe(msg: &impl wayc::backend::protocol::MessageInfo) -> String {
+        format!("{}.{} ({:?})", msg.interface().name, msg.name(), msg.signature())
+    }
+    assert_eq!(describe(&release), "wl_output.release ([])");
+    let name = wl_output::Event::Name { name: "out".into() };
+    assert_eq!(describe(&name), "wl_output.name ([Str(No)])");
+    assert_eq!(wayc::backend::protocol::MessageInfo::since(&name), wl_output::EVT_NAME_SINCE);
+    assert!(std::ptr::eq(
+        wayc::backend::protocol::MessageInfo::desc(&name),
+        wl_output::WlOutput::interface().event(wl_output::EVT_NAME_OPCODE).unwrap()
+    ));
+
+    // the signature of a message, as in the description of the interface
+    let desc = wl_output::WlOutput::interface().event(wl_output::EVT_MODE_OPCODE).unwrap();
+    assert_eq!(desc.signature, wl_output::EVT_MODE_SIGNATURE);
+    assert_eq!(
+        wl_output::EVT_MODE_SIGNATURE,
+        &[
+            wayc::backend::protocol::ArgumentType::Uint,
+            wayc::backend::protocol::ArgumentType::Int,
+            wayc::backend::protocol::ArgumentType::Int,
+            wayc::backend::protocol::ArgumentType::Int,
+        ]
+    );
+}
+
 #[test]
 fn proxy_user_data() {
     let mut server = TestServer::new();
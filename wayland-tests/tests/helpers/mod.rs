@@ -88,8 +88,7 @@ pub fn roundtrip<CD: 'static, SD: 'static>(
     while !done2.load(Ordering::Acquire) {
         match client.conn.flush() {
             Ok(_) => {}
-            Err(wayc::backend::WaylandError::Io(e))
-                if e.kind() == ::std::io::ErrorKind::BrokenPipe => {}
+            Err(wayc::backend::WaylandError::Disconnected(_)) => {}
             Err(e) => return Err(e),
         }
         ::std::thread::sleep(::std::time::Duration::from_millis(100));
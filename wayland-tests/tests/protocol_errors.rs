@@ -57,6 +57,44 @@ fn client_receive_generic_error() {
     }
 }
 
+#[test]
+fn client_receive_enum_error() {
+    let mut server = TestServer::new();
+    server.display.handle().create_global::<ServerHandler, ways::protocol::wl_shm::WlShm, _>(1, ());
+
+    let (s_client, mut client) = server.add_client();
+
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut ServerHandler).unwrap();
+
+    client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_shm::WlShm, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut ServerHandler).unwrap();
+
+    // the server sends a protocol error of the error enum of wl_shm
+    let shm = s_client
+        .object_from_protocol_id::<ways::protocol::wl_shm::WlShm>(&server.display.handle(), 3)
+        .unwrap();
+    shm.post_invalid_fd_error("This is not a file descriptor");
+
+    assert!(roundtrip(&mut client, &mut server, &mut client_ddata, &mut ServerHandler).is_err());
+    let error = client.conn.protocol_error().unwrap();
+    assert_eq!(error.code, ways::protocol::wl_shm::Error::InvalidFd as u32);
+    assert_eq!(error.object_id, 3);
+    assert_eq!(error.object_interface, "wl_shm");
+}
+
 struct ClientHandler {
     globals: globals::GlobalList,
 }
@@ -78,14 +116,17 @@ wayc::delegate_dispatch!(ClientHandler:
 );
 
 client_ignore_impl!(ClientHandler => [
-    wayc::protocol::wl_compositor::WlCompositor
+    wayc::protocol::wl_compositor::WlCompositor,
+    wayc::protocol::wl_shm::WlShm
 ]);
 
 struct ServerHandler;
 
 server_ignore_impl!(ServerHandler => [
-    ways::protocol::wl_compositor::WlCompositor
+    ways::protocol::wl_compositor::WlCompositor,
+    ways::protocol::wl_shm::WlShm
 ]);
 server_ignore_global_impl!(ServerHandler => [
-    ways::protocol::wl_compositor::WlCompositor
+    ways::protocol::wl_compositor::WlCompositor,
+    ways::protocol::wl_shm::WlShm
 ]);
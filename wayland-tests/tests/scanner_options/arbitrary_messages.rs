@@ -0,0 +1,219 @@
+use std::{
+    fs::File,
+    os::unix::io::{AsFd, BorrowedFd},
+};
+
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+use arbitrary::{Error, Unstructured};
+
+use wayc::Proxy;
+use ways::Resource;
+
+mod canvas {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!("tests/protocols/arbitrary-messages.xml");
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!("tests/protocols/arbitrary-messages.xml", arbitrary);
+    }
+
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!("tests/protocols/arbitrary-messages.xml", arbitrary);
+    }
+}
+
+use canvas::client::test_canvas as client_canvas;
+use canvas::server::test_canvas as server_canvas;
+
+// fixed bytes, so that the test is deterministic
+fn fuzz_data() -> Vec<u8> {
+    (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect()
+}
+
+#[test]
+fn arbitrary_messages() {
+    let mut server = TestServer::new();
+    server.display.handle().create_global::<ServerHandler, server_canvas::TestCanvas, _>(1, ());
+    let mut server_ddata = ServerHandler { canvas: None, requests: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default(), events: Vec::new() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    // a version 1 object is never sent the requests added by version 2
+    let canvas = client_ddata
+        .globals
+        .bind::<client_canvas::TestCanvas, _, _>(&client.event_queue.handle(), &registry, 1..2, ())
+        .unwrap();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    let data = fuzz_data();
+
+    // the server sends arbitrary events to the client
+    let server_canvas = server_ddata.canvas.clone().unwrap();
+    let mut objects = ServerObjects;
+    let mut u = Unstructured::new(&data);
+    let event = server_canvas::Event::arbitrary(&mut u, 1, &mut objects).unwrap();
+    server_canvas.send_event(event).unwrap();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let [client_canvas::Event::Drawn { label, mode }] = &client_ddata.events[..] else {
+        panic!("Unexpected events {:?}", client_ddata.events);
+    };
+    assert!(label.as_ref().map_or(true, |label| !label.contains('\0')));
+    assert!(matches!(mode, wayc::WEnum::Value(_)));
+
+    // and the client sends arbitrary requests until it destroys the canvas
+    let null = File::open("/dev/null").unwrap();
+    let mut objects =
+        ClientObjects { conn: client.conn.clone(), canvas: canvas.clone(), fd: &null };
+    let mut u = Unstructured::new(&data);
+    let mut sent = 0;
+    while !u.is_empty() {
+        let request = client_canvas::Request::arbitrary(&mut u, 1, &mut objects).unwrap();
+        let destructor = matches!(request, client_canvas::Request::Destroy);
+        canvas.send_request(request).unwrap();
+        sent += 1;
+        if destructor {
+            break;
+        }
+    }
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    assert!(sent > 1);
+    assert_eq!(server_ddata.requests.len(), sent);
+    for request in &server_ddata.requests {
+        match request {
+            server_canvas::Request::Draw { label, mode, flags, pattern, .. } => {
+                assert!(!label.contains('\0'));
+                assert!(matches!(mode, ways::WEnum::Value(_)));
+                assert!(server_canvas::Flags::all().contains(*flags));
+                if let Some(pattern) = pattern {
+                    assert_eq!(pattern.id().protocol_id(), canvas.id().protocol_id());
+                }
+            }
+            server_canvas::Request::Destroy => {}
+            request => panic!("Unexpected request {:?}", request),
+        }
+    }
+}
+
+struct ClientObjects<'a> {
+    conn: wayc::Connection,
+    canvas: client_canvas::TestCanvas,
+    fd: &'a File,
+}
+
+impl<'a> wayc::ArbitraryObjects<'a> for ClientObjects<'a> {
+    fn object<I: Proxy>(&mut self, _: &mut Unstructured<'_>) -> arbitrary::Result<I> {
+        I::from_id(&self.conn, self.canvas.id()).map_err(|_| Error::IncorrectFormat)
+    }
+
+    fn object_id(
+        &mut self,
+        _: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<wayc::backend::ObjectId> {
+        Ok(self.canvas.id())
+    }
+
+    fn new_object_interface(
+        &mut self,
+        _: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<(&'static wayc::backend::protocol::Interface, u32)> {
+        Err(Error::IncorrectFormat)
+    }
+
+    fn fd(&mut self, _: &mut Unstructured<'_>) -> arbitrary::Result<BorrowedFd<'a>> {
+        Ok(self.fd.as_fd())
+    }
+}
+
+struct ServerObjects;
+
+// the event of the canvas only has a string and an enum
+impl<'a> ways::ArbitraryObjects<'a> for ServerObjects {
+    fn object<I: Resource>(&mut self, _: &mut Unstructured<'_>) -> arbitrary::Result<I> {
+        Err(Error::IncorrectFormat)
+    }
+
+    fn object_id(
+        &mut self,
+        _: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<ways::backend::ObjectId> {
+        Err(Error::IncorrectFormat)
+    }
+
+    fn new_object<I: Resource>(&mut self, _: &mut Unstructured<'_>) -> arbitrary::Result<I> {
+        Err(Error::IncorrectFormat)
+    }
+
+    fn fd(&mut self, _: &mut Unstructured<'_>) -> arbitrary::Result<BorrowedFd<'a>> {
+        Err(Error::IncorrectFormat)
+    }
+}
+
+struct ServerHandler {
+    canvas: Option<server_canvas::TestCanvas>,
+    requests: Vec<server_canvas::Request>,
+}
+
+impl ways::GlobalDispatch<server_canvas::TestCanvas, ()> for ServerHandler {
+    fn bind(
+        state: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        canvas: ways::New<server_canvas::TestCanvas>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        state.canvas = Some(data_init.init(canvas, ()));
+    }
+}
+
+impl ways::Dispatch<server_canvas::TestCanvas, ()> for ServerHandler {
+    fn request(
+        state: &mut Self,
+        _: &ways::Client,
+        _: &server_canvas::TestCanvas,
+        request: server_canvas::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, Self>,
+    ) {
+        state.requests.push(request);
+    }
+}
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    events: Vec<client_canvas::Event>,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+impl wayc::Dispatch<client_canvas::TestCanvas, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &client_canvas::TestCanvas,
+        event: client_canvas::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        state.events.push(event);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+use serde_json::json;
+use wayc::Proxy;
+use ways::Resource;
+
+mod switch {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!("tests/protocols/enum-results.xml");
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!(
+            "tests/protocols/enum-results.xml",
+            enum_results,
+            serde
+        );
+    }
+
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!(
+            "tests/protocols/enum-results.xml",
+            enum_results,
+            serde
+        );
+    }
+}
+
+use switch::client::test_switch as client_switch;
+use switch::server::test_switch as server_switch;
+
+#[test]
+fn enum_results() {
+    let mut server = TestServer::new();
+    server.display.handle().create_global::<ServerHandler, server_switch::TestSwitch, _>(1, ());
+    let mut server_ddata = ServerHandler { states: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default(), states: Vec::new() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let switch = client_ddata
+        .globals
+        .bind::<client_switch::TestSwitch, _, _>(&client.event_queue.handle(), &registry, 1..2, ())
+        .unwrap();
+
+    // the methods take the enum itself, the unknown values can still be sent as raw messages
+    switch.set_state(client_switch::State::On);
+    switch
+        .send_request(client_switch::Request::SetState { state: Err(wayc::UnknownVariant(5)) })
+        .unwrap();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert_eq!(
+        server_ddata.states,
+        vec![Ok(server_switch::State::On), Err(ways::UnknownVariant(5))]
+    );
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert_eq!(
+        client_ddata.states,
+        vec![Ok(client_switch::State::On), Err(wayc::UnknownVariant(5))]
+    );
+
+    // the unknown values are serialized as their raw value
+    let event = client_switch::Event::State { state: Err(wayc::UnknownVariant(7)) };
+    assert_eq!(serde_json::to_value(&event).unwrap(), json!({ "State": { "state": 7 } }));
+    let request = client_switch::Request::SetState { state: Ok(client_switch::State::On) };
+    assert_eq!(serde_json::to_value(&request).unwrap(), json!({ "SetState": { "state": 1 } }));
+}
+
+struct ServerHandler {
+    states: Vec<Result<server_switch::State, ways::UnknownVariant>>,
+}
+
+impl ways::GlobalDispatch<server_switch::TestSwitch, ()> for ServerHandler {
+    fn bind(
+        _: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        switch: ways::New<server_switch::TestSwitch>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        data_init.init(switch, ());
+    }
+}
+
+impl ways::Dispatch<server_switch::TestSwitch, ()> for ServerHandler {
+    fn request(
+        state: &mut Self,
+        _: &ways::Client,
+        switch: &server_switch::TestSwitch,
+        request: server_switch::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, Self>,
+    ) {
+        let server_switch::Request::SetState { state: switch_state } = request;
+        state.states.push(switch_state);
+        // echo the state back to the client
+        match switch_state {
+            Ok(known) => switch.state(known),
+            Err(unknown) => {
+                switch.send_event(server_switch::Event::State { state: Err(unknown) }).unwrap()
+            }
+        }
+    }
+}
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    states: Vec<Result<client_switch::State, wayc::UnknownVariant>>,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+impl wayc::Dispatch<client_switch::TestSwitch, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &client_switch::TestSwitch,
+        event: client_switch::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        let client_switch::Event::State { state: switch_state } = event;
+        state.states.push(switch_state);
+    }
+}
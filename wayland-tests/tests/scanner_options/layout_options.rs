@@ -0,0 +1,136 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+// the modules of the protocol are crate-private, named without the `suite_` prefix, and the
+// interface types are re-exported alongside them
+mod suite {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!([
+            "tests/protocols/suite-manager.xml",
+            "tests/protocols/suite-widget.xml"
+        ]);
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!(
+            ["tests/protocols/suite-manager.xml", "tests/protocols/suite-widget.xml"],
+            visibility = "pub(crate)",
+            strip_prefix = "suite_",
+            reexport_types,
+        );
+    }
+
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!(
+            ["tests/protocols/suite-manager.xml", "tests/protocols/suite-widget.xml"],
+            visibility = "pub(crate)",
+            strip_prefix = "suite_",
+            reexport_types,
+        );
+    }
+}
+
+use suite::{client, server};
+
+#[test]
+fn layout_options() {
+    let mut server = TestServer::new();
+    server.display.handle().create_global::<ServerHandler, server::SuiteManager, _>(1, ());
+    let mut server_ddata = ServerHandler;
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default(), capabilities: None };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let manager = client_ddata
+        .globals
+        .bind::<client::manager::SuiteManager, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+    let widget: client::SuiteWidget = manager.get_widget(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    assert_eq!(
+        client_ddata.capabilities.take(),
+        Some(client::manager::Capability::Resize | client::manager::Capability::Rotate)
+    );
+
+    // the modules are still reachable under their original names
+    let _: &client::suite_widget::SuiteWidget = &widget;
+}
+
+struct ServerHandler;
+
+impl ways::GlobalDispatch<server::SuiteManager, ()> for ServerHandler {
+    fn bind(
+        _: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        manager: ways::New<server::SuiteManager>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        data_init.init(manager, ());
+    }
+}
+
+impl ways::Dispatch<server::SuiteManager, ()> for ServerHandler {
+    fn request(
+        _: &mut Self,
+        _: &ways::Client,
+        manager: &server::SuiteManager,
+        request: server::manager::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        let server::manager::Request::GetWidget { id } = request;
+        let widget = data_init.init(id, ());
+        widget.capabilities(
+            manager,
+            server::manager::Capability::Resize | server::manager::Capability::Rotate,
+        );
+    }
+}
+
+server_ignore_impl!(ServerHandler => [server::SuiteWidget]);
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    capabilities: Option<client::manager::Capability>,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [client::SuiteManager]);
+
+impl wayc::Dispatch<client::SuiteWidget, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &client::SuiteWidget,
+        event: client::widget::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        let client::widget::Event::Capabilities { capabilities, .. } = event;
+        state.capabilities = Some(capabilities);
+    }
+}
@@ -0,0 +1,22 @@
+// The options of the code generation macros shared by both sides, and the protocols generated with
+// them, one module per option
+
+// the generated wire types only refer to `core` and `alloc`, as in the `no_std` crates
+extern crate alloc;
+
+#[macro_use]
+#[path = "../helpers/mod.rs"]
+mod helpers;
+
+mod arbitrary_messages;
+#[cfg(feature = "serde")]
+mod enum_results;
+mod layout_options;
+mod no_c_interfaces;
+mod protocol_suites;
+mod renames;
+#[cfg(feature = "serde")]
+mod serde_messages;
+mod shared_enums;
+mod tracing_spans;
+mod wire_types;
@@ -0,0 +1,118 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+// a protocol without the tables of libwayland, which the system backends generate when needed
+mod suite {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!(
+            ["tests/protocols/suite-manager.xml", "tests/protocols/suite-widget.xml"],
+            no_c_interfaces
+        );
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!([
+            "tests/protocols/suite-manager.xml",
+            "tests/protocols/suite-widget.xml"
+        ]);
+    }
+
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!([
+            "tests/protocols/suite-manager.xml",
+            "tests/protocols/suite-widget.xml"
+        ]);
+    }
+}
+
+use suite::{client, server};
+
+#[test]
+fn no_c_interfaces() {
+    assert!(suite::__interfaces::SUITE_MANAGER_INTERFACE.c_ptr.is_none());
+
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, server::suite_manager::SuiteManager, _>(1, ());
+    let mut server_ddata = ServerHandler { widgets: 0 };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let manager = client_ddata
+        .globals
+        .bind::<client::suite_manager::SuiteManager, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+    let widget = manager.get_widget(&client.event_queue.handle(), ());
+    widget.destroy();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    assert_eq!(server_ddata.widgets, 1);
+}
+
+struct ServerHandler {
+    widgets: usize,
+}
+
+impl ways::GlobalDispatch<server::suite_manager::SuiteManager, ()> for ServerHandler {
+    fn bind(
+        _: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        manager: ways::New<server::suite_manager::SuiteManager>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        data_init.init(manager, ());
+    }
+}
+
+impl ways::Dispatch<server::suite_manager::SuiteManager, ()> for ServerHandler {
+    fn request(
+        state: &mut Self,
+        _: &ways::Client,
+        _: &server::suite_manager::SuiteManager,
+        request: server::suite_manager::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        let server::suite_manager::Request::GetWidget { id } = request;
+        data_init.init(id, ());
+        state.widgets += 1;
+    }
+}
+
+server_ignore_impl!(ServerHandler => [server::suite_widget::SuiteWidget]);
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [
+    client::suite_manager::SuiteManager,
+    client::suite_widget::SuiteWidget
+]);
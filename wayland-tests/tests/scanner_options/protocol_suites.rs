@@ -0,0 +1,135 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+// two protocol files referring to the interfaces and enums of each other, the order of the files
+// does not matter
+mod suite {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!([
+            "tests/protocols/suite-widget.xml",
+            "tests/protocols/suite-manager.xml"
+        ]);
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!([
+            "tests/protocols/suite-widget.xml",
+            "tests/protocols/suite-manager.xml"
+        ]);
+    }
+
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!([
+            "tests/protocols/suite-widget.xml",
+            "tests/protocols/suite-manager.xml"
+        ]);
+    }
+}
+
+use suite::{client, server};
+
+#[test]
+fn protocol_suite() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, server::suite_manager::SuiteManager, _>(1, ());
+    let mut server_ddata = ServerHandler;
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default(), capabilities: None };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let manager = client_ddata
+        .globals
+        .bind::<client::suite_manager::SuiteManager, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+    manager.get_widget(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let (sender, capabilities) = client_ddata.capabilities.take().unwrap();
+    assert_eq!(sender, manager);
+    // the enum of the other file is known to be a bitfield
+    assert_eq!(
+        capabilities,
+        client::suite_manager::Capability::Resize | client::suite_manager::Capability::Rotate
+    );
+}
+
+struct ServerHandler;
+
+impl ways::GlobalDispatch<server::suite_manager::SuiteManager, ()> for ServerHandler {
+    fn bind(
+        _: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        manager: ways::New<server::suite_manager::SuiteManager>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        data_init.init(manager, ());
+    }
+}
+
+impl ways::Dispatch<server::suite_manager::SuiteManager, ()> for ServerHandler {
+    fn request(
+        _: &mut Self,
+        _: &ways::Client,
+        manager: &server::suite_manager::SuiteManager,
+        request: server::suite_manager::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        let server::suite_manager::Request::GetWidget { id } = request;
+        let widget = data_init.init(id, ());
+        widget.capabilities(
+            manager,
+            server::suite_manager::Capability::Resize | server::suite_manager::Capability::Rotate,
+        );
+    }
+}
+
+server_ignore_impl!(ServerHandler => [server::suite_widget::SuiteWidget]);
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    capabilities: Option<(client::suite_manager::SuiteManager, client::suite_manager::Capability)>,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [client::suite_manager::SuiteManager]);
+
+impl wayc::Dispatch<client::suite_widget::SuiteWidget, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &client::suite_widget::SuiteWidget,
+        event: client::suite_widget::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        let client::suite_widget::Event::Capabilities { manager, capabilities } = event;
+        state.capabilities = Some((manager, capabilities));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+use wayc::WEnum;
+
+// the `move` request and the `type` arguments are rust keywords, the entries of the `layout` enum
+// start with a digit or only differ by their case
+mod awkward {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!("tests/protocols/awkward-names.xml");
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!(
+            "tests/protocols/awkward-names.xml",
+            rename = "test_awkward.request.move=start_move",
+            rename = "test_awkward.request.move.type=layout",
+            rename = "test_awkward.event.moved.type=layout",
+            rename = "test_awkward.enum.layout=dimensions",
+            rename = "test_awkward.enum.layout.2d=flat",
+            rename = "test_awkward.enum.layout.None=unset",
+        );
+    }
+
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!(
+            "tests/protocols/awkward-names.xml",
+            rename = "test_awkward.request.move=start_move",
+            rename = "test_awkward.request.move.type=layout",
+            rename = "test_awkward.event.moved.type=layout",
+            rename = "test_awkward.enum.layout=dimensions",
+            rename = "test_awkward.enum.layout.2d=flat",
+            rename = "test_awkward.enum.layout.None=unset",
+        );
+    }
+}
+
+use awkward::client::test_awkward as client_awkward;
+use awkward::server::test_awkward as server_awkward;
+
+#[test]
+fn renames() {
+    let mut server = TestServer::new();
+    server.display.handle().create_global::<ServerHandler, server_awkward::TestAwkward, _>(1, ());
+    let mut server_ddata = ServerHandler { layouts: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default(), layouts: Vec::new() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let awkward = client_ddata
+        .globals
+        .bind::<client_awkward::TestAwkward, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+
+    awkward.start_move(client_awkward::Dimensions::Flat);
+    awkward.start_move(client_awkward::Dimensions::_3d);
+    awkward.start_move(client_awkward::Dimensions::Unset);
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert_eq!(
+        server_ddata.layouts,
+        vec![
+            WEnum::Value(server_awkward::Dimensions::Flat),
+            WEnum::Value(server_awkward::Dimensions::_3d),
+            WEnum::Value(server_awkward::Dimensions::Unset),
+        ]
+    );
+
+    // the renamed messages keep their opcode, the entries their value
+    assert_eq!(client_awkward::REQ_START_MOVE_OPCODE, 0);
+    assert_eq!(u32::from(client_awkward::Dimensions::None), 2);
+    assert_eq!(u32::from(client_awkward::Dimensions::Unset), 3);
+    // and the messages their name on the wire
+    let layout = WEnum::Value(client_awkward::Dimensions::Flat);
+    assert_eq!(client_awkward::Request::StartMove { layout }.name(), "move");
+    assert_eq!(<client_awkward::TestAwkward as wayc::Proxy>::interface().requests[0].name, "move");
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert_eq!(
+        client_ddata.layouts,
+        vec![
+            WEnum::Value(client_awkward::Dimensions::Flat),
+            WEnum::Value(client_awkward::Dimensions::_3d),
+            WEnum::Value(client_awkward::Dimensions::Unset),
+        ]
+    );
+}
+
+struct ServerHandler {
+    layouts: Vec<WEnum<server_awkward::Dimensions>>,
+}
+
+impl ways::GlobalDispatch<server_awkward::TestAwkward, ()> for ServerHandler {
+    fn bind(
+        _: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        awkward: ways::New<server_awkward::TestAwkward>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        data_init.init(awkward, ());
+    }
+}
+
+impl ways::Dispatch<server_awkward::TestAwkward, ()> for ServerHandler {
+    fn request(
+        state: &mut Self,
+        _: &ways::Client,
+        awkward: &server_awkward::TestAwkward,
+        request: server_awkward::Request,
+        _: &(),
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, Self>,
+    ) {
+        let server_awkward::Request::StartMove { layout } = request;
+        state.layouts.push(layout);
+        if let WEnum::Value(layout) = layout {
+            awkward.moved(layout);
+        }
+    }
+}
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    layouts: Vec<WEnum<client_awkward::Dimensions>>,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+impl wayc::Dispatch<client_awkward::TestAwkward, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &client_awkward::TestAwkward,
+        event: client_awkward::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        let client_awkward::Event::Moved { layout } = event;
+        state.layouts.push(layout);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+use serde_json::json;
+use wayc::Proxy;
+
+#[test]
+fn serialize_client_messages() {
+    use wayc::protocol::{wl_compositor, wl_display, wl_output, wl_registry, wl_shm, wl_surface};
+
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_output::WlOutput, _>(1, ());
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut ServerHandler).unwrap();
+    let output = client_ddata
+        .globals
+        .bind::<wl_output::WlOutput, _, _>(&client.event_queue.handle(), &registry, 1..2, ())
+        .unwrap();
+
+    // objects are serialized as their protocol id
+    let event = wl_surface::Event::Enter { output: output.clone() };
+    assert_eq!(serde_json::to_value(&event).unwrap(), json!({ "Enter": { "output": 3 } }));
+    let request = wl_surface::Request::Attach { buffer: None, x: 1, y: -2 };
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({ "Attach": { "buffer": null, "x": 1, "y": -2 } })
+    );
+    let event = wl_display::Event::Error {
+        object_id: client.display.id(),
+        code: 4,
+        message: "oops".into(),
+    };
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        json!({ "Error": { "object_id": 1, "code": 4, "message": "oops" } })
+    );
+
+    // the interface of a generic constructor is serialized by its name, along the version
+    let request =
+        wl_registry::Request::Bind { name: 1, id: (wl_compositor::WlCompositor::interface(), 4) };
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({ "Bind": { "name": 1, "id": ["wl_compositor", 4] } })
+    );
+
+    // the file descriptors are replaced by their index in the message
+    let stdin = std::io::stdin();
+    let request = wl_shm::Request::CreatePool { fd: std::os::fd::AsFd::as_fd(&stdin), size: 42 };
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({ "CreatePool": { "fd": { "fd": 0 }, "size": 42 } })
+    );
+}
+
+#[test]
+fn serialize_server_messages() {
+    use ways::protocol::{wl_output, wl_shm};
+    use ways::WEnum;
+
+    let event = wl_shm::Event::Format { format: WEnum::Value(wl_shm::Format::Xrgb8888) };
+    assert_eq!(serde_json::to_value(&event).unwrap(), json!({ "Format": { "format": 1 } }));
+    let event = wl_shm::Event::Format { format: WEnum::Unknown(0xdead) };
+    assert_eq!(serde_json::to_value(&event).unwrap(), json!({ "Format": { "format": 0xdead } }));
+    let event = wl_output::Event::Mode {
+        flags: wl_output::Mode::Current | wl_output::Mode::Preferred,
+        width: 800,
+        height: 600,
+        refresh: 60000,
+    };
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        json!({ "Mode": { "flags": 3, "width": 800, "height": 600, "refresh": 60000 } })
+    );
+}
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+}
+
+impl ClientHandler {
+    fn new() -> ClientHandler {
+        ClientHandler { globals: Default::default() }
+    }
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [
+    wayc::protocol::wl_output::WlOutput
+]);
+
+struct ServerHandler;
+
+server_ignore_impl!(ServerHandler => [
+    ways::protocol::wl_output::WlOutput
+]);
+server_ignore_global_impl!(ServerHandler => [
+    ways::protocol::wl_output::WlOutput
+]);
@@ -0,0 +1,163 @@
+use std::{
+    fmt::{Debug, Write},
+    sync::{Arc, Mutex},
+};
+
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+
+// the core protocol, generated with the tracing spans
+mod traced {
+    pub mod client {
+        use wayland_client;
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_client_code!("../wayland-client/wayland.xml", tracing);
+    }
+
+    pub mod server {
+        use wayland_server;
+        use wayland_server::protocol::__interfaces::*;
+        wayland_scanner::generate_server_code!("../wayland-client/wayland.xml", tracing);
+    }
+}
+
+use traced::client::wl_output;
+
+#[test]
+fn tracing_spans() {
+    let spans = SpanRecorder::default();
+    tracing::subscriber::with_default(spans.clone(), || {
+        let mut server = TestServer::new();
+        server
+            .display
+            .handle()
+            .create_global::<ServerHandler, traced::server::wl_output::WlOutput, _>(3, ());
+        let mut server_ddata = ServerHandler;
+
+        let (_, mut client) = server.add_client();
+        let mut client_ddata = ClientHandler { globals: Default::default(), done: false };
+
+        let registry = client.display.get_registry(&client.event_queue.handle(), ());
+        roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+        let output = client_ddata
+            .globals
+            .bind::<wl_output::WlOutput, _, _>(&client.event_queue.handle(), &registry, 3..4, ())
+            .unwrap();
+        roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+        assert!(client_ddata.done);
+
+        let id = wayc::Proxy::id(&output).to_string();
+        output.release();
+        roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+        // the spans name the interface and the message, and display the object id
+        let spans = spans.0.lock().unwrap();
+        assert!(spans
+            .contains(&format!("parse_event interface=\"wl_output\" event=\"done\" id={}", id)));
+        assert!(spans.contains(&format!(
+            "write_request interface=\"wl_output\" request=\"release\" id={}",
+            id
+        )));
+        // the same messages on the server side
+        assert!(spans
+            .iter()
+            .any(|span| span.starts_with("write_event interface=\"wl_output\" event=\"done\"")));
+        assert!(spans
+            .iter()
+            .any(|span| span
+                .starts_with("parse_request interface=\"wl_output\" request=\"release\"")));
+    });
+
+    // only the generated code with the option is instrumented
+    let spans = spans.0.lock().unwrap();
+    assert!(spans.iter().all(|span| span.contains("interface=\"wl_output\"")));
+}
+
+// records the spans as their name followed by their fields
+#[derive(Clone, Default)]
+struct SpanRecorder(Arc<Mutex<Vec<String>>>);
+
+impl tracing::Subscriber for SpanRecorder {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = FieldsRecorder(span.metadata().name().to_owned());
+        span.record(&mut fields);
+        let mut spans = self.0.lock().unwrap();
+        spans.push(fields.0);
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &tracing::Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+struct FieldsRecorder(String);
+
+impl Visit for FieldsRecorder {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        write!(self.0, " {}={:?}", field.name(), value).unwrap();
+    }
+}
+
+struct ServerHandler;
+
+impl ways::GlobalDispatch<traced::server::wl_output::WlOutput, ()> for ServerHandler {
+    fn bind(
+        _: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        output: ways::New<traced::server::wl_output::WlOutput>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        let output = data_init.init(output, ());
+        output.done();
+    }
+}
+
+server_ignore_impl!(ServerHandler => [traced::server::wl_output::WlOutput]);
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    done: bool,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+impl wayc::Dispatch<wl_output::WlOutput, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Done = event {
+            state.done = true;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+mod wire {
+    wayland_scanner::generate_wire_types!("../wayland-client/wayland.xml");
+}
+
+mod canvas {
+    wayland_scanner::generate_wire_types!("tests/protocols/arbitrary-messages.xml");
+}
+
+use canvas::test_canvas;
+use wire::{wl_registry, wl_shm, wl_surface};
+
+#[test]
+fn wire_types() {
+    let attach = wl_surface::Request::Attach { buffer: Some(7), x: 1, y: -1 };
+    assert_eq!((attach.opcode(), attach.name(), attach.since()), (1, "attach", 1));
+    let offset = wl_surface::Request::Offset { x: 0, y: 0 };
+    assert_eq!(offset.since(), 5);
+    assert_eq!(wl_surface::VERSION, 6);
+
+    // the objects and the new objects are their protocol id
+    let bind = wl_registry::Request::Bind { name: 1, id: ("wl_compositor".into(), 6, 3) };
+    assert_eq!(bind, wl_registry::Request::Bind { name: 1, id: ("wl_compositor".into(), 6, 3) });
+
+    // the enums hold the raw values which are not one of their variants
+    let format = wl_shm::Event::Format { format: Ok(wl_shm::Format::Argb8888) };
+    assert_ne!(format, wl_shm::Event::Format { format: Err(1) });
+
+    let draw = test_canvas::Request::Draw {
+        label: "label".into(),
+        scale: 0.5,
+        points: vec![1, 2, 3],
+        pattern: None,
+        mode: Err(2),
+        flags: test_canvas::Flags::Smooth | test_canvas::Flags::Dashed,
+    };
+    assert_eq!((draw.opcode(), draw.name()), (0, "draw"));
+    let attach = test_canvas::Request::Attach { fd: 0 };
+    assert_eq!((attach.opcode(), attach.since()), (1, 2));
+}
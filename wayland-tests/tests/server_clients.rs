@@ -69,6 +69,154 @@ fn client_user_data() {
     }
 }
 
+#[test]
+fn client_label() {
+    let mut server = TestServer::<()>::new();
+
+    let (s_client, _) = server.add_client::<()>();
+
+    assert_eq!(s_client.label(&server.display.handle()), None);
+    s_client.set_label(&server.display.handle(), "test-client (pid 1234)").unwrap();
+    assert_eq!(s_client.label(&server.display.handle()).as_deref(), Some("test-client (pid 1234)"));
+}
+
+#[test]
+fn client_resource_usage() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_output::WlOutput, _>(1, ());
+    let mut server_ddata = ServerHandler {};
+
+    let (s_client, mut client) = server.add_client_with_data(Arc::new(MyClientData {
+        has_compositor: AtomicBool::new(false),
+        has_output: AtomicBool::new(false),
+    }));
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let before = s_client.resource_usage(&server.display.handle()).unwrap();
+    assert!(before.memory > 0);
+
+    client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_output::WlOutput, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let after = s_client.resource_usage(&server.display.handle()).unwrap();
+    assert_eq!(after.objects, before.objects + 1);
+}
+
+#[test]
+fn client_disconnect_notification() {
+    let mut server = TestServer::<()>::new();
+
+    let (s_client, client) = server.add_client::<()>();
+    assert!(s_client.is_alive());
+
+    let disconnected = Arc::new(AtomicBool::new(false));
+    let disconnected2 = disconnected.clone();
+    s_client
+        .on_disconnect(move |_| {
+            disconnected2.store(true, Ordering::SeqCst);
+        })
+        .unwrap();
+
+    drop(client);
+    server.display.dispatch_clients(&mut ()).unwrap();
+
+    assert!(disconnected.load(Ordering::SeqCst));
+    assert!(!s_client.is_alive());
+    assert!(s_client.on_disconnect(|_| {}).is_err());
+}
+
+#[test]
+fn flush_clients_except() {
+    let mut server = TestServer::<()>::new();
+
+    let (_, client1) = server.add_client::<()>();
+    let (s_client2, client2) = server.add_client::<()>();
+    let (s_client3, client3) = server.add_client::<()>();
+
+    let synced = |client: &helpers::TestClient<()>| {
+        let done = Arc::new(AtomicBool::new(false));
+        let done2 = done.clone();
+        client.conn.backend().sync(move |_| done2.store(true, Ordering::SeqCst)).unwrap();
+        client.conn.flush().unwrap();
+        done
+    };
+    let done1 = synced(&client1);
+    let done2 = synced(&client2);
+    let _ = synced(&client3);
+
+    // the callbacks of all the clients are now buffered in the server
+    server.display.dispatch_clients(&mut ()).unwrap();
+    drop(client3);
+
+    let result = server.display.handle().flush_clients_except(&[s_client2.id()]);
+    // the system backend does not report the flush errors of a client
+    if cfg!(not(feature = "server_system")) {
+        let errors = result.unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, s_client3.id());
+    }
+
+    client1.conn.prepare_read().unwrap().read().unwrap();
+    assert!(done1.load(Ordering::SeqCst));
+    // nothing to read, the rust client reports `WouldBlock` while libwayland reads nothing
+    let _ = client2.conn.prepare_read().unwrap().read();
+    assert!(!done2.load(Ordering::SeqCst));
+
+    server.display.flush_clients().unwrap();
+    client2.conn.prepare_read().unwrap().read().unwrap();
+    assert!(done2.load(Ordering::SeqCst));
+}
+
+#[test]
+fn core_interfaces_registered() {
+    use ways::Resource;
+
+    let _server = TestServer::<ServerHandler>::new();
+
+    let output = ways::backend::protocol::Interface::find("wl_output").unwrap();
+    assert!(ways::backend::protocol::same_interface(
+        output,
+        ways::protocol::wl_output::WlOutput::interface()
+    ));
+}
+
+#[test]
+fn display_backend_kind() {
+    use ways::backend::BackendKind;
+
+    let (available, other) = if cfg!(feature = "server_system") {
+        (BackendKind::Sys, BackendKind::Rust)
+    } else {
+        (BackendKind::Rust, BackendKind::Sys)
+    };
+
+    let display = ways::Display::<ServerHandler>::new_checked(available).unwrap();
+    assert_eq!(display.backend_kind(), available);
+
+    match ways::Display::<ServerHandler>::new_checked(other) {
+        Err(ways::backend::InitError::Io(err)) => {
+            assert_eq!(err.kind(), std::io::ErrorKind::Unsupported)
+        }
+        _ => panic!("the {other:?} backend should not be available"),
+    }
+}
+
 #[test]
 fn client_credentials() {
     let mut server = TestServer::<()>::new();
@@ -102,6 +102,54 @@ fn global_filter_try_force() {
     assert!(roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).is_err());
 }
 
+#[test]
+fn global_policy() {
+    use std::os::unix::fs::MetadataExt;
+    // the client runs in this process, so it has our credentials
+    let uid = std::fs::metadata("/proc/self").unwrap().uid();
+    let pid = std::process::id() as i32;
+
+    let mut server = TestServer::new();
+    let compositor = server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_compositor::WlCompositor, _>(1, ());
+    let shm = server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_shm::WlShm, _>(1, ());
+    server
+        .display
+        .handle()
+        .restrict_global::<ServerHandler>(compositor, ways::GlobalPolicy::UidIn(vec![uid]))
+        .unwrap();
+    server
+        .display
+        .handle()
+        .restrict_global::<ServerHandler>(
+            shm,
+            ways::GlobalPolicy::Callback(Arc::new(move |creds| creds.pid != pid)),
+        )
+        .unwrap();
+    let mut server_ddata = ServerHandler;
+
+    let (_, mut client) = server.add_client_with_data(Arc::new(MyClientData { privileged: false }));
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let globals = client_ddata.globals.list();
+    assert_eq!(globals.len(), 1);
+    assert_eq!(globals[0].interface, "wl_compositor");
+
+    // binding the hidden global is a protocol error
+    registry.bind::<wayc::protocol::wl_shm::WlShm, _, _>(2, 1, &client.event_queue.handle(), ());
+
+    assert!(roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).is_err());
+}
+
 struct ClientHandler {
     globals: globals::GlobalList,
 }
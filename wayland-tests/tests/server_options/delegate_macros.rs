@@ -0,0 +1,148 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+// a protocol of two interfaces, generated with its delegation macro
+#[macro_use]
+mod suite {
+    pub mod __interfaces {
+        wayland_scanner::generate_interfaces!([
+            "tests/protocols/suite-widget.xml",
+            "tests/protocols/suite-manager.xml"
+        ]);
+    }
+
+    pub mod client {
+        use super::__interfaces::*;
+        use wayland_client;
+        wayland_scanner::generate_client_code!([
+            "tests/protocols/suite-widget.xml",
+            "tests/protocols/suite-manager.xml"
+        ]);
+    }
+
+    #[macro_use]
+    pub mod server {
+        use super::__interfaces::*;
+        use wayland_server;
+        wayland_scanner::generate_server_code!(
+            ["tests/protocols/suite-widget.xml", "tests/protocols/suite-manager.xml"],
+            delegates
+        );
+    }
+}
+
+use suite::{client, server};
+
+#[test]
+fn delegate_protocol() {
+    let mut server = TestServer::<ServerHandler>::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, server::suite_manager::SuiteManager, u32>(1, 42);
+    let mut server_ddata = ServerHandler { bound: Vec::new(), destroyed: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler { globals: Default::default() };
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let manager = client_ddata
+        .globals
+        .bind::<client::suite_manager::SuiteManager, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            1..2,
+            (),
+        )
+        .unwrap();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert_eq!(server_ddata.bound, vec![42]);
+
+    let widget = manager.get_widget(&client.event_queue.handle(), ());
+    widget.destroy();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    // the widget got the user data given by the delegate to the objects it creates
+    assert_eq!(server_ddata.destroyed, vec![43]);
+}
+
+struct ServerHandler {
+    bound: Vec<u32>,
+    destroyed: Vec<u32>,
+}
+
+struct SuiteDelegate;
+
+delegate_test_suite_widget!(ServerHandler: [suite::server: u32] => SuiteDelegate);
+
+impl ways::GlobalDispatch<server::suite_manager::SuiteManager, u32, ServerHandler>
+    for SuiteDelegate
+{
+    fn bind(
+        state: &mut ServerHandler,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        manager: ways::New<server::suite_manager::SuiteManager>,
+        global_data: &u32,
+        data_init: &mut ways::DataInit<'_, ServerHandler>,
+    ) {
+        state.bound.push(*global_data);
+        data_init.init(manager, *global_data);
+    }
+}
+
+impl ways::Dispatch<server::suite_manager::SuiteManager, u32, ServerHandler> for SuiteDelegate {
+    fn request(
+        _: &mut ServerHandler,
+        _: &ways::Client,
+        _: &server::suite_manager::SuiteManager,
+        request: server::suite_manager::Request,
+        data: &u32,
+        _: &ways::DisplayHandle,
+        data_init: &mut ways::DataInit<'_, ServerHandler>,
+    ) {
+        let server::suite_manager::Request::GetWidget { id } = request;
+        data_init.init(id, data + 1);
+    }
+}
+
+impl ways::Dispatch<server::suite_widget::SuiteWidget, u32, ServerHandler> for SuiteDelegate {
+    fn request(
+        _: &mut ServerHandler,
+        _: &ways::Client,
+        _: &server::suite_widget::SuiteWidget,
+        _: server::suite_widget::Request,
+        _: &u32,
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, ServerHandler>,
+    ) {
+    }
+
+    fn destroyed(
+        state: &mut ServerHandler,
+        _: ways::backend::ClientId,
+        _: &server::suite_widget::SuiteWidget,
+        data: &u32,
+    ) {
+        state.destroyed.push(*data);
+    }
+}
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+client_ignore_impl!(ClientHandler => [
+    client::suite_manager::SuiteManager,
+    client::suite_widget::SuiteWidget
+]);
@@ -0,0 +1,8 @@
+// The options of `generate_server_code!` only available on the server side, one module per option
+
+#[macro_use]
+#[path = "../helpers/mod.rs"]
+mod helpers;
+
+mod delegate_macros;
+mod mocks;
@@ -0,0 +1,109 @@
+use crate::helpers::{globals, roundtrip, wayc, ways, TestServer};
+
+use ways::protocol::{wl_pointer, wl_seat};
+
+#[test]
+fn mock_records_requests() {
+    let mut server = TestServer::<ServerHandler>::new();
+    let seat = wl_seat::MockWlSeat::new::<ServerHandler>(&server.display.handle(), 5);
+    let mut server_ddata = ServerHandler;
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let client_seat = client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_seat::WlSeat, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            5..6,
+            (),
+        )
+        .unwrap();
+    let pointer = client_seat.get_pointer(&client.event_queue.handle(), ());
+    pointer.set_cursor(42, None, 1, 2);
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let requests = seat.take_requests::<wl_seat::WlSeat>();
+    assert_eq!(requests.len(), 1);
+    assert!(matches!(requests[0].1, wl_seat::Request::GetPointer { .. }));
+    // the requests are only taken once
+    assert!(seat.take_requests::<wl_seat::WlSeat>().is_empty());
+
+    // the created objects are mocked as well
+    let requests = seat.take_requests::<wl_pointer::WlPointer>();
+    assert_eq!(requests.len(), 1);
+    assert!(matches!(
+        requests[0].1,
+        wl_pointer::Request::SetCursor { serial: 42, surface: None, hotspot_x: 1, hotspot_y: 2 }
+    ));
+
+    // the events are scripted through the objects of the mock
+    let dh = server.display.handle();
+    assert_eq!(seat.objects::<wl_pointer::WlPointer>(&dh).len(), 1);
+    for s_seat in seat.objects::<wl_seat::WlSeat>(&dh) {
+        s_seat.capabilities(wl_seat::Capability::Pointer | wl_seat::Capability::Keyboard);
+    }
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert_eq!(
+        client_ddata.capabilities,
+        Some(
+            wayc::protocol::wl_seat::Capability::Pointer
+                | wayc::protocol::wl_seat::Capability::Keyboard
+        )
+    );
+
+    // destroyed objects are no longer listed
+    pointer.release();
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+    assert!(seat.objects::<wl_pointer::WlPointer>(&dh).is_empty());
+    assert!(matches!(
+        seat.take_requests::<wl_pointer::WlPointer>()[..],
+        [(_, wl_pointer::Request::Release)]
+    ));
+}
+
+struct ServerHandler;
+
+struct ClientHandler {
+    globals: globals::GlobalList,
+    capabilities: Option<wayc::protocol::wl_seat::Capability>,
+}
+
+impl ClientHandler {
+    fn new() -> ClientHandler {
+        ClientHandler { globals: Default::default(), capabilities: None }
+    }
+}
+
+impl AsMut<globals::GlobalList> for ClientHandler {
+    fn as_mut(&mut self) -> &mut globals::GlobalList {
+        &mut self.globals
+    }
+}
+
+wayc::delegate_dispatch!(ClientHandler:
+    [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
+);
+
+impl wayc::Dispatch<wayc::protocol::wl_seat::WlSeat, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &wayc::protocol::wl_seat::WlSeat,
+        event: wayc::protocol::wl_seat::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        if let wayc::protocol::wl_seat::Event::Capabilities { capabilities } = event {
+            state.capabilities = Some(capabilities);
+        }
+    }
+}
+
+client_ignore_impl!(ClientHandler => [
+    wayc::protocol::wl_pointer::WlPointer
+]);
@@ -199,13 +199,108 @@ fn get_resource() {
         .is_ok());
 }
 
+#[test]
+fn retarget_resource() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<RetargetHandler, ways::protocol::wl_output::WlOutput, _>(3, ());
+    let mut server_ddata = RetargetHandler { outputs: Vec::new(), retargeted_requests: 0 };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let client_output = client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_output::WlOutput, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            3..4,
+            (),
+        )
+        .unwrap();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    // a delegate for another state type is rejected
+    assert!(server
+        .display
+        .handle()
+        .retarget_resource::<ServerHandler, ServerHandler, _, UData>(&server_ddata.outputs[0])
+        .is_err());
+
+    server
+        .display
+        .handle()
+        .retarget_resource::<RetargetHandler, Retargeted, _, UData>(&server_ddata.outputs[0])
+        .unwrap();
+
+    client_output.release();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    // the release request and the destruction were both handled by the new delegate
+    assert_eq!(server_ddata.retargeted_requests, 2);
+    // the user data is preserved
+    assert_eq!(server_ddata.outputs[0].data::<UData>().unwrap().0, 1000);
+}
+
+#[test]
+fn queue_event_from_thread() {
+    let mut server = TestServer::new();
+    server
+        .display
+        .handle()
+        .create_global::<ServerHandler, ways::protocol::wl_output::WlOutput, _>(3, ());
+    let mut server_ddata = ServerHandler { outputs: Vec::new() };
+
+    let (_, mut client) = server.add_client();
+    let mut client_ddata = ClientHandler::new();
+
+    let registry = client.display.get_registry(&client.event_queue.handle(), ());
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    client_ddata
+        .globals
+        .bind::<wayc::protocol::wl_output::WlOutput, _, _>(
+            &client.event_queue.handle(),
+            &registry,
+            3..4,
+            (),
+        )
+        .unwrap();
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    let output = server_ddata.outputs[0].clone();
+    std::thread::spawn(move || {
+        output.queue_event(wl_output::Event::Done).unwrap();
+        output.queue_event(wl_output::Event::Done).unwrap();
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(client_ddata.output_done, 0);
+
+    roundtrip(&mut client, &mut server, &mut client_ddata, &mut server_ddata).unwrap();
+
+    assert_eq!(client_ddata.output_done, 2);
+}
+
 struct ClientHandler {
     globals: globals::GlobalList,
+    output_done: usize,
 }
 
 impl ClientHandler {
     fn new() -> ClientHandler {
-        ClientHandler { globals: Default::default() }
+        ClientHandler { globals: Default::default(), output_done: 0 }
     }
 }
 
@@ -219,7 +314,20 @@ wayc::delegate_dispatch!(ClientHandler:
     [wayc::protocol::wl_registry::WlRegistry: ()] => globals::GlobalList
 );
 
-client_ignore_impl!(ClientHandler => [ClientOutput]);
+impl wayc::Dispatch<ClientOutput, ()> for ClientHandler {
+    fn event(
+        state: &mut Self,
+        _: &ClientOutput,
+        event: wayc::protocol::wl_output::Event,
+        _: &(),
+        _: &wayc::Connection,
+        _: &wayc::QueueHandle<Self>,
+    ) {
+        if let wayc::protocol::wl_output::Event::Done = event {
+            state.output_done += 1;
+        }
+    }
+}
 
 struct ServerHandler {
     outputs: Vec<wl_output::WlOutput>,
@@ -253,3 +361,60 @@ impl ways::Dispatch<wl_output::WlOutput, UData> for ServerHandler {
     ) {
     }
 }
+
+struct RetargetHandler {
+    outputs: Vec<wl_output::WlOutput>,
+    retargeted_requests: usize,
+}
+
+impl ways::GlobalDispatch<wl_output::WlOutput, ()> for RetargetHandler {
+    fn bind(
+        state: &mut Self,
+        _: &ways::DisplayHandle,
+        _: &ways::Client,
+        output: ways::New<ways::protocol::wl_output::WlOutput>,
+        _: &(),
+        data_init: &mut ways::DataInit<'_, Self>,
+    ) {
+        let output = data_init.init(output, UData(1000 + state.outputs.len()));
+        state.outputs.push(output);
+    }
+}
+
+impl ways::Dispatch<wl_output::WlOutput, UData> for RetargetHandler {
+    fn request(
+        _: &mut Self,
+        _: &ways::Client,
+        _: &wl_output::WlOutput,
+        _: wl_output::Request,
+        _: &UData,
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, Self>,
+    ) {
+    }
+}
+
+struct Retargeted;
+
+impl ways::Dispatch<wl_output::WlOutput, UData, RetargetHandler> for Retargeted {
+    fn request(
+        state: &mut RetargetHandler,
+        _: &ways::Client,
+        _: &wl_output::WlOutput,
+        _: wl_output::Request,
+        _: &UData,
+        _: &ways::DisplayHandle,
+        _: &mut ways::DataInit<'_, RetargetHandler>,
+    ) {
+        state.retargeted_requests += 1;
+    }
+
+    fn destroyed(
+        state: &mut RetargetHandler,
+        _: ways::backend::ClientId,
+        _: &wl_output::WlOutput,
+        _: &UData,
+    ) {
+        state.retargeted_requests += 1;
+    }
+}